@@ -0,0 +1,415 @@
+//! One scripted client.
+//!
+//! Each bot is its own headless Bevy app with its own connection, run on its own thread. It
+//! creates its account (or finds it already exists), logs in, makes a character the first
+//! time, and then plays: wanders, picks fights with nearby enemies, says something now and
+//! then, and walks back to its corpse when it dies.
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_renet2::netcode::{ClientAuthentication, NativeSocket, NetcodeClientTransport};
+use bevy_renet2::prelude::{ConnectionConfig, RenetClient};
+use bevy_replicon::prelude::*;
+use bevy_replicon_renet2::{RenetChannelsExt, RepliconRenetPlugins};
+use eryndor_shared::*;
+use rand::Rng;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use crate::report::{Action, BotStatus, Latency, Phase, Report};
+use crate::Options;
+
+/// Frames per second each bot runs at; also how often it sends movement
+const TICK_RATE: f64 = 20.0;
+
+/// How far a bot looks for something to fight
+const AGGRO_RANGE: f32 = 200.0;
+
+/// Close enough to stop walking and let auto-attack do its work
+const ATTACK_RANGE: f32 = 40.0;
+
+/// Close enough to a corpse to resurrect
+const CORPSE_RANGE: f32 = 30.0;
+
+/// Give up on a bot that hasn't connected by then
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Everything a bot knows about itself
+#[derive(Resource)]
+struct Bot {
+    index: usize,
+    username: String,
+    password: String,
+    report: Arc<Report>,
+    stop: Arc<AtomicBool>,
+    phase: Phase,
+    started: Instant,
+    /// When the last login or character select went out
+    request_sent: Option<Instant>,
+    player: Option<Entity>,
+    target: Option<Entity>,
+    direction: Vec2,
+    next_turn: Timer,
+    next_target_check: Timer,
+    next_chat: Timer,
+    next_resurrect: Timer,
+    /// Say messages waiting to come back, with when they were sent
+    pending_chat: Vec<(String, Instant)>,
+    chat_count: u32,
+    released: bool,
+}
+
+impl Bot {
+    fn fail(&mut self, message: String) {
+        self.report.error(self.index, message);
+        self.phase = Phase::Failed;
+    }
+}
+
+/// Run one bot until `stop` is set
+pub fn run(index: usize, options: Arc<Options>, report: Arc<Report>, stop: Arc<AtomicBool>) {
+    let username = format!("{}{:04}", options.prefix, index);
+    let mut rng = rand::thread_rng();
+    let chat_interval = options.chat_interval.as_secs_f32().max(1.0);
+
+    App::new()
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / TICK_RATE))),
+            StatesPlugin,
+            RepliconPlugins,
+            RepliconRenetPlugins,
+            ProtocolPlugin,
+        ))
+        .insert_resource(Bot {
+            index,
+            username,
+            password: options.password.clone(),
+            report,
+            stop,
+            phase: Phase::Connecting,
+            started: Instant::now(),
+            request_sent: None,
+            player: None,
+            target: None,
+            direction: Vec2::ZERO,
+            next_turn: Timer::from_seconds(0.0, TimerMode::Once),
+            next_target_check: Timer::from_seconds(1.0, TimerMode::Repeating),
+            // Stagger chat so every bot doesn't talk on the same frame
+            next_chat: Timer::from_seconds(rng.gen_range(1.0..=chat_interval), TimerMode::Once),
+            next_resurrect: Timer::from_seconds(1.0, TimerMode::Repeating),
+            pending_chat: Vec::new(),
+            chat_count: 0,
+            released: false,
+        })
+        .insert_resource(ServerAddress(options.server))
+        .insert_resource(ChatInterval(chat_interval))
+        .add_observer(handle_create_account_response)
+        .add_observer(handle_login_response)
+        .add_observer(handle_character_list)
+        .add_observer(handle_create_character_response)
+        .add_observer(handle_select_character_response)
+        .add_observer(handle_chat_message)
+        .add_observer(handle_notification)
+        .add_systems(Startup, connect)
+        .add_systems(Update, (
+            start_session,
+            find_player,
+            play,
+            report_status.run_if(bevy::time::common_conditions::on_timer(Duration::from_secs(1))),
+            check_stop,
+        ).chain())
+        .run();
+}
+
+#[derive(Resource)]
+struct ServerAddress(SocketAddr);
+
+#[derive(Resource)]
+struct ChatInterval(f32);
+
+fn connect(mut commands: Commands, channels: Res<RepliconChannels>, server: Res<ServerAddress>, mut bot: ResMut<Bot>) {
+    let connection_config = ConnectionConfig::from_channels(
+        channels.server_configs(),
+        channels.client_configs(),
+    );
+
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    // Bots started in the same millisecond still need distinct ids
+    let client_id = current_time.as_millis() as u64 * 1000 + bot.index as u64 % 1000;
+
+    let authentication = ClientAuthentication::Unsecure {
+        client_id,
+        protocol_id: 0,
+        socket_id: 0,
+        server_addr: server.0,
+        user_data: None,
+    };
+
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(socket) => socket,
+        Err(e) => return bot.fail(format!("could not bind a socket: {}", e)),
+    };
+    let transport = match NativeSocket::new(socket)
+        .map_err(|e| e.to_string())
+        .and_then(|socket| NetcodeClientTransport::new(current_time, authentication, socket).map_err(|e| e.to_string()))
+    {
+        Ok(transport) => transport,
+        Err(e) => return bot.fail(format!("could not create transport: {}", e)),
+    };
+
+    commands.insert_resource(RenetClient::new(connection_config, false));
+    commands.insert_resource(transport);
+}
+
+/// Create the account once connected. If it already exists the login goes ahead anyway.
+fn start_session(mut commands: Commands, client: Option<Res<RenetClient>>, mut bot: ResMut<Bot>) {
+    if bot.phase != Phase::Connecting {
+        return;
+    }
+    let Some(client) = client else { return };
+
+    if client.is_connected() {
+        bot.phase = Phase::LoggingIn;
+        commands.client_trigger(CreateAccountRequest {
+            email: format!("{}@bots.invalid", bot.username),
+            username: bot.username.clone(),
+            password: bot.password.clone(),
+        });
+    } else if bot.started.elapsed() > CONNECT_TIMEOUT {
+        bot.fail("timed out connecting".to_string());
+    }
+}
+
+fn handle_create_account_response(
+    _trigger: On<CreateAccountResponse>,
+    mut commands: Commands,
+    mut bot: ResMut<Bot>,
+) {
+    bot.request_sent = Some(Instant::now());
+    commands.client_trigger(LoginRequest {
+        username: bot.username.clone(),
+        password: bot.password.clone(),
+        totp_code: None,
+        hardware_id: None,
+    });
+}
+
+fn handle_login_response(trigger: On<LoginResponse>, mut bot: ResMut<Bot>) {
+    let response = trigger.event();
+    if let Some(sent) = bot.request_sent.take() {
+        bot.report.record_latency(Latency::Login, sent.elapsed());
+    }
+    if response.success {
+        bot.phase = Phase::SelectingCharacter;
+    } else {
+        bot.fail(format!("login refused: {}", response.message));
+    }
+}
+
+/// Play the first character, making one if the account has none
+fn handle_character_list(
+    trigger: On<CharacterListResponse>,
+    mut commands: Commands,
+    mut bot: ResMut<Bot>,
+) {
+    if bot.phase != Phase::SelectingCharacter {
+        return;
+    }
+    match trigger.event().characters.first() {
+        Some(character) => {
+            bot.request_sent = Some(Instant::now());
+            commands.client_trigger(SelectCharacterRequest { character_id: character.id });
+        }
+        None => {
+            let classes = [CharacterClass::Rogue, CharacterClass::Mage, CharacterClass::Knight];
+            let class = classes[bot.index % classes.len()];
+            commands.client_trigger(CreateCharacterRequest {
+                name: character_name(&bot.username),
+                class,
+                faction: Faction::default(),
+            });
+        }
+    }
+}
+
+fn handle_create_character_response(
+    trigger: On<CreateCharacterResponse>,
+    mut commands: Commands,
+    mut bot: ResMut<Bot>,
+) {
+    let response = trigger.event();
+    match &response.character {
+        Some(character) if response.success => {
+            bot.request_sent = Some(Instant::now());
+            commands.client_trigger(SelectCharacterRequest { character_id: character.id });
+        }
+        _ => bot.fail(format!("character creation refused: {}", response.message)),
+    }
+}
+
+fn handle_select_character_response(_trigger: On<SelectCharacterResponse>, mut bot: ResMut<Bot>) {
+    if let Some(sent) = bot.request_sent.take() {
+        bot.report.record_latency(Latency::Select, sent.elapsed());
+    }
+    bot.phase = Phase::InGame;
+}
+
+/// Time how long our own say messages take to come back
+fn handle_chat_message(trigger: On<ChatMessage>, mut bot: ResMut<Bot>) {
+    let message = trigger.event();
+    if message.channel != ChatChannel::Say {
+        return;
+    }
+    if let Some(index) = bot.pending_chat.iter().position(|(text, _)| *text == message.message) {
+        let (_, sent) = bot.pending_chat.remove(index);
+        bot.report.record_latency(Latency::Chat, sent.elapsed());
+    }
+}
+
+/// Errors the server sends while the bot is still getting in
+fn handle_notification(trigger: On<NotificationEvent>, mut bot: ResMut<Bot>) {
+    let notification = trigger.event();
+    if matches!(notification.notification_type, NotificationType::Error) && bot.phase == Phase::SelectingCharacter {
+        let message = notification.message.clone();
+        bot.fail(message);
+    }
+}
+
+/// "bot0001" plays "Bot0001"
+fn character_name(username: &str) -> String {
+    let mut chars = username.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Pick out our own character once it has been replicated
+fn find_player(mut bot: ResMut<Bot>, players: Query<(Entity, &Character), With<Player>>) {
+    if bot.phase != Phase::InGame || bot.player.is_some() {
+        return;
+    }
+    let name = character_name(&bot.username);
+    if let Some((entity, _)) = players.iter().find(|(_, character)| character.name.eq_ignore_ascii_case(&name)) {
+        bot.player = Some(entity);
+    }
+}
+
+fn play(
+    mut commands: Commands,
+    mut bot: ResMut<Bot>,
+    time: Res<Time>,
+    chat_interval: Res<ChatInterval>,
+    players: Query<(&Position, Option<&Dead>, Option<&Ghost>), With<Player>>,
+    enemies: Query<(Entity, &Position), (With<Enemy>, Without<Dead>)>,
+) {
+    if bot.phase != Phase::InGame {
+        return;
+    }
+    let Some(player) = bot.player else { return };
+    let Ok((position, dead, ghost)) = players.get(player) else { return };
+    let mut rng = rand::thread_rng();
+
+    // Dead: release, then walk back and resurrect
+    if dead.is_some() {
+        if !bot.released {
+            bot.released = true;
+            bot.target = None;
+            bot.report.record(Action::Death);
+            commands.client_trigger(ReleaseSpiritRequest);
+        }
+        return;
+    }
+    bot.released = false;
+    if let Some(ghost) = ghost {
+        let offset = ghost.corpse_position - position.0;
+        let direction = if offset.length() > CORPSE_RANGE { offset.normalize_or_zero() } else { Vec2::ZERO };
+        send_move(&mut commands, &bot, direction);
+        if direction == Vec2::ZERO && bot.next_resurrect.tick(time.delta()).just_finished() {
+            commands.client_trigger(ResurrectAtCorpseRequest);
+        }
+        return;
+    }
+
+    // Pick a fight with the nearest enemy, or keep wandering
+    if bot.next_target_check.tick(time.delta()).just_finished() {
+        let nearest = enemies.iter()
+            .map(|(entity, enemy)| (entity, enemy.0.distance(position.0)))
+            .filter(|(_, distance)| *distance <= AGGRO_RANGE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(entity, _)| entity);
+        if nearest != bot.target {
+            bot.target = nearest;
+            if nearest.is_some() {
+                bot.report.record(Action::Target);
+            }
+            commands.client_trigger(SetTargetRequest { target: nearest });
+        }
+    }
+
+    let target_position = bot.target.and_then(|target| enemies.get(target).ok()).map(|(_, enemy)| enemy.0);
+    let direction = match target_position {
+        Some(enemy) if enemy.distance(position.0) > ATTACK_RANGE => (enemy - position.0).normalize_or_zero(),
+        Some(_) => Vec2::ZERO,
+        None => {
+            if bot.next_turn.tick(time.delta()).is_finished() {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                // Stand still now and then, like a player reading a quest
+                bot.direction = if rng.gen_bool(0.2) { Vec2::ZERO } else { Vec2::from_angle(angle) };
+                bot.next_turn = Timer::from_seconds(rng.gen_range(1.0..4.0), TimerMode::Once);
+            }
+            bot.direction
+        }
+    };
+    send_move(&mut commands, &bot, direction);
+
+    if bot.next_chat.tick(time.delta()).is_finished() {
+        bot.chat_count += 1;
+        let text = format!("load test {} from {}", bot.chat_count, bot.username);
+        commands.client_trigger(SendChatMessage { message: text.clone(), channel: ChatChannel::Say });
+        bot.pending_chat.push((text, Instant::now()));
+        // Messages that never came back (rate limited, filtered) aren't waited on forever
+        bot.pending_chat.retain(|(_, sent)| sent.elapsed() < Duration::from_secs(30));
+        bot.report.record(Action::Chat);
+        bot.next_chat = Timer::from_seconds(chat_interval.0, TimerMode::Once);
+    }
+}
+
+fn send_move(commands: &mut Commands, bot: &Bot, direction: Vec2) {
+    commands.client_trigger(MoveInput { direction });
+    bot.report.record(Action::Move);
+}
+
+fn report_status(mut bot: ResMut<Bot>, client: Option<Res<RenetClient>>) {
+    let mut status = BotStatus::default();
+    if let Some(client) = client {
+        if client.is_disconnected() && !matches!(bot.phase, Phase::Connecting | Phase::Failed) {
+            bot.fail("disconnected by the server".to_string());
+        }
+        let info = client.network_info();
+        status.rtt_ms = info.rtt * 1000.0;
+        status.bytes_sent_per_sec = info.bytes_sent_per_second;
+        status.bytes_received_per_sec = info.bytes_received_per_second;
+    }
+    status.phase = bot.phase;
+    bot.report.set_status(bot.index, status);
+}
+
+/// Disconnect cleanly and exit once the run is over, or as soon as the bot has failed
+fn check_stop(
+    bot: Res<Bot>,
+    transport: Option<ResMut<NetcodeClientTransport>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if bot.stop.load(Ordering::Relaxed) || bot.phase == Phase::Failed {
+        if let Some(mut transport) = transport {
+            transport.disconnect();
+        }
+        bot.report.set_status(bot.index, BotStatus { phase: bot.phase, ..Default::default() });
+        exit.write(AppExit::Success);
+    }
+}
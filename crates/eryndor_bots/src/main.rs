@@ -0,0 +1,157 @@
+//! Eryndor load test bots
+//!
+//! Starts a crowd of scripted clients against a running server and reports how it holds up:
+//! login and character select times, chat round trips, RTT and bandwidth.
+//!
+//! ```text
+//! cargo run -p eryndor_bots --release -- --bots 200 --server 127.0.0.1:5001 --duration 300
+//! ```
+//!
+//! Every bot creates its own account on first run and logs in from the same address, so the
+//! target server's `[rate_limits]` need raising well past the bot count before a run.
+
+mod bot;
+mod report;
+
+use report::Report;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often progress is printed while the test runs
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How a run is set up, from the command line
+pub struct Options {
+    pub server: SocketAddr,
+    pub bots: usize,
+    pub duration: Duration,
+    /// Delay between starting one bot and the next
+    pub ramp: Duration,
+    /// Usernames are the prefix plus the bot's number, e.g. "bot0007"
+    pub prefix: String,
+    pub password: String,
+    pub chat_interval: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            server: SocketAddr::from(([127, 0, 0, 1], eryndor_shared::SERVER_PORT)),
+            bots: 10,
+            duration: Duration::from_secs(60),
+            ramp: Duration::from_millis(100),
+            prefix: "bot".to_string(),
+            password: "BotPassword123".to_string(),
+            chat_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => Arc::new(options),
+        Err(e) => {
+            eprintln!("{}", e);
+            print_usage();
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "Starting {} bots against {} for {}s",
+        options.bots, options.server, options.duration.as_secs(),
+    );
+
+    let report = Arc::new(Report::new(options.bots));
+    let stop = Arc::new(AtomicBool::new(false));
+    let started = Instant::now();
+
+    let ramp = {
+        let options = options.clone();
+        let report = report.clone();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut handles = Vec::with_capacity(options.bots);
+            for index in 0..options.bots {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let (options, report, stop) = (options.clone(), report.clone(), stop.clone());
+                let spawned = thread::Builder::new()
+                    .name(format!("bot-{}", index))
+                    .spawn(move || bot::run(index, options, report, stop));
+                match spawned {
+                    Ok(handle) => handles.push(handle),
+                    Err(e) => report.error(index, format!("could not start thread: {}", e)),
+                }
+                thread::sleep(options.ramp);
+            }
+            handles
+        })
+    };
+
+    let mut next_status = STATUS_INTERVAL;
+    while started.elapsed() < options.duration {
+        thread::sleep(Duration::from_millis(250));
+        if started.elapsed() >= next_status {
+            println!("{}", report.status_line(started.elapsed()));
+            next_status += STATUS_INTERVAL;
+        }
+    }
+
+    println!("Stopping bots...");
+    stop.store(true, Ordering::Relaxed);
+    let handles = ramp.join().unwrap_or_default();
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    println!("\n{}", report.summary(started.elapsed()));
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            print_usage();
+            std::process::exit(0);
+        }
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+
+        match flag.as_str() {
+            "--bots" => options.bots = value.parse().map_err(|_| invalid())?,
+            "--server" => options.server = value.parse().map_err(|_| invalid())?,
+            "--duration" => options.duration = Duration::from_secs(value.parse().map_err(|_| invalid())?),
+            "--ramp" => options.ramp = Duration::from_millis(value.parse().map_err(|_| invalid())?),
+            "--chat-interval" => options.chat_interval = Duration::from_secs(value.parse().map_err(|_| invalid())?),
+            "--prefix" => options.prefix = value,
+            "--password" => options.password = value,
+            _ => return Err(format!("Unknown option: {}", flag)),
+        }
+    }
+
+    if options.bots == 0 {
+        return Err("--bots must be at least 1".to_string());
+    }
+    Ok(options)
+}
+
+fn print_usage() {
+    println!("Eryndor load test bots");
+    println!("\nUsage: bots [options]");
+    println!("\nOptions:");
+    println!("  --bots <n>              - Number of bots to run (default: 10)");
+    println!("  --server <addr:port>    - Server to connect to (default: 127.0.0.1:{})", eryndor_shared::SERVER_PORT);
+    println!("  --duration <secs>       - How long to run before disconnecting (default: 60)");
+    println!("  --ramp <ms>             - Delay between starting each bot (default: 100)");
+    println!("  --chat-interval <secs>  - How often each bot says something (default: 15)");
+    println!("  --prefix <name>         - Username prefix, numbered per bot (default: bot)");
+    println!("  --password <password>   - Password for every bot account (default: BotPassword123)");
+    println!("\nThe server rate limits account creation and login per address. Raise");
+    println!("[rate_limits] in the server's config.toml above the bot count first.");
+}
@@ -0,0 +1,196 @@
+//! Numbers collected from every bot, and the summary printed from them.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Where a bot has got to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Phase {
+    #[default]
+    Connecting,
+    LoggingIn,
+    SelectingCharacter,
+    InGame,
+    Failed,
+}
+
+/// One bot's connection as of its last update
+#[derive(Clone, Copy, Default)]
+pub struct BotStatus {
+    pub phase: Phase,
+    pub rtt_ms: f64,
+    pub bytes_sent_per_sec: f64,
+    pub bytes_received_per_sec: f64,
+}
+
+/// Shared by every bot thread and the reporter
+#[derive(Default)]
+pub struct Report {
+    data: Mutex<ReportData>,
+}
+
+#[derive(Default)]
+struct ReportData {
+    bots: Vec<BotStatus>,
+    login_ms: Vec<f64>,
+    select_ms: Vec<f64>,
+    chat_ms: Vec<f64>,
+    moves_sent: u64,
+    chats_sent: u64,
+    targets_set: u64,
+    deaths: u64,
+    errors: Vec<String>,
+}
+
+/// Kept so a server that rejects every bot doesn't fill the screen
+const MAX_ERRORS: usize = 20;
+
+/// What a latency measurement is of
+#[derive(Clone, Copy)]
+pub enum Latency {
+    /// Login request to login response
+    Login,
+    /// Character select to the character being in the world
+    Select,
+    /// Say message sent to it coming back from the server
+    Chat,
+}
+
+/// Something a bot did, for the throughput figures
+#[derive(Clone, Copy)]
+pub enum Action {
+    Move,
+    Chat,
+    Target,
+    Death,
+}
+
+impl Report {
+    pub fn new(bots: usize) -> Self {
+        Self {
+            data: Mutex::new(ReportData {
+                bots: vec![BotStatus::default(); bots],
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut ReportData) -> T) -> T {
+        let mut data = self.data.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut data)
+    }
+
+    pub fn set_status(&self, bot: usize, status: BotStatus) {
+        self.with(|data| {
+            if let Some(slot) = data.bots.get_mut(bot) {
+                *slot = status;
+            }
+        });
+    }
+
+    pub fn record_latency(&self, latency: Latency, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.with(|data| match latency {
+            Latency::Login => data.login_ms.push(ms),
+            Latency::Select => data.select_ms.push(ms),
+            Latency::Chat => data.chat_ms.push(ms),
+        });
+    }
+
+    pub fn record(&self, action: Action) {
+        self.with(|data| match action {
+            Action::Move => data.moves_sent += 1,
+            Action::Chat => data.chats_sent += 1,
+            Action::Target => data.targets_set += 1,
+            Action::Death => data.deaths += 1,
+        });
+    }
+
+    pub fn error(&self, bot: usize, message: String) {
+        self.with(|data| {
+            if data.errors.len() < MAX_ERRORS {
+                data.errors.push(format!("bot {}: {}", bot, message));
+            }
+        });
+    }
+
+    /// One status line, with rates worked out over `elapsed`
+    pub fn status_line(&self, elapsed: Duration) -> String {
+        self.with(|data| {
+            let count = |phase| data.bots.iter().filter(|bot| bot.phase == phase).count();
+            let connected: Vec<&BotStatus> = data.bots.iter()
+                .filter(|bot| !matches!(bot.phase, Phase::Connecting | Phase::Failed))
+                .collect();
+            let average_rtt = if connected.is_empty() {
+                0.0
+            } else {
+                connected.iter().map(|bot| bot.rtt_ms).sum::<f64>() / connected.len() as f64
+            };
+            let received: f64 = data.bots.iter().map(|bot| bot.bytes_received_per_sec).sum();
+            let sent: f64 = data.bots.iter().map(|bot| bot.bytes_sent_per_sec).sum();
+            let seconds = elapsed.as_secs_f64().max(1.0);
+
+            format!(
+                "[{:>5.0}s] in game {}/{} (connecting {}, logging in {}, selecting {}, failed {}) | rtt {:.0} ms | in {:.1} KB/s, out {:.1} KB/s | {:.0} moves/s, {:.1} chats/s",
+                elapsed.as_secs_f64(),
+                count(Phase::InGame),
+                data.bots.len(),
+                count(Phase::Connecting),
+                count(Phase::LoggingIn),
+                count(Phase::SelectingCharacter),
+                count(Phase::Failed),
+                average_rtt,
+                received / 1024.0,
+                sent / 1024.0,
+                data.moves_sent as f64 / seconds,
+                data.chats_sent as f64 / seconds,
+            )
+        })
+    }
+
+    /// Everything, for the end of the run
+    pub fn summary(&self, elapsed: Duration) -> String {
+        let mut lines = vec![self.status_line(elapsed)];
+        self.with(|data| {
+            lines.push(format!("login        {}", percentiles(&data.login_ms)));
+            lines.push(format!("select       {}", percentiles(&data.select_ms)));
+            lines.push(format!("chat echo    {}", percentiles(&data.chat_ms)));
+            lines.push(format!(
+                "sent {} moves, {} chats, {} targets; {} deaths",
+                data.moves_sent, data.chats_sent, data.targets_set, data.deaths,
+            ));
+            if !data.errors.is_empty() {
+                lines.push(format!("errors (first {}):", data.errors.len()));
+                lines.extend(data.errors.iter().map(|error| format!("  {}", error)));
+            }
+        });
+        lines.join("\n")
+    }
+}
+
+/// "n=.. p50 .. p95 .. p99 .. max .." in milliseconds
+fn percentiles(samples: &[f64]) -> String {
+    if samples.is_empty() {
+        return "no samples".to_string();
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let at = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+    format!(
+        "n={} p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+        sorted.len(), at(0.5), at(0.95), at(0.99), sorted[sorted.len() - 1],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_of_a_spread() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentiles(&samples), "n=100 p50 51.0 ms, p95 95.0 ms, p99 99.0 ms, max 100.0 ms");
+        assert_eq!(percentiles(&[]), "no samples");
+        assert_eq!(percentiles(&[7.0]), "n=1 p50 7.0 ms, p95 7.0 ms, p99 7.0 ms, max 7.0 ms");
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
-use eryndor_shared::{AbilityDefinition, AbilityType, AbilityUnlockRequirement, DebuffType};
+use eryndor_shared::{AbilityDefinition, AbilityType, AbilityUnlockRequirement, AoePlacement, AoeShape, DebuffType};
 
 #[derive(Resource)]
 pub struct ClientAbilityDatabase {
@@ -18,6 +18,8 @@ pub struct ClientAbilityInfo {
     pub mana_cost: f32,
     pub effect_summary: String,
     pub unlock_level: Option<u32>,
+    /// Area shape, placement and radius (meters) for AoE abilities
+    pub aoe: Option<(AoeShape, AoePlacement, f32)>,
 }
 
 impl ClientAbilityInfo {
@@ -40,6 +42,7 @@ impl ClientAbilityInfo {
             mana_cost: def.mana_cost,
             effect_summary: generate_effect_summary(&def.ability_types),
             unlock_level,
+            aoe: def.area_of_effect(),
         }
     }
 }
@@ -55,8 +58,18 @@ fn generate_effect_summary(ability_types: &[AbilityType]) -> String {
                     parts.push("Direct Damage".to_string());
                 }
             }
-            AbilityType::AreaOfEffect { radius, max_targets } => {
-                parts.push(format!("AoE ({:.1} radius, {} targets)", radius, max_targets));
+            AbilityType::AreaOfEffect { radius, max_targets, shape, placement } => {
+                let area = match shape {
+                    AoeShape::Circle => format!("{:.1} radius", radius),
+                    AoeShape::Cone { angle } => format!("{:.0}° cone, {:.1} reach", angle, radius),
+                    AoeShape::Line { width } => format!("{:.1} x {:.1} line", radius, width),
+                };
+                let placement = match placement {
+                    AoePlacement::Target => "",
+                    AoePlacement::Caster => ", around caster",
+                    AoePlacement::Ground => ", ground targeted",
+                };
+                parts.push(format!("AoE ({}, {} targets{})", area, max_targets, placement));
             }
             AbilityType::DamageOverTime { duration: _, ticks, damage_per_tick } => {
                 parts.push(format!("DoT ({} ticks, {:.1} per tick)", ticks, damage_per_tick));
@@ -99,6 +112,9 @@ fn generate_effect_summary(ability_types: &[AbilityType]) -> String {
                     DebuffType::Root => {
                         parts.push(format!("Root ({:.1}s)", duration));
                     }
+                    DebuffType::Silence => {
+                        parts.push(format!("Silence ({:.1}s)", duration));
+                    }
                 }
             }
             AbilityType::Mobility { distance, .. } => {
@@ -107,6 +123,15 @@ fn generate_effect_summary(ability_types: &[AbilityType]) -> String {
             AbilityType::ManaShield { duration, mana_per_damage } => {
                 parts.push(format!("Mana Shield ({:.1}s, {:.1} mana/dmg)", duration, mana_per_damage));
             }
+            AbilityType::Taunt { duration } => {
+                parts.push(format!("Taunt ({:.1}s)", duration));
+            }
+            AbilityType::Projectile { speed, .. } => {
+                parts.push(format!("Projectile ({:.0} m/s)", speed));
+            }
+            AbilityType::Resurrect { health_percent } => {
+                parts.push(format!("Resurrect ({:.0}% health)", health_percent));
+            }
         }
     }
 
@@ -184,6 +209,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 15.0,
             effect_summary: "Direct Damage".to_string(),
             unlock_level: None,
+            aoe: None,
         });
 
         abilities.insert(101, ClientAbilityInfo {
@@ -196,18 +222,20 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 20.0,
             effect_summary: "Damage + Stun (2s)".to_string(),
             unlock_level: Some(3),
+            aoe: None,
         });
 
         abilities.insert(102, ClientAbilityInfo {
             id: 102,
             name: "Taunt".to_string(),
-            description: "Taunt nearby enemies, reducing their attack power.".to_string(),
+            description: "Taunt nearby enemies, forcing them to attack you and reducing their attack power.".to_string(),
             damage_multiplier: 0.0,
             cooldown: 12.0,
             range: 5.0,
             mana_cost: 25.0,
-            effect_summary: "AoE (5.0 radius) + Weaken (6s, -30% attack)".to_string(),
+            effect_summary: "AoE (5.0 radius) + Weaken (6s, -30% attack) + Taunt (6.0s)".to_string(),
             unlock_level: Some(5),
+            aoe: Some((AoeShape::Circle, AoePlacement::Caster, 5.0)),
         });
 
         abilities.insert(103, ClientAbilityInfo {
@@ -218,8 +246,9 @@ impl Default for ClientAbilityDatabase {
             cooldown: 6.0,
             range: 2.0,
             mana_cost: 30.0,
-            effect_summary: "Damage + AoE (2.0 radius, 3 targets)".to_string(),
+            effect_summary: "Damage + AoE (120° cone, 2.0 reach, 3 targets, around caster)".to_string(),
             unlock_level: Some(7),
+            aoe: Some((AoeShape::Cone { angle: 120.0 }, AoePlacement::Caster, 2.0)),
         });
 
         abilities.insert(104, ClientAbilityInfo {
@@ -232,6 +261,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 40.0,
             effect_summary: "Heal (30% max HP) + Defense Buff (+5.0, 10s)".to_string(),
             unlock_level: Some(10),
+            aoe: None,
         });
 
         abilities.insert(105, ClientAbilityInfo {
@@ -244,6 +274,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 25.0,
             effect_summary: "Dash (8.0 distance) + Damage".to_string(),
             unlock_level: Some(12),
+            aoe: None,
         });
 
         // ============================================================================
@@ -260,6 +291,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 25.0,
             effect_summary: "Direct Damage".to_string(),
             unlock_level: None,
+            aoe: None,
         });
 
         abilities.insert(201, ClientAbilityInfo {
@@ -272,6 +304,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 20.0,
             effect_summary: "Damage + Slow (4s, -50% move speed)".to_string(),
             unlock_level: Some(3),
+            aoe: None,
         });
 
         abilities.insert(202, ClientAbilityInfo {
@@ -284,6 +317,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 30.0,
             effect_summary: "Damage + DoT (8 ticks, 8.0 per tick)".to_string(),
             unlock_level: Some(5),
+            aoe: None,
         });
 
         abilities.insert(203, ClientAbilityInfo {
@@ -296,6 +330,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 45.0,
             effect_summary: "Damage + AoE (5.0 radius, 8 targets)".to_string(),
             unlock_level: Some(7),
+            aoe: Some((AoeShape::Circle, AoePlacement::Caster, 5.0)),
         });
 
         abilities.insert(204, ClientAbilityInfo {
@@ -308,6 +343,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 50.0,
             effect_summary: "Defense Buff (+8.0, 15s)".to_string(),
             unlock_level: Some(10),
+            aoe: None,
         });
 
         abilities.insert(205, ClientAbilityInfo {
@@ -320,6 +356,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 35.0,
             effect_summary: "Instant Teleport (10.0 distance)".to_string(),
             unlock_level: Some(12),
+            aoe: None,
         });
 
         // ============================================================================
@@ -336,6 +373,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 10.0,
             effect_summary: "Direct Damage".to_string(),
             unlock_level: None,
+            aoe: None,
         });
 
         abilities.insert(301, ClientAbilityInfo {
@@ -348,6 +386,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 20.0,
             effect_summary: "Direct Damage".to_string(),
             unlock_level: Some(3),
+            aoe: None,
         });
 
         abilities.insert(302, ClientAbilityInfo {
@@ -360,6 +399,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 25.0,
             effect_summary: "Damage + DoT (8 ticks, 5.0 per tick)".to_string(),
             unlock_level: Some(5),
+            aoe: None,
         });
 
         abilities.insert(303, ClientAbilityInfo {
@@ -372,6 +412,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 30.0,
             effect_summary: "Dash (8.0 distance) + Attack Buff (+3.0, 5s)".to_string(),
             unlock_level: Some(7),
+            aoe: None,
         });
 
         abilities.insert(304, ClientAbilityInfo {
@@ -382,8 +423,9 @@ impl Default for ClientAbilityDatabase {
             cooldown: 20.0,
             range: 10.0,
             mana_cost: 35.0,
-            effect_summary: "Damage + AoE (4.0 radius, 5 targets) + Root (3s)".to_string(),
+            effect_summary: "Damage + AoE (4.0 radius, 5 targets, ground targeted) + Root (3s)".to_string(),
             unlock_level: Some(10),
+            aoe: Some((AoeShape::Circle, AoePlacement::Ground, 4.0)),
         });
 
         abilities.insert(305, ClientAbilityInfo {
@@ -396,6 +438,7 @@ impl Default for ClientAbilityDatabase {
             mana_cost: 40.0,
             effect_summary: "Direct Damage".to_string(),
             unlock_level: Some(12),
+            aoe: None,
         });
 
         info!("ClientAbilityDatabase initialized with {} hardcoded abilities", abilities.len());
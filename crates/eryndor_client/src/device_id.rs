@@ -0,0 +1,82 @@
+//! Device id sent with logins so the server can enforce hardware bans.
+//!
+//! There's no portable way to read a real hardware id (and the browser can't at all), so
+//! the client makes a random id on first launch and keeps it: in a file under the user's
+//! config directory on native, and in local storage on the web.
+
+use bevy::prelude::*;
+
+/// This device's id, or None if it couldn't be stored
+#[derive(Resource)]
+pub struct DeviceId(pub Option<String>);
+
+impl DeviceId {
+    /// Load the stored id, creating one the first time
+    pub fn load() -> Self {
+        let id = load_or_create();
+        if id.is_none() {
+            warn!("Could not store a device id; logging in without one");
+        }
+        Self(id)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn load_or_create() -> Option<String> {
+    use std::hash::{BuildHasher, Hasher};
+
+    let path = storage_path()?;
+    if let Ok(id) = std::fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return Some(id.to_string());
+        }
+    }
+
+    // RandomState is seeded from the OS, which is all the randomness an id needs
+    let mut id = String::new();
+    for _ in 0..2 {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default());
+        id.push_str(&format!("{:016x}", hasher.finish()));
+    }
+
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::write(&path, &id).ok()?;
+    Some(id)
+}
+
+/// `%APPDATA%\Eryndor\device_id` on Windows, `~/.config/eryndor/device_id` elsewhere
+#[cfg(not(target_family = "wasm"))]
+fn storage_path() -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    if let Some(appdata) = std::env::var_os("APPDATA") {
+        return Some(PathBuf::from(appdata).join("Eryndor").join("device_id"));
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("eryndor").join("device_id"))
+}
+
+#[cfg(target_family = "wasm")]
+fn load_or_create() -> Option<String> {
+    const KEY: &str = "eryndor_device_id";
+
+    let storage = web_sys::window()?.local_storage().ok()??;
+    if let Ok(Some(id)) = storage.get_item(KEY) {
+        if !id.is_empty() {
+            return Some(id);
+        }
+    }
+
+    let id: String = (0..4)
+        .map(|_| format!("{:08x}", (js_sys::Math::random() * u32::MAX as f64) as u32))
+        .collect();
+    storage.set_item(KEY, &id).ok()?;
+    Some(id)
+}
@@ -21,6 +21,64 @@ pub struct MyClientState {
     pub player_entity: Option<Entity>,
     pub notifications: Vec<String>,
     pub connection_error_shown: bool,
+    /// Shard this client is connected to, and every shard it could switch to
+    pub current_shard: Option<String>,
+    pub shards: Vec<ShardInfo>,
+    /// Shard picked at character select; the connection moves there next frame
+    pub pending_shard_switch: Option<ShardInfo>,
+}
+
+/// Where `connect_to_server` connects. Starts out as the build's configured server and
+/// changes when the player switches shards.
+#[derive(Resource, Clone, Debug)]
+pub struct ServerEndpoint {
+    pub address: String,
+    pub udp_port: u16,
+    pub webtransport_port: u16,
+    /// Where a web client fetches the server's certificate hash
+    pub cert_url: String,
+}
+
+impl Default for ServerEndpoint {
+    #[cfg(not(target_family = "wasm"))]
+    fn default() -> Self {
+        Self {
+            address: SERVER_ADDR.to_string(),
+            udp_port: SERVER_PORT,
+            webtransport_port: 5002,
+            cert_url: format!("http://{}:8080/cert", SERVER_ADDR),
+        }
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn default() -> Self {
+        // Environment-based configuration - allows testing local client against prod server
+        // Local dev: defaults to 127.0.0.1
+        // Test against prod: SERVER_IP=165.227.217.144 bevy run web
+        // Production build: .do/app.yaml sets SERVER_IP and CERT_URL
+        let address = option_env!("SERVER_IP").unwrap_or("127.0.0.1").to_string();
+        let webtransport_port = option_env!("SERVER_PORT_WT")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5002);
+        // For production, use HTTPS cert URL via nginx proxy
+        // For local dev, use HTTP directly to cert server
+        let cert_url = match option_env!("CERT_URL") {
+            Some(url) => url.to_string(),
+            None => format!("http://{}:8080/cert", address),
+        };
+        Self { address, udp_port: SERVER_PORT, webtransport_port, cert_url }
+    }
+}
+
+impl ServerEndpoint {
+    pub fn for_shard(shard: &ShardInfo) -> Self {
+        Self {
+            address: shard.address.clone(),
+            udp_port: shard.udp_port,
+            webtransport_port: shard.webtransport_port,
+            cert_url: shard.cert_url.clone(),
+        }
+    }
 }
 
 pub fn handle_login_response(
@@ -34,8 +92,14 @@ pub fn handle_login_response(
         info!("Login successful!");
         client_state.account_id = response.account_id;
         ui_state.is_admin = response.is_admin;
+        ui_state.two_factor_enabled = response.two_factor_enabled;
+        ui_state.two_factor_required = false;
+        ui_state.totp_code.clear();
         next_state.set(GameState::CharacterSelect);
     } else {
+        if response.two_factor_required {
+            ui_state.two_factor_required = true;
+        }
         warn!("Login failed: {}", response.message);
         client_state.notifications.push(response.message.clone());
     }
@@ -50,6 +114,52 @@ pub fn handle_character_list(
     client_state.characters = response.characters.clone();
 }
 
+/// Ask for the shard list whenever character select opens
+pub fn request_shard_list(mut commands: Commands) {
+    commands.client_trigger(GetShardListRequest);
+}
+
+pub fn handle_shard_list(
+    trigger: On<ShardListResponse>,
+    mut client_state: ResMut<MyClientState>,
+) {
+    let response = trigger.event();
+    info!("Received {} shards (connected to '{}')", response.shards.len(), response.current_shard);
+    client_state.current_shard = Some(response.current_shard.clone());
+    client_state.shards = response.shards.clone();
+}
+
+/// Move the connection to the shard picked at character select. Logins don't carry over
+/// between servers, so the player lands back on the login screen.
+pub fn switch_shard(
+    mut commands: Commands,
+    mut client_state: ResMut<MyClientState>,
+    mut next_state: ResMut<NextState<GameState>>,
+    channels: Res<RepliconChannels>,
+    transport: Option<ResMut<bevy_renet2::netcode::NetcodeClientTransport>>,
+) {
+    let Some(shard) = client_state.pending_shard_switch.take() else { return };
+    info!("Switching to shard '{}' at {}", shard.id, shard.address);
+
+    if let Some(mut transport) = transport {
+        transport.disconnect();
+    }
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<bevy_renet2::netcode::NetcodeClientTransport>();
+
+    let endpoint = ServerEndpoint::for_shard(&shard);
+    connect(&mut commands, &channels, &endpoint);
+    commands.insert_resource(endpoint);
+
+    client_state.account_id = None;
+    client_state.characters.clear();
+    client_state.shards.clear();
+    client_state.current_shard = None;
+    client_state.connection_error_shown = false;
+    client_state.notifications.push(format!("Switched to {}. Log in to continue.", shard.name));
+    next_state.set(GameState::Login);
+}
+
 pub fn handle_create_account_response(
     trigger: On<CreateAccountResponse>,
     mut client_state: ResMut<MyClientState>,
@@ -142,8 +252,12 @@ pub fn detect_player_entity(
     }
 }
 
+pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>, endpoint: Res<ServerEndpoint>) {
+    connect(&mut commands, &channels, &endpoint);
+}
+
 #[cfg(not(target_family = "wasm"))]
-pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>, _time: Res<Time>) {
+fn connect(commands: &mut Commands, channels: &RepliconChannels, endpoint: &ServerEndpoint) {
     info!("Connecting to server...");
 
     let connection_config = ConnectionConfig::from_channels(
@@ -151,9 +265,13 @@ pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>
         channels.client_configs(),
     );
 
-    let server_addr: std::net::SocketAddr = format!("{}:{}", SERVER_ADDR, SERVER_PORT)
-        .parse()
-        .expect("Invalid server address");
+    let server_addr: std::net::SocketAddr = match format!("{}:{}", endpoint.address, endpoint.udp_port).parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            error!("Invalid server address {}:{}: {}", endpoint.address, endpoint.udp_port, e);
+            return;
+        }
+    };
 
     use bevy_renet2::netcode::{ClientAuthentication, NetcodeClientTransport};
 
@@ -198,7 +316,7 @@ pub struct PendingWebTransportConnection {
 }
 
 #[cfg(target_family = "wasm")]
-pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>, _time: Res<Time>) {
+fn connect(commands: &mut Commands, channels: &RepliconChannels, endpoint: &ServerEndpoint) {
     // WASM Client: Uses WebTransport (HTTP/3 + QUIC)
     // - Production: Connects to SERVER_IP via port 5002, fetches cert from CERT_URL (HTTPS via nginx)
     // - Local dev: Defaults to 127.0.0.1:5002, fetches cert from http://127.0.0.1:8080/cert
@@ -208,18 +326,8 @@ pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>
 
     info!("Connecting to server via WebTransport...");
 
-    // Environment-based configuration - allows testing local client against prod server
-    // Local dev: defaults to 127.0.0.1
-    // Test against prod: SERVER_IP=165.227.217.144 bevy run web
-    // Production build: .do/app.yaml sets SERVER_IP and CERT_URL
-    let server_ip = option_env!("SERVER_IP").unwrap_or("127.0.0.1");
-    let wt_port: u16 = option_env!("SERVER_PORT_WT")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(5002);
-
-    // For production, use HTTPS cert URL via nginx proxy
-    // For local dev, use HTTP directly to cert server
-    let cert_url_override = option_env!("CERT_URL");
+    let server_ip = &endpoint.address;
+    let wt_port = endpoint.webtransport_port;
 
     info!("Server IP: {}, WebTransport port: {}", server_ip, wt_port);
 
@@ -242,11 +350,7 @@ pub fn connect_to_server(mut commands: Commands, channels: Res<RepliconChannels>
     info!("Generated client_id: {}", client_id);
 
     // Fetch certificate hash and connect - this must be async
-    // Use CERT_URL if set (production via nginx HTTPS), otherwise fall back to HTTP
-    let cert_url = match cert_url_override {
-        Some(url) => url.to_string(),
-        None => format!("http://{}:8080/cert", server_ip),
-    };
+    let cert_url = endpoint.cert_url.clone();
     let server_url_str = format!("https://{}:{}", server_ip, wt_port);
 
     info!("Fetching WebTransport certificate hash from {}", cert_url);
@@ -1,12 +1,34 @@
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use eryndor_shared::*;
+use crate::ability_cache::ClientAbilityDatabase;
 use crate::game_state::MyClientState;
 use crate::ui::UiState;
 
 #[derive(Resource, Default)]
 pub struct InputState {
     pub selected_target: Option<Entity>,
+    /// Ground-targeted ability waiting for the player to click where to place it
+    pub pending_ground_ability: Option<u32>,
+}
+
+impl InputState {
+    /// Use an ability, or start placing it first if it is ground targeted
+    pub fn use_ability(&mut self, ability_id: u32, target_position: Option<Vec2>, ability_db: &ClientAbilityDatabase, commands: &mut Commands) {
+        let ground_targeted = ability_db.get_ability_info(ability_id)
+            .and_then(|info| info.aoe)
+            .is_some_and(|(_, placement, _)| placement == AoePlacement::Ground);
+
+        if ground_targeted {
+            self.pending_ground_ability = Some(ability_id);
+            info!("Placing ground-targeted ability {}", ability_id);
+        } else {
+            commands.client_trigger(UseAbilityRequest {
+                ability_id,
+                target_position,
+            });
+        }
+    }
 }
 
 pub fn handle_movement_input(
@@ -15,8 +37,8 @@ pub fn handle_movement_input(
     ui_state: Res<UiState>,
     mut commands: Commands,
 ) {
-    // Don't handle movement if ESC menu is open, chat has focus, or system menu is open
-    if ui_state.show_esc_menu || ui_state.chat_has_focus || ui_state.show_system_menu {
+    // Don't handle movement if ESC menu is open, chat has focus, or system menu or settings are open
+    if ui_state.show_esc_menu || ui_state.chat_has_focus || ui_state.show_system_menu || ui_state.show_settings {
         return;
     }
 
@@ -61,6 +83,11 @@ pub fn handle_targeting_input(
         return;
     }
 
+    // Clicks place a pending ground-targeted ability instead (see handle_ground_targeting)
+    if input_state.pending_ground_ability.is_some() {
+        return;
+    }
+
     // Debug: Log how many targetable entities exist
     let targetable_count = targetable_query.iter().count();
     let npc_count = npc_query.iter().count();
@@ -138,8 +165,16 @@ pub fn handle_ability_input(
     player_query: Query<&Hotbar>,
     windows: Query<&Window>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
+    ability_db: Res<ClientAbilityDatabase>,
+    ui_state: Res<UiState>,
+    mut input_state: ResMut<InputState>,
     mut commands: Commands,
 ) {
+    // Number keys type into the settings window's code field
+    if ui_state.show_settings {
+        return;
+    }
+
     let Some(player_entity) = client_state.player_entity else { return };
     let Ok(hotbar) = player_query.get(player_entity) else { return };
 
@@ -154,23 +189,51 @@ pub fn handle_ability_input(
             if let Some(slot) = &hotbar.slots[i] {
                 match slot {
                     HotbarSlot::Ability(ability_id) => {
-                        // Get cursor position in world coordinates for ground-targeted abilities
+                        // Cursor position aims dashes, cones and lines
                         let target_position = get_cursor_world_position(&windows, &camera_query);
 
-                        commands.client_trigger(UseAbilityRequest {
-                            ability_id: *ability_id,
-                            target_position,
-                        });
+                        input_state.use_ability(*ability_id, target_position, &ability_db, &mut commands);
                         info!("Used ability from slot {} (target_pos: {:?})", i + 1, target_position);
                     }
+                    HotbarSlot::Item(item_id) => {
+                        commands.client_trigger(UseItemRequest { item_id: *item_id });
+                    }
                 }
             }
         }
     }
 }
 
+/// Place a pending ground-targeted ability: left-click casts at the cursor, right-click cancels
+pub fn handle_ground_targeting(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    ui_state: Res<UiState>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut input_state: ResMut<InputState>,
+    mut commands: Commands,
+) {
+    let Some(ability_id) = input_state.pending_ground_ability else { return };
+
+    if ui_state.show_esc_menu || mouse_button.just_pressed(MouseButton::Right) {
+        input_state.pending_ground_ability = None;
+        info!("Cancelled ground-targeted ability {}", ability_id);
+        return;
+    }
+
+    if mouse_button.just_pressed(MouseButton::Left) {
+        let Some(target_position) = get_cursor_world_position(&windows, &camera_query) else { return };
+        input_state.pending_ground_ability = None;
+        commands.client_trigger(UseAbilityRequest {
+            ability_id,
+            target_position: Some(target_position),
+        });
+        info!("Placed ground-targeted ability {} at {:?}", ability_id, target_position);
+    }
+}
+
 /// Helper function to get cursor position in world coordinates
-fn get_cursor_world_position(
+pub fn get_cursor_world_position(
     windows: &Query<&Window>,
     camera_query: &Query<(&Camera, &GlobalTransform)>,
 ) -> Option<Vec2> {
@@ -0,0 +1,120 @@
+//! Entity interpolation for remote entities.
+//!
+//! Replicated positions arrive at the server's tick rate, so drawing them directly makes
+//! other players and enemies stutter. Instead we buffer timestamped snapshots and render
+//! each remote entity slightly in the past, blending between the two snapshots around
+//! the render time. The local player is not interpolated.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use eryndor_shared::*;
+
+use crate::game_state::MyClientState;
+
+/// How far in the past remote entities are rendered (seconds)
+pub const INTERPOLATION_DELAY: f64 = 0.1;
+/// Snapshots older than this are dropped (seconds)
+const SNAPSHOT_RETENTION: f64 = 1.0;
+/// Position jumps larger than this are treated as teleports and snapped instead of blended
+const TELEPORT_DISTANCE: f32 = 200.0;
+
+/// Timestamped replicated positions for one entity, oldest first
+#[derive(Component, Default, Debug)]
+pub struct PositionBuffer {
+    pub snapshots: VecDeque<(f64, Vec2)>,
+}
+
+impl PositionBuffer {
+    pub fn push(&mut self, time: f64, position: Vec2) {
+        if let Some(&(_, last)) = self.snapshots.back() {
+            if last.distance(position) > TELEPORT_DISTANCE {
+                self.snapshots.clear();
+            }
+        }
+        self.snapshots.push_back((time, position));
+
+        // Keep one snapshot older than the retention window so we can still interpolate from it
+        while self.snapshots.len() > 2 && self.snapshots[1].0 < time - SNAPSHOT_RETENTION {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Position at `render_time`, clamped to the buffered range (no extrapolation)
+    pub fn sample(&self, render_time: f64) -> Option<Vec2> {
+        let &(first_time, first) = self.snapshots.front()?;
+        if render_time <= first_time {
+            return Some(first);
+        }
+
+        for window in self.snapshots.iter().zip(self.snapshots.iter().skip(1)) {
+            let (&(from_time, from), &(to_time, to)) = window;
+            if render_time <= to_time {
+                let span = (to_time - from_time).max(f64::EPSILON);
+                let t = ((render_time - from_time) / span) as f32;
+                return Some(from.lerp(to, t.clamp(0.0, 1.0)));
+            }
+        }
+
+        self.snapshots.back().map(|&(_, last)| last)
+    }
+}
+
+/// Smoothed position used for rendering remote entities
+#[derive(Component, Clone, Copy, Debug)]
+pub struct InterpolatedPosition(pub Vec2);
+
+/// Record a snapshot whenever a remote entity's replicated position changes
+pub fn record_position_snapshots(
+    mut commands: Commands,
+    client_state: Res<MyClientState>,
+    time: Res<Time>,
+    mut query: Query<(Entity, &Position, Option<&mut PositionBuffer>), Changed<Position>>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    for (entity, position, buffer) in &mut query {
+        // Our own character may have been buffered before detect_player_entity found it
+        if client_state.player_entity == Some(entity) {
+            if buffer.is_some() {
+                commands.entity(entity).remove::<(PositionBuffer, InterpolatedPosition)>();
+            }
+            continue;
+        }
+
+        match buffer {
+            Some(mut buffer) => buffer.push(now, position.0),
+            None => {
+                let mut buffer = PositionBuffer::default();
+                buffer.push(now, position.0);
+                commands.entity(entity).insert((buffer, InterpolatedPosition(position.0)));
+            }
+        }
+    }
+}
+
+/// Sample every buffer at the delayed render time
+pub fn interpolate_positions(
+    time: Res<Time>,
+    mut query: Query<(&PositionBuffer, &mut InterpolatedPosition)>,
+) {
+    let render_time = time.elapsed_secs_f64() - INTERPOLATION_DELAY;
+
+    for (buffer, mut interpolated) in &mut query {
+        if let Some(position) = buffer.sample(render_time) {
+            interpolated.0 = position;
+        }
+    }
+}
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            record_position_snapshots,
+            interpolate_positions,
+        ).chain().before(crate::rendering::update_visual_positions)
+            .before(crate::rendering::update_name_label_positions));
+    }
+}
+
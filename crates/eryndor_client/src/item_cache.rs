@@ -250,6 +250,43 @@ impl Default for ClientItemDatabase {
             },
         });
 
+        // ========== CONSUMABLES ==========
+
+        items.insert(ITEM_MINOR_HEALING_POTION, ClientItemInfo {
+            id: ITEM_MINOR_HEALING_POTION,
+            name: "Minor Healing Potion".to_string(),
+            item_type: ItemType::Consumable,
+            stat_bonuses: ClientStatBonuses::default(),
+        });
+
+        items.insert(ITEM_MINOR_MANA_POTION, ClientItemInfo {
+            id: ITEM_MINOR_MANA_POTION,
+            name: "Minor Mana Potion".to_string(),
+            item_type: ItemType::Consumable,
+            stat_bonuses: ClientStatBonuses::default(),
+        });
+
+        items.insert(ITEM_ELIXIR_OF_STRENGTH, ClientItemInfo {
+            id: ITEM_ELIXIR_OF_STRENGTH,
+            name: "Elixir of Strength".to_string(),
+            item_type: ItemType::Consumable,
+            stat_bonuses: ClientStatBonuses::default(),
+        });
+
+        items.insert(ITEM_TRAVEL_BREAD, ClientItemInfo {
+            id: ITEM_TRAVEL_BREAD,
+            name: "Travel Bread".to_string(),
+            item_type: ItemType::Consumable,
+            stat_bonuses: ClientStatBonuses::default(),
+        });
+
+        items.insert(ITEM_ROASTED_BOAR, ClientItemInfo {
+            id: ITEM_ROASTED_BOAR,
+            name: "Roasted Boar".to_string(),
+            item_type: ItemType::Consumable,
+            stat_bonuses: ClientStatBonuses::default(),
+        });
+
         Self { items }
     }
 }
@@ -332,3 +369,40 @@ impl ClientStatBonuses {
         self.crit_chance += other.crit_chance;
     }
 }
+
+/// Consumable cooldowns as told by the server, for the hotbar sweep
+#[derive(Resource, Default)]
+pub struct ItemCooldownTimers {
+    /// Item ID -> (seconds remaining, total duration)
+    cooldowns: HashMap<u32, (f32, f32)>,
+}
+
+impl ItemCooldownTimers {
+    /// Share of the cooldown still to run, 0.0 when ready
+    pub fn remaining_fraction(&self, item_id: u32) -> f32 {
+        self.cooldowns.get(&item_id)
+            .map(|(remaining, duration)| if *duration > 0.0 { remaining / duration } else { 0.0 })
+            .unwrap_or(0.0)
+    }
+}
+
+pub fn handle_item_cooldown(
+    trigger: On<ItemCooldownEvent>,
+    mut timers: ResMut<ItemCooldownTimers>,
+) {
+    let event = trigger.event();
+    for item_id in &event.item_ids {
+        timers.cooldowns.insert(*item_id, (event.duration, event.duration));
+    }
+}
+
+pub fn tick_item_cooldowns(mut timers: ResMut<ItemCooldownTimers>, time: Res<Time>) {
+    if timers.cooldowns.is_empty() {
+        return;
+    }
+    let delta = time.delta_secs();
+    timers.cooldowns.retain(|_, (remaining, _)| {
+        *remaining -= delta;
+        *remaining > 0.0
+    });
+}
@@ -11,11 +11,15 @@
 
 mod rendering;
 mod ui;
+mod device_id;
 mod input;
 mod game_state;
 mod item_cache;
 mod ability_cache;
 mod sprite_loader;
+mod tilemap;
+mod interpolation;
+mod weather;
 
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
@@ -52,108 +56,25 @@ fn main() {
             ShapePlugin,
             EguiPlugin::default(),
             sprite_loader::SpriteLoaderPlugin,
+            tilemap::ZoneTilemapPlugin,
+            interpolation::InterpolationPlugin,
         ))
         // Game state
         .init_state::<GameState>()
         .init_resource::<MyClientState>()
+        .init_resource::<ServerEndpoint>()
         .init_resource::<input::InputState>()
         .init_resource::<ui::UiState>()
+        .init_resource::<ui::CombatLogState>()
+        .init_resource::<ui::FriendsState>()
+        .init_resource::<ui::SettingsState>()
+        .insert_resource(device_id::DeviceId::load())
+        .init_resource::<weather::RainSpawner>()
         .init_resource::<item_cache::ClientItemDatabase>()
+        .init_resource::<item_cache::ItemCooldownTimers>()
         .init_resource::<ability_cache::ClientAbilityDatabase>()
-        // Register replicated components (same as server)
-        .replicate::<Player>()
-        .replicate::<Character>()
-        .replicate::<OwnedBy>()
-        .replicate::<Position>()
-        .replicate::<Experience>()
-        .replicate::<WeaponProficiencyExp>()
-        .replicate::<ArmorProficiency>()
-        .replicate::<ArmorProficiencyExp>()
-        .replicate::<UnlockedArmorPassives>()
-        .replicate::<Velocity>()
-        .replicate::<MoveSpeed>()
-        .replicate::<AnimationState>()
-        .replicate::<Health>()
-        .replicate::<Mana>()
-        .replicate::<HealthRegen>()
-        .replicate::<ManaRegen>()
-        .replicate::<CombatStats>()
-        .replicate::<CurrentTarget>()
-        .replicate::<InCombat>()
-        .replicate::<AutoAttack>()
-        .replicate::<WeaponProficiency>()
-        .replicate::<Gold>()
-        .replicate::<Inventory>()
-        .replicate::<Equipment>()
-        .replicate::<Hotbar>()
-        .replicate::<LearnedAbilities>()
-        .replicate::<QuestLog>()
-        .replicate::<Npc>()
-        .replicate::<QuestGiver>()
-        .replicate::<NpcName>()
-        .replicate::<Enemy>()
-        .replicate::<EnemyType>()
-        .replicate::<EnemyName>()
-        .replicate::<AiState>()
-        .replicate::<WorldItem>()
-        .replicate::<GoldDrop>()
-        .replicate::<LootContainer>()
-        .replicate::<Interactable>()
-        .replicate::<VisualShape>()
-        .replicate::<ActiveBuffs>()
-        .replicate::<ActiveDebuffs>()
-        .replicate::<ActiveDoTs>()
-        // Register client -> server events
-        .add_client_event::<LoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateAccountRequest>(Channel::Ordered)
-        .add_client_event::<OAuthLoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateCharacterRequest>(Channel::Ordered)
-        .add_client_event::<SelectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<MoveInput>(Channel::Unreliable)
-        .add_mapped_client_event::<SetTargetRequest>(Channel::Ordered)
-        .add_client_event::<UseAbilityRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PickupItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<OpenLootContainerRequest>(Channel::Ordered)
-        .add_mapped_client_event::<LootItemRequest>(Channel::Ordered)
-        .add_client_event::<AutoLootRequest>(Channel::Ordered)
-        .add_client_event::<DropItemRequest>(Channel::Ordered)
-        .add_client_event::<EquipItemRequest>(Channel::Ordered)
-        .add_client_event::<UnequipItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<InteractNpcRequest>(Channel::Ordered)
-        .add_client_event::<AcceptQuestRequest>(Channel::Ordered)
-        .add_client_event::<CompleteQuestRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PurchaseFromTrainerRequest>(Channel::Ordered)
-        .add_client_event::<SetHotbarSlotRequest>(Channel::Ordered)
-        .add_client_event::<DisconnectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<AdminCommandRequest>(Channel::Ordered)
-        .add_client_event::<SendChatMessage>(Channel::Ordered)
-        // Dashboard query events
-        .add_client_event::<GetPlayerListRequest>(Channel::Ordered)
-        .add_client_event::<GetBanListRequest>(Channel::Ordered)
-        .add_client_event::<GetServerStatsRequest>(Channel::Ordered)
-        .add_client_event::<GetAuditLogsRequest>(Channel::Ordered)
-        // Register server -> client events
-        .add_server_event::<LoginResponse>(Channel::Ordered)
-        .add_server_event::<CreateAccountResponse>(Channel::Ordered)
-        .add_server_event::<OAuthLoginResponse>(Channel::Ordered)
-        .add_server_event::<CharacterListResponse>(Channel::Ordered)
-        .add_server_event::<CreateCharacterResponse>(Channel::Ordered)
-        .add_server_event::<SelectCharacterResponse>(Channel::Ordered)
-        .add_server_event::<CombatEvent>(Channel::Ordered)
-        .add_server_event::<QuestUpdateEvent>(Channel::Ordered)
-        .add_server_event::<DeathEvent>(Channel::Ordered)
-        .add_server_event::<NotificationEvent>(Channel::Ordered)
-        .add_server_event::<QuestDialogueEvent>(Channel::Ordered)
-        .add_server_event::<TrainerDialogueEvent>(Channel::Ordered)
-        .add_mapped_server_event::<LootContainerContentsEvent>(Channel::Ordered)
-        .add_server_event::<LevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ProficiencyLevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ChatMessage>(Channel::Ordered)
-        // Dashboard response events
-        .add_server_event::<PlayerListResponse>(Channel::Ordered)
-        .add_server_event::<BanListResponse>(Channel::Ordered)
-        .add_server_event::<ServerStatsResponse>(Channel::Ordered)
-        .add_server_event::<AuditLogsResponse>(Channel::Ordered)
+        // Replicated components and network events (same as the server)
+        .add_plugins(ProtocolPlugin)
         // Register observers for server -> client events
         .add_observer(game_state::handle_login_response)
         .add_observer(game_state::handle_character_list)
@@ -161,6 +82,7 @@ fn main() {
         .add_observer(game_state::handle_oauth_login_response)
         .add_observer(game_state::handle_create_character_response)
         .add_observer(game_state::handle_select_character_response)
+        .add_observer(game_state::handle_shard_list)
         .add_observer(game_state::handle_notifications)
         .add_observer(game_state::handle_level_up)
         .add_observer(game_state::handle_proficiency_level_up)
@@ -170,8 +92,17 @@ fn main() {
         .add_observer(game_state::handle_server_stats_response)
         .add_observer(game_state::handle_audit_logs_response)
         .add_observer(ui::handle_quest_dialogue)
+        .add_observer(ui::handle_npc_dialogue)
         .add_observer(ui::handle_trainer_dialogue)
+        .add_observer(ui::handle_vendor_window)
         .add_observer(ui::handle_loot_container_contents)
+        .add_observer(ui::receive_combat_log)
+        .add_observer(ui::handle_duel_challenge)
+        .add_observer(ui::handle_group_invite)
+        .add_observer(ui::receive_friend_list)
+        .add_observer(ui::receive_two_factor_setup)
+        .add_observer(ui::receive_two_factor_status)
+        .add_observer(item_cache::handle_item_cooldown)
         .add_observer(rendering::spawn_damage_numbers)
         // Systems
         .add_systems(Startup, (setup_camera, game_state::connect_to_server))
@@ -181,27 +112,43 @@ fn main() {
             ui::character_select_ui.run_if(in_state(GameState::CharacterSelect)),
             ui::game_ui.run_if(in_state(GameState::InGame)),
             ui::chat_window.run_if(in_state(GameState::InGame)),
+            ui::combat_log_window.run_if(in_state(GameState::InGame)),
+            ui::friends_window.run_if(in_state(GameState::InGame)),
+            ui::settings_window.run_if(in_state(GameState::CharacterSelect).or(in_state(GameState::InGame))),
+        ))
+        .add_systems(OnExit(GameState::InGame), (game_state::cleanup_game_entities, weather::cleanup_rain))
+        // Shard selector at character select
+        .add_systems(OnEnter(GameState::CharacterSelect), game_state::request_shard_list)
+        .add_systems(Update, game_state::switch_shard.run_if(in_state(GameState::CharacterSelect)))
+        // Day/night tint and weather effects
+        .add_systems(Update, (
+            weather::spawn_weather_overlay,
+            weather::update_weather_overlay.run_if(in_state(GameState::InGame)),
+            weather::update_rain.run_if(in_state(GameState::InGame)),
         ))
-        .add_systems(OnExit(GameState::InGame), game_state::cleanup_game_entities)
         // Core game state systems
         .add_systems(Update, (
             ui::check_oauth_callback.run_if(in_state(GameState::Login)),
             ui::receive_chat_messages.run_if(in_state(GameState::InGame)),
+            item_cache::tick_item_cooldowns.run_if(in_state(GameState::InGame)),
             game_state::monitor_connection,
             game_state::detect_player_entity.run_if(in_state(GameState::InGame)),
             game_state::handle_character_despawn.run_if(in_state(GameState::InGame)),
         ))
-        // Tilemap rendering systems (bevy_map integration pending)
-        // TODO: Add bevy_map_runtime plugin when ready
+        // Zone tilemap rendering is handled by tilemap::ZoneTilemapPlugin
         // Entity rendering systems
         .add_systems(Update, (
             rendering::spawn_visual_entities,
             rendering::update_visual_positions,
             rendering::spawn_name_labels,
+            rendering::update_player_nameplates,
             rendering::update_name_label_positions,
             rendering::cleanup_despawned_entities,
             rendering::update_camera_follow.run_if(in_state(GameState::InGame)),
             rendering::draw_target_indicator.run_if(in_state(GameState::InGame)),
+            rendering::draw_ground_target_reticle.run_if(in_state(GameState::InGame)),
+            rendering::draw_corpse_marker.run_if(in_state(GameState::InGame)),
+            rendering::draw_duel_arena.run_if(in_state(GameState::InGame)),
             rendering::update_damage_numbers.run_if(in_state(GameState::InGame)),
         ))
         // Input systems
@@ -210,6 +157,7 @@ fn main() {
             input::handle_movement_input.run_if(in_state(GameState::InGame)),
             input::handle_targeting_input.run_if(in_state(GameState::InGame)),
             input::handle_ability_input.run_if(in_state(GameState::InGame)),
+            input::handle_ground_targeting.run_if(in_state(GameState::InGame)).after(input::handle_targeting_input),
             input::handle_interaction_input.run_if(in_state(GameState::InGame)),
         ))
         .run();
@@ -247,108 +195,25 @@ fn start_app() {
             EguiPlugin::default(),
             WebKeepalivePlugin { wake_delay: 1000.0 },
             sprite_loader::SpriteLoaderPlugin,
+            tilemap::ZoneTilemapPlugin,
+            interpolation::InterpolationPlugin,
         ))
         // Game state
         .init_state::<GameState>()
         .init_resource::<MyClientState>()
+        .init_resource::<ServerEndpoint>()
         .init_resource::<input::InputState>()
         .init_resource::<ui::UiState>()
+        .init_resource::<ui::CombatLogState>()
+        .init_resource::<ui::FriendsState>()
+        .init_resource::<ui::SettingsState>()
+        .insert_resource(device_id::DeviceId::load())
+        .init_resource::<weather::RainSpawner>()
         .init_resource::<item_cache::ClientItemDatabase>()
+        .init_resource::<item_cache::ItemCooldownTimers>()
         .insert_resource(ability_cache::ClientAbilityDatabase::default())
-        // Register replicated components (same as server)
-        .replicate::<Player>()
-        .replicate::<Character>()
-        .replicate::<OwnedBy>()
-        .replicate::<Position>()
-        .replicate::<Experience>()
-        .replicate::<WeaponProficiencyExp>()
-        .replicate::<ArmorProficiency>()
-        .replicate::<ArmorProficiencyExp>()
-        .replicate::<UnlockedArmorPassives>()
-        .replicate::<Velocity>()
-        .replicate::<MoveSpeed>()
-        .replicate::<AnimationState>()
-        .replicate::<Health>()
-        .replicate::<Mana>()
-        .replicate::<HealthRegen>()
-        .replicate::<ManaRegen>()
-        .replicate::<CombatStats>()
-        .replicate::<CurrentTarget>()
-        .replicate::<InCombat>()
-        .replicate::<AutoAttack>()
-        .replicate::<WeaponProficiency>()
-        .replicate::<Gold>()
-        .replicate::<Inventory>()
-        .replicate::<Equipment>()
-        .replicate::<Hotbar>()
-        .replicate::<LearnedAbilities>()
-        .replicate::<QuestLog>()
-        .replicate::<Npc>()
-        .replicate::<QuestGiver>()
-        .replicate::<NpcName>()
-        .replicate::<Enemy>()
-        .replicate::<EnemyType>()
-        .replicate::<EnemyName>()
-        .replicate::<AiState>()
-        .replicate::<WorldItem>()
-        .replicate::<GoldDrop>()
-        .replicate::<LootContainer>()
-        .replicate::<Interactable>()
-        .replicate::<VisualShape>()
-        .replicate::<ActiveBuffs>()
-        .replicate::<ActiveDebuffs>()
-        .replicate::<ActiveDoTs>()
-        // Register client -> server events
-        .add_client_event::<LoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateAccountRequest>(Channel::Ordered)
-        .add_client_event::<OAuthLoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateCharacterRequest>(Channel::Ordered)
-        .add_client_event::<SelectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<MoveInput>(Channel::Unreliable)
-        .add_mapped_client_event::<SetTargetRequest>(Channel::Ordered)
-        .add_client_event::<UseAbilityRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PickupItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<OpenLootContainerRequest>(Channel::Ordered)
-        .add_mapped_client_event::<LootItemRequest>(Channel::Ordered)
-        .add_client_event::<AutoLootRequest>(Channel::Ordered)
-        .add_client_event::<DropItemRequest>(Channel::Ordered)
-        .add_client_event::<EquipItemRequest>(Channel::Ordered)
-        .add_client_event::<UnequipItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<InteractNpcRequest>(Channel::Ordered)
-        .add_client_event::<AcceptQuestRequest>(Channel::Ordered)
-        .add_client_event::<CompleteQuestRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PurchaseFromTrainerRequest>(Channel::Ordered)
-        .add_client_event::<SetHotbarSlotRequest>(Channel::Ordered)
-        .add_client_event::<DisconnectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<AdminCommandRequest>(Channel::Ordered)
-        .add_client_event::<SendChatMessage>(Channel::Ordered)
-        // Dashboard query events
-        .add_client_event::<GetPlayerListRequest>(Channel::Ordered)
-        .add_client_event::<GetBanListRequest>(Channel::Ordered)
-        .add_client_event::<GetServerStatsRequest>(Channel::Ordered)
-        .add_client_event::<GetAuditLogsRequest>(Channel::Ordered)
-        // Register server -> client events
-        .add_server_event::<LoginResponse>(Channel::Ordered)
-        .add_server_event::<CreateAccountResponse>(Channel::Ordered)
-        .add_server_event::<OAuthLoginResponse>(Channel::Ordered)
-        .add_server_event::<CharacterListResponse>(Channel::Ordered)
-        .add_server_event::<CreateCharacterResponse>(Channel::Ordered)
-        .add_server_event::<SelectCharacterResponse>(Channel::Ordered)
-        .add_server_event::<CombatEvent>(Channel::Ordered)
-        .add_server_event::<QuestUpdateEvent>(Channel::Ordered)
-        .add_server_event::<DeathEvent>(Channel::Ordered)
-        .add_server_event::<NotificationEvent>(Channel::Ordered)
-        .add_server_event::<QuestDialogueEvent>(Channel::Ordered)
-        .add_server_event::<TrainerDialogueEvent>(Channel::Ordered)
-        .add_mapped_server_event::<LootContainerContentsEvent>(Channel::Ordered)
-        .add_server_event::<LevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ProficiencyLevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ChatMessage>(Channel::Ordered)
-        // Dashboard response events
-        .add_server_event::<PlayerListResponse>(Channel::Ordered)
-        .add_server_event::<BanListResponse>(Channel::Ordered)
-        .add_server_event::<ServerStatsResponse>(Channel::Ordered)
-        .add_server_event::<AuditLogsResponse>(Channel::Ordered)
+        // Replicated components and network events (same as the server)
+        .add_plugins(ProtocolPlugin)
         // Register observers for server -> client events
         .add_observer(game_state::handle_login_response)
         .add_observer(game_state::handle_character_list)
@@ -356,6 +221,7 @@ fn start_app() {
         .add_observer(game_state::handle_oauth_login_response)
         .add_observer(game_state::handle_create_character_response)
         .add_observer(game_state::handle_select_character_response)
+        .add_observer(game_state::handle_shard_list)
         .add_observer(game_state::handle_notifications)
         .add_observer(game_state::handle_level_up)
         .add_observer(game_state::handle_proficiency_level_up)
@@ -365,8 +231,17 @@ fn start_app() {
         .add_observer(game_state::handle_server_stats_response)
         .add_observer(game_state::handle_audit_logs_response)
         .add_observer(ui::handle_quest_dialogue)
+        .add_observer(ui::handle_npc_dialogue)
         .add_observer(ui::handle_trainer_dialogue)
+        .add_observer(ui::handle_vendor_window)
         .add_observer(ui::handle_loot_container_contents)
+        .add_observer(ui::receive_combat_log)
+        .add_observer(ui::handle_duel_challenge)
+        .add_observer(ui::handle_group_invite)
+        .add_observer(ui::receive_friend_list)
+        .add_observer(ui::receive_two_factor_setup)
+        .add_observer(ui::receive_two_factor_status)
+        .add_observer(item_cache::handle_item_cooldown)
         .add_observer(rendering::spawn_damage_numbers)
         // Systems
         .add_systems(Startup, (setup_camera, game_state::connect_to_server))
@@ -376,28 +251,44 @@ fn start_app() {
             ui::character_select_ui.run_if(in_state(GameState::CharacterSelect)),
             ui::game_ui.run_if(in_state(GameState::InGame)),
             ui::chat_window.run_if(in_state(GameState::InGame)),
+            ui::combat_log_window.run_if(in_state(GameState::InGame)),
+            ui::friends_window.run_if(in_state(GameState::InGame)),
+            ui::settings_window.run_if(in_state(GameState::CharacterSelect).or(in_state(GameState::InGame))),
+        ))
+        .add_systems(OnExit(GameState::InGame), (game_state::cleanup_game_entities, weather::cleanup_rain))
+        // Shard selector at character select
+        .add_systems(OnEnter(GameState::CharacterSelect), game_state::request_shard_list)
+        .add_systems(Update, game_state::switch_shard.run_if(in_state(GameState::CharacterSelect)))
+        // Day/night tint and weather effects
+        .add_systems(Update, (
+            weather::spawn_weather_overlay,
+            weather::update_weather_overlay.run_if(in_state(GameState::InGame)),
+            weather::update_rain.run_if(in_state(GameState::InGame)),
         ))
-        .add_systems(OnExit(GameState::InGame), game_state::cleanup_game_entities)
         // Core game state systems
         .add_systems(Update, (
             ui::check_oauth_callback.run_if(in_state(GameState::Login)),
             ui::receive_chat_messages.run_if(in_state(GameState::InGame)),
+            item_cache::tick_item_cooldowns.run_if(in_state(GameState::InGame)),
             game_state::poll_webtransport_connection,
             game_state::monitor_connection,
             game_state::detect_player_entity.run_if(in_state(GameState::InGame)),
             game_state::handle_character_despawn.run_if(in_state(GameState::InGame)),
         ))
-        // Tilemap rendering systems (bevy_map integration pending)
-        // TODO: Add bevy_map_runtime plugin when ready
+        // Zone tilemap rendering is handled by tilemap::ZoneTilemapPlugin
         // Entity rendering systems
         .add_systems(Update, (
             rendering::spawn_visual_entities,
             rendering::update_visual_positions,
             rendering::spawn_name_labels,
+            rendering::update_player_nameplates,
             rendering::update_name_label_positions,
             rendering::cleanup_despawned_entities,
             rendering::update_camera_follow.run_if(in_state(GameState::InGame)),
             rendering::draw_target_indicator.run_if(in_state(GameState::InGame)),
+            rendering::draw_ground_target_reticle.run_if(in_state(GameState::InGame)),
+            rendering::draw_corpse_marker.run_if(in_state(GameState::InGame)),
+            rendering::draw_duel_arena.run_if(in_state(GameState::InGame)),
             rendering::update_damage_numbers.run_if(in_state(GameState::InGame)),
         ))
         // Input systems
@@ -406,6 +297,7 @@ fn start_app() {
             input::handle_movement_input.run_if(in_state(GameState::InGame)),
             input::handle_targeting_input.run_if(in_state(GameState::InGame)),
             input::handle_ability_input.run_if(in_state(GameState::InGame)),
+            input::handle_ground_targeting.run_if(in_state(GameState::InGame)).after(input::handle_targeting_input),
             input::handle_interaction_input.run_if(in_state(GameState::InGame)),
         ))
         .run();
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use eryndor_shared::*;
 use crate::game_state::MyClientState;
+use crate::interpolation::InterpolatedPosition;
 
 /// Marker for visual representation entities
 #[derive(Component)]
@@ -97,13 +98,15 @@ pub fn spawn_visual_entities(
     }
 }
 
+/// Remote entities are drawn at their interpolated position; the local player uses the latest replicated one
 pub fn update_visual_positions(
-    game_entities: Query<(Entity, &Position), Changed<Position>>,
+    game_entities: Query<(&Position, Option<&InterpolatedPosition>)>,
     mut visual_entities: Query<(&VisualEntity, &mut Transform)>,
 ) {
     for (visual, mut transform) in &mut visual_entities {
-        if let Ok((_entity, position)) = game_entities.get(visual.game_entity) {
-            transform.translation = Vec3::new(position.0.x, position.0.y, 0.0);
+        if let Ok((position, interpolated)) = game_entities.get(visual.game_entity) {
+            let render_pos = interpolated.map(|p| p.0).unwrap_or(position.0);
+            transform.translation = Vec3::new(render_pos.x, render_pos.y, 0.0);
         }
     }
 }
@@ -177,22 +180,22 @@ pub fn spawn_name_labels(
 }
 
 pub fn update_name_label_positions(
-    player_entities: Query<(Entity, &Position), With<Player>>,
-    npc_entities: Query<(Entity, &Position), With<Npc>>,
-    enemy_entities: Query<(Entity, &Position), With<Enemy>>,
+    player_entities: Query<(&Position, Option<&InterpolatedPosition>), With<Player>>,
+    npc_entities: Query<(&Position, Option<&InterpolatedPosition>), With<Npc>>,
+    enemy_entities: Query<(&Position, Option<&InterpolatedPosition>), With<Enemy>>,
     mut label_entities: Query<(&NameLabel, &mut Transform)>,
 ) {
     for (label, mut transform) in &mut label_entities {
         // Try to find position from players, NPCs, or enemies
         let position = player_entities
             .get(label.game_entity)
-            .map(|(_, pos)| pos)
-            .or_else(|_| npc_entities.get(label.game_entity).map(|(_, pos)| pos))
-            .or_else(|_| enemy_entities.get(label.game_entity).map(|(_, pos)| pos));
+            .or_else(|_| npc_entities.get(label.game_entity))
+            .or_else(|_| enemy_entities.get(label.game_entity))
+            .map(|(pos, interpolated)| interpolated.map(|p| p.0).unwrap_or(pos.0));
 
         if let Ok(position) = position {
             // Position name label above the entity
-            transform.translation = Vec3::new(position.0.x, position.0.y + 25.0, 1.0);
+            transform.translation = Vec3::new(position.x, position.y + 25.0, 1.0);
         }
     }
 }
@@ -373,32 +376,146 @@ pub fn draw_target_indicator(
     }
 }
 
+/// Draw the placement reticle for a pending ground-targeted ability
+/// Green when the cursor is within the ability's range, red when it is too far away
+pub fn draw_ground_target_reticle(
+    mut gizmos: Gizmos,
+    input_state: Res<crate::input::InputState>,
+    client_state: Res<crate::game_state::MyClientState>,
+    ability_db: Res<crate::ability_cache::ClientAbilityDatabase>,
+    player_query: Query<&Position, With<Player>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+) {
+    let Some(ability_id) = input_state.pending_ground_ability else { return };
+    let Some(info) = ability_db.get_ability_info(ability_id) else { return };
+    let Some((_, _, radius)) = info.aoe else { return };
+    let Some(cursor) = crate::input::get_cursor_world_position(&windows, &camera_query) else { return };
+    let Some(player_pos) = client_state.player_entity
+        .and_then(|p| player_query.get(p).ok())
+        .map(|pos| pos.0) else { return };
+
+    let range = info.range * PIXELS_PER_METER;
+    let color = if player_pos.distance(cursor) <= range {
+        Color::srgba(0.2, 1.0, 0.4, 0.8)
+    } else {
+        Color::srgba(1.0, 0.2, 0.2, 0.8)
+    };
+
+    gizmos.circle_2d(cursor, radius * PIXELS_PER_METER, color);
+    gizmos.circle_2d(cursor, 3.0, color);
+    // Faint ring showing how far the ability can be placed
+    gizmos.circle_2d(player_pos, range, Color::srgba(1.0, 1.0, 1.0, 0.2));
+}
+
+/// Guide a ghost back to their corpse: a line to it and a ring showing where they can resurrect
+pub fn draw_corpse_marker(
+    mut gizmos: Gizmos,
+    client_state: Res<crate::game_state::MyClientState>,
+    player_query: Query<(&Position, &Ghost), With<Player>>,
+) {
+    let Some((player_pos, ghost)) = client_state.player_entity
+        .and_then(|p| player_query.get(p).ok()) else { return };
+
+    let in_range = player_pos.0.distance(ghost.corpse_position) <= CORPSE_RESURRECT_RANGE;
+    let color = if in_range {
+        Color::srgba(0.2, 1.0, 0.4, 0.8)
+    } else {
+        Color::srgba(0.7, 0.8, 1.0, 0.6)
+    };
+
+    gizmos.circle_2d(ghost.corpse_position, CORPSE_RESURRECT_RANGE, color);
+    if !in_range {
+        gizmos.line_2d(player_pos.0, ghost.corpse_position, color);
+    }
+}
+
+/// Keep player nameplates in step with PvP status: "[PvP]" when flagged, red when the local
+/// player could fight them, orange when flagged but not hostile
+pub fn update_player_nameplates(
+    client_state: Res<MyClientState>,
+    players: Query<(&Character, Option<&Faction>, Has<PvpFlagged>, Option<&Dueling>), With<Player>>,
+    mut labels: Query<(&NameLabel, &mut Text2d, &mut TextColor)>,
+) {
+    let standing = |(_, faction, flagged, duel): (&Character, Option<&Faction>, bool, Option<&Dueling>)| PvpStanding {
+        faction: faction.copied().unwrap_or_default(),
+        flagged,
+        duel_opponent: duel.map(|duel| duel.opponent),
+    };
+    let me = client_state.player_entity
+        .and_then(|player| players.get(player).ok().map(|data| (player, standing(data))));
+
+    for (label, mut text, mut color) in &mut labels {
+        let Ok(data) = players.get(label.game_entity) else { continue };
+        let (character, _, flagged, _) = data;
+        let theirs = standing(data);
+
+        let name = if flagged { format!("{} [PvP]", character.name) } else { character.name.clone() };
+        let hostile = me.as_ref().is_some_and(|(me, mine)| mine.can_attack(*me, label.game_entity, &theirs));
+        let new_color = if hostile {
+            Color::srgb(1.0, 0.3, 0.3)
+        } else if flagged {
+            Color::srgb(1.0, 0.6, 0.2)
+        } else {
+            Color::WHITE
+        };
+
+        // Only write on change so the text isn't re-laid out every frame
+        if text.0 != name {
+            text.0 = name;
+        }
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+    }
+}
+
+/// Outline the duel arena while the local player is dueling
+pub fn draw_duel_arena(
+    mut gizmos: Gizmos,
+    client_state: Res<MyClientState>,
+    player_query: Query<(&Position, &Dueling), With<Player>>,
+) {
+    let Some((player_pos, duel)) = client_state.player_entity
+        .and_then(|p| player_query.get(p).ok()) else { return };
+
+    // Brighten the boundary as the player nears it
+    let closeness = (player_pos.0.distance(duel.arena_center) / DUEL_ARENA_RADIUS).clamp(0.0, 1.0);
+    gizmos.circle_2d(duel.arena_center, DUEL_ARENA_RADIUS, Color::srgba(1.0, 0.8, 0.2, 0.3 + 0.6 * closeness));
+}
+
 /// Spawn damage number at target position when combat event occurs
 pub fn spawn_damage_numbers(
     trigger: On<CombatEvent>,
     mut commands: Commands,
 ) {
     let event = trigger.event();
-    info!("CombatEvent received! Target position: {:?}, Damage: {:.1}, Crit: {}",
-        event.target_position, event.damage, event.is_crit);
+    info!("CombatEvent received! Target position: {:?}, Damage: {:.1}, Result: {:?}",
+        event.target_position, event.damage, event.result);
 
     // Calculate font size based on damage (bigger for more damage)
     let base_size = 20.0;
     let size_multiplier = (event.damage / 50.0).clamp(0.8, 2.5);
     let font_size = base_size * size_multiplier;
 
-    // Choose color based on damage type
-    let color = if event.is_crit {
-        Color::srgba(1.0, 0.8, 0.0, 1.0) // Gold for crits
-    } else {
-        Color::srgba(1.0, 1.0, 1.0, 1.0) // White for normal
-    };
-
-    // Format damage text
-    let damage_text = if event.is_crit {
-        format!("{:.0}!", event.damage) // Add ! for crits
-    } else {
-        format!("{:.0}", event.damage)
+    // Choose color and text based on how the attack landed
+    let (color, damage_text) = match event.result {
+        AttackResult::Miss | AttackResult::Dodge | AttackResult::Parry => (
+            Color::srgba(0.7, 0.7, 0.7, 1.0), // Gray for avoided attacks
+            format!("{:?}", event.result),
+        ),
+        AttackResult::Block => (
+            Color::srgba(0.6, 0.8, 1.0, 1.0), // Light blue for blocks
+            format!("{:.0} (Block)", event.damage),
+        ),
+        _ if event.is_crit => (
+            Color::srgba(1.0, 0.8, 0.0, 1.0), // Gold for crits
+            format!("{:.0}!", event.damage), // Add ! for crits
+        ),
+        _ => (
+            Color::srgba(1.0, 1.0, 1.0, 1.0), // White for normal
+            format!("{:.0}", event.damage),
+        ),
     };
 
     // Spawn damage number slightly above target (using position from event to avoid entity mapping issues)
@@ -0,0 +1,404 @@
+//! Zone tilemap rendering.
+//!
+//! Loads the active zone's tile data through the AssetServer (filesystem on native,
+//! HTTP on WASM), renders ground and decoration layers with bevy_ecs_tilemap, and
+//! only keeps chunks that overlap the camera view spawned.
+
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::{
+    TileBundle, TilePos, TileStorage, TileTextureIndex, TilemapBundle, TilemapGridSize, TilemapId,
+    TilemapPlugin, TilemapSize, TilemapTexture, TilemapTileSize, TilemapType,
+    TileFlip as EcsTileFlip,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use eryndor_shared::*;
+
+use crate::game_state::GameState;
+
+/// Tileset used by legacy ZoneTilemap data, which stores bare 1-based tile indices
+const LEGACY_TILESET_PATH: &str = "tiles/Tiles/Grass/combined_tileset.png";
+/// The only zone the server spawns
+const DEFAULT_ZONE: &str = "starter_zone";
+/// Extra chunks kept around the camera view so panning doesn't pop
+const CULL_MARGIN_CHUNKS: i32 = 1;
+
+const GROUND_Z: f32 = -10.0;
+const DECORATION_Z: f32 = -9.0;
+
+// ============================================================================
+// ZONE ASSET
+// ============================================================================
+
+/// Client-side view of a zone file - only the fields needed for rendering
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct ClientZoneAsset {
+    pub zone_id: String,
+    #[serde(default)]
+    pub zone_name: String,
+    #[serde(default)]
+    pub tilemap: Option<ZoneTilemap>,
+    #[serde(default)]
+    pub tilemap_map: Option<TilemapMap>,
+}
+
+#[derive(Default)]
+pub struct ClientZoneAssetLoader;
+
+impl AssetLoader for ClientZoneAssetLoader {
+    type Asset = ClientZoneAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["zone.json"]
+    }
+}
+
+// ============================================================================
+// RENDER DATA
+// ============================================================================
+
+/// One tile ready to be placed in a chunk tilemap
+#[derive(Clone, Copy, Debug)]
+struct RenderTile {
+    local: (u32, u32),
+    index: u32,
+    flip: TileFlip,
+}
+
+/// All tiles of one layer within one chunk that share a texture
+#[derive(Clone, Debug)]
+struct RenderLayer {
+    texture: String,
+    z: f32,
+    tiles: Vec<RenderTile>,
+}
+
+/// Active zone's tile data, pre-bucketed by chunk, plus the chunk tilemaps currently spawned
+#[derive(Resource, Default)]
+pub struct ZoneTilemapRender {
+    pub zone_id: String,
+    handle: Handle<ClientZoneAsset>,
+    tile_size: f32,
+    chunk_tiles: u32,
+    chunks: HashMap<(i32, i32), Vec<RenderLayer>>,
+    textures: HashMap<String, Handle<Image>>,
+    spawned: HashMap<(i32, i32), Vec<Entity>>,
+    built: bool,
+}
+
+impl ZoneTilemapRender {
+    fn chunk_world_size(&self) -> f32 {
+        self.tile_size * self.chunk_tiles as f32
+    }
+
+    /// Despawn every chunk and forget the loaded zone data
+    fn clear(&mut self, commands: &mut Commands) {
+        for entities in self.spawned.values() {
+            for &entity in entities {
+                if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                    entity_commands.despawn();
+                }
+            }
+        }
+        self.spawned.clear();
+        self.chunks.clear();
+        self.built = false;
+    }
+}
+
+/// Bucket a legacy ZoneTilemap's ground and decoration layers by chunk
+fn build_legacy_chunks(tilemap: &ZoneTilemap) -> HashMap<(i32, i32), Vec<RenderLayer>> {
+    let mut chunks = HashMap::new();
+
+    for (key, chunk) in &tilemap.chunks {
+        let Some(coords) = ZoneTilemap::parse_chunk_key(key) else {
+            continue;
+        };
+
+        let mut layers = Vec::new();
+        for (grid, z) in [(&chunk.ground, GROUND_Z), (&chunk.decorations, DECORATION_Z)] {
+            let tiles: Vec<RenderTile> = grid.iter().enumerate()
+                .flat_map(|(row, cells)| cells.iter().enumerate().map(move |(col, &id)| (col, row, id)))
+                .filter(|&(_, _, id)| id != 0)
+                .map(|(col, row, id)| RenderTile {
+                    local: (col as u32, row as u32),
+                    index: id - 1,
+                    flip: TileFlip::default(),
+                })
+                .collect();
+
+            if !tiles.is_empty() {
+                layers.push(RenderLayer { texture: LEGACY_TILESET_PATH.to_string(), z, tiles });
+            }
+        }
+
+        if !layers.is_empty() {
+            chunks.insert(coords, layers);
+        }
+    }
+
+    chunks
+}
+
+/// Bucket a Tiled-compatible map's visible tile layers by chunk and tileset.
+/// The collision layer is never rendered; tilesets without an embedded image are skipped.
+fn build_map_chunks(map: &TilemapMap, chunk_tiles: i32) -> HashMap<(i32, i32), Vec<RenderLayer>> {
+    let collision_layer_id = map.collision_layer().map(|layer| layer.id);
+    let mut chunks: HashMap<(i32, i32), Vec<RenderLayer>> = HashMap::new();
+
+    let tile_layers = map.layers.iter()
+        .filter(|layer| layer.layer_type == "tilelayer" && layer.visible && Some(layer.id) != collision_layer_id);
+
+    for (layer_idx, layer) in tile_layers.enumerate() {
+        let z = GROUND_Z + layer_idx as f32 * 0.1;
+
+        // Flatten finite and infinite layers into absolute tile coordinates
+        let mut cells: Vec<(i32, i32, u32)> = Vec::new();
+        if let Some(layer_chunks) = &layer.chunks {
+            for chunk in layer_chunks {
+                let width = chunk.width.max(1) as i32;
+                for (idx, &gid) in chunk.data.iter().enumerate() {
+                    cells.push((chunk.x + idx as i32 % width, chunk.y + idx as i32 / width, gid));
+                }
+            }
+        } else if let Some(data) = &layer.data {
+            let width = map.width.max(1) as i32;
+            for (idx, &gid) in data.iter().enumerate() {
+                cells.push((idx as i32 % width, idx as i32 / width, gid));
+            }
+        }
+
+        // layer tiles keyed by (chunk, texture) so each tilemap uses a single texture
+        let mut grouped: HashMap<((i32, i32), String), Vec<RenderTile>> = HashMap::new();
+        for (x, y, raw_gid) in cells {
+            let (gid, flip) = TileFlip::split_gid(raw_gid);
+            if gid == 0 {
+                continue;
+            }
+            let Some((tileset_idx, local_id)) = map.gid_to_tileset(gid) else {
+                continue;
+            };
+            let Some(image) = map.tilesets.get(tileset_idx)
+                .and_then(|tileset| tileset.embedded.as_ref())
+                .and_then(|embedded| embedded.image.clone())
+            else {
+                continue;
+            };
+
+            let chunk = (x.div_euclid(chunk_tiles), y.div_euclid(chunk_tiles));
+            grouped.entry((chunk, image)).or_default().push(RenderTile {
+                local: (x.rem_euclid(chunk_tiles) as u32, y.rem_euclid(chunk_tiles) as u32),
+                index: local_id,
+                flip,
+            });
+        }
+
+        for ((chunk, texture), tiles) in grouped {
+            chunks.entry(chunk).or_default().push(RenderLayer { texture, z, tiles });
+        }
+    }
+
+    chunks
+}
+
+// ============================================================================
+// SYSTEMS
+// ============================================================================
+
+/// Start loading a zone's tile data, dropping whatever was rendered before
+fn load_zone(commands: &mut Commands, render: &mut ZoneTilemapRender, asset_server: &AssetServer, zone_id: &str) {
+    render.clear(commands);
+    render.zone_id = zone_id.to_string();
+    render.handle = asset_server.load(format!("content/zones/{}.zone.json", zone_id));
+    info!("Loading tilemap for zone {}", zone_id);
+}
+
+/// Load the default zone when entering the game
+pub fn load_initial_zone(
+    mut commands: Commands,
+    mut render: ResMut<ZoneTilemapRender>,
+    asset_server: Res<AssetServer>,
+) {
+    if render.zone_id.is_empty() {
+        load_zone(&mut commands, &mut render, &asset_server, DEFAULT_ZONE);
+    }
+}
+
+/// Build chunk render data once the zone asset has loaded (and again on hot reload)
+#[allow(deprecated)]
+pub fn build_zone_tilemap(
+    mut commands: Commands,
+    mut render: ResMut<ZoneTilemapRender>,
+    mut events: bevy::ecs::event::EventReader<AssetEvent<ClientZoneAsset>>,
+    zones: Res<Assets<ClientZoneAsset>>,
+    asset_server: Res<AssetServer>,
+) {
+    let render_id = render.handle.id();
+    let reloaded = events.read().any(|event| event.is_modified(render_id));
+    if render.built && !reloaded {
+        return;
+    }
+    let Some(zone) = zones.get(render_id) else { return };
+
+    let zone_id = render.zone_id.clone();
+    render.clear(&mut commands);
+    render.zone_id = zone_id;
+
+    let (chunks, tile_size, chunk_tiles) = if let Some(map) = &zone.tilemap_map {
+        let chunk_tiles = 16;
+        (build_map_chunks(map, chunk_tiles as i32), map.tile_width as f32, chunk_tiles)
+    } else if let Some(tilemap) = &zone.tilemap {
+        (build_legacy_chunks(tilemap), tilemap.tile_size as f32, tilemap.chunk_size)
+    } else {
+        (HashMap::new(), 16.0, 16)
+    };
+
+    for layer in chunks.values().flatten() {
+        if !render.textures.contains_key(&layer.texture) {
+            let handle = asset_server.load(layer.texture.clone());
+            render.textures.insert(layer.texture.clone(), handle);
+        }
+    }
+
+    info!("Built tilemap for zone {} ({} chunks)", zone.zone_id, chunks.len());
+    render.tile_size = tile_size.max(1.0);
+    render.chunk_tiles = chunk_tiles.max(1);
+    render.chunks = chunks;
+    render.built = true;
+}
+
+/// Spawn chunks that overlap the camera view and despawn the ones that scrolled out
+pub fn cull_tilemap_chunks(
+    mut commands: Commands,
+    mut render: ResMut<ZoneTilemapRender>,
+    camera: Query<(&Transform, &Projection), With<Camera2d>>,
+) {
+    if !render.built || render.chunks.is_empty() {
+        return;
+    }
+    let Ok((camera_transform, projection)) = camera.single() else { return };
+    let Projection::Orthographic(ortho) = projection else { return };
+
+    let chunk_world = render.chunk_world_size();
+    let center = camera_transform.translation.truncate();
+    let min = center + ortho.area.min;
+    let max = center + ortho.area.max;
+    let min_chunk = ((min.x / chunk_world).floor() as i32 - CULL_MARGIN_CHUNKS, (min.y / chunk_world).floor() as i32 - CULL_MARGIN_CHUNKS);
+    let max_chunk = ((max.x / chunk_world).floor() as i32 + CULL_MARGIN_CHUNKS, (max.y / chunk_world).floor() as i32 + CULL_MARGIN_CHUNKS);
+    let in_view = |chunk: &(i32, i32)| {
+        chunk.0 >= min_chunk.0 && chunk.0 <= max_chunk.0 && chunk.1 >= min_chunk.1 && chunk.1 <= max_chunk.1
+    };
+
+    let out_of_view: Vec<(i32, i32)> = render.spawned.keys().copied().filter(|c| !in_view(c)).collect();
+    for chunk in out_of_view {
+        if let Some(entities) = render.spawned.remove(&chunk) {
+            for entity in entities {
+                if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                    entity_commands.despawn();
+                }
+            }
+        }
+    }
+
+    let to_spawn: HashSet<(i32, i32)> = render.chunks.keys()
+        .copied()
+        .filter(|c| in_view(c) && !render.spawned.contains_key(c))
+        .collect();
+    for chunk in to_spawn {
+        let entities: Vec<Entity> = render.chunks[&chunk].iter()
+            .filter_map(|layer| spawn_chunk_layer(&mut commands, &render, chunk, layer))
+            .collect();
+        render.spawned.insert(chunk, entities);
+    }
+}
+
+/// Spawn one bevy_ecs_tilemap tilemap for a layer within a chunk
+fn spawn_chunk_layer(commands: &mut Commands, render: &ZoneTilemapRender, chunk: (i32, i32), layer: &RenderLayer) -> Option<Entity> {
+    let texture = render.textures.get(&layer.texture)?.clone();
+    let size = TilemapSize { x: render.chunk_tiles, y: render.chunk_tiles };
+    let tile_size = TilemapTileSize { x: render.tile_size, y: render.tile_size };
+    let grid_size: TilemapGridSize = tile_size.into();
+
+    let tilemap_entity = commands.spawn_empty().id();
+    let mut storage = TileStorage::empty(size);
+
+    for tile in &layer.tiles {
+        let position = TilePos { x: tile.local.0, y: tile.local.1 };
+        if !position.within_map_bounds(&size) {
+            continue;
+        }
+        let tile_entity = commands.spawn((
+            TileBundle {
+                position,
+                tilemap_id: TilemapId(tilemap_entity),
+                texture_index: TileTextureIndex(tile.index),
+                flip: EcsTileFlip {
+                    x: tile.flip.horizontal,
+                    y: tile.flip.vertical,
+                    d: tile.flip.diagonal,
+                },
+                ..default()
+            },
+            ChildOf(tilemap_entity),
+        )).id();
+        storage.set(&position, tile_entity);
+    }
+
+    // Tile (0, 0) of the chunk sits centered on the chunk's first world tile
+    let origin = Vec2::new(chunk.0 as f32, chunk.1 as f32) * render.chunk_world_size() + Vec2::splat(render.tile_size / 2.0);
+    commands.entity(tilemap_entity).insert(TilemapBundle {
+        grid_size,
+        map_type: TilemapType::Square,
+        size,
+        storage,
+        texture: TilemapTexture::Single(texture),
+        tile_size,
+        transform: Transform::from_xyz(origin.x, origin.y, layer.z),
+        ..default()
+    });
+
+    Some(tilemap_entity)
+}
+
+/// Drop the rendered zone when leaving the game
+pub fn cleanup_zone_tilemap(mut commands: Commands, mut render: ResMut<ZoneTilemapRender>) {
+    render.clear(&mut commands);
+    render.zone_id.clear();
+}
+
+// ============================================================================
+// PLUGIN
+// ============================================================================
+
+pub struct ZoneTilemapPlugin;
+
+impl Plugin for ZoneTilemapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(TilemapPlugin)
+            .init_asset::<ClientZoneAsset>()
+            .init_asset_loader::<ClientZoneAssetLoader>()
+            .init_resource::<ZoneTilemapRender>()
+            .add_systems(OnEnter(GameState::InGame), load_initial_zone)
+            .add_systems(OnExit(GameState::InGame), cleanup_zone_tilemap)
+            .add_systems(Update, (
+                build_zone_tilemap,
+                cull_tilemap_chunks,
+            ).chain().run_if(in_state(GameState::InGame)));
+    }
+}
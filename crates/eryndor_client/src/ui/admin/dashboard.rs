@@ -147,15 +147,19 @@ fn render_bans_tab(
             ui.text_edit_singleline(&mut dashboard.ban_form_reason);
         });
 
+        ui.checkbox(&mut dashboard.ban_form_ip_and_device, "Also ban their last IP address and device");
+
         if ui.button("Create Ban").clicked() {
             let duration_str = if dashboard.ban_form_duration == 0 {
-                "permanent".to_string()
+                "perm".to_string()
             } else {
                 format!("{}h", dashboard.ban_form_duration)
             };
+            let ban_command = if dashboard.ban_form_ip_and_device { "/banip" } else { "/ban" };
 
             commands.client_trigger(AdminCommandRequest {
-                command: format!("/ban {} {} {}",
+                command: format!("{} {} {} {}",
+                    ban_command,
                     dashboard.ban_form_username,
                     duration_str,
                     dashboard.ban_form_reason
@@ -165,6 +169,7 @@ fn render_bans_tab(
             dashboard.ban_form_username.clear();
             dashboard.ban_form_duration = 0;
             dashboard.ban_form_reason.clear();
+            dashboard.ban_form_ip_and_device = false;
         }
     });
 
@@ -188,6 +193,7 @@ fn render_bans_tab(
                         ui.label("Target");
                         ui.label("Reason");
                         ui.label("Expires");
+                        ui.label("Appeal");
                         ui.label("Actions");
                         ui.end_row();
 
@@ -195,8 +201,19 @@ fn render_bans_tab(
                         for ban in &dashboard.ban_list {
                             ui.label(format!("{}", ban.id));
                             ui.label(&ban.ban_type);
-                            ui.label(&ban.target);
-                            ui.label(&ban.reason);
+                            let also_matches: Vec<String> = ban.ip_address.iter().cloned()
+                                .chain(ban.hardware_id.iter().map(|id| format!("device {}", id)))
+                                .collect();
+                            if also_matches.is_empty() {
+                                ui.label(&ban.target);
+                            } else {
+                                ui.label(&ban.target).on_hover_text(format!("Also matches {}", also_matches.join(", ")));
+                            }
+
+                            match &ban.notes {
+                                Some(notes) => ui.label(&ban.reason).on_hover_text(notes),
+                                None => ui.label(&ban.reason),
+                            };
 
                             if let Some(expires) = ban.expires_at {
                                 ui.label(format!("Expires: {}", expires));
@@ -204,9 +221,22 @@ fn render_bans_tab(
                                 ui.label("Permanent");
                             }
 
+                            match &ban.appeal {
+                                Some(appeal) => {
+                                    let mut details = appeal.text.clone();
+                                    if let Some(notes) = &appeal.review_notes {
+                                        details.push_str(&format!("\n\nReview notes: {}", notes));
+                                    }
+                                    ui.label(&appeal.status).on_hover_text(details);
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+
                             if ui.button("Unban").clicked() {
                                 commands.client_trigger(AdminCommandRequest {
-                                    command: format!("/unban {}", ban.target),
+                                    command: format!("/unban #{}", ban.id),
                                 });
                             }
 
@@ -262,7 +292,78 @@ fn render_stats_tab(
                 let local_time = format_local_time();
                 ui.label(local_time);
                 ui.end_row();
+
+                ui.label("Tick Rate:");
+                ui.label(format!("{} Hz", stats.tick_rate));
+                ui.end_row();
+
+                ui.label("Replicated Entities:");
+                ui.label(format!("{}", stats.replicated_entities));
+                ui.end_row();
+
+                ui.label("Entity Changes / Tick:");
+                ui.label(format!("{:.1}", stats.entity_changes_per_tick));
+                ui.end_row();
             });
+
+        if !stats.shards.is_empty() {
+            ui.separator();
+            ui.heading("Shards");
+
+            egui::Grid::new("shards_grid")
+                .num_columns(3)
+                .spacing([15.0, 6.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Shard");
+                    ui.strong("Population");
+                    ui.strong("Status");
+                    ui.end_row();
+
+                    for shard in &stats.shards {
+                        ui.label(format!("{} ({})", shard.name, shard.id));
+                        ui.label(format!("{} / {}", shard.population, shard.max_players));
+                        ui.label(if !shard.online { "Offline" } else if shard.is_full() { "Full" } else { "Online" });
+                        ui.end_row();
+                    }
+                });
+        }
+
+        ui.separator();
+        ui.heading("Client Bandwidth");
+
+        if stats.clients.is_empty() {
+            ui.label("No connected clients.");
+        } else {
+            let total_sent: f64 = stats.clients.iter().map(|c| c.bytes_sent_per_sec).sum();
+            ui.label(format!("Total outgoing: {:.1} KB/s", total_sent / 1024.0));
+
+            egui::Grid::new("client_bandwidth_grid")
+                .num_columns(6)
+                .spacing([15.0, 6.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Client");
+                    ui.strong("Out (KB/s)");
+                    ui.strong("In (KB/s)");
+                    ui.strong("RTT (ms)");
+                    ui.strong("Loss");
+                    ui.strong("Changes/Tick");
+                    ui.end_row();
+
+                    for client in &stats.clients {
+                        let name = client.character_name.clone()
+                            .unwrap_or_else(|| format!("#{}", client.client_id));
+                        ui.label(name);
+                        ui.label(format!("{:.1}", client.bytes_sent_per_sec / 1024.0));
+                        ui.label(format!("{:.1}", client.bytes_received_per_sec / 1024.0));
+                        ui.label(format!("{:.0}", client.rtt_ms));
+                        ui.label(format!("{:.1}%", client.packet_loss * 100.0));
+                        ui.label(format!("{:.1}", client.entity_changes_per_tick));
+                        ui.end_row();
+                    }
+                });
+        }
     } else {
         ui.label("No stats data loaded yet.");
         ui.label("Click 'Refresh Stats' to fetch current data.");
@@ -6,7 +6,69 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 
 use crate::game_state::MyClientState;
-use super::state::UiState;
+use super::state::{ChatLine, UiState};
+
+/// How many chat lines are kept
+const MAX_CHAT_HISTORY: usize = 50;
+
+/// Text color for each channel
+fn channel_color(channel: Option<ChatChannel>) -> egui::Color32 {
+    match channel {
+        Some(ChatChannel::Say) => egui::Color32::from_rgb(230, 230, 230),
+        Some(ChatChannel::Party) => egui::Color32::from_rgb(120, 170, 255),
+        Some(ChatChannel::Guild) => egui::Color32::from_rgb(100, 220, 100),
+        Some(ChatChannel::Global) => egui::Color32::from_rgb(255, 190, 110),
+        Some(ChatChannel::Whisper) => egui::Color32::from_rgb(240, 130, 230),
+        None => egui::Color32::GRAY,
+    }
+}
+
+fn push_chat_line(ui_state: &mut UiState, channel: Option<ChatChannel>, text: String) {
+    ui_state.chat_history.push(ChatLine { channel, text });
+    if ui_state.chat_history.len() > MAX_CHAT_HISTORY {
+        ui_state.chat_history.remove(0);
+    }
+}
+
+/// Split a channel prefix such as "/p " off a message
+fn parse_channel_prefix(message: &str) -> Option<(ChatChannel, &str)> {
+    const PREFIXES: [(&str, ChatChannel); 8] = [
+        ("/s ", ChatChannel::Say),
+        ("/say ", ChatChannel::Say),
+        ("/p ", ChatChannel::Party),
+        ("/party ", ChatChannel::Party),
+        ("/g ", ChatChannel::Guild),
+        ("/guild ", ChatChannel::Guild),
+        ("/1 ", ChatChannel::Global),
+        ("/global ", ChatChannel::Global),
+    ];
+
+    PREFIXES.iter().find_map(|(prefix, channel)| {
+        message.strip_prefix(prefix).map(|rest| (*channel, rest.trim()))
+    })
+}
+
+/// Send a party or guild command such as "/invite Name". Returns false if it isn't one.
+fn send_group_command(commands: &mut Commands, message: &str) -> bool {
+    let (command, argument) = message.split_once(' ')
+        .map_or((message, ""), |(command, argument)| (command, argument.trim()));
+
+    match command {
+        "/invite" if !argument.is_empty() => commands.client_trigger(GroupInviteRequest {
+            kind: GroupKind::Party,
+            target: argument.to_string(),
+        }),
+        "/pleave" => commands.client_trigger(LeaveGroupRequest { kind: GroupKind::Party }),
+        "/ginvite" if !argument.is_empty() => commands.client_trigger(GroupInviteRequest {
+            kind: GroupKind::Guild,
+            target: argument.to_string(),
+        }),
+        "/gcreate" if !argument.is_empty() => commands.client_trigger(CreateGuildRequest { name: argument.to_string() }),
+        "/gleave" => commands.client_trigger(LeaveGroupRequest { kind: GroupKind::Guild }),
+        _ => return false,
+    }
+    true
+}
 
 /// Chat window for sending admin commands and regular chat messages
 pub fn chat_window(
@@ -33,6 +95,22 @@ pub fn chat_window(
         .default_size([500.0, 250.0])
         .resizable(true)
         .show(ctx, |ui| {
+            // Channel tabs; picking a channel tab also sends on that channel
+            ui.horizontal(|ui| {
+                if ui.selectable_label(ui_state.chat_tab.is_none(), "All").clicked() {
+                    ui_state.chat_tab = None;
+                }
+                for channel in ChatChannel::ALL {
+                    let label = egui::RichText::new(channel.label()).color(channel_color(Some(channel)));
+                    if ui.selectable_label(ui_state.chat_tab == Some(channel), label).clicked() {
+                        ui_state.chat_tab = Some(channel);
+                        if channel != ChatChannel::Whisper {
+                            ui_state.chat_channel = channel;
+                        }
+                    }
+                }
+            });
+
             // Chat history display (scrollable area)
             egui::ScrollArea::vertical()
                 .max_height(150.0)
@@ -42,19 +120,27 @@ pub fn chat_window(
                     if ui_state.chat_history.is_empty() {
                         ui.label("No messages yet. Type to chat with other players!");
                         if ui_state.is_admin {
-                            ui.label("Admin commands: /ban, /unban, /kick, /broadcast, /help");
+                            ui.label("Admin commands: /ban, /unban, /kick, /mute, /unmute, /broadcast, /help");
                         }
                     } else {
-                        for message in &ui_state.chat_history {
-                            ui.label(message);
+                        for line in &ui_state.chat_history {
+                            // System lines show on every tab
+                            if ui_state.chat_tab.is_some() && line.channel.is_some() && line.channel != ui_state.chat_tab {
+                                continue;
+                            }
+                            ui.label(egui::RichText::new(&line.text).color(channel_color(line.channel)));
                         }
                     }
                 });
 
             ui.separator();
 
-            // Chat input field
-            let response = ui.text_edit_singleline(&mut ui_state.chat_input);
+            // Chat input field, labelled with the channel plain messages go to
+            let response = ui.horizontal(|ui| {
+                let channel = ui_state.chat_channel;
+                ui.label(egui::RichText::new(format!("{}:", channel.label())).color(channel_color(Some(channel))));
+                ui.text_edit_singleline(&mut ui_state.chat_input)
+            }).inner;
 
             // Track focus state changes
             let current_focus = response.has_focus();
@@ -72,22 +158,37 @@ pub fn chat_window(
                 let message = ui_state.chat_input.trim().to_string();
 
                 if !message.is_empty() {
-                    // Check if it's an admin command (starts with /)
-                    if message.starts_with('/') {
+                    // Whispers: "/w Name message"
+                    let whisper = message.strip_prefix("/w ")
+                        .or_else(|| message.strip_prefix("/whisper "))
+                        .and_then(|rest| rest.trim().split_once(' '));
+
+                    // The server echoes chat back to us, so only admin commands are shown locally
+                    if let Some((target, text)) = whisper {
+                        commands.client_trigger(WhisperRequest {
+                            target: target.to_string(),
+                            message: text.trim().to_string(),
+                        });
+                    } else if let Some((channel, text)) = parse_channel_prefix(&message) {
+                        if !text.is_empty() {
+                            commands.client_trigger(SendChatMessage {
+                                message: text.to_string(),
+                                channel,
+                            });
+                        }
+                    } else if send_group_command(&mut commands, &message) {
+                        // Party and guild commands answer with notifications
+                    } else if message.starts_with('/') {
+                        // Admin command
                         commands.client_trigger(AdminCommandRequest {
                             command: message.clone(),
                         });
-                        ui_state.chat_history.push(format!("[{}] {}", character_name, message));
+                        push_chat_line(&mut ui_state, None, format!("[{}] {}", character_name, message));
                     } else {
                         commands.client_trigger(SendChatMessage {
                             message: message.clone(),
+                            channel: ui_state.chat_channel,
                         });
-                        ui_state.chat_history.push(format!("[{}] {}", character_name, message));
-                    }
-
-                    // Keep only last 50 messages
-                    if ui_state.chat_history.len() > 50 {
-                        ui_state.chat_history.remove(0);
                     }
 
                     ui_state.chat_input.clear();
@@ -96,29 +197,38 @@ pub fn chat_window(
             }
 
             let help_text = if ui_state.is_admin {
-                "Press Enter to send | Type / for admin commands"
+                "Enter to send | /s /p /g /1 to pick a channel | /w Name to whisper | Type / for admin commands"
             } else {
-                "Press Enter to send"
+                "Enter to send | /s /p /g /1 to pick a channel | /w Name to whisper"
             };
             ui.label(help_text);
+            ui.label("Groups: /invite Name, /pleave | /gcreate Guild, /ginvite Name, /gleave");
         });
 }
 
 /// Receive chat messages from server and add them to chat history
 pub fn receive_chat_messages(
     mut ui_state: ResMut<UiState>,
+    client_state: Res<MyClientState>,
+    character_query: Query<&Character>,
     mut chat_events: Option<MessageReader<ChatMessage>>,
 ) {
     let Some(chat_events) = chat_events.as_mut() else {
         return;
     };
 
-    for chat_event in chat_events.read() {
-        let formatted_message = format!("[{}] {}", chat_event.sender, chat_event.message);
-        ui_state.chat_history.push(formatted_message);
+    let my_name = client_state.player_entity
+        .and_then(|entity| character_query.get(entity).ok())
+        .map(|character| character.name.clone());
 
-        if ui_state.chat_history.len() > 50 {
-            ui_state.chat_history.remove(0);
-        }
+    for chat_event in chat_events.read() {
+        let text = match (chat_event.channel, &chat_event.target) {
+            (ChatChannel::Whisper, Some(target)) if my_name.as_deref() == Some(chat_event.sender.as_str()) => {
+                format!("[To {}] {}", target, chat_event.message)
+            }
+            (ChatChannel::Whisper, _) => format!("[From {}] {}", chat_event.sender, chat_event.message),
+            (channel, _) => format!("[{}] [{}] {}", channel.label(), chat_event.sender, chat_event.message),
+        };
+        push_chat_line(&mut ui_state, Some(chat_event.channel), text);
     }
 }
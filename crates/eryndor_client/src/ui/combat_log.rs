@@ -0,0 +1,249 @@
+//! Combat log window.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use eryndor_shared::*;
+use std::collections::VecDeque;
+
+use crate::ability_cache::ClientAbilityDatabase;
+use crate::game_state::MyClientState;
+use super::state::UiState;
+
+/// Entries kept in the log
+const MAX_ENTRIES: usize = 500;
+/// Seconds without combat that end an encounter for the DPS summary
+const ENCOUNTER_GAP: f64 = 5.0;
+
+/// Filter categories for combat log entries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CombatLogCategory {
+    Outgoing,
+    Incoming,
+    Healing,
+    Avoidance,
+    Effects,
+    Deaths,
+}
+
+impl CombatLogCategory {
+    pub const ALL: [Self; 6] = [
+        Self::Outgoing,
+        Self::Incoming,
+        Self::Healing,
+        Self::Avoidance,
+        Self::Effects,
+        Self::Deaths,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Outgoing => "Damage done",
+            Self::Incoming => "Damage taken",
+            Self::Healing => "Healing",
+            Self::Avoidance => "Misses",
+            Self::Effects => "Effects",
+            Self::Deaths => "Deaths",
+        }
+    }
+
+    /// Categorize an entry from the point of view of the player called `me`
+    fn of(event: &CombatLogEvent, me: &str) -> Self {
+        match event.entry {
+            CombatLogEntry::Damage { .. } | CombatLogEntry::Periodic { .. } if event.source == me => Self::Outgoing,
+            CombatLogEntry::Damage { .. } | CombatLogEntry::Periodic { .. } => Self::Incoming,
+            CombatLogEntry::Heal { .. } => Self::Healing,
+            CombatLogEntry::Avoided { .. } => Self::Avoidance,
+            CombatLogEntry::Debuff { .. } => Self::Effects,
+            CombatLogEntry::Death => Self::Deaths,
+        }
+    }
+}
+
+/// A received combat log entry and when it arrived
+pub struct CombatLogLine {
+    pub time: f64,
+    pub event: CombatLogEvent,
+}
+
+/// Combat log history and filter settings
+#[derive(Resource, Default)]
+pub struct CombatLogState {
+    pub entries: VecDeque<CombatLogLine>,
+    pub hidden: Vec<CombatLogCategory>,
+}
+
+impl CombatLogState {
+    /// Damage done and taken per second over the most recent encounter
+    fn encounter_dps(&self, me: &str, now: f64) -> Option<(f32, f32)> {
+        let last = self.entries.back()?;
+        if now - last.time > ENCOUNTER_GAP {
+            return None;
+        }
+
+        let mut start = last.time;
+        let (mut done, mut taken) = (0.0, 0.0);
+        for line in self.entries.iter().rev() {
+            if start - line.time > ENCOUNTER_GAP {
+                break;
+            }
+            start = line.time;
+            let amount = match line.event.entry {
+                CombatLogEntry::Damage { amount, .. } | CombatLogEntry::Periodic { amount } => amount,
+                _ => continue,
+            };
+            if line.event.source == me {
+                done += amount;
+            } else if line.event.target == me {
+                taken += amount;
+            }
+        }
+
+        let duration = (now - start).max(1.0) as f32;
+        Some((done / duration, taken / duration))
+    }
+}
+
+/// Record combat log entries from the server
+pub fn receive_combat_log(
+    trigger: On<CombatLogEvent>,
+    mut log: ResMut<CombatLogState>,
+    time: Res<Time>,
+) {
+    log.entries.push_back(CombatLogLine {
+        time: time.elapsed_secs_f64(),
+        event: trigger.event().clone(),
+    });
+    while log.entries.len() > MAX_ENTRIES {
+        log.entries.pop_front();
+    }
+}
+
+/// Scrollable, filterable combat log with a DPS summary
+pub fn combat_log_window(
+    mut contexts: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut log: ResMut<CombatLogState>,
+    client_state: Res<MyClientState>,
+    character_query: Query<&Character>,
+    ability_db: Res<ClientAbilityDatabase>,
+    time: Res<Time>,
+) {
+    if !ui_state.show_combat_log {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+
+    let me = client_state.player_entity
+        .and_then(|player| character_query.get(player).ok())
+        .map(|character| character.name.clone())
+        .unwrap_or_default();
+
+    let mut open = true;
+    egui::Window::new("Combat Log")
+        .open(&mut open)
+        .default_pos([820.0, 400.0])
+        .default_size([440.0, 260.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+            // DPS summary for the current (or last) encounter
+            match log.encounter_dps(&me, time.elapsed_secs_f64()) {
+                Some((done, taken)) => {
+                    ui.label(format!("DPS: {:.1} done | {:.1} taken", done, taken));
+                }
+                None => {
+                    ui.label("DPS: out of combat");
+                }
+            }
+
+            // Per-category toggles
+            ui.horizontal_wrapped(|ui| {
+                for category in CombatLogCategory::ALL {
+                    let mut shown = !log.hidden.contains(&category);
+                    if ui.checkbox(&mut shown, category.label()).changed() {
+                        if shown {
+                            log.hidden.retain(|hidden| *hidden != category);
+                        } else {
+                            log.hidden.push(category);
+                        }
+                    }
+                }
+                if ui.button("Clear").clicked() {
+                    log.entries.clear();
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &log.entries {
+                        let category = CombatLogCategory::of(&line.event, &me);
+                        if log.hidden.contains(&category) {
+                            continue;
+                        }
+                        let color = match category {
+                            CombatLogCategory::Outgoing => egui::Color32::from_rgb(255, 200, 120),
+                            CombatLogCategory::Incoming => egui::Color32::from_rgb(255, 110, 110),
+                            CombatLogCategory::Healing => egui::Color32::from_rgb(120, 230, 120),
+                            CombatLogCategory::Avoidance => egui::Color32::GRAY,
+                            CombatLogCategory::Effects => egui::Color32::from_rgb(180, 150, 255),
+                            CombatLogCategory::Deaths => egui::Color32::WHITE,
+                        };
+                        ui.colored_label(color, format_entry(&line.event, &ability_db));
+                    }
+                });
+        });
+
+    if !open {
+        ui_state.show_combat_log = false;
+    }
+}
+
+/// One line of combat log text
+fn format_entry(event: &CombatLogEvent, ability_db: &ClientAbilityDatabase) -> String {
+    let ability = if event.ability_id == 0 {
+        "auto-attack".to_string()
+    } else {
+        ability_db.get_ability_name(event.ability_id)
+    };
+    let (source, target) = (&event.source, &event.target);
+
+    match &event.entry {
+        CombatLogEntry::Damage { amount, result, mitigated, absorbed } => {
+            let mut details = Vec::new();
+            match result {
+                AttackResult::Crit => details.push("critical".to_string()),
+                AttackResult::Block => details.push("blocked".to_string()),
+                _ => {}
+            }
+            if *mitigated > 0.0 {
+                details.push(format!("{:.0} mitigated", mitigated));
+            }
+            if *absorbed > 0.0 {
+                details.push(format!("{:.0} absorbed", absorbed));
+            }
+            let details = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+            format!("{}'s {} hits {} for {:.0}{}", source, ability, target, amount, details)
+        }
+        CombatLogEntry::Avoided { result } => {
+            let verb = match result {
+                AttackResult::Dodge => "dodged by",
+                AttackResult::Parry => "parried by",
+                _ => "misses",
+            };
+            format!("{}'s {} {} {}", source, ability, verb, target)
+        }
+        CombatLogEntry::Periodic { amount } => format!("{}'s {} ticks on {} for {:.0}", source, ability, target, amount),
+        CombatLogEntry::Heal { amount } => format!("{}'s {} heals {} for {:.0}", source, ability, target, amount),
+        CombatLogEntry::Debuff { effect, duration: Some(duration) } => {
+            format!("{} is afflicted by {:?} from {}'s {} ({:.1}s)", target, effect, source, ability, duration)
+        }
+        CombatLogEntry::Debuff { effect, duration: None } => {
+            format!("{} resists {:?} from {}'s {} (diminishing returns)", target, effect, source, ability)
+        }
+        CombatLogEntry::Death if source == target => format!("{} dies", target),
+        CombatLogEntry::Death => format!("{} dies ({} was fighting it)", target, source),
+    }
+}
@@ -0,0 +1,100 @@
+//! Friends window.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+
+use super::state::UiState;
+
+/// Friend list from the server and the add-friend input
+#[derive(Resource, Default)]
+pub struct FriendsState {
+    pub friends: Vec<FriendInfo>,
+    pub add_name: String,
+}
+
+/// Replace the friend list whenever the server sends it
+pub fn receive_friend_list(
+    trigger: On<FriendListEvent>,
+    mut friends: ResMut<FriendsState>,
+) {
+    friends.friends = trigger.event().friends.clone();
+}
+
+/// Friend list with online status; right-click a friend to whisper or remove them
+pub fn friends_window(
+    mut contexts: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut friends: ResMut<FriendsState>,
+    mut commands: Commands,
+) {
+    if !ui_state.show_friends {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+
+    let mut open = true;
+    egui::Window::new("Friends")
+        .open(&mut open)
+        .default_pos([820.0, 120.0])
+        .default_size([220.0, 260.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+            let online_count = friends.friends.iter().filter(|friend| friend.online).count();
+            ui.label(format!("{} / {} online", online_count, friends.friends.len()));
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(180.0)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if friends.friends.is_empty() {
+                        ui.label("No friends yet");
+                    }
+
+                    // Online friends first
+                    let mut sorted: Vec<&FriendInfo> = friends.friends.iter().collect();
+                    sorted.sort_by_key(|friend| !friend.online);
+
+                    for friend in sorted {
+                        let (color, status) = if friend.online {
+                            (egui::Color32::from_rgb(120, 230, 120), "Online")
+                        } else {
+                            (egui::Color32::GRAY, "Offline")
+                        };
+                        let response = ui.add(
+                            egui::Label::new(egui::RichText::new(format!("{} - {}", friend.name, status)).color(color))
+                                .sense(egui::Sense::click()),
+                        );
+
+                        response.context_menu(|ui| {
+                            if friend.online && ui.button("Whisper").clicked() {
+                                ui_state.chat_input = format!("/w {} ", friend.name);
+                                ui.close();
+                            }
+                            if ui.button("Remove").clicked() {
+                                commands.client_trigger(RemoveFriendRequest { character_id: friend.character_id });
+                                ui.close();
+                            }
+                        });
+                    }
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut friends.add_name);
+                if ui.button("Add").clicked() {
+                    let name = friends.add_name.trim().to_string();
+                    if !name.is_empty() {
+                        commands.client_trigger(AddFriendRequest { name });
+                        friends.add_name.clear();
+                    }
+                }
+            });
+        });
+
+    if !open {
+        ui_state.show_friends = false;
+    }
+}
@@ -6,7 +6,7 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 
 use crate::game_state::MyClientState;
-use crate::ui::state::{UiState, TrainerTab, QuestDialogueData, TrainerWindowData, LootWindowData};
+use crate::ui::state::{UiState, TrainerTab, VendorTab, QuestDialogueData, NpcDialogueData, TrainerWindowData, VendorWindowData, LootWindowData};
 use crate::ui::tooltips::{show_ability_tooltip, show_item_tooltip};
 use crate::ui::admin::system_menu_window;
 
@@ -19,10 +19,14 @@ pub fn game_ui(
     player_query: Query<(Entity, &Health, &Mana, &CurrentTarget, &Hotbar, &Inventory, &Equipment, &CombatStats, &LearnedAbilities, &QuestLog, &Character, &Gold, &Position), With<Player>>,
     progression_query: Query<(&Experience, &WeaponProficiency, &WeaponProficiencyExp, &ArmorProficiency)>,
     buffs_query: Query<Option<&ActiveBuffs>>,
-    target_query: Query<(&Health, Option<&Character>, Option<&NpcName>)>,
+    target_query: Query<(&Health, Option<&Character>, Option<&NpcName>, Option<&ActiveDebuffs>)>,
     loot_query: Query<(Entity, &Position), With<LootContainer>>,
+    death_query: Query<(Has<Dead>, Option<&Ghost>)>,
+    pvp_query: Query<Has<PvpFlagged>>,
     item_db: Res<crate::item_cache::ClientItemDatabase>,
     ability_db: Res<crate::ability_cache::ClientAbilityDatabase>,
+    item_cooldowns: Res<crate::item_cache::ItemCooldownTimers>,
+    mut input_state: ResMut<crate::input::InputState>,
 ) {
     let Ok(ctx) = contexts.ctx_mut() else { return };
 
@@ -43,21 +47,37 @@ pub fn game_ui(
 
     // Active buffs display
     if let Ok(Some(active_buffs)) = buffs_query.get(player_entity) {
-        render_buffs(ctx, active_buffs, &ability_db);
+        render_buffs(ctx, active_buffs, &ability_db, &item_db);
     }
 
     // Target frame
-    render_target_frame(ctx, current_target, &target_query);
+    render_target_frame(ctx, current_target, &target_query, &mut commands);
 
     // Hotbar
-    render_hotbar(ctx, hotbar, &ability_db, &mut commands);
+    render_hotbar(ctx, hotbar, inventory, &ability_db, &item_db, &item_cooldowns, &mut input_state, &mut commands);
 
     // Action buttons
-    render_action_buttons(ctx, &mut ui_state);
+    let pvp_flagged = pvp_query.get(player_entity).unwrap_or(false);
+    render_action_buttons(ctx, &mut ui_state, pvp_flagged, &mut commands);
 
     // Loot hint
     render_loot_hint(ctx, player_pos, &loot_query);
 
+    // Release / resurrect prompt while dead
+    if let Ok((is_dead, ghost)) = death_query.get(player_entity) {
+        render_death_window(ctx, is_dead, ghost, player_pos, &mut commands);
+    }
+
+    // Incoming duel challenge
+    if let Some(challenger) = ui_state.duel_challenge.clone() {
+        render_duel_challenge(ctx, &challenger, &mut ui_state, &mut commands);
+    }
+
+    // Incoming party or guild invite
+    if let Some(invite) = ui_state.group_invite.clone() {
+        render_group_invite(ctx, &invite, &mut ui_state, &mut commands);
+    }
+
     // Equipment window
     if ui_state.show_equipment {
         render_equipment_window(ctx, equipment, &item_db, &mut commands);
@@ -70,7 +90,7 @@ pub fn game_ui(
 
     // Inventory window
     if ui_state.show_inventory {
-        render_inventory_window(ctx, inventory, equipment, &item_db, &mut commands);
+        render_inventory_window(ctx, inventory, equipment, hotbar, &item_db, &mut commands);
     }
 
     // Quest log
@@ -92,11 +112,21 @@ pub fn game_ui(
         render_quest_dialogue(ctx, &dialogue, &mut ui_state, &mut commands);
     }
 
+    // NPC Dialogue Window
+    if let Some(dialogue) = ui_state.npc_dialogue.clone() {
+        render_npc_dialogue(ctx, &dialogue, &mut ui_state, &mut commands);
+    }
+
     // Trainer Window
     if let Some(trainer_data) = ui_state.trainer_window.clone() {
         render_trainer_window(ctx, trainer_data, &mut ui_state, gold, &item_db, &mut commands);
     }
 
+    // Vendor Window
+    if let Some(vendor_data) = ui_state.vendor_window.clone() {
+        render_vendor_window(ctx, vendor_data, &mut ui_state, gold, inventory, &item_db, &mut commands);
+    }
+
     // Loot Container Window
     if let Some(loot_data) = ui_state.loot_window.clone() {
         render_loot_window(ctx, loot_data, &mut ui_state, player_pos, &loot_query, &item_db, &mut commands);
@@ -129,7 +159,7 @@ fn render_player_status(ctx: &egui::Context, health: &Health, mana: &Mana, gold:
         });
 }
 
-fn render_buffs(ctx: &egui::Context, active_buffs: &ActiveBuffs, ability_db: &crate::ability_cache::ClientAbilityDatabase) {
+fn render_buffs(ctx: &egui::Context, active_buffs: &ActiveBuffs, ability_db: &crate::ability_cache::ClientAbilityDatabase, item_db: &crate::item_cache::ClientItemDatabase) {
     if active_buffs.buffs.is_empty() {
         return;
     }
@@ -141,7 +171,10 @@ fn render_buffs(ctx: &egui::Context, active_buffs: &ActiveBuffs, ability_db: &cr
         .show(ctx, |ui| {
             ui.horizontal_wrapped(|ui| {
                 for buff in &active_buffs.buffs {
-                    let ability_name = ability_db.get_ability_name(buff.ability_id);
+                    let ability_name = match buff.item_id {
+                        Some(item_id) => item_db.get_item_name(item_id),
+                        None => ability_db.get_ability_name(buff.ability_id),
+                    };
                     let response = ui.colored_label(egui::Color32::from_rgb(100, 200, 255), &ability_name);
                     response.on_hover_ui(|ui| {
                         ui.label(&ability_name);
@@ -162,10 +195,31 @@ fn render_buffs(ctx: &egui::Context, active_buffs: &ActiveBuffs, ability_db: &cr
         });
 }
 
-fn render_target_frame(ctx: &egui::Context, current_target: &CurrentTarget, target_query: &Query<(&Health, Option<&Character>, Option<&NpcName>)>) {
+fn render_target_frame(
+    ctx: &egui::Context,
+    current_target: &CurrentTarget,
+    target_query: &Query<(&Health, Option<&Character>, Option<&NpcName>, Option<&ActiveDebuffs>)>,
+    commands: &mut Commands,
+) {
     let Some(target_entity) = current_target.0 else { return };
 
-    let Ok((target_health, target_char, target_npc)) = target_query.get(target_entity) else { return };
+    let Ok((target_health, target_char, target_npc, target_debuffs)) = target_query.get(target_entity) else { return };
+
+    // Crowd control status icons
+    let statuses: Vec<(&str, egui::Color32)> = target_debuffs
+        .map(|debuffs| {
+            [
+                (debuffs.is_stunned(), "STUN", egui::Color32::from_rgb(255, 215, 0)),
+                (debuffs.is_rooted() && !debuffs.is_stunned(), "ROOT", egui::Color32::from_rgb(110, 200, 90)),
+                (debuffs.is_silenced() && !debuffs.is_stunned(), "SILENCE", egui::Color32::from_rgb(180, 120, 255)),
+                (debuffs.is_slowed(), "SLOW", egui::Color32::from_rgb(100, 180, 255)),
+            ]
+            .into_iter()
+            .filter(|(active, ..)| *active)
+            .map(|(_, label, color)| (label, color))
+            .collect()
+        })
+        .unwrap_or_default();
 
     let target_name = if let Some(character) = target_char {
         character.name.clone()
@@ -182,10 +236,30 @@ fn render_target_frame(ctx: &egui::Context, current_target: &CurrentTarget, targ
         .show(ctx, |ui| {
             ui.label(&target_name);
             ui.add(egui::ProgressBar::new(target_health.percent()).text(format!("{:.0}/{:.0}", target_health.current, target_health.max)));
+            if !statuses.is_empty() {
+                ui.horizontal(|ui| {
+                    for (label, color) in &statuses {
+                        ui.label(egui::RichText::new(*label).small().strong().color(*color));
+                    }
+                });
+            }
+            // Other players can be challenged to a duel
+            if target_char.is_some() && ui.small_button("Duel").clicked() {
+                commands.client_trigger(DuelChallengeRequest { target: target_entity });
+            }
         });
 }
 
-fn render_hotbar(ctx: &egui::Context, hotbar: &Hotbar, ability_db: &crate::ability_cache::ClientAbilityDatabase, commands: &mut Commands) {
+fn render_hotbar(
+    ctx: &egui::Context,
+    hotbar: &Hotbar,
+    inventory: &Inventory,
+    ability_db: &crate::ability_cache::ClientAbilityDatabase,
+    item_db: &crate::item_cache::ClientItemDatabase,
+    item_cooldowns: &crate::item_cache::ItemCooldownTimers,
+    input_state: &mut crate::input::InputState,
+    commands: &mut Commands,
+) {
     egui::Window::new("Hotbar")
         .fixed_pos([440.0, 630.0])
         .fixed_size([400.0, 70.0])
@@ -193,25 +267,40 @@ fn render_hotbar(ctx: &egui::Context, hotbar: &Hotbar, ability_db: &crate::abili
         .show(ctx, |ui| {
             ui.horizontal(|ui| {
                 for (i, slot) in hotbar.slots.iter().enumerate() {
-                    let button_text = if let Some(HotbarSlot::Ability(ability_id)) = slot {
-                        let ability_name = ability_db.get_ability_name(*ability_id);
-                        format!("{}\n[{}]", ability_name, i + 1)
-                    } else {
-                        format!("[{}]", i + 1)
+                    let button_text = match slot {
+                        Some(HotbarSlot::Ability(ability_id)) => {
+                            let ability_name = ability_db.get_ability_name(*ability_id);
+                            format!("{}\n[{}]", ability_name, i + 1)
+                        }
+                        Some(HotbarSlot::Item(item_id)) => {
+                            let item_name = item_db.get_item_name(*item_id);
+                            format!("{} x{}\n[{}]", item_name, inventory.count_item(*item_id), i + 1)
+                        }
+                        None => format!("[{}]", i + 1),
                     };
 
                     let mut response = ui.button(button_text);
 
-                    if let Some(HotbarSlot::Ability(ability_id)) = slot {
-                        response = show_ability_tooltip(response, *ability_id, ability_db);
+                    match slot {
+                        Some(HotbarSlot::Ability(ability_id)) => {
+                            response = show_ability_tooltip(response, *ability_id, ability_db);
+                        }
+                        Some(HotbarSlot::Item(item_id)) => {
+                            draw_cooldown_sweep(ui, response.rect, item_cooldowns.remaining_fraction(*item_id));
+                            response = show_item_tooltip(response, *item_id, item_db, false);
+                        }
+                        None => {}
                     }
 
                     if response.clicked() {
-                        if let Some(HotbarSlot::Ability(ability_id)) = slot {
-                            commands.client_trigger(UseAbilityRequest {
-                                ability_id: *ability_id,
-                                target_position: None,
-                            });
+                        match slot {
+                            Some(HotbarSlot::Ability(ability_id)) => {
+                                input_state.use_ability(*ability_id, None, ability_db, commands);
+                            }
+                            Some(HotbarSlot::Item(item_id)) => {
+                                commands.client_trigger(UseItemRequest { item_id: *item_id });
+                            }
+                            None => {}
                         }
                     }
                 }
@@ -219,8 +308,31 @@ fn render_hotbar(ctx: &egui::Context, hotbar: &Hotbar, ability_db: &crate::abili
         });
 }
 
-fn render_action_buttons(ctx: &egui::Context, ui_state: &mut UiState) {
-    let button_count = if ui_state.is_admin { 4 } else { 3 };
+/// Darken the part of a hotbar button still cooling down; the lit part grows clockwise from the top
+fn draw_cooldown_sweep(ui: &egui::Ui, rect: egui::Rect, fraction: f32) {
+    if fraction <= 0.0 {
+        return;
+    }
+
+    use std::f32::consts::TAU;
+    let painter = ui.painter_at(rect);
+    let center = rect.center();
+    // Reach past the corners; the painter clips to the button
+    let radius = rect.size().length();
+    let point = |angle: f32| center + radius * egui::vec2(angle.sin(), -angle.cos());
+    let color = egui::Color32::from_black_alpha(160);
+
+    let start = TAU * (1.0 - fraction);
+    let steps = (fraction * 48.0).ceil() as usize;
+    for step in 0..steps {
+        let from = start + TAU * fraction * step as f32 / steps as f32;
+        let to = start + TAU * fraction * (step + 1) as f32 / steps as f32;
+        painter.add(egui::Shape::convex_polygon(vec![center, point(from), point(to)], color, egui::Stroke::NONE));
+    }
+}
+
+fn render_action_buttons(ctx: &egui::Context, ui_state: &mut UiState, pvp_flagged: bool, commands: &mut Commands) {
+    let button_count = if ui_state.is_admin { 7 } else { 6 };
     egui::Window::new("Actions")
         .fixed_pos([1090.0, 10.0])
         .fixed_size([180.0, 30.0 * button_count as f32 + 20.0])
@@ -235,6 +347,16 @@ fn render_action_buttons(ctx: &egui::Context, ui_state: &mut UiState) {
             if ui.button("Inventory").clicked() {
                 ui_state.show_inventory = !ui_state.show_inventory;
             }
+            if ui.button("Combat Log").clicked() {
+                ui_state.show_combat_log = !ui_state.show_combat_log;
+            }
+            if ui.button("Friends").clicked() {
+                ui_state.show_friends = !ui_state.show_friends;
+            }
+            let pvp_label = if pvp_flagged { "PvP: On" } else { "PvP: Off" };
+            if ui.button(pvp_label).clicked() {
+                commands.client_trigger(SetPvpFlagRequest { enabled: !pvp_flagged });
+            }
             if ui.button("System Menu").clicked() {
                 ui_state.show_system_menu = !ui_state.show_system_menu;
             }
@@ -264,6 +386,86 @@ fn render_loot_hint(ctx: &egui::Context, player_pos: &Position, loot_query: &Que
     }
 }
 
+fn render_death_window(ctx: &egui::Context, is_dead: bool, ghost: Option<&Ghost>, player_pos: &Position, commands: &mut Commands) {
+    if !is_dead && ghost.is_none() {
+        return;
+    }
+
+    egui::Window::new("You Have Died")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 80.0])
+        .fixed_size([260.0, 80.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                if let Some(ghost) = ghost {
+                    let distance = player_pos.0.distance(ghost.corpse_position);
+                    ui.label("Return to your corpse, or speak to a spirit healer.");
+                    ui.label(format!("Corpse: {:.0} away", distance));
+                    let in_range = distance <= CORPSE_RESURRECT_RANGE;
+                    if ui.add_enabled(in_range, egui::Button::new("Resurrect")).clicked() {
+                        commands.client_trigger(ResurrectAtCorpseRequest);
+                    }
+                } else {
+                    ui.label("Wait for a resurrection, or release your spirit.");
+                    if ui.button("Release Spirit").clicked() {
+                        commands.client_trigger(ReleaseSpiritRequest);
+                    }
+                }
+            });
+        });
+}
+
+fn render_duel_challenge(ctx: &egui::Context, challenger: &str, ui_state: &mut UiState, commands: &mut Commands) {
+    egui::Window::new("Duel Challenge")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 180.0])
+        .fixed_size([240.0, 70.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(format!("{} challenges you to a duel!", challenger));
+                ui.horizontal(|ui| {
+                    if ui.button("Accept").clicked() {
+                        commands.client_trigger(DuelResponseRequest { accept: true });
+                        ui_state.duel_challenge = None;
+                    }
+                    if ui.button("Decline").clicked() {
+                        commands.client_trigger(DuelResponseRequest { accept: false });
+                        ui_state.duel_challenge = None;
+                    }
+                });
+            });
+        });
+}
+
+fn render_group_invite(ctx: &egui::Context, invite: &GroupInviteEvent, ui_state: &mut UiState, commands: &mut Commands) {
+    let text = match &invite.group_name {
+        Some(guild) => format!("{} invites you to join the guild {}.", invite.inviter_name, guild),
+        None => format!("{} invites you to join their {}.", invite.inviter_name, invite.kind.label()),
+    };
+    egui::Window::new("Invitation")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 260.0])
+        .fixed_size([260.0, 70.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(text);
+                ui.horizontal(|ui| {
+                    if ui.button("Accept").clicked() {
+                        commands.client_trigger(GroupInviteResponse { accept: true });
+                        ui_state.group_invite = None;
+                    }
+                    if ui.button("Decline").clicked() {
+                        commands.client_trigger(GroupInviteResponse { accept: false });
+                        ui_state.group_invite = None;
+                    }
+                });
+            });
+        });
+}
+
 fn render_equipment_window(ctx: &egui::Context, equipment: &Equipment, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
     egui::Window::new("Equipment")
         .collapsible(false)
@@ -385,11 +587,11 @@ fn render_character_stats(
         });
 }
 
-fn render_inventory_window(ctx: &egui::Context, inventory: &Inventory, equipment: &Equipment, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
+fn render_inventory_window(ctx: &egui::Context, inventory: &Inventory, equipment: &Equipment, hotbar: &Hotbar, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
     egui::Window::new("Inventory")
         .collapsible(false)
         .show(ctx, |ui| {
-            ui.label("Right-click items to equip/unequip");
+            ui.label("Right-click items to equip, use or drop");
             ui.separator();
 
             let mut equipped_ids = std::collections::HashSet::new();
@@ -408,8 +610,28 @@ fn render_inventory_window(ctx: &egui::Context, inventory: &Inventory, equipment
 
                             response = show_item_tooltip(response, item_stack.item_id, item_db, false);
 
+                            let is_consumable = item_db.get_item_info(item_stack.item_id)
+                                .is_some_and(|item| item.item_type == crate::item_cache::ItemType::Consumable);
+
                             response.context_menu(|ui| {
-                                if ui.button("Equip").clicked() {
+                                if is_consumable {
+                                    if ui.button("Use").clicked() {
+                                        commands.client_trigger(UseItemRequest { item_id: item_stack.item_id });
+                                        ui.close();
+                                    }
+                                    let on_hotbar = hotbar.slots.iter()
+                                        .any(|slot| matches!(slot, Some(HotbarSlot::Item(id)) if *id == item_stack.item_id));
+                                    let free_slot = hotbar.slots.iter().position(|slot| slot.is_none());
+                                    if let (false, Some(slot_index)) = (on_hotbar, free_slot) {
+                                        if ui.button("Add to Hotbar").clicked() {
+                                            commands.client_trigger(SetHotbarSlotRequest {
+                                                slot_index,
+                                                content: Some(HotbarSlot::Item(item_stack.item_id)),
+                                            });
+                                            ui.close();
+                                        }
+                                    }
+                                } else if ui.button("Equip").clicked() {
                                     commands.client_trigger(EquipItemRequest { slot_index: i });
                                     ui.close();
                                 }
@@ -497,7 +719,7 @@ fn render_esc_menu(ctx: &egui::Context, ui_state: &mut UiState, commands: &mut C
     egui::Window::new("Menu")
         .collapsible(false)
         .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
-        .fixed_size([300.0, 200.0])
+        .fixed_size([300.0, 240.0])
         .show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(20.0);
@@ -512,6 +734,13 @@ fn render_esc_menu(ctx: &egui::Context, ui_state: &mut UiState, commands: &mut C
 
                 ui.add_space(10.0);
 
+                if ui.button("Settings").clicked() {
+                    ui_state.show_esc_menu = false;
+                    ui_state.show_settings = true;
+                }
+
+                ui.add_space(10.0);
+
                 if ui.button("Resume").clicked() {
                     ui_state.show_esc_menu = false;
                 }
@@ -570,6 +799,32 @@ fn render_quest_dialogue(ctx: &egui::Context, dialogue: &QuestDialogueData, ui_s
         });
 }
 
+fn render_npc_dialogue(ctx: &egui::Context, dialogue: &NpcDialogueData, ui_state: &mut UiState, commands: &mut Commands) {
+    egui::Window::new(&dialogue.npc_name)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .fixed_size([500.0, 300.0])
+        .show(ctx, |ui| {
+            ui.add_space(10.0);
+            ui.label(&dialogue.text);
+            ui.add_space(20.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            // The server answers a reply with the next node, or nothing if it ends the conversation
+            for choice in &dialogue.choices {
+                if ui.button(&choice.text).clicked() {
+                    commands.client_trigger(ChooseDialogueOptionRequest { index: choice.index });
+                    ui_state.npc_dialogue = None;
+                }
+            }
+
+            if dialogue.choices.is_empty() && ui.button("Goodbye").clicked() {
+                ui_state.npc_dialogue = None;
+            }
+        });
+}
+
 fn render_trainer_window(
     ctx: &egui::Context,
     mut trainer_data: TrainerWindowData,
@@ -672,6 +927,143 @@ fn render_trainer_shop(ui: &mut egui::Ui, trainer_data: &TrainerWindowData, gold
     }
 }
 
+fn render_vendor_window(
+    ctx: &egui::Context,
+    mut vendor_data: VendorWindowData,
+    ui_state: &mut UiState,
+    gold: &Gold,
+    inventory: &Inventory,
+    item_db: &crate::item_cache::ClientItemDatabase,
+    commands: &mut Commands,
+) {
+    let window_title = if vendor_data.shop_name.is_empty() {
+        format!("{} - Shop", vendor_data.npc_name)
+    } else {
+        format!("{} - {}", vendor_data.npc_name, vendor_data.shop_name)
+    };
+
+    egui::Window::new(window_title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .fixed_size([450.0, 550.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (tab, label) in [(VendorTab::Buy, "Buy"), (VendorTab::Sell, "Sell"), (VendorTab::Buyback, "Buyback")] {
+                    if ui.selectable_label(vendor_data.active_tab == tab, label).clicked() {
+                        vendor_data.active_tab = tab;
+                        ui_state.vendor_window = Some(vendor_data.clone());
+                    }
+                }
+            });
+            ui.separator();
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("Your Gold:");
+                ui.colored_label(egui::Color32::GOLD, format!("{}", gold.0));
+            });
+            ui.add_space(5.0);
+            ui.separator();
+
+            egui::ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                match vendor_data.active_tab {
+                    VendorTab::Buy => render_vendor_buy(ui, &vendor_data, gold, item_db, commands),
+                    VendorTab::Sell => render_vendor_sell(ui, &vendor_data, inventory, item_db, commands),
+                    VendorTab::Buyback => render_vendor_buyback(ui, &vendor_data, gold, item_db, commands),
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.vertical_centered(|ui| {
+                if ui.button("Close").clicked() {
+                    ui_state.vendor_window = None;
+                }
+            });
+        });
+}
+
+fn render_vendor_buy(ui: &mut egui::Ui, vendor_data: &VendorWindowData, gold: &Gold, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
+    if vendor_data.items.is_empty() {
+        ui.label("No items for sale.");
+        return;
+    }
+
+    for vendor_item in &vendor_data.items {
+        let Some(item_def) = item_db.items.get(&vendor_item.item_id) else { continue };
+        ui.horizontal(|ui| {
+            let response = ui.label(egui::RichText::new(&item_def.name).strong());
+            show_item_tooltip(response, vendor_item.item_id, item_db, false);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let can_afford = gold.0 >= vendor_item.price;
+                if ui.add_enabled(can_afford, egui::Button::new(format!("Buy ({} gold)", vendor_item.price))).clicked() {
+                    commands.client_trigger(BuyFromVendorRequest { item_id: vendor_item.item_id });
+                }
+            });
+        });
+        ui.separator();
+    }
+}
+
+fn render_vendor_sell(ui: &mut egui::Ui, vendor_data: &VendorWindowData, inventory: &Inventory, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
+    let mut any_sellable = false;
+
+    for (slot_index, slot) in inventory.slots.iter().enumerate() {
+        let Some(stack) = slot else { continue };
+        let Some(item_def) = item_db.items.get(&stack.item_id) else { continue };
+        let Some(sell_price) = vendor_data.sell_prices.iter().find(|info| info.item_id == stack.item_id) else { continue };
+        any_sellable = true;
+
+        ui.horizontal(|ui| {
+            let label = if stack.quantity > 1 {
+                format!("{} x{}", item_def.name, stack.quantity)
+            } else {
+                item_def.name.clone()
+            };
+            let response = ui.label(egui::RichText::new(label).strong());
+            show_item_tooltip(response, stack.item_id, item_db, false);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button(format!("Sell ({} gold)", sell_price.price * stack.quantity)).clicked() {
+                    commands.client_trigger(SellToVendorRequest { slot_index });
+                }
+            });
+        });
+        ui.separator();
+    }
+
+    if !any_sellable {
+        ui.label("You have nothing this vendor will buy.");
+    }
+}
+
+fn render_vendor_buyback(ui: &mut egui::Ui, vendor_data: &VendorWindowData, gold: &Gold, item_db: &crate::item_cache::ClientItemDatabase, commands: &mut Commands) {
+    if vendor_data.buyback.is_empty() {
+        ui.label("Nothing to buy back.");
+        return;
+    }
+
+    for (index, entry) in vendor_data.buyback.iter().enumerate() {
+        let name = item_db.items.get(&entry.item_id)
+            .map(|def| def.name.clone())
+            .unwrap_or_else(|| format!("Item #{}", entry.item_id));
+        ui.horizontal(|ui| {
+            if entry.quantity > 1 {
+                ui.label(format!("{} x{}", name, entry.quantity));
+            } else {
+                ui.label(name);
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let can_afford = gold.0 >= entry.price;
+                if ui.add_enabled(can_afford, egui::Button::new(format!("Buy back ({} gold)", entry.price))).clicked() {
+                    commands.client_trigger(BuybackRequest { index });
+                }
+            });
+        });
+        ui.separator();
+    }
+}
+
 fn render_trainer_training(ui: &mut egui::Ui, trainer_data: &TrainerWindowData, ui_state: &mut UiState, commands: &mut Commands) {
     if trainer_data.teaching_quests.is_empty() {
         ui.centered_and_justified(|ui| { ui.label("No training available."); });
@@ -850,6 +1242,39 @@ pub fn handle_quest_dialogue(
     });
 }
 
+/// Observer for DialogueNodeEvent - opens the NPC dialogue window on the reached node
+pub fn handle_npc_dialogue(
+    trigger: On<DialogueNodeEvent>,
+    mut ui_state: ResMut<UiState>,
+) {
+    let event = trigger.event();
+    ui_state.npc_dialogue = Some(NpcDialogueData {
+        npc_name: event.npc_name.clone(),
+        text: event.text.clone(),
+        choices: event.choices.clone(),
+    });
+}
+
+/// Observer for DuelChallengeEvent - asks the player to accept or decline
+pub fn handle_duel_challenge(
+    trigger: On<DuelChallengeEvent>,
+    mut ui_state: ResMut<UiState>,
+) {
+    let event = trigger.event();
+    info!("[DUEL] Challenged by {}", event.challenger_name);
+    ui_state.duel_challenge = Some(event.challenger_name.clone());
+}
+
+/// Observer for GroupInviteEvent - asks the player to accept or decline
+pub fn handle_group_invite(
+    trigger: On<GroupInviteEvent>,
+    mut ui_state: ResMut<UiState>,
+) {
+    let event = trigger.event();
+    info!("[GROUP] Invited to a {} by {}", event.kind.label(), event.inviter_name);
+    ui_state.group_invite = Some(event.clone());
+}
+
 /// Handle loot container contents event from server
 pub fn handle_loot_container_contents(
     trigger: On<LootContainerContentsEvent>,
@@ -889,3 +1314,27 @@ pub fn handle_trainer_dialogue(
         active_tab: default_tab,
     });
 }
+
+/// Observer for VendorWindowEvent - opens or refreshes the vendor window
+pub fn handle_vendor_window(
+    trigger: On<VendorWindowEvent>,
+    mut ui_state: ResMut<UiState>,
+) {
+    let event = trigger.event();
+    info!("[VENDOR] Received shop '{}' from {} with {} items", event.shop_name, event.npc_name, event.items.len());
+
+    // Keep the current tab when the window is refreshed after a transaction
+    let active_tab = ui_state.vendor_window.as_ref()
+        .filter(|window| window.npc_name == event.npc_name)
+        .map(|window| window.active_tab)
+        .unwrap_or_default();
+
+    ui_state.vendor_window = Some(VendorWindowData {
+        npc_name: event.npc_name.clone(),
+        shop_name: event.shop_name.clone(),
+        items: event.items.clone(),
+        sell_prices: event.sell_prices.clone(),
+        buyback: event.buyback.clone(),
+        active_tab,
+    });
+}
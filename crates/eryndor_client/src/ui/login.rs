@@ -5,6 +5,7 @@ use bevy_egui::{egui, EguiContexts};
 use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 
+use crate::device_id::DeviceId;
 use crate::game_state::{GameState, MyClientState};
 use super::state::UiState;
 
@@ -14,6 +15,7 @@ pub fn login_ui(
     mut ui_state: ResMut<UiState>,
     mut commands: Commands,
     client_state: Res<MyClientState>,
+    device_id: Res<DeviceId>,
 ) {
     let Ok(ctx) = contexts.ctx_mut() else { return };
     egui::CentralPanel::default().show(ctx, |ui| {
@@ -37,7 +39,7 @@ pub fn login_ui(
 
             // Show either Login or Register form
             if !ui_state.show_register_tab {
-                login_form(ui, &mut ui_state, &mut commands);
+                login_form(ui, &mut ui_state, &mut commands, &device_id);
             } else {
                 register_form(ui, &mut ui_state, &mut commands);
             }
@@ -54,7 +56,7 @@ pub fn login_ui(
     });
 }
 
-fn login_form(ui: &mut egui::Ui, ui_state: &mut UiState, commands: &mut Commands) {
+fn login_form(ui: &mut egui::Ui, ui_state: &mut UiState, commands: &mut Commands, device_id: &DeviceId) {
     ui.heading("Login");
     ui.add_space(10.0);
 
@@ -64,14 +66,25 @@ fn login_form(ui: &mut egui::Ui, ui_state: &mut UiState, commands: &mut Commands
 
     ui.label("Password:");
     ui.add(egui::TextEdit::singleline(&mut ui_state.password).password(true));
-    ui.add_space(20.0);
+    ui.add_space(10.0);
+
+    // The server asks for this once the password checks out
+    if ui_state.two_factor_required {
+        ui.label("Authenticator or backup code:");
+        ui.text_edit_singleline(&mut ui_state.totp_code);
+        ui.add_space(10.0);
+    }
+    ui.add_space(10.0);
 
     if ui.button("Login").clicked()
         && !ui_state.username.is_empty() && !ui_state.password.is_empty() {
         info!("Sending login request for user: {}", ui_state.username);
+        let totp_code = ui_state.totp_code.trim().to_string();
         commands.client_trigger(LoginRequest {
             username: ui_state.username.clone(),
             password: ui_state.password.clone(),
+            totp_code: (!totp_code.is_empty()).then_some(totp_code),
+            hardware_id: device_id.0.clone(),
         });
     }
 }
@@ -164,26 +177,62 @@ pub fn character_select_ui(
     mut contexts: EguiContexts,
     mut ui_state: ResMut<UiState>,
     mut commands: Commands,
-    client_state: Res<MyClientState>,
+    mut client_state: ResMut<MyClientState>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     let Ok(ctx) = contexts.ctx_mut() else { return };
+    let mut switch_to = None;
     egui::CentralPanel::default().show(ctx, |ui| {
         ui.vertical_centered(|ui| {
             ui.add_space(50.0);
             ui.heading("Select Character");
             ui.add_space(20.0);
 
+            // Shard selector; only shown once there's more than one to pick from
+            let current_shard = client_state.current_shard.clone().unwrap_or_default();
+            if client_state.shards.len() > 1 {
+                ui.label("Shard:");
+                for shard in &client_state.shards {
+                    ui.horizontal(|ui| {
+                        let status = if !shard.online {
+                            "offline".to_string()
+                        } else if shard.is_full() {
+                            format!("{}/{} - full", shard.population, shard.max_players)
+                        } else {
+                            format!("{}/{}", shard.population, shard.max_players)
+                        };
+                        let current = shard.id == current_shard;
+                        let can_join = shard.online && !shard.is_full();
+                        if ui.add_enabled(current || can_join, egui::Button::new(format!("{} ({})", shard.name, status)).selected(current)).clicked() && !current {
+                            switch_to = Some(shard.clone());
+                        }
+                    });
+                }
+                ui.add_space(20.0);
+            }
+
             // List characters
             for character in &client_state.characters {
                 ui.horizontal(|ui| {
-                    ui.label(format!("{} - {} (Level {})", character.name, character.class.as_str(), character.level));
-
-                    if ui.button("Play").clicked() {
-                        commands.client_trigger(SelectCharacterRequest {
-                            character_id: character.id,
-                        });
-                        next_state.set(GameState::InGame);
+                    ui.label(format!("{} - {} {} (Level {})", character.name, character.faction.as_str(), character.class.as_str(), character.level));
+
+                    // Characters can only be played on the shard they were made on
+                    if current_shard.is_empty() || character.shard_id == current_shard {
+                        if ui.button("Play").clicked() {
+                            commands.client_trigger(SelectCharacterRequest {
+                                character_id: character.id,
+                            });
+                            next_state.set(GameState::InGame);
+                        }
+                    } else {
+                        let home = client_state.shards.iter().find(|shard| shard.id == character.shard_id);
+                        let name = home.map(|shard| shard.name.as_str()).unwrap_or(&character.shard_id);
+                        ui.weak(format!("on {}", name));
+                        if let Some(home) = home.filter(|shard| shard.online && !shard.is_full()) {
+                            if ui.button("Switch").clicked() {
+                                switch_to = Some(home.clone());
+                            }
+                        }
                     }
                 });
                 ui.add_space(10.0);
@@ -194,6 +243,9 @@ pub fn character_select_ui(
             if ui.button("Create New Character").clicked() {
                 ui_state.show_create_character = true;
             }
+            if ui.button("Settings").clicked() {
+                ui_state.show_settings = !ui_state.show_settings;
+            }
 
             // Show notifications
             for notification in &client_state.notifications {
@@ -202,6 +254,10 @@ pub fn character_select_ui(
         });
     });
 
+    if switch_to.is_some() {
+        client_state.pending_shard_switch = switch_to;
+    }
+
     // Create character window
     if ui_state.show_create_character {
         create_character_window(ctx, &mut ui_state, &mut commands);
@@ -231,12 +287,24 @@ fn create_character_window(ctx: &egui::Context, ui_state: &mut UiState, commands
 
             ui.add_space(10.0);
 
+            ui.label("Faction:");
+            ui.horizontal(|ui| {
+                for faction in Faction::ALL {
+                    if ui.selectable_label(ui_state.selected_faction == faction, faction.as_str()).clicked() {
+                        ui_state.selected_faction = faction;
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+
             ui.horizontal(|ui| {
                 if ui.button("Create").clicked()
                     && !ui_state.new_character_name.is_empty() {
                     commands.client_trigger(CreateCharacterRequest {
                         name: ui_state.new_character_name.clone(),
                         class: ui_state.selected_class,
+                        faction: ui_state.selected_faction,
                     });
                     ui_state.show_create_character = false;
                     ui_state.new_character_name.clear();
@@ -254,6 +322,7 @@ fn create_character_window(ctx: &egui::Context, ui_state: &mut UiState, commands
 pub fn check_oauth_callback(
     mut ui_state: ResMut<UiState>,
     mut commands: Commands,
+    device_id: Res<DeviceId>,
 ) {
     use wasm_bindgen::JsCast;
 
@@ -289,6 +358,7 @@ pub fn check_oauth_callback(
             commands.client_trigger(OAuthLoginRequest {
                 provider: "google".to_string(),
                 token: token.clone(),
+                hardware_id: device_id.0.clone(),
             });
 
             // Clean up URL by removing hash
@@ -5,6 +5,9 @@
 //! - `login` - Login and character selection screens
 //! - `game` - Main game UI systems and windows
 //! - `chat` - Chat system
+//! - `combat_log` - Combat log window
+//! - `friends` - Friends window
+//! - `settings` - Account settings (two-factor authentication)
 //! - `admin` - Admin dashboard
 //! - `tooltips` - Tooltip helper functions
 //! - `helpers` - Helper functions for formatting
@@ -13,6 +16,9 @@ pub mod state;
 pub mod login;
 pub mod game;
 pub mod chat;
+pub mod combat_log;
+pub mod friends;
+pub mod settings;
 pub mod admin;
 pub mod tooltips;
 pub mod helpers;
@@ -20,5 +26,8 @@ pub mod helpers;
 // Re-export commonly used items
 pub use state::{UiState, SystemMenuState, SystemMenuTab, LootWindowData, QuestDialogueData, TrainerWindowData, TrainerTab};
 pub use login::{login_ui, character_select_ui, check_oauth_callback};
-pub use game::{game_ui, handle_esc_key, handle_quest_dialogue, handle_loot_container_contents, handle_trainer_dialogue};
+pub use game::{game_ui, handle_esc_key, handle_duel_challenge, handle_group_invite, handle_quest_dialogue, handle_npc_dialogue, handle_loot_container_contents, handle_trainer_dialogue, handle_vendor_window};
 pub use chat::{chat_window, receive_chat_messages};
+pub use combat_log::{CombatLogState, combat_log_window, receive_combat_log};
+pub use friends::{FriendsState, friends_window, receive_friend_list};
+pub use settings::{SettingsState, settings_window, receive_two_factor_setup, receive_two_factor_status};
@@ -0,0 +1,137 @@
+//! Account settings window: two-factor authentication enrollment.
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+
+use super::state::UiState;
+
+/// Two-factor enrollment in progress and the last server reply
+#[derive(Resource, Default)]
+pub struct SettingsState {
+    /// Secret and otpauth link, while enrollment waits for the first code
+    pub pending_setup: Option<(String, String)>,
+    pub code: String,
+    /// Backup codes from enrollment; only kept until the window closes
+    pub backup_codes: Vec<String>,
+    /// Last server reply, and whether it was a success
+    pub message: Option<(bool, String)>,
+}
+
+pub fn receive_two_factor_setup(
+    trigger: On<TwoFactorSetupResponse>,
+    mut settings: ResMut<SettingsState>,
+) {
+    let response = trigger.event();
+    if response.success {
+        settings.pending_setup = Some((response.secret.clone(), response.otpauth_uri.clone()));
+        settings.code.clear();
+    }
+    settings.message = Some((response.success, response.message.clone()));
+}
+
+pub fn receive_two_factor_status(
+    trigger: On<TwoFactorStatusResponse>,
+    mut settings: ResMut<SettingsState>,
+    mut ui_state: ResMut<UiState>,
+) {
+    let response = trigger.event();
+    ui_state.two_factor_enabled = response.enabled;
+    if response.success {
+        settings.pending_setup = None;
+        settings.code.clear();
+        settings.backup_codes = response.backup_codes.clone();
+    }
+    settings.message = Some((response.success, response.message.clone()));
+}
+
+pub fn settings_window(
+    mut contexts: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut settings: ResMut<SettingsState>,
+    mut commands: Commands,
+) {
+    if !ui_state.show_settings {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else { return };
+
+    let mut open = true;
+    egui::Window::new("Settings")
+        .open(&mut open)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            ui.heading("Two-Factor Authentication");
+            ui.add_space(10.0);
+
+            if ui_state.two_factor_enabled {
+                ui.colored_label(egui::Color32::from_rgb(120, 230, 120), "Enabled");
+
+                if !settings.backup_codes.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label("Backup codes - each works once if you lose your device:");
+                    let codes = settings.backup_codes.join("\n");
+                    ui.label(egui::RichText::new(&codes).monospace());
+                    if ui.button("Copy codes").clicked() {
+                        ui.ctx().copy_text(codes);
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.label("To turn it off, enter a current or backup code:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut settings.code);
+                    if ui.button("Disable").clicked() && !settings.code.trim().is_empty() {
+                        commands.client_trigger(TwoFactorDisableRequest { code: settings.code.trim().to_string() });
+                    }
+                });
+            } else if let Some((secret, otpauth_uri)) = settings.pending_setup.clone() {
+                ui.label("Add this key to your authenticator app:");
+                ui.label(egui::RichText::new(&secret).monospace().strong());
+                ui.horizontal(|ui| {
+                    if ui.button("Copy key").clicked() {
+                        ui.ctx().copy_text(secret);
+                    }
+                    if ui.button("Copy otpauth link").clicked() {
+                        ui.ctx().copy_text(otpauth_uri);
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.label("Then enter the 6-digit code it shows:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut settings.code);
+                    if ui.button("Confirm").clicked() && !settings.code.trim().is_empty() {
+                        commands.client_trigger(TwoFactorConfirmRequest { code: settings.code.trim().to_string() });
+                    }
+                    if ui.button("Cancel").clicked() {
+                        settings.pending_setup = None;
+                        settings.message = None;
+                    }
+                });
+            } else {
+                ui.label("Require a code from an authenticator app when logging in with your password.");
+                ui.add_space(5.0);
+                if ui.button("Set up").clicked() {
+                    commands.client_trigger(TwoFactorSetupRequest);
+                }
+            }
+
+            if let Some((success, message)) = &settings.message {
+                ui.add_space(10.0);
+                let color = if *success { egui::Color32::LIGHT_GREEN } else { egui::Color32::YELLOW };
+                ui.colored_label(color, message);
+            }
+        });
+
+    if !open {
+        ui_state.show_settings = false;
+        // Backup codes are only ever shown once
+        settings.backup_codes.clear();
+        settings.code.clear();
+        settings.message = None;
+    }
+}
@@ -17,6 +17,8 @@ pub struct SystemMenuState {
     pub ban_form_username: String,
     pub ban_form_duration: u32,
     pub ban_form_reason: String,
+    /// Also ban the IP address and device the player last logged in from
+    pub ban_form_ip_and_device: bool,
     pub ban_username: String,
     pub ban_duration: String,
     pub ban_reason: String,
@@ -38,6 +40,7 @@ impl Default for SystemMenuState {
             ban_form_username: String::new(),
             ban_form_duration: 0,
             ban_form_reason: String::new(),
+            ban_form_ip_and_device: false,
             ban_username: String::new(),
             ban_duration: String::new(),
             ban_reason: String::new(),
@@ -62,24 +65,42 @@ pub struct UiState {
     pub email: String,
     pub username: String,
     pub password: String,
+    /// Authenticator code, asked for once the server says the account needs one
+    pub totp_code: String,
+    pub two_factor_required: bool,
+    pub two_factor_enabled: bool,
     pub new_character_name: String,
     pub selected_class: CharacterClass,
+    pub selected_faction: Faction,
     pub show_create_character: bool,
     pub show_inventory: bool,
     pub show_equipment: bool,
     pub show_character_stats: bool,
     pub show_esc_menu: bool,
     pub quest_dialogue: Option<QuestDialogueData>,
+    pub npc_dialogue: Option<NpcDialogueData>,
     pub trainer_window: Option<TrainerWindowData>,
+    pub vendor_window: Option<VendorWindowData>,
     pub loot_window: Option<LootWindowData>,
     pub show_register_tab: bool,
     pub oauth_checked: bool,
     pub chat_input: String,
-    pub chat_history: Vec<String>,
+    pub chat_history: Vec<ChatLine>,
+    /// Chat tab being viewed; None shows every channel
+    pub chat_tab: Option<ChatChannel>,
+    /// Channel plain messages are sent on
+    pub chat_channel: ChatChannel,
     pub chat_has_focus: bool,
     pub chat_previous_focus: bool,
     pub is_admin: bool,
     pub show_system_menu: bool,
+    pub show_combat_log: bool,
+    pub show_friends: bool,
+    pub show_settings: bool,
+    /// Name of the player whose duel challenge is awaiting an answer
+    pub duel_challenge: Option<String>,
+    /// Party or guild invite awaiting an answer
+    pub group_invite: Option<GroupInviteEvent>,
     pub system_menu: SystemMenuState,
 }
 
@@ -89,29 +110,50 @@ impl Default for UiState {
             email: String::new(),
             username: String::new(),
             password: String::new(),
+            totp_code: String::new(),
+            two_factor_required: false,
+            two_factor_enabled: false,
             new_character_name: String::new(),
             selected_class: CharacterClass::Rogue,
+            selected_faction: Faction::default(),
             show_create_character: false,
             show_inventory: false,
             show_equipment: false,
             show_character_stats: false,
             show_esc_menu: false,
             quest_dialogue: None,
+            npc_dialogue: None,
             trainer_window: None,
+            vendor_window: None,
             loot_window: None,
             show_register_tab: false,
             oauth_checked: false,
             chat_input: String::new(),
             chat_history: Vec::new(),
+            chat_tab: None,
+            chat_channel: ChatChannel::Say,
             chat_has_focus: false,
             chat_previous_focus: false,
             is_admin: false,
             show_system_menu: false,
+            show_combat_log: false,
+            show_friends: false,
+            show_settings: false,
+            duel_challenge: None,
+            group_invite: None,
             system_menu: SystemMenuState::default(),
         }
     }
 }
 
+/// A line in the chat window
+#[derive(Clone)]
+pub struct ChatLine {
+    /// None for local system lines such as echoed admin commands
+    pub channel: Option<ChatChannel>,
+    pub text: String,
+}
+
 /// Data for the loot container window
 #[derive(Clone)]
 pub struct LootWindowData {
@@ -131,6 +173,14 @@ pub struct QuestDialogueData {
     pub rewards_text: String,
 }
 
+/// Data for the NPC dialogue window
+#[derive(Clone)]
+pub struct NpcDialogueData {
+    pub npc_name: String,
+    pub text: String,
+    pub choices: Vec<DialogueOption>,
+}
+
 /// Data for the trainer window
 #[derive(Clone)]
 pub struct TrainerWindowData {
@@ -141,6 +191,26 @@ pub struct TrainerWindowData {
     pub active_tab: TrainerTab,
 }
 
+/// Data for the vendor shop window
+#[derive(Clone)]
+pub struct VendorWindowData {
+    pub npc_name: String,
+    pub shop_name: String,
+    pub items: Vec<VendorItemInfo>,
+    pub sell_prices: Vec<VendorItemInfo>,
+    pub buyback: Vec<BuybackEntry>,
+    pub active_tab: VendorTab,
+}
+
+/// Tab selection for vendor window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VendorTab {
+    #[default]
+    Buy,
+    Sell,
+    Buyback,
+}
+
 /// Tab selection for trainer window
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum TrainerTab {
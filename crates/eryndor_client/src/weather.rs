@@ -0,0 +1,172 @@
+//! Day/night and weather rendering.
+//!
+//! A screen-sized overlay parented to the camera is tinted from the server's `WorldClock` and
+//! the current zone's `ZoneWeather`: deep blue at night, grey under clouds and storms, pale in
+//! fog. Rain and storms also spawn short-lived raindrop sprites around the camera.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+
+use crate::tilemap::ZoneTilemapRender;
+
+/// Strongest darkening at midnight
+const NIGHT_DARKNESS: f32 = 0.55;
+/// Raindrops spawned per second at full intensity
+const RAIN_RATE: f32 = 400.0;
+const RAIN_VELOCITY: Vec2 = Vec2::new(-80.0, -600.0);
+const RAIN_LIFETIME: f32 = 1.0;
+/// Half-size of the area around the camera raindrops start in
+const RAIN_AREA: Vec2 = Vec2::new(700.0, 450.0);
+
+/// Full-screen tint for time of day and weather
+#[derive(Component)]
+pub struct WeatherOverlay;
+
+#[derive(Component)]
+pub struct RainDrop {
+    lifetime: f32,
+}
+
+/// Fractional raindrops carried over between frames, and a cheap random source for placing them
+#[derive(Resource)]
+pub struct RainSpawner {
+    pending: f32,
+    seed: u32,
+}
+
+impl Default for RainSpawner {
+    fn default() -> Self {
+        Self { pending: 0.0, seed: 0x9E37_79B9 }
+    }
+}
+
+impl RainSpawner {
+    /// Xorshift; raindrops only need to look scattered
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.random()
+    }
+}
+
+/// Weather in the zone the player is in (clear when the zone has none)
+fn current_weather<'a>(render: &ZoneTilemapRender, zones: &'a Query<&ZoneWeather>) -> Option<&'a ZoneWeather> {
+    zones.iter().find(|weather| weather.zone_id == render.zone_id)
+}
+
+pub fn spawn_weather_overlay(
+    mut commands: Commands,
+    camera: Query<Entity, (With<Camera2d>, Without<WeatherOverlay>)>,
+    overlays: Query<(), With<WeatherOverlay>>,
+) {
+    if !overlays.is_empty() {
+        return;
+    }
+    let Ok(camera) = camera.single() else { return };
+
+    let overlay = commands.spawn((
+        WeatherOverlay,
+        Sprite::from_color(Color::NONE, Vec2::splat(10000.0)),
+        // Just in front of the camera so it covers the world but not the UI
+        Transform::from_xyz(0.0, 0.0, -1.0),
+    )).id();
+    commands.entity(camera).add_child(overlay);
+}
+
+pub fn update_weather_overlay(
+    clocks: Query<&WorldClock>,
+    zones: Query<&ZoneWeather>,
+    render: Res<ZoneTilemapRender>,
+    mut overlays: Query<&mut Sprite, With<WeatherOverlay>>,
+) {
+    let daylight = clocks.iter().next().map(WorldClock::daylight).unwrap_or(1.0);
+    let (weather, intensity) = current_weather(&render, &zones)
+        .map(|zone| (zone.weather, zone.intensity))
+        .unwrap_or_default();
+
+    // Clouds darken the sky; fog washes it out
+    let (gloom, fog) = match weather {
+        Weather::Clear => (0.0, 0.0),
+        Weather::Cloudy => (0.12, 0.0),
+        Weather::Fog => (0.05, 0.45 * intensity),
+        Weather::Rain => (0.1 + 0.1 * intensity, 0.0),
+        Weather::Storm => (0.2 + 0.15 * intensity, 0.0),
+    };
+    let darkness = ((1.0 - daylight) * NIGHT_DARKNESS + gloom).min(0.8);
+    let alpha = (darkness + fog).min(0.85);
+
+    let night = LinearRgba::new(0.02, 0.03, 0.15, 1.0);
+    let mist = LinearRgba::new(0.75, 0.78, 0.8, 1.0);
+    let fog_share = if alpha > 0.0 { fog / (darkness + fog) } else { 0.0 };
+    let color = Color::from(night.mix(&mist, fog_share)).with_alpha(alpha);
+
+    for mut sprite in &mut overlays {
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Spawn, move and expire raindrops while it's raining in the player's zone
+pub fn update_rain(
+    mut commands: Commands,
+    mut spawner: ResMut<RainSpawner>,
+    zones: Query<&ZoneWeather>,
+    render: Res<ZoneTilemapRender>,
+    camera: Query<&Transform, (With<Camera2d>, Without<RainDrop>)>,
+    mut drops: Query<(Entity, &mut Transform, &mut RainDrop)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut transform, mut drop) in &mut drops {
+        drop.lifetime -= delta;
+        if drop.lifetime <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (RAIN_VELOCITY * delta).extend(0.0);
+    }
+
+    let intensity = current_weather(&render, &zones)
+        .filter(|zone| zone.weather.is_wet())
+        .map(|zone| zone.intensity)
+        .unwrap_or(0.0);
+    let Ok(camera) = camera.single() else { return };
+    if intensity <= 0.0 {
+        spawner.pending = 0.0;
+        return;
+    }
+
+    spawner.pending += RAIN_RATE * intensity * delta;
+    let count = spawner.pending as u32;
+    spawner.pending -= count as f32;
+
+    let drop_color = Color::srgba(0.7, 0.8, 1.0, 0.35 + 0.3 * intensity);
+    for _ in 0..count {
+        // Start above the view so drops fall into it
+        let offset = Vec2::new(
+            spawner.range(-RAIN_AREA.x, RAIN_AREA.x),
+            spawner.range(-RAIN_AREA.y, RAIN_AREA.y) + RAIN_AREA.y * 0.5,
+        );
+        let lifetime = spawner.range(RAIN_LIFETIME * 0.5, RAIN_LIFETIME);
+        let position = camera.translation.truncate() + offset;
+        commands.spawn((
+            RainDrop { lifetime },
+            Sprite::from_color(drop_color, Vec2::new(1.5, 10.0)),
+            Transform::from_translation(position.extend(500.0))
+                .with_rotation(Quat::from_rotation_z(RAIN_VELOCITY.to_angle() - std::f32::consts::FRAC_PI_2)),
+        ));
+    }
+}
+
+/// Remove raindrops when leaving the game
+pub fn cleanup_rain(mut commands: Commands, drops: Query<Entity, With<RainDrop>>) {
+    for entity in &drops {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,115 @@
+//! Crowd control diminishing returns.
+//!
+//! Stuns, roots and silences each have their own diminishing returns category. Every
+//! application within `DR_RESET_SECONDS` of the previous one lasts a fraction of the one
+//! before (full, half, quarter), after which the target is immune until the timer runs out.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use std::collections::HashMap;
+use crate::combat_log::CombatLogRecord;
+use super::add_debuff;
+
+/// Seconds without a new application before a category resets to full duration
+pub const DR_RESET_SECONDS: f32 = 18.0;
+/// Duration multipliers for consecutive applications; anything past the last is resisted
+const DR_MULTIPLIERS: [f32; 3] = [1.0, 0.5, 0.25];
+
+/// Crowd control effects that share diminishing returns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CcCategory {
+    Stun,
+    Root,
+    Silence,
+}
+
+impl CcCategory {
+    /// Slows and weakens aren't crowd control and never diminish
+    pub fn of(effect: &DebuffType) -> Option<Self> {
+        match effect {
+            DebuffType::Stun => Some(Self::Stun),
+            DebuffType::Root => Some(Self::Root),
+            DebuffType::Silence => Some(Self::Silence),
+            DebuffType::Slow { .. } | DebuffType::Weaken { .. } => None,
+        }
+    }
+}
+
+/// Recent crowd control applications on an entity
+#[derive(Component, Default, Debug)]
+pub struct DiminishingReturns {
+    /// Applications so far and when the category resets
+    categories: HashMap<CcCategory, (usize, f32)>,
+}
+
+impl DiminishingReturns {
+    /// Record an application and return its diminished duration, or None if the target is immune
+    pub fn apply(&mut self, category: CcCategory, duration: f32, now: f32) -> Option<f32> {
+        let (applications, reset_at) = self.categories.entry(category).or_insert((0, 0.0));
+        if now >= *reset_at {
+            *applications = 0;
+        }
+
+        let multiplier = DR_MULTIPLIERS.get(*applications).copied()?;
+        *applications += 1;
+        *reset_at = now + DR_RESET_SECONDS;
+        Some(duration * multiplier)
+    }
+}
+
+/// Apply a debuff from `caster` to `target`, shortening crowd control by its diminishing returns
+pub fn apply_debuff(commands: &mut Commands, caster: Entity, target: Entity, ability_id: u32, effect: DebuffType, duration: f32, now: f32) {
+    commands.entity(target).queue(move |mut entity: EntityWorldMut| {
+        let log = |entity: &mut EntityWorldMut, effect: DebuffType, duration: Option<f32>| {
+            let record = CombatLogRecord { source: caster, target, ability_id, entry: CombatLogEntry::Debuff { effect, duration } };
+            entity.world_scope(|world| world.trigger(record));
+        };
+
+        let duration = match CcCategory::of(&effect) {
+            Some(category) => {
+                let diminished = match entity.get_mut::<DiminishingReturns>() {
+                    Some(mut dr) => dr.apply(category, duration, now),
+                    None => {
+                        let mut dr = DiminishingReturns::default();
+                        let diminished = dr.apply(category, duration, now);
+                        entity.insert(dr);
+                        diminished
+                    }
+                };
+                let Some(diminished) = diminished else {
+                    info!("{:?} is immune to {:?} (diminishing returns)", entity.id(), category);
+                    log(&mut entity, effect, None);
+                    return;
+                };
+                diminished
+            }
+            None => duration,
+        };
+        log(&mut entity, effect.clone(), Some(duration));
+        add_debuff(&mut entity, ability_id, effect, duration, now);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_stuns_diminish_to_immunity() {
+        let mut dr = DiminishingReturns::default();
+        assert_eq!(dr.apply(CcCategory::Stun, 4.0, 0.0), Some(4.0));
+        assert_eq!(dr.apply(CcCategory::Stun, 4.0, 5.0), Some(2.0));
+        assert_eq!(dr.apply(CcCategory::Stun, 4.0, 10.0), Some(1.0));
+        assert_eq!(dr.apply(CcCategory::Stun, 4.0, 15.0), None);
+        // Roots are tracked separately
+        assert_eq!(dr.apply(CcCategory::Root, 4.0, 15.0), Some(4.0));
+    }
+
+    #[test]
+    fn categories_reset_after_a_quiet_period() {
+        let mut dr = DiminishingReturns::default();
+        dr.apply(CcCategory::Silence, 3.0, 0.0);
+        dr.apply(CcCategory::Silence, 3.0, 1.0);
+        assert_eq!(dr.apply(CcCategory::Silence, 3.0, 1.0 + DR_RESET_SECONDS), Some(3.0));
+    }
+}
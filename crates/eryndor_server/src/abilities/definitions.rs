@@ -84,7 +84,7 @@ pub fn create_knight_abilities() -> Vec<AbilityDefinition> {
         AbilityDefinition {
             id: ABILITY_TAUNT,
             name: "Taunt".to_string(),
-            description: "Taunt nearby enemies, reducing their attack power.".to_string(),
+            description: "Taunt nearby enemies, forcing them to attack you and reducing their attack power.".to_string(),
             damage_multiplier: 0.0,
             cooldown: 12.0,
             range: 5.0,
@@ -93,11 +93,14 @@ pub fn create_knight_abilities() -> Vec<AbilityDefinition> {
                 AbilityType::AreaOfEffect {
                     radius: 5.0,
                     max_targets: 5,
+                    shape: AoeShape::Circle,
+                    placement: AoePlacement::Caster,
                 },
                 AbilityType::Debuff {
                     duration: 6.0,
                     effect: DebuffType::Weaken { attack_reduction: 0.3 },
                 },
+                AbilityType::Taunt { duration: 6.0 },
             ],
             unlock_requirement: AbilityUnlockRequirement::Level(5),
         },
@@ -116,6 +119,8 @@ pub fn create_knight_abilities() -> Vec<AbilityDefinition> {
                 AbilityType::AreaOfEffect {
                     radius: 2.0,
                     max_targets: 3,
+                    shape: AoeShape::Cone { angle: 120.0 },
+                    placement: AoePlacement::Caster,
                 },
             ],
             unlock_requirement: AbilityUnlockRequirement::Level(7),
@@ -185,6 +190,7 @@ pub fn create_mage_abilities() -> Vec<AbilityDefinition> {
             mana_cost: 25.0,
             ability_types: vec![
                 AbilityType::DirectDamage { multiplier: 1.5 },
+                AbilityType::Projectile { speed: 10.0, radius: 0.4 },
             ],
             unlock_requirement: AbilityUnlockRequirement::None,
         },
@@ -204,6 +210,7 @@ pub fn create_mage_abilities() -> Vec<AbilityDefinition> {
                     duration: 4.0,
                     effect: DebuffType::Slow { move_speed_reduction: 0.5 },
                 },
+                AbilityType::Projectile { speed: 14.0, radius: 0.3 },
             ],
             unlock_requirement: AbilityUnlockRequirement::Level(3),
         },
@@ -242,6 +249,8 @@ pub fn create_mage_abilities() -> Vec<AbilityDefinition> {
                 AbilityType::AreaOfEffect {
                     radius: 5.0,
                     max_targets: 8,
+                    shape: AoeShape::Circle,
+                    placement: AoePlacement::Caster,
                 },
             ],
             unlock_requirement: AbilityUnlockRequirement::Level(7),
@@ -385,6 +394,8 @@ pub fn create_rogue_abilities() -> Vec<AbilityDefinition> {
                 AbilityType::AreaOfEffect {
                     radius: 4.0,
                     max_targets: 5,
+                    shape: AoeShape::Circle,
+                    placement: AoePlacement::Ground,
                 },
                 AbilityType::Debuff {
                     duration: 3.0,
@@ -16,6 +16,7 @@ pub fn process_buffs_and_debuffs(
     )>,
     time: Res<Time>,
 ) {
+    let _timer = crate::metrics::time_system("abilities::process_buffs_and_debuffs");
     let current_time = time.elapsed().as_secs_f32();
 
     for (active_buffs, active_debuffs, base_stats, mut stats, mut move_speed) in &mut query {
@@ -57,6 +58,8 @@ pub fn process_buffs_and_debuffs(
                     DebuffType::Stun | DebuffType::Root => {
                         is_rooted = true;
                     },
+                    // Enforced where abilities are used
+                    DebuffType::Silence => {},
                 }
             }
 
@@ -72,29 +75,16 @@ pub fn process_buffs_and_debuffs(
     }
 }
 
-/// Add a buff to an entity
-pub fn add_buff(
-    entity: &mut EntityWorldMut,
-    ability_id: u32,
-    stat_bonuses: StatBonuses,
-    duration: f32,
-    current_time: f32,
-) {
-    let buff = ActiveBuff {
-        ability_id,
-        stat_bonuses,
-        expires_at: current_time + duration,
-    };
-
+/// Add a buff to an entity, refreshing any earlier buff from the same ability or item
+pub fn add_buff(entity: &mut EntityWorldMut, buff: ActiveBuff) {
     if let Some(mut active_buffs) = entity.get_mut::<ActiveBuffs>() {
+        active_buffs.buffs.retain(|existing| existing.ability_id != buff.ability_id || existing.item_id != buff.item_id);
         active_buffs.buffs.push(buff);
-        info!("Added buff from ability {} to entity {:?}", ability_id, entity.id());
     } else {
         // Entity doesn't have ActiveBuffs component, add it
         entity.insert(ActiveBuffs {
             buffs: vec![buff],
         });
-        info!("Created ActiveBuffs and added buff from ability {} to entity {:?}", ability_id, entity.id());
     }
 }
 
@@ -133,6 +123,7 @@ pub fn process_dots(
     mut query: Query<(Entity, &mut ActiveDoTs, &mut Health)>,
     time: Res<Time>,
 ) {
+    let _timer = crate::metrics::time_system("abilities::process_dots");
     let current_time = time.elapsed().as_secs_f32();
 
     for (entity, mut active_dots, mut health) in &mut query {
@@ -145,6 +136,17 @@ pub fn process_dots(
                 health.current -= dot.damage_per_tick;
                 info!("DoT tick: {} damage to entity {:?} ({} HP remaining)",
                     dot.damage_per_tick, entity, health.current);
+                commands.trigger(crate::threat::ThreatEvent {
+                    enemy: entity,
+                    source: dot.caster,
+                    amount: dot.damage_per_tick,
+                });
+                commands.trigger(crate::combat_log::CombatLogRecord {
+                    source: dot.caster,
+                    target: entity,
+                    ability_id: dot.ability_id,
+                    entry: CombatLogEntry::Periodic { amount: dot.damage_per_tick },
+                });
 
                 // Update tick counter
                 dot.ticks_remaining = dot.ticks_remaining.saturating_sub(1);
@@ -1,7 +1,13 @@
 mod definitions;
 mod database;
 pub mod effects;
+mod targeting;
+mod projectiles;
+mod crowd_control;
 
 pub use definitions::*;
 pub use database::*;
 pub use effects::*;
+pub use targeting::*;
+pub use projectiles::*;
+pub use crowd_control::*;
@@ -0,0 +1,178 @@
+//! Projectile abilities.
+//!
+//! Abilities with a `Projectile` effect don't land on cast. `handle_use_ability` launches a
+//! replicated projectile toward where the target stood, moved by Avian as a kinematic body.
+//! `update_projectiles` checks it for overlaps every frame and applies the ability's target
+//! effects to the first enemy (or player the caster may fight) it reaches. Walls stop it, and so does flying past the ability's range.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use avian2d::prelude::{Collider, LinearVelocity, Position as PhysicsPosition, RigidBody, SpatialQuery, SpatialQueryFilter};
+use eryndor_shared::*;
+use crate::combat_formulas::{AttackerProfile, CombatFormulas, DefenderProfile};
+use crate::combat_log::CombatLogRecord;
+use crate::pvp::{self, PvpStandingQuery};
+use crate::threat::{TauntEvent, ThreatEvent};
+use super::{apply_debuff, AbilityDatabase, AoeArea};
+
+const PROJECTILE_COLOR: [f32; 4] = [1.0, 0.6, 0.2, 1.0];
+
+/// Server-side flight data for a projectile
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ProjectileFlight {
+    pub caster: Entity,
+    /// Caster's offensive stats when the projectile was launched
+    pub attacker: AttackerProfile,
+    /// Collision radius in pixels
+    pub radius: f32,
+    pub expires_at: f32,
+}
+
+/// Spawn a projectile at `from` travelling with `velocity` (pixels per second)
+pub fn spawn_projectile(commands: &mut Commands, ability_id: u32, flight: ProjectileFlight, from: Vec2, velocity: Vec2) -> Entity {
+    commands.spawn((
+        Replicated,
+        Projectile { ability_id },
+        Position(from),
+        Velocity(velocity),
+        VisualShape {
+            shape_type: ShapeType::Circle,
+            color: PROJECTILE_COLOR,
+            size: flight.radius * 2.0,
+        },
+        flight,
+        PhysicsPosition(from),
+        LinearVelocity(velocity),
+        RigidBody::Kinematic,
+    )).id()
+}
+
+/// Resolve projectile impacts and expire projectiles that flew out of range
+pub fn update_projectiles(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &PhysicsPosition, &LinearVelocity, &Projectile, &ProjectileFlight)>,
+    mut enemies: Query<(&Position, &mut Health, &CombatStats), (With<Enemy>, Without<AiActivationDelay>)>,
+    players: Query<(&Position, &CombatStats), (With<Player>, Without<Enemy>, Without<Dead>, Without<Ghost>)>,
+    standings: Query<PvpStandingQuery>,
+    spatial_query: SpatialQuery,
+    ability_db: Res<AbilityDatabase>,
+    formulas: Res<CombatFormulas>,
+    time: Res<Time>,
+) {
+    let _timer = crate::metrics::time_system("abilities::update_projectiles");
+    let now = time.elapsed_secs();
+    let mut rng = rand::thread_rng();
+
+    for (entity, position, velocity, projectile, flight) in &projectiles {
+        if now >= flight.expires_at {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // Position and stats of something the caster may hit
+        let hostile = |target: Entity| -> Option<(Vec2, CombatStats)> {
+            if let Ok((target_pos, _, stats)) = enemies.get(target) {
+                return Some((target_pos.0, *stats));
+            }
+            if !pvp::can_attack(&standings, flight.caster, target) {
+                return None;
+            }
+            players.get(target).ok().map(|(target_pos, stats)| (target_pos.0, *stats))
+        };
+
+        let shape = Collider::circle(flight.radius);
+        let target_filter = SpatialQueryFilter::from_mask([GameLayer::Enemy, GameLayer::Player]).with_excluded_entities([flight.caster]);
+        let hit = spatial_query.shape_intersections(&shape, position.0, 0.0, &target_filter)
+            .into_iter()
+            .filter_map(|hit| hostile(hit).map(|(hit_pos, _)| (hit, hit_pos)))
+            .min_by(|a, b| a.1.distance(position.0).total_cmp(&b.1.distance(position.0)));
+
+        let Some((hit_entity, _)) = hit else {
+            // Fizzle against walls
+            let wall_filter = SpatialQueryFilter::from_mask(GameLayer::Environment);
+            if !spatial_query.shape_intersections(&shape, position.0, 0.0, &wall_filter).is_empty() {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        commands.entity(entity).despawn();
+
+        let Some(ability) = ability_db.get(projectile.ability_id) else { continue };
+
+        // Projectiles with an area of effect burst around the impact point
+        let mut targets = vec![hit_entity];
+        if let Some((radius, max_targets)) = ability.ability_types.iter().find_map(|ability_type| match ability_type {
+            AbilityType::AreaOfEffect { radius, max_targets, .. } => Some((*radius, *max_targets)),
+            _ => None,
+        }) {
+            let area = AoeArea {
+                shape: AoeShape::Circle,
+                origin: position.0,
+                direction: velocity.0.normalize_or(Vec2::X),
+                radius: radius * PIXELS_PER_METER,
+                scale: PIXELS_PER_METER,
+            };
+            let mut splash: Vec<(Entity, f32)> = area.overlapping(&spatial_query, [GameLayer::Enemy, GameLayer::Player], flight.caster)
+                .into_iter()
+                .filter(|e| *e != hit_entity)
+                .filter_map(|e| hostile(e).map(|(pos, _)| (e, pos.distance(position.0))))
+                .collect();
+            splash.sort_by(|a, b| a.1.total_cmp(&b.1));
+            targets.extend(splash.into_iter().map(|(e, _)| e).take((max_targets as usize).saturating_sub(1)));
+        }
+
+        let targets: Vec<(Entity, Vec2, CombatStats)> = targets.into_iter()
+            .filter_map(|target| hostile(target).map(|(target_pos, stats)| (target, target_pos, stats)))
+            .collect();
+
+        for (target, target_pos, stats) in targets {
+
+            for ability_type in &ability.ability_types {
+                match ability_type {
+                    AbilityType::DirectDamage { multiplier } => {
+                        let outcome = formulas.resolve_attack(&flight.attacker, &DefenderProfile::from_stats(&stats), *multiplier, &mut rng);
+                        if let Ok((_, mut health, _)) = enemies.get_mut(target) {
+                            health.current = (health.current - outcome.damage).max(0.0);
+                        } else {
+                            pvp::damage_player(&mut commands, target, outcome.damage);
+                        }
+                        commands.trigger(ThreatEvent { enemy: target, source: flight.caster, amount: outcome.damage });
+                        commands.trigger(CombatLogRecord::attack(flight.caster, target, ability.id, &outcome, 0.0));
+                        commands.server_trigger(ToClients {
+                            mode: SendMode::Broadcast,
+                            message: CombatEvent {
+                                attacker_position: position.0,
+                                target_position: target_pos,
+                                damage: outcome.damage,
+                                ability_id: ability.id,
+                                is_crit: outcome.result == AttackResult::Crit,
+                                result: outcome.result,
+                            },
+                        });
+                        info!("Projectile {} hit {:?} for {:.1} damage ({:?})", ability.name, target, outcome.damage, outcome.result);
+                    }
+                    AbilityType::DamageOverTime { duration: _, ticks, damage_per_tick } => {
+                        commands.entity(target).insert(ActiveDoTs {
+                            dots: vec![ActiveDoT {
+                                ability_id: ability.id,
+                                caster: flight.caster,
+                                damage_per_tick: *damage_per_tick,
+                                ticks_remaining: *ticks,
+                                next_tick_at: now + 1.0,
+                            }],
+                        });
+                    }
+                    AbilityType::Debuff { duration, effect } => {
+                        apply_debuff(&mut commands, flight.caster, target, ability.id, effect.clone(), *duration, now);
+                        commands.trigger(ThreatEvent { enemy: target, source: flight.caster, amount: 0.0 });
+                    }
+                    AbilityType::Taunt { duration } => {
+                        commands.trigger(TauntEvent { enemy: target, taunter: flight.caster, duration: *duration });
+                    }
+                    // Caster-side effects already applied at launch
+                    _ => {}
+                }
+            }
+        }
+    }
+}
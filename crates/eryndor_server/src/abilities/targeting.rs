@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use avian2d::prelude::{Collider, LayerMask, SpatialQuery, SpatialQueryFilter};
+use eryndor_shared::AoeShape;
+
+/// A placed area-of-effect, in world pixels
+#[derive(Clone, Copy, Debug)]
+pub struct AoeArea {
+    pub shape: AoeShape,
+    pub origin: Vec2,
+    /// Unit vector cones and lines extend along
+    pub direction: Vec2,
+    /// Circle radius, or cone/line reach
+    pub radius: f32,
+    /// Pixels per meter, for line widths
+    pub scale: f32,
+}
+
+impl AoeArea {
+    /// Exact point-in-shape test; the physics query only narrows candidates for cones
+    pub fn contains(&self, point: Vec2) -> bool {
+        let offset = point - self.origin;
+        match self.shape {
+            AoeShape::Circle => offset.length() <= self.radius,
+            AoeShape::Cone { angle } => {
+                if offset.length() > self.radius {
+                    return false;
+                }
+                if offset.length_squared() < f32::EPSILON {
+                    return true;
+                }
+                offset.angle_to(self.direction).abs() <= (angle / 2.0).to_radians()
+            }
+            AoeShape::Line { width } => {
+                let along = offset.dot(self.direction);
+                let across = offset.perp_dot(self.direction).abs();
+                (0.0..=self.radius).contains(&along) && across <= width * self.scale / 2.0
+            }
+        }
+    }
+
+    /// Collider and pose covering the shape
+    fn query_shape(&self) -> (Collider, Vec2, f32) {
+        match self.shape {
+            AoeShape::Circle | AoeShape::Cone { .. } => (Collider::circle(self.radius), self.origin, 0.0),
+            AoeShape::Line { width } => (
+                Collider::rectangle(self.radius, width * self.scale),
+                self.origin + self.direction * self.radius / 2.0,
+                self.direction.to_angle(),
+            ),
+        }
+    }
+
+    /// Entities on the given layers whose colliders overlap the area, excluding `caster`
+    pub fn overlapping(&self, spatial_query: &SpatialQuery, layers: impl Into<LayerMask>, caster: Entity) -> Vec<Entity> {
+        let (shape, position, rotation) = self.query_shape();
+        let filter = SpatialQueryFilter::from_mask(layers).with_excluded_entities([caster]);
+        spatial_query.shape_intersections(&shape, position, rotation, &filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(shape: AoeShape) -> AoeArea {
+        AoeArea { shape, origin: Vec2::ZERO, direction: Vec2::X, radius: 100.0, scale: 20.0 }
+    }
+
+    #[test]
+    fn cone_only_covers_its_arc() {
+        let cone = area(AoeShape::Cone { angle: 90.0 });
+        assert!(cone.contains(Vec2::new(50.0, 40.0)));
+        assert!(!cone.contains(Vec2::new(50.0, 60.0)));
+        assert!(!cone.contains(Vec2::new(-50.0, 0.0)));
+        assert!(!cone.contains(Vec2::new(120.0, 0.0)));
+    }
+
+    #[test]
+    fn line_extends_forward_only() {
+        let line = area(AoeShape::Line { width: 1.0 });
+        assert!(line.contains(Vec2::new(90.0, 9.0)));
+        assert!(!line.contains(Vec2::new(90.0, 11.0)));
+        assert!(!line.contains(Vec2::new(-5.0, 0.0)));
+    }
+}
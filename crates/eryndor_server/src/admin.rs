@@ -9,7 +9,10 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::database::DatabaseConnection;
 use crate::auth::{Authenticated, ClientMetadata};
-use sqlx::{SqlitePool, Row};
+use crate::bans::{disconnect_banned_clients, BanMatch};
+use crate::social::notify;
+use sqlx::Row;
+use crate::database::DbPool;
 
 /// Admin command parsing result
 pub enum AdminCommand {
@@ -18,6 +21,12 @@ pub enum AdminCommand {
         duration: Option<i64>,  // Duration in seconds, None = permanent
         reason: String,
     },
+    /// Ban an IP address, or an account together with its last IP and device
+    BanIp {
+        target: String,
+        duration: Option<i64>,  // Duration in seconds, None = permanent
+        reason: String,
+    },
     Unban {
         username: String,
     },
@@ -28,6 +37,20 @@ pub enum AdminCommand {
     Broadcast {
         message: String,
     },
+    Mute {
+        character_name: String,
+        duration: Option<i64>,  // Duration in seconds, None = permanent
+        reason: String,
+    },
+    Unmute {
+        character_name: String,
+    },
+    Trace {
+        start: bool,
+    },
+    ResetTwoFactor {
+        username: String,
+    },
     Help,
     Invalid(String),
 }
@@ -53,22 +76,34 @@ pub fn parse_command(command: String) -> AdminCommand {
             }
 
             let username = parts[1].to_string();
-            let duration_str = parts[2];
             let reason = parts.get(3..).map(|p| p.join(" ")).unwrap_or_else(|| "No reason provided".to_string());
 
             // Parse duration: "perm" = permanent, "1h" = 1 hour, "30m" = 30 minutes, "7d" = 7 days
-            let duration = if duration_str == "perm" {
-                None  // Permanent ban
-            } else {
-                parse_duration(duration_str)
+            let duration = match parse_ban_duration(parts[2]) {
+                Ok(duration) => duration,
+                Err(e) => return AdminCommand::Invalid(e),
             };
 
             AdminCommand::Ban { username, duration, reason }
         }
 
+        "/banip" => {
+            if parts.len() < 3 {
+                return AdminCommand::Invalid("/banip usage: /banip <username|ip> <duration|perm> [reason]".to_string());
+            }
+
+            let duration = match parse_ban_duration(parts[2]) {
+                Ok(duration) => duration,
+                Err(e) => return AdminCommand::Invalid(e),
+            };
+            let reason = parts.get(3..).map(|p| p.join(" ")).unwrap_or_else(|| "No reason provided".to_string());
+
+            AdminCommand::BanIp { target: parts[1].to_string(), duration, reason }
+        }
+
         "/unban" => {
             if parts.len() < 2 {
-                return AdminCommand::Invalid("/unban usage: /unban <username>".to_string());
+                return AdminCommand::Invalid("/unban usage: /unban <username|ip|#ban_id>".to_string());
             }
             AdminCommand::Unban {
                 username: parts[1].to_string(),
@@ -93,6 +128,50 @@ pub fn parse_command(command: String) -> AdminCommand {
             AdminCommand::Broadcast { message }
         }
 
+        "/mute" => {
+            if parts.len() < 3 {
+                return AdminCommand::Invalid("/mute usage: /mute <character_name> <duration|perm> [reason]".to_string());
+            }
+
+            let duration = if parts[2] == "perm" {
+                None
+            } else {
+                match parse_duration(parts[2]) {
+                    Some(seconds) => Some(seconds),
+                    None => return AdminCommand::Invalid(format!("Invalid mute duration: {}", parts[2])),
+                }
+            };
+            let reason = parts.get(3..).map(|p| p.join(" ")).unwrap_or_else(|| "No reason provided".to_string());
+
+            AdminCommand::Mute { character_name: parts[1].to_string(), duration, reason }
+        }
+
+        "/unmute" => {
+            if parts.len() < 2 {
+                return AdminCommand::Invalid("/unmute usage: /unmute <character_name>".to_string());
+            }
+            AdminCommand::Unmute {
+                character_name: parts[1].to_string(),
+            }
+        }
+
+        "/trace" => {
+            match parts.get(1) {
+                Some(&"start") => AdminCommand::Trace { start: true },
+                Some(&"stop") => AdminCommand::Trace { start: false },
+                _ => AdminCommand::Invalid("/trace usage: /trace <start|stop>".to_string()),
+            }
+        }
+
+        "/reset2fa" => {
+            if parts.len() < 2 {
+                return AdminCommand::Invalid("/reset2fa usage: /reset2fa <username>".to_string());
+            }
+            AdminCommand::ResetTwoFactor {
+                username: parts[1].to_string(),
+            }
+        }
+
         "/help" => {
             AdminCommand::Help
         }
@@ -129,9 +208,20 @@ fn parse_duration(duration_str: &str) -> Option<i64> {
     }
 }
 
+/// Ban duration in seconds, None for "perm". A typo must not turn into a permanent ban.
+fn parse_ban_duration(duration_str: &str) -> Result<Option<i64>, String> {
+    if duration_str == "perm" {
+        return Ok(None);
+    }
+    match parse_duration(duration_str) {
+        Some(seconds) => Ok(Some(seconds)),
+        None => Err(format!("Invalid ban duration: {}", duration_str)),
+    }
+}
+
 /// Check if account has admin permissions
-pub async fn is_admin(pool: &SqlitePool, account_id: i64) -> Result<bool, String> {
-    let result = sqlx::query("SELECT is_admin FROM accounts WHERE id = ?1")
+pub async fn is_admin(pool: &DbPool, account_id: i64) -> Result<bool, String> {
+    let result = sqlx::query("SELECT is_admin FROM accounts WHERE id = $1")
         .bind(account_id)
         .fetch_optional(pool)
         .await;
@@ -154,8 +244,14 @@ pub fn get_help_text() -> String {
   Examples: /ban john123 1h spam
            /ban PlayerName perm harassment
 
-/unban <username> - Remove ban from player
-  Example: /unban john123
+/banip <username|character|ip> <duration|perm> [reason] - Ban an IP address, or a
+  player along with the IP and device they last logged in from
+  Examples: /banip 203.0.113.7 7d botting
+           /banip john123 perm ban evasion
+
+/unban <username|ip|#ban_id> - Lift bans on a player or IP, or one ban by id
+  Examples: /unban john123
+           /unban #42
 
 /kick <character_name> [reason] - Kick player from server
   Example: /kick PlayerName disruptive behavior
@@ -163,6 +259,18 @@ pub fn get_help_text() -> String {
 /broadcast <message> - Send server-wide message
   Example: /broadcast Server restart in 5 minutes
 
+/mute <character_name> <duration|perm> [reason] - Stop a player from chatting
+  Example: /mute PlayerName 30m spamming global
+
+/unmute <character_name> - Lift a chat mute
+  Example: /unmute PlayerName
+
+/trace <start|stop> - Record a Chrome trace of server systems
+  Open the written file in chrome://tracing or Perfetto
+
+/reset2fa <username> - Turn off two-factor authentication for an account
+  Example: /reset2fa john123
+
 /help - Show this help message
 
 Duration formats: m=minutes, h=hours, d=days, w=weeks, perm=permanent
@@ -201,6 +309,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_ban_rejects_bad_duration() {
+        assert!(matches!(parse_command("/ban baduser 1x spam".to_string()), AdminCommand::Invalid(_)));
+    }
+
+    #[test]
+    fn test_parse_ban_ip_command() {
+        match parse_command("/banip 203.0.113.7 7d botting".to_string()) {
+            AdminCommand::BanIp { target, duration, reason } => {
+                assert_eq!(target, "203.0.113.7");
+                assert_eq!(duration, Some(604800));
+                assert_eq!(reason, "botting");
+            }
+            _ => panic!("Expected BanIp command"),
+        }
+        assert!(matches!(parse_command("/banip john123".to_string()), AdminCommand::Invalid(_)));
+    }
+
     #[test]
     fn test_parse_duration() {
         assert_eq!(parse_duration("30m"), Some(1800));
@@ -209,6 +335,36 @@ mod tests {
         assert_eq!(parse_duration("invalid"), None);
     }
 
+    #[test]
+    fn test_parse_mute_command() {
+        match parse_command("/mute Spammer 30m flooding global".to_string()) {
+            AdminCommand::Mute { character_name, duration, reason } => {
+                assert_eq!(character_name, "Spammer");
+                assert_eq!(duration, Some(1800));
+                assert_eq!(reason, "flooding global");
+            }
+            _ => panic!("Expected Mute command"),
+        }
+
+        assert!(matches!(parse_command("/mute Spammer soon".to_string()), AdminCommand::Invalid(_)));
+    }
+
+    #[test]
+    fn test_parse_trace_command() {
+        assert!(matches!(parse_command("/trace start".to_string()), AdminCommand::Trace { start: true }));
+        assert!(matches!(parse_command("/trace stop".to_string()), AdminCommand::Trace { start: false }));
+        assert!(matches!(parse_command("/trace".to_string()), AdminCommand::Invalid(_)));
+    }
+
+    #[test]
+    fn test_parse_reset_two_factor_command() {
+        match parse_command("/reset2fa john123".to_string()) {
+            AdminCommand::ResetTwoFactor { username } => assert_eq!(username, "john123"),
+            _ => panic!("Expected ResetTwoFactor command"),
+        }
+        assert!(matches!(parse_command("/reset2fa".to_string()), AdminCommand::Invalid(_)));
+    }
+
     #[test]
     fn test_invalid_command() {
         let cmd = parse_command("/unknown".to_string());
@@ -228,9 +384,7 @@ pub fn handle_admin_command(
     trigger: On<FromClient<AdminCommandRequest>>,
     mut commands: Commands,
     client_query: Query<(&Authenticated, Option<&ClientMetadata>)>,
-    characters: Query<(Entity, &Character, &OwnedBy)>,
     db: Res<DatabaseConnection>,
-    tokio_runtime: Res<crate::TokioRuntimeResource>,
 ) {
     let Some(client_entity) = trigger.client_id.entity() else {
         warn!("No client entity in admin command trigger");
@@ -250,38 +404,98 @@ pub fn handle_admin_command(
         return;
     };
 
-    let account_id = auth.account_id;
-    let ip_address = metadata_opt.map(|m| m.ip_address.to_string());
+    let request = AdminRequest {
+        client_entity,
+        account_id: auth.account_id,
+        ip_address: metadata_opt.map(|m| m.ip_address.to_string()),
+        command: trigger.event().command.clone(),
+    };
 
     // Check if user has admin permissions
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         error!("Database not available for admin command");
         return;
-    };
+    }
+
+    let account_id = request.account_id;
+    db.run(
+        move |pool| async move { is_admin(&pool, account_id).await },
+        move |is_admin_result, world| {
+            if let Err(e) = world.run_system_cached_with(run_admin_command, (request, is_admin_result)) {
+                error!("Failed to run admin command: {}", e);
+            }
+        },
+    );
+}
+
+/// An admin command waiting on the permission check
+struct AdminRequest {
+    client_entity: Entity,
+    account_id: i64,
+    ip_address: Option<String>,
+    command: String,
+}
+
+/// Queue an audit log entry for something an admin did
+fn audit(
+    db: &DatabaseConnection,
+    action: crate::audit::AuditActionType,
+    account_id: i64,
+    target: Option<String>,
+    ip_address: Option<String>,
+    details: String,
+) {
+    db.execute("log_audit_event", move |pool| async move {
+        crate::audit::log_audit_event(
+            &pool,
+            action,
+            Some(account_id),
+            None,
+            target.as_deref(),
+            ip_address.as_deref(),
+            Some(&details),
+        ).await
+    });
+}
+
+/// Online character with this name, as (character, owning client)
+fn find_online_character(world: &mut World, name: &str) -> Option<(Entity, Entity)> {
+    world.query::<(Entity, &Character, &OwnedBy)>()
+        .iter(world)
+        .find(|(_, character, _)| character.name.eq_ignore_ascii_case(name))
+        .map(|(entity, _, owned_by)| (entity, owned_by.0))
+}
 
-    // Use shared tokio runtime instead of creating a new one each time
-    let is_admin_result = tokio_runtime.0.block_on(is_admin(pool, account_id));
+fn run_admin_command(
+    In((request, is_admin_result)): In<(AdminRequest, Result<bool, String>)>,
+    mut commands: Commands,
+    characters: Query<(Entity, &Character, &OwnedBy)>,
+    db: Res<DatabaseConnection>,
+    config: Res<crate::config::ServerConfig>,
+    relay: Res<crate::relay::Relay>,
+) {
+    let AdminRequest { client_entity, account_id, ip_address, command } = request;
+    let client_id = ClientId::Client(client_entity);
 
     match is_admin_result {
         Ok(true) => {
             // User is admin, proceed with command
         }
         Ok(false) => {
-            warn!("Non-admin account {} attempted admin command: {}", account_id, trigger.event().command);
+            warn!("Non-admin account {} attempted admin command: {}", account_id, command);
 
             // AUDIT LOG: Unauthorized admin command attempt
-            let _ = tokio_runtime.0.block_on(crate::audit::log_audit_event(
-                pool,
+            audit(
+                &db,
                 crate::audit::AuditActionType::SuspiciousActivity,
-                Some(account_id),
-                None,
+                account_id,
                 None,
-                ip_address.as_deref(),
-                Some(&format!("attempted admin command: {}", trigger.event().command)),
-            ));
+                ip_address,
+                format!("attempted admin command: {}", command),
+            );
 
             commands.server_trigger(ToClients {
-                mode: SendMode::Direct(trigger.client_id),
+                mode: SendMode::Direct(client_id),
                 message: NotificationEvent {
                     message: "You do not have permission to use admin commands".to_string(),
                     notification_type: NotificationType::Error,
@@ -296,13 +510,13 @@ pub fn handle_admin_command(
     }
 
     // Parse the command
-    let command = parse_command(trigger.event().command.clone());
+    let command = parse_command(command);
 
     match command {
         AdminCommand::Help => {
             let help_text = get_help_text();
             commands.server_trigger(ToClients {
-                mode: SendMode::Direct(trigger.client_id),
+                mode: SendMode::Direct(client_id),
                 message: NotificationEvent {
                     message: help_text,
                     notification_type: NotificationType::Info,
@@ -314,79 +528,50 @@ pub fn handle_admin_command(
             info!("Admin {} executing ban command: username={}, duration={:?}, reason={}",
                   account_id, username, duration, reason);
 
-            let result = tokio_runtime.0.block_on(execute_ban(pool, &username, duration, &reason, account_id));
+            db.run(
+                move |pool| async move { execute_ban(&pool, &username, duration, &reason, account_id).await },
+                move |result, world| ban_issued(world, result, client_entity, account_id, ip_address),
+            );
+        }
 
-            match result {
-                Ok(message) => {
-                    // AUDIT LOG: Account banned
-                    let _ = tokio_runtime.0.block_on(crate::audit::log_audit_event(
-                        pool,
-                        crate::audit::AuditActionType::AdminCommandExecuted,
-                        Some(account_id),
-                        None,
-                        Some(&username),
-                        ip_address.as_deref(),
-                        Some(&format!("banned user: {} for {} (duration: {:?})", username, reason, duration)),
-                    ));
-
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: NotificationEvent {
-                            message,
-                            notification_type: NotificationType::Success,
-                        },
-                    });
-                }
-                Err(e) => {
-                    error!("Ban command failed: {}", e);
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: NotificationEvent {
-                            message: format!("Ban failed: {}", e),
-                            notification_type: NotificationType::Error,
-                        },
-                    });
-                }
-            }
+        AdminCommand::BanIp { target, duration, reason } => {
+            info!("Admin {} executing banip command: target={}, duration={:?}, reason={}",
+                  account_id, target, duration, reason);
+
+            db.run(
+                move |pool| async move { execute_ban_ip(&pool, &target, duration, &reason, account_id).await },
+                move |result, world| ban_issued(world, result, client_entity, account_id, ip_address),
+            );
         }
 
         AdminCommand::Unban { username } => {
             info!("Admin {} executing unban command: username={}", account_id, username);
 
-            let result = tokio_runtime.0.block_on(execute_unban(pool, &username));
-
-            match result {
-                Ok(message) => {
-                    // AUDIT LOG: Account unbanned
-                    let _ = tokio_runtime.0.block_on(crate::audit::log_audit_event(
-                        pool,
-                        crate::audit::AuditActionType::AdminCommandExecuted,
-                        Some(account_id),
-                        None,
-                        Some(&username),
-                        ip_address.as_deref(),
-                        Some(&format!("unbanned user: {}", username)),
-                    ));
-
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: NotificationEvent {
-                            message,
-                            notification_type: NotificationType::Success,
-                        },
-                    });
-                }
-                Err(e) => {
-                    error!("Unban command failed: {}", e);
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: NotificationEvent {
-                            message: format!("Unban failed: {}", e),
-                            notification_type: NotificationType::Error,
-                        },
-                    });
-                }
-            }
+            db.run(
+                move |pool| async move { execute_unban(&pool, &username).await.map(|message| (message, username)) },
+                move |result, world| {
+                    let (message, notification_type) = match result {
+                        Ok((message, username)) => {
+                            // AUDIT LOG: Account unbanned
+                            let details = format!("unbanned user: {}", username);
+                            audit(
+                                world.resource::<DatabaseConnection>(),
+                                crate::audit::AuditActionType::AdminCommandExecuted,
+                                account_id,
+                                Some(username),
+                                ip_address,
+                                details,
+                            );
+                            (message, NotificationType::Success)
+                        }
+                        Err(e) => {
+                            error!("Unban command failed: {}", e);
+                            (format!("Unban failed: {}", e), NotificationType::Error)
+                        }
+                    };
+                    notify(&mut world.commands(), client_entity, message, notification_type);
+                },
+            );
         }
 
         AdminCommand::Kick { username, reason } => {
@@ -402,21 +587,20 @@ pub fn handle_admin_command(
                 }
             }
 
-            if let Some((char_entity, client_entity)) = found_character {
+            if let Some((char_entity, kicked_client)) = found_character {
                 // AUDIT LOG: Player kicked
-                let _ = tokio_runtime.0.block_on(crate::audit::log_audit_event(
-                    pool,
+                audit(
+                    &db,
                     crate::audit::AuditActionType::PlayerKicked,
-                    Some(account_id),
-                    None,
-                    Some(&username),
-                    ip_address.as_deref(),
-                    Some(&format!("kicked for: {}", reason)),
-                ));
+                    account_id,
+                    Some(username.clone()),
+                    ip_address,
+                    format!("kicked for: {}", reason),
+                );
 
                 // Notify the kicked player before despawning
                 commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(ClientId::Client(client_entity)),
+                    mode: SendMode::Direct(ClientId::Client(kicked_client)),
                     message: NotificationEvent {
                         message: format!("You have been kicked from the server. Reason: {}", reason),
                         notification_type: NotificationType::Error,
@@ -425,12 +609,13 @@ pub fn handle_admin_command(
 
                 // Despawn the character - this will trigger disconnect handling
                 commands.entity(char_entity).despawn();
+                crate::death::remove_corpses(&mut commands, char_entity);
 
-                info!("Kicked character '{}' (entity {:?}) owned by client {:?}", username, char_entity, client_entity);
+                info!("Kicked character '{}' (entity {:?}) owned by client {:?}", username, char_entity, kicked_client);
 
                 // Confirm to admin
                 commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(trigger.client_id),
+                    mode: SendMode::Direct(client_id),
                     message: NotificationEvent {
                         message: format!("Player '{}' has been kicked from the server", username),
                         notification_type: NotificationType::Success,
@@ -440,7 +625,7 @@ pub fn handle_admin_command(
                 // Character not found
                 warn!("Attempted to kick '{}' but character not found (not online)", username);
                 commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(trigger.client_id),
+                    mode: SendMode::Direct(client_id),
                     message: NotificationEvent {
                         message: format!("Player '{}' not found (may not be online)", username),
                         notification_type: NotificationType::Error,
@@ -453,17 +638,9 @@ pub fn handle_admin_command(
             info!("Admin {} broadcasting message: {}", account_id, message);
 
             // AUDIT LOG: Broadcast sent
-            let _ = tokio_runtime.0.block_on(crate::audit::log_audit_event(
-                pool,
-                crate::audit::AuditActionType::AdminBroadcast,
-                Some(account_id),
-                None,
-                None,
-                ip_address.as_deref(),
-                Some(&message),
-            ));
+            audit(&db, crate::audit::AuditActionType::AdminBroadcast, account_id, None, ip_address, message.clone());
 
-            // Send to all clients
+            // Send to all clients, on this shard and the others
             commands.server_trigger(ToClients {
                 mode: SendMode::Broadcast,
                 message: NotificationEvent {
@@ -471,10 +648,11 @@ pub fn handle_admin_command(
                     notification_type: NotificationType::Warning,
                 },
             });
+            relay.send(crate::relay::RelayMessage::Broadcast { message });
 
             // Confirm to sender
             commands.server_trigger(ToClients {
-                mode: SendMode::Direct(trigger.client_id),
+                mode: SendMode::Direct(client_id),
                 message: NotificationEvent {
                     message: "Broadcast sent successfully".to_string(),
                     notification_type: NotificationType::Success,
@@ -482,10 +660,172 @@ pub fn handle_admin_command(
             });
         }
 
+        AdminCommand::Mute { character_name, duration, reason } => {
+            info!("Admin {} executing mute command: character={}, duration={:?}, reason={}",
+                  account_id, character_name, duration, reason);
+
+            let muted_until = match duration {
+                Some(seconds) => std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64 + seconds,
+                None => i64::MAX,
+            };
+
+            db.run(
+                {
+                    let (character_name, reason) = (character_name.clone(), reason.clone());
+                    move |pool| async move { crate::database::set_mute(&pool, &character_name, Some(muted_until), &reason).await }
+                },
+                move |result, world| {
+                    let (message, notification_type) = match result {
+                        Ok(true) => {
+                            // AUDIT LOG: Character muted
+                            audit(
+                                world.resource::<DatabaseConnection>(),
+                                crate::audit::AuditActionType::AdminCommandExecuted,
+                                account_id,
+                                Some(character_name.clone()),
+                                ip_address,
+                                format!("muted character: {} for {} (duration: {:?})", character_name, reason, duration),
+                            );
+
+                            // Apply the mute straight away if they're online
+                            if let Some((char_entity, owner)) = find_online_character(world, &character_name) {
+                                let mut commands = world.commands();
+                                commands.entity(char_entity).try_insert(crate::chat::ChatMute {
+                                    until: muted_until,
+                                    reason: reason.clone(),
+                                });
+                                let length = duration.map(format_duration).unwrap_or_else(|| "permanently".to_string());
+                                notify(
+                                    &mut commands,
+                                    owner,
+                                    format!("You have been muted ({}). Reason: {}", length, reason),
+                                    NotificationType::Warning,
+                                );
+                            }
+
+                            (format!("Character '{}' has been muted", character_name), NotificationType::Success)
+                        }
+                        Ok(false) => (format!("Character '{}' not found", character_name), NotificationType::Error),
+                        Err(e) => {
+                            error!("Mute command failed: {}", e);
+                            (format!("Mute failed: {}", e), NotificationType::Error)
+                        }
+                    };
+                    notify(&mut world.commands(), client_entity, message, notification_type);
+                },
+            );
+        }
+
+        AdminCommand::Unmute { character_name } => {
+            info!("Admin {} executing unmute command: character={}", account_id, character_name);
+
+            db.run(
+                {
+                    let character_name = character_name.clone();
+                    move |pool| async move { crate::database::set_mute(&pool, &character_name, None, "").await }
+                },
+                move |result, world| {
+                    let (message, notification_type) = match result {
+                        Ok(true) => {
+                            // AUDIT LOG: Character unmuted
+                            audit(
+                                world.resource::<DatabaseConnection>(),
+                                crate::audit::AuditActionType::AdminCommandExecuted,
+                                account_id,
+                                Some(character_name.clone()),
+                                ip_address,
+                                format!("unmuted character: {}", character_name),
+                            );
+
+                            if let Some((char_entity, owner)) = find_online_character(world, &character_name) {
+                                let mut commands = world.commands();
+                                commands.entity(char_entity).remove::<crate::chat::ChatMute>();
+                                notify(&mut commands, owner, "You are no longer muted".to_string(), NotificationType::Info);
+                            }
+
+                            (format!("Character '{}' has been unmuted", character_name), NotificationType::Success)
+                        }
+                        Ok(false) => (format!("Character '{}' not found", character_name), NotificationType::Error),
+                        Err(e) => {
+                            error!("Unmute command failed: {}", e);
+                            (format!("Unmute failed: {}", e), NotificationType::Error)
+                        }
+                    };
+                    notify(&mut world.commands(), client_entity, message, notification_type);
+                },
+            );
+        }
+
+        AdminCommand::Trace { start } => {
+            info!("Admin {} executing trace command: start={}", account_id, start);
+
+            let result = if start {
+                crate::profiling::start_recording()
+                    .map(|_| "Trace recording started. Use /trace stop to write it out".to_string())
+            } else {
+                crate::profiling::stop_recording(&config.profiling.trace_dir)
+                    .map(|(path, events)| format!("Trace written to {} ({} events)", path.display(), events))
+            };
+
+            let (message, notification_type) = match result {
+                Ok(message) => {
+                    // AUDIT LOG: Trace recording toggled
+                    audit(
+                        &db,
+                        crate::audit::AuditActionType::AdminCommandExecuted,
+                        account_id,
+                        None,
+                        ip_address,
+                        if start { "started trace recording" } else { "stopped trace recording" }.to_string(),
+                    );
+                    info!("{}", message);
+                    (message, NotificationType::Success)
+                }
+                Err(e) => (e, NotificationType::Error),
+            };
+
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(client_id),
+                message: NotificationEvent { message, notification_type },
+            });
+        }
+
+        AdminCommand::ResetTwoFactor { username } => {
+            info!("Admin {} executing reset2fa command: username={}", account_id, username);
+
+            db.run(
+                move |pool| async move { execute_reset_two_factor(&pool, &username).await.map(|message| (message, username)) },
+                move |result, world| {
+                    let (message, notification_type) = match result {
+                        Ok((message, username)) => {
+                            // AUDIT LOG: Two-factor removed by an admin
+                            audit(
+                                world.resource::<DatabaseConnection>(),
+                                crate::audit::AuditActionType::AdminCommandExecuted,
+                                account_id,
+                                Some(username.clone()),
+                                ip_address,
+                                format!("reset two-factor for user: {}", username),
+                            );
+                            (message, NotificationType::Success)
+                        }
+                        Err(e) => {
+                            error!("Reset2fa command failed: {}", e);
+                            (format!("Reset failed: {}", e), NotificationType::Error)
+                        }
+                    };
+                    notify(&mut world.commands(), client_entity, message, notification_type);
+                },
+            );
+        }
+
         AdminCommand::Invalid(error_msg) => {
             warn!("Invalid admin command from {}: {}", account_id, error_msg);
             commands.server_trigger(ToClients {
-                mode: SendMode::Direct(trigger.client_id),
+                mode: SendMode::Direct(client_id),
                 message: NotificationEvent {
                     message: error_msg,
                     notification_type: NotificationType::Error,
@@ -499,35 +839,80 @@ pub fn handle_admin_command(
 // COMMAND EXECUTION HELPERS
 // ============================================================================
 
-/// Execute a ban command
-async fn execute_ban(
-    pool: &SqlitePool,
-    name_or_username: &str,
-    duration: Option<i64>,
-    reason: &str,
-    banned_by_account_id: i64,
-) -> Result<String, String> {
+/// A ban that was just recorded
+struct IssuedBan {
+    /// Confirmation for the admin
+    message: String,
+    /// Username or IP address the ban was issued against
+    target: String,
+    info: crate::database::BanInfo,
+    /// Who to disconnect if they're online
+    matches: BanMatch,
+}
+
+/// Confirm a ban to the admin, audit it and disconnect everyone it matches
+fn ban_issued(
+    world: &mut World,
+    result: Result<IssuedBan, String>,
+    client_entity: Entity,
+    account_id: i64,
+    ip_address: Option<String>,
+) {
+    let (message, notification_type) = match result {
+        Ok(ban) => {
+            // AUDIT LOG: Account banned
+            let details = format!(
+                "ban #{} ({}) on {} for {} (expires_at: {:?})",
+                ban.info.id, ban.info.ban_type, ban.target, ban.info.reason, ban.info.expires_at,
+            );
+            audit(
+                world.resource::<DatabaseConnection>(),
+                crate::audit::AuditActionType::AccountBanned,
+                account_id,
+                Some(ban.target.clone()),
+                ip_address,
+                details,
+            );
+
+            let subject = if ban.info.ban_type == "ip" { "This IP address" } else { "Your account" };
+            let notice = crate::auth::ban_message(subject, &ban.info);
+            let disconnected = disconnect_banned_clients(world, &ban.matches, &notice);
+            if disconnected > 0 {
+                info!("Disconnecting {} client(s) matched by ban #{}", disconnected, ban.info.id);
+            }
+            (ban.message, NotificationType::Success)
+        }
+        Err(e) => {
+            error!("Ban command failed: {}", e);
+            (format!("Ban failed: {}", e), NotificationType::Error)
+        }
+    };
+    notify(&mut world.commands(), client_entity, message, notification_type);
+}
+
+/// Account id and username for a username or character name
+async fn find_account(pool: &DbPool, name_or_username: &str) -> Result<(i64, String), String> {
     // Try to find account by username first
     let account_result = sqlx::query(
-        "SELECT id, username FROM accounts WHERE username = ?1"
+        "SELECT id, username FROM accounts WHERE username = $1"
     )
     .bind(name_or_username)
     .fetch_optional(pool)
     .await;
 
     // If not found by username, try to find by character name
-    let (account_id, username) = match account_result {
+    match account_result {
         Ok(Some(row)) => {
             let id: i64 = row.try_get("id")
                 .map_err(|e| format!("Failed to get id: {}", e))?;
             let username: String = row.try_get("username")
                 .map_err(|e| format!("Failed to get username: {}", e))?;
-            (id, username)
+            Ok((id, username))
         }
         Ok(None) => {
             // Not found by username, try character name
             let char_result = sqlx::query(
-                "SELECT account_id FROM characters WHERE name = ?1"
+                "SELECT account_id FROM characters WHERE name = $1"
             )
             .bind(name_or_username)
             .fetch_optional(pool)
@@ -540,7 +925,7 @@ async fn execute_ban(
 
                     // Get username for this account
                     let username_result = sqlx::query(
-                        "SELECT username FROM accounts WHERE id = ?1"
+                        "SELECT username FROM accounts WHERE id = $1"
                     )
                     .bind(acc_id)
                     .fetch_optional(pool)
@@ -550,92 +935,193 @@ async fn execute_ban(
                         Ok(Some(username_row)) => {
                             let username: String = username_row.try_get("username")
                                 .map_err(|e| format!("Failed to get username: {}", e))?;
-                            (acc_id, username)
+                            Ok((acc_id, username))
                         }
-                        _ => return Err(format!("Account not found for character '{}'", name_or_username)),
+                        _ => Err(format!("Account not found for character '{}'", name_or_username)),
                     }
                 }
-                Ok(None) => {
-                    return Err(format!("User or character '{}' not found", name_or_username));
-                }
-                Err(e) => {
-                    return Err(format!("Database error: {}", e));
-                }
+                Ok(None) => Err(format!("User or character '{}' not found", name_or_username)),
+                Err(e) => Err(format!("Database error: {}", e)),
             }
         }
-        Err(e) => {
-            return Err(format!("Database error: {}", e));
-        }
-    };
+        Err(e) => Err(format!("Database error: {}", e)),
+    }
+}
 
-    // Calculate expiry time if not permanent
-    let expires_at = duration.map(|seconds| {
+/// Expiry timestamp for a ban lasting `duration` seconds from now
+fn ban_expiry(duration: Option<i64>) -> Option<i64> {
+    duration.map(|seconds| {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
         now + seconds
-    });
+    })
+}
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+/// "permanently banned" or "banned for 2 hours"
+fn describe_ban(duration: Option<i64>) -> String {
+    match duration {
+        None => "permanently banned".to_string(),
+        Some(seconds) => format!("banned for {}", format_duration(seconds)),
+    }
+}
 
-    // Insert ban record using new bans table schema
-    let result = sqlx::query(
-        "INSERT INTO bans (ban_type, target, account_id, reason, banned_by, banned_at, expires_at, is_active)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1)"
-    )
-    .bind("account")  // ban_type: 'account', 'ip', or 'both'
-    .bind(&username)  // target: username for account bans
-    .bind(account_id)
-    .bind(reason)
-    .bind(banned_by_account_id)
-    .bind(now)
-    .bind(expires_at)
-    .execute(pool)
-    .await;
+/// Record a ban and package it up for `ban_issued`
+async fn record_ban(
+    pool: &DbPool,
+    ban: crate::database::NewBan<'_>,
+    description: String,
+    matches: BanMatch,
+) -> Result<IssuedBan, String> {
+    let id = crate::database::insert_ban(pool, &ban).await?;
+    Ok(IssuedBan {
+        message: format!("{} (ban #{}). Reason: {}", description, id, ban.reason),
+        target: ban.target.to_string(),
+        info: crate::database::BanInfo {
+            id,
+            ban_type: ban.ban_type.to_string(),
+            reason: ban.reason.to_string(),
+            expires_at: ban.expires_at,
+            is_permanent: ban.expires_at.is_none(),
+        },
+        matches,
+    })
+}
 
-    match result {
-        Ok(_) => {
-            let message = if duration.is_none() {
-                format!("User '{}' has been permanently banned. Reason: {}", username, reason)
-            } else {
-                let duration_str = format_duration(duration.unwrap());
-                format!("User '{}' has been banned for {}. Reason: {}", username, duration_str, reason)
-            };
-            Ok(message)
-        }
-        Err(e) => Err(format!("Failed to ban user: {}", e)),
+/// Execute a ban command
+async fn execute_ban(
+    pool: &DbPool,
+    name_or_username: &str,
+    duration: Option<i64>,
+    reason: &str,
+    banned_by_account_id: i64,
+) -> Result<IssuedBan, String> {
+    let (account_id, username) = find_account(pool, name_or_username).await?;
+
+    let ban = crate::database::NewBan {
+        ban_type: "account",
+        target: &username,
+        account_id: Some(account_id),
+        ip_address: None,
+        hardware_id: None,
+        reason,
+        banned_by: banned_by_account_id,
+        expires_at: ban_expiry(duration),
+    };
+    let description = format!("User '{}' has been {}", username, describe_ban(duration));
+    let matches = BanMatch { account_id: Some(account_id), ..Default::default() };
+    record_ban(pool, ban, description, matches).await
+}
+
+/// Execute a banip command: a bare IP address, or an account plus the IP address and
+/// device it last logged in from
+async fn execute_ban_ip(
+    pool: &DbPool,
+    target: &str,
+    duration: Option<i64>,
+    reason: &str,
+    banned_by_account_id: i64,
+) -> Result<IssuedBan, String> {
+    if let Ok(ip) = target.parse::<std::net::IpAddr>() {
+        let ip_str = ip.to_string();
+        let ban = crate::database::NewBan {
+            ban_type: "ip",
+            target: &ip_str,
+            account_id: None,
+            ip_address: Some(&ip_str),
+            hardware_id: None,
+            reason,
+            banned_by: banned_by_account_id,
+            expires_at: ban_expiry(duration),
+        };
+        let description = format!("IP address {} has been {}", ip_str, describe_ban(duration));
+        let matches = BanMatch { ip_address: Some(ip), ..Default::default() };
+        return record_ban(pool, ban, description, matches).await;
+    }
+
+    let (account_id, username) = find_account(pool, target).await?;
+    let (last_ip, last_hardware_id) = crate::database::account::last_login_origin(pool, account_id).await?;
+    if last_ip.is_none() && last_hardware_id.is_none() {
+        return Err(format!("User '{}' has no recorded login to take an IP or device from; use /ban", username));
     }
+
+    let ban = crate::database::NewBan {
+        ban_type: "both",
+        target: &username,
+        account_id: Some(account_id),
+        ip_address: last_ip.as_deref(),
+        hardware_id: last_hardware_id.as_deref(),
+        reason,
+        banned_by: banned_by_account_id,
+        expires_at: ban_expiry(duration),
+    };
+    let description = format!(
+        "User '{}' has been {} along with their last IP ({}) and device",
+        username,
+        describe_ban(duration),
+        last_ip.as_deref().unwrap_or("none"),
+    );
+    let matches = BanMatch {
+        account_id: Some(account_id),
+        ip_address: last_ip.as_deref().and_then(|ip| ip.parse().ok()),
+        hardware_id: last_hardware_id.clone(),
+    };
+    record_ban(pool, ban, description, matches).await
 }
 
-/// Execute an unban command
+/// Execute an unban command: "#42" lifts that one ban, anything else lifts every active
+/// ban issued against that username or IP address
 async fn execute_unban(
-    pool: &SqlitePool,
+    pool: &DbPool,
     username: &str,
 ) -> Result<String, String> {
-    // Deactivate all bans for this username (using target field from new bans table)
-    let result = sqlx::query(
-        "UPDATE bans SET is_active = 0 WHERE target = ?1 AND is_active = 1 AND ban_type = 'account'"
-    )
-    .bind(username)
-    .execute(pool)
-    .await;
+    let result = match username.strip_prefix('#').and_then(|id| id.parse::<i64>().ok()) {
+        Some(ban_id) => {
+            sqlx::query("UPDATE bans SET is_active = FALSE WHERE id = $1 AND is_active = TRUE")
+                .bind(ban_id)
+                .execute(pool)
+                .await
+        }
+        None => {
+            sqlx::query("UPDATE bans SET is_active = FALSE WHERE target = $1 AND is_active = TRUE")
+                .bind(username)
+                .execute(pool)
+                .await
+        }
+    };
 
     match result {
         Ok(result) => {
             if result.rows_affected() > 0 {
-                Ok(format!("User '{}' has been unbanned", username))
+                Ok(format!("'{}' has been unbanned", username))
             } else {
-                Err(format!("User '{}' is not currently banned", username))
+                Err(format!("'{}' is not currently banned", username))
             }
         }
         Err(e) => Err(format!("Failed to unban user: {}", e)),
     }
 }
 
+/// Remove two-factor (secret and backup codes) from an account
+async fn execute_reset_two_factor(pool: &DbPool, username: &str) -> Result<String, String> {
+    let account = sqlx::query("SELECT id FROM accounts WHERE username = $1")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    let Some(account) = account else {
+        return Err(format!("User '{}' not found", username));
+    };
+
+    let account_id: i64 = account.try_get("id").map_err(|e| format!("Failed to get id: {}", e))?;
+    if crate::database::two_factor::remove_two_factor(pool, account_id).await? {
+        Ok(format!("Two-factor authentication removed for '{}'", username))
+    } else {
+        Err(format!("User '{}' does not have two-factor authentication", username))
+    }
+}
+
 /// Format duration in seconds to human-readable string
 fn format_duration(seconds: i64) -> String {
     if seconds < 60 {
@@ -650,46 +1136,3 @@ fn format_duration(seconds: i64) -> String {
         format!("{} weeks", seconds / 604800)
     }
 }
-
-// ============================================================================
-// CHAT SYSTEM
-// ============================================================================
-
-/// Handle regular chat messages from players
-/// Broadcasts the message to all connected players with the sender's name
-pub fn handle_chat_message(
-    trigger: On<FromClient<SendChatMessage>>,
-    mut commands: Commands,
-    characters: Query<(&Character, &OwnedBy), With<Player>>,
-) {
-    let Some(client_entity) = trigger.client_id.entity() else {
-        warn!("No client entity in chat message trigger");
-        return;
-    };
-
-    // Find the player character owned by this client
-    let mut sender_name = None;
-    for (character, owned_by) in characters.iter() {
-        if owned_by.0 == client_entity {
-            sender_name = Some(character.name.clone());
-            break;
-        }
-    }
-
-    let Some(sender_name) = sender_name else {
-        warn!("Chat message from client {:?} without spawned character", client_entity);
-        return;
-    };
-
-    // Create the chat message event to broadcast
-    let chat_message = ChatMessage {
-        sender: sender_name,
-        message: trigger.message.message.clone(),
-    };
-
-    // Broadcast to all connected players
-    commands.server_trigger(ToClients {
-        mode: SendMode::Broadcast,
-        message: chat_message,
-    });
-}
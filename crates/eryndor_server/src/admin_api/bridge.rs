@@ -0,0 +1,103 @@
+//! Game loop side of the admin API.
+//!
+//! HTTP handlers run on tokio and can't reach into the ECS, so the game loop publishes a
+//! snapshot of what the dashboard shows and picks up broadcasts the dashboard queued.
+
+use bevy::prelude::*;
+use bevy_renet2::prelude::RenetServer;
+use bevy_replicon::prelude::*;
+use bevy_replicon::shared::backend::connected_client::NetworkId;
+use eryndor_shared::*;
+use std::sync::{Arc, Mutex, RwLock};
+use crate::auth::{ActiveCharacterEntity, Authenticated};
+use crate::dashboard::LiveStats;
+use crate::relay::{Relay, RelayMessage};
+use crate::replication::ReplicationStats;
+use crate::shards::ShardRegistry;
+
+/// World state published for the web dashboard
+#[derive(Default)]
+struct LiveSnapshot {
+    /// Usernames are filled in by the HTTP handler
+    players: Vec<OnlinePlayerInfo>,
+    stats: LiveStats,
+}
+
+/// Shared between the game loop and the HTTP handlers
+#[derive(Resource, Clone, Default)]
+pub struct AdminApiBridge {
+    live: Arc<RwLock<LiveSnapshot>>,
+    broadcasts: Arc<Mutex<Vec<String>>>,
+}
+
+impl AdminApiBridge {
+    pub fn players(&self) -> Vec<OnlinePlayerInfo> {
+        self.live.read().map(|live| live.players.clone()).unwrap_or_default()
+    }
+
+    pub fn stats(&self) -> LiveStats {
+        self.live.read().map(|live| live.stats.clone()).unwrap_or_default()
+    }
+
+    pub fn queue_broadcast(&self, message: String) {
+        if let Ok(mut queue) = self.broadcasts.lock() {
+            queue.push(message);
+        }
+    }
+}
+
+/// Publish online players and live statistics for the dashboard
+pub fn publish_snapshot(
+    bridge: Res<AdminApiBridge>,
+    players: Query<(&Character, &Position, &OwnedBy), With<Player>>,
+    owners: Query<&Authenticated>,
+    characters: Query<&Character>,
+    network_clients: Query<(&NetworkId, Option<&ActiveCharacterEntity>), With<ConnectedClient>>,
+    renet_server: Option<Res<RenetServer>>,
+    replication: Res<ReplicationStats>,
+    shards: Res<ShardRegistry>,
+) {
+    let players = players.iter()
+        .filter_map(|(character, position, owned_by)| {
+            let owner_auth = owners.get(owned_by.0).ok()?;
+            Some(OnlinePlayerInfo {
+                username: String::new(),
+                character_name: character.name.clone(),
+                account_id: owner_auth.account_id,
+                level: character.level,
+                class: character.class,
+                position_x: position.0.x,
+                position_y: position.0.y,
+            })
+        })
+        .collect();
+    let stats = LiveStats::collect(&characters, &network_clients, renet_server.as_deref(), &replication, &shards);
+
+    if let Ok(mut live) = bridge.live.write() {
+        *live = LiveSnapshot { players, stats };
+    }
+}
+
+/// Send broadcasts queued from the dashboard to every player
+pub fn deliver_broadcasts(bridge: Res<AdminApiBridge>, relay: Res<Relay>, mut commands: Commands) {
+    let pending = match bridge.broadcasts.lock() {
+        Ok(mut queue) => std::mem::take(&mut *queue),
+        Err(_) => return,
+    };
+
+    for message in pending {
+        send_broadcast(&mut commands, &relay, message);
+    }
+}
+
+/// Show an admin broadcast to every player, on this shard and the others
+pub fn send_broadcast(commands: &mut Commands, relay: &Relay, message: String) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Broadcast,
+        message: NotificationEvent {
+            message: format!("[ADMIN BROADCAST] {}", message),
+            notification_type: NotificationType::Warning,
+        },
+    });
+    relay.send(RelayMessage::Broadcast { message });
+}
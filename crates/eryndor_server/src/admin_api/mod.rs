@@ -0,0 +1,377 @@
+//! Admin API - web dashboard served from the server's HTTP endpoint.
+//!
+//! Admins log in with their account credentials and get a JWT, which every other endpoint
+//! requires as a bearer token. Database-backed views (bans, audit logs) query the database
+//! directly; live views (players, stats) read a snapshot the game loop publishes once a
+//! second, and broadcasts and console commands are queued for the game loop to run.
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Query, State},
+    http::{header, request::Parts, StatusCode},
+    response::{Html, IntoResponse},
+    routing::{get, post},
+    Json, Router,
+};
+use eryndor_shared::{AuditLogsResponse, BanInfo, OnlinePlayerInfo, ServerStatsResponse};
+use governor::{state::keyed::DefaultKeyedStateStore, Quota, RateLimiter};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use crate::database::DbPool;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::admin::is_admin;
+use crate::audit::{log_audit_event, AuditActionType};
+use crate::config::ServerConfig;
+use crate::console::ServerConsole;
+use crate::dashboard::{fetch_audit_logs, fetch_ban_list, fetch_server_stats, fetch_username};
+use crate::editor_api::ApiResponse;
+use crate::two_factor::SecondFactorError;
+
+mod bridge;
+
+pub use bridge::{deliver_broadcasts, publish_snapshot, send_broadcast, AdminApiBridge};
+
+type ApiError = (StatusCode, Json<ApiResponse<()>>);
+
+/// How long a console command may wait for the game loop before the request gives up
+const CONSOLE_REPLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> ApiError {
+    (status, ApiResponse::error(message))
+}
+
+// =============================================================================
+// Shared Types
+// =============================================================================
+
+/// Shared state for the admin API
+#[derive(Clone)]
+pub struct AdminApiState {
+    pool: DbPool,
+    bridge: AdminApiBridge,
+    console: ServerConsole,
+    jwt_secret: String,
+    jwt_expiry_hours: i64,
+    login_attempts: Arc<RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, governor::clock::DefaultClock>>,
+}
+
+impl AdminApiState {
+    pub fn new(pool: DbPool, bridge: AdminApiBridge, console: ServerConsole, config: &ServerConfig) -> Self {
+        let per_hour = NonZeroU32::new(config.rate_limits.login_attempts_per_hour).unwrap_or(NonZeroU32::MIN);
+        Self {
+            pool,
+            bridge,
+            console,
+            jwt_secret: config.admin.jwt_secret.clone(),
+            jwt_expiry_hours: config.admin.jwt_expiry_hours,
+            login_attempts: Arc::new(RateLimiter::keyed(Quota::per_hour(per_hour))),
+        }
+    }
+}
+
+/// JWT claims for a dashboard session
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    account_id: i64,
+    username: String,
+    exp: usize,
+}
+
+/// An authenticated admin, taken from the request's bearer token
+pub struct AdminSession {
+    pub account_id: i64,
+    pub username: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AdminApiState> for AdminSession {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AdminApiState) -> Result<Self, Self::Rejection> {
+        let token = parts.headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Missing bearer token"))?;
+
+        let claims = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(state.jwt_secret.as_bytes()),
+            &Validation::default(),
+        )
+        .map_err(|_| api_error(StatusCode::UNAUTHORIZED, "Invalid or expired token"))?
+        .claims;
+
+        // Admin rights may have been revoked since the token was issued
+        match is_admin(&state.pool, claims.account_id).await {
+            Ok(true) => Ok(AdminSession {
+                account_id: claims.account_id,
+                username: claims.username,
+            }),
+            Ok(false) => Err(api_error(StatusCode::FORBIDDEN, "Admin permissions required")),
+            Err(e) => Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+    /// Required for accounts with two-factor enabled
+    #[serde(default)]
+    pub totp_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    pub token: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BroadcastRequest {
+    pub message: String,
+}
+
+/// A server console command, e.g. "who" or "kick Alice"
+#[derive(Debug, Deserialize)]
+pub struct ConsoleCommandRequest {
+    pub command: String,
+}
+
+// =============================================================================
+// Router
+// =============================================================================
+
+/// Create the admin API router
+pub fn create_admin_router(state: AdminApiState) -> Router {
+    Router::new()
+        .route("/login", post(login))
+        .route("/players", get(list_players))
+        .route("/bans", get(list_bans))
+        .route("/stats", get(server_stats))
+        .route("/audit-logs", get(audit_logs))
+        .route("/broadcast", post(broadcast))
+        .route("/console", post(console_command))
+        .with_state(state)
+}
+
+/// The dashboard page itself; it talks to the API with fetch()
+pub async fn dashboard_page() -> impl IntoResponse {
+    Html(include_str!("dashboard.html"))
+}
+
+// =============================================================================
+// Handlers
+// =============================================================================
+
+async fn login(
+    State(state): State<AdminApiState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<ApiResponse<TokenResponse>>, ApiError> {
+    let ip = addr.ip().to_string();
+
+    if state.login_attempts.check_key(&addr.ip()).is_err() {
+        let _ = log_audit_event(
+            &state.pool,
+            AuditActionType::RateLimitExceeded,
+            None,
+            None,
+            Some(&request.username),
+            Some(&ip),
+            Some("admin dashboard login"),
+        ).await;
+        return Err(api_error(StatusCode::TOO_MANY_REQUESTS, "Too many login attempts"));
+    }
+
+    let account_id = match crate::database::verify_credentials(&state.pool, &request.username, &request.password).await {
+        Ok(account_id) => account_id,
+        Err(_) => {
+            let _ = log_audit_event(
+                &state.pool,
+                AuditActionType::AccountLoginFailed,
+                None,
+                None,
+                Some(&request.username),
+                Some(&ip),
+                Some("admin dashboard login"),
+            ).await;
+            return Err(api_error(StatusCode::UNAUTHORIZED, "Invalid credentials"));
+        }
+    };
+
+    let code = request.totp_code.as_deref();
+    match crate::two_factor::check_second_factor(&state.pool, account_id, code).await {
+        Ok(_) => {}
+        Err(SecondFactorError::Missing) => {
+            return Err(api_error(StatusCode::UNAUTHORIZED, "Two-factor code required"));
+        }
+        Err(SecondFactorError::Rejected(e)) => {
+            let _ = log_audit_event(
+                &state.pool,
+                AuditActionType::AccountLoginFailed,
+                Some(account_id),
+                None,
+                Some(&request.username),
+                Some(&ip),
+                Some("admin dashboard login: two-factor code rejected"),
+            ).await;
+            return Err(api_error(StatusCode::UNAUTHORIZED, e));
+        }
+    }
+
+    match is_admin(&state.pool, account_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = log_audit_event(
+                &state.pool,
+                AuditActionType::SuspiciousActivity,
+                Some(account_id),
+                None,
+                Some(&request.username),
+                Some(&ip),
+                Some("non-admin attempted admin dashboard login"),
+            ).await;
+            return Err(api_error(StatusCode::FORBIDDEN, "Admin permissions required"));
+        }
+        Err(e) => return Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+
+    let expires_at = chrono::Utc::now().timestamp() + state.jwt_expiry_hours * 3600;
+    let claims = Claims {
+        account_id,
+        username: request.username.clone(),
+        exp: expires_at as usize,
+    };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(state.jwt_secret.as_bytes()))
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create token: {}", e)))?;
+
+    let _ = log_audit_event(
+        &state.pool,
+        AuditActionType::AccountLogin,
+        Some(account_id),
+        None,
+        Some(&request.username),
+        Some(&ip),
+        Some("admin dashboard login"),
+    ).await;
+    info!("Admin {} logged into the web dashboard from {}", request.username, ip);
+
+    Ok(ApiResponse::success(TokenResponse { token, expires_at }))
+}
+
+async fn list_players(
+    State(state): State<AdminApiState>,
+    _session: AdminSession,
+) -> Json<ApiResponse<Vec<OnlinePlayerInfo>>> {
+    let mut players = state.bridge.players();
+
+    for player in &mut players {
+        player.username = fetch_username(&state.pool, player.account_id).await;
+    }
+
+    ApiResponse::success(players)
+}
+
+async fn list_bans(
+    State(state): State<AdminApiState>,
+    _session: AdminSession,
+) -> Result<Json<ApiResponse<Vec<BanInfo>>>, ApiError> {
+    fetch_ban_list(&state.pool)
+        .await
+        .map(ApiResponse::success)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn server_stats(
+    State(state): State<AdminApiState>,
+    _session: AdminSession,
+) -> Result<Json<ApiResponse<ServerStatsResponse>>, ApiError> {
+    let live = state.bridge.stats();
+
+    fetch_server_stats(&state.pool, live)
+        .await
+        .map(ApiResponse::success)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn audit_logs(
+    State(state): State<AdminApiState>,
+    _session: AdminSession,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<ApiResponse<AuditLogsResponse>>, ApiError> {
+    let limit = query.limit.unwrap_or(50).min(500);
+    let offset = query.offset.unwrap_or(0);
+
+    fetch_audit_logs(&state.pool, limit, offset)
+        .await
+        .map(ApiResponse::success)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn broadcast(
+    State(state): State<AdminApiState>,
+    session: AdminSession,
+    Json(request): Json<BroadcastRequest>,
+) -> Result<Json<ApiResponse<()>>, ApiError> {
+    let message = request.message.trim().to_string();
+    if message.is_empty() {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Message is empty"));
+    }
+
+    let _ = log_audit_event(
+        &state.pool,
+        AuditActionType::AdminBroadcast,
+        Some(session.account_id),
+        None,
+        None,
+        None,
+        Some(&message),
+    ).await;
+    info!("Admin {} broadcasting from the web dashboard: {}", session.username, message);
+
+    state.bridge.queue_broadcast(message);
+
+    Ok(ApiResponse::success(()))
+}
+
+/// Run a server console command and return what it printed
+async fn console_command(
+    State(state): State<AdminApiState>,
+    session: AdminSession,
+    Json(request): Json<ConsoleCommandRequest>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let command = request.command.trim().to_string();
+    if command.is_empty() {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Command is empty"));
+    }
+
+    let _ = log_audit_event(
+        &state.pool,
+        AuditActionType::AdminCommandExecuted,
+        Some(session.account_id),
+        None,
+        None,
+        None,
+        Some(&format!("console: {}", command)),
+    ).await;
+    info!("Admin {} running console command from the admin API: {}", session.username, command);
+
+    match tokio::time::timeout(CONSOLE_REPLY_TIMEOUT, state.console.submit(command)).await {
+        Ok(Ok(Ok(output))) => Ok(ApiResponse::success(output)),
+        Ok(Ok(Err(e))) => Err(api_error(StatusCode::BAD_REQUEST, e)),
+        Ok(Err(_)) | Err(_) => Err(api_error(StatusCode::SERVICE_UNAVAILABLE, "The game loop didn't answer")),
+    }
+}
@@ -1,4 +1,5 @@
-use sqlx::SqlitePool;
+use crate::database::{self, DbPool};
+use sqlx::Row;
 use std::env;
 
 pub async fn run_admin_command() {
@@ -36,9 +37,20 @@ pub async fn run_admin_command() {
             }
             reset_password(&args[2]).await;
         }
+        "reset-2fa" => {
+            if args.len() < 3 {
+                eprintln!("Usage: {} reset-2fa <email>", args[0]);
+                std::process::exit(1);
+            }
+            reset_two_factor(&args[2]).await;
+        }
         "list-users" => {
             list_users().await;
         }
+        "validate-data" => {
+            let dir = args.get(2).map(String::as_str).unwrap_or(crate::data_reload::CONTENT_DIR);
+            validate_data(dir);
+        }
         _ => {
             eprintln!("Unknown command: {}", command);
             print_usage();
@@ -55,29 +67,31 @@ fn print_usage() {
     println!("  remove-admin <email>   - Revoke admin privileges from a user");
     println!("  list-admins            - List all admin users");
     println!("  reset-password <email> - Reset user password (generates temp password)");
+    println!("  reset-2fa <email>      - Turn off two-factor authentication for a user");
     println!("  list-users             - List all users");
+    println!("  validate-data [dir]    - Cross-check game content (default: assets/content)");
     println!("\nEnvironment Variables:");
+    println!("  DATABASE_URL           - sqlite:<path> or postgres://... (overrides DATABASE_PATH)");
     println!("  DATABASE_PATH          - Path to SQLite database (default: eryndor.db)");
 }
 
-async fn get_pool() -> SqlitePool {
-    let db_path = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "eryndor.db".to_string());
-    let connection_string = format!("sqlite:{}?mode=rwc", db_path);
-
-    SqlitePool::connect(&connection_string)
-        .await
-        .expect("Failed to connect to database")
+async fn get_pool() -> DbPool {
+    match database::connect(&database::database_url()).await {
+        Ok((pool, _)) => pool,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
 async fn make_admin(email: &str) {
     let pool = get_pool().await;
 
-    let result = sqlx::query!(
-        "UPDATE accounts SET is_admin = 1 WHERE email = ?",
-        email
-    )
-    .execute(&pool)
-    .await;
+    let result = sqlx::query("UPDATE accounts SET is_admin = TRUE WHERE email = $1")
+        .bind(email)
+        .execute(&pool)
+        .await;
 
     match result {
         Ok(result) => {
@@ -98,12 +112,10 @@ async fn make_admin(email: &str) {
 async fn remove_admin(email: &str) {
     let pool = get_pool().await;
 
-    let result = sqlx::query!(
-        "UPDATE accounts SET is_admin = 0 WHERE email = ?",
-        email
-    )
-    .execute(&pool)
-    .await;
+    let result = sqlx::query("UPDATE accounts SET is_admin = FALSE WHERE email = $1")
+        .bind(email)
+        .execute(&pool)
+        .await;
 
     match result {
         Ok(result) => {
@@ -124,12 +136,10 @@ async fn remove_admin(email: &str) {
 async fn list_admins() {
     let pool = get_pool().await;
 
-    let admins = sqlx::query!(
-        "SELECT email, username, created_at FROM accounts WHERE is_admin = 1 ORDER BY email"
-    )
-    .fetch_all(&pool)
-    .await
-    .expect("Failed to query admins");
+    let admins = sqlx::query("SELECT email, username, created_at FROM accounts WHERE is_admin = TRUE ORDER BY email")
+        .fetch_all(&pool)
+        .await
+        .expect("Failed to query admins");
 
     if admins.is_empty() {
         println!("No admin users found.");
@@ -141,11 +151,12 @@ async fn list_admins() {
     println!("{}", "-".repeat(70));
 
     for admin in admins {
-        let email = admin.email.as_deref().unwrap_or("N/A");
-        let created_ts = chrono::DateTime::from_timestamp(admin.created_at, 0)
+        let email: Option<String> = admin.get("email");
+        let username: String = admin.get("username");
+        let created_ts = chrono::DateTime::from_timestamp(admin.get("created_at"), 0)
             .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
             .unwrap_or_else(|| "Invalid date".to_string());
-        println!("{:<30} {:<20} {}", email, admin.username, created_ts);
+        println!("{:<30} {:<20} {}", email.as_deref().unwrap_or("N/A"), username, created_ts);
     }
 }
 
@@ -173,13 +184,11 @@ async fn reset_password(email: &str) {
         .expect("Failed to hash password")
         .to_string();
 
-    let result = sqlx::query!(
-        "UPDATE accounts SET password_hash = ? WHERE email = ?",
-        password_hash,
-        email
-    )
-    .execute(&pool)
-    .await;
+    let result = sqlx::query("UPDATE accounts SET password_hash = $1 WHERE email = $2")
+        .bind(&password_hash)
+        .bind(email)
+        .execute(&pool)
+        .await;
 
     match result {
         Ok(result) => {
@@ -199,15 +208,42 @@ async fn reset_password(email: &str) {
     }
 }
 
+async fn reset_two_factor(email: &str) {
+    let pool = get_pool().await;
+
+    let account = match sqlx::query("SELECT id FROM accounts WHERE email = $1")
+        .bind(email)
+        .fetch_optional(&pool)
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            eprintln!("✗ User not found: {}", email);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("✗ Database error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match database::two_factor::remove_two_factor(&pool, account.get(0)).await {
+        Ok(true) => println!("✓ Two-factor authentication removed for: {}", email),
+        Ok(false) => println!("User {} does not have two-factor authentication", email),
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 async fn list_users() {
     let pool = get_pool().await;
 
-    let users = sqlx::query!(
-        "SELECT email, username, is_admin, account_type, created_at FROM accounts ORDER BY created_at DESC LIMIT 50"
-    )
-    .fetch_all(&pool)
-    .await
-    .expect("Failed to query users");
+    let users = sqlx::query("SELECT email, username, is_admin, account_type, created_at FROM accounts ORDER BY created_at DESC LIMIT 50")
+        .fetch_all(&pool)
+        .await
+        .expect("Failed to query users");
 
     if users.is_empty() {
         println!("No users found.");
@@ -219,12 +255,34 @@ async fn list_users() {
     println!("{}", "-".repeat(90));
 
     for user in users {
-        let email = user.email.as_deref().unwrap_or("N/A");
-        let is_admin = if user.is_admin.unwrap_or(false) { "Yes" } else { "No" };
-        let account_type = user.account_type.as_deref().unwrap_or("unknown");
-        let created_ts = chrono::DateTime::from_timestamp(user.created_at, 0)
+        let email: Option<String> = user.get("email");
+        let username: String = user.get("username");
+        let is_admin = if user.get::<Option<bool>, _>("is_admin").unwrap_or(false) { "Yes" } else { "No" };
+        let account_type: Option<String> = user.get("account_type");
+        let created_ts = chrono::DateTime::from_timestamp(user.get("created_at"), 0)
             .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
             .unwrap_or_else(|| "Invalid date".to_string());
-        println!("{:<30} {:<20} {:<8} {:<12} {}", email, user.username, is_admin, account_type, created_ts);
+        println!(
+            "{:<30} {:<20} {:<8} {:<12} {}",
+            email.as_deref().unwrap_or("N/A"),
+            username,
+            is_admin,
+            account_type.as_deref().unwrap_or("unknown"),
+            created_ts,
+        );
+    }
+}
+
+fn validate_data(dir: &str) {
+    let issues = crate::validation::validate_content(std::path::Path::new(dir));
+    if issues.is_empty() {
+        println!("✓ No problems found in {}", dir);
+        return;
+    }
+
+    for issue in &issues {
+        println!("{}", issue);
     }
+    eprintln!("\n✗ {} problems found in {}", issues.len(), dir);
+    std::process::exit(1);
 }
@@ -2,9 +2,11 @@ use bevy::prelude::*;
 use bevy::asset::{AssetLoader, LoadContext, io::Reader};
 use std::collections::HashMap;
 use std::path::Path;
-use crate::game_data::{ItemDefinition, ItemDatabase, EnemyDefinition, EnemyDatabase, QuestDefinition, QuestDatabase, ZoneDefinition, ZoneDatabase};
+use crate::game_data::{ItemDefinition, ItemDatabase, EnemyDefinition, EnemyDatabase, QuestDefinition, QuestDatabase, ZoneDefinition, ZoneDatabase, DialogueDefinition, DialogueDatabase, LootTableDefinition, LootTableDatabase, ShopDefinition, ShopDatabase};
 use eryndor_shared::AbilityDefinition;
 use crate::abilities::AbilityDatabase;
+use crate::combat_formulas::CombatFormulas;
+use crate::behavior::{BehaviorTreeDefinition, BehaviorTreeDatabase};
 
 // ============================================================================
 // ASSET TYPES
@@ -30,6 +32,26 @@ pub struct QuestAsset(pub QuestDefinition);
 #[derive(Asset, TypePath, Debug)]
 pub struct AbilityAsset(pub AbilityDefinition);
 
+/// Wrapper for DialogueDefinition as a Bevy Asset
+#[derive(Asset, TypePath, Debug)]
+pub struct DialogueAsset(pub DialogueDefinition);
+
+/// Wrapper for LootTableDefinition as a Bevy Asset
+#[derive(Asset, TypePath, Debug)]
+pub struct LootTableAsset(pub LootTableDefinition);
+
+/// Wrapper for ShopDefinition as a Bevy Asset
+#[derive(Asset, TypePath, Debug)]
+pub struct ShopAsset(pub ShopDefinition);
+
+/// Wrapper for CombatFormulas as a Bevy Asset
+#[derive(Asset, TypePath, Debug)]
+pub struct CombatFormulasAsset(pub CombatFormulas);
+
+/// Wrapper for BehaviorTreeDefinition as a Bevy Asset
+#[derive(Asset, TypePath, Debug)]
+pub struct BehaviorTreeAsset(pub BehaviorTreeDefinition);
+
 // ============================================================================
 // ASSET LOADERS
 // ============================================================================
@@ -169,6 +191,141 @@ impl AssetLoader for AbilityAssetLoader {
     }
 }
 
+/// Loader for dialogue JSON files
+#[derive(Default)]
+pub struct DialogueAssetLoader;
+
+impl AssetLoader for DialogueAssetLoader {
+    type Asset = DialogueAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let dialogue: DialogueDefinition = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(DialogueAsset(dialogue))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.json"]
+    }
+}
+
+/// Loader for loot table JSON files
+#[derive(Default)]
+pub struct LootTableAssetLoader;
+
+impl AssetLoader for LootTableAssetLoader {
+    type Asset = LootTableAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let table: LootTableDefinition = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(LootTableAsset(table))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["loot.json"]
+    }
+}
+
+/// Loader for vendor shop JSON files
+#[derive(Default)]
+pub struct ShopAssetLoader;
+
+impl AssetLoader for ShopAssetLoader {
+    type Asset = ShopAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let shop: ShopDefinition = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(ShopAsset(shop))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["shop.json"]
+    }
+}
+
+/// Loader for combat formula JSON files
+#[derive(Default)]
+pub struct CombatFormulasAssetLoader;
+
+impl AssetLoader for CombatFormulasAssetLoader {
+    type Asset = CombatFormulasAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let formulas: CombatFormulas = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(CombatFormulasAsset(formulas))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["formulas.json"]
+    }
+}
+
+/// Loader for enemy behavior tree JSON files
+#[derive(Default)]
+pub struct BehaviorTreeAssetLoader;
+
+impl AssetLoader for BehaviorTreeAssetLoader {
+    type Asset = BehaviorTreeAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let tree: BehaviorTreeDefinition = serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(BehaviorTreeAsset(tree))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["behavior.json"]
+    }
+}
+
 // ============================================================================
 // ASSET HANDLE TRACKING
 // ============================================================================
@@ -181,6 +338,32 @@ pub struct LoadedContentAssets {
     pub zones: HashMap<AssetId<ZoneAsset>, Handle<ZoneAsset>>,
     pub quests: HashMap<AssetId<QuestAsset>, Handle<QuestAsset>>,
     pub abilities: HashMap<AssetId<AbilityAsset>, Handle<AbilityAsset>>,
+    pub dialogues: HashMap<AssetId<DialogueAsset>, Handle<DialogueAsset>>,
+    pub loot_tables: HashMap<AssetId<LootTableAsset>, Handle<LootTableAsset>>,
+    pub shops: HashMap<AssetId<ShopAsset>, Handle<ShopAsset>>,
+    pub formulas: HashMap<AssetId<CombatFormulasAsset>, Handle<CombatFormulasAsset>>,
+    pub behaviors: HashMap<AssetId<BehaviorTreeAsset>, Handle<BehaviorTreeAsset>>,
+}
+
+impl LoadedContentAssets {
+    /// Ask the asset server to re-read the tracked content files that `reload-data` doesn't swap
+    /// in itself (items, enemies, quests and abilities are validated and replaced as a set, see
+    /// `data_reload`). Returns how many were queued.
+    pub fn reload_unvalidated(&self, asset_server: &AssetServer) -> usize {
+        reload_handles(asset_server, &self.zones)
+            + reload_handles(asset_server, &self.dialogues)
+            + reload_handles(asset_server, &self.loot_tables)
+            + reload_handles(asset_server, &self.shops)
+            + reload_handles(asset_server, &self.formulas)
+            + reload_handles(asset_server, &self.behaviors)
+    }
+}
+
+fn reload_handles<A: Asset>(asset_server: &AssetServer, handles: &HashMap<AssetId<A>, Handle<A>>) -> usize {
+    handles.values()
+        .filter_map(|handle| handle.path())
+        .map(|path| asset_server.reload(path.clone()))
+        .count()
 }
 
 // ============================================================================
@@ -260,9 +443,88 @@ pub fn load_all_content_assets(
         }
     }
 
+    // Load all dialogue assets
+    if let Ok(entries) = std::fs::read_dir("assets/content/dialogues") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let asset_path = format!("content/dialogues/{}", path.file_name().unwrap().to_str().unwrap());
+                let handle: Handle<DialogueAsset> = asset_server.load(&asset_path);
+                info!("Loading dialogue asset: {}", asset_path);
+                loaded_assets.dialogues.insert(handle.id(), handle);
+            }
+        }
+    }
+
+    // Load all loot table assets
+    if let Ok(entries) = std::fs::read_dir("assets/content/loot") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let asset_path = format!("content/loot/{}", path.file_name().unwrap().to_str().unwrap());
+                let handle: Handle<LootTableAsset> = asset_server.load(&asset_path);
+                info!("Loading loot table asset: {}", asset_path);
+                loaded_assets.loot_tables.insert(handle.id(), handle);
+            }
+        }
+    }
+
+    // Load all shop assets
+    if let Ok(entries) = std::fs::read_dir("assets/content/shops") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let asset_path = format!("content/shops/{}", path.file_name().unwrap().to_str().unwrap());
+                let handle: Handle<ShopAsset> = asset_server.load(&asset_path);
+                info!("Loading shop asset: {}", asset_path);
+                loaded_assets.shops.insert(handle.id(), handle);
+            }
+        }
+    }
+
+    // Load combat formulas
+    if let Ok(entries) = std::fs::read_dir("assets/content/formulas") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let asset_path = format!("content/formulas/{}", path.file_name().unwrap().to_str().unwrap());
+                let handle: Handle<CombatFormulasAsset> = asset_server.load(&asset_path);
+                info!("Loading combat formulas asset: {}", asset_path);
+                loaded_assets.formulas.insert(handle.id(), handle);
+            }
+        }
+    }
+
+    // Load all enemy behavior trees
+    if let Ok(entries) = std::fs::read_dir("assets/content/behaviors") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let asset_path = format!("content/behaviors/{}", path.file_name().unwrap().to_str().unwrap());
+                let handle: Handle<BehaviorTreeAsset> = asset_server.load(&asset_path);
+                info!("Loading behavior tree asset: {}", asset_path);
+                loaded_assets.behaviors.insert(handle.id(), handle);
+            }
+        }
+    }
+
     info!("Content asset loading initiated");
 }
 
+/// Re-read zones, dialogues, loot tables, shops, formulas and behaviors from disk on request,
+/// and start loading any content files added since startup. The asset event handlers below
+/// apply the new definitions as they finish loading. Returns how many existing files were queued.
+pub fn reload_content_assets(
+    asset_server: Res<AssetServer>,
+    loaded_assets: ResMut<LoadedContentAssets>,
+) -> usize {
+    let reloaded = loaded_assets.reload_unvalidated(&asset_server);
+    info!("Reloading {} content assets", reloaded);
+    // Already-tracked files keep their handles; only new ones start loading here
+    load_all_content_assets(asset_server, loaded_assets);
+    reloaded
+}
+
 /// System to handle item asset events (loaded/modified)
 #[allow(deprecated)]
 fn handle_item_asset_events(
@@ -292,21 +554,58 @@ fn handle_item_asset_events(
     }
 }
 
+/// Spawned enemies' stats that follow their definition when it changes
+pub type SpawnedEnemyStats<'w, 's> = Query<'w, 's, (
+    &'static eryndor_shared::EnemyType,
+    &'static mut eryndor_shared::Health,
+    &'static mut eryndor_shared::MoveSpeed,
+    &'static mut eryndor_shared::CombatStats,
+    &'static mut eryndor_shared::BaseStats,
+    &'static mut eryndor_shared::AggroRange,
+    &'static mut eryndor_shared::VisualShape,
+), With<eryndor_shared::Enemy>>;
+
+/// Update every spawned enemy of this type to a changed definition. Returns how many changed.
+pub fn apply_enemy_definition(def: &EnemyDefinition, enemies: &mut SpawnedEnemyStats) -> usize {
+    let mut updated_count = 0;
+    for (enemy_type, mut health, mut move_speed, mut combat_stats, mut base_stats, mut aggro_range, mut visual) in enemies {
+        if enemy_type.0 == def.id {
+            // Update health (preserve current/max ratio if damaged)
+            let health_ratio = health.current / health.max;
+            health.max = def.max_health;
+            health.current = def.max_health * health_ratio;
+
+            // Update other stats
+            move_speed.0 = def.move_speed;
+            combat_stats.attack_power = def.attack_power;
+            combat_stats.defense = def.defense;
+            base_stats.attack_power = def.attack_power;
+            base_stats.defense = def.defense;
+            base_stats.move_speed = def.move_speed;
+            aggro_range.aggro = def.aggro_range;
+            aggro_range.leash = def.leash_range;
+
+            // Update visual
+            visual.color = def.visual.color;
+            visual.size = def.visual.size;
+            visual.shape_type = match def.visual.shape.as_str() {
+                "Square" | "Rectangle" => eryndor_shared::ShapeType::Square,
+                _ => eryndor_shared::ShapeType::Circle,
+            };
+
+            updated_count += 1;
+        }
+    }
+    updated_count
+}
+
 /// System to handle enemy asset events (loaded/modified)
 #[allow(deprecated)]
 fn handle_enemy_asset_events(
     mut events: bevy::ecs::event::EventReader<AssetEvent<EnemyAsset>>,
     enemy_assets: Res<Assets<EnemyAsset>>,
     mut enemy_db: ResMut<EnemyDatabase>,
-    mut enemies_query: Query<(
-        &eryndor_shared::EnemyType,
-        &mut eryndor_shared::Health,
-        &mut eryndor_shared::MoveSpeed,
-        &mut eryndor_shared::CombatStats,
-        &mut eryndor_shared::BaseStats,
-        &mut eryndor_shared::AggroRange,
-        &mut eryndor_shared::VisualShape,
-    ), With<eryndor_shared::Enemy>>,
+    mut enemies_query: SpawnedEnemyStats,
 ) {
     for event in events.read() {
         match event {
@@ -324,35 +623,7 @@ fn handle_enemy_asset_events(
                     enemy_db.enemies.insert(def.id, def.clone());
 
                     // Update all spawned enemies of this type
-                    let mut updated_count = 0;
-                    for (enemy_type, mut health, mut move_speed, mut combat_stats, mut base_stats, mut aggro_range, mut visual) in &mut enemies_query {
-                        if enemy_type.0 == def.id {
-                            // Update health (preserve current/max ratio if damaged)
-                            let health_ratio = health.current / health.max;
-                            health.max = def.max_health;
-                            health.current = def.max_health * health_ratio;
-
-                            // Update other stats
-                            move_speed.0 = def.move_speed;
-                            combat_stats.attack_power = def.attack_power;
-                            combat_stats.defense = def.defense;
-                            base_stats.attack_power = def.attack_power;
-                            base_stats.defense = def.defense;
-                            base_stats.move_speed = def.move_speed;
-                            aggro_range.aggro = def.aggro_range;
-                            aggro_range.leash = def.leash_range;
-
-                            // Update visual
-                            visual.color = def.visual.color;
-                            visual.size = def.visual.size;
-                            visual.shape_type = match def.visual.shape.as_str() {
-                                "Square" | "Rectangle" => eryndor_shared::ShapeType::Square,
-                                _ => eryndor_shared::ShapeType::Circle,
-                            };
-
-                            updated_count += 1;
-                        }
-                    }
+                    let updated_count = apply_enemy_definition(def, &mut enemies_query);
                     if updated_count > 0 {
                         info!("Hot-reloaded {} spawned {} enemies with new stats", updated_count, def.name);
                     }
@@ -454,6 +725,143 @@ fn handle_ability_asset_events(
     }
 }
 
+/// System to handle dialogue asset events (loaded/modified)
+#[allow(deprecated)]
+fn handle_dialogue_asset_events(
+    mut events: bevy::ecs::event::EventReader<AssetEvent<DialogueAsset>>,
+    dialogue_assets: Res<Assets<DialogueAsset>>,
+    mut dialogue_db: ResMut<DialogueDatabase>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(dialogue_asset) = dialogue_assets.get(*id) {
+                    let dialogue = &dialogue_asset.0;
+                    info!("Dialogue asset loaded/modified: {} ({} nodes)", dialogue.id, dialogue.nodes.len());
+                    dialogue_db.dialogues.insert(dialogue.id.clone(), dialogue.clone());
+                }
+            }
+            AssetEvent::Removed { id } => {
+                if let Some(dialogue_asset) = dialogue_assets.get(*id) {
+                    let dialogue_id = dialogue_asset.0.id.clone();
+                    info!("Dialogue asset removed: {}", dialogue_id);
+                    dialogue_db.dialogues.remove(&dialogue_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to handle loot table asset events (loaded/modified)
+#[allow(deprecated)]
+fn handle_loot_table_asset_events(
+    mut events: bevy::ecs::event::EventReader<AssetEvent<LootTableAsset>>,
+    loot_table_assets: Res<Assets<LootTableAsset>>,
+    mut loot_table_db: ResMut<LootTableDatabase>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(loot_table_asset) = loot_table_assets.get(*id) {
+                    let table = &loot_table_asset.0;
+                    info!("Loot table asset loaded/modified: {} ({} items, {} groups)",
+                        table.id, table.table.items.len(), table.table.groups.len());
+                    loot_table_db.tables.insert(table.id.clone(), table.clone());
+                }
+            }
+            AssetEvent::Removed { id } => {
+                if let Some(loot_table_asset) = loot_table_assets.get(*id) {
+                    let table_id = loot_table_asset.0.id.clone();
+                    info!("Loot table asset removed: {}", table_id);
+                    loot_table_db.tables.remove(&table_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to handle shop asset events (loaded/modified)
+#[allow(deprecated)]
+fn handle_shop_asset_events(
+    mut events: bevy::ecs::event::EventReader<AssetEvent<ShopAsset>>,
+    shop_assets: Res<Assets<ShopAsset>>,
+    mut shop_db: ResMut<ShopDatabase>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(shop_asset) = shop_assets.get(*id) {
+                    let shop = &shop_asset.0;
+                    info!("Shop asset loaded/modified: {} ({} items)", shop.id, shop.items.len());
+                    shop_db.shops.insert(shop.id.clone(), shop.clone());
+                }
+            }
+            AssetEvent::Removed { id } => {
+                if let Some(shop_asset) = shop_assets.get(*id) {
+                    let shop_id = shop_asset.0.id.clone();
+                    info!("Shop asset removed: {}", shop_id);
+                    shop_db.shops.remove(&shop_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to handle combat formula asset events (loaded/modified)
+#[allow(deprecated)]
+fn handle_combat_formulas_asset_events(
+    mut events: bevy::ecs::event::EventReader<AssetEvent<CombatFormulasAsset>>,
+    formulas_assets: Res<Assets<CombatFormulasAsset>>,
+    mut formulas: ResMut<CombatFormulas>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(formulas_asset) = formulas_assets.get(*id) {
+                    info!("Combat formulas loaded/modified: {}", formulas_asset.0.id);
+                    *formulas = formulas_asset.0.clone();
+                }
+            }
+            AssetEvent::Removed { .. } => {
+                info!("Combat formulas removed, reverting to defaults");
+                *formulas = CombatFormulas::default();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to handle behavior tree asset events (loaded/modified)
+#[allow(deprecated)]
+fn handle_behavior_tree_asset_events(
+    mut events: bevy::ecs::event::EventReader<AssetEvent<BehaviorTreeAsset>>,
+    tree_assets: Res<Assets<BehaviorTreeAsset>>,
+    mut tree_db: ResMut<BehaviorTreeDatabase>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                if let Some(tree_asset) = tree_assets.get(*id) {
+                    let tree = &tree_asset.0;
+                    info!("Behavior tree loaded/modified: {} ({} nodes)", tree.id, tree.root.size());
+                    tree_db.trees.insert(tree.id.clone(), tree.clone());
+                }
+            }
+            AssetEvent::Removed { id } => {
+                if let Some(tree_asset) = tree_assets.get(*id) {
+                    let tree_id = tree_asset.0.id.clone();
+                    info!("Behavior tree removed: {}", tree_id);
+                    tree_db.trees.remove(&tree_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 // ============================================================================
 // PLUGIN
 // ============================================================================
@@ -473,15 +881,30 @@ impl Plugin for GameAssetsPlugin {
             .init_asset::<ZoneAsset>()
             .init_asset::<QuestAsset>()
             .init_asset::<AbilityAsset>()
+            .init_asset::<DialogueAsset>()
+            .init_asset::<LootTableAsset>()
+            .init_asset::<ShopAsset>()
+            .init_asset::<CombatFormulasAsset>()
+            .init_asset::<BehaviorTreeAsset>()
             // Register asset loaders
             .init_asset_loader::<ItemAssetLoader>()
             .init_asset_loader::<EnemyAssetLoader>()
             .init_asset_loader::<ZoneAssetLoader>()
             .init_asset_loader::<QuestAssetLoader>()
             .init_asset_loader::<AbilityAssetLoader>()
+            .init_asset_loader::<DialogueAssetLoader>()
+            .init_asset_loader::<LootTableAssetLoader>()
+            .init_asset_loader::<ShopAssetLoader>()
+            .init_asset_loader::<CombatFormulasAssetLoader>()
+            .init_asset_loader::<BehaviorTreeAssetLoader>()
             // Initialize resources
             .init_resource::<LoadedContentAssets>()
             .init_resource::<ZoneDatabase>()
+            .init_resource::<DialogueDatabase>()
+            .init_resource::<LootTableDatabase>()
+            .init_resource::<ShopDatabase>()
+            .init_resource::<CombatFormulas>()
+            .init_resource::<BehaviorTreeDatabase>()
             // Load all content at startup
             .add_systems(Startup, load_all_content_assets)
             // Handle asset events for hot reloading
@@ -491,6 +914,11 @@ impl Plugin for GameAssetsPlugin {
                 handle_zone_asset_events,
                 handle_quest_asset_events,
                 handle_ability_asset_events,
+                handle_dialogue_asset_events,
+                handle_loot_table_asset_events,
+                handle_shop_asset_events,
+                handle_combat_formulas_asset_events,
+                handle_behavior_tree_asset_events,
             ));
     }
 }
@@ -4,7 +4,7 @@
 // Comprehensive logging system for administrative actions and security events
 // Stores logs in database for accountability and security monitoring
 
-use sqlx::SqlitePool;
+use crate::database::DbPool;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Types of actions that can be logged
@@ -16,6 +16,8 @@ pub enum AuditActionType {
     AccountLoginFailed,
     AccountBanned,
     AccountUnbanned,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
 
     // Character management
     CharacterCreated,
@@ -28,6 +30,8 @@ pub enum AuditActionType {
 
     // Content moderation
     InappropriateContentBlocked,
+    SpamBlocked,
+    PlayerAutoMuted,
 
     // Security events
     RateLimitExceeded,
@@ -43,12 +47,16 @@ impl AuditActionType {
             AuditActionType::AccountLoginFailed => "account_login_failed",
             AuditActionType::AccountBanned => "account_banned",
             AuditActionType::AccountUnbanned => "account_unbanned",
+            AuditActionType::TwoFactorEnabled => "two_factor_enabled",
+            AuditActionType::TwoFactorDisabled => "two_factor_disabled",
             AuditActionType::CharacterCreated => "character_created",
             AuditActionType::CharacterDeleted => "character_deleted",
             AuditActionType::AdminCommandExecuted => "admin_command_executed",
             AuditActionType::AdminBroadcast => "admin_broadcast",
             AuditActionType::PlayerKicked => "player_kicked",
             AuditActionType::InappropriateContentBlocked => "inappropriate_content_blocked",
+            AuditActionType::SpamBlocked => "spam_blocked",
+            AuditActionType::PlayerAutoMuted => "player_auto_muted",
             AuditActionType::RateLimitExceeded => "rate_limit_exceeded",
             AuditActionType::SuspiciousActivity => "suspicious_activity",
         }
@@ -66,7 +74,7 @@ impl AuditActionType {
 /// * `ip_address` - IP address of the actor
 /// * `details` - Additional details about the action (JSON-formatted or plain text)
 pub async fn log_audit_event(
-    pool: &SqlitePool,
+    pool: &DbPool,
     action_type: AuditActionType,
     actor_account_id: Option<i64>,
     target_account_id: Option<i64>,
@@ -83,7 +91,7 @@ pub async fn log_audit_event(
 
     let result = sqlx::query(
         "INSERT INTO audit_logs (timestamp, action_type, actor_account_id, target_account_id, target_username, ip_address, details)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
     )
     .bind(timestamp)
     .bind(action_str)
@@ -108,7 +116,7 @@ pub async fn log_audit_event(
 /// * `limit` - Maximum number of logs to retrieve
 /// * `offset` - Number of logs to skip (for pagination)
 pub async fn get_audit_logs(
-    pool: &SqlitePool,
+    pool: &DbPool,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<AuditLogEntry>, String> {
@@ -116,7 +124,7 @@ pub async fn get_audit_logs(
         "SELECT id, timestamp, action_type, actor_account_id, target_account_id, target_username, ip_address, details
          FROM audit_logs
          ORDER BY timestamp DESC
-         LIMIT ?1 OFFSET ?2"
+         LIMIT $1 OFFSET $2"
     )
     .bind(limit)
     .bind(offset)
@@ -131,16 +139,16 @@ pub async fn get_audit_logs(
 
 /// Retrieve audit logs for a specific account
 pub async fn get_audit_logs_for_account(
-    pool: &SqlitePool,
+    pool: &DbPool,
     account_id: i64,
     limit: i64,
 ) -> Result<Vec<AuditLogEntry>, String> {
     let result = sqlx::query_as::<_, AuditLogEntry>(
         "SELECT id, timestamp, action_type, actor_account_id, target_account_id, target_username, ip_address, details
          FROM audit_logs
-         WHERE actor_account_id = ?1 OR target_account_id = ?1
+         WHERE actor_account_id = $1 OR target_account_id = $1
          ORDER BY timestamp DESC
-         LIMIT ?2"
+         LIMIT $2"
     )
     .bind(account_id)
     .bind(limit)
@@ -6,6 +6,9 @@ use bevy_renet2::netcode::NetcodeServerTransport;
 use eryndor_shared::*;
 use crate::database::{self, DatabaseConnection};
 use crate::config::ServerConfig;
+use crate::metrics::TimedQuery;
+use crate::persistence::{queue_save, CharacterSaveData};
+use crate::two_factor::{self, SecondFactor, SecondFactorError};
 use sqlx::Row;
 use std::net::IpAddr;
 
@@ -29,6 +32,8 @@ pub struct ClientMetadata {
     pub ip_address: IpAddr,
     pub socket_id: usize,  // 0=UDP, 1=WebTransport, 2=WebSocket
     pub connect_time: std::time::SystemTime,
+    /// Device id the client sent with its login, if any
+    pub hardware_id: Option<String>,
 }
 
 /// System that captures client IP addresses when they connect
@@ -38,6 +43,7 @@ pub fn track_client_connections(
     network_id_map: Res<NetworkIdMap>,
     transport: Res<NetcodeServerTransport>,
     db: Res<DatabaseConnection>,
+    shutdown: Res<crate::shutdown::ShutdownState>,
     mut commands: Commands,
 ) {
     for event in server_events.read() {
@@ -45,6 +51,13 @@ pub fn track_client_connections(
             ServerEvent::ClientConnected { client_id } => {
                 // Look up the entity using NetworkIdMap
                 if let Some(&client_entity) = network_id_map.get(&NetworkId::new(*client_id)) {
+                    // No new players while the server is shutting down
+                    if shutdown.in_progress() {
+                        info!("Client {:?} turned away - server is shutting down", client_id);
+                        commands.entity(client_entity).despawn();
+                        continue;
+                    }
+
                     // Extract IP address from transport
                     if let Some((socket_id, socket_addr)) = transport.client_addr(*client_id) {
                         let ip_address = socket_addr.ip();
@@ -64,39 +77,31 @@ pub fn track_client_connections(
                             transport_name
                         );
 
-                        // CHECK FOR IP BAN
-                        if let Some(pool) = db.pool() {
-                            let runtime = tokio::runtime::Runtime::new().unwrap();
-                            let ip_str = ip_address.to_string();
-
-                            let ban_check = runtime.block_on(database::check_ip_ban(pool, &ip_str));
-                            if let Ok(Some(ban_info)) = ban_check {
-                                let message = if ban_info.is_permanent {
-                                    format!("This IP address has been permanently banned. Reason: {}", ban_info.reason)
-                                } else if let Some(expires_at) = ban_info.expires_at {
-                                    let expires_date = chrono::DateTime::from_timestamp(expires_at, 0)
-                                        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                                        .unwrap_or_else(|| "unknown time".to_string());
-                                    format!("This IP address is banned until {}. Reason: {}", expires_date, ban_info.reason)
-                                } else {
-                                    format!("This IP address has been banned. Reason: {}", ban_info.reason)
-                                };
-
-                                warn!("Banned IP {} attempted connection via {} - disconnecting", ip_address, transport_name);
-                                warn!("Ban details: {}", message);
-
-                                // Disconnect the client immediately
-                                commands.entity(client_entity).despawn();
-                                continue; // Skip adding metadata and continue to next event
-                            }
-
-                            // Attach metadata to client entity
-                            commands.entity(client_entity).insert(ClientMetadata {
-                                ip_address,
-                                socket_id,
-                                connect_time: std::time::SystemTime::now(),
-                            });
-                        }
+                        // Attach metadata to client entity
+                        commands.entity(client_entity).insert(ClientMetadata {
+                            ip_address,
+                            socket_id,
+                            connect_time: std::time::SystemTime::now(),
+                            hardware_id: None,
+                        });
+
+                        // CHECK FOR IP BAN - queued ahead of anything the client sends next
+                        let ip_str = ip_address.to_string();
+                        db.run(
+                            move |pool| async move { database::check_ip_ban(&pool, &ip_str).timed("check_ip_ban").await },
+                            move |ban_check, world| {
+                                if let Ok(Some(ban_info)) = ban_check {
+                                    let message = ban_message("This IP address", &ban_info);
+                                    warn!("Banned IP {} attempted connection via {} - disconnecting", ip_address, transport_name);
+                                    warn!("Ban details: {}", message);
+
+                                    // Disconnect the client if it hasn't left already
+                                    if let Ok(client) = world.get_entity_mut(client_entity) {
+                                        client.despawn();
+                                    }
+                                }
+                            },
+                        );
                     } else {
                         warn!("Could not get address for client {:?}", client_id);
                     }
@@ -111,19 +116,112 @@ pub fn track_client_connections(
     }
 }
 
+/// Ban notice shown to a banned IP ("This IP address") or account ("Your account"),
+/// with the ban id to quote in an appeal
+pub fn ban_message(subject: &str, ban_info: &database::BanInfo) -> String {
+    let notice = if ban_info.is_permanent {
+        format!("{} has been permanently banned. Reason: {}", subject, ban_info.reason)
+    } else if let Some(expires_at) = ban_info.expires_at {
+        let expires_date = chrono::DateTime::from_timestamp(expires_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "unknown time".to_string());
+        format!("{} is banned until {}. Reason: {}", subject, expires_date, ban_info.reason)
+    } else {
+        format!("{} has been banned. Reason: {}", subject, ban_info.reason)
+    };
+    format!("{} (appeal reference: ban #{})", notice, ban_info.id)
+}
+
+/// Device id from a login request, if it looks like one the client generated
+fn sanitize_hardware_id(hardware_id: Option<&String>) -> Option<String> {
+    let hardware_id = hardware_id?.trim();
+    let valid = !hardware_id.is_empty()
+        && hardware_id.len() <= 64
+        && hardware_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| hardware_id.to_string())
+}
+
+/// Where a login came from, checked against IP and hardware bans
+struct LoginOrigin {
+    ip_address: String,
+    hardware_id: Option<String>,
+}
+
+/// Outcome of the database side of a login
+enum LoginCheck {
+    Rejected(String),
+    Banned(String),
+    /// Password was right; the client needs to ask for a two-factor code
+    TwoFactorRequired,
+    Accepted {
+        account_id: i64,
+        is_admin: bool,
+        second_factor: SecondFactor,
+        characters: Result<Vec<CharacterData>, String>,
+    },
+}
+
+/// Ban check, admin flag and character list for an account whose credentials checked out.
+/// Bans on the account, the IP address or the device all keep the login out.
+async fn check_account(
+    pool: &database::DbPool,
+    account_id: i64,
+    origin: &LoginOrigin,
+    second_factor: SecondFactor,
+) -> LoginCheck {
+    let ban_check = database::find_active_ban(
+        pool,
+        Some(account_id),
+        Some(&origin.ip_address),
+        origin.hardware_id.as_deref(),
+    ).timed("find_active_ban").await;
+    match ban_check {
+        Ok(Some(ban_info)) => {
+            let subject = match ban_info.ban_type.as_str() {
+                "ip" => "This IP address",
+                _ => "Your account",
+            };
+            return LoginCheck::Banned(ban_message(subject, &ban_info));
+        }
+        Ok(None) => {}
+        Err(e) => error!("Ban check failed for account {}: {}", account_id, e),
+    }
+
+    if let Err(e) = database::record_login(pool, account_id, &origin.ip_address, origin.hardware_id.as_deref()).await {
+        warn!("{}", e);
+    }
+
+    let is_admin = crate::admin::is_admin(pool, account_id).await.unwrap_or(false);
+    let characters = database::get_characters(pool, account_id).timed("get_characters").await;
+    LoginCheck::Accepted { account_id, is_admin, second_factor, characters }
+}
+
+fn login_failed(commands: &mut Commands, client_entity: Entity, message: String) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: LoginResponse {
+            success: false,
+            message,
+            account_id: None,
+            is_admin: false,
+            two_factor_required: false,
+            two_factor_enabled: false,
+        },
+    });
+}
+
 pub fn handle_login(
     trigger: On<FromClient<LoginRequest>>,
     mut commands: Commands,
     db: Res<DatabaseConnection>,
-    authenticated_clients: Query<&Authenticated>,
     rate_limiters: Res<crate::RateLimiters>,
-    client_metadata: Query<&ClientMetadata>,
+    mut client_metadata: Query<&mut ClientMetadata>,
 ) {
     info!("handle_login observer triggered!");
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         warn!("Database pool not available");
         return
-    };
+    }
 
     let Some(client_entity) = trigger.client_id.entity() else {
         warn!("No client entity in trigger");
@@ -131,17 +229,9 @@ pub fn handle_login(
     };
 
     // RATE LIMIT CHECK
-    let Ok(metadata) = client_metadata.get(client_entity) else {
+    let Ok(mut metadata) = client_metadata.get_mut(client_entity) else {
         warn!("No IP address for client {:?}", client_entity);
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(trigger.client_id),
-            message: LoginResponse {
-                success: false,
-                message: "Connection error. Please try again.".to_string(),
-                account_id: None,
-                is_admin: false,
-            },
-        });
+        login_failed(&mut commands, client_entity, "Connection error. Please try again.".to_string());
         return;
     };
 
@@ -149,23 +239,13 @@ pub fn handle_login(
         warn!("Rate limit exceeded for login from IP: {}", metadata.ip_address);
 
         // Log violation to database
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let _ = runtime.block_on(database::log_rate_limit_violation(
-            pool,
-            &metadata.ip_address.to_string(),
-            "login_attempt",
-            "Rate limit exceeded"
-        ));
-
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(trigger.client_id),
-            message: LoginResponse {
-                success: false,
-                message: "Too many login attempts. Please try again later.".to_string(),
-                account_id: None,
-                is_admin: false,
-            },
+        let ip = metadata.ip_address.to_string();
+        db.execute("log_rate_limit_violation", move |pool| async move {
+            database::log_rate_limit_violation(&pool, &ip, "login_attempt", "Rate limit exceeded")
+                .timed("log_rate_limit_violation").await
         });
+
+        login_failed(&mut commands, client_entity, "Too many login attempts. Please try again later.".to_string());
         return;
     }
 
@@ -173,97 +253,113 @@ pub fn handle_login(
 
     info!("Login attempt from client {:?}: username={} (IP: {})", client_entity, request.username, metadata.ip_address);
 
-    // Verify credentials (blocking for simplicity in POC)
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let result = runtime.block_on(database::verify_credentials(pool, &request.username, &request.password));
-
-    match result {
-        Ok(account_id) => {
-            // CHECK FOR ACCOUNT BAN
-            let ban_check = runtime.block_on(database::check_account_ban(pool, account_id));
-            if let Ok(Some(ban_info)) = ban_check {
-                let message = if ban_info.is_permanent {
-                    format!("Your account has been permanently banned. Reason: {}", ban_info.reason)
-                } else if let Some(expires_at) = ban_info.expires_at {
-                    let expires_date = chrono::DateTime::from_timestamp(expires_at, 0)
-                        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                        .unwrap_or_else(|| "unknown time".to_string());
-                    format!("Your account is banned until {}. Reason: {}", expires_date, ban_info.reason)
-                } else {
-                    format!("Your account has been banned. Reason: {}", ban_info.reason)
-                };
+    metadata.hardware_id = sanitize_hardware_id(request.hardware_id.as_ref());
+    let origin = LoginOrigin {
+        ip_address: metadata.ip_address.to_string(),
+        hardware_id: metadata.hardware_id.clone(),
+    };
 
-                warn!("Banned account {} (ID: {}) attempted login", request.username, account_id);
-                commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(ClientId::Client(client_entity)),
-                    message: LoginResponse {
-                        success: false,
-                        message,
-                        account_id: None,
-                        is_admin: false,
-                    },
-                });
-                return;
+    // Verify credentials on the database worker; finish_login picks up the result
+    let username = request.username.clone();
+    let password = request.password.clone();
+    let totp_code = request.totp_code.clone();
+    let login_username = username.clone();
+    db.run(
+        move |pool| async move {
+            let account_id = match database::verify_credentials(&pool, &login_username, &password).timed("verify_credentials").await {
+                Ok(account_id) => account_id,
+                Err(e) => return LoginCheck::Rejected(e),
+            };
+            match two_factor::check_second_factor(&pool, account_id, totp_code.as_deref()).await {
+                Ok(second_factor) => check_account(&pool, account_id, &origin, second_factor).await,
+                Err(SecondFactorError::Missing) => LoginCheck::TwoFactorRequired,
+                Err(SecondFactorError::Rejected(e)) => LoginCheck::Rejected(e),
             }
+        },
+        move |check, world| {
+            if let Err(e) = world.run_system_cached_with(finish_login, (client_entity, username, check)) {
+                error!("Failed to finish login: {}", e);
+            }
+        },
+    );
+}
 
+fn finish_login(
+    In((client_entity, username, check)): In<(Entity, String, LoginCheck)>,
+    mut commands: Commands,
+    connected: Query<(), With<ConnectedClient>>,
+    authenticated_clients: Query<&Authenticated>,
+) {
+    // The client may have left while the database was busy
+    if !connected.contains(client_entity) {
+        return;
+    }
+
+    match check {
+        LoginCheck::Accepted { account_id, is_admin, second_factor, characters } => {
             // Check if this account is already logged in
             let already_logged_in = authenticated_clients.iter().any(|auth| auth.account_id == account_id);
 
             if already_logged_in {
-                warn!("Account {} (ID: {}) is already logged in, rejecting duplicate login", request.username, account_id);
-                commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(ClientId::Client(client_entity)),
-                    message: LoginResponse {
-                        success: false,
-                        message: "This account is already logged in".to_string(),
-                        account_id: None,
-                        is_admin: false,
-                    },
-                });
+                warn!("Account {} (ID: {}) is already logged in, rejecting duplicate login", username, account_id);
+                login_failed(&mut commands, client_entity, "This account is already logged in".to_string());
                 return;
             }
 
-            info!("Login successful for {} (ID: {})", request.username, account_id);
-
-            // Check if user is admin
-            let is_admin = runtime.block_on(crate::admin::is_admin(pool, account_id))
-                .unwrap_or(false);
+            info!("Login successful for {} (ID: {})", username, account_id);
 
             // Mark client as authenticated
             commands.entity(client_entity).insert(Authenticated { account_id });
 
+            let message = match second_factor {
+                SecondFactor::BackupCode { remaining } => {
+                    format!("Login successful. Backup code used - {} remaining", remaining)
+                }
+                _ => "Login successful".to_string(),
+            };
+
             // Send success response
             commands.server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
                 message: LoginResponse {
                     success: true,
-                    message: "Login successful".to_string(),
+                    message,
                     account_id: Some(account_id),
                     is_admin,
+                    two_factor_required: false,
+                    two_factor_enabled: second_factor != SecondFactor::NotEnabled,
                 },
             });
 
-            // Load and send character list
-            let chars_result = runtime.block_on(database::get_characters(pool, account_id));
-            if let Ok(characters) = chars_result {
+            // Send character list
+            if let Ok(characters) = characters {
                 commands.server_trigger(ToClients {
                     mode: SendMode::Direct(ClientId::Client(client_entity)),
                     message: CharacterListResponse { characters },
                 });
             }
         }
-        Err(e) => {
-            warn!("Login failed for {}: {}", request.username, e);
+        LoginCheck::Banned(message) => {
+            warn!("Banned account {} attempted login", username);
+            login_failed(&mut commands, client_entity, message);
+        }
+        LoginCheck::TwoFactorRequired => {
             commands.server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
                 message: LoginResponse {
                     success: false,
-                    message: e,
+                    message: "Enter the code from your authenticator app".to_string(),
                     account_id: None,
                     is_admin: false,
+                    two_factor_required: true,
+                    two_factor_enabled: true,
                 },
             });
         }
+        LoginCheck::Rejected(e) => {
+            warn!("Login failed for {}: {}", username, e);
+            login_failed(&mut commands, client_entity, e);
+        }
     }
 }
 
@@ -275,10 +371,10 @@ pub fn handle_create_account(
     client_metadata: Query<&ClientMetadata>,
 ) {
     info!("handle_create_account observer triggered!");
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         warn!("Database pool not available");
         return
-    };
+    }
 
     let Some(client_entity) = trigger.client_id.entity() else {
         warn!("No client entity in trigger");
@@ -302,13 +398,11 @@ pub fn handle_create_account(
         warn!("Rate limit exceeded for account creation from IP: {}", metadata.ip_address);
 
         // Log violation to database
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let _ = runtime.block_on(database::log_rate_limit_violation(
-            pool,
-            &metadata.ip_address.to_string(),
-            "account_creation",
-            "Rate limit exceeded"
-        ));
+        let ip = metadata.ip_address.to_string();
+        db.execute("log_rate_limit_violation", move |pool| async move {
+            database::log_rate_limit_violation(&pool, &ip, "account_creation", "Rate limit exceeded")
+                .timed("log_rate_limit_violation").await
+        });
 
         commands.server_trigger(ToClients {
             mode: SendMode::Direct(ClientId::Client(client_entity)),
@@ -342,16 +436,20 @@ pub fn handle_create_account(
         warn!("Account creation rejected - inappropriate username: {}", request.username);
 
         // AUDIT LOG: Inappropriate content blocked
-        let runtime_audit = tokio::runtime::Runtime::new().unwrap();
-        let _ = runtime_audit.block_on(crate::audit::log_audit_event(
-            pool,
-            crate::audit::AuditActionType::InappropriateContentBlocked,
-            None,
-            None,
-            Some(&request.username),
-            Some(&metadata.ip_address.to_string()),
-            Some(&format!("username moderation failed: {}", moderation_result.reason.clone().unwrap_or_default())),
-        ));
+        let username = request.username.clone();
+        let ip = metadata.ip_address.to_string();
+        let details = format!("username moderation failed: {}", moderation_result.reason.clone().unwrap_or_default());
+        db.execute("log_audit_event", move |pool| async move {
+            crate::audit::log_audit_event(
+                &pool,
+                crate::audit::AuditActionType::InappropriateContentBlocked,
+                None,
+                None,
+                Some(&username),
+                Some(&ip),
+                Some(&details),
+            ).await
+        });
 
         commands.server_trigger(ToClients {
             mode: SendMode::Direct(ClientId::Client(client_entity)),
@@ -402,74 +500,72 @@ pub fn handle_create_account(
 
     let password_hash = hash_password(&request.password);
 
-    let runtime = tokio::runtime::Runtime::new().unwrap();
     // Use validated_username from moderation check
-    let result = runtime.block_on(database::create_account(pool, &request.email, &validated_username, &password_hash));
-
-    match result {
-        Ok(account_id) => {
-            info!("Account created: {} ({})", validated_username, request.email);
-
-            // AUDIT LOG: Account creation
-            let runtime_audit = tokio::runtime::Runtime::new().unwrap();
-            let _ = runtime_audit.block_on(crate::audit::log_audit_event(
-                pool,
-                crate::audit::AuditActionType::AccountCreated,
-                Some(account_id),
-                Some(account_id),
-                Some(&validated_username),
-                Some(&metadata.ip_address.to_string()),
-                Some(&format!("email: {}", request.email)),
-            ));
-
-            commands.server_trigger(ToClients {
-                mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: CreateAccountResponse {
+    let email = request.email.clone();
+    let ip = metadata.ip_address.to_string();
+    db.run(
+        move |pool| async move {
+            let result = database::create_account(&pool, &email, &validated_username, &password_hash).timed("create_account").await;
+            if let Ok(account_id) = result {
+                // AUDIT LOG: Account creation
+                let _ = crate::audit::log_audit_event(
+                    &pool,
+                    crate::audit::AuditActionType::AccountCreated,
+                    Some(account_id),
+                    Some(account_id),
+                    Some(&validated_username),
+                    Some(&ip),
+                    Some(&format!("email: {}", email)),
+                ).await;
+                info!("Account created: {} ({})", validated_username, email);
+            }
+            result
+        },
+        move |result, world| {
+            let message = match result {
+                Ok(_) => CreateAccountResponse {
                     success: true,
                     message: "Account created successfully! Please log in.".to_string(),
                 },
-            });
-        }
-        Err(e) => {
-            warn!("Account creation failed: {}", e);
-
-            // Parse the error to provide more accurate messaging
-            let error_message = if e.contains("no such column") {
-                // Database schema issue - needs migration
-                "Server database needs updating. Please contact administrator or restart the server.".to_string()
-            } else if e.contains("UNIQUE constraint failed") {
-                // Parse which field caused the unique constraint violation
-                if e.contains("email") {
-                    "An account with this email already exists".to_string()
-                } else if e.contains("username") {
-                    "An account with this username already exists".to_string()
-                } else {
-                    "Email or username already exists".to_string()
+                Err(e) => {
+                    warn!("Account creation failed: {}", e);
+
+                    // Parse the error to provide more accurate messaging
+                    let error_message = if e.contains("no such column") {
+                        // Database schema issue - needs migration
+                        "Server database needs updating. Please contact administrator or restart the server.".to_string()
+                    } else if e.contains("UNIQUE constraint failed") || e.contains("duplicate key") {
+                        // Parse which field caused the unique constraint violation
+                        if e.contains("email") {
+                            "An account with this email already exists".to_string()
+                        } else if e.contains("username") {
+                            "An account with this username already exists".to_string()
+                        } else {
+                            "Email or username already exists".to_string()
+                        }
+                    } else {
+                        // Generic database error
+                        "Failed to create account. Please try again or contact administrator.".to_string()
+                    };
+                    CreateAccountResponse { success: false, message: error_message }
                 }
-            } else {
-                // Generic database error
-                "Failed to create account. Please try again or contact administrator.".to_string()
             };
 
-            commands.server_trigger(ToClients {
+            world.commands().server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: CreateAccountResponse {
-                    success: false,
-                    message: error_message,
-                },
+                message,
             });
-        }
-    }
+        },
+    );
 }
 
 pub fn handle_create_character(
     trigger: On<FromClient<CreateCharacterRequest>>,
     mut commands: Commands,
     clients: Query<&Authenticated>,
+    config: Res<ServerConfig>,
     db: Res<DatabaseConnection>,
 ) {
-    let Some(pool) = db.pool() else { return };
-
     let Some(client_entity) = trigger.client_id.entity() else { return };
     let request = trigger.event();
 
@@ -499,50 +595,53 @@ pub fn handle_create_character(
     // Use the filtered/trimmed name from moderation
     let validated_name = moderation_result.filtered_text;
 
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let result = runtime.block_on(database::create_character(
-        pool,
-        auth.account_id,
-        &validated_name,  // Use the validated/filtered name from moderation
-        request.class,
-    ));
+    let account_id = auth.account_id;
+    let (class, faction) = (request.class, request.faction);
+    // Characters belong to the shard they were made on
+    let shard_id = config.shard.id.clone();
+    db.run(
+        move |pool| async move {
+            database::create_character(&pool, account_id, &validated_name, class, faction, &shard_id)
+                .timed("create_character").await
+        },
+        move |result, world| {
+            let message = match result {
+                Ok(character_data) => {
+                    info!("Character created: {}", character_data.name);
+                    CreateCharacterResponse {
+                        success: true,
+                        message: "Character created successfully!".to_string(),
+                        character: Some(character_data),
+                    }
+                }
+                Err(e) => {
+                    warn!("Character creation failed: {}", e);
+                    CreateCharacterResponse {
+                        success: false,
+                        message: "Character name already exists".to_string(),
+                        character: None,
+                    }
+                }
+            };
 
-    match result {
-        Ok(character_data) => {
-            info!("Character created: {}", request.name);
-            commands.server_trigger(ToClients {
-                mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: CreateCharacterResponse {
-                    success: true,
-                    message: "Character created successfully!".to_string(),
-                    character: Some(character_data),
-                },
-            });
-        }
-        Err(e) => {
-            warn!("Character creation failed: {}", e);
-            commands.server_trigger(ToClients {
+            world.commands().server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: CreateCharacterResponse {
-                    success: false,
-                    message: "Character name already exists".to_string(),
-                    character: None,
-                },
+                message,
             });
-        }
-    }
+        },
+    );
 }
 
 pub fn handle_select_character(
     trigger: On<FromClient<SelectCharacterRequest>>,
     mut commands: Commands,
     clients: Query<&Authenticated>,
+    config: Res<ServerConfig>,
+    online: Res<crate::social::OnlineCharacters>,
     db: Res<DatabaseConnection>,
 ) {
-    let Some(pool) = db.pool() else { return };
-
     let Some(client_entity) = trigger.client_id.entity() else { return };
-    let request = trigger.event();
+    let character_id = trigger.event().character_id;
 
     // Check if client is authenticated
     let Ok(auth) = clients.get(client_entity) else {
@@ -550,79 +649,201 @@ pub fn handle_select_character(
         return;
     };
 
-    info!("Character selection: ID {}", request.character_id);
+    info!("Character selection: ID {}", character_id);
 
-    // Load character from database
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let result = runtime.block_on(database::load_character(pool, request.character_id));
+    if !crate::shards::has_room(&config, &online) {
+        warn!("Character selection refused: shard '{}' is full", config.shard.id);
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message: format!("{} is full ({} players). Try again shortly or pick another shard.",
+                    config.shard.name, config.server.max_players),
+                notification_type: NotificationType::Error,
+            },
+        });
+        return;
+    }
 
-    match result {
-        Ok((character, position, health, mana, gold)) => {
-            info!("Spawning character: {} with {} gold", character.name, gold.0);
-
-            // Load equipment, inventory, and quest log from database
-            let equipment = runtime.block_on(database::load_equipment(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load equipment: {}, using defaults", e);
-                    Equipment::default()
-                });
+    // Load character from database; spawn_loaded_character brings it into the world
+    let shard_id = config.shard.id.clone();
+    db.run(
+        move |pool| async move {
+            match database::shards::character_shard(&pool, character_id).timed("character_shard").await {
+                Ok(Some(home)) if home != shard_id => return Err(SelectError::WrongShard(home)),
+                Err(e) => return Err(SelectError::Load(e)),
+                _ => {}
+            }
+            load_character_data(&pool, character_id).await.map_err(SelectError::Load)
+        },
+        move |result, world| {
+            if let Err(e) = world.run_system_cached_with(spawn_loaded_character, (client_entity, character_id, result)) {
+                error!("Failed to spawn character: {}", e);
+            }
+        },
+    );
+}
 
-            let inventory = runtime.block_on(database::load_inventory(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load inventory: {}, using defaults", e);
-                    Inventory::new(MAX_INVENTORY_SLOTS)
-                });
+/// Why a selected character couldn't be brought into the world
+enum SelectError {
+    /// The character lives on another shard
+    WrongShard(String),
+    Load(String),
+}
 
-            let quest_log = runtime.block_on(database::load_quest_log(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load quest log: {}, using defaults", e);
-                    QuestLog::default()
-                });
+/// Everything loaded from the database to bring a character into the world
+struct LoadedCharacter {
+    character: Character,
+    position: Position,
+    health: Health,
+    mana: Mana,
+    gold: Gold,
+    equipment: Equipment,
+    inventory: Inventory,
+    quest_log: QuestLog,
+    hotbar: Hotbar,
+    learned_abilities: LearnedAbilities,
+    faction: Faction,
+    friends: Vec<(i64, String)>,
+    progression: (
+        Experience,
+        WeaponProficiency,
+        WeaponProficiencyExp,
+        ArmorProficiency,
+        ArmorProficiencyExp,
+        UnlockedArmorPassives,
+    ),
+    mute: Option<(i64, String)>,
+    guild: Option<String>,
+}
 
-            let hotbar = runtime.block_on(database::load_hotbar(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load hotbar: {}, using defaults", e);
-                    Hotbar::default()
-                });
+/// Load a character and everything attached to it, falling back to defaults for any part
+/// that fails after the character itself loaded
+async fn load_character_data(pool: &database::DbPool, character_id: i64) -> Result<LoadedCharacter, String> {
+    let (character, position, health, mana, gold) =
+        database::load_character(pool, character_id).timed("load_character").await?;
+
+    // Load equipment, inventory, and quest log from database
+    let equipment = database::load_equipment(pool, character_id).timed("load_equipment").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load equipment: {}, using defaults", e);
+            Equipment::default()
+        });
 
-            let learned_abilities = runtime.block_on(database::load_learned_abilities(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load learned abilities: {}, using defaults", e);
-                    LearnedAbilities::default()
-                });
+    let inventory = database::load_inventory(pool, character_id).timed("load_inventory").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load inventory: {}, using defaults", e);
+            Inventory::new(MAX_INVENTORY_SLOTS)
+        });
 
-            // Load progression data
-            let (experience, weapon_prof, weapon_exp, armor_prof, armor_exp, unlocked_passives) =
-                runtime.block_on(database::load_progression(pool, request.character_id))
-                .unwrap_or_else(|e| {
-                    warn!("Failed to load progression: {}, using defaults", e);
-                    // Return defaults based on character level
-                    let level = 1; // Will be overridden by Experience::new
-                    (
-                        Experience::new(level),
-                        WeaponProficiency::default(),
-                        WeaponProficiencyExp::default(),
-                        ArmorProficiency::default(),
-                        ArmorProficiencyExp::default(),
-                        UnlockedArmorPassives::default(),
-                    )
-                });
+    let quest_log = database::load_quest_log(pool, character_id).timed("load_quest_log").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load quest log: {}, using defaults", e);
+            QuestLog::default()
+        });
+
+    let hotbar = database::load_hotbar(pool, character_id).timed("load_hotbar").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load hotbar: {}, using defaults", e);
+            Hotbar::default()
+        });
+
+    let learned_abilities = database::load_learned_abilities(pool, character_id).timed("load_learned_abilities").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load learned abilities: {}, using defaults", e);
+            LearnedAbilities::default()
+        });
+
+    let faction = database::load_faction(pool, character_id).timed("load_faction").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load faction: {}, using default", e);
+            Faction::default()
+        });
+
+    let friends = database::load_friends(pool, character_id).timed("load_friends").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load friends: {}, using empty list", e);
+            Vec::new()
+        });
+
+    // Load progression data
+    let progression = database::load_progression(pool, character_id).timed("load_progression").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load progression: {}, using defaults", e);
+            // Return defaults based on character level
+            let level = 1; // Will be overridden by Experience::new
+            (
+                Experience::new(level),
+                WeaponProficiency::default(),
+                WeaponProficiencyExp::default(),
+                ArmorProficiency::default(),
+                ArmorProficiencyExp::default(),
+                UnlockedArmorPassives::default(),
+            )
+        });
+
+    let mute = database::load_mute(pool, character_id).timed("load_mute").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load mute: {}", e);
+            None
+        });
+
+    let guild = database::load_guild(pool, character_id).timed("load_guild").await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load guild: {}", e);
+            None
+        });
+
+    Ok(LoadedCharacter {
+        character,
+        position,
+        health,
+        mana,
+        gold,
+        equipment,
+        inventory,
+        quest_log,
+        hotbar,
+        learned_abilities,
+        faction,
+        friends,
+        progression,
+        mute,
+        guild,
+    })
+}
+
+fn spawn_loaded_character(
+    In((client_entity, character_id, result)): In<(Entity, i64, Result<LoadedCharacter, SelectError>)>,
+    mut commands: Commands,
+    clients: Query<Option<&ActiveCharacterEntity>, (With<ConnectedClient>, With<Authenticated>)>,
+) {
+    // The client may have left, or already be playing, by the time the load finishes
+    let Ok(active_character) = clients.get(client_entity) else { return };
+    if active_character.is_some() {
+        warn!("Client {:?} already has an active character, ignoring selection", client_entity);
+        return;
+    }
+
+    match result {
+        Ok(loaded) => {
+            info!("Spawning character: {} with {} gold", loaded.character.name, loaded.gold.0);
 
             // Use character module to spawn character with all components
             let character_entity = crate::character::spawn_character_components(
                 &mut commands,
-                character,
-                position,
-                health,
-                mana,
-                equipment,
-                inventory,
-                quest_log,
+                loaded.character,
+                loaded.position,
+                loaded.health,
+                loaded.mana,
+                loaded.equipment,
+                loaded.inventory,
+                loaded.quest_log,
                 client_entity,
-                request.character_id,
+                character_id,
             );
 
             // Override default progression components with loaded data
+            let (experience, weapon_prof, weapon_exp, armor_prof, armor_exp, unlocked_passives) = loaded.progression;
             commands.entity(character_entity).insert((
                 experience,
                 weapon_prof,
@@ -630,30 +851,46 @@ pub fn handle_select_character(
                 armor_prof,
                 armor_exp,
                 unlocked_passives,
-                hotbar,
-                learned_abilities,
-                gold,
+                loaded.hotbar,
+                loaded.learned_abilities,
+                loaded.gold,
+                loaded.faction,
+                crate::social::FriendList { friends: loaded.friends },
             ));
 
+            if let Some((until, reason)) = loaded.mute {
+                commands.entity(character_entity).insert(crate::chat::ChatMute { until, reason });
+            }
+            if let Some(guild) = loaded.guild {
+                commands.entity(character_entity).insert(crate::groups::GuildName(guild));
+            }
+
             // Link client to character
             commands.entity(client_entity).insert(ActiveCharacterEntity(character_entity));
 
             // Tell the client which character was selected
             commands.server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: SelectCharacterResponse {
-                    character_id: request.character_id,
-                },
+                message: SelectCharacterResponse { character_id },
             });
 
             info!("Character spawned: entity {:?}", character_entity);
         }
         Err(e) => {
-            warn!("Failed to load character: {}", e);
+            let message = match e {
+                SelectError::WrongShard(shard_id) => {
+                    warn!("Character {} lives on shard '{}', not this one", character_id, shard_id);
+                    format!("This character lives on shard '{}'. Switch shards to play it.", shard_id)
+                }
+                SelectError::Load(e) => {
+                    warn!("Failed to load character: {}", e);
+                    "Failed to load character".to_string()
+                }
+            };
             commands.server_trigger(ToClients {
                 mode: SendMode::Direct(ClientId::Client(client_entity)),
                 message: NotificationEvent {
-                    message: "Failed to load character".to_string(),
+                    message,
                     notification_type: NotificationType::Error,
                 },
             });
@@ -665,30 +902,13 @@ pub fn handle_client_disconnect(
     mut commands: Commands,
     mut disconnected: RemovedComponents<ConnectedClient>,
     authenticated: Query<&Authenticated>,
-    characters: Query<(
-        Entity,
-        &OwnedBy,
-        &Character,
-        &Position,
-        &Health,
-        &Mana,
-        &CharacterDatabaseId,
-        &Equipment,
-        &Inventory,
-        &QuestLog,
-        &Gold,
-    )>,
-    progression: Query<(
-        &Experience,
-        &WeaponProficiency,
-        &WeaponProficiencyExp,
-        &ArmorProficiency,
-        &ArmorProficiencyExp,
-        &UnlockedArmorPassives,
-    )>,
+    characters: Query<CharacterSaveData>,
     db: Res<DatabaseConnection>,
 ) {
-    let Some(pool) = db.pool() else { return };
+    let _timer = crate::metrics::time_system("auth::handle_client_disconnect");
+    if db.pool().is_none() {
+        return;
+    }
 
     for client_entity in disconnected.read() {
         // Log account info if available
@@ -699,79 +919,20 @@ pub fn handle_client_disconnect(
         }
 
         // Find and despawn their character using OwnedBy component
-        let mut found_character = false;
-        for (entity, owned_by, character, position, health, mana, db_id, equipment, inventory, quest_log, gold) in characters.iter() {
-            if owned_by.0 == client_entity {
-                // Get progression components
-                let Ok((experience, weapon_prof, weapon_exp, armor_prof, armor_exp, unlocked_passives)) =
-                    progression.get(entity) else {
-                    error!("Failed to get progression components for character '{}'", character.name);
-                    continue;
-                };
-                found_character = true;
-                info!("Saving character '{}' (DB ID: {}) at position ({:.1}, {:.1}) with {} gold",
-                    character.name, db_id.0, position.0.x, position.0.y, gold.0);
-
-                // Save all character data to database
-                let runtime = tokio::runtime::Runtime::new().unwrap();
-
-                // Save basic character data
-                match runtime.block_on(database::save_character(
-                    pool,
-                    db_id.0,
-                    position,
-                    health,
-                    mana,
-                    gold,
-                )) {
-                    Ok(_) => info!("Character '{}' basic data saved", character.name),
-                    Err(e) => error!("Failed to save character '{}': {}", character.name, e),
-                }
-
-                // Save equipment
-                match runtime.block_on(database::save_equipment(pool, db_id.0, equipment)) {
-                    Ok(_) => info!("Character '{}' equipment saved", character.name),
-                    Err(e) => error!("Failed to save equipment for '{}': {}", character.name, e),
-                }
-
-                // Save inventory
-                match runtime.block_on(database::save_inventory(pool, db_id.0, inventory)) {
-                    Ok(_) => info!("Character '{}' inventory saved", character.name),
-                    Err(e) => error!("Failed to save inventory for '{}': {}", character.name, e),
-                }
-
-                // Save quest log
-                match runtime.block_on(database::save_quest_log(pool, db_id.0, quest_log)) {
-                    Ok(_) => info!("Character '{}' quest log saved", character.name),
-                    Err(e) => error!("Failed to save quest log for '{}': {}", character.name, e),
-                }
-
-                // Save progression data
-                match runtime.block_on(database::save_progression(
-                    pool,
-                    db_id.0,
-                    character.level,
-                    experience,
-                    weapon_prof,
-                    weapon_exp,
-                    armor_prof,
-                    armor_exp,
-                    unlocked_passives,
-                )) {
-                    Ok(_) => info!("Character '{}' progression saved (level: {})", character.name, character.level),
-                    Err(e) => error!("Failed to save progression for '{}': {}", character.name, e),
-                }
+        let Some(data) = characters.iter().find(|data| data.owned_by.0 == client_entity) else {
+            info!("Client had no active character");
+            continue;
+        };
+        info!("Saving character '{}' (DB ID: {}) at position ({:.1}, {:.1}) with {} gold",
+            data.character.name, data.db_id.0, data.position.0.x, data.position.0.y, data.gold.0);
 
-                // Despawn character - this will replicate to all clients
-                commands.entity(entity).despawn();
-                info!("Character '{}' despawned from world (will replicate to all clients)", character.name);
-                break;
-            }
-        }
+        // The save works from a snapshot, so the character can go right away
+        queue_save(&db, &data, "saved on disconnect");
 
-        if !found_character {
-            info!("Client had no active character");
-        }
+        // Despawn character - this will replicate to all clients
+        commands.entity(data.entity).despawn();
+        crate::death::remove_corpses(&mut commands, data.entity);
+        info!("Character '{}' despawned from world (will replicate to all clients)", data.character.name);
     }
 }
 
@@ -779,30 +940,12 @@ pub fn handle_disconnect_character(
     trigger: On<FromClient<DisconnectCharacterRequest>>,
     mut commands: Commands,
     clients: Query<&Authenticated>,
-    characters: Query<(
-        Entity,
-        &OwnedBy,
-        &Character,
-        &Position,
-        &Health,
-        &Mana,
-        &CharacterDatabaseId,
-        &Equipment,
-        &Inventory,
-        &QuestLog,
-        &Gold,
-    )>,
-    progression: Query<(
-        &Experience,
-        &WeaponProficiency,
-        &WeaponProficiencyExp,
-        &ArmorProficiency,
-        &ArmorProficiencyExp,
-        &UnlockedArmorPassives,
-    )>,
+    characters: Query<CharacterSaveData>,
     db: Res<DatabaseConnection>,
 ) {
-    let Some(pool) = db.pool() else { return };
+    if db.pool().is_none() {
+        return;
+    }
 
     let Some(client_entity) = trigger.client_id.entity() else { return };
 
@@ -815,80 +958,21 @@ pub fn handle_disconnect_character(
     info!("Client {:?} (Account ID: {}) requested disconnect from character", client_entity, auth.account_id);
 
     // Find and save/despawn their character
-    for (entity, owned_by, character, position, health, mana, db_id, equipment, inventory, quest_log, gold) in characters.iter() {
-        if owned_by.0 == client_entity {
-            // Get progression components
-            let Ok((experience, weapon_prof, weapon_exp, armor_prof, armor_exp, unlocked_passives)) =
-                progression.get(entity) else {
-                error!("Failed to get progression components for character '{}'", character.name);
-                // Remove ActiveCharacterEntity link even if we fail to save progression
-                commands.entity(client_entity).remove::<ActiveCharacterEntity>();
-                return;
-            };
-            info!("Disconnecting character '{}' (DB ID: {}) from client {:?}", character.name, db_id.0, client_entity);
-
-            // Save all character data to database
-            let runtime = tokio::runtime::Runtime::new().unwrap();
-
-            // Save basic character data
-            match runtime.block_on(database::save_character(
-                pool,
-                db_id.0,
-                position,
-                health,
-                mana,
-                gold,
-            )) {
-                Ok(_) => info!("Character '{}' basic data saved with {} gold", character.name, gold.0),
-                Err(e) => error!("Failed to save character '{}': {}", character.name, e),
-            }
-
-            // Save equipment
-            match runtime.block_on(database::save_equipment(pool, db_id.0, equipment)) {
-                Ok(_) => info!("Character '{}' equipment saved", character.name),
-                Err(e) => error!("Failed to save equipment for '{}': {}", character.name, e),
-            }
-
-            // Save inventory
-            match runtime.block_on(database::save_inventory(pool, db_id.0, inventory)) {
-                Ok(_) => info!("Character '{}' inventory saved", character.name),
-                Err(e) => error!("Failed to save inventory for '{}': {}", character.name, e),
-            }
-
-            // Save quest log
-            match runtime.block_on(database::save_quest_log(pool, db_id.0, quest_log)) {
-                Ok(_) => info!("Character '{}' quest log saved", character.name),
-                Err(e) => error!("Failed to save quest log for '{}': {}", character.name, e),
-            }
-
-            // Save progression data
-            match runtime.block_on(database::save_progression(
-                pool,
-                db_id.0,
-                character.level,
-                experience,
-                weapon_prof,
-                weapon_exp,
-                armor_prof,
-                armor_exp,
-                unlocked_passives,
-            )) {
-                Ok(_) => info!("Character '{}' progression saved (level: {})", character.name, character.level),
-                Err(e) => error!("Failed to save progression for '{}': {}", character.name, e),
-            }
-
-            // Despawn character - this will replicate to all clients
-            commands.entity(entity).despawn();
-            info!("Character '{}' despawned (will replicate to all clients)", character.name);
+    let Some(data) = characters.iter().find(|data| data.owned_by.0 == client_entity) else {
+        warn!("Client {:?} requested disconnect but had no active character", client_entity);
+        return;
+    };
+    info!("Disconnecting character '{}' (DB ID: {}) from client {:?}", data.character.name, data.db_id.0, client_entity);
 
-            // Remove ActiveCharacterEntity link
-            commands.entity(client_entity).remove::<ActiveCharacterEntity>();
+    queue_save(&db, &data, "saved on logout");
 
-            return;
-        }
-    }
+    // Despawn character - this will replicate to all clients
+    commands.entity(data.entity).despawn();
+    crate::death::remove_corpses(&mut commands, data.entity);
+    info!("Character '{}' despawned (will replicate to all clients)", data.character.name);
 
-    warn!("Client {:?} requested disconnect but had no active character", client_entity);
+    // Remove ActiveCharacterEntity link
+    commands.entity(client_entity).remove::<ActiveCharacterEntity>();
 }
 
 // Simple password hashing (use argon2 in production)
@@ -910,20 +994,31 @@ fn hash_password(password: &str) -> String {
 // OAUTH AUTHENTICATION HANDLERS
 // ============================================================================
 
+fn oauth_failed(commands: &mut Commands, client_entity: Entity, message: String) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: OAuthLoginResponse {
+            success: false,
+            message,
+            account_id: None,
+        },
+    });
+}
+
 pub fn handle_oauth_login(
     trigger: On<FromClient<OAuthLoginRequest>>,
     mut commands: Commands,
     db: Res<DatabaseConnection>,
     config: Res<ServerConfig>,
     rate_limiters: Res<crate::RateLimiters>,
-    client_metadata: Query<&ClientMetadata>,
+    mut client_metadata: Query<&mut ClientMetadata>,
 ) {
     info!("handle_oauth_login observer triggered!");
 
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         warn!("Database pool not available");
         return;
-    };
+    }
 
     let Some(client_entity) = trigger.client_id.entity() else {
         warn!("No client entity in trigger");
@@ -931,16 +1026,9 @@ pub fn handle_oauth_login(
     };
 
     // RATE LIMIT CHECK
-    let Ok(metadata) = client_metadata.get(client_entity) else {
+    let Ok(mut metadata) = client_metadata.get_mut(client_entity) else {
         warn!("No IP address for client {:?}", client_entity);
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(ClientId::Client(client_entity)),
-            message: OAuthLoginResponse {
-                success: false,
-                message: "Connection error. Please try again.".to_string(),
-                account_id: None,
-            },
-        });
+        oauth_failed(&mut commands, client_entity, "Connection error. Please try again.".to_string());
         return;
     };
 
@@ -948,22 +1036,13 @@ pub fn handle_oauth_login(
         warn!("Rate limit exceeded for OAuth login from IP: {}", metadata.ip_address);
 
         // Log violation to database
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let _ = runtime.block_on(database::log_rate_limit_violation(
-            pool,
-            &metadata.ip_address.to_string(),
-            "oauth_login_attempt",
-            "Rate limit exceeded"
-        ));
-
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(ClientId::Client(client_entity)),
-            message: OAuthLoginResponse {
-                success: false,
-                message: "Too many login attempts. Please try again later.".to_string(),
-                account_id: None,
-            },
+        let ip = metadata.ip_address.to_string();
+        db.execute("log_rate_limit_violation", move |pool| async move {
+            database::log_rate_limit_violation(&pool, &ip, "oauth_login_attempt", "Rate limit exceeded")
+                .timed("log_rate_limit_violation").await
         });
+
+        oauth_failed(&mut commands, client_entity, "Too many login attempts. Please try again later.".to_string());
         return;
     }
 
@@ -971,154 +1050,125 @@ pub fn handle_oauth_login(
     info!("OAuth login attempt with provider: {} (IP: {})", request.provider, metadata.ip_address);
 
     if request.provider != "google" {
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(ClientId::Client(client_entity)),
-            message: OAuthLoginResponse {
-                success: false,
-                message: "Unsupported OAuth provider".to_string(),
-                account_id: None,
-            },
-        });
+        oauth_failed(&mut commands, client_entity, "Unsupported OAuth provider".to_string());
         return;
     }
 
     // Check if OAuth is enabled
     if !config.oauth.is_google_enabled() {
-        commands.server_trigger(ToClients {
-            mode: SendMode::Direct(ClientId::Client(client_entity)),
-            message: OAuthLoginResponse {
-                success: false,
-                message: "Google OAuth is not configured".to_string(),
-                account_id: None,
-            },
-        });
+        oauth_failed(&mut commands, client_entity, "Google OAuth is not configured".to_string());
         return;
     }
 
-    // Verify the token with Google
-    let runtime = tokio::runtime::Runtime::new().unwrap();
+    metadata.hardware_id = sanitize_hardware_id(request.hardware_id.as_ref());
+    let origin = LoginOrigin {
+        ip_address: metadata.ip_address.to_string(),
+        hardware_id: metadata.hardware_id.clone(),
+    };
+
+    // Verify the token against Google's API without holding up the database queue
     let token = request.token.clone();
     let client_id = config.oauth.google_client_id.clone();
-
-    // Verify token against Google's API
-    let verification_result = runtime.block_on(async {
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://oauth2.googleapis.com/tokeninfo")
-            .query(&[("access_token", &token)])
-            .send()
-            .await;
-
-        let response = match response {
-            Ok(r) => r,
-            Err(e) => return Err(format!("Failed to verify token: {}", e)),
-        };
-
-        if !response.status().is_success() {
-            return Err("Token verification failed".to_string());
+    db.spawn(verify_google_token(token, client_id), move |verification_result, world| {
+        match verification_result {
+            Ok((google_id, email, name)) => {
+                world.resource::<DatabaseConnection>().run(
+                    move |pool| async move { oauth_account(&pool, &google_id, &email, &name, &origin).await },
+                    move |check, world| finish_oauth_login(world.commands(), client_entity, check),
+                );
+            }
+            Err(e) => {
+                warn!("OAuth verification failed: {}", e);
+                oauth_failed(&mut world.commands(), client_entity, format!("Authentication failed: {}", e));
+            }
         }
+    });
+}
 
-        let token_info: serde_json::Value = match response.json().await {
-            Ok(info) => info,
-            Err(e) => return Err(format!("Failed to parse token info: {}", e)),
-        };
+/// Check a Google access token, returning the account's (google id, email, name)
+async fn verify_google_token(token: String, client_id: String) -> Result<(String, String, String), String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://oauth2.googleapis.com/tokeninfo")
+        .query(&[("access_token", &token)])
+        .send()
+        .await;
+
+    let response = match response {
+        Ok(r) => r,
+        Err(e) => return Err(format!("Failed to verify token: {}", e)),
+    };
 
-        // Verify the token is for our app
-        let aud = token_info["aud"].as_str().unwrap_or("");
-        if aud != client_id {
-            return Err("Invalid audience".to_string());
-        }
+    if !response.status().is_success() {
+        return Err("Token verification failed".to_string());
+    }
 
-        // Extract user info
-        let google_id = token_info["sub"].as_str().unwrap_or("").to_string();
-        let email = token_info["email"].as_str().unwrap_or("").to_string();
-        let name = token_info["name"].as_str().unwrap_or("Google User").to_string();
+    let token_info: serde_json::Value = match response.json().await {
+        Ok(info) => info,
+        Err(e) => return Err(format!("Failed to parse token info: {}", e)),
+    };
 
-        if google_id.is_empty() || email.is_empty() {
-            return Err("Missing required user information".to_string());
-        }
+    // Verify the token is for our app
+    let aud = token_info["aud"].as_str().unwrap_or("");
+    if aud != client_id {
+        return Err("Invalid audience".to_string());
+    }
 
-        Ok((google_id, email, name))
-    });
+    // Extract user info
+    let google_id = token_info["sub"].as_str().unwrap_or("").to_string();
+    let email = token_info["email"].as_str().unwrap_or("").to_string();
+    let name = token_info["name"].as_str().unwrap_or("Google User").to_string();
 
-    match verification_result {
-        Ok((google_id, email, name)) => {
-            // Check if account with this OAuth ID already exists
-            let account_result = runtime.block_on(
-                database::find_account_by_oauth(pool, "google", &google_id)
-            );
+    if google_id.is_empty() || email.is_empty() {
+        return Err("Missing required user information".to_string());
+    }
 
-            let account_id = match account_result {
-                Ok(Some(id)) => {
-                    // Account exists, log them in
-                    info!("Existing OAuth account logged in: {}", email);
+    Ok((google_id, email, name))
+}
+
+/// Find or create the account for a verified Google user
+async fn oauth_account(
+    pool: &database::DbPool,
+    google_id: &str,
+    email: &str,
+    name: &str,
+    origin: &LoginOrigin,
+) -> LoginCheck {
+    // Check if account with this OAuth ID already exists
+    let account_id = match database::find_account_by_oauth(pool, "google", google_id).await {
+        Ok(Some(id)) => {
+            // Account exists, log them in
+            info!("Existing OAuth account logged in: {}", email);
+            id
+        }
+        Ok(None) => {
+            // New OAuth user - create account
+            match database::create_oauth_account(pool, email, name, "google", google_id).await {
+                Ok(id) => {
+                    info!("New OAuth account created: {}", email);
                     id
                 }
-                Ok(None) => {
-                    // New OAuth user - create account
-                    match runtime.block_on(
-                        database::create_oauth_account(pool, &email, &name, "google", &google_id)
-                    ) {
-                        Ok(id) => {
-                            info!("New OAuth account created: {}", email);
-                            id
-                        }
-                        Err(e) => {
-                            error!("Failed to create OAuth account: {}", e);
-                            commands.server_trigger(ToClients {
-                                mode: SendMode::Direct(ClientId::Client(client_entity)),
-                                message: OAuthLoginResponse {
-                                    success: false,
-                                    message: "Failed to create account".to_string(),
-                                    account_id: None,
-                                },
-                            });
-                            return;
-                        }
-                    }
-                }
                 Err(e) => {
-                    error!("Database error: {}", e);
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(ClientId::Client(client_entity)),
-                        message: OAuthLoginResponse {
-                            success: false,
-                            message: "Database error".to_string(),
-                            account_id: None,
-                        },
-                    });
-                    return;
+                    error!("Failed to create OAuth account: {}", e);
+                    return LoginCheck::Rejected("Failed to create account".to_string());
                 }
-            };
-
-            // CHECK FOR ACCOUNT BAN
-            let ban_check = runtime.block_on(database::check_account_ban(pool, account_id));
-            if let Ok(Some(ban_info)) = ban_check {
-                let message = if ban_info.is_permanent {
-                    format!("Your account has been permanently banned. Reason: {}", ban_info.reason)
-                } else if let Some(expires_at) = ban_info.expires_at {
-                    let expires_date = chrono::DateTime::from_timestamp(expires_at, 0)
-                        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                        .unwrap_or_else(|| "unknown time".to_string());
-                    format!("Your account is banned until {}. Reason: {}", expires_date, ban_info.reason)
-                } else {
-                    format!("Your account has been banned. Reason: {}", ban_info.reason)
-                };
-
-                warn!("Banned account (ID: {}) attempted OAuth login", account_id);
-                commands.server_trigger(ToClients {
-                    mode: SendMode::Direct(ClientId::Client(client_entity)),
-                    message: OAuthLoginResponse {
-                        success: false,
-                        message,
-                        account_id: None,
-                    },
-                });
-                return;
             }
+        }
+        Err(e) => {
+            error!("Database error: {}", e);
+            return LoginCheck::Rejected("Database error".to_string());
+        }
+    };
+
+    // The provider handles the second factor for OAuth accounts
+    check_account(pool, account_id, origin, SecondFactor::NotEnabled).await
+}
 
+fn finish_oauth_login(mut commands: Commands, client_entity: Entity, check: LoginCheck) {
+    match check {
+        LoginCheck::Accepted { account_id, characters, .. } => {
             // Send character list
-            match runtime.block_on(database::get_characters(pool, account_id)) {
+            match characters {
                 Ok(characters) => {
                     commands.server_trigger(ToClients {
                         mode: SendMode::Direct(ClientId::Client(client_entity)),
@@ -1139,16 +1189,13 @@ pub fn handle_oauth_login(
                 },
             });
         }
-        Err(e) => {
-            warn!("OAuth verification failed: {}", e);
-            commands.server_trigger(ToClients {
-                mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: OAuthLoginResponse {
-                    success: false,
-                    message: format!("Authentication failed: {}", e),
-                    account_id: None,
-                },
-            });
+        LoginCheck::Banned(message) => {
+            warn!("Banned account attempted OAuth login");
+            oauth_failed(&mut commands, client_entity, message);
+        }
+        LoginCheck::Rejected(message) => oauth_failed(&mut commands, client_entity, message),
+        LoginCheck::TwoFactorRequired => {
+            oauth_failed(&mut commands, client_entity, "Two-factor code required".to_string());
         }
     }
 }
@@ -0,0 +1,107 @@
+//! Ban enforcement outside the login path.
+//!
+//! New connections and logins are checked in `auth`. This module covers the rest: players
+//! who are online when a ban lands are told why and disconnected, and temporary bans are
+//! marked inactive once they run out so ban lists only show bans that still apply.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::net::IpAddr;
+use crate::auth::{Authenticated, ClientMetadata};
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+use crate::social::notify;
+
+/// How long a banned client keeps its connection, so the ban notice reaches it first
+const DISCONNECT_DELAY_SECONDS: f32 = 1.0;
+
+/// What a new ban matches, for finding players who are online right now
+#[derive(Default)]
+pub struct BanMatch {
+    pub account_id: Option<i64>,
+    pub ip_address: Option<IpAddr>,
+    pub hardware_id: Option<String>,
+}
+
+impl BanMatch {
+    fn matches(&self, auth: Option<&Authenticated>, metadata: Option<&ClientMetadata>) -> bool {
+        let account = self.account_id.is_some() && auth.map(|auth| auth.account_id) == self.account_id;
+        let ip = self.ip_address.is_some() && metadata.map(|m| m.ip_address) == self.ip_address;
+        let hardware = self.hardware_id.is_some()
+            && metadata.and_then(|m| m.hardware_id.as_ref()) == self.hardware_id.as_ref();
+        account || ip || hardware
+    }
+}
+
+/// Client that has been banned or kicked and is dropped when the timer runs out
+#[derive(Component)]
+pub struct PendingDisconnect(Timer);
+
+/// Send the ban notice to every connected client the ban matches and schedule their
+/// disconnect. Returns how many clients were affected.
+pub fn disconnect_banned_clients(world: &mut World, ban: &BanMatch, message: &str) -> usize {
+    let clients: Vec<Entity> = world
+        .query_filtered::<(Entity, Option<&Authenticated>, Option<&ClientMetadata>), With<ConnectedClient>>()
+        .iter(world)
+        .filter(|(_, auth, metadata)| ban.matches(*auth, *metadata))
+        .map(|(entity, _, _)| entity)
+        .collect();
+
+    let mut commands = world.commands();
+    for &client in &clients {
+        disconnect_with_notice(&mut commands, client, message.to_string());
+    }
+    clients.len()
+}
+
+/// Tell a client why it's being dropped and disconnect it once the message has gone out
+pub fn disconnect_with_notice(commands: &mut Commands, client: Entity, message: String) {
+    notify(commands, client, message, NotificationType::Error);
+    commands.entity(client).try_insert(PendingDisconnect(Timer::from_seconds(DISCONNECT_DELAY_SECONDS, TimerMode::Once)));
+}
+
+/// Drop banned and kicked clients once their notice has had time to go out. Despawning the client
+/// disconnects it, and `handle_client_disconnect` saves its character as usual.
+pub fn disconnect_pending_clients(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending: Query<(Entity, &mut PendingDisconnect)>,
+) {
+    for (client, mut timer) in &mut pending {
+        if timer.0.tick(time.delta()).is_finished() {
+            info!("Disconnecting client {:?}", client);
+            commands.entity(client).despawn();
+        }
+    }
+}
+
+/// Deactivate temporary bans that have run out
+pub fn expire_bans(db: Res<DatabaseConnection>) {
+    db.run(
+        |pool| async move {
+            let expired = database::expire_bans(&pool).timed("expire_bans").await?;
+            for (ban_id, target) in &expired {
+                // AUDIT LOG: Ban lifted by expiry
+                let _ = crate::audit::log_audit_event(
+                    &pool,
+                    crate::audit::AuditActionType::AccountUnbanned,
+                    None,
+                    None,
+                    Some(target),
+                    None,
+                    Some(&format!("ban #{} expired", ban_id)),
+                ).await;
+            }
+            Ok::<_, String>(expired)
+        },
+        |result, _world| match result {
+            Ok(expired) => {
+                for (ban_id, target) in expired {
+                    info!("Ban #{} on '{}' expired", ban_id, target);
+                }
+            }
+            Err(e) => error!("{}", e),
+        },
+    );
+}
@@ -0,0 +1,415 @@
+//! Behavior trees for enemy AI.
+//!
+//! Trees are authored as `content/behaviors/*.behavior.json` and attached to enemy types
+//! through `EnemyDefinition::behavior`. They run before `combat::enemy_ai` each frame.
+//! Most nodes only make decisions; movement actions (flee, patrol) take control of the
+//! enemy for that frame, otherwise the threat-driven chase/attack logic carries on as usual.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::abilities::AbilityDatabase;
+use crate::combat_formulas::{AttackerProfile, CombatFormulas, DefenderProfile};
+use crate::game_data::{EnemyDatabase, ItemDatabase};
+use crate::spatial::SpatialIndex;
+use crate::spawn::SpawnPoint;
+use crate::threat::{ThreatEvent, ThreatTable};
+
+/// Patrolling enemies count as arrived within this distance (pixels)
+const PATROL_ARRIVE_DISTANCE: f32 = 6.0;
+
+/// Behavior trees authored in the editor, keyed by tree id
+#[derive(Resource, Default)]
+pub struct BehaviorTreeDatabase {
+    pub trees: HashMap<String, BehaviorTreeDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BehaviorTreeDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub root: BehaviorNode,
+    /// Node positions and other editor-only data; ignored by the server
+    #[serde(default)]
+    pub editor_layout: serde_json::Value,
+}
+
+/// A behavior tree node. Composites and decorators wrap other nodes;
+/// conditions and actions are leaves.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum BehaviorNode {
+    /// Runs children in order until one succeeds or is running
+    Selector { children: Vec<BehaviorNode> },
+    /// Runs children in order until one fails or is running
+    Sequence { children: Vec<BehaviorNode> },
+    /// Swaps success and failure
+    Inverter { child: Box<BehaviorNode> },
+    /// Fails without running the child until `seconds` after the child last succeeded
+    Cooldown { seconds: f32, child: Box<BehaviorNode> },
+    /// Current health below a fraction of max
+    HealthBelow { fraction: f32 },
+    /// Has anyone on its threat table
+    InCombat,
+    /// Current target closer than `distance` pixels
+    TargetWithin { distance: f32 },
+    /// Succeeds with the given probability each evaluation
+    Chance { probability: f32 },
+    /// Run away from the current target
+    Flee { speed_multiplier: f32 },
+    /// Pull idle allies within `radius` pixels into the fight
+    CallForHelp { radius: f32 },
+    /// Use an ability on the current target (or self for heals)
+    UseAbility { ability_id: u32 },
+    /// Wander between random points around spawn, pausing at each
+    Patrol { radius: f32, pause: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BehaviorStatus {
+    Success,
+    Failure,
+    Running,
+}
+
+impl BehaviorNode {
+    /// Number of nodes in this subtree; used to give every node a stable index
+    pub fn size(&self) -> usize {
+        1 + match self {
+            BehaviorNode::Selector { children } | BehaviorNode::Sequence { children } =>
+                children.iter().map(BehaviorNode::size).sum(),
+            BehaviorNode::Inverter { child } | BehaviorNode::Cooldown { child, .. } => child.size(),
+            _ => 0,
+        }
+    }
+
+    fn tick(&self, index: usize, bb: &mut Blackboard) -> BehaviorStatus {
+        match self {
+            BehaviorNode::Selector { children } => {
+                let mut child_index = index + 1;
+                for child in children {
+                    let status = child.tick(child_index, bb);
+                    if status != BehaviorStatus::Failure {
+                        return status;
+                    }
+                    child_index += child.size();
+                }
+                BehaviorStatus::Failure
+            }
+            BehaviorNode::Sequence { children } => {
+                let mut child_index = index + 1;
+                for child in children {
+                    let status = child.tick(child_index, bb);
+                    if status != BehaviorStatus::Success {
+                        return status;
+                    }
+                    child_index += child.size();
+                }
+                BehaviorStatus::Success
+            }
+            BehaviorNode::Inverter { child } => match child.tick(index + 1, bb) {
+                BehaviorStatus::Success => BehaviorStatus::Failure,
+                BehaviorStatus::Failure => BehaviorStatus::Success,
+                BehaviorStatus::Running => BehaviorStatus::Running,
+            },
+            BehaviorNode::Cooldown { seconds, child } => {
+                if bb.memory.node_ready_at.get(&index).is_some_and(|ready_at| bb.now < *ready_at) {
+                    return BehaviorStatus::Failure;
+                }
+                let status = child.tick(index + 1, bb);
+                if status == BehaviorStatus::Success {
+                    bb.memory.node_ready_at.insert(index, bb.now + seconds);
+                }
+                status
+            }
+            BehaviorNode::HealthBelow { fraction } => bb.check(bb.health_fraction < *fraction),
+            BehaviorNode::InCombat => bb.check(bb.in_combat),
+            BehaviorNode::TargetWithin { distance } => {
+                let within = bb.target.is_some_and(|(_, target_pos)| bb.position.distance(target_pos) < *distance);
+                bb.check(within)
+            }
+            BehaviorNode::Chance { probability } => {
+                let roll = rand::thread_rng().gen::<f32>() < *probability;
+                bb.check(roll)
+            }
+            BehaviorNode::Flee { speed_multiplier } => {
+                let Some((_, target_pos)) = bb.target else { return BehaviorStatus::Failure };
+                let away = (bb.position - target_pos).normalize_or(Vec2::X);
+                bb.movement = Some(away * *speed_multiplier);
+                BehaviorStatus::Running
+            }
+            BehaviorNode::CallForHelp { radius } => {
+                let Some((target, _)) = bb.target else { return BehaviorStatus::Failure };
+                bb.call_for_help = Some((target, *radius));
+                BehaviorStatus::Success
+            }
+            BehaviorNode::UseAbility { ability_id } => {
+                if bb.silenced {
+                    return BehaviorStatus::Failure;
+                }
+                let Some(ability) = bb.abilities.get(*ability_id) else { return BehaviorStatus::Failure };
+                if bb.memory.ability_ready_at.get(ability_id).is_some_and(|ready_at| bb.now < *ready_at) {
+                    return BehaviorStatus::Failure;
+                }
+                let targets_self = ability.ability_types.iter().all(|t| matches!(t, AbilityType::Heal { .. } | AbilityType::Buff { .. }));
+                if !targets_self {
+                    let Some((_, target_pos)) = bb.target else { return BehaviorStatus::Failure };
+                    if bb.position.distance(target_pos) > ability.range * PIXELS_PER_METER {
+                        return BehaviorStatus::Failure;
+                    }
+                }
+                bb.memory.ability_ready_at.insert(*ability_id, bb.now + ability.cooldown);
+                bb.cast_ability = Some(*ability_id);
+                BehaviorStatus::Success
+            }
+            BehaviorNode::Patrol { radius, pause } => {
+                let Some(home) = bb.home else { return BehaviorStatus::Failure };
+                if bb.now < bb.memory.patrol_wait_until {
+                    bb.movement = Some(Vec2::ZERO);
+                    return BehaviorStatus::Running;
+                }
+
+                let goal = *bb.memory.patrol_goal.get_or_insert_with(|| {
+                    let mut rng = rand::thread_rng();
+                    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                    let distance = rng.gen_range(0.0..radius.max(1.0));
+                    home + Vec2::from_angle(angle) * distance
+                });
+
+                let to_goal = goal - bb.position;
+                if to_goal.length() <= PATROL_ARRIVE_DISTANCE {
+                    bb.memory.patrol_goal = None;
+                    bb.memory.patrol_wait_until = bb.now + pause;
+                    bb.movement = Some(Vec2::ZERO);
+                    return BehaviorStatus::Success;
+                }
+                bb.movement = Some(to_goal.normalize());
+                BehaviorStatus::Running
+            }
+        }
+    }
+}
+
+/// Per-enemy state the tree keeps between frames
+#[derive(Default, Debug)]
+pub struct BehaviorMemory {
+    /// Cooldown decorator expiry, keyed by node index
+    node_ready_at: HashMap<usize, f32>,
+    ability_ready_at: HashMap<u32, f32>,
+    patrol_goal: Option<Vec2>,
+    patrol_wait_until: f32,
+}
+
+/// Behavior tree an enemy runs, with its memory
+#[derive(Component, Debug)]
+pub struct EnemyBehavior {
+    pub tree_id: String,
+    pub memory: BehaviorMemory,
+    /// A movement action drove the enemy this frame, so enemy_ai leaves it alone
+    pub controlling: bool,
+}
+
+/// What the tree sees and what it asked for during one evaluation
+struct Blackboard<'a> {
+    now: f32,
+    position: Vec2,
+    home: Option<Vec2>,
+    health_fraction: f32,
+    in_combat: bool,
+    silenced: bool,
+    target: Option<(Entity, Vec2)>,
+    abilities: &'a AbilityDatabase,
+    memory: &'a mut BehaviorMemory,
+    /// Movement direction scaled by a speed multiplier
+    movement: Option<Vec2>,
+    call_for_help: Option<(Entity, f32)>,
+    cast_ability: Option<u32>,
+}
+
+impl Blackboard<'_> {
+    fn check(&self, condition: bool) -> BehaviorStatus {
+        if condition { BehaviorStatus::Success } else { BehaviorStatus::Failure }
+    }
+}
+
+/// Give newly spawned enemies the behavior tree their type is configured with
+pub fn attach_enemy_behaviors(
+    mut commands: Commands,
+    enemies: Query<(Entity, &EnemyType), Added<EnemyType>>,
+    enemy_db: Res<EnemyDatabase>,
+) {
+    for (entity, enemy_type) in &enemies {
+        if let Some(tree_id) = enemy_db.enemies.get(&enemy_type.0).and_then(|def| def.behavior.clone()) {
+            commands.entity(entity).insert(EnemyBehavior {
+                tree_id,
+                memory: BehaviorMemory::default(),
+                controlling: false,
+            });
+        }
+    }
+}
+
+/// Evaluate every enemy's behavior tree and carry out what it decided
+pub fn run_behavior_trees(
+    mut commands: Commands,
+    mut enemies: Query<(
+        Entity,
+        &Position,
+        &mut Velocity,
+        &mut avian2d::prelude::LinearVelocity,
+        &mut Health,
+        &MoveSpeed,
+        &CombatStats,
+        &CurrentTarget,
+        &AiState,
+        &ThreatTable,
+        &mut EnemyBehavior,
+        Option<&SpawnPoint>,
+        Option<&ActiveDebuffs>,
+    ), (With<Enemy>, Without<Player>, Without<AiActivationDelay>)>,
+    allies: Query<(Entity, &Position, &ThreatTable), With<Enemy>>,
+    mut players: Query<(&Position, &mut Health, &CombatStats, &Equipment), (With<Player>, Without<Enemy>)>,
+    tree_db: Res<BehaviorTreeDatabase>,
+    ability_db: Res<AbilityDatabase>,
+    item_db: Res<ItemDatabase>,
+    formulas: Res<CombatFormulas>,
+    spatial: Res<SpatialIndex>,
+    time: Res<Time>,
+) {
+    let _timer = crate::metrics::time_system("behavior::run_behavior_trees");
+    let now = time.elapsed_secs();
+    let mut help_calls = Vec::new();
+
+    for (entity, position, mut velocity, mut physics_velocity, mut health, move_speed, stats, current_target, ai_state, threat, mut behavior, spawn_point, debuffs) in &mut enemies {
+        behavior.controlling = false;
+
+        // Leashing enemies ignore their tree until they're home, stunned ones until it wears off
+        if matches!(ai_state, AiState::Returning) || debuffs.is_some_and(ActiveDebuffs::is_stunned) {
+            continue;
+        }
+        let Some(tree) = tree_db.trees.get(&behavior.tree_id) else { continue };
+
+        let target = current_target.0
+            .and_then(|target| players.get(target).ok().map(|(target_pos, ..)| (target, target_pos.0)));
+
+        let mut bb = Blackboard {
+            now,
+            position: position.0,
+            home: spawn_point.map(|spawn| spawn.position),
+            health_fraction: health.percent(),
+            in_combat: !threat.is_empty(),
+            silenced: debuffs.is_some_and(ActiveDebuffs::is_silenced),
+            target,
+            abilities: &ability_db,
+            memory: &mut behavior.memory,
+            movement: None,
+            call_for_help: None,
+            cast_ability: None,
+        };
+        tree.root.tick(0, &mut bb);
+        let (movement, call_for_help, cast_ability) = (bb.movement, bb.call_for_help, bb.cast_ability);
+
+        if let Some(movement) = movement {
+            let vel = movement * move_speed.0;
+            velocity.0 = vel;
+            physics_velocity.0 = vel;
+            behavior.controlling = true;
+        }
+
+        if let Some((target, radius)) = call_for_help {
+            help_calls.push((entity, position.0, target, radius));
+        }
+
+        let Some(ability) = cast_ability.and_then(|id| ability_db.get(id)) else { continue };
+        for ability_type in &ability.ability_types {
+            match ability_type {
+                AbilityType::Heal { amount, is_percent } => {
+                    let heal = if *is_percent { health.max * (*amount / 100.0) } else { *amount };
+                    let before = health.current;
+                    health.current = (health.current + heal).min(health.max);
+                    commands.trigger(crate::combat_log::CombatLogRecord {
+                        source: entity,
+                        target: entity,
+                        ability_id: ability.id,
+                        entry: CombatLogEntry::Heal { amount: health.current - before },
+                    });
+                }
+                AbilityType::DirectDamage { multiplier } => {
+                    let Some((target_entity, _)) = target else { continue };
+                    let Ok((target_pos, mut target_health, target_stats, equipment)) = players.get_mut(target_entity) else { continue };
+
+                    let mut defender = DefenderProfile::from_stats(target_stats);
+                    defender.defense += item_db.calculate_equipment_bonuses(equipment).defense;
+                    let attacker = AttackerProfile {
+                        attack_power: stats.attack_power,
+                        crit_chance: stats.crit_chance,
+                        proficiency_level: 0,
+                    };
+                    let outcome = formulas.resolve_attack(&attacker, &defender, *multiplier, &mut rand::thread_rng());
+                    target_health.current = (target_health.current - outcome.damage).max(0.0);
+                    commands.trigger(crate::combat_log::CombatLogRecord::attack(entity, target_entity, ability.id, &outcome, 0.0));
+
+                    commands.server_trigger(ToClients {
+                        mode: SendMode::Broadcast,
+                        message: CombatEvent {
+                            attacker_position: position.0,
+                            target_position: target_pos.0,
+                            damage: outcome.damage,
+                            ability_id: ability.id,
+                            is_crit: outcome.result == AttackResult::Crit,
+                            result: outcome.result,
+                        },
+                    });
+                }
+                AbilityType::Debuff { duration, effect } => {
+                    let Some((target_entity, _)) = target else { continue };
+                    crate::abilities::apply_debuff(&mut commands, entity, target_entity, ability.id, effect.clone(), *duration, now);
+                }
+                _ => {}
+            }
+        }
+        info!("Enemy {:?} used ability {} via behavior tree '{}'", entity, ability.name, behavior.tree_id);
+    }
+
+    // Idle allies within earshot join the fight against the caller's target
+    for (caller, caller_pos, target, radius) in help_calls {
+        for (ally, ally_pos, ally_threat) in spatial.nearby(caller_pos, radius).filter_map(|entity| allies.get(entity).ok()) {
+            if ally != caller && ally_threat.is_empty() && caller_pos.distance(ally_pos.0) <= radius {
+                commands.trigger(ThreatEvent { enemy: ally, source: target, amount: 0.0 });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_indices_cover_whole_tree() {
+        let tree = BehaviorNode::Selector {
+            children: vec![
+                BehaviorNode::Sequence { children: vec![BehaviorNode::InCombat, BehaviorNode::Flee { speed_multiplier: 1.0 }] },
+                BehaviorNode::Cooldown { seconds: 5.0, child: Box::new(BehaviorNode::CallForHelp { radius: 100.0 }) },
+            ],
+        };
+        assert_eq!(tree.size(), 6);
+    }
+
+    #[test]
+    fn trees_deserialize_from_json() {
+        let json = r#"{
+            "id": "test",
+            "root": { "Sequence": { "children": [
+                "InCombat",
+                { "HealthBelow": { "fraction": 0.25 } },
+                { "Flee": { "speed_multiplier": 1.2 } }
+            ] } }
+        }"#;
+        let tree: BehaviorTreeDefinition = serde_json::from_str(json).unwrap();
+        assert_eq!(tree.root.size(), 4);
+    }
+}
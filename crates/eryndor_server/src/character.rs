@@ -191,6 +191,7 @@ pub fn spawn_character_components(
     commands.entity(character_entity).insert((
         quest_log,
         AbilityCooldowns::default(),
+        ItemCooldowns::default(),
         visual,
         OwnedBy(client_entity),
         crate::auth::CharacterDatabaseId(character_db_id),
@@ -0,0 +1,407 @@
+//! Chat channels: zone-local say, party, guild, global and whispers.
+//!
+//! Every message is checked against the sender's chat mute, per-channel rate limits and the
+//! chat filter before it is routed to the players who should see it. Blocked messages earn
+//! the sender a strike; enough strikes in a short time and they are muted automatically.
+//! Global chat also goes out over the relay to players on the other shards.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::collections::{HashMap, VecDeque};
+use crate::audit::{self, AuditActionType};
+use crate::auth::{ActiveCharacterEntity, Authenticated, ClientMetadata};
+use crate::config::{self, ServerConfig};
+use crate::database::{self, DatabaseConnection};
+use crate::groups::{GuildName, PartyId};
+use crate::moderation::{self, ChatVerdict};
+use crate::metrics::TimedQuery;
+use crate::world::{CurrentZone, STARTER_ZONE};
+use crate::relay::{Relay, RelayMessage};
+use crate::social::{notify, OnlineCharacters};
+
+/// Rate limits count messages over this many seconds
+const RATE_WINDOW: f64 = 60.0;
+
+/// Muted by a moderator until `until` (unix seconds)
+#[derive(Component, Clone, Debug)]
+pub struct ChatMute {
+    pub until: i64,
+    pub reason: String,
+}
+
+struct SentMessage {
+    time: f64,
+    channel: ChatChannel,
+    text: String,
+}
+
+/// Recent messages and strikes per character, for rate limiting and spam detection
+#[derive(Resource, Default)]
+pub struct ChatRateLimiter {
+    sent: HashMap<Entity, VecDeque<SentMessage>>,
+    strikes: HashMap<Entity, VecDeque<f64>>,
+}
+
+impl ChatRateLimiter {
+    /// Record a message if the sender is under both the channel's limit and the overall limit
+    pub fn try_send(&mut self, sender: Entity, channel: ChatChannel, text: &str, now: f64, channel_limit: u32, total_limit: u32) -> bool {
+        // Forget anyone who hasn't chatted within the window
+        self.sent.retain(|_, sent| sent.back().is_some_and(|message| now - message.time < RATE_WINDOW));
+
+        let sent = self.sent.entry(sender).or_default();
+        while sent.front().is_some_and(|message| now - message.time >= RATE_WINDOW) {
+            sent.pop_front();
+        }
+
+        let in_channel = sent.iter().filter(|message| message.channel == channel).count() as u32;
+        if in_channel >= channel_limit || sent.len() as u32 >= total_limit {
+            return false;
+        }
+        sent.push_back(SentMessage { time: now, channel, text: text.to_string() });
+        true
+    }
+
+    /// How many times the sender has sent this exact message within the window
+    pub fn repeats(&self, sender: Entity, text: &str, now: f64) -> u32 {
+        self.sent.get(&sender).map_or(0, |sent| {
+            sent.iter()
+                .filter(|message| now - message.time < RATE_WINDOW && message.text.eq_ignore_ascii_case(text))
+                .count() as u32
+        })
+    }
+
+    /// Give the sender a strike, returning how many they have within `window` seconds
+    pub fn add_strike(&mut self, sender: Entity, now: f64, window: f64) -> u32 {
+        self.strikes.retain(|_, strikes| strikes.back().is_some_and(|time| now - time < window));
+
+        let strikes = self.strikes.entry(sender).or_default();
+        while strikes.front().is_some_and(|time| now - time >= window) {
+            strikes.pop_front();
+        }
+        strikes.push_back(now);
+        strikes.len() as u32
+    }
+
+    pub fn clear_strikes(&mut self, sender: Entity) {
+        self.strikes.remove(&sender);
+    }
+}
+
+/// Who is sending a chat message, for moderation and audit logging
+struct ChatSender<'a> {
+    client: Entity,
+    character: Entity,
+    name: &'a str,
+    account_id: Option<i64>,
+    ip_address: Option<String>,
+}
+
+/// Queue an audit log entry about the sender; does nothing when running without a database
+fn audit_sender(db: &DatabaseConnection, sender: &ChatSender, action: AuditActionType, actor: Option<i64>, details: String) {
+    let target_account = sender.account_id;
+    let name = sender.name.to_string();
+    let ip_address = sender.ip_address.clone();
+    db.execute("log_audit_event", move |pool| async move {
+        audit::log_audit_event(
+            &pool,
+            action,
+            actor,
+            target_account,
+            Some(&name),
+            ip_address.as_deref(),
+            Some(&details),
+        ).await
+    });
+}
+
+fn channel_limit(chat: &config::Chat, channel: ChatChannel) -> u32 {
+    match channel {
+        ChatChannel::Say => chat.say_per_minute,
+        ChatChannel::Party => chat.party_per_minute,
+        ChatChannel::Guild => chat.guild_per_minute,
+        ChatChannel::Global => chat.global_per_minute,
+        ChatChannel::Whisper => chat.whisper_per_minute,
+    }
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Check a message against the sender's mute, the rate limits and the chat filter.
+/// Returns the text to send, which may have been censored, or None if it was refused.
+fn moderate(
+    commands: &mut Commands,
+    sender: &ChatSender,
+    channel: ChatChannel,
+    text: &str,
+    mute: Option<&ChatMute>,
+    limiter: &mut ChatRateLimiter,
+    config: &ServerConfig,
+    db: &DatabaseConnection,
+    now: f64,
+) -> Option<String> {
+    if let Some(mute) = mute.filter(|mute| mute.until > unix_now()) {
+        let message = if mute.reason.is_empty() {
+            "You are muted".to_string()
+        } else {
+            format!("You are muted: {}", mute.reason)
+        };
+        notify(commands, sender.client, message, NotificationType::Warning);
+        return None;
+    }
+
+    let chat = &config.chat;
+    let (action, reason) = if limiter.repeats(sender.character, text, now) >= chat.max_duplicate_messages {
+        (AuditActionType::SpamBlocked, "Stop repeating yourself".to_string())
+    } else {
+        match moderation::check_chat_message(text, &config.moderation, chat) {
+            ChatVerdict::Allow(filtered) => {
+                let limit = channel_limit(chat, channel);
+                if limiter.try_send(sender.character, channel, text, now, limit, config.rate_limits.chat_messages_per_minute) {
+                    return Some(filtered);
+                }
+                (AuditActionType::RateLimitExceeded, "You are sending messages too quickly".to_string())
+            }
+            ChatVerdict::Spam(reason) => (AuditActionType::SpamBlocked, reason),
+            ChatVerdict::Block(reason) => (AuditActionType::InappropriateContentBlocked, reason),
+            ChatVerdict::Mute(reason) => {
+                audit_sender(db, sender, AuditActionType::InappropriateContentBlocked, sender.account_id, format!("[{}] {}", channel.label(), text));
+                auto_mute(commands, sender, &reason, limiter, config, db);
+                return None;
+            }
+        }
+    };
+
+    notify(commands, sender.client, reason.clone(), NotificationType::Warning);
+    audit_sender(db, sender, action, sender.account_id, format!("{} - [{}] {}", reason, channel.label(), text));
+
+    let window = chat.strike_window_minutes as f64 * 60.0;
+    if chat.strikes_before_mute > 0 && limiter.add_strike(sender.character, now, window) >= chat.strikes_before_mute {
+        auto_mute(commands, sender, &reason, limiter, config, db);
+    }
+    None
+}
+
+/// Mute a player for `chat.auto_mute_minutes`, saving it so it survives relogging
+fn auto_mute(
+    commands: &mut Commands,
+    sender: &ChatSender,
+    reason: &str,
+    limiter: &mut ChatRateLimiter,
+    config: &ServerConfig,
+    db: &DatabaseConnection,
+) {
+    let minutes = config.chat.auto_mute_minutes;
+    let until = unix_now() + minutes as i64 * 60;
+    let reason = format!("Automatic mute: {}", reason);
+
+    limiter.clear_strikes(sender.character);
+    commands.entity(sender.character).insert(ChatMute { until, reason: reason.clone() });
+    let (name, saved_reason) = (sender.name.to_string(), reason.clone());
+    db.execute("set_mute", move |pool| async move {
+        database::set_mute(&pool, &name, Some(until), &saved_reason).timed("set_mute").await
+    });
+
+    info!("Auto-muted {} for {} minutes ({})", sender.name, minutes, reason);
+    audit_sender(db, sender, AuditActionType::PlayerAutoMuted, None, format!("muted for {} minutes: {}", minutes, reason));
+    notify(commands, sender.client, format!("You have been muted for {} minutes. {}", minutes, reason), NotificationType::Error);
+}
+
+fn zone_of(zone: Option<&CurrentZone>) -> &str {
+    zone.map(|zone| zone.0.as_str()).unwrap_or(STARTER_ZONE)
+}
+
+/// Route a chat message to the players on its channel
+pub fn handle_chat_message(
+    trigger: On<FromClient<SendChatMessage>>,
+    mut commands: Commands,
+    clients: Query<(&ActiveCharacterEntity, Option<&Authenticated>, Option<&ClientMetadata>)>,
+    senders: Query<(&Character, Option<&CurrentZone>, Option<&PartyId>, Option<&GuildName>, Option<&ChatMute>)>,
+    listeners: Query<(&OwnedBy, Option<&CurrentZone>, Option<&PartyId>, Option<&GuildName>), With<Player>>,
+    mut limiter: ResMut<ChatRateLimiter>,
+    config: Res<ServerConfig>,
+    time: Res<Time>,
+    db: Res<DatabaseConnection>,
+    relay: Res<Relay>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok((active_char, auth, metadata)) = clients.get(client_entity) else { return };
+    let Ok((character, zone, party, guild, mute)) = senders.get(active_char.0) else {
+        warn!("Chat message from client {:?} without spawned character", client_entity);
+        return;
+    };
+
+    let request = trigger.event();
+    let text = request.message.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    // Refuse channels the sender can't use before counting the message against them
+    match request.channel {
+        ChatChannel::Party if party.is_none() => {
+            notify(&mut commands, client_entity, "You are not in a party".to_string(), NotificationType::Warning);
+            return;
+        }
+        ChatChannel::Guild if guild.is_none() => {
+            notify(&mut commands, client_entity, "You are not in a guild".to_string(), NotificationType::Warning);
+            return;
+        }
+        // Whispers need a recipient and come in through WhisperRequest
+        ChatChannel::Whisper => return,
+        _ => {}
+    }
+
+    let sender = ChatSender {
+        client: client_entity,
+        character: active_char.0,
+        name: &character.name,
+        account_id: auth.map(|auth| auth.account_id),
+        ip_address: metadata.map(|metadata| metadata.ip_address.to_string()),
+    };
+    let Some(text) = moderate(&mut commands, &sender, request.channel, text, mute, &mut limiter, &config, &db, time.elapsed_secs_f64()) else {
+        return;
+    };
+
+    let message = ChatMessage {
+        sender: character.name.clone(),
+        message: text,
+        channel: request.channel,
+        target: None,
+    };
+
+    if request.channel == ChatChannel::Global {
+        relay.send(RelayMessage::GlobalChat {
+            sender: message.sender.clone(),
+            message: message.message.clone(),
+        });
+        commands.server_trigger(ToClients {
+            mode: SendMode::Broadcast,
+            message,
+        });
+        return;
+    }
+
+    for (owned_by, listener_zone, listener_party, listener_guild) in &listeners {
+        let hears = match request.channel {
+            ChatChannel::Say => zone_of(listener_zone) == zone_of(zone),
+            ChatChannel::Party => listener_party.is_some() && listener_party == party,
+            ChatChannel::Guild => listener_guild.is_some() && listener_guild == guild,
+            ChatChannel::Global | ChatChannel::Whisper => false,
+        };
+        if hears {
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(owned_by.0)),
+                message: message.clone(),
+            });
+        }
+    }
+}
+
+/// Deliver a whisper to an online character, echoing it back to the sender
+pub fn handle_whisper(
+    trigger: On<FromClient<WhisperRequest>>,
+    mut commands: Commands,
+    clients: Query<(&ActiveCharacterEntity, Option<&Authenticated>, Option<&ClientMetadata>)>,
+    mutes: Query<&ChatMute>,
+    online: Res<OnlineCharacters>,
+    mut limiter: ResMut<ChatRateLimiter>,
+    config: Res<ServerConfig>,
+    time: Res<Time>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok((active_char, auth, metadata)) = clients.get(client_entity) else { return };
+    let Some(online_sender) = online.characters.get(&active_char.0) else { return };
+
+    let request = trigger.event();
+    let text = request.message.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    let Some(recipient) = online.find_by_name(&request.target) else {
+        notify(&mut commands, client_entity, format!("{} is not online", request.target), NotificationType::Warning);
+        return;
+    };
+
+    let sender = ChatSender {
+        client: client_entity,
+        character: active_char.0,
+        name: &online_sender.name,
+        account_id: auth.map(|auth| auth.account_id),
+        ip_address: metadata.map(|metadata| metadata.ip_address.to_string()),
+    };
+    let mute = mutes.get(active_char.0).ok();
+    let Some(text) = moderate(&mut commands, &sender, ChatChannel::Whisper, text, mute, &mut limiter, &config, &db, time.elapsed_secs_f64()) else {
+        return;
+    };
+
+    let message = ChatMessage {
+        sender: online_sender.name.clone(),
+        message: text,
+        channel: ChatChannel::Whisper,
+        target: Some(recipient.name.clone()),
+    };
+    for client in [recipient.client, client_entity] {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client)),
+            message: message.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender() -> Entity {
+        Entity::from_raw_u32(1).unwrap()
+    }
+
+    #[test]
+    fn channel_limit_applies_per_channel() {
+        let mut limiter = ChatRateLimiter::default();
+        assert!(limiter.try_send(sender(), ChatChannel::Global, "hi", 0.0, 2, 10));
+        assert!(limiter.try_send(sender(), ChatChannel::Global, "hi", 1.0, 2, 10));
+        assert!(!limiter.try_send(sender(), ChatChannel::Global, "hi", 2.0, 2, 10));
+        // Other channels are still open
+        assert!(limiter.try_send(sender(), ChatChannel::Say, "hi", 3.0, 2, 10));
+    }
+
+    #[test]
+    fn total_limit_spans_channels() {
+        let mut limiter = ChatRateLimiter::default();
+        assert!(limiter.try_send(sender(), ChatChannel::Say, "hi", 0.0, 5, 2));
+        assert!(limiter.try_send(sender(), ChatChannel::Party, "hi", 0.0, 5, 2));
+        assert!(!limiter.try_send(sender(), ChatChannel::Guild, "hi", 0.0, 5, 2));
+    }
+
+    #[test]
+    fn repeats_and_strikes() {
+        let mut limiter = ChatRateLimiter::default();
+        assert!(limiter.try_send(sender(), ChatChannel::Say, "WTS sword", 0.0, 10, 10));
+        assert!(limiter.try_send(sender(), ChatChannel::Global, "wts sword", 1.0, 10, 10));
+        assert_eq!(limiter.repeats(sender(), "WTS SWORD", 2.0), 2);
+        assert_eq!(limiter.repeats(sender(), "WTS SWORD", 61.0), 1);
+
+        assert_eq!(limiter.add_strike(sender(), 0.0, 600.0), 1);
+        assert_eq!(limiter.add_strike(sender(), 100.0, 600.0), 2);
+        assert_eq!(limiter.add_strike(sender(), 650.0, 600.0), 2);
+        limiter.clear_strikes(sender());
+        assert_eq!(limiter.add_strike(sender(), 700.0, 600.0), 1);
+    }
+
+    #[test]
+    fn old_messages_fall_out_of_the_window() {
+        let mut limiter = ChatRateLimiter::default();
+        assert!(limiter.try_send(sender(), ChatChannel::Say, "hi", 0.0, 1, 10));
+        assert!(!limiter.try_send(sender(), ChatChannel::Say, "hi", 30.0, 1, 10));
+        assert!(limiter.try_send(sender(), ChatChannel::Say, "hi", 61.0, 1, 10));
+    }
+}
@@ -2,10 +2,16 @@ use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::auth::ActiveCharacterEntity;
-use crate::abilities::AbilityDatabase;
+use crate::abilities::{add_buff, apply_debuff, AbilityDatabase, AoeArea, ProjectileFlight};
 use crate::spawn::{SpawnPoint, RespawnEvent, EntityTemplate};
-use avian2d::prelude::{LinearVelocity, Position as PhysicsPosition};
+use crate::combat_formulas::{CombatFormulas, AttackerProfile, DefenderProfile};
+use crate::combat_log::CombatLogRecord;
+use crate::pvp::{self, PvpStandingQuery};
+use crate::spatial::SpatialIndex;
+use crate::threat::{ThreatTable, ThreatEvent, HealingThreatEvent, TauntEvent, PROXIMITY_THREAT, LEASH_ARRIVE_DISTANCE};
+use avian2d::prelude::{LinearVelocity, Position as PhysicsPosition, SpatialQuery};
 use rand::Rng;
+use std::collections::HashSet;
 
 pub fn handle_set_target(
     trigger: On<FromClient<SetTargetRequest>>,
@@ -13,6 +19,7 @@ pub fn handle_set_target(
     mut players: Query<(&mut CurrentTarget, &mut AutoAttack, &mut InCombat, &Character)>,
     enemies: Query<&EnemyType, With<Enemy>>,
     npcs: Query<&NpcName, With<Npc>>,
+    standings: Query<PvpStandingQuery>,
 ) {
     let Some(client_entity) = trigger.client_id.entity() else { return };
     let request = trigger.event();
@@ -33,6 +40,12 @@ pub fn handle_set_target(
                 in_combat.0 = true;
                 info!("{} targeted enemy: {} - auto-attack enabled",
                     character.name, enemy_type.0);
+            } else if pvp::can_attack(&standings, char_entity, target_entity) {
+                // Targeting a duel opponent or hostile flagged player
+                auto_attack.enabled = true;
+                in_combat.0 = true;
+                info!("{} targeted hostile player {:?} - auto-attack enabled",
+                    character.name, target_entity);
             } else if let Ok(npc_name) = npcs.get(target_entity) {
                 // Targeting NPC - no combat
                 info!("{} targeted NPC: {}",
@@ -63,12 +76,16 @@ pub fn process_auto_attacks(
         &mut WeaponProficiencyExp,
         &WeaponProficiency,
         Option<&ActiveDebuffs>,
-    ), With<Player>>,
+    ), (With<Player>, Without<Dead>, Without<Ghost>)>,
     mut targets: Query<(&Position, &mut Health, &CombatStats), (With<Enemy>, Without<AiActivationDelay>)>,
     all_enemies: Query<Entity, With<Enemy>>,
+    pvp_targets: Query<(&Position, &CombatStats), (With<Player>, Without<Enemy>, Without<Dead>, Without<Ghost>)>,
+    standings: Query<PvpStandingQuery>,
     item_db: Res<crate::game_data::ItemDatabase>,
+    formulas: Res<CombatFormulas>,
     time: Res<Time>,
 ) {
+    let _timer = crate::metrics::time_system("combat::process_auto_attacks");
     for (attacker_entity, attacker_pos, current_target, attacker_stats, mut auto_attack, equipment, mut weapon_exp, weapon_prof, active_debuffs) in &mut attackers {
         // Skip if auto-attack is disabled
         if !auto_attack.enabled {
@@ -76,10 +93,8 @@ pub fn process_auto_attacks(
         }
 
         // Skip if stunned (cannot attack while stunned)
-        if let Some(debuffs) = active_debuffs {
-            if debuffs.debuffs.iter().any(|d| matches!(d.effect, DebuffType::Stun)) {
-                continue;
-            }
+        if active_debuffs.is_some_and(ActiveDebuffs::is_stunned) {
+            continue;
         }
 
         // Debug: Log that we're processing auto-attack
@@ -100,14 +115,21 @@ pub fn process_auto_attacks(
             continue;
         };
 
-        // Check if target is an enemy
-        if all_enemies.get(target_entity).is_err() {
-            continue;
-        }
-
-        // Get target data
-        let Ok((target_pos, mut target_health, target_stats)) = targets.get_mut(target_entity) else {
-            continue;
+        // Enemies, or players this attacker is allowed to fight
+        let target_is_player = all_enemies.get(target_entity).is_err();
+        let (target_pos, target_stats) = if target_is_player {
+            if !pvp::can_attack(&standings, attacker_entity, target_entity) {
+                continue;
+            }
+            let Ok((target_pos, target_stats)) = pvp_targets.get(target_entity) else {
+                continue;
+            };
+            (*target_pos, *target_stats)
+        } else {
+            let Ok((target_pos, _, target_stats)) = targets.get(target_entity) else {
+                continue;
+            };
+            (*target_pos, *target_stats)
         };
 
         // Get weapon stats from equipped weapon (default to unarmed/fists if no weapon)
@@ -147,45 +169,37 @@ pub fn process_auto_attacks(
         // Calculate equipment bonuses
         let equipment_bonuses = item_db.calculate_equipment_bonuses(equipment);
 
-        // Apply equipment bonuses to combat stats
-        let total_attack = attacker_stats.attack_power + equipment_bonuses.attack_power;
-        let total_crit = attacker_stats.crit_chance + equipment_bonuses.crit_chance;
-
-        // Calculate damage: total attack * weapon multiplier
-        let base_damage = total_attack * weapon_stats.damage_multiplier;
-
-        // Apply weapon proficiency bonus (2% per level)
-        let prof_level = crate::weapon::get_proficiency_level(weapon_prof, &weapon_stats.weapon_type);
-        let prof_bonus = 1.0 + ((prof_level - 1) as f32 * 0.02);
-        let damage_with_prof = base_damage * prof_bonus;
-
-        // Apply defense mitigation
-        let mitigation = target_stats.defense / (target_stats.defense + 100.0);
-        let mut damage = damage_with_prof * (1.0 - mitigation);
-
-        // Critical hit check
-        let is_crit = rand::random::<f32>() < total_crit;
-        if is_crit {
-            damage *= 1.5;
-        }
-
-        // Apply damage
-        target_health.current = (target_health.current - damage).max(0.0);
-
-        // Make enemy aggro on the attacker
-        if let Ok(mut enemy) = commands.get_entity(target_entity) {
-            enemy.insert(AiState::Chasing(attacker_entity));
-            enemy.insert(CurrentTarget(Some(attacker_entity)));
-            info!("Enemy {:?} aggroed on attacker {:?}", target_entity, attacker_entity);
+        // Resolve the swing through the data-driven combat formulas
+        let attacker_profile = AttackerProfile {
+            attack_power: attacker_stats.attack_power + equipment_bonuses.attack_power,
+            crit_chance: attacker_stats.crit_chance + equipment_bonuses.crit_chance,
+            proficiency_level: crate::weapon::get_proficiency_level(weapon_prof, &weapon_stats.weapon_type),
+        };
+        let outcome = formulas.resolve_attack(
+            &attacker_profile,
+            &DefenderProfile::from_stats(&target_stats),
+            weapon_stats.damage_multiplier,
+            &mut rand::thread_rng(),
+        );
+        let damage = outcome.damage;
+        let is_crit = outcome.result == AttackResult::Crit;
+
+        // Apply damage; damage (or a whiffed swing) draws an enemy's attention
+        if target_is_player {
+            pvp::damage_player(&mut commands, target_entity, damage);
+        } else if let Ok((_, mut target_health, _)) = targets.get_mut(target_entity) {
+            target_health.current = (target_health.current - damage).max(0.0);
+            commands.trigger(ThreatEvent { enemy: target_entity, source: attacker_entity, amount: damage });
         }
+        commands.trigger(CombatLogRecord::attack(attacker_entity, target_entity, 0, &outcome, 0.0));
 
         // Reset cooldown based on weapon attack speed
         // attack_speed is attacks per second, so cooldown = 1.0 / attack_speed
         auto_attack.cooldown_timer = 1.0 / weapon_stats.attack_speed;
 
         info!(
-            "Auto-attack: {:?} hit {:?} for {:.1} damage (crit: {})",
-            attacker_entity, target_entity, damage, is_crit
+            "Auto-attack: {:?} hit {:?} for {:.1} damage ({:?})",
+            attacker_entity, target_entity, damage, outcome.result
         );
 
         // Award weapon proficiency XP for successful attack
@@ -230,6 +244,7 @@ pub fn process_auto_attacks(
                 damage,
                 ability_id: 0, // 0 indicates auto-attack (not an ability)
                 is_crit,
+                result: outcome.result,
             },
         });
     }
@@ -249,11 +264,19 @@ pub fn handle_use_ability(
         &mut AbilityCooldowns,
         &LearnedAbilities,
         &Equipment,
+        &WeaponProficiency,
         Option<&ActiveDebuffs>,
     ), Without<Enemy>>,
     mut targets: Query<(Entity, &Position, &mut Health, &CombatStats), (With<Enemy>, Without<AiActivationDelay>)>,
+    allies: Query<(&Position, &CombatStats), (With<Player>, Without<Enemy>)>,
+    fallen: Query<(), Or<(With<Dead>, With<Ghost>)>>,
+    dead_players: Query<&Position, With<Dead>>,
+    corpses: Query<(&Position, &crate::death::CorpseOf)>,
+    standings: Query<PvpStandingQuery>,
+    spatial_query: SpatialQuery,
     ability_db: Res<AbilityDatabase>,
     item_db: Res<crate::game_data::ItemDatabase>,
+    formulas: Res<CombatFormulas>,
     time: Res<Time>,
 ) {
     let Some(client_entity) = trigger.client_id.entity() else { return };
@@ -276,26 +299,37 @@ pub fn handle_use_ability(
     info!("Found ability: {} ({})", ability.name, ability.id);
 
     // Get attacker data
-    let Ok((attacker_entity, attacker_pos, current_target, stats, mut mana, mut attacker_health, mut cooldowns, learned, equipment, active_debuffs)) =
+    let Ok((attacker_entity, attacker_pos, current_target, stats, mut mana, mut attacker_health, mut cooldowns, learned, equipment, weapon_prof, active_debuffs)) =
         attackers.get_mut(char_entity) else {
             warn!("Could not get attacker components for {:?}", char_entity);
             return
         };
     info!("Got attacker components");
 
-    // Check if stunned (cannot use abilities while stunned)
-    if let Some(debuffs) = active_debuffs {
-        if debuffs.debuffs.iter().any(|d| matches!(d.effect, DebuffType::Stun)) {
-            warn!("Player {:?} is stunned, cannot use abilities", char_entity);
-            commands.server_trigger(ToClients {
-                mode: SendMode::Direct(ClientId::Client(client_entity)),
-                message: NotificationEvent {
-                    message: "You are stunned!".to_string(),
-                    notification_type: NotificationType::Warning,
-                },
-            });
-            return;
-        }
+    // The dead can't cast
+    if fallen.contains(char_entity) {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message: "You are dead!".to_string(),
+                notification_type: NotificationType::Warning,
+            },
+        });
+        return;
+    }
+
+    // Check if stunned or silenced (cannot use abilities while crowd controlled)
+    if let Some(debuffs) = active_debuffs.filter(|debuffs| debuffs.is_silenced()) {
+        let message = if debuffs.is_stunned() { "You are stunned!" } else { "You are silenced!" };
+        warn!("Player {:?} is crowd controlled, cannot use abilities", char_entity);
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message: message.to_string(),
+                notification_type: NotificationType::Warning,
+            },
+        });
+        return;
     }
 
     // Store attacker position for later use (after mutable borrow ends)
@@ -334,12 +368,33 @@ pub fn handle_use_ability(
     // Check target (some abilities like self-heals might not need a target)
     let target_entity_opt = current_target.0;
 
+    // Position and stats of something this caster may harm: an enemy, or a player they can fight
+    let hostile = |target: Entity| -> Option<(Vec2, CombatStats)> {
+        if let Ok((_, target_pos, _, target_stats)) = targets.get(target) {
+            return Some((target_pos.0, *target_stats));
+        }
+        if fallen.contains(target) || !pvp::can_attack(&standings, attacker_entity, target) {
+            return None;
+        }
+        allies.get(target).ok().map(|(target_pos, target_stats)| (target_pos.0, *target_stats))
+    };
+
+    // Area-of-effect parameters (radius in pixels), if this ability has one
+    let aoe_params = ability.ability_types.iter().find_map(|ability_type| match ability_type {
+        AbilityType::AreaOfEffect { radius, max_targets, shape, placement } =>
+            Some((*radius * PIXELS_PER_METER, *max_targets, *shape, *placement)),
+        _ => None,
+    });
+    // Areas centered on the caster or the ground don't need a target
+    let untargeted_aoe = aoe_params.is_some_and(|(.., placement)| placement != AoePlacement::Target);
+
     // Check if this ability requires a target
-    let requires_target = ability.ability_types.iter().any(|t| matches!(t,
+    let requires_target = !untargeted_aoe && ability.ability_types.iter().any(|t| matches!(t,
         AbilityType::DirectDamage { .. } |
         AbilityType::DamageOverTime { .. } |
         AbilityType::AreaOfEffect { .. } |
-        AbilityType::Debuff { .. }
+        AbilityType::Debuff { .. } |
+        AbilityType::Taunt { .. }
     ));
 
     // Validate target if required
@@ -350,14 +405,13 @@ pub fn handle_use_ability(
         };
         info!("Has target: {:?}", target_entity);
 
-        let Ok((_, target_pos, target_health, target_stats)) = targets.get(target_entity) else {
+        let Some((target_pos, target_stats)) = hostile(target_entity) else {
             warn!("Could not get target components for {:?}", target_entity);
             return;
         };
 
-        // Check range (convert ability range from meters to pixels: 1 meter = 20 pixels)
-        const PIXELS_PER_METER: f32 = 20.0;
-        let distance = attacker_position.distance(target_pos.0);
+        // Check range (ability range is in meters)
+        let distance = attacker_position.distance(target_pos);
         let ability_range_pixels = ability.range * PIXELS_PER_METER;
         if distance > ability_range_pixels {
             warn!("Target out of range: {:.1} > {:.1}", distance, ability_range_pixels);
@@ -365,7 +419,63 @@ pub fn handle_use_ability(
         }
         info!("Range check passed: {:.1} <= {:.1}", distance, ability_range_pixels);
 
-        Some((target_entity, target_pos.0, *target_stats))
+        Some((target_entity, target_pos, target_stats))
+    } else {
+        // Cones and lines still aim at the current target when there is one in reach
+        target_entity_opt
+            .and_then(|target| hostile(target).map(|(target_pos, target_stats)| (target, target_pos, target_stats)))
+            .filter(|(_, target_pos, _)| attacker_position.distance(*target_pos) <= ability.range * PIXELS_PER_METER)
+    };
+
+    // Ground-targeted abilities land where the player aimed, within range
+    let ground_position = if aoe_params.is_some_and(|(.., placement)| placement == AoePlacement::Ground) {
+        let Some(ground) = request.target_position else {
+            warn!("Ground-targeted ability {} used without a target position", ability.id);
+            return;
+        };
+        if attacker_position.distance(ground) > ability.range * PIXELS_PER_METER {
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: NotificationEvent {
+                    message: "Out of range!".to_string(),
+                    notification_type: NotificationType::Warning,
+                },
+            });
+            return;
+        }
+        Some(ground)
+    } else {
+        None
+    };
+
+    // Resurrection targets a dead player's body or the corpse a ghost left behind
+    let resurrect_target = if ability.ability_types.iter().any(|t| matches!(t, AbilityType::Resurrect { .. })) {
+        let fallen_target = target_entity_opt.and_then(|target| {
+            dead_players.get(target).map(|position| (target, position.0))
+                .or_else(|_| corpses.get(target).map(|(position, owner)| (owner.0, position.0)))
+                .ok()
+        });
+        let Some((player, position)) = fallen_target else {
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: NotificationEvent {
+                    message: "You must target a fallen player!".to_string(),
+                    notification_type: NotificationType::Warning,
+                },
+            });
+            return;
+        };
+        if attacker_position.distance(position) > ability.range * PIXELS_PER_METER {
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: NotificationEvent {
+                    message: "Out of range!".to_string(),
+                    notification_type: NotificationType::Warning,
+                },
+            });
+            return;
+        }
+        Some((player, position))
     } else {
         None
     };
@@ -374,43 +484,91 @@ pub fn handle_use_ability(
     let equipment_bonuses = item_db.calculate_equipment_bonuses(equipment);
 
     // Apply equipment bonuses to combat stats
-    let total_attack = stats.attack_power + equipment_bonuses.attack_power;
-    let total_crit = stats.crit_chance + equipment_bonuses.crit_chance;
+    let attacker_profile = AttackerProfile {
+        attack_power: stats.attack_power + equipment_bonuses.attack_power,
+        crit_chance: stats.crit_chance + equipment_bonuses.crit_chance,
+        proficiency_level: equipment.weapon
+            .and_then(crate::weapon::WeaponType::from_item_id)
+            .map(|weapon_type| crate::weapon::get_proficiency_level(weapon_prof, &weapon_type))
+            .unwrap_or(0),
+    };
+    let mut rng = rand::thread_rng();
 
     // Process ability effects
     let current_time = time.elapsed().as_secs_f32();
-    const PIXELS_PER_METER: f32 = 20.0;
 
-    // First, determine if this is an AoE ability and collect targets
-    let mut aoe_params: Option<(f32, u32)> = None;
-    for ability_type in &ability.ability_types {
-        if let AbilityType::AreaOfEffect { radius, max_targets } = ability_type {
-            aoe_params = Some((*radius * PIXELS_PER_METER, *max_targets));
-            break;
+    // Projectiles carry the target effects and apply them on impact (see abilities::update_projectiles)
+    let projectile = ability.projectile();
+    if let Some((speed, radius)) = projectile {
+        let aim = primary_target_data.as_ref().map(|(_, target_pos, _)| *target_pos).or(request.target_position);
+        if let Some(aim) = aim {
+            let speed = speed * PIXELS_PER_METER;
+            let flight = ProjectileFlight {
+                caster: attacker_entity,
+                attacker: attacker_profile,
+                radius: radius * PIXELS_PER_METER,
+                expires_at: current_time + ability.range * PIXELS_PER_METER / speed.max(1.0),
+            };
+            let velocity = (aim - attacker_position).normalize_or(Vec2::X) * speed;
+            let projectile_entity = crate::abilities::spawn_projectile(&mut commands, ability.id, flight, attacker_position, velocity);
+            info!("Launched projectile {:?} for ability {}", projectile_entity, ability.name);
+        } else {
+            warn!("Projectile ability {} used without a target or aim point", ability.id);
         }
     }
 
-    // Collect targets for damage/effects
-    let affected_targets: Vec<(Entity, Vec2, CombatStats)> = if let Some((radius, max_targets)) = aoe_params {
-        // AoE: Find all enemies within radius of the primary target
-        let (_, target_pos, _) = primary_target_data.as_ref()
-            .expect("AoE ability should have a target");
-
-        let mut nearby_enemies: Vec<(Entity, Vec2, CombatStats, f32)> = Vec::new();
-        for (enemy_entity, enemy_pos, _, enemy_stats) in targets.iter() {
-            let dist = target_pos.distance(enemy_pos.0);
-            if dist <= radius {
-                nearby_enemies.push((enemy_entity, enemy_pos.0, *enemy_stats, dist));
+    // Collect targets for damage/effects. Hostile players are hit like enemies; other players
+    // are only caught in the area when friendly fire is on.
+    let mut friendly_targets: Vec<(Entity, Vec2, CombatStats)> = Vec::new();
+    let affected_targets: Vec<(Entity, Vec2, CombatStats)> = if projectile.is_some() {
+        vec![]
+    } else if let Some((radius, max_targets, shape, placement)) = aoe_params {
+        let primary_pos = primary_target_data.as_ref().map(|(_, target_pos, _)| *target_pos);
+        let origin = match placement {
+            AoePlacement::Target => primary_pos.expect("Targeted AoE ability should have a target"),
+            AoePlacement::Caster => attacker_position,
+            AoePlacement::Ground => ground_position.unwrap_or(attacker_position),
+        };
+        // Cones and lines point away from the caster, toward the target or else the cursor
+        let aim = primary_pos.or(request.target_position).unwrap_or(origin + Vec2::X);
+        let area = AoeArea {
+            shape,
+            origin,
+            direction: (aim - attacker_position).normalize_or(Vec2::X),
+            radius,
+            scale: PIXELS_PER_METER,
+        };
+
+        let mut hits: Vec<(Entity, Vec2, CombatStats, bool, f32)> = Vec::new();
+        for entity in area.overlapping(&spatial_query, [GameLayer::Enemy, GameLayer::Player], attacker_entity) {
+            let (position, stats, is_player) = if let Some((hostile_pos, hostile_stats)) = hostile(entity) {
+                (hostile_pos, hostile_stats, false)
+            } else if let (true, false, Ok((ally_pos, ally_stats))) = (formulas.friendly_fire, fallen.contains(entity), allies.get(entity)) {
+                (ally_pos.0, *ally_stats, true)
+            } else {
+                continue;
+            };
+            if matches!(shape, AoeShape::Cone { .. }) && !area.contains(position) {
+                continue;
             }
+            hits.push((entity, position, stats, is_player, origin.distance(position)));
         }
 
-        // Sort by distance and take up to max_targets
-        nearby_enemies.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
-        nearby_enemies.truncate(max_targets as usize);
+        // Closest targets first, up to max_targets
+        hits.sort_by(|a, b| a.4.total_cmp(&b.4));
+        hits.truncate(max_targets as usize);
 
-        info!("AoE ability hit {} targets within {} radius", nearby_enemies.len(), radius);
+        info!("AoE ability hit {} targets ({:?} at {:?})", hits.len(), shape, origin);
 
-        nearby_enemies.into_iter().map(|(e, p, s, _)| (e, p, s)).collect()
+        let mut enemies = Vec::new();
+        for (entity, position, stats, is_player, _) in hits {
+            if is_player {
+                friendly_targets.push((entity, position, stats));
+            } else {
+                enemies.push((entity, position, stats));
+            }
+        }
+        enemies
     } else if let Some((target_entity, target_pos, target_stats)) = primary_target_data {
         // Single target
         vec![(target_entity, target_pos, target_stats)]
@@ -421,6 +579,8 @@ pub fn handle_use_ability(
     // Track total damage for combat events
     let mut total_damage = 0.0;
     let mut is_crit = false;
+    // Avoidance result reported when the ability dealt no damage
+    let mut last_result = AttackResult::Hit;
     let mut primary_target_pos = attacker_position;
 
     // Process each ability effect
@@ -429,32 +589,56 @@ pub fn handle_use_ability(
             AbilityType::DirectDamage { multiplier } => {
                 // Apply damage to all affected targets
                 for (target_entity, target_pos, target_stats) in &affected_targets {
-                    let base_damage = total_attack * multiplier;
-                    let mitigation = target_stats.defense / (target_stats.defense + 100.0);
-                    let mut damage = base_damage * (1.0 - mitigation);
-
-                    // Critical hit check (roll once per target)
-                    let crit_roll = rand::random::<f32>() < total_crit;
-                    if crit_roll {
-                        damage *= 1.5;
+                    // Hit, avoidance and crit are rolled once per target
+                    let outcome = formulas.resolve_attack(
+                        &attacker_profile,
+                        &DefenderProfile::from_stats(target_stats),
+                        *multiplier,
+                        &mut rng,
+                    );
+                    let damage = outcome.damage;
+                    if outcome.result == AttackResult::Crit {
                         is_crit = true;
                     }
+                    if outcome.result != AttackResult::Hit && outcome.result != AttackResult::Crit {
+                        last_result = outcome.result;
+                    }
 
                     // Apply damage
                     if let Ok((_, _, mut target_health, _)) = targets.get_mut(*target_entity) {
                         target_health.current = (target_health.current - damage).max(0.0);
+                    } else {
+                        pvp::damage_player(&mut commands, *target_entity, damage);
                     }
 
-                    // Make enemy aggro on attacker
-                    if let Ok(mut enemy) = commands.get_entity(*target_entity) {
-                        enemy.insert(AiState::Chasing(attacker_entity));
-                        enemy.insert(CurrentTarget(Some(attacker_entity)));
-                    }
+                    // Generate threat on the target
+                    commands.trigger(ThreatEvent { enemy: *target_entity, source: attacker_entity, amount: damage });
+                    commands.trigger(CombatLogRecord::attack(attacker_entity, *target_entity, ability.id, &outcome, 0.0));
 
                     total_damage += damage;
                     primary_target_pos = *target_pos;
 
-                    info!("DirectDamage: {:?} took {:.1} damage (crit: {})", target_entity, damage, crit_roll);
+                    info!("DirectDamage: {:?} took {:.1} damage ({:?})", target_entity, damage, outcome.result);
+                }
+
+                // Friendly fire: players caught in the area take the damage too
+                for (ally_entity, ally_pos, ally_stats) in &friendly_targets {
+                    let outcome = formulas.resolve_attack(&attacker_profile, &DefenderProfile::from_stats(ally_stats), *multiplier, &mut rng);
+                    let damage = outcome.damage;
+                    pvp::damage_player(&mut commands, *ally_entity, damage);
+                    commands.server_trigger(ToClients {
+                        mode: SendMode::Broadcast,
+                        message: CombatEvent {
+                            attacker_position,
+                            target_position: *ally_pos,
+                            damage,
+                            ability_id: ability.id,
+                            is_crit: outcome.result == AttackResult::Crit,
+                            result: outcome.result,
+                        },
+                    });
+                    commands.trigger(CombatLogRecord::attack(attacker_entity, *ally_entity, ability.id, &outcome, 0.0));
+                    info!("Friendly fire: {:?} took {:.1} damage from {:?}", ally_entity, damage, char_entity);
                 }
             }
             AbilityType::DamageOverTime { duration: _, ticks, damage_per_tick } => {
@@ -480,36 +664,33 @@ pub fn handle_use_ability(
                 // Add buff to caster (self-buff)
                 let buff = ActiveBuff {
                     ability_id: ability.id,
+                    item_id: None,
                     stat_bonuses: stat_bonuses.clone(),
                     expires_at: current_time + duration,
                 };
 
                 if let Ok(mut caster) = commands.get_entity(char_entity) {
-                    caster.insert(ActiveBuffs {
-                        buffs: vec![buff],
-                    });
+                    // Keeps buffs from other abilities and consumables
+                    caster.queue(move |mut entity: EntityWorldMut| add_buff(&mut entity, buff));
                     info!("Applied buff from ability {} to caster", ability.id);
                 }
             }
             AbilityType::Debuff { duration, effect } => {
                 // Apply debuff to all affected targets
                 for (target_entity, _, _) in &affected_targets {
-                    let debuff = ActiveDebuff {
-                        ability_id: ability.id,
-                        effect: effect.clone(),
-                        expires_at: current_time + duration,
-                    };
-
-                    if let Ok(mut target) = commands.get_entity(*target_entity) {
-                        target.insert(ActiveDebuffs {
-                            debuffs: vec![debuff],
-                        });
-                        info!("Applied debuff to {:?}", target_entity);
-                    }
+                    // Crowd control is shortened by diminishing returns
+                    apply_debuff(&mut commands, attacker_entity, *target_entity, ability.id, effect.clone(), *duration, current_time);
+                    commands.trigger(ThreatEvent { enemy: *target_entity, source: attacker_entity, amount: 0.0 });
+                }
+            }
+            AbilityType::Taunt { duration } => {
+                for (target_entity, target_pos, _) in &affected_targets {
+                    commands.trigger(TauntEvent { enemy: *target_entity, taunter: attacker_entity, duration: *duration });
+                    primary_target_pos = *target_pos;
                 }
             }
-            AbilityType::AreaOfEffect { .. } => {
-                // AoE is handled by target collection above, no additional action needed
+            AbilityType::AreaOfEffect { .. } | AbilityType::Projectile { .. } => {
+                // AoE is handled by target collection above, projectiles on impact
             }
             AbilityType::Mobility { distance, dash_speed: _ } => {
                 // Determine dash direction - prefer target entity, fall back to cursor position
@@ -550,6 +731,16 @@ pub fn handle_use_ability(
                 info!("Heal: Restored {:.1} HP to caster (was {:.1}, now {:.1})",
                     actual_heal, old_health, attacker_health.current);
 
+                commands.trigger(CombatLogRecord {
+                    source: attacker_entity,
+                    target: attacker_entity,
+                    ability_id: ability.id,
+                    entry: CombatLogEntry::Heal { amount: actual_heal },
+                });
+
+                // Healing angers every enemy already fighting the caster
+                commands.trigger(HealingThreatEvent { healer: attacker_entity, amount: actual_heal });
+
                 // Send heal event to clients
                 commands.server_trigger(ToClients {
                     mode: SendMode::Broadcast,
@@ -559,9 +750,16 @@ pub fn handle_use_ability(
                         damage: -actual_heal, // Negative damage = heal
                         ability_id: ability.id,
                         is_crit: false,
+                        result: AttackResult::Hit,
                     },
                 });
             }
+            AbilityType::Resurrect { health_percent } => {
+                if let Some((player, position)) = resurrect_target {
+                    crate::death::resurrect(&mut commands, player, position, health_percent / 100.0);
+                    info!("Player {:?} resurrected {:?} with {}", char_entity, player, ability.name);
+                }
+            }
             AbilityType::ManaShield { duration, mana_per_damage } => {
                 // Apply Mana Shield to caster
                 let mana_shield = ActiveManaShield {
@@ -602,8 +800,13 @@ pub fn handle_use_ability(
         char_entity, ability.name, total_damage, is_crit, affected_targets.len()
     );
 
-    // Send combat event to all clients for VFX (only if there was damage dealt)
-    if total_damage > 0.0 {
+    // Send combat event to all clients for VFX (damage dealt, or the avoidance if everything missed)
+    if total_damage > 0.0 || last_result != AttackResult::Hit {
+        let result = if total_damage > 0.0 {
+            if is_crit { AttackResult::Crit } else { AttackResult::Hit }
+        } else {
+            last_result
+        };
         commands.server_trigger(ToClients {
             mode: SendMode::Broadcast,
             message: CombatEvent {
@@ -612,6 +815,7 @@ pub fn handle_use_ability(
                 damage: total_damage,
                 ability_id: ability.id,
                 is_crit,
+                result,
             },
         });
     }
@@ -630,7 +834,7 @@ pub fn update_ability_cooldowns(
 
 /// Regenerate health and mana over time
 pub fn regenerate_resources(
-    mut query: Query<(&mut Health, &mut Mana, &HealthRegen, &ManaRegen, &InCombat), With<Player>>,
+    mut query: Query<(&mut Health, &mut Mana, &HealthRegen, &ManaRegen, &InCombat), (With<Player>, Without<Dead>, Without<Ghost>)>,
     time: Res<Time>,
 ) {
     for (mut health, mut mana, health_regen, mana_regen, in_combat) in &mut query {
@@ -689,12 +893,31 @@ pub fn check_deaths(
         &mut Experience,
         &mut QuestLog,
     )>,
+    fallen: Query<(), Or<(With<Dead>, With<Ghost>)>>,
+    duelists: Query<(), With<Dueling>>,
+    xp_multiplier: Res<crate::events::XpMultiplier>,
     quest_db: Res<crate::game_data::QuestDatabase>,
+    loot_db: Res<crate::game_data::LootTableDatabase>,
 ) {
     for (entity, health, position, is_enemy, is_player, loot_table, enemy_type, enemy_name, spawn_point, move_speed, combat_stats, visual_shape, aggro_range) in &query {
-        if health.is_dead() {
+        // Duelists are held at 1 HP and lose the duel instead (see pvp::protect_duelists)
+        if duelists.contains(entity) {
+            continue;
+        }
+        // Players stay at zero health until resurrected; don't kill them twice
+        if health.is_dead() && !fallen.contains(entity) {
             info!("Entity {:?} died", entity);
 
+            // Log the death to whoever was fighting it (or the player who died), before it despawns
+            if is_player.is_some() {
+                commands.trigger(CombatLogRecord { source: entity, target: entity, ability_id: 0, entry: CombatLogEntry::Death });
+            }
+            for (player_entity, current_target, ..) in players.iter() {
+                if current_target.0 == Some(entity) {
+                    commands.trigger(CombatLogRecord { source: player_entity, target: entity, ability_id: 0, entry: CombatLogEntry::Death });
+                }
+            }
+
             // Trigger death event both for server (observers) and clients (visuals)
             commands.trigger(DeathEvent { entity, position: position.0 });
             commands.server_trigger(ToClients {
@@ -703,11 +926,16 @@ pub fn check_deaths(
             });
 
             if is_enemy.is_some() {
+                // Active quests of everyone credited with the kill, for quest-conditional drops
+                let mut looter_quests = HashSet::new();
+
                 // Grant XP to all players who had this enemy as their target
                 for (player_entity, mut current_target, mut auto_attack, mut in_combat, character, mut experience, mut quest_log) in &mut players {
                     if current_target.0 == Some(entity) {
-                        // Grant 50 base XP for killing an enemy
-                        let xp_gained = 50;
+                        looter_quests.extend(quest_log.active_quests.iter().map(|q| q.quest_id));
+
+                        // Grant 50 base XP for killing an enemy, more during XP events
+                        let xp_gained = (50.0 * xp_multiplier.0).round() as u32;
                         let leveled_up = experience.add_xp(xp_gained, character.level);
 
                         info!("{} gained {} XP for killing enemy", character.name, xp_gained);
@@ -754,7 +982,7 @@ pub fn check_deaths(
 
                 // Drop loot if enemy has a loot table
                 if let Some(loot) = loot_table {
-                    drop_loot(&mut commands, loot, *position, enemy_name);
+                    drop_loot(&mut commands, loot, &loot_db, &looter_quests, *position, enemy_name);
                 }
 
                 // Schedule respawn if enemy has a spawn point
@@ -778,6 +1006,7 @@ pub fn check_deaths(
                         spawn_position: sp.position,
                         respawn_delay: sp.respawn_delay,
                         template,
+                        region_id: sp.region_id.clone(),
                     });
 
                     info!("Scheduled respawn for {} at {:?} in {:.1}s", en.0, sp.position, sp.respawn_delay);
@@ -790,9 +1019,12 @@ pub fn check_deaths(
             }
 
             if is_player.is_some() {
-                // For now, just reset health (no death penalty in POC)
-                commands.entity(entity).insert(Health::new(100.0));
-                info!("Player respawned");
+                if let Ok((_, mut current_target, mut auto_attack, mut in_combat, character, ..)) = players.get_mut(entity) {
+                    auto_attack.enabled = false;
+                    in_combat.0 = false;
+                    current_target.0 = None;
+                    crate::death::kill_player(&mut commands, entity, position.0, &character.name);
+                }
             }
         }
     }
@@ -924,6 +1156,10 @@ pub fn check_weapon_proficiency_level_ups(
     }
 }
 
+/// Seconds until an enemy's next melee swing (server-only, not replicated)
+#[derive(Component, Default, Debug)]
+pub struct EnemySwingTimer(pub f32);
+
 pub fn enemy_ai(
     mut commands: Commands,
     mut enemies: Query<(
@@ -936,6 +1172,12 @@ pub fn enemy_ai(
         &CombatStats,
         &EnemyType,
         &AggroRange,
+        &mut EnemySwingTimer,
+        &mut ThreatTable,
+        &mut Health,
+        Option<&SpawnPoint>,
+        Option<&crate::behavior::EnemyBehavior>,
+        (Entity, Option<&crate::patrol::PatrolRoute>, Option<&ActiveDebuffs>),
     ), (With<Enemy>, Without<Player>, Without<AiActivationDelay>)>,
     mut players: Query<(
         Entity,
@@ -945,31 +1187,97 @@ pub fn enemy_ai(
         &CombatStats,
         &Equipment,
         &ArmorProficiency,
+        &WeaponProficiency,
         Option<&ActiveManaShield>,
     ), (With<Player>, Without<Enemy>)>,
     item_db: Res<crate::game_data::ItemDatabase>,
+    formulas: Res<CombatFormulas>,
+    spatial: Res<SpatialIndex>,
     time: Res<Time>,
 ) {
-    for (mut ai_state, enemy_pos, mut velocity, mut physics_velocity, mut current_target, move_speed, stats, _enemy_type, aggro_range) in &mut enemies {
-        match *ai_state {
-            AiState::Idle => {
-                // Look for nearby players
-                for (player_entity, player_pos, _, _, _, _, _, _) in &players {
-                    let distance = enemy_pos.0.distance(player_pos.0);
-                    if distance < aggro_range.aggro {
-                        *ai_state = AiState::Chasing(player_entity);
-                        current_target.0 = Some(player_entity);
-                        break;
-                    }
+    let _timer = crate::metrics::time_system("combat::enemy_ai");
+    for (mut ai_state, enemy_pos, mut velocity, mut physics_velocity, mut current_target, move_speed, stats, _enemy_type, aggro_range, mut swing, mut threat, mut enemy_health, spawn_point, behavior, (enemy_entity, patrol, debuffs)) in &mut enemies {
+        swing.0 = (swing.0 - time.delta_secs()).max(0.0);
+
+        // Stunned enemies neither move nor attack until it wears off
+        if debuffs.is_some_and(ActiveDebuffs::is_stunned) {
+            velocity.0 = Vec2::ZERO;
+            physics_velocity.0 = Vec2::ZERO;
+            continue;
+        }
+        let home = crate::patrol::enemy_home(spawn_point, patrol);
+
+        // Leashed enemies run home, then reset to full health
+        if matches!(*ai_state, AiState::Returning) {
+            let to_home = home.map(|home| home - enemy_pos.0).unwrap_or(Vec2::ZERO);
+            if to_home.length() <= LEASH_ARRIVE_DISTANCE {
+                *ai_state = AiState::Idle;
+                enemy_health.current = enemy_health.max;
+                velocity.0 = Vec2::ZERO;
+                physics_velocity.0 = Vec2::ZERO;
+            } else {
+                let vel = to_home.normalize() * move_speed.0;
+                velocity.0 = vel;
+                physics_velocity.0 = vel;
+            }
+            continue;
+        }
+
+        // Idle enemies pick up players wandering into aggro range
+        if matches!(*ai_state, AiState::Idle) {
+            if let Some((player_entity, ..)) = spatial.nearby(enemy_pos.0, aggro_range.aggro)
+                .filter_map(|entity| players.get(entity).ok())
+                .find(|(_, player_pos, health, ..)| health.current > 0.0 && enemy_pos.0.distance(player_pos.0) < aggro_range.aggro)
+            {
+                threat.add(player_entity, PROXIMITY_THREAT);
+            }
+        }
+
+        // Strayed too far from spawn - drop all threat and head home
+        if home.is_some_and(|home| enemy_pos.0.distance(home) > aggro_range.leash) {
+            threat.clear();
+            current_target.0 = None;
+            *ai_state = AiState::Returning;
+            continue;
+        }
+
+        // Attack whoever the threat table says
+        let engaged_target = match *ai_state {
+            AiState::Chasing(target) | AiState::Attacking(target) => Some(target),
+            AiState::Idle | AiState::Returning => None,
+        };
+        match threat.select_target(engaged_target) {
+            Some(target) if Some(target) != engaged_target => {
+                *ai_state = AiState::Chasing(target);
+                current_target.0 = Some(target);
+            }
+            Some(_) => {}
+            None => {
+                if engaged_target.is_some() {
+                    *ai_state = if home.is_some() { AiState::Returning } else { AiState::Idle };
+                    current_target.0 = None;
+                    velocity.0 = Vec2::ZERO;
+                    physics_velocity.0 = Vec2::ZERO;
                 }
+                continue;
             }
+        }
+
+        // A behavior tree movement action (flee, patrol) already steered this enemy
+        if behavior.is_some_and(|behavior| behavior.controlling) {
+            continue;
+        }
+
+        match *ai_state {
+            AiState::Idle | AiState::Returning => {}
             AiState::Chasing(target_entity) => {
                 // Check if target still exists
-                if let Ok((_, target_pos, _, _, _, _, _, _)) = players.get(target_entity) {
+                if let Ok((_, target_pos, _, _, _, _, _, _, _)) = players.get(target_entity) {
                     let distance = enemy_pos.0.distance(target_pos.0);
 
-                    // Check leash range
-                    if distance > aggro_range.leash {
+                    // Without a spawn point to leash to, give up when the target gets too far away
+                    if home.is_none() && distance > aggro_range.leash {
+                        threat.remove(target_entity);
                         *ai_state = AiState::Idle;
                         current_target.0 = None;
                         velocity.0 = Vec2::ZERO;
@@ -990,30 +1298,42 @@ pub fn enemy_ai(
                         physics_velocity.0 = vel;
                     }
                 } else {
+                    // Target is gone - the threat table picks a new one next frame
+                    threat.remove(target_entity);
                     *ai_state = AiState::Idle;
                     current_target.0 = None;
                 }
             }
             AiState::Attacking(target_entity) => {
                 // Check if target still exists and in range
-                if let Ok((player_entity, target_pos, mut target_health, mut target_mana, player_stats, equipment, armor_prof, mana_shield)) = players.get_mut(target_entity) {
+                if let Ok((player_entity, target_pos, mut target_health, mut target_mana, player_stats, equipment, armor_prof, weapon_prof, mana_shield)) = players.get_mut(target_entity) {
                     let distance = enemy_pos.0.distance(target_pos.0);
 
                     if distance > MELEE_RANGE {
                         *ai_state = AiState::Chasing(target_entity);
-                    } else {
-                        // Simple auto-attack every second
-                        // TODO: Add attack cooldown timer
-                        let base_damage = stats.attack_power;
+                    } else if swing.0 <= 0.0 {
+                        let interval = formulas.enemy_attack_interval.max(0.1);
+                        swing.0 = interval;
 
                         // Calculate player's total defense including equipment and armor proficiency
                         let equipment_bonuses = item_db.calculate_equipment_bonuses(equipment);
                         let armor_prof_bonus = item_db.calculate_armor_proficiency_bonus(equipment, armor_prof);
-                        let total_defense = player_stats.defense + equipment_bonuses.defense + armor_prof_bonus;
-
-                        // Apply defense mitigation
-                        let mitigation = total_defense / (total_defense + 100.0);
-                        let mut damage = base_damage * (1.0 - mitigation) * time.delta_secs();
+                        let defender = DefenderProfile {
+                            defense: player_stats.defense + equipment_bonuses.defense + armor_prof_bonus,
+                            weapon_proficiency_level: equipment.weapon
+                                .and_then(crate::weapon::WeaponType::from_item_id)
+                                .map(|weapon_type| crate::weapon::get_proficiency_level(weapon_prof, &weapon_type)),
+                            armor_proficiency_level: crate::combat_formulas::worn_armor_proficiency(&item_db, equipment, armor_prof),
+                        };
+                        let attacker = AttackerProfile {
+                            attack_power: stats.attack_power,
+                            crit_chance: stats.crit_chance,
+                            proficiency_level: 0,
+                        };
+
+                        // Attack power is damage per second, so each swing carries a full interval's worth
+                        let outcome = formulas.resolve_attack(&attacker, &defender, interval, &mut rand::thread_rng());
+                        let mut damage = outcome.damage;
 
                         // Check for Mana Shield - absorb damage using mana
                         if let Some(shield) = mana_shield {
@@ -1037,8 +1357,23 @@ pub fn enemy_ai(
                         }
 
                         target_health.current = (target_health.current - damage).max(0.0);
+                        commands.trigger(CombatLogRecord::attack(enemy_entity, player_entity, 0, &outcome, outcome.damage - damage));
+
+                        commands.server_trigger(ToClients {
+                            mode: SendMode::Broadcast,
+                            message: CombatEvent {
+                                attacker_position: enemy_pos.0,
+                                target_position: target_pos.0,
+                                damage,
+                                ability_id: 0,
+                                is_crit: outcome.result == AttackResult::Crit,
+                                result: outcome.result,
+                            },
+                        });
                     }
                 } else {
+                    // Target is gone - the threat table picks a new one next frame
+                    threat.remove(target_entity);
                     *ai_state = AiState::Idle;
                     current_target.0 = None;
                 }
@@ -1065,8 +1400,17 @@ pub fn update_ai_activation_delays(
 }
 
 /// Drop loot from an enemy based on its loot table
-/// Spawns a LootContainer entity containing all dropped gold and items
-fn drop_loot(commands: &mut Commands, loot_table: &LootTable, position: Position, enemy_name: Option<&EnemyName>) {
+/// Spawns a LootContainer entity containing all dropped gold and items.
+/// `active_quests` holds the active quest ids of every player credited with the kill,
+/// used to gate quest-conditional drops.
+fn drop_loot(
+    commands: &mut Commands,
+    loot_table: &LootTable,
+    loot_db: &crate::game_data::LootTableDatabase,
+    active_quests: &HashSet<u32>,
+    position: Position,
+    enemy_name: Option<&EnemyName>,
+) {
     let mut rng = rand::thread_rng();
     let mut loot_contents = Vec::new();
 
@@ -1075,37 +1419,20 @@ fn drop_loot(commands: &mut Commands, loot_table: &LootTable, position: Position
         .map(|n| n.0.clone())
         .unwrap_or_else(|| "Unknown Enemy".to_string());
 
-    // Always drop gold if the range is non-zero
-    if loot_table.gold_max > 0 {
-        let gold_amount = if loot_table.gold_min == loot_table.gold_max {
-            loot_table.gold_max
-        } else {
-            rng.gen_range(loot_table.gold_min..=loot_table.gold_max)
-        };
+    roll_loot_table(loot_table, active_quests, &mut rng, &mut loot_contents);
 
-        if gold_amount > 0 {
-            loot_contents.push(LootContents::Gold(gold_amount));
-            info!("Rolling {} gold into loot container at {:?}", gold_amount, position.0);
+    // Roll the referenced data-driven table on top of the inline entries
+    if let Some(table_id) = &loot_table.table_id {
+        match loot_db.tables.get(table_id) {
+            Some(definition) => roll_loot_table(&definition.table, active_quests, &mut rng, &mut loot_contents),
+            None => warn!("Loot table '{}' referenced by {} not found", table_id, source_name),
         }
     }
 
-    // Roll for item drops
-    for loot_item in &loot_table.items {
-        let roll: f32 = rng.gen();
-        if roll <= loot_item.drop_chance {
-            let quantity = if loot_item.quantity_min == loot_item.quantity_max {
-                loot_item.quantity_max
-            } else {
-                rng.gen_range(loot_item.quantity_min..=loot_item.quantity_max)
-            };
-
-            if quantity > 0 {
-                loot_contents.push(LootContents::Item(ItemStack {
-                    item_id: loot_item.item_id,
-                    quantity,
-                }));
-                info!("Rolling item {} (x{}) into loot container at {:?}", loot_item.item_id, quantity, position.0);
-            }
+    for contents in &loot_contents {
+        match contents {
+            LootContents::Gold(amount) => info!("Rolling {} gold into loot container at {:?}", amount, position.0),
+            LootContents::Item(stack) => info!("Rolling item {} (x{}) into loot container at {:?}", stack.item_id, stack.quantity, position.0),
         }
     }
 
@@ -1129,3 +1456,76 @@ fn drop_loot(commands: &mut Commands, loot_table: &LootTable, position: Position
         info!("Spawned loot container from {} with {} items at {:?}", source_name, loot_contents.len(), position.0);
     }
 }
+
+/// Roll a single loot table into `loot_contents`: gold, independent item drops, then weighted groups
+fn roll_loot_table(
+    loot_table: &LootTable,
+    active_quests: &HashSet<u32>,
+    rng: &mut impl Rng,
+    loot_contents: &mut Vec<LootContents>,
+) {
+    let quest_allows = |required: Option<u32>| required.is_none_or(|quest_id| active_quests.contains(&quest_id));
+
+    // Always drop gold if the range is non-zero
+    if loot_table.gold_max > 0 {
+        let gold_amount = roll_quantity(rng, loot_table.gold_min, loot_table.gold_max);
+        if gold_amount > 0 {
+            loot_contents.push(LootContents::Gold(gold_amount));
+        }
+    }
+
+    // Roll for independent item drops
+    for loot_item in &loot_table.items {
+        if !quest_allows(loot_item.required_quest) {
+            continue;
+        }
+
+        let roll: f32 = rng.gen();
+        if roll <= loot_item.drop_chance {
+            let quantity = roll_quantity(rng, loot_item.quantity_min, loot_item.quantity_max);
+            if quantity > 0 {
+                loot_contents.push(LootContents::Item(ItemStack {
+                    item_id: loot_item.item_id,
+                    quantity,
+                }));
+            }
+        }
+    }
+
+    // Each group roll picks at most one eligible entry by weight
+    for group in &loot_table.groups {
+        let eligible: Vec<&LootGroupEntry> = group.entries.iter()
+            .filter(|entry| entry.weight > 0.0 && quest_allows(entry.required_quest))
+            .collect();
+        let total_weight: f32 = eligible.iter().map(|entry| entry.weight).sum::<f32>() + group.empty_weight.max(0.0);
+        if eligible.is_empty() || total_weight <= 0.0 {
+            continue;
+        }
+
+        for _ in 0..group.rolls {
+            let mut roll = rng.gen_range(0.0..total_weight);
+            let picked = eligible.iter().find(|entry| {
+                roll -= entry.weight;
+                roll < 0.0
+            });
+
+            // Falling through every entry means the empty weight was rolled
+            let Some(entry) = picked else { continue };
+            let quantity = roll_quantity(rng, entry.quantity_min, entry.quantity_max);
+            if quantity > 0 {
+                loot_contents.push(LootContents::Item(ItemStack {
+                    item_id: entry.item_id,
+                    quantity,
+                }));
+            }
+        }
+    }
+}
+
+fn roll_quantity(rng: &mut impl Rng, min: u32, max: u32) -> u32 {
+    if min >= max {
+        max
+    } else {
+        rng.gen_range(min..=max)
+    }
+}
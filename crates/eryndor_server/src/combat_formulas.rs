@@ -0,0 +1,290 @@
+//! Combat formulas for hit, crit, avoidance and mitigation.
+//!
+//! All tunables live in `CombatFormulas`, which is loaded from
+//! `assets/content/formulas/*.formulas.json` so designers can adjust them from the editor.
+//! Combat systems describe the two sides of an attack as profiles and let
+//! `CombatFormulas::resolve_attack` roll the outcome.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::game_data::{ArmorClass, ItemDatabase};
+
+/// Data-driven combat tuning values
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CombatFormulas {
+    pub id: String,
+    pub name: String,
+    /// Defense needed to mitigate half of incoming damage
+    pub mitigation_constant: f32,
+    pub crit_multiplier: f32,
+    pub base_hit_chance: f32,
+    pub hit_per_proficiency_level: f32,
+    pub crit_per_proficiency_level: f32,
+    pub damage_per_proficiency_level: f32,
+    pub base_dodge_chance: f32,
+    pub dodge_per_defense: f32,
+    pub max_dodge_chance: f32,
+    /// Parry needs a weapon; scales with proficiency in the equipped weapon
+    pub base_parry_chance: f32,
+    pub parry_per_weapon_proficiency_level: f32,
+    pub max_parry_chance: f32,
+    /// Block scales with proficiency in the worn armor class
+    pub base_block_chance: f32,
+    pub block_per_armor_proficiency_level: f32,
+    pub max_block_chance: f32,
+    /// Fraction of damage removed by a block
+    pub block_damage_reduction: f32,
+    /// Seconds between enemy melee swings
+    pub enemy_attack_interval: f32,
+    /// Damaging area-of-effect abilities also hit other players (never the caster)
+    pub friendly_fire: bool,
+}
+
+impl Default for CombatFormulas {
+    fn default() -> Self {
+        Self {
+            id: "combat".to_string(),
+            name: "Combat Formulas".to_string(),
+            mitigation_constant: 100.0,
+            crit_multiplier: 1.5,
+            base_hit_chance: 0.95,
+            hit_per_proficiency_level: 0.002,
+            crit_per_proficiency_level: 0.0,
+            damage_per_proficiency_level: 0.02,
+            base_dodge_chance: 0.05,
+            dodge_per_defense: 0.0005,
+            max_dodge_chance: 0.3,
+            base_parry_chance: 0.0,
+            parry_per_weapon_proficiency_level: 0.005,
+            max_parry_chance: 0.25,
+            base_block_chance: 0.0,
+            block_per_armor_proficiency_level: 0.01,
+            max_block_chance: 0.3,
+            block_damage_reduction: 0.5,
+            enemy_attack_interval: 1.0,
+            friendly_fire: false,
+        }
+    }
+}
+
+/// Offensive side of an attack
+#[derive(Clone, Copy, Debug)]
+pub struct AttackerProfile {
+    /// Attack power including equipment
+    pub attack_power: f32,
+    /// Crit chance including equipment
+    pub crit_chance: f32,
+    /// Proficiency level with the weapon used (0 for enemies)
+    pub proficiency_level: u32,
+}
+
+/// Defensive side of an attack
+#[derive(Clone, Copy, Debug)]
+pub struct DefenderProfile {
+    /// Defense including equipment and armor proficiency
+    pub defense: f32,
+    /// Proficiency with the equipped weapon, if any (enables parry)
+    pub weapon_proficiency_level: Option<u32>,
+    /// Proficiency with worn armor, if any (enables block)
+    pub armor_proficiency_level: Option<u32>,
+}
+
+impl DefenderProfile {
+    /// Defender without weapon or armor skills (enemies)
+    pub fn from_stats(stats: &CombatStats) -> Self {
+        Self {
+            defense: stats.defense,
+            weapon_proficiency_level: None,
+            armor_proficiency_level: None,
+        }
+    }
+}
+
+/// Rolled result of a single attack
+#[derive(Clone, Copy, Debug)]
+pub struct AttackOutcome {
+    pub result: AttackResult,
+    pub damage: f32,
+    /// Damage removed by defense and blocking
+    pub mitigated: f32,
+}
+
+impl CombatFormulas {
+    /// Fraction of damage removed by defense
+    pub fn mitigation(&self, defense: f32) -> f32 {
+        let defense = defense.max(0.0);
+        defense / (defense + self.mitigation_constant.max(f32::EPSILON))
+    }
+
+    /// Damage multiplier from weapon proficiency (levels start at 1)
+    pub fn proficiency_damage_bonus(&self, proficiency_level: u32) -> f32 {
+        1.0 + proficiency_level.saturating_sub(1) as f32 * self.damage_per_proficiency_level
+    }
+
+    pub fn hit_chance(&self, attacker: &AttackerProfile) -> f32 {
+        (self.base_hit_chance + attacker.proficiency_level as f32 * self.hit_per_proficiency_level).clamp(0.0, 1.0)
+    }
+
+    pub fn crit_chance(&self, attacker: &AttackerProfile) -> f32 {
+        (attacker.crit_chance + attacker.proficiency_level as f32 * self.crit_per_proficiency_level).clamp(0.0, 1.0)
+    }
+
+    // Caps are applied with min/max rather than clamp, which panics on a negative cap
+    pub fn dodge_chance(&self, defender: &DefenderProfile) -> f32 {
+        (self.base_dodge_chance + defender.defense.max(0.0) * self.dodge_per_defense).min(self.max_dodge_chance).max(0.0)
+    }
+
+    pub fn parry_chance(&self, defender: &DefenderProfile) -> f32 {
+        defender.weapon_proficiency_level
+            .map(|level| (self.base_parry_chance + level as f32 * self.parry_per_weapon_proficiency_level).min(self.max_parry_chance).max(0.0))
+            .unwrap_or(0.0)
+    }
+
+    pub fn block_chance(&self, defender: &DefenderProfile) -> f32 {
+        defender.armor_proficiency_level
+            .map(|level| (self.base_block_chance + level as f32 * self.block_per_armor_proficiency_level).min(self.max_block_chance).max(0.0))
+            .unwrap_or(0.0)
+    }
+
+    /// Roll an attack dealing `multiplier` times the attacker's power.
+    /// Avoidance is checked in order miss, dodge, parry, then block and crit modify the damage.
+    pub fn resolve_attack(
+        &self,
+        attacker: &AttackerProfile,
+        defender: &DefenderProfile,
+        multiplier: f32,
+        rng: &mut impl Rng,
+    ) -> AttackOutcome {
+        let avoided = |result| AttackOutcome { result, damage: 0.0, mitigated: 0.0 };
+
+        if rng.gen::<f32>() >= self.hit_chance(attacker) {
+            return avoided(AttackResult::Miss);
+        }
+        if rng.gen::<f32>() < self.dodge_chance(defender) {
+            return avoided(AttackResult::Dodge);
+        }
+        if rng.gen::<f32>() < self.parry_chance(defender) {
+            return avoided(AttackResult::Parry);
+        }
+
+        let mut base_damage = attacker.attack_power * multiplier * self.proficiency_damage_bonus(attacker.proficiency_level);
+        let mut damage = base_damage * (1.0 - self.mitigation(defender.defense));
+        let mut result = AttackResult::Hit;
+
+        if rng.gen::<f32>() < self.block_chance(defender) {
+            damage *= 1.0 - self.block_damage_reduction.clamp(0.0, 1.0);
+            result = AttackResult::Block;
+        } else if rng.gen::<f32>() < self.crit_chance(attacker) {
+            base_damage *= self.crit_multiplier;
+            damage *= self.crit_multiplier;
+            result = AttackResult::Crit;
+        }
+
+        let damage = damage.max(0.0);
+        AttackOutcome { result, damage, mitigated: (base_damage - damage).max(0.0) }
+    }
+}
+
+/// Highest proficiency level among the armor classes the player is wearing
+pub fn worn_armor_proficiency(item_db: &ItemDatabase, equipment: &Equipment, armor_prof: &ArmorProficiency) -> Option<u32> {
+    [equipment.helmet, equipment.chest, equipment.legs, equipment.boots]
+        .iter()
+        .flatten()
+        .filter_map(|item_id| item_db.items.get(item_id)?.armor_class)
+        .map(|armor_class| match armor_class {
+            ArmorClass::Light => armor_prof.light,
+            ArmorClass::Medium => armor_prof.medium,
+            ArmorClass::Heavy => armor_prof.heavy,
+        })
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn attacker() -> AttackerProfile {
+        AttackerProfile { attack_power: 100.0, crit_chance: 0.0, proficiency_level: 1 }
+    }
+
+    #[test]
+    fn mitigation_halves_damage_at_constant() {
+        let formulas = CombatFormulas::default();
+        assert!((formulas.mitigation(formulas.mitigation_constant) - 0.5).abs() < 1e-6);
+        assert_eq!(formulas.mitigation(0.0), 0.0);
+    }
+
+    #[test]
+    fn avoidance_chances_are_capped() {
+        let formulas = CombatFormulas::default();
+        let defender = DefenderProfile {
+            defense: 100_000.0,
+            weapon_proficiency_level: Some(1_000),
+            armor_proficiency_level: Some(1_000),
+        };
+        assert_eq!(formulas.dodge_chance(&defender), formulas.max_dodge_chance);
+        assert_eq!(formulas.parry_chance(&defender), formulas.max_parry_chance);
+        assert_eq!(formulas.block_chance(&defender), formulas.max_block_chance);
+    }
+
+    #[test]
+    fn negative_caps_mean_no_avoidance() {
+        let formulas = CombatFormulas {
+            max_dodge_chance: -0.1,
+            max_parry_chance: -0.1,
+            max_block_chance: -0.1,
+            ..CombatFormulas::default()
+        };
+        let defender = DefenderProfile {
+            defense: 100.0,
+            weapon_proficiency_level: Some(10),
+            armor_proficiency_level: Some(10),
+        };
+        assert_eq!(formulas.dodge_chance(&defender), 0.0);
+        assert_eq!(formulas.parry_chance(&defender), 0.0);
+        assert_eq!(formulas.block_chance(&defender), 0.0);
+    }
+
+    #[test]
+    fn no_parry_or_block_without_skills() {
+        let formulas = CombatFormulas::default();
+        let defender = DefenderProfile::from_stats(&CombatStats::default());
+        assert_eq!(formulas.parry_chance(&defender), 0.0);
+        assert_eq!(formulas.block_chance(&defender), 0.0);
+    }
+
+    #[test]
+    fn guaranteed_hit_applies_mitigation() {
+        let formulas = CombatFormulas {
+            base_hit_chance: 1.0,
+            base_dodge_chance: 0.0,
+            dodge_per_defense: 0.0,
+            ..Default::default()
+        };
+        let defender = DefenderProfile { defense: 100.0, weapon_proficiency_level: None, armor_proficiency_level: None };
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let outcome = formulas.resolve_attack(&attacker(), &defender, 1.0, &mut rng);
+        assert_eq!(outcome.result, AttackResult::Hit);
+        assert!((outcome.damage - 50.0).abs() < 1e-4);
+        assert!((outcome.mitigated - 50.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_hit_chance_always_misses() {
+        let formulas = CombatFormulas { base_hit_chance: 0.0, hit_per_proficiency_level: 0.0, ..Default::default() };
+        let defender = DefenderProfile::from_stats(&CombatStats::default());
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..20 {
+            let outcome = formulas.resolve_attack(&attacker(), &defender, 1.0, &mut rng);
+            assert_eq!(outcome.result, AttackResult::Miss);
+            assert_eq!(outcome.damage, 0.0);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Structured combat log.
+//!
+//! Combat code triggers a `CombatLogRecord` naming the entities involved. `send_combat_log`
+//! resolves their display names and sends a `CombatLogEvent` to the players on either end.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use crate::combat_formulas::AttackOutcome;
+
+/// A combat log entry between two entities
+#[derive(Event, Clone, Debug)]
+pub struct CombatLogRecord {
+    pub source: Entity,
+    pub target: Entity,
+    /// Ability used, 0 for auto-attacks
+    pub ability_id: u32,
+    pub entry: CombatLogEntry,
+}
+
+impl CombatLogRecord {
+    /// Damage or avoidance from a resolved attack; `absorbed` is damage a mana shield soaked up
+    pub fn attack(source: Entity, target: Entity, ability_id: u32, outcome: &AttackOutcome, absorbed: f32) -> Self {
+        let entry = match outcome.result {
+            AttackResult::Miss | AttackResult::Dodge | AttackResult::Parry => CombatLogEntry::Avoided { result: outcome.result },
+            AttackResult::Hit | AttackResult::Crit | AttackResult::Block => CombatLogEntry::Damage {
+                amount: outcome.damage - absorbed,
+                result: outcome.result,
+                mitigated: outcome.mitigated,
+                absorbed,
+            },
+        };
+        Self { source, target, ability_id, entry }
+    }
+}
+
+/// Resolve names and send the entry to whichever players were involved
+pub fn send_combat_log(
+    trigger: On<CombatLogRecord>,
+    mut commands: Commands,
+    names: Query<(Option<&Character>, Option<&EnemyName>, Option<&NpcName>, Option<&OwnedBy>)>,
+) {
+    let record = trigger.event();
+
+    let name_of = |entity: Entity| {
+        names.get(entity).ok()
+            .and_then(|(character, enemy, npc, _)| {
+                character.map(|c| c.name.clone())
+                    .or_else(|| enemy.map(|e| e.0.clone()))
+                    .or_else(|| npc.map(|n| n.0.clone()))
+            })
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+    let event = CombatLogEvent {
+        source: name_of(record.source),
+        target: name_of(record.target),
+        ability_id: record.ability_id,
+        entry: record.entry.clone(),
+    };
+
+    let mut recipients: Vec<Entity> = [record.source, record.target]
+        .into_iter()
+        .filter_map(|entity| names.get(entity).ok().and_then(|(.., owner)| owner).map(|owner| owner.0))
+        .collect();
+    recipients.dedup();
+
+    for client in recipients {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client)),
+            message: event.clone(),
+        });
+    }
+}
@@ -10,6 +10,22 @@ pub struct ServerConfig {
     pub rate_limits: RateLimits,
     pub moderation: Moderation,
     pub oauth: OAuth,
+    #[serde(default)]
+    pub network: Network,
+    #[serde(default)]
+    pub chat: Chat,
+    #[serde(default)]
+    pub metrics: Metrics,
+    #[serde(default)]
+    pub profiling: Profiling,
+    #[serde(default)]
+    pub shutdown: Shutdown,
+    #[serde(default)]
+    pub persistence: Persistence,
+    #[serde(default)]
+    pub shard: Shard,
+    #[serde(default)]
+    pub relay: Relay,
 }
 
 #[derive(Clone, Deserialize)]
@@ -21,7 +37,6 @@ pub struct Server {
 #[derive(Clone, Deserialize)]
 pub struct Admin {
     pub dashboard_enabled: bool,
-    pub dashboard_port: u16,
     pub jwt_secret: String,
     pub jwt_expiry_hours: i64,
 }
@@ -57,12 +72,228 @@ pub struct OAuth {
     pub google_client_secret: String,
 }
 
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Network {
+    /// Replication snapshots sent to clients per second (independent of the 60 Hz physics tick)
+    pub tick_rate: u32,
+    /// Most times per second each of these is sent. They change every frame for anything
+    /// moving or regenerating; 0 sends them in every snapshot. Everything else is only sent
+    /// when it changes.
+    pub position_rate: u32,
+    pub velocity_rate: u32,
+    pub health_rate: u32,
+    pub mana_rate: u32,
+    /// Entities within this many pixels of a player's character are updated in every snapshot
+    pub full_priority_distance: f32,
+    /// Update priority for the furthest entities: 0.25 sends them in one snapshot in four
+    pub min_priority: f32,
+    /// Bytes per second per client before far entities are slowed further (0 = no budget)
+    pub client_bandwidth_budget: u32,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            tick_rate: 30,
+            position_rate: 20,
+            velocity_rate: 20,
+            health_rate: 10,
+            mana_rate: 10,
+            full_priority_distance: 600.0,
+            min_priority: 0.2,
+            client_bandwidth_budget: 32_768,
+        }
+    }
+}
+
+/// Prometheus metrics export
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Metrics {
+    /// Serve /metrics on the HTTP server
+    pub enabled: bool,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Chrome trace recording (`/trace start`, `/trace stop`)
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Profiling {
+    /// Directory trace files are written to
+    pub trace_dir: String,
+}
+
+impl Default for Profiling {
+    fn default() -> Self {
+        Self { trace_dir: "traces".to_string() }
+    }
+}
+
+/// Periodic character checkpoints
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Persistence {
+    /// Seconds between saves of characters whose state changed
+    pub checkpoint_interval_seconds: u64,
+}
+
+impl Default for Persistence {
+    fn default() -> Self {
+        Self { checkpoint_interval_seconds: 60 }
+    }
+}
+
+/// This process's world shard. Every server process is one shard; processes sharing a
+/// database find each other through it and players pick a shard at character select.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Shard {
+    /// Stored with every character created here. Must be unique among the shards.
+    pub id: String,
+    /// Shown in the shard selector
+    pub name: String,
+    /// Host clients connect to; the ports are this process's SERVER_PORT* settings
+    pub public_address: String,
+    /// Where web clients fetch the WebTransport certificate hash.
+    /// Empty means http://<public_address>:<SERVER_CERT_PORT>/cert.
+    pub cert_url: String,
+    /// Seconds between registry updates; shards not heard from in three intervals show offline
+    pub heartbeat_seconds: u64,
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self {
+            id: "main".to_string(),
+            name: "Eryndor".to_string(),
+            public_address: "127.0.0.1".to_string(),
+            cert_url: String::new(),
+            heartbeat_seconds: 10,
+        }
+    }
+}
+
+/// Message relay between shard processes, for global chat, friend presence and admin
+/// broadcasts. One process hosts the hub; every shard (the host included) connects to it.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Relay {
+    pub enabled: bool,
+    /// Address to host the hub on, e.g. "0.0.0.0:5010". Empty on every shard but one.
+    pub hub_bind: String,
+    /// Hub every shard connects to
+    pub hub_address: String,
+    /// Shared by the hub and every shard; connections without it are dropped
+    pub secret: String,
+}
+
+impl Default for Relay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hub_bind: String::new(),
+            hub_address: "127.0.0.1:5010".to_string(),
+            secret: String::new(),
+        }
+    }
+}
+
+/// Graceful shutdown on SIGTERM / Ctrl+C
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Shutdown {
+    /// Seconds players get to finish up before the server exits
+    pub grace_period_seconds: u64,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self { grace_period_seconds: 8 }
+    }
+}
+
+/// Chat rate limits and filtering.
+/// `rate_limits.chat_messages_per_minute` caps all channels together.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Chat {
+    /// Messages per minute each player may send in each channel
+    pub say_per_minute: u32,
+    pub party_per_minute: u32,
+    pub guild_per_minute: u32,
+    pub global_per_minute: u32,
+    pub whisper_per_minute: u32,
+    /// Words to act on, on top of the profanity filter in `[moderation]`
+    pub filtered_words: Vec<FilteredWord>,
+    /// Drop messages containing links to domains that aren't allowed
+    pub block_links: bool,
+    pub allowed_link_domains: Vec<String>,
+    /// The same message sent this many times in a minute counts as spam
+    pub max_duplicate_messages: u32,
+    /// Runs of one character longer than this ("!!!!!!!!!!!!") count as spam
+    pub max_repeated_characters: usize,
+    /// Blocked messages within `strike_window_minutes` before the player is muted
+    pub strikes_before_mute: u32,
+    pub strike_window_minutes: u32,
+    pub auto_mute_minutes: u32,
+}
+
+impl Default for Chat {
+    fn default() -> Self {
+        Self {
+            say_per_minute: 10,
+            party_per_minute: 10,
+            guild_per_minute: 10,
+            global_per_minute: 3,
+            whisper_per_minute: 10,
+            filtered_words: Vec::new(),
+            block_links: true,
+            allowed_link_domains: Vec::new(),
+            max_duplicate_messages: 3,
+            max_repeated_characters: 12,
+            strikes_before_mute: 3,
+            strike_window_minutes: 10,
+            auto_mute_minutes: 15,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct FilteredWord {
+    pub word: String,
+    pub severity: FilterSeverity,
+}
+
+/// What happens to a message containing a filtered word
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterSeverity {
+    /// Replace the word with asterisks
+    Censor,
+    /// Drop the message and give the sender a strike
+    Block,
+    /// Drop the message and mute the sender straight away
+    Mute,
+}
+
 impl OAuth {
     pub fn is_google_enabled(&self) -> bool {
         !self.google_client_id.is_empty()
     }
 }
 
+/// Placeholder secrets shipped in the defaults and `config.example.toml`
+const PUBLIC_JWT_SECRETS: [&str; 2] = ["dev-secret-change-in-production-please", "REPLACE-WITH-SECURE-SECRET"];
+
+/// Shortest `admin.jwt_secret` accepted while the dashboard is enabled
+const MIN_JWT_SECRET_BYTES: usize = 32;
+
 impl ServerConfig {
     pub fn load() -> Result<Self, String> {
         // Support CONFIG_PATH environment variable, default to "config.toml"
@@ -89,20 +320,64 @@ impl ServerConfig {
             config.oauth.google_client_secret = client_secret;
         }
 
+        // Several shard processes can share one config file
+        if let Ok(shard_id) = std::env::var("SHARD_ID") {
+            config.shard.id = shard_id;
+        }
+        if let Ok(shard_name) = std::env::var("SHARD_NAME") {
+            config.shard.name = shard_name;
+        }
+        if let Ok(public_address) = std::env::var("SHARD_PUBLIC_ADDRESS") {
+            config.shard.public_address = public_address;
+        }
+        if let Ok(hub_bind) = std::env::var("RELAY_HUB_BIND") {
+            config.relay.hub_bind = hub_bind;
+        }
+        if let Ok(hub_address) = std::env::var("RELAY_HUB_ADDRESS") {
+            config.relay.hub_address = hub_address;
+        }
+        if let Ok(secret) = std::env::var("RELAY_SECRET") {
+            config.relay.secret = secret;
+        }
+
         // Override JWT secret from environment variable (for production secrets)
         if let Ok(jwt_secret) = std::env::var("JWT_SECRET") {
             config.admin.jwt_secret = jwt_secret;
         }
 
         // Validate configuration
-        if config.admin.dashboard_enabled && config.admin.jwt_secret == "dev-secret-change-in-production-please" {
-            warn!("WARNING: Using default JWT secret! Generate a secure one for production with: openssl rand -base64 32");
+        // The secret signs admin tokens, so a known one hands out the console to anybody
+        if config.admin.dashboard_enabled {
+            let secret = config.admin.jwt_secret.as_str();
+            if PUBLIC_JWT_SECRETS.contains(&secret) || secret.len() < MIN_JWT_SECRET_BYTES {
+                return Err(format!(
+                    "admin.jwt_secret must be a private value of at least {} bytes while the dashboard is enabled \
+                     (set JWT_SECRET, e.g. from: openssl rand -base64 32)",
+                    MIN_JWT_SECRET_BYTES,
+                ));
+            }
         }
 
         if config.security.password_min_length < 6 {
             return Err("password_min_length must be at least 6".to_string());
         }
 
+        if config.shard.id.is_empty() || config.shard.id.len() > 32 {
+            return Err("shard.id must be 1-32 characters".to_string());
+        }
+
+        if config.relay.enabled && config.relay.secret.is_empty() {
+            return Err("relay.secret must be set when the relay is enabled".to_string());
+        }
+
+        if !(1..=120).contains(&config.network.tick_rate) {
+            return Err("network.tick_rate must be between 1 and 120".to_string());
+        }
+
+        if !(config.network.min_priority > 0.0 && config.network.min_priority <= 1.0) {
+            return Err("network.min_priority must be above 0 and at most 1".to_string());
+        }
+
         info!("Configuration loaded successfully");
         Ok(config)
     }
@@ -117,7 +392,6 @@ impl Default for ServerConfig {
             },
             admin: Admin {
                 dashboard_enabled: true,
-                dashboard_port: 8080,
                 jwt_secret: "dev-secret-change-in-production-please".to_string(),
                 jwt_expiry_hours: 24,
             },
@@ -144,6 +418,14 @@ impl Default for ServerConfig {
                 google_client_id: String::new(),
                 google_client_secret: String::new(),
             },
+            network: Network::default(),
+            chat: Chat::default(),
+            metrics: Metrics::default(),
+            profiling: Profiling::default(),
+            shutdown: Shutdown::default(),
+            persistence: Persistence::default(),
+            shard: Shard::default(),
+            relay: Relay::default(),
         }
     }
 }
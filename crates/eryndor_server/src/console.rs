@@ -0,0 +1,350 @@
+//! Operator console.
+//!
+//! Runtime commands for whoever runs the server, without a game client: type them into the
+//! server's terminal, or POST them to `/api/admin/console` from the admin API. Both feed the
+//! same queue, which the game loop works through once a frame.
+//!
+//! ```text
+//! who
+//! kick Alice Spamming trade chat
+//! broadcast Restarting in 10 minutes
+//! save-all
+//! reload-data
+//! spawn 3 400 250 5
+//! set-loglevel info,eryndor_server::combat=debug
+//! ```
+
+use bevy::log::tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+use bevy::log::BoxedLayer;
+use bevy::prelude::*;
+use eryndor_shared::*;
+use std::io::BufRead;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::oneshot;
+use crate::auth::Authenticated;
+use crate::database::DatabaseConnection;
+use crate::game_data::EnemyDatabase;
+use crate::persistence::{queue_save, CharacterSaveData};
+use crate::world::{CurrentZone, STARTER_ZONE};
+use crate::relay::Relay;
+use crate::spawn::{EnemyTemplate, EntityTemplate};
+
+const DEFAULT_KICK_REASON: &str = "Kicked by an administrator";
+
+/// Handle for `set-loglevel`, installed by `log_layers`
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// A parsed console command
+#[derive(Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Who,
+    Kick {
+        character_name: String,
+        reason: String,
+    },
+    Broadcast {
+        message: String,
+    },
+    SaveAll,
+    ReloadData,
+    Spawn {
+        enemy_id: u32,
+        position: Vec2,
+        level: u32,
+    },
+    SetLogLevel {
+        filter: String,
+    },
+    Help,
+}
+
+pub fn parse_console_command(line: &str) -> Result<ConsoleCommand, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let args: Vec<&str> = rest.split_whitespace().collect();
+
+    match name {
+        "who" => Ok(ConsoleCommand::Who),
+        "kick" => {
+            let Some((character_name, reason)) = args.split_first() else {
+                return Err("kick usage: kick <character_name> [reason]".to_string());
+            };
+            let reason = if reason.is_empty() { DEFAULT_KICK_REASON.to_string() } else { reason.join(" ") };
+            Ok(ConsoleCommand::Kick { character_name: character_name.to_string(), reason })
+        }
+        "broadcast" => {
+            if rest.is_empty() {
+                return Err("broadcast usage: broadcast <message>".to_string());
+            }
+            Ok(ConsoleCommand::Broadcast { message: rest.to_string() })
+        }
+        "save-all" => Ok(ConsoleCommand::SaveAll),
+        "reload-data" => Ok(ConsoleCommand::ReloadData),
+        "spawn" => {
+            let usage = || "spawn usage: spawn <enemy_id> <x> <y> [level]".to_string();
+            if !(3..=4).contains(&args.len()) {
+                return Err(usage());
+            }
+            let enemy_id = args[0].parse().map_err(|_| usage())?;
+            let x: f32 = args[1].parse().map_err(|_| usage())?;
+            let y: f32 = args[2].parse().map_err(|_| usage())?;
+            let level = match args.get(3) {
+                Some(level) => level.parse().map_err(|_| usage())?,
+                None => 1,
+            };
+            Ok(ConsoleCommand::Spawn { enemy_id, position: Vec2::new(x, y), level })
+        }
+        "set-loglevel" => {
+            if rest.is_empty() {
+                return Err("set-loglevel usage: set-loglevel <filter>, e.g. debug or info,eryndor_server::combat=trace".to_string());
+            }
+            Ok(ConsoleCommand::SetLogLevel { filter: rest.to_string() })
+        }
+        "help" => Ok(ConsoleCommand::Help),
+        "" => Err("Empty command".to_string()),
+        _ => Err(format!("Unknown command: {}. Type help for available commands.", name)),
+    }
+}
+
+pub fn help_text() -> String {
+    r#"=== SERVER CONSOLE ===
+who                               - List online characters
+kick <character> [reason]         - Disconnect a player
+broadcast <message>               - Message every player on every shard
+save-all                          - Save every online character now
+reload-data                       - Re-read game content from disk, if it all validates
+spawn <enemy_id> <x> <y> [level]  - Spawn an enemy (it doesn't respawn)
+set-loglevel <filter>             - Change log verbosity, e.g. debug or info,eryndor_server::combat=trace
+help                              - Show this help"#.to_string()
+}
+
+/// A console line waiting for the game loop. Lines typed at the terminal print their
+/// result; lines from the admin API send it back to the HTTP handler.
+struct ConsoleRequest {
+    line: String,
+    reply: Option<oneshot::Sender<Result<String, String>>>,
+}
+
+/// Console commands queued from the terminal and the admin API
+#[derive(Resource, Clone, Default)]
+pub struct ServerConsole {
+    queue: Arc<Mutex<Vec<ConsoleRequest>>>,
+}
+
+impl ServerConsole {
+    /// Queue a command and get its output once the game loop has run it
+    pub fn submit(&self, line: String) -> oneshot::Receiver<Result<String, String>> {
+        let (reply, output) = oneshot::channel();
+        self.push(ConsoleRequest { line, reply: Some(reply) });
+        output
+    }
+
+    fn push(&self, request: ConsoleRequest) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.push(request);
+        }
+    }
+}
+
+/// Read console commands from the terminal on a background thread. Ends quietly when stdin
+/// closes, e.g. when the server runs detached.
+pub fn read_stdin(console: Res<ServerConsole>) {
+    let console = console.clone();
+    let spawned = std::thread::Builder::new()
+        .name("console".to_string())
+        .spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if !line.trim().is_empty() {
+                    console.push(ConsoleRequest { line, reply: None });
+                }
+            }
+        });
+    match spawned {
+        Ok(_) => info!("Server console ready - type help for commands"),
+        Err(e) => error!("Failed to start the server console: {}", e),
+    }
+}
+
+/// Run queued console commands
+pub fn run_console_commands(world: &mut World) {
+    let pending = match world.resource::<ServerConsole>().queue.lock() {
+        Ok(mut queue) => std::mem::take(&mut *queue),
+        Err(_) => return,
+    };
+
+    for request in pending {
+        info!("Console: {}", request.line);
+        let result = parse_console_command(&request.line).and_then(|command| execute(world, command));
+        match request.reply {
+            Some(reply) => {
+                let _ = reply.send(result);
+            }
+            None => match result {
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("Error: {}", e),
+            },
+        }
+    }
+}
+
+fn execute(world: &mut World, command: ConsoleCommand) -> Result<String, String> {
+    match command {
+        ConsoleCommand::Who => finish(world.run_system_cached(who)),
+        ConsoleCommand::Kick { character_name, reason } => finish(world.run_system_cached_with(kick, (character_name, reason))),
+        ConsoleCommand::Broadcast { message } => finish(world.run_system_cached_with(broadcast, message)),
+        ConsoleCommand::SaveAll => finish(world.run_system_cached(save_all)),
+        ConsoleCommand::ReloadData => crate::data_reload::reload_game_data(world),
+        ConsoleCommand::Spawn { enemy_id, position, level } => finish(world.run_system_cached_with(spawn_enemy, (enemy_id, position, level))),
+        ConsoleCommand::SetLogLevel { filter } => set_log_level(&filter),
+        ConsoleCommand::Help => Ok(help_text()),
+    }
+}
+
+/// A command's output, or why its system couldn't run
+fn finish(result: Result<Result<String, String>, impl std::fmt::Display>) -> Result<String, String> {
+    result.unwrap_or_else(|e| Err(format!("Console command failed: {}", e)))
+}
+
+fn who(
+    players: Query<(&Character, &Position, &OwnedBy, Option<&CurrentZone>), With<Player>>,
+    owners: Query<&Authenticated>,
+) -> Result<String, String> {
+    let mut lines: Vec<String> = players.iter()
+        .map(|(character, position, owned_by, zone)| {
+            let account = owners.get(owned_by.0)
+                .map(|auth| auth.account_id.to_string())
+                .unwrap_or_else(|_| "?".to_string());
+            format!(
+                "  {} - level {} {:?} in {} at ({:.0}, {:.0}), account {}",
+                character.name,
+                character.level,
+                character.class,
+                zone.map(|zone| zone.0.as_str()).unwrap_or(STARTER_ZONE),
+                position.0.x,
+                position.0.y,
+                account,
+            )
+        })
+        .collect();
+    lines.sort();
+
+    Ok(format!("{} online\n{}", lines.len(), lines.join("\n")).trim_end().to_string())
+}
+
+fn kick(
+    In((character_name, reason)): In<(String, String)>,
+    mut commands: Commands,
+    characters: Query<(&Character, &OwnedBy), With<Player>>,
+) -> Result<String, String> {
+    let (character, owned_by) = characters.iter()
+        .find(|(character, _)| character.name.eq_ignore_ascii_case(&character_name))
+        .ok_or_else(|| format!("{} is not online", character_name))?;
+
+    // Disconnecting the client saves the character as usual
+    crate::bans::disconnect_with_notice(
+        &mut commands,
+        owned_by.0,
+        format!("You have been kicked from the server. Reason: {}", reason),
+    );
+    info!("Kicked {} from the console: {}", character.name, reason);
+    Ok(format!("Kicked {}", character.name))
+}
+
+fn broadcast(In(message): In<String>, mut commands: Commands, relay: Res<Relay>) -> Result<String, String> {
+    crate::admin_api::send_broadcast(&mut commands, &relay, message);
+    Ok("Broadcast sent".to_string())
+}
+
+fn save_all(characters: Query<CharacterSaveData>, db: Res<DatabaseConnection>) -> Result<String, String> {
+    let total = characters.iter().count();
+    let queued = characters.iter()
+        .filter(|data| queue_save(&db, data, "saved from the console"))
+        .count();
+    if queued < total {
+        return Err(format!("Database not available - {} of {} characters could not be saved", total - queued, total));
+    }
+    Ok(format!("Saving {} online characters", queued))
+}
+
+fn spawn_enemy(
+    In((enemy_id, position, level)): In<(u32, Vec2, u32)>,
+    mut commands: Commands,
+    enemy_db: Res<EnemyDatabase>,
+) -> Result<String, String> {
+    let definition = enemy_db.enemies.get(&enemy_id)
+        .ok_or_else(|| format!("No enemy with id {}", enemy_id))?;
+    let template = EnemyTemplate::from_definition(definition, level.max(1));
+    let entity = EntityTemplate::Enemy(template).spawn(&mut commands, position);
+    Ok(format!("Spawned {} (level {}) at ({:.0}, {:.0}) as {:?}", definition.name, level.max(1), position.x, position.y, entity))
+}
+
+fn set_log_level(filter: &str) -> Result<String, String> {
+    let handle = LOG_FILTER.get().ok_or("This server wasn't started with a reloadable log filter")?;
+    let new_filter = EnvFilter::try_new(filter).map_err(|e| format!("Invalid log filter '{}': {}", filter, e))?;
+    handle.reload(new_filter).map_err(|e| format!("Failed to change the log filter: {}", e))?;
+    Ok(format!("Log filter set to '{}'", filter))
+}
+
+/// `LogPlugin::custom_layer` for the server binary: the Chrome trace recorder behind a log
+/// filter that `set-loglevel` can swap at runtime. Starts from `RUST_LOG`, or `info`.
+///
+/// The filter here decides what's logged, so `LogPlugin` itself has to let everything
+/// through (`level: TRACE`); with `RUST_LOG` set, that still caps what `set-loglevel` can
+/// turn on.
+pub fn log_layers(app: &mut App) -> Option<BoxedLayer> {
+    let initial = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(initial);
+    let _ = LOG_FILTER.set(handle);
+
+    let layer = match crate::profiling::chrome_trace_layer(app) {
+        Some(trace) => filter.and_then(trace).boxed(),
+        None => filter.boxed(),
+    };
+    Some(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_with_arguments() {
+        assert_eq!(parse_console_command("who"), Ok(ConsoleCommand::Who));
+        assert_eq!(
+            parse_console_command("kick Alice spamming trade chat"),
+            Ok(ConsoleCommand::Kick { character_name: "Alice".to_string(), reason: "spamming trade chat".to_string() }),
+        );
+        assert_eq!(
+            parse_console_command("  broadcast  Restart in 5 minutes "),
+            Ok(ConsoleCommand::Broadcast { message: "Restart in 5 minutes".to_string() }),
+        );
+        assert_eq!(
+            parse_console_command("spawn 3 400 -250.5"),
+            Ok(ConsoleCommand::Spawn { enemy_id: 3, position: Vec2::new(400.0, -250.5), level: 1 }),
+        );
+        assert_eq!(
+            parse_console_command("set-loglevel info,eryndor_server::combat=debug"),
+            Ok(ConsoleCommand::SetLogLevel { filter: "info,eryndor_server::combat=debug".to_string() }),
+        );
+    }
+
+    #[test]
+    fn kick_has_a_default_reason() {
+        match parse_console_command("kick Bob") {
+            Ok(ConsoleCommand::Kick { reason, .. }) => assert_eq!(reason, DEFAULT_KICK_REASON),
+            other => panic!("Expected Kick, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_console_command("kick").is_err());
+        assert!(parse_console_command("broadcast").is_err());
+        assert!(parse_console_command("spawn goblin 1 2").is_err());
+        assert!(parse_console_command("spawn 3 1").is_err());
+        assert!(parse_console_command("set-loglevel").is_err());
+        assert!(parse_console_command("teleport Alice").is_err());
+    }
+}
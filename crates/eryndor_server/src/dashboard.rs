@@ -9,13 +9,17 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::database::DatabaseConnection;
 use crate::admin::is_admin;
-use crate::auth::Authenticated;
-use sqlx::{SqlitePool, Row};
+use crate::auth::{Authenticated, ActiveCharacterEntity};
+use crate::replication::{ReplicationStats, collect_client_network_stats};
+use crate::shards::ShardRegistry;
+use bevy_replicon::shared::backend::connected_client::NetworkId;
+use bevy_renet2::prelude::RenetServer;
+use sqlx::Row;
+use crate::database::DbPool;
 
 /// Handle request for online player list
 pub fn handle_get_player_list(
     trigger: On<FromClient<GetPlayerListRequest>>,
-    mut commands: Commands,
     client_query: Query<&Authenticated>,
     characters: Query<(&Character, &Position, &OwnedBy), With<Player>>,
     owners: Query<&Authenticated>,
@@ -32,31 +36,19 @@ pub fn handle_get_player_list(
         return;
     };
 
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         error!("Database not available");
         return;
-    };
+    }
 
     // Collect player list - accessible to all authenticated players
-    let runtime = tokio::runtime::Runtime::new().unwrap();
     let mut players = Vec::new();
 
     for (character, position, owned_by) in characters.iter() {
         // Get the account_id from the owner (client connection entity)
         if let Ok(owner_auth) = owners.get(owned_by.0) {
-            // Query username from database
-            let username = runtime.block_on(async {
-                sqlx::query("SELECT username FROM accounts WHERE id = ?")
-                    .bind(owner_auth.account_id)
-                    .fetch_one(pool)
-                    .await
-                    .ok()
-                    .and_then(|row| row.try_get::<String, _>("username").ok())
-                    .unwrap_or_else(|| format!("user_{}", owner_auth.account_id))
-            });
-
             players.push(OnlinePlayerInfo {
-                username,
+                username: String::new(),
                 character_name: character.name.clone(),
                 account_id: owner_auth.account_id,
                 level: character.level,
@@ -69,16 +61,26 @@ pub fn handle_get_player_list(
 
     info!("Account {} requested player list, returning {} players", auth.account_id, players.len());
 
-    commands.server_trigger(ToClients {
-        mode: SendMode::Direct(trigger.client_id),
-        message: PlayerListResponse { players },
-    });
+    // Fill in usernames from the database
+    db.run(
+        move |pool| async move {
+            for player in &mut players {
+                player.username = fetch_username(&pool, player.account_id).await;
+            }
+            players
+        },
+        move |players, world| {
+            world.commands().server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: PlayerListResponse { players },
+            });
+        },
+    );
 }
 
 /// Handle request for ban list
 pub fn handle_get_ban_list(
     trigger: On<FromClient<GetBanListRequest>>,
-    mut commands: Commands,
     client_query: Query<&Authenticated>,
     db: Res<DatabaseConnection>,
 ) {
@@ -92,48 +94,49 @@ pub fn handle_get_ban_list(
         return;
     };
 
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         error!("Database not available");
         return;
-    };
+    }
 
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let is_admin_result = runtime.block_on(is_admin(pool, auth.account_id));
-
-    match is_admin_result {
-        Ok(true) => {
-            // Fetch bans from database
-            let bans_result = runtime.block_on(fetch_ban_list(pool));
-
-            match bans_result {
-                Ok(bans) => {
-                    info!("Admin {} requested ban list, returning {} bans", auth.account_id, bans.len());
-
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: BanListResponse { bans },
-                    });
-                }
-                Err(e) => {
-                    error!("Failed to fetch ban list: {}", e);
-                }
+    let account_id = auth.account_id;
+    db.run(
+        move |pool| async move {
+            match is_admin(&pool, account_id).await {
+                // Fetch bans from database
+                Ok(true) => fetch_ban_list(&pool).await.map(Some),
+                Ok(false) => Ok(None),
+                Err(e) => Err(e),
             }
-        }
-        Ok(false) => {
-            warn!("Non-admin account {} attempted to get ban list", auth.account_id);
-        }
-        Err(e) => {
-            error!("Database error checking admin status: {}", e);
-        }
-    }
+        },
+        move |bans_result, world| match bans_result {
+            Ok(Some(bans)) => {
+                info!("Admin {} requested ban list, returning {} bans", account_id, bans.len());
+
+                world.commands().server_trigger(ToClients {
+                    mode: SendMode::Direct(ClientId::Client(client_entity)),
+                    message: BanListResponse { bans },
+                });
+            }
+            Ok(None) => {
+                warn!("Non-admin account {} attempted to get ban list", account_id);
+            }
+            Err(e) => {
+                error!("Failed to fetch ban list: {}", e);
+            }
+        },
+    );
 }
 
 /// Handle request for server statistics (accessible to all authenticated players)
 pub fn handle_get_server_stats(
     trigger: On<FromClient<GetServerStatsRequest>>,
-    mut commands: Commands,
     client_query: Query<&Authenticated>,
     characters: Query<&Character>,
+    network_clients: Query<(&NetworkId, Option<&ActiveCharacterEntity>), With<ConnectedClient>>,
+    renet_server: Option<Res<RenetServer>>,
+    replication: Res<ReplicationStats>,
+    shards: Res<ShardRegistry>,
     db: Res<DatabaseConnection>,
 ) {
     let Some(client_entity) = trigger.client_id.entity() else {
@@ -146,37 +149,36 @@ pub fn handle_get_server_stats(
         return;
     };
 
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         error!("Database not available");
         return;
-    };
+    }
 
-    // Count online players
-    let online_players = characters.iter().count() as u32;
+    let live = LiveStats::collect(&characters, &network_clients, renet_server.as_deref(), &replication, &shards);
 
     // Fetch database stats
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let stats_result = runtime.block_on(fetch_server_stats(pool, online_players));
-
-    match stats_result {
-        Ok(stats) => {
-            info!("Account {} requested server stats", auth.account_id);
-
-            commands.server_trigger(ToClients {
-                mode: SendMode::Direct(trigger.client_id),
-                message: stats,
-            });
-        }
-        Err(e) => {
-            error!("Failed to fetch server stats: {}", e);
-        }
-    }
+    let account_id = auth.account_id;
+    db.run(
+        move |pool| async move { fetch_server_stats(&pool, live).await },
+        move |stats_result, world| match stats_result {
+            Ok(stats) => {
+                info!("Account {} requested server stats", account_id);
+
+                world.commands().server_trigger(ToClients {
+                    mode: SendMode::Direct(ClientId::Client(client_entity)),
+                    message: stats,
+                });
+            }
+            Err(e) => {
+                error!("Failed to fetch server stats: {}", e);
+            }
+        },
+    );
 }
 
 /// Handle request for audit logs
 pub fn handle_get_audit_logs(
     trigger: On<FromClient<GetAuditLogsRequest>>,
-    mut commands: Commands,
     client_query: Query<&Authenticated>,
     db: Res<DatabaseConnection>,
 ) {
@@ -190,39 +192,75 @@ pub fn handle_get_audit_logs(
         return;
     };
 
-    let Some(pool) = db.pool() else {
+    if db.pool().is_none() {
         error!("Database not available");
         return;
-    };
+    }
 
-    let runtime = tokio::runtime::Runtime::new().unwrap();
-    let is_admin_result = runtime.block_on(is_admin(pool, auth.account_id));
-
-    match is_admin_result {
-        Ok(true) => {
-            let request = trigger.event();
-            let logs_result = runtime.block_on(fetch_audit_logs(pool, request.limit, request.offset));
-
-            match logs_result {
-                Ok(response) => {
-                    info!("Admin {} requested audit logs (limit: {}, offset: {})",
-                          auth.account_id, request.limit, request.offset);
-
-                    commands.server_trigger(ToClients {
-                        mode: SendMode::Direct(trigger.client_id),
-                        message: response,
-                    });
-                }
-                Err(e) => {
-                    error!("Failed to fetch audit logs: {}", e);
-                }
+    let account_id = auth.account_id;
+    let (limit, offset) = (trigger.event().limit, trigger.event().offset);
+    db.run(
+        move |pool| async move {
+            match is_admin(&pool, account_id).await {
+                Ok(true) => fetch_audit_logs(&pool, limit, offset).await.map(Some),
+                Ok(false) => Ok(None),
+                Err(e) => Err(e),
             }
-        }
-        Ok(false) => {
-            warn!("Non-admin account {} attempted to get audit logs", auth.account_id);
-        }
-        Err(e) => {
-            error!("Database error checking admin status: {}", e);
+        },
+        move |logs_result, world| match logs_result {
+            Ok(Some(response)) => {
+                info!("Admin {} requested audit logs (limit: {}, offset: {})", account_id, limit, offset);
+
+                world.commands().server_trigger(ToClients {
+                    mode: SendMode::Direct(ClientId::Client(client_entity)),
+                    message: response,
+                });
+            }
+            Ok(None) => {
+                warn!("Non-admin account {} attempted to get audit logs", account_id);
+            }
+            Err(e) => {
+                error!("Failed to fetch audit logs: {}", e);
+            }
+        },
+    );
+}
+
+// ============================================================================
+// LIVE STATISTICS
+// ============================================================================
+
+/// Server statistics that come from the running world rather than the database
+#[derive(Clone, Default)]
+pub struct LiveStats {
+    pub online_players: u32,
+    pub tick_rate: u32,
+    pub replicated_entities: u32,
+    pub entity_changes_per_tick: f32,
+    pub clients: Vec<ClientNetworkStats>,
+    pub shards: Vec<ShardInfo>,
+}
+
+impl LiveStats {
+    pub fn collect(
+        characters: &Query<&Character>,
+        network_clients: &Query<(&NetworkId, Option<&ActiveCharacterEntity>), With<ConnectedClient>>,
+        renet_server: Option<&RenetServer>,
+        replication: &ReplicationStats,
+        shards: &ShardRegistry,
+    ) -> Self {
+        let online_players = characters.iter().count() as u32;
+        Self {
+            online_players,
+            tick_rate: replication.tick_rate,
+            replicated_entities: replication.replicated_entities,
+            entity_changes_per_tick: replication.entity_changes_per_tick(),
+            // Per-client bandwidth from the transport
+            clients: renet_server
+                .map(|server| collect_client_network_stats(server, network_clients, characters, replication))
+                .unwrap_or_default(),
+            // Every shard's population as of its last heartbeat, this one's as of now
+            shards: shards.with_population(online_players),
         }
     }
 }
@@ -231,13 +269,27 @@ pub fn handle_get_audit_logs(
 // DATABASE QUERY FUNCTIONS
 // ============================================================================
 
-/// Fetch list of active bans from database
-async fn fetch_ban_list(pool: &SqlitePool) -> Result<Vec<BanInfo>, String> {
+/// Look up an account's username, falling back to "user_<id>"
+pub async fn fetch_username(pool: &DbPool, account_id: i64) -> String {
+    sqlx::query("SELECT username FROM accounts WHERE id = $1")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .ok()
+        .and_then(|row| row.try_get::<String, _>("username").ok())
+        .unwrap_or_else(|| format!("user_{}", account_id))
+}
+
+/// Fetch list of active bans from database, each with its latest appeal
+pub async fn fetch_ban_list(pool: &DbPool) -> Result<Vec<BanInfo>, String> {
     let rows = sqlx::query(
-        "SELECT id, ban_type, target, reason, banned_by, banned_at, expires_at, is_active
-         FROM bans
-         WHERE is_active = 1
-         ORDER BY banned_at DESC
+        "SELECT b.id, b.ban_type, b.target, b.reason, b.banned_by, b.banned_at, b.expires_at, b.is_active,
+                b.account_id, b.ip_address, b.hardware_id, b.notes,
+                a.id AS appeal_id, a.appeal_text, a.submitted_at, a.status, a.review_notes
+         FROM bans b
+         LEFT JOIN ban_appeals a ON a.id = (SELECT MAX(id) FROM ban_appeals WHERE ban_id = b.id)
+         WHERE b.is_active = TRUE
+         ORDER BY b.banned_at DESC
          LIMIT 100"
     )
     .fetch_all(pool)
@@ -246,6 +298,14 @@ async fn fetch_ban_list(pool: &SqlitePool) -> Result<Vec<BanInfo>, String> {
 
     let mut bans = Vec::new();
     for row in rows {
+        let appeal = row.try_get::<i64, _>("appeal_id").ok().map(|id| BanAppeal {
+            id,
+            text: row.try_get("appeal_text").unwrap_or_default(),
+            submitted_at: row.try_get("submitted_at").unwrap_or(0),
+            status: row.try_get("status").unwrap_or_else(|_| "pending".to_string()),
+            review_notes: row.try_get("review_notes").ok(),
+        });
+
         bans.push(BanInfo {
             id: row.try_get("id").unwrap_or(0),
             ban_type: row.try_get("ban_type").unwrap_or_else(|_| "account".to_string()),
@@ -255,6 +315,11 @@ async fn fetch_ban_list(pool: &SqlitePool) -> Result<Vec<BanInfo>, String> {
             banned_at: row.try_get("banned_at").unwrap_or(0),
             expires_at: row.try_get("expires_at").ok(),
             is_active: row.try_get("is_active").unwrap_or(false),
+            account_id: row.try_get("account_id").ok(),
+            ip_address: row.try_get("ip_address").ok(),
+            hardware_id: row.try_get("hardware_id").ok(),
+            notes: row.try_get("notes").ok(),
+            appeal,
         });
     }
 
@@ -262,7 +327,7 @@ async fn fetch_ban_list(pool: &SqlitePool) -> Result<Vec<BanInfo>, String> {
 }
 
 /// Fetch server statistics from database
-async fn fetch_server_stats(pool: &SqlitePool, online_players: u32) -> Result<ServerStatsResponse, String> {
+pub async fn fetch_server_stats(pool: &DbPool, live: LiveStats) -> Result<ServerStatsResponse, String> {
     // Count total accounts
     let row = sqlx::query("SELECT COUNT(*) FROM accounts")
         .fetch_one(pool)
@@ -278,7 +343,7 @@ async fn fetch_server_stats(pool: &SqlitePool, online_players: u32) -> Result<Se
     let total_characters = row.get::<i64, _>(0) as u32;
 
     // Count active bans
-    let row = sqlx::query("SELECT COUNT(*) FROM bans WHERE is_active = 1")
+    let row = sqlx::query("SELECT COUNT(*) FROM bans WHERE is_active = TRUE")
         .fetch_one(pool)
         .await
         .map_err(|e| format!("Failed to count bans: {}", e))?;
@@ -291,16 +356,21 @@ async fn fetch_server_stats(pool: &SqlitePool, online_players: u32) -> Result<Se
         .as_secs() as i64;
 
     Ok(ServerStatsResponse {
-        online_players,
+        online_players: live.online_players,
         total_accounts,
         total_characters,
         active_bans,
         server_time_utc,
+        tick_rate: live.tick_rate,
+        replicated_entities: live.replicated_entities,
+        entity_changes_per_tick: live.entity_changes_per_tick,
+        clients: live.clients,
+        shards: live.shards,
     })
 }
 
 /// Fetch audit logs with pagination
-async fn fetch_audit_logs(pool: &SqlitePool, limit: u32, offset: u32) -> Result<AuditLogsResponse, String> {
+pub async fn fetch_audit_logs(pool: &DbPool, limit: u32, offset: u32) -> Result<AuditLogsResponse, String> {
     // Get total count
     let row = sqlx::query("SELECT COUNT(*) FROM audit_logs")
         .fetch_one(pool)
@@ -313,7 +383,7 @@ async fn fetch_audit_logs(pool: &SqlitePool, limit: u32, offset: u32) -> Result<
         "SELECT id, action_type, actor_account_id, target_account_id, ip_address, details, timestamp
          FROM audit_logs
          ORDER BY timestamp DESC
-         LIMIT ?1 OFFSET ?2"
+         LIMIT $1 OFFSET $2"
     )
     .bind(limit as i64)
     .bind(offset as i64)
@@ -0,0 +1,209 @@
+//! `reload-data`: swap item, quest, ability, enemy and trainer data on a running server.
+//!
+//! The file watcher applies each content file as soon as it changes, one at a time and
+//! unchecked, which is what you want while editing. Balance changes on a live server go through
+//! the console instead: everything is read from `assets/content` into fresh databases, run
+//! through `validation`, and only if all of it is clean are the five databases replaced together
+//! in one exclusive system. A bad file leaves the running data exactly as it was.
+//!
+//! Trainer stock comes from the `Trainer` NPCs in the zone files, on top of the built-in ones.
+//! Zones, loot tables, shops and combat formulas are read too so they can be checked, but the
+//! asset server still loads the live copies.
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::abilities::AbilityDatabase;
+use crate::assets::{apply_enemy_definition, SpawnedEnemyStats};
+use crate::combat_formulas::CombatFormulas;
+use crate::game_data::*;
+use crate::validation::{self, ValidationIssue};
+use eryndor_shared::AbilityDefinition;
+
+/// Where content is read from, relative to the server's working directory
+pub const CONTENT_DIR: &str = "assets/content";
+
+/// Issues listed in a console reply before the rest are summarized
+const MAX_REPORTED_ISSUES: usize = 20;
+
+/// Everything `reload-data` swaps in or checks against, plus the file each entry came from
+#[derive(Default)]
+pub struct GameData {
+    pub items: HashMap<u32, ItemDefinition>,
+    pub quests: HashMap<u32, QuestDefinition>,
+    pub abilities: HashMap<u32, AbilityDefinition>,
+    pub enemies: HashMap<u32, EnemyDefinition>,
+    pub trainers: HashMap<String, TrainerDefinition>,
+    pub zones: HashMap<String, ZoneDefinition>,
+    pub loot_tables: HashMap<String, LootTableDefinition>,
+    pub shops: HashMap<String, ShopDefinition>,
+    pub formulas: HashMap<String, CombatFormulas>,
+    /// Source file per entry, keyed like "item 3", "npc 12" or "trainer Weapon Master"
+    pub files: HashMap<String, PathBuf>,
+}
+
+impl GameData {
+    /// Read every definition under `dir`. Returns the data along with anything that failed to
+    /// parse or reused an id; cross-references are left to `validation::validate`.
+    pub fn load(dir: &Path) -> (Self, Vec<ValidationIssue>) {
+        let mut data = GameData {
+            quests: QuestDatabase::default().quests,
+            trainers: TrainerDatabase::new().trainers,
+            ..Default::default()
+        };
+        let mut issues = Vec::new();
+
+        for (path, item) in read_definitions::<ItemDefinition>(&dir.join("items"), &mut issues) {
+            data.track(format!("item {}", item.id), path, &mut issues);
+            data.items.insert(item.id, item);
+        }
+        for (path, quest) in read_definitions::<QuestDefinition>(&dir.join("quests"), &mut issues) {
+            data.track(format!("quest {}", quest.id), path, &mut issues);
+            data.quests.insert(quest.id, quest);
+        }
+        for (path, ability) in read_definitions::<AbilityDefinition>(&dir.join("abilities"), &mut issues) {
+            data.track(format!("ability {}", ability.id), path, &mut issues);
+            data.abilities.insert(ability.id, ability);
+        }
+        for (path, enemy) in read_definitions::<EnemyDefinition>(&dir.join("enemies"), &mut issues) {
+            data.track(format!("enemy {}", enemy.id), path, &mut issues);
+            data.enemies.insert(enemy.id, enemy);
+        }
+        for (path, table) in read_definitions::<LootTableDefinition>(&dir.join("loot"), &mut issues) {
+            data.track(format!("loot table {}", table.id), path, &mut issues);
+            data.loot_tables.insert(table.id.clone(), table);
+        }
+        for (path, shop) in read_definitions::<ShopDefinition>(&dir.join("shops"), &mut issues) {
+            data.track(format!("shop {}", shop.id), path, &mut issues);
+            data.shops.insert(shop.id.clone(), shop);
+        }
+        for (path, formulas) in read_definitions::<CombatFormulas>(&dir.join("formulas"), &mut issues) {
+            data.track(format!("formulas {}", formulas.id), path, &mut issues);
+            data.formulas.insert(formulas.id.clone(), formulas);
+        }
+        for (path, zone) in read_definitions::<ZoneDefinition>(&dir.join("zones"), &mut issues) {
+            data.track(format!("zone {}", zone.zone_id), path.clone(), &mut issues);
+            for npc in &zone.npc_spawns {
+                data.track(format!("npc {}", npc.npc_id), path.clone(), &mut issues);
+                if npc.npc_type == "Trainer" {
+                    data.track(format!("trainer {}", npc.name), path.clone(), &mut issues);
+                    data.trainers.insert(npc.name.clone(), TrainerDefinition {
+                        name: npc.name.clone(),
+                        items: npc.trainer_items.clone(),
+                    });
+                }
+            }
+            data.zones.insert(zone.zone_id.clone(), zone);
+        }
+
+        // An empty directory would otherwise leave every player without abilities
+        if data.abilities.is_empty() {
+            issues.push(ValidationIssue::new(dir.join("abilities").display(), None, "no abilities found"));
+        }
+
+        (data, issues)
+    }
+
+    /// Remember where an entry came from, reporting it if another file already defined it
+    fn track(&mut self, key: String, path: PathBuf, issues: &mut Vec<ValidationIssue>) {
+        if let Some(previous) = self.files.get(&key) {
+            let message = format!("already defined in {}", previous.display());
+            issues.push(ValidationIssue::new(path.display(), Some(&key), message));
+        }
+        self.files.insert(key, path);
+    }
+
+    /// File an entry was read from, or "built-in" for ones defined in code
+    pub fn source(&self, key: &str) -> String {
+        match self.files.get(key) {
+            Some(path) => path.display().to_string(),
+            None => "built-in".to_string(),
+        }
+    }
+
+    /// Replace the live databases and bring spawned enemies in line with their new definitions
+    fn apply(self, world: &mut World) -> Result<usize, String> {
+        let enemies: Vec<EnemyDefinition> = self.enemies.values().cloned().collect();
+
+        world.insert_resource(ItemDatabase { items: self.items });
+        world.insert_resource(QuestDatabase { quests: self.quests });
+        world.insert_resource(AbilityDatabase { abilities: self.abilities });
+        world.insert_resource(EnemyDatabase { enemies: self.enemies });
+        world.insert_resource(TrainerDatabase { trainers: self.trainers });
+
+        world.run_system_cached_with(refresh_spawned_enemies, enemies)
+            .map_err(|e| format!("Failed to update spawned enemies: {}", e))
+    }
+}
+
+/// Parse every `.json` file in `dir`, in file name order
+fn read_definitions<T: DeserializeOwned>(dir: &Path, issues: &mut Vec<ValidationIssue>) -> Vec<(PathBuf, T)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            issues.push(ValidationIssue::new(dir.display(), None, e.to_string()));
+            return Vec::new();
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+
+    paths.into_iter()
+        .filter_map(|path| {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str::<T>(&content).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(definition) => Some((path, definition)),
+                Err(e) => {
+                    issues.push(ValidationIssue::new(path.display(), None, e));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn refresh_spawned_enemies(In(enemies): In<Vec<EnemyDefinition>>, mut spawned: SpawnedEnemyStats) -> usize {
+    enemies.iter().map(|def| apply_enemy_definition(def, &mut spawned)).sum()
+}
+
+/// Read, check and swap in the game data. Nothing changes unless every file is valid.
+pub fn reload_game_data(world: &mut World) -> Result<String, String> {
+    let (data, mut issues) = GameData::load(Path::new(CONTENT_DIR));
+    issues.extend(validation::validate(&data));
+    if !issues.is_empty() {
+        warn!("reload-data rejected: {} problems in {}", issues.len(), CONTENT_DIR);
+        let mut report = format!("Game data unchanged, {} problems found:", issues.len());
+        for issue in issues.iter().take(MAX_REPORTED_ISSUES) {
+            report.push_str(&format!("\n  {}", issue));
+        }
+        if issues.len() > MAX_REPORTED_ISSUES {
+            report.push_str(&format!("\n  ... and {} more", issues.len() - MAX_REPORTED_ISSUES));
+        }
+        return Err(report);
+    }
+
+    let counts = format!(
+        "{} items, {} quests, {} abilities, {} enemies, {} trainers",
+        data.items.len(), data.quests.len(), data.abilities.len(), data.enemies.len(), data.trainers.len(),
+    );
+    let refreshed = data.apply(world)?;
+    info!("reload-data swapped in {}", counts);
+
+    // Zones, dialogues, loot tables, shops, formulas and behaviors still load one file at a time
+    let reloading = world.run_system_cached(crate::assets::reload_content_assets)
+        .map_err(|e| format!("Failed to reload other content: {}", e))?;
+
+    Ok(format!(
+        "Loaded {}; updated {} spawned enemies. Reloading {} other content files",
+        counts, refreshed, reloading,
+    ))
+}
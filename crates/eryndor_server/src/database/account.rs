@@ -1,12 +1,14 @@
 //! Account-related database operations.
 //!
-//! Handles account creation, credential verification, and existence checks.
+//! Handles account creation, credential verification, existence checks, and the
+//! last-login record used for IP and hardware bans.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 
 /// Check if an email already exists in the database
-pub async fn email_exists(pool: &SqlitePool, email: &str) -> Result<bool, String> {
-    let result = sqlx::query("SELECT 1 FROM accounts WHERE email = ?1")
+pub async fn email_exists(pool: &DbPool, email: &str) -> Result<bool, String> {
+    let result = sqlx::query("SELECT 1 FROM accounts WHERE email = $1")
         .bind(email)
         .fetch_optional(pool)
         .await;
@@ -19,8 +21,8 @@ pub async fn email_exists(pool: &SqlitePool, email: &str) -> Result<bool, String
 }
 
 /// Check if a username already exists in the database
-pub async fn username_exists(pool: &SqlitePool, username: &str) -> Result<bool, String> {
-    let result = sqlx::query("SELECT 1 FROM accounts WHERE username = ?1")
+pub async fn username_exists(pool: &DbPool, username: &str) -> Result<bool, String> {
+    let result = sqlx::query("SELECT 1 FROM accounts WHERE username = $1")
         .bind(username)
         .fetch_optional(pool)
         .await;
@@ -34,7 +36,7 @@ pub async fn username_exists(pool: &SqlitePool, username: &str) -> Result<bool,
 
 /// Create a new registered account
 pub async fn create_account(
-    pool: &SqlitePool,
+    pool: &DbPool,
     email: &str,
     username: &str,
     password_hash: &str,
@@ -60,24 +62,25 @@ pub async fn create_account(
 
     let result = sqlx::query(
         "INSERT INTO accounts (email, username, password_hash, created_at, account_type)
-         VALUES (?1, ?2, ?3, ?4, 'registered')"
+         VALUES ($1, $2, $3, $4, 'registered')
+         RETURNING id"
     )
     .bind(email)
     .bind(username)
     .bind(password_hash)
     .bind(now)
-    .execute(pool)
+    .fetch_one(pool)
     .await;
 
     match result {
-        Ok(result) => Ok(result.last_insert_rowid()),
+        Ok(row) => Ok(row.get(0)),
         Err(e) => Err(format!("Failed to create account: {}", e)),
     }
 }
 
 /// Verify login credentials and return account ID if valid
 pub async fn verify_credentials(
-    pool: &SqlitePool,
+    pool: &DbPool,
     username: &str,
     password: &str,
 ) -> Result<i64, String> {
@@ -86,7 +89,7 @@ pub async fn verify_credentials(
         Argon2,
     };
 
-    let result = sqlx::query("SELECT id, password_hash FROM accounts WHERE username = ?1")
+    let result = sqlx::query("SELECT id, password_hash FROM accounts WHERE username = $1")
         .bind(username)
         .fetch_optional(pool)
         .await;
@@ -110,3 +113,37 @@ pub async fn verify_credentials(
         Err(e) => Err(format!("Database error: {}", e)),
     }
 }
+
+/// Remember when and where an account last logged in, for IP and hardware bans
+pub async fn record_login(
+    pool: &DbPool,
+    account_id: i64,
+    ip_address: &str,
+    hardware_id: Option<&str>,
+) -> Result<(), String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    sqlx::query("UPDATE accounts SET last_login_at = $1, last_login_ip = $2, last_hardware_id = $3 WHERE id = $4")
+        .bind(now)
+        .bind(ip_address)
+        .bind(hardware_id)
+        .bind(account_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to record login: {}", e))?;
+    Ok(())
+}
+
+/// IP address and hardware id an account last logged in with
+pub async fn last_login_origin(pool: &DbPool, account_id: i64) -> Result<(Option<String>, Option<String>), String> {
+    let row = sqlx::query("SELECT last_login_ip, last_hardware_id FROM accounts WHERE id = $1")
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Account not found".to_string())?;
+    Ok((row.get(0), row.get(1)))
+}
@@ -0,0 +1,112 @@
+//! Database engines.
+//!
+//! Everything that differs between SQLite and PostgreSQL lives behind `DatabaseBackend`:
+//! which URLs an engine takes, how its pool is set up, its column types and whether it can
+//! hold a schema from before versioned migrations. Queries are shared by both and go
+//! through sqlx's `Any` driver, so they only use SQL both engines accept.
+
+use sqlx::any::AnyPoolOptions;
+
+/// A database engine the server can run on
+pub trait DatabaseBackend: Send + Sync + 'static {
+    /// Short name for logs, e.g. "sqlite"
+    fn name(&self) -> &'static str;
+
+    /// Whether `url` points at this engine
+    fn handles(&self, url: &str) -> bool;
+
+    /// Pool settings for `url`
+    fn pool_options(&self, _url: &str) -> AnyPoolOptions {
+        AnyPoolOptions::new()
+    }
+
+    /// Adapt a migration statement, written for SQLite, to this engine's column types
+    fn translate(&self, statement: &str) -> String;
+
+    /// Query returning a row when the database has tables from before versioned migrations.
+    /// None for engines that never ran the unversioned schema.
+    fn legacy_schema_probe(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// SQLite, the development default
+pub struct Sqlite;
+
+impl DatabaseBackend for Sqlite {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn handles(&self, url: &str) -> bool {
+        url.starts_with("sqlite:")
+    }
+
+    fn pool_options(&self, url: &str) -> AnyPoolOptions {
+        // Every connection would get its own empty in-memory database
+        if url.contains(":memory:") {
+            AnyPoolOptions::new().max_connections(1)
+        } else {
+            AnyPoolOptions::new()
+        }
+    }
+
+    fn translate(&self, statement: &str) -> String {
+        statement.to_string()
+    }
+
+    fn legacy_schema_probe(&self) -> Option<&'static str> {
+        Some("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'accounts'")
+    }
+}
+
+/// PostgreSQL, for production deployments
+pub struct Postgres;
+
+impl DatabaseBackend for Postgres {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn handles(&self, url: &str) -> bool {
+        url.starts_with("postgres://") || url.starts_with("postgresql://")
+    }
+
+    fn translate(&self, statement: &str) -> String {
+        statement
+            .replace("INTEGER PRIMARY KEY AUTOINCREMENT", "BIGSERIAL PRIMARY KEY")
+            .replace("INTEGER", "BIGINT")
+            .replace("REAL", "DOUBLE PRECISION")
+    }
+}
+
+const BACKENDS: [&dyn DatabaseBackend; 2] = [&Sqlite, &Postgres];
+
+/// The engine for a database URL
+pub fn backend_for(url: &str) -> Result<&'static dyn DatabaseBackend, String> {
+    BACKENDS.into_iter()
+        .find(|backend| backend.handles(url))
+        .ok_or_else(|| format!("Unsupported database URL '{}' (expected sqlite: or postgres://)", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_pick_their_engine() {
+        assert_eq!(backend_for("sqlite::memory:").unwrap().name(), "sqlite");
+        assert_eq!(backend_for("postgresql://db/eryndor").unwrap().name(), "postgres");
+        assert!(backend_for("mysql://db/eryndor").is_err());
+    }
+
+    #[test]
+    fn postgres_translation_widens_types() {
+        let sql = "CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT, n INTEGER NOT NULL, x REAL)";
+        assert_eq!(
+            Postgres.translate(sql),
+            "CREATE TABLE t (id BIGSERIAL PRIMARY KEY, n BIGINT NOT NULL, x DOUBLE PRECISION)"
+        );
+        assert_eq!(Sqlite.translate(sql), sql);
+    }
+}
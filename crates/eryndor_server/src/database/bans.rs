@@ -1,40 +1,67 @@
 //! Ban system database operations.
 //!
-//! Handles ban checking and rate limit violation logging.
+//! A ban matches on any of the account, IP address and hardware id it was recorded with,
+//! so a ban of type 'both' keeps a player out from a new account on the same machine or
+//! connection. Also handles expiring temporary bans and rate limit violation logging.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 use tracing::info;
 
 /// Information about an active ban
 #[derive(Debug, Clone)]
 pub struct BanInfo {
+    /// Ban id, quoted by players when they appeal
+    pub id: i64,
     pub ban_type: String,
     pub reason: String,
     pub expires_at: Option<i64>,
     pub is_permanent: bool,
 }
 
-/// Check if an account is banned
-/// Returns Ok(None) if not banned, Ok(Some(BanInfo)) if banned
-pub async fn check_account_ban(
-    pool: &SqlitePool,
-    account_id: i64,
-) -> Result<Option<BanInfo>, String> {
-    let now = std::time::SystemTime::now()
+/// A ban about to be recorded
+pub struct NewBan<'a> {
+    /// 'account', 'ip', or 'both'
+    pub ban_type: &'a str,
+    /// Username or IP address, as shown in ban lists and matched by unban
+    pub target: &'a str,
+    pub account_id: Option<i64>,
+    pub ip_address: Option<&'a str>,
+    pub hardware_id: Option<&'a str>,
+    pub reason: &'a str,
+    pub banned_by: i64,
+    /// None for a permanent ban
+    pub expires_at: Option<i64>,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
-        .as_secs() as i64;
+        .as_secs() as i64
+}
 
+/// Find an active ban matching any of the given account, IP address or hardware id.
+/// Returns Ok(None) if not banned. When several bans match, the longest one wins.
+pub async fn find_active_ban(
+    pool: &DbPool,
+    account_id: Option<i64>,
+    ip_address: Option<&str>,
+    hardware_id: Option<&str>,
+) -> Result<Option<BanInfo>, String> {
     let result = sqlx::query(
-        "SELECT ban_type, reason, expires_at
+        "SELECT id, ban_type, reason, expires_at
          FROM bans
-         WHERE banned_account_id = ?1
-           AND is_active = TRUE
-           AND (expires_at IS NULL OR expires_at > ?2)
+         WHERE is_active = TRUE
+           AND (expires_at IS NULL OR expires_at > $1)
+           AND (account_id = $2 OR ip_address = $3 OR hardware_id = $4)
+         ORDER BY (expires_at IS NULL) DESC, expires_at DESC
          LIMIT 1"
     )
+    .bind(now())
     .bind(account_id)
-    .bind(now)
+    .bind(ip_address)
+    .bind(hardware_id)
     .fetch_optional(pool)
     .await;
 
@@ -44,6 +71,7 @@ pub async fn check_account_ban(
             let is_permanent = expires_at.is_none();
 
             Ok(Some(BanInfo {
+                id: row.get("id"),
                 ban_type: row.get("ban_type"),
                 reason: row.get("reason"),
                 expires_at,
@@ -51,67 +79,70 @@ pub async fn check_account_ban(
             }))
         }
         Ok(None) => Ok(None),
-        Err(e) => Err(format!("Failed to check account ban: {}", e)),
+        Err(e) => Err(format!("Failed to check bans: {}", e)),
     }
 }
 
 /// Check if an IP address is banned
 /// Returns Ok(None) if not banned, Ok(Some(BanInfo)) if banned
-pub async fn check_ip_ban(pool: &SqlitePool, ip_address: &str) -> Result<Option<BanInfo>, String> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+pub async fn check_ip_ban(pool: &DbPool, ip_address: &str) -> Result<Option<BanInfo>, String> {
+    find_active_ban(pool, None, Some(ip_address), None).await
+}
 
+/// Record a ban and return its id
+pub async fn insert_ban(pool: &DbPool, ban: &NewBan<'_>) -> Result<i64, String> {
     let result = sqlx::query(
-        "SELECT ban_type, reason, expires_at
-         FROM bans
-         WHERE banned_ip = ?1
-           AND is_active = TRUE
-           AND (expires_at IS NULL OR expires_at > ?2)
-         LIMIT 1"
+        "INSERT INTO bans (ban_type, target, account_id, ip_address, hardware_id, reason, banned_by, banned_at, expires_at, is_active)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, TRUE)
+         RETURNING id"
     )
-    .bind(ip_address)
-    .bind(now)
-    .fetch_optional(pool)
+    .bind(ban.ban_type)
+    .bind(ban.target)
+    .bind(ban.account_id)
+    .bind(ban.ip_address)
+    .bind(ban.hardware_id)
+    .bind(ban.reason)
+    .bind(ban.banned_by)
+    .bind(now())
+    .bind(ban.expires_at)
+    .fetch_one(pool)
     .await;
 
     match result {
-        Ok(Some(row)) => {
-            let expires_at: Option<i64> = row.get("expires_at");
-            let is_permanent = expires_at.is_none();
-
-            Ok(Some(BanInfo {
-                ban_type: row.get("ban_type"),
-                reason: row.get("reason"),
-                expires_at,
-                is_permanent,
-            }))
-        }
-        Ok(None) => Ok(None),
-        Err(e) => Err(format!("Failed to check IP ban: {}", e)),
+        Ok(row) => Ok(row.get(0)),
+        Err(e) => Err(format!("Failed to record ban: {}", e)),
     }
 }
 
+/// Deactivate temporary bans that have run out, returning their (id, target)
+pub async fn expire_bans(pool: &DbPool) -> Result<Vec<(i64, String)>, String> {
+    let rows = sqlx::query(
+        "UPDATE bans SET is_active = FALSE
+         WHERE is_active = TRUE AND expires_at IS NOT NULL AND expires_at <= $1
+         RETURNING id, target"
+    )
+    .bind(now())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to expire bans: {}", e))?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
 /// Log a rate limit violation to the database
 pub async fn log_rate_limit_violation(
-    pool: &SqlitePool,
+    pool: &DbPool,
     identifier: &str,
     violation_type: &str,
     details: &str,
 ) -> Result<(), String> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-
     let result = sqlx::query(
         "INSERT INTO rate_limit_violations (identifier, violation_type, violated_at, details)
-         VALUES (?1, ?2, ?3, ?4)"
+         VALUES ($1, $2, $3, $4)"
     )
     .bind(identifier)
     .bind(violation_type)
-    .bind(now)
+    .bind(now())
     .bind(details)
     .execute(pool)
     .await;
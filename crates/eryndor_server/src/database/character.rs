@@ -3,12 +3,13 @@
 //! Handles character creation, loading, and saving.
 
 use bevy::prelude::*;
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 use eryndor_shared::*;
 
 /// Get all characters for an account
-pub async fn get_characters(pool: &SqlitePool, account_id: i64) -> Result<Vec<CharacterData>, String> {
-    let result = sqlx::query("SELECT id, name, class, level FROM characters WHERE account_id = ?1")
+pub async fn get_characters(pool: &DbPool, account_id: i64) -> Result<Vec<CharacterData>, String> {
+    let result = sqlx::query("SELECT id, name, class, level, faction, shard_id FROM characters WHERE account_id = $1")
         .bind(account_id)
         .fetch_all(pool)
         .await;
@@ -31,6 +32,8 @@ pub async fn get_characters(pool: &SqlitePool, account_id: i64) -> Result<Vec<Ch
                         name: row.get(1),
                         class,
                         level: row.get::<i32, _>(3) as u32,
+                        faction: faction_from_id(row.try_get(4).unwrap_or(0)),
+                        shard_id: row.get(5),
                     }
                 })
                 .collect();
@@ -41,12 +44,14 @@ pub async fn get_characters(pool: &SqlitePool, account_id: i64) -> Result<Vec<Ch
     }
 }
 
-/// Create a new character for an account
+/// Create a new character for an account on the given shard
 pub async fn create_character(
-    pool: &SqlitePool,
+    pool: &DbPool,
     account_id: i64,
     name: &str,
     class: CharacterClass,
+    faction: Faction,
+    shard_id: &str,
 ) -> Result<CharacterData, String> {
     let class_id = match class {
         CharacterClass::Rogue => 0,
@@ -54,21 +59,25 @@ pub async fn create_character(
         CharacterClass::Knight => 2,
     };
 
-    let result = sqlx::query("INSERT INTO characters (account_id, name, class) VALUES (?1, ?2, ?3)")
+    let result = sqlx::query("INSERT INTO characters (account_id, name, class, faction, shard_id) VALUES ($1, $2, $3, $4, $5) RETURNING id")
         .bind(account_id)
         .bind(name)
         .bind(class_id)
-        .execute(pool)
+        .bind(faction_id(faction))
+        .bind(shard_id)
+        .fetch_one(pool)
         .await;
 
     match result {
-        Ok(result) => {
-            let character_id = result.last_insert_rowid();
+        Ok(row) => {
+            let character_id: i64 = row.get(0);
             Ok(CharacterData {
                 id: character_id,
                 name: name.to_string(),
                 class,
                 level: 1,
+                faction,
+                shard_id: shard_id.to_string(),
             })
         }
         Err(e) => Err(format!("Failed to create character: {}", e)),
@@ -77,12 +86,12 @@ pub async fn create_character(
 
 /// Load character data from the database
 pub async fn load_character(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
 ) -> Result<(Character, Position, Health, Mana, Gold), String> {
     let result = sqlx::query(
         "SELECT name, class, level, position_x, position_y, health, mana, gold
-         FROM characters WHERE id = ?1"
+         FROM characters WHERE id = $1"
     )
     .bind(character_id)
     .fetch_optional(pool)
@@ -104,13 +113,14 @@ pub async fn load_character(
                 level: row.get::<i32, _>(2) as u32,
             };
 
-            let position = Position(Vec2::new(row.get(3), row.get(4)));
+            // REAL columns decode as f64 on both backends
+            let position = Position(Vec2::new(row.get::<f64, _>(3) as f32, row.get::<f64, _>(4) as f32));
             let health = Health {
-                current: row.get(5),
+                current: row.get::<f64, _>(5) as f32,
                 max: 100.0,
             };
             let mana = Mana {
-                current: row.get(6),
+                current: row.get::<f64, _>(6) as f32,
                 max: 100.0,
             };
             let gold = Gold(row.try_get::<i32, _>(7).unwrap_or(0) as u32);
@@ -122,9 +132,127 @@ pub async fn load_character(
     }
 }
 
+/// Load a character's faction
+pub async fn load_faction(pool: &DbPool, character_id: i64) -> Result<Faction, String> {
+    let result = sqlx::query("SELECT faction FROM characters WHERE id = $1")
+        .bind(character_id)
+        .fetch_optional(pool)
+        .await;
+
+    match result {
+        Ok(Some(row)) => Ok(faction_from_id(row.get(0))),
+        Ok(None) => Err("Character not found".to_string()),
+        Err(e) => Err(format!("Failed to load faction: {}", e)),
+    }
+}
+
+/// Load an unexpired chat mute as (ends at unix time, reason)
+pub async fn load_mute(pool: &DbPool, character_id: i64) -> Result<Option<(i64, String)>, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let result = sqlx::query(
+        "SELECT muted_until, mute_reason FROM characters WHERE id = $1 AND muted_until > $2"
+    )
+    .bind(character_id)
+    .bind(now)
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(row)) => {
+            let reason: Option<String> = row.get(1);
+            Ok(Some((row.get(0), reason.unwrap_or_default())))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => Err(format!("Failed to load mute: {}", e)),
+    }
+}
+
+/// Mute a character by name until the given unix time, or lift the mute with `None`.
+/// Returns false if there is no such character.
+pub async fn set_mute(
+    pool: &DbPool,
+    character_name: &str,
+    muted_until: Option<i64>,
+    reason: &str,
+) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE characters SET muted_until = $1, mute_reason = $2 WHERE LOWER(name) = LOWER($3)"
+    )
+    .bind(muted_until)
+    .bind(muted_until.map(|_| reason))
+    .bind(character_name)
+    .execute(pool)
+    .await;
+
+    match result {
+        Ok(result) => Ok(result.rows_affected() > 0),
+        Err(e) => Err(format!("Failed to update mute: {}", e)),
+    }
+}
+
+/// Load the name of the guild a character belongs to
+pub async fn load_guild(pool: &DbPool, character_id: i64) -> Result<Option<String>, String> {
+    let result = sqlx::query("SELECT guild_name FROM characters WHERE id = $1")
+        .bind(character_id)
+        .fetch_optional(pool)
+        .await;
+
+    match result {
+        Ok(row) => Ok(row.and_then(|row| row.get(0))),
+        Err(e) => Err(format!("Failed to load guild: {}", e)),
+    }
+}
+
+/// Put a character in a guild, or take them out of theirs with `None`
+pub async fn set_guild(pool: &DbPool, character_id: i64, guild_name: Option<&str>) -> Result<(), String> {
+    let result = sqlx::query("UPDATE characters SET guild_name = $1 WHERE id = $2")
+        .bind(guild_name)
+        .bind(character_id)
+        .execute(pool)
+        .await;
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Failed to update guild: {}", e)),
+    }
+}
+
+/// Found a guild with the character as its first member.
+/// Fails if any character already belongs to a guild of that name, ignoring case.
+pub async fn create_guild(pool: &DbPool, character_id: i64, guild_name: &str) -> Result<(), String> {
+    let existing = sqlx::query("SELECT 1 FROM characters WHERE LOWER(guild_name) = LOWER($1)")
+        .bind(guild_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up guild: {}", e))?;
+
+    if existing.is_some() {
+        return Err(format!("A guild named '{}' already exists", guild_name));
+    }
+    set_guild(pool, character_id, Some(guild_name)).await
+}
+
+fn faction_id(faction: Faction) -> i32 {
+    match faction {
+        Faction::Dawnguard => 0,
+        Faction::Ironclad => 1,
+    }
+}
+
+fn faction_from_id(faction_id: i32) -> Faction {
+    match faction_id {
+        1 => Faction::Ironclad,
+        _ => Faction::Dawnguard,
+    }
+}
+
 /// Save character position, health, mana, and gold
 pub async fn save_character(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     position: &Position,
     health: &Health,
@@ -132,8 +260,8 @@ pub async fn save_character(
     gold: &Gold,
 ) -> Result<(), String> {
     let result = sqlx::query(
-        "UPDATE characters SET position_x = ?1, position_y = ?2, health = ?3, mana = ?4, gold = ?5
-         WHERE id = ?6"
+        "UPDATE characters SET position_x = $1, position_y = $2, health = $3, mana = $4, gold = $5
+         WHERE id = $6"
     )
     .bind(position.0.x)
     .bind(position.0.y)
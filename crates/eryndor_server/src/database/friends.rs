@@ -0,0 +1,87 @@
+//! Friend list persistence.
+//!
+//! Friendships are one-way: adding someone puts them on your list only.
+
+use sqlx::Row;
+use super::DbPool;
+use tracing::info;
+
+/// Load a character's friends as (character id, name) pairs
+pub async fn load_friends(pool: &DbPool, character_id: i64) -> Result<Vec<(i64, String)>, String> {
+    let result = sqlx::query(
+        "SELECT c.id, c.name
+         FROM character_friends f
+         JOIN characters c ON c.id = f.friend_character_id
+         WHERE f.character_id = $1
+         ORDER BY c.name"
+    )
+    .bind(character_id)
+    .fetch_all(pool)
+    .await;
+
+    match result {
+        Ok(rows) => {
+            let friends: Vec<(i64, String)> = rows.iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect();
+            info!("Loaded {} friends for character {}", friends.len(), character_id);
+            Ok(friends)
+        }
+        Err(e) => Err(format!("Failed to load friends: {}", e)),
+    }
+}
+
+/// Add the character called `friend_name` to a friend list.
+/// Returns the friend's character id and name as stored.
+pub async fn add_friend(
+    pool: &DbPool,
+    character_id: i64,
+    friend_name: &str,
+) -> Result<(i64, String), String> {
+    let friend = sqlx::query("SELECT id, name FROM characters WHERE LOWER(name) = LOWER($1)")
+        .bind(friend_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up character: {}", e))?;
+
+    let Some(friend) = friend else {
+        return Err(format!("No character named '{}'", friend_name));
+    };
+    let friend_id: i64 = friend.get(0);
+    let name: String = friend.get(1);
+
+    if friend_id == character_id {
+        return Err("You can't add yourself as a friend".to_string());
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    sqlx::query(
+        "INSERT INTO character_friends (character_id, friend_character_id, created_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(character_id)
+    .bind(friend_id)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to add friend: {}", e))?;
+
+    Ok((friend_id, name))
+}
+
+/// Remove a character from a friend list
+pub async fn remove_friend(pool: &DbPool, character_id: i64, friend_id: i64) -> Result<(), String> {
+    sqlx::query("DELETE FROM character_friends WHERE character_id = $1 AND friend_character_id = $2")
+        .bind(character_id)
+        .bind(friend_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to remove friend: {}", e))?;
+
+    Ok(())
+}
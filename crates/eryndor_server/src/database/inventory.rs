@@ -2,7 +2,8 @@
 //!
 //! Handles inventory, equipment, learned abilities, and hotbar persistence.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 use tracing::info;
 use eryndor_shared::*;
 
@@ -11,9 +12,9 @@ use eryndor_shared::*;
 // =============================================================================
 
 /// Load equipment for a character
-pub async fn load_equipment(pool: &SqlitePool, character_id: i64) -> Result<Equipment, String> {
+pub async fn load_equipment(pool: &DbPool, character_id: i64) -> Result<Equipment, String> {
     let result = sqlx::query(
-        "SELECT weapon, helmet, chest, legs, boots FROM character_equipment WHERE character_id = ?1"
+        "SELECT weapon, helmet, chest, legs, boots FROM character_equipment WHERE character_id = $1"
     )
     .bind(character_id)
     .fetch_optional(pool)
@@ -21,12 +22,13 @@ pub async fn load_equipment(pool: &SqlitePool, character_id: i64) -> Result<Equi
 
     match result {
         Ok(Some(row)) => {
+            let item = |index: usize| row.get::<Option<i64>, _>(index).map(|id| id as u32);
             let equipment = Equipment {
-                weapon: row.get(0),
-                helmet: row.get(1),
-                chest: row.get(2),
-                legs: row.get(3),
-                boots: row.get(4),
+                weapon: item(0),
+                helmet: item(1),
+                chest: item(2),
+                legs: item(3),
+                boots: item(4),
             };
             info!(
                 "Loaded equipment for character {}: weapon={:?}, helmet={:?}, chest={:?}, legs={:?}, boots={:?}",
@@ -44,7 +46,7 @@ pub async fn load_equipment(pool: &SqlitePool, character_id: i64) -> Result<Equi
 
 /// Save equipment for a character
 pub async fn save_equipment(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     equipment: &Equipment,
 ) -> Result<(), String> {
@@ -55,16 +57,16 @@ pub async fn save_equipment(
 
     let result = sqlx::query(
         "INSERT INTO character_equipment (character_id, weapon, helmet, chest, legs, boots)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         VALUES ($1, $2, $3, $4, $5, $6)
          ON CONFLICT(character_id) DO UPDATE SET
-            weapon = ?2, helmet = ?3, chest = ?4, legs = ?5, boots = ?6"
+            weapon = $2, helmet = $3, chest = $4, legs = $5, boots = $6"
     )
     .bind(character_id)
-    .bind(equipment.weapon)
-    .bind(equipment.helmet)
-    .bind(equipment.chest)
-    .bind(equipment.legs)
-    .bind(equipment.boots)
+    .bind(equipment.weapon.map(i64::from))
+    .bind(equipment.helmet.map(i64::from))
+    .bind(equipment.chest.map(i64::from))
+    .bind(equipment.legs.map(i64::from))
+    .bind(equipment.boots.map(i64::from))
     .execute(pool)
     .await;
 
@@ -82,9 +84,9 @@ pub async fn save_equipment(
 // =============================================================================
 
 /// Load inventory for a character
-pub async fn load_inventory(pool: &SqlitePool, character_id: i64) -> Result<Inventory, String> {
+pub async fn load_inventory(pool: &DbPool, character_id: i64) -> Result<Inventory, String> {
     let result = sqlx::query(
-        "SELECT slot_index, item_id, quantity FROM character_inventory WHERE character_id = ?1 ORDER BY slot_index"
+        "SELECT slot_index, item_id, quantity FROM character_inventory WHERE character_id = $1 ORDER BY slot_index"
     )
     .bind(character_id)
     .fetch_all(pool)
@@ -117,7 +119,7 @@ pub async fn load_inventory(pool: &SqlitePool, character_id: i64) -> Result<Inve
 
 /// Save inventory for a character
 pub async fn save_inventory(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     inventory: &Inventory,
 ) -> Result<(), String> {
@@ -125,7 +127,7 @@ pub async fn save_inventory(
     info!("Saving {} items to inventory for character {}", item_count, character_id);
 
     // Delete all existing inventory items for this character
-    let delete_result = sqlx::query("DELETE FROM character_inventory WHERE character_id = ?1")
+    let delete_result = sqlx::query("DELETE FROM character_inventory WHERE character_id = $1")
         .bind(character_id)
         .execute(pool)
         .await;
@@ -139,7 +141,7 @@ pub async fn save_inventory(
         if let Some(item_stack) = item_slot {
             let insert_result = sqlx::query(
                 "INSERT INTO character_inventory (character_id, slot_index, item_id, quantity)
-                 VALUES (?1, ?2, ?3, ?4)"
+                 VALUES ($1, $2, $3, $4)"
             )
             .bind(character_id)
             .bind(slot_index as i32)
@@ -164,11 +166,11 @@ pub async fn save_inventory(
 
 /// Load learned abilities for a character
 pub async fn load_learned_abilities(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
 ) -> Result<LearnedAbilities, String> {
     let result = sqlx::query(
-        "SELECT ability_id FROM character_learned_abilities WHERE character_id = ?1"
+        "SELECT ability_id FROM character_learned_abilities WHERE character_id = $1"
     )
     .bind(character_id)
     .fetch_all(pool)
@@ -194,13 +196,13 @@ pub async fn load_learned_abilities(
 
 /// Save learned abilities for a character
 pub async fn save_learned_abilities(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     abilities: &LearnedAbilities,
 ) -> Result<(), String> {
     // Delete all existing abilities for this character
     let delete_result =
-        sqlx::query("DELETE FROM character_learned_abilities WHERE character_id = ?1")
+        sqlx::query("DELETE FROM character_learned_abilities WHERE character_id = $1")
             .bind(character_id)
             .execute(pool)
             .await;
@@ -213,7 +215,7 @@ pub async fn save_learned_abilities(
     for &ability_id in &abilities.abilities {
         let insert_result = sqlx::query(
             "INSERT INTO character_learned_abilities (character_id, ability_id)
-             VALUES (?1, ?2)"
+             VALUES ($1, $2)"
         )
         .bind(character_id)
         .bind(ability_id as i32)
@@ -233,9 +235,9 @@ pub async fn save_learned_abilities(
 // =============================================================================
 
 /// Load hotbar for a character
-pub async fn load_hotbar(pool: &SqlitePool, character_id: i64) -> Result<Hotbar, String> {
+pub async fn load_hotbar(pool: &DbPool, character_id: i64) -> Result<Hotbar, String> {
     let result = sqlx::query(
-        "SELECT slot_index, slot_type, slot_item_id FROM character_hotbar WHERE character_id = ?1"
+        "SELECT slot_index, slot_type, slot_item_id FROM character_hotbar WHERE character_id = $1"
     )
     .bind(character_id)
     .fetch_all(pool)
@@ -256,6 +258,7 @@ pub async fn load_hotbar(pool: &SqlitePool, character_id: i64) -> Result<Hotbar,
                 if slot_index >= 0 && (slot_index as usize) < hotbar.slots.len() {
                     let hotbar_slot = match slot_type {
                         0 => Some(HotbarSlot::Ability(slot_item_id as u32)),
+                        1 => Some(HotbarSlot::Item(slot_item_id as u32)),
                         _ => None,
                     };
                     hotbar.slots[slot_index as usize] = hotbar_slot;
@@ -269,12 +272,12 @@ pub async fn load_hotbar(pool: &SqlitePool, character_id: i64) -> Result<Hotbar,
 
 /// Save hotbar for a character
 pub async fn save_hotbar(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     hotbar: &Hotbar,
 ) -> Result<(), String> {
     // Delete all existing hotbar slots for this character
-    let delete_result = sqlx::query("DELETE FROM character_hotbar WHERE character_id = ?1")
+    let delete_result = sqlx::query("DELETE FROM character_hotbar WHERE character_id = $1")
         .bind(character_id)
         .execute(pool)
         .await;
@@ -288,11 +291,12 @@ pub async fn save_hotbar(
         if let Some(slot) = hotbar_slot {
             let (slot_type, slot_item_id) = match slot {
                 HotbarSlot::Ability(ability_id) => (0, *ability_id),
+                HotbarSlot::Item(item_id) => (1, *item_id),
             };
 
             let insert_result = sqlx::query(
                 "INSERT INTO character_hotbar (character_id, slot_index, slot_type, slot_item_id)
-                 VALUES (?1, ?2, ?3, ?4)"
+                 VALUES ($1, $2, $3, $4)"
             )
             .bind(character_id)
             .bind(slot_index as i32)
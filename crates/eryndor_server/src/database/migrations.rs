@@ -1,390 +1,432 @@
-//! Database schema migrations.
+//! Versioned database migrations.
 //!
-//! All table creation and schema migrations are defined here for maintainability.
-
-use sqlx::SqlitePool;
-use tracing::info;
-
-/// Run all database migrations and schema setup.
-/// This is called once at server startup.
-pub async fn run_migrations(pool: &SqlitePool) {
-    // Core tables
-    create_accounts_table(pool).await;
-    create_characters_table(pool).await;
-    create_inventory_table(pool).await;
-    create_quests_table(pool).await;
-    create_equipment_table(pool).await;
-    create_learned_abilities_table(pool).await;
-    create_hotbar_table(pool).await;
-
-    // Run account migrations
-    run_account_migrations(pool).await;
-
-    // Run character migrations
-    run_character_migrations(pool).await;
-
-    // Security tables
-    create_audit_logs_table(pool).await;
-    create_bans_table(pool).await;
-    create_ban_appeals_table(pool).await;
-    create_content_flags_table(pool).await;
-    create_admin_actions_table(pool).await;
-    create_rate_limit_violations_table(pool).await;
-    create_active_sessions_table(pool).await;
-
-    // One-time cleanup migrations
-    cleanup_guest_accounts(pool).await;
-
-    info!("Database initialized successfully with security tables");
-}
-
-// =============================================================================
-// Core Tables
-// =============================================================================
-
-async fn create_accounts_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS accounts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT UNIQUE NOT NULL,
-            password_hash TEXT NOT NULL,
-            created_at INTEGER NOT NULL
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create accounts table");
-}
-
-async fn create_characters_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS characters (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            account_id INTEGER NOT NULL,
-            name TEXT UNIQUE NOT NULL,
-            class INTEGER NOT NULL,
-            level INTEGER NOT NULL DEFAULT 1,
-            position_x REAL NOT NULL DEFAULT 0.0,
-            position_y REAL NOT NULL DEFAULT 0.0,
-            health REAL NOT NULL DEFAULT 100.0,
-            mana REAL NOT NULL DEFAULT 100.0,
-            FOREIGN KEY (account_id) REFERENCES accounts(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create characters table");
-}
-
-async fn create_inventory_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS character_inventory (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            character_id INTEGER NOT NULL,
-            slot_index INTEGER NOT NULL,
-            item_id INTEGER NOT NULL,
-            quantity INTEGER NOT NULL DEFAULT 1,
-            FOREIGN KEY (character_id) REFERENCES characters(id),
-            UNIQUE(character_id, slot_index)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create inventory table");
-}
-
-async fn create_quests_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS character_quests (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            character_id INTEGER NOT NULL,
-            quest_id INTEGER NOT NULL,
-            completed INTEGER NOT NULL DEFAULT 0,
-            progress TEXT,
-            FOREIGN KEY (character_id) REFERENCES characters(id),
-            UNIQUE(character_id, quest_id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create quests table");
-
-    // Migration: Add progress column if it doesn't exist
-    let _ = sqlx::query("ALTER TABLE character_quests ADD COLUMN progress TEXT")
-        .execute(pool)
-        .await;
+//! Each migration runs once, in order, inside a transaction, and is recorded in the
+//! `schema_migrations` table. Never edit a migration that has shipped - add a new one.
+//! Statements are written for SQLite; each backend's `translate` adapts the column types.
+
+use sqlx::Row;
+use tracing::{info, warn};
+use super::{DatabaseBackend, DbPool};
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    statements: &'static [&'static str],
 }
 
-async fn create_equipment_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS character_equipment (
-            character_id INTEGER PRIMARY KEY,
-            weapon INTEGER,
-            helmet INTEGER,
-            chest INTEGER,
-            legs INTEGER,
-            boots INTEGER,
-            FOREIGN KEY (character_id) REFERENCES characters(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create equipment table");
-}
-
-async fn create_learned_abilities_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS character_learned_abilities (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            character_id INTEGER NOT NULL,
-            ability_id INTEGER NOT NULL,
-            FOREIGN KEY (character_id) REFERENCES characters(id),
-            UNIQUE(character_id, ability_id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create learned abilities table");
-}
-
-async fn create_hotbar_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS character_hotbar (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            character_id INTEGER NOT NULL,
-            slot_index INTEGER NOT NULL,
-            slot_type INTEGER NOT NULL,
-            slot_item_id INTEGER NOT NULL,
-            FOREIGN KEY (character_id) REFERENCES characters(id),
-            UNIQUE(character_id, slot_index)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create hotbar table");
-}
-
-// =============================================================================
-// Account Migrations
-// =============================================================================
-
-async fn run_account_migrations(pool: &SqlitePool) {
-    // Add new columns to accounts table
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN email TEXT UNIQUE").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN email_verified BOOLEAN DEFAULT FALSE").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN account_type TEXT DEFAULT 'registered'").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN is_admin BOOLEAN DEFAULT FALSE").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN guest_token TEXT UNIQUE").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN guest_created_at INTEGER").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN guest_expires_at INTEGER").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN last_login_at INTEGER").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN last_login_ip TEXT").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN oauth_provider TEXT").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE accounts ADD COLUMN oauth_id TEXT").execute(pool).await;
-
-    // Create indexes
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_accounts_email ON accounts(email)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_accounts_guest_token ON accounts(guest_token)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_accounts_type ON accounts(account_type)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_accounts_oauth_id ON accounts(oauth_id)").execute(pool).await;
-}
-
-// =============================================================================
-// Character Migrations
-// =============================================================================
-
-async fn run_character_migrations(pool: &SqlitePool) {
-    // Gold
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN gold INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-
-    // Progression columns
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN current_xp INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_sword INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_dagger INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_staff INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_mace INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_bow INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_prof_axe INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_sword INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_dagger INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_staff INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_mace INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_bow INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN weapon_exp_axe INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_prof_light INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_prof_medium INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_prof_heavy INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_exp_light INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_exp_medium INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN armor_exp_heavy INTEGER NOT NULL DEFAULT 0").execute(pool).await;
-    let _ = sqlx::query("ALTER TABLE characters ADD COLUMN unlocked_armor_passives TEXT").execute(pool).await;
-}
-
-// =============================================================================
-// Security Tables
-// =============================================================================
-
-async fn create_audit_logs_table(pool: &SqlitePool) {
-    let _ = sqlx::query(
-        "CREATE TABLE IF NOT EXISTS audit_logs (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp INTEGER NOT NULL,
-            action_type TEXT NOT NULL,
-            actor_account_id INTEGER,
-            target_account_id INTEGER,
-            target_username TEXT,
-            ip_address TEXT,
-            details TEXT
-        )"
-    ).execute(pool).await;
-
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_logs_timestamp ON audit_logs(timestamp)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_logs_action_type ON audit_logs(action_type)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_logs_actor ON audit_logs(actor_account_id)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_logs_target ON audit_logs(target_account_id)").execute(pool).await;
-}
-
-async fn create_bans_table(pool: &SqlitePool) {
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        statements: &[
+            // Core tables
+            "CREATE TABLE IF NOT EXISTS accounts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                username TEXT UNIQUE NOT NULL,
+                password_hash TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                email TEXT UNIQUE,
+                email_verified BOOLEAN DEFAULT FALSE,
+                account_type TEXT DEFAULT 'registered',
+                is_admin BOOLEAN DEFAULT FALSE,
+                guest_token TEXT UNIQUE,
+                guest_created_at INTEGER,
+                guest_expires_at INTEGER,
+                last_login_at INTEGER,
+                last_login_ip TEXT,
+                oauth_provider TEXT,
+                oauth_id TEXT
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_accounts_email ON accounts(email)",
+            "CREATE INDEX IF NOT EXISTS idx_accounts_guest_token ON accounts(guest_token)",
+            "CREATE INDEX IF NOT EXISTS idx_accounts_type ON accounts(account_type)",
+            "CREATE INDEX IF NOT EXISTS idx_accounts_oauth_id ON accounts(oauth_id)",
+            "CREATE TABLE IF NOT EXISTS characters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id INTEGER NOT NULL,
+                name TEXT UNIQUE NOT NULL,
+                class INTEGER NOT NULL,
+                level INTEGER NOT NULL DEFAULT 1,
+                position_x REAL NOT NULL DEFAULT 0.0,
+                position_y REAL NOT NULL DEFAULT 0.0,
+                health REAL NOT NULL DEFAULT 100.0,
+                mana REAL NOT NULL DEFAULT 100.0,
+                gold INTEGER NOT NULL DEFAULT 0,
+                faction INTEGER NOT NULL DEFAULT 0,
+                muted_until INTEGER,
+                mute_reason TEXT,
+                current_xp INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_sword INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_dagger INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_staff INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_mace INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_bow INTEGER NOT NULL DEFAULT 0,
+                weapon_prof_axe INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_sword INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_dagger INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_staff INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_mace INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_bow INTEGER NOT NULL DEFAULT 0,
+                weapon_exp_axe INTEGER NOT NULL DEFAULT 0,
+                armor_prof_light INTEGER NOT NULL DEFAULT 0,
+                armor_prof_medium INTEGER NOT NULL DEFAULT 0,
+                armor_prof_heavy INTEGER NOT NULL DEFAULT 0,
+                armor_exp_light INTEGER NOT NULL DEFAULT 0,
+                armor_exp_medium INTEGER NOT NULL DEFAULT 0,
+                armor_exp_heavy INTEGER NOT NULL DEFAULT 0,
+                unlocked_armor_passives TEXT,
+                FOREIGN KEY (account_id) REFERENCES accounts(id)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_inventory (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character_id INTEGER NOT NULL,
+                slot_index INTEGER NOT NULL,
+                item_id INTEGER NOT NULL,
+                quantity INTEGER NOT NULL DEFAULT 1,
+                FOREIGN KEY (character_id) REFERENCES characters(id),
+                UNIQUE(character_id, slot_index)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_quests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character_id INTEGER NOT NULL,
+                quest_id INTEGER NOT NULL,
+                completed INTEGER NOT NULL DEFAULT 0,
+                progress TEXT,
+                FOREIGN KEY (character_id) REFERENCES characters(id),
+                UNIQUE(character_id, quest_id)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_equipment (
+                character_id INTEGER PRIMARY KEY,
+                weapon INTEGER,
+                helmet INTEGER,
+                chest INTEGER,
+                legs INTEGER,
+                boots INTEGER,
+                FOREIGN KEY (character_id) REFERENCES characters(id)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_learned_abilities (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character_id INTEGER NOT NULL,
+                ability_id INTEGER NOT NULL,
+                FOREIGN KEY (character_id) REFERENCES characters(id),
+                UNIQUE(character_id, ability_id)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_hotbar (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character_id INTEGER NOT NULL,
+                slot_index INTEGER NOT NULL,
+                slot_type INTEGER NOT NULL,
+                slot_item_id INTEGER NOT NULL,
+                FOREIGN KEY (character_id) REFERENCES characters(id),
+                UNIQUE(character_id, slot_index)
+            )",
+            "CREATE TABLE IF NOT EXISTS character_friends (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character_id INTEGER NOT NULL,
+                friend_character_id INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (character_id) REFERENCES characters(id),
+                FOREIGN KEY (friend_character_id) REFERENCES characters(id),
+                UNIQUE(character_id, friend_character_id)
+            )",
+            // Security tables
+            "CREATE TABLE IF NOT EXISTS audit_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                action_type TEXT NOT NULL,
+                actor_account_id INTEGER,
+                target_account_id INTEGER,
+                target_username TEXT,
+                ip_address TEXT,
+                details TEXT
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_audit_logs_timestamp ON audit_logs(timestamp)",
+            "CREATE INDEX IF NOT EXISTS idx_audit_logs_action_type ON audit_logs(action_type)",
+            "CREATE INDEX IF NOT EXISTS idx_audit_logs_actor ON audit_logs(actor_account_id)",
+            "CREATE INDEX IF NOT EXISTS idx_audit_logs_target ON audit_logs(target_account_id)",
+            "CREATE TABLE IF NOT EXISTS bans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                ban_type TEXT NOT NULL,
+                target TEXT NOT NULL,
+                account_id INTEGER,
+                reason TEXT NOT NULL,
+                banned_by INTEGER,
+                banned_at INTEGER NOT NULL,
+                expires_at INTEGER,
+                is_active BOOLEAN DEFAULT TRUE,
+                notes TEXT,
+                FOREIGN KEY(account_id) REFERENCES accounts(id),
+                FOREIGN KEY(banned_by) REFERENCES accounts(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_bans_target ON bans(target, is_active)",
+            "CREATE INDEX IF NOT EXISTS idx_bans_account ON bans(account_id, is_active)",
+            "CREATE INDEX IF NOT EXISTS idx_bans_expiry ON bans(expires_at)",
+            "CREATE TABLE IF NOT EXISTS ban_appeals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                ban_id INTEGER NOT NULL,
+                appeal_text TEXT NOT NULL,
+                submitted_at INTEGER NOT NULL,
+                status TEXT DEFAULT 'pending',
+                reviewed_by INTEGER,
+                reviewed_at INTEGER,
+                review_notes TEXT,
+                FOREIGN KEY(ban_id) REFERENCES bans(id),
+                FOREIGN KEY(reviewed_by) REFERENCES accounts(id)
+            )",
+            "CREATE TABLE IF NOT EXISTS content_flags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id INTEGER NOT NULL,
+                content_type TEXT NOT NULL,
+                content TEXT NOT NULL,
+                toxicity_score REAL,
+                flagged_at INTEGER NOT NULL,
+                status TEXT DEFAULT 'pending',
+                reviewed_by INTEGER,
+                reviewed_at INTEGER,
+                FOREIGN KEY(account_id) REFERENCES accounts(id),
+                FOREIGN KEY(reviewed_by) REFERENCES accounts(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_content_flags_status ON content_flags(status)",
+            "CREATE INDEX IF NOT EXISTS idx_content_flags_account ON content_flags(account_id)",
+            "CREATE TABLE IF NOT EXISTS admin_actions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                admin_id INTEGER NOT NULL,
+                action_type TEXT NOT NULL,
+                target_id INTEGER,
+                details TEXT,
+                ip_address TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(admin_id) REFERENCES accounts(id),
+                FOREIGN KEY(target_id) REFERENCES accounts(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_admin_actions_admin ON admin_actions(admin_id)",
+            "CREATE INDEX IF NOT EXISTS idx_admin_actions_target ON admin_actions(target_id)",
+            "CREATE INDEX IF NOT EXISTS idx_admin_actions_created ON admin_actions(created_at)",
+            "CREATE TABLE IF NOT EXISTS rate_limit_violations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                identifier TEXT NOT NULL,
+                violation_type TEXT NOT NULL,
+                violated_at INTEGER NOT NULL,
+                details TEXT
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_rate_limit_identifier ON rate_limit_violations(identifier, violation_type)",
+            "CREATE TABLE IF NOT EXISTS active_sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id INTEGER NOT NULL,
+                session_token TEXT UNIQUE NOT NULL,
+                ip_address TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_activity INTEGER NOT NULL,
+                FOREIGN KEY(account_id) REFERENCES accounts(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_sessions_account ON active_sessions(account_id)",
+            "CREATE INDEX IF NOT EXISTS idx_sessions_token ON active_sessions(session_token)",
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "remove_guest_accounts",
+        statements: &["DELETE FROM accounts WHERE account_type = 'guest'"],
+    },
+    Migration {
+        version: 3,
+        name: "two_factor_auth",
+        statements: &[
+            // enabled_at stays NULL until the first code is confirmed
+            "CREATE TABLE IF NOT EXISTS account_two_factor (
+                account_id INTEGER PRIMARY KEY,
+                secret TEXT NOT NULL,
+                enabled_at INTEGER,
+                last_used_step INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY(account_id) REFERENCES accounts(id)
+            )",
+            "CREATE TABLE IF NOT EXISTS account_backup_codes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id INTEGER NOT NULL,
+                code_hash TEXT NOT NULL,
+                used_at INTEGER,
+                FOREIGN KEY(account_id) REFERENCES accounts(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_backup_codes_account ON account_backup_codes(account_id)",
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "ban_enforcement",
+        statements: &[
+            // A ban can match on any of account_id, ip_address and hardware_id
+            "ALTER TABLE bans ADD COLUMN ip_address TEXT",
+            "ALTER TABLE bans ADD COLUMN hardware_id TEXT",
+            "UPDATE bans SET ip_address = target WHERE ban_type = 'ip'",
+            "CREATE INDEX IF NOT EXISTS idx_bans_ip ON bans(ip_address, is_active)",
+            "CREATE INDEX IF NOT EXISTS idx_bans_hardware ON bans(hardware_id, is_active)",
+            "CREATE INDEX IF NOT EXISTS idx_ban_appeals_ban ON ban_appeals(ban_id)",
+            "ALTER TABLE accounts ADD COLUMN last_hardware_id TEXT",
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "shards",
+        statements: &[
+            // Characters made before sharding live on the default shard
+            "ALTER TABLE characters ADD COLUMN shard_id TEXT NOT NULL DEFAULT 'main'",
+            "CREATE TABLE IF NOT EXISTS shards (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                address TEXT NOT NULL,
+                udp_port INTEGER NOT NULL,
+                webtransport_port INTEGER NOT NULL,
+                websocket_port INTEGER NOT NULL,
+                cert_url TEXT NOT NULL,
+                population INTEGER NOT NULL DEFAULT 0,
+                max_players INTEGER NOT NULL,
+                last_heartbeat INTEGER NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "guilds",
+        statements: &[
+            // A guild exists for as long as any character carries its name
+            "ALTER TABLE characters ADD COLUMN guild_name TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_characters_guild ON characters(guild_name)",
+        ],
+    },
+];
+
+/// Columns added one ALTER at a time before migrations were versioned.
+/// Only run against databases that predate `schema_migrations`, which only SQLite ones can.
+const LEGACY_COLUMNS: &[&str] = &[
+    "ALTER TABLE character_quests ADD COLUMN progress TEXT",
+    // SQLite can't add UNIQUE columns; fresh databases get the constraint from migration 1
+    "ALTER TABLE accounts ADD COLUMN email TEXT",
+    "ALTER TABLE accounts ADD COLUMN email_verified BOOLEAN DEFAULT FALSE",
+    "ALTER TABLE accounts ADD COLUMN account_type TEXT DEFAULT 'registered'",
+    "ALTER TABLE accounts ADD COLUMN is_admin BOOLEAN DEFAULT FALSE",
+    "ALTER TABLE accounts ADD COLUMN guest_token TEXT",
+    "ALTER TABLE accounts ADD COLUMN guest_created_at INTEGER",
+    "ALTER TABLE accounts ADD COLUMN guest_expires_at INTEGER",
+    "ALTER TABLE accounts ADD COLUMN last_login_at INTEGER",
+    "ALTER TABLE accounts ADD COLUMN last_login_ip TEXT",
+    "ALTER TABLE accounts ADD COLUMN oauth_provider TEXT",
+    "ALTER TABLE accounts ADD COLUMN oauth_id TEXT",
+    "ALTER TABLE characters ADD COLUMN gold INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN faction INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN muted_until INTEGER",
+    "ALTER TABLE characters ADD COLUMN mute_reason TEXT",
+    "ALTER TABLE characters ADD COLUMN current_xp INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_sword INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_dagger INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_staff INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_mace INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_bow INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_prof_axe INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_sword INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_dagger INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_staff INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_mace INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_bow INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN weapon_exp_axe INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_prof_light INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_prof_medium INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_prof_heavy INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_exp_light INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_exp_medium INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN armor_exp_heavy INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE characters ADD COLUMN unlocked_armor_passives TEXT",
+];
+
+/// Apply every migration that hasn't run yet.
+/// This is called once at server startup.
+pub async fn run_migrations(pool: &DbPool, backend: &dyn DatabaseBackend) -> Result<(), String> {
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS bans (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            ban_type TEXT NOT NULL,
-            target TEXT NOT NULL,
-            account_id INTEGER,
-            reason TEXT NOT NULL,
-            banned_by INTEGER,
-            banned_at INTEGER NOT NULL,
-            expires_at INTEGER,
-            is_active BOOLEAN DEFAULT TRUE,
-            notes TEXT,
-            FOREIGN KEY(account_id) REFERENCES accounts(id),
-            FOREIGN KEY(banned_by) REFERENCES accounts(id)
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at BIGINT NOT NULL
         )"
     )
     .execute(pool)
     .await
-    .expect("Failed to create bans table");
+    .map_err(|e| format!("Failed to create schema_migrations table: {}", e))?;
+
+    let applied: Vec<i64> = sqlx::query("SELECT version FROM schema_migrations")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read schema_migrations: {}", e))?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    if applied.is_empty() && has_legacy_schema(pool, backend).await {
+        upgrade_legacy_schema(pool).await;
+    }
 
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_bans_target ON bans(target, is_active)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_bans_account ON bans(account_id, is_active)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_bans_expiry ON bans(expires_at)").execute(pool).await;
-}
+    for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+        apply(pool, backend, migration).await?;
+        info!("Applied database migration {} ({})", migration.version, migration.name);
+    }
 
-async fn create_ban_appeals_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS ban_appeals (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            ban_id INTEGER NOT NULL,
-            appeal_text TEXT NOT NULL,
-            submitted_at INTEGER NOT NULL,
-            status TEXT DEFAULT 'pending',
-            reviewed_by INTEGER,
-            reviewed_at INTEGER,
-            review_notes TEXT,
-            FOREIGN KEY(ban_id) REFERENCES bans(id),
-            FOREIGN KEY(reviewed_by) REFERENCES accounts(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create ban appeals table");
+    info!("Database schema at version {}", MIGRATIONS.last().map_or(0, |m| m.version));
+    Ok(())
 }
 
-async fn create_content_flags_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS content_flags (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            account_id INTEGER NOT NULL,
-            content_type TEXT NOT NULL,
-            content TEXT NOT NULL,
-            toxicity_score REAL,
-            flagged_at INTEGER NOT NULL,
-            status TEXT DEFAULT 'pending',
-            reviewed_by INTEGER,
-            reviewed_at INTEGER,
-            FOREIGN KEY(account_id) REFERENCES accounts(id),
-            FOREIGN KEY(reviewed_by) REFERENCES accounts(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create content flags table");
-
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_content_flags_status ON content_flags(status)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_content_flags_account ON content_flags(account_id)").execute(pool).await;
-}
+async fn apply(pool: &DbPool, backend: &dyn DatabaseBackend, migration: &Migration) -> Result<(), String> {
+    let fail = |e: sqlx::Error| format!("Migration {} ({}) failed: {}", migration.version, migration.name, e);
 
-async fn create_admin_actions_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS admin_actions (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            admin_id INTEGER NOT NULL,
-            action_type TEXT NOT NULL,
-            target_id INTEGER,
-            details TEXT,
-            ip_address TEXT,
-            created_at INTEGER NOT NULL,
-            FOREIGN KEY(admin_id) REFERENCES accounts(id),
-            FOREIGN KEY(target_id) REFERENCES accounts(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create admin actions table");
+    let mut tx = pool.begin().await.map_err(fail)?;
+    for statement in migration.statements {
+        sqlx::query(&backend.translate(statement))
+            .execute(&mut *tx)
+            .await
+            .map_err(fail)?;
+    }
 
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_admin_actions_admin ON admin_actions(admin_id)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_admin_actions_target ON admin_actions(target_id)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_admin_actions_created ON admin_actions(created_at)").execute(pool).await;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    sqlx::query("INSERT INTO schema_migrations (version, name, applied_at) VALUES ($1, $2, $3)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(fail)?;
+
+    tx.commit().await.map_err(fail)
 }
 
-async fn create_rate_limit_violations_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS rate_limit_violations (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            identifier TEXT NOT NULL,
-            violation_type TEXT NOT NULL,
-            violated_at INTEGER NOT NULL,
-            details TEXT
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create rate limit violations table");
-
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_rate_limit_identifier ON rate_limit_violations(identifier, violation_type)").execute(pool).await;
+/// SQLite databases created before versioned migrations have tables but no history
+async fn has_legacy_schema(pool: &DbPool, backend: &dyn DatabaseBackend) -> bool {
+    let Some(probe) = backend.legacy_schema_probe() else {
+        return false;
+    };
+    sqlx::query(probe)
+        .fetch_optional(pool)
+        .await
+        .map(|row| row.is_some())
+        .unwrap_or(false)
 }
 
-async fn create_active_sessions_table(pool: &SqlitePool) {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS active_sessions (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            account_id INTEGER NOT NULL,
-            session_token TEXT UNIQUE NOT NULL,
-            ip_address TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            last_activity INTEGER NOT NULL,
-            FOREIGN KEY(account_id) REFERENCES accounts(id)
-        )"
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create active sessions table");
-
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_account ON active_sessions(account_id)").execute(pool).await;
-    let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_token ON active_sessions(session_token)").execute(pool).await;
+/// Add any columns an old database is missing so migration 1 finds the schema it expects
+async fn upgrade_legacy_schema(pool: &DbPool) {
+    warn!("Found a database without migration history - upgrading it in place");
+    for statement in LEGACY_COLUMNS {
+        // Fails harmlessly when the column already exists
+        let _ = sqlx::query(statement).execute(pool).await;
+    }
 }
 
-// =============================================================================
-// One-time Cleanup Migrations
-// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-async fn cleanup_guest_accounts(pool: &SqlitePool) {
-    let result = sqlx::query("DELETE FROM accounts WHERE account_type = 'guest'")
-        .execute(pool)
-        .await;
-    if let Ok(rows_affected) = result {
-        if rows_affected.rows_affected() > 0 {
-            info!("Deleted {} guest accounts during migration", rows_affected.rows_affected());
+    #[test]
+    fn migration_versions_increase() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
         }
     }
 }
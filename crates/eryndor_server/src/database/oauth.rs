@@ -2,16 +2,17 @@
 //!
 //! Handles OAuth provider account linking and creation.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 
 /// Find account by OAuth provider and ID
 pub async fn find_account_by_oauth(
-    pool: &SqlitePool,
+    pool: &DbPool,
     provider: &str,
     oauth_id: &str,
 ) -> Result<Option<i64>, String> {
     let result = sqlx::query(
-        "SELECT id FROM accounts WHERE oauth_provider = ?1 AND oauth_id = ?2"
+        "SELECT id FROM accounts WHERE oauth_provider = $1 AND oauth_id = $2"
     )
     .bind(provider)
     .bind(oauth_id)
@@ -30,7 +31,7 @@ pub async fn find_account_by_oauth(
 
 /// Create a new OAuth account
 pub async fn create_oauth_account(
-    pool: &SqlitePool,
+    pool: &DbPool,
     email: &str,
     username: &str,
     provider: &str,
@@ -44,18 +45,19 @@ pub async fn create_oauth_account(
     // OAuth accounts don't have passwords (use empty hash)
     let result = sqlx::query(
         "INSERT INTO accounts (email, username, password_hash, oauth_provider, oauth_id, created_at, account_type)
-         VALUES (?1, ?2, '', ?3, ?4, ?5, 'registered')"
+         VALUES ($1, $2, '', $3, $4, $5, 'registered')
+         RETURNING id"
     )
     .bind(email)
     .bind(username)
     .bind(provider)
     .bind(oauth_id)
     .bind(now)
-    .execute(pool)
+    .fetch_one(pool)
     .await;
 
     match result {
-        Ok(result) => Ok(result.last_insert_rowid()),
+        Ok(row) => Ok(row.get(0)),
         Err(e) => Err(format!("Failed to create OAuth account: {}", e)),
     }
 }
@@ -2,12 +2,13 @@
 //!
 //! Handles experience, weapon proficiency, and armor proficiency saving/loading.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 use eryndor_shared::*;
 
 /// Load all progression data for a character
 pub async fn load_progression(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
 ) -> Result<
     (
@@ -27,7 +28,7 @@ pub async fn load_progression(
                 armor_prof_light, armor_prof_medium, armor_prof_heavy,
                 armor_exp_light, armor_exp_medium, armor_exp_heavy,
                 unlocked_armor_passives
-         FROM characters WHERE id = ?1"
+         FROM characters WHERE id = $1"
     )
     .bind(character_id)
     .fetch_optional(pool)
@@ -39,7 +40,7 @@ pub async fn load_progression(
             let current_xp: i32 = row.try_get(0).unwrap_or(0);
 
             // Get character level for Experience initialization
-            let level_result = sqlx::query("SELECT level FROM characters WHERE id = ?1")
+            let level_result = sqlx::query("SELECT level FROM characters WHERE id = $1")
                 .bind(character_id)
                 .fetch_one(pool)
                 .await;
@@ -54,38 +55,38 @@ pub async fn load_progression(
 
             // Weapon Proficiency
             let weapon_prof = WeaponProficiency {
-                sword: row.try_get(1).unwrap_or(0),
-                dagger: row.try_get(2).unwrap_or(0),
-                staff: row.try_get(3).unwrap_or(0),
+                sword: row.try_get::<i64, _>(1).unwrap_or(0) as u32,
+                dagger: row.try_get::<i64, _>(2).unwrap_or(0) as u32,
+                staff: row.try_get::<i64, _>(3).unwrap_or(0) as u32,
                 wand: 1, // Default wand proficiency (not in DB yet)
-                mace: row.try_get(4).unwrap_or(0),
-                bow: row.try_get(5).unwrap_or(0),
-                axe: row.try_get(6).unwrap_or(0),
+                mace: row.try_get::<i64, _>(4).unwrap_or(0) as u32,
+                bow: row.try_get::<i64, _>(5).unwrap_or(0) as u32,
+                axe: row.try_get::<i64, _>(6).unwrap_or(0) as u32,
             };
 
             // Weapon Proficiency Experience
             let weapon_exp = WeaponProficiencyExp {
-                sword_xp: row.try_get(7).unwrap_or(0),
-                dagger_xp: row.try_get(8).unwrap_or(0),
-                staff_xp: row.try_get(9).unwrap_or(0),
+                sword_xp: row.try_get::<i64, _>(7).unwrap_or(0) as u32,
+                dagger_xp: row.try_get::<i64, _>(8).unwrap_or(0) as u32,
+                staff_xp: row.try_get::<i64, _>(9).unwrap_or(0) as u32,
                 wand_xp: 0, // Default wand XP (not in DB yet)
-                mace_xp: row.try_get(10).unwrap_or(0),
-                bow_xp: row.try_get(11).unwrap_or(0),
-                axe_xp: row.try_get(12).unwrap_or(0),
+                mace_xp: row.try_get::<i64, _>(10).unwrap_or(0) as u32,
+                bow_xp: row.try_get::<i64, _>(11).unwrap_or(0) as u32,
+                axe_xp: row.try_get::<i64, _>(12).unwrap_or(0) as u32,
             };
 
             // Armor Proficiency
             let armor_prof = ArmorProficiency {
-                light: row.try_get(13).unwrap_or(0),
-                medium: row.try_get(14).unwrap_or(0),
-                heavy: row.try_get(15).unwrap_or(0),
+                light: row.try_get::<i64, _>(13).unwrap_or(0) as u32,
+                medium: row.try_get::<i64, _>(14).unwrap_or(0) as u32,
+                heavy: row.try_get::<i64, _>(15).unwrap_or(0) as u32,
             };
 
             // Armor Proficiency Experience
             let armor_exp = ArmorProficiencyExp {
-                light_xp: row.try_get(16).unwrap_or(0),
-                medium_xp: row.try_get(17).unwrap_or(0),
-                heavy_xp: row.try_get(18).unwrap_or(0),
+                light_xp: row.try_get::<i64, _>(16).unwrap_or(0) as u32,
+                medium_xp: row.try_get::<i64, _>(17).unwrap_or(0) as u32,
+                heavy_xp: row.try_get::<i64, _>(18).unwrap_or(0) as u32,
             };
 
             // Unlocked Armor Passives
@@ -115,7 +116,7 @@ pub async fn load_progression(
 
 /// Save all progression data for a character
 pub async fn save_progression(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     character_level: u32,
     experience: &Experience,
@@ -130,37 +131,37 @@ pub async fn save_progression(
 
     let result = sqlx::query(
         "UPDATE characters SET
-            level = ?1,
-            current_xp = ?2,
-            weapon_prof_sword = ?3, weapon_prof_dagger = ?4, weapon_prof_staff = ?5,
-            weapon_prof_mace = ?6, weapon_prof_bow = ?7, weapon_prof_axe = ?8,
-            weapon_exp_sword = ?9, weapon_exp_dagger = ?10, weapon_exp_staff = ?11,
-            weapon_exp_mace = ?12, weapon_exp_bow = ?13, weapon_exp_axe = ?14,
-            armor_prof_light = ?15, armor_prof_medium = ?16, armor_prof_heavy = ?17,
-            armor_exp_light = ?18, armor_exp_medium = ?19, armor_exp_heavy = ?20,
-            unlocked_armor_passives = ?21
-         WHERE id = ?22"
+            level = $1,
+            current_xp = $2,
+            weapon_prof_sword = $3, weapon_prof_dagger = $4, weapon_prof_staff = $5,
+            weapon_prof_mace = $6, weapon_prof_bow = $7, weapon_prof_axe = $8,
+            weapon_exp_sword = $9, weapon_exp_dagger = $10, weapon_exp_staff = $11,
+            weapon_exp_mace = $12, weapon_exp_bow = $13, weapon_exp_axe = $14,
+            armor_prof_light = $15, armor_prof_medium = $16, armor_prof_heavy = $17,
+            armor_exp_light = $18, armor_exp_medium = $19, armor_exp_heavy = $20,
+            unlocked_armor_passives = $21
+         WHERE id = $22"
     )
     .bind(character_level as i32)
     .bind(experience.current_xp as i32)
-    .bind(weapon_prof.sword)
-    .bind(weapon_prof.dagger)
-    .bind(weapon_prof.staff)
-    .bind(weapon_prof.mace)
-    .bind(weapon_prof.bow)
-    .bind(weapon_prof.axe)
-    .bind(weapon_exp.sword_xp)
-    .bind(weapon_exp.dagger_xp)
-    .bind(weapon_exp.staff_xp)
-    .bind(weapon_exp.mace_xp)
-    .bind(weapon_exp.bow_xp)
-    .bind(weapon_exp.axe_xp)
-    .bind(armor_prof.light)
-    .bind(armor_prof.medium)
-    .bind(armor_prof.heavy)
-    .bind(armor_exp.light_xp)
-    .bind(armor_exp.medium_xp)
-    .bind(armor_exp.heavy_xp)
+    .bind(weapon_prof.sword as i64)
+    .bind(weapon_prof.dagger as i64)
+    .bind(weapon_prof.staff as i64)
+    .bind(weapon_prof.mace as i64)
+    .bind(weapon_prof.bow as i64)
+    .bind(weapon_prof.axe as i64)
+    .bind(weapon_exp.sword_xp as i64)
+    .bind(weapon_exp.dagger_xp as i64)
+    .bind(weapon_exp.staff_xp as i64)
+    .bind(weapon_exp.mace_xp as i64)
+    .bind(weapon_exp.bow_xp as i64)
+    .bind(weapon_exp.axe_xp as i64)
+    .bind(armor_prof.light as i64)
+    .bind(armor_prof.medium as i64)
+    .bind(armor_prof.heavy as i64)
+    .bind(armor_exp.light_xp as i64)
+    .bind(armor_exp.medium_xp as i64)
+    .bind(armor_exp.heavy_xp as i64)
     .bind(passives_json)
     .bind(character_id)
     .execute(pool)
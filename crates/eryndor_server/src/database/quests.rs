@@ -2,14 +2,15 @@
 //!
 //! Handles saving and loading quest progress.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use super::DbPool;
 use tracing::info;
 use eryndor_shared::*;
 
 /// Load quest log for a character
-pub async fn load_quest_log(pool: &SqlitePool, character_id: i64) -> Result<QuestLog, String> {
+pub async fn load_quest_log(pool: &DbPool, character_id: i64) -> Result<QuestLog, String> {
     let result = sqlx::query(
-        "SELECT quest_id, completed, progress FROM character_quests WHERE character_id = ?1"
+        "SELECT quest_id, completed, progress FROM character_quests WHERE character_id = $1"
     )
     .bind(character_id)
     .fetch_all(pool)
@@ -54,7 +55,7 @@ pub async fn load_quest_log(pool: &SqlitePool, character_id: i64) -> Result<Ques
 
 /// Save quest log for a character
 pub async fn save_quest_log(
-    pool: &SqlitePool,
+    pool: &DbPool,
     character_id: i64,
     quest_log: &QuestLog,
 ) -> Result<(), String> {
@@ -67,7 +68,7 @@ pub async fn save_quest_log(
     );
 
     // Delete all existing quests for this character
-    let delete_result = sqlx::query("DELETE FROM character_quests WHERE character_id = ?1")
+    let delete_result = sqlx::query("DELETE FROM character_quests WHERE character_id = $1")
         .bind(character_id)
         .execute(pool)
         .await;
@@ -83,7 +84,7 @@ pub async fn save_quest_log(
 
         let insert_result = sqlx::query(
             "INSERT INTO character_quests (character_id, quest_id, completed, progress)
-             VALUES (?1, ?2, 0, ?3)"
+             VALUES ($1, $2, 0, $3)"
         )
         .bind(character_id)
         .bind(active_quest.quest_id as i32)
@@ -103,7 +104,7 @@ pub async fn save_quest_log(
     for &quest_id in &quest_log.completed_quests {
         let insert_result = sqlx::query(
             "INSERT INTO character_quests (character_id, quest_id, completed, progress)
-             VALUES (?1, ?2, 1, NULL)"
+             VALUES ($1, $2, 1, NULL)"
         )
         .bind(character_id)
         .bind(quest_id as i32)
@@ -0,0 +1,89 @@
+//! Shard registry.
+//!
+//! Every server process keeps its own row in `shards` up to date on a heartbeat. The
+//! shard selector lists every row, treating shards that have gone quiet as offline.
+
+use sqlx::Row;
+use super::DbPool;
+use eryndor_shared::ShardInfo;
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Register a shard or refresh its entry
+pub async fn heartbeat(pool: &DbPool, shard: &ShardInfo) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO shards (id, name, address, udp_port, webtransport_port, websocket_port, cert_url, population, max_players, last_heartbeat)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (id) DO UPDATE SET
+            name = $2, address = $3, udp_port = $4, webtransport_port = $5, websocket_port = $6,
+            cert_url = $7, population = $8, max_players = $9, last_heartbeat = $10"
+    )
+    .bind(&shard.id)
+    .bind(&shard.name)
+    .bind(&shard.address)
+    .bind(i64::from(shard.udp_port))
+    .bind(i64::from(shard.webtransport_port))
+    .bind(i64::from(shard.websocket_port))
+    .bind(&shard.cert_url)
+    .bind(i64::from(shard.population))
+    .bind(i64::from(shard.max_players))
+    .bind(now())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update shard registry: {}", e))?;
+    Ok(())
+}
+
+/// Mark a shard offline straight away, for a clean shutdown
+pub async fn deregister(pool: &DbPool, shard_id: &str) -> Result<(), String> {
+    sqlx::query("UPDATE shards SET population = 0, last_heartbeat = 0 WHERE id = $1")
+        .bind(shard_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update shard registry: {}", e))?;
+    Ok(())
+}
+
+/// Every registered shard. Shards without a heartbeat in `stale_after` seconds are offline.
+pub async fn list_shards(pool: &DbPool, stale_after: i64) -> Result<Vec<ShardInfo>, String> {
+    let rows = sqlx::query(
+        "SELECT id, name, address, udp_port, webtransport_port, websocket_port, cert_url, population, max_players, last_heartbeat
+         FROM shards
+         ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list shards: {}", e))?;
+
+    let cutoff = now() - stale_after;
+    Ok(rows.iter().map(|row| {
+        let online = row.get::<i64, _>(9) > cutoff;
+        ShardInfo {
+            id: row.get(0),
+            name: row.get(1),
+            address: row.get(2),
+            udp_port: row.get::<i64, _>(3) as u16,
+            webtransport_port: row.get::<i64, _>(4) as u16,
+            websocket_port: row.get::<i64, _>(5) as u16,
+            cert_url: row.get(6),
+            population: if online { row.get::<i64, _>(7) as u32 } else { 0 },
+            max_players: row.get::<i64, _>(8) as u32,
+            online,
+        }
+    }).collect())
+}
+
+/// Shard a character was created on, if the character exists
+pub async fn character_shard(pool: &DbPool, character_id: i64) -> Result<Option<String>, String> {
+    let row = sqlx::query("SELECT shard_id FROM characters WHERE id = $1")
+        .bind(character_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up character shard: {}", e))?;
+    Ok(row.map(|row| row.get(0)))
+}
@@ -0,0 +1,156 @@
+//! Two-factor authentication persistence.
+//!
+//! An account has at most one TOTP secret. It is stored when enrollment starts and only
+//! takes effect once `enable_two_factor` records the first confirmed code. Backup codes
+//! are stored as argon2 hashes, the same as passwords, and each can be used once.
+
+use sqlx::Row;
+use super::DbPool;
+
+/// An account's TOTP enrollment
+pub struct TwoFactorRecord {
+    /// Base32 shared secret
+    pub secret: String,
+    /// False while enrollment is waiting for the first code
+    pub enabled: bool,
+    /// Last TOTP time step accepted, so a code can't be replayed
+    pub last_used_step: i64,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Load an account's TOTP enrollment, if it has started one
+pub async fn get_two_factor(pool: &DbPool, account_id: i64) -> Result<Option<TwoFactorRecord>, String> {
+    let row = sqlx::query("SELECT secret, enabled_at, last_used_step FROM account_two_factor WHERE account_id = $1")
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load two-factor settings: {}", e))?;
+
+    Ok(row.map(|row| TwoFactorRecord {
+        secret: row.get(0),
+        enabled: row.get::<Option<i64>, _>(1).is_some(),
+        last_used_step: row.get(2),
+    }))
+}
+
+/// Username of an account that logs in with a password. None for OAuth accounts, whose
+/// provider handles the second factor.
+pub async fn password_account_username(pool: &DbPool, account_id: i64) -> Result<Option<String>, String> {
+    let row = sqlx::query("SELECT username, oauth_provider FROM accounts WHERE id = $1")
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load account: {}", e))?
+        .ok_or_else(|| "Account not found".to_string())?;
+
+    let oauth_provider: Option<String> = row.get(1);
+    Ok(oauth_provider.is_none().then(|| row.get(0)))
+}
+
+/// Store a new, not yet enabled secret, replacing any unfinished enrollment
+pub async fn begin_enrollment(pool: &DbPool, account_id: i64, secret: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO account_two_factor (account_id, secret, enabled_at, last_used_step)
+         VALUES ($1, $2, NULL, 0)
+         ON CONFLICT (account_id) DO UPDATE SET secret = $2, enabled_at = NULL, last_used_step = 0"
+    )
+    .bind(account_id)
+    .bind(secret)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to store two-factor secret: {}", e))?;
+    Ok(())
+}
+
+/// Turn on two-factor for an account and replace its backup codes
+pub async fn enable_two_factor(
+    pool: &DbPool,
+    account_id: i64,
+    step: i64,
+    backup_code_hashes: &[String],
+) -> Result<(), String> {
+    let fail = |e: sqlx::Error| format!("Failed to enable two-factor: {}", e);
+
+    let mut tx = pool.begin().await.map_err(fail)?;
+    sqlx::query("UPDATE account_two_factor SET enabled_at = $1, last_used_step = $2 WHERE account_id = $3")
+        .bind(now())
+        .bind(step)
+        .bind(account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(fail)?;
+    sqlx::query("DELETE FROM account_backup_codes WHERE account_id = $1")
+        .bind(account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(fail)?;
+    for hash in backup_code_hashes {
+        sqlx::query("INSERT INTO account_backup_codes (account_id, code_hash) VALUES ($1, $2)")
+            .bind(account_id)
+            .bind(hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(fail)?;
+    }
+    tx.commit().await.map_err(fail)
+}
+
+/// Record `step` as used. Returns false if it (or a later step) was already used.
+pub async fn claim_totp_step(pool: &DbPool, account_id: i64, step: i64) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE account_two_factor SET last_used_step = $1 WHERE account_id = $2 AND last_used_step < $1"
+    )
+    .bind(step)
+    .bind(account_id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record two-factor code: {}", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hashes of an account's unused backup codes, as (code id, hash)
+pub async fn unused_backup_codes(pool: &DbPool, account_id: i64) -> Result<Vec<(i64, String)>, String> {
+    let rows = sqlx::query("SELECT id, code_hash FROM account_backup_codes WHERE account_id = $1 AND used_at IS NULL")
+        .bind(account_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load backup codes: {}", e))?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Mark a backup code used. Returns false if it was used in the meantime.
+pub async fn claim_backup_code(pool: &DbPool, code_id: i64) -> Result<bool, String> {
+    let result = sqlx::query("UPDATE account_backup_codes SET used_at = $1 WHERE id = $2 AND used_at IS NULL")
+        .bind(now())
+        .bind(code_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to use backup code: {}", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove an account's secret and backup codes. Returns false if it had none.
+pub async fn remove_two_factor(pool: &DbPool, account_id: i64) -> Result<bool, String> {
+    let fail = |e: sqlx::Error| format!("Failed to remove two-factor: {}", e);
+
+    let mut tx = pool.begin().await.map_err(fail)?;
+    sqlx::query("DELETE FROM account_backup_codes WHERE account_id = $1")
+        .bind(account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(fail)?;
+    let removed = sqlx::query("DELETE FROM account_two_factor WHERE account_id = $1")
+        .bind(account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(fail)?
+        .rows_affected();
+    tx.commit().await.map_err(fail)?;
+    Ok(removed > 0)
+}
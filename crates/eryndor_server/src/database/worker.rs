@@ -0,0 +1,136 @@
+//! Runs database work off the game loop.
+//!
+//! Systems and observers never wait on the database. They queue a job through
+//! `DatabaseConnection::run`, a single worker task executes jobs in the order they were
+//! queued (so a character's save always lands before a later load of the same character),
+//! and each result comes back over a channel as a callback that `apply_results` runs
+//! against the world at the start of the next frame.
+
+use bevy::prelude::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use tokio::sync::{mpsc as tokio_mpsc, oneshot};
+use super::DbPool;
+
+type JobFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type Job = Box<dyn FnOnce(DbPool) -> JobFuture + Send>;
+pub(super) type Callback = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Queue of database jobs and the channel their results come back on
+pub(super) struct DbWorker {
+    runtime: tokio::runtime::Runtime,
+    jobs: tokio_mpsc::UnboundedSender<Job>,
+    results: mpsc::Sender<Callback>,
+    completed: Mutex<mpsc::Receiver<Callback>>,
+}
+
+impl DbWorker {
+    /// Start the worker task for `pool` on `runtime`
+    pub(super) fn start(runtime: tokio::runtime::Runtime, pool: DbPool) -> Self {
+        let (jobs, job_queue) = tokio_mpsc::unbounded_channel();
+        let (results, completed) = mpsc::channel();
+        runtime.spawn(run_jobs(pool, job_queue));
+        Self { runtime, jobs, results, completed: Mutex::new(completed) }
+    }
+
+    pub(super) fn run<T, Q, Fut, C>(&self, query: Q, then: C) -> bool
+    where
+        T: Send + 'static,
+        Q: FnOnce(DbPool) -> Fut + Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        C: FnOnce(T, &mut World) + Send + 'static,
+    {
+        let results = self.results.clone();
+        let job: Job = Box::new(move |pool| -> JobFuture {
+            Box::pin(async move {
+                let output = query(pool).await;
+                let _ = results.send(Box::new(move |world: &mut World| then(output, world)));
+            })
+        });
+        self.jobs.send(job).is_ok()
+    }
+
+    pub(super) fn spawn<T, Fut, C>(&self, future: Fut, then: C)
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        C: FnOnce(T, &mut World) + Send + 'static,
+    {
+        let results = self.results.clone();
+        self.runtime.spawn(async move {
+            let output = future.await;
+            let _ = results.send(Box::new(move |world: &mut World| then(output, world)));
+        });
+    }
+
+    pub(super) fn flush(&self) {
+        let (done, finished) = oneshot::channel();
+        let barrier: Job = Box::new(move |_| -> JobFuture {
+            Box::pin(async move {
+                let _ = done.send(());
+            })
+        });
+        if self.jobs.send(barrier).is_ok() {
+            let _ = finished.blocking_recv();
+        }
+    }
+
+    /// Callbacks for every job that has finished since the last call
+    pub(super) fn take_completed(&self) -> Vec<Callback> {
+        self.completed.lock()
+            .map(|completed| completed.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Run queued jobs one at a time until the server shuts down
+async fn run_jobs(pool: DbPool, mut job_queue: tokio_mpsc::UnboundedReceiver<Job>) {
+    while let Some(job) = job_queue.recv().await {
+        // A separate task so a panicking query can't take the worker down with it
+        if let Err(e) = tokio::spawn(job(pool.clone())).await {
+            error!("Database job failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{apply_results, connect, DatabaseConnection};
+
+    #[derive(Resource, Default)]
+    struct Finished(Vec<u32>);
+
+    #[test]
+    fn results_come_back_in_queue_order() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let (pool, backend) = runtime.block_on(connect("sqlite::memory:")).unwrap();
+        assert_eq!(backend.name(), "sqlite");
+
+        let mut world = World::new();
+        world.init_resource::<Finished>();
+        world.insert_resource(DatabaseConnection {
+            pool: Some(pool.clone()),
+            backend,
+            worker: Some(DbWorker::start(runtime, pool)),
+        });
+
+        let db = world.resource::<DatabaseConnection>();
+        for n in 0..3u32 {
+            // Earlier jobs take longer, so only the queue keeps them in order
+            db.run(
+                move |pool| async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(u64::from(3 - n) * 10)).await;
+                    sqlx::query("SELECT 1").execute(&pool).await.map(|_| n)
+                },
+                |result, world| world.resource_mut::<Finished>().0.push(result.unwrap()),
+            );
+        }
+        db.flush();
+
+        apply_results(&mut world);
+        assert_eq!(world.resource::<Finished>().0, vec![0, 1, 2]);
+    }
+}
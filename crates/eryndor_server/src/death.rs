@@ -0,0 +1,157 @@
+//! Player death, corpse runs and resurrection.
+//!
+//! A player who dies leaves a `Corpse` where they fell and stays `Dead` until they release
+//! their spirit. Released players become a `Ghost` at the nearest spirit healer, the zone's
+//! graveyard, and either run back to resurrect at their corpse or let the spirit healer
+//! bring them back on the spot with resurrection sickness. Resurrect abilities revive a
+//! fallen player at their corpse without the sickness.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use crate::abilities::add_debuff;
+use crate::auth::ActiveCharacterEntity;
+use crate::{PhysicsPosition, PhysicsVelocity};
+
+/// Fraction of max health and mana restored when resurrecting at the corpse
+const CORPSE_RESURRECT_FRACTION: f32 = 0.5;
+/// Fraction of max health and mana restored by a spirit healer
+const SPIRIT_HEALER_RESURRECT_FRACTION: f32 = 0.25;
+/// Penalty for skipping the corpse run
+const RESURRECTION_SICKNESS: DebuffType = DebuffType::Weaken { attack_reduction: 0.5 };
+const RESURRECTION_SICKNESS_SECONDS: f32 = 120.0;
+
+/// NPC that marks a graveyard: ghosts appear next to it and can be resurrected by it
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SpiritHealer;
+
+/// Server-side link from a corpse to the player it belongs to
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CorpseOf(pub Entity);
+
+/// Leave a corpse where the player fell and hold them in place until they release
+pub fn kill_player(commands: &mut Commands, player: Entity, position: Vec2, name: &str) {
+    commands.spawn((
+        Replicated,
+        Corpse { owner_name: name.to_string() },
+        CorpseOf(player),
+        Position(position),
+        Interactable::corpse(),
+        VisualShape {
+            shape_type: ShapeType::Square,
+            color: COLOR_CORPSE,
+            size: PLAYER_SIZE,
+        },
+    ));
+
+    commands.entity(player)
+        .insert((Dead, Velocity(Vec2::ZERO), PhysicsVelocity(Vec2::ZERO)))
+        .remove::<(ActiveDoTs, ActiveManaShield)>();
+    info!("{} died at {:?}", name, position);
+}
+
+/// Bring a fallen player back at `position` with a fraction of their health and mana,
+/// clearing their corpse
+pub fn resurrect(commands: &mut Commands, player: Entity, position: Vec2, fraction: f32) {
+    commands.entity(player).queue(move |mut entity: EntityWorldMut| {
+        entity.remove::<(Dead, Ghost)>();
+        entity.insert((Position(position), PhysicsPosition(position)));
+        if let Some(mut health) = entity.get_mut::<Health>() {
+            health.current = health.max * fraction;
+        }
+        if let Some(mut mana) = entity.get_mut::<Mana>() {
+            mana.current = mana.max * fraction;
+        }
+
+        entity.world_scope(|world| despawn_corpses(world, player));
+    });
+    info!("Resurrected {:?} at {:?}", player, position);
+}
+
+/// Clear a player's corpse when they leave the world, since nothing could claim it afterwards
+pub fn remove_corpses(commands: &mut Commands, player: Entity) {
+    commands.queue(move |world: &mut World| despawn_corpses(world, player));
+}
+
+fn despawn_corpses(world: &mut World, player: Entity) {
+    let corpses: Vec<Entity> = world.query::<(Entity, &CorpseOf)>()
+        .iter(world)
+        .filter(|(_, owner)| owner.0 == player)
+        .map(|(corpse, _)| corpse)
+        .collect();
+    for corpse in corpses {
+        world.despawn(corpse);
+    }
+}
+
+/// Spirit healer resurrection: immediate, but weak and sick for a while
+pub fn resurrect_at_spirit_healer(commands: &mut Commands, player: Entity, position: Vec2, now: f32) {
+    resurrect(commands, player, position, SPIRIT_HEALER_RESURRECT_FRACTION);
+    commands.entity(player).queue(move |mut entity: EntityWorldMut| {
+        add_debuff(&mut entity, 0, RESURRECTION_SICKNESS, RESURRECTION_SICKNESS_SECONDS, now);
+    });
+}
+
+/// Release a dead player's spirit to the nearest graveyard
+pub fn handle_release_spirit(
+    trigger: On<FromClient<ReleaseSpiritRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    dead_players: Query<&Position, With<Dead>>,
+    spirit_healers: Query<&Position, With<SpiritHealer>>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+
+    let Ok(corpse_position) = dead_players.get(char_entity).map(|position| position.0) else {
+        warn!("Player {:?} tried to release without being dead", char_entity);
+        return;
+    };
+
+    // Ghosts appear beside the closest spirit healer, or at the world spawn if the zone has none
+    let graveyard = spirit_healers.iter()
+        .map(|position| position.0)
+        .min_by(|a, b| a.distance(corpse_position).total_cmp(&b.distance(corpse_position)))
+        .map(|healer| healer + Vec2::new(0.0, -INTERACTION_RANGE / 2.0))
+        .unwrap_or(SPAWN_POINT);
+
+    commands.entity(char_entity)
+        .remove::<Dead>()
+        .insert((Ghost { corpse_position }, Position(graveyard), PhysicsPosition(graveyard)));
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: NotificationEvent {
+            message: "You are a ghost. Return to your corpse, or speak to the spirit healer.".to_string(),
+            notification_type: NotificationType::Info,
+        },
+    });
+    info!("Player {:?} released their spirit to {:?}", char_entity, graveyard);
+}
+
+/// Resurrect a ghost who made it back to their corpse
+pub fn handle_resurrect_at_corpse(
+    trigger: On<FromClient<ResurrectAtCorpseRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    ghosts: Query<(&Position, &Ghost)>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+
+    let Ok((position, ghost)) = ghosts.get(char_entity) else { return };
+    if position.0.distance(ghost.corpse_position) > CORPSE_RESURRECT_RANGE {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message: "You are too far from your corpse!".to_string(),
+                notification_type: NotificationType::Warning,
+            },
+        });
+        return;
+    }
+
+    resurrect(&mut commands, char_entity, ghost.corpse_position, CORPSE_RESURRECT_FRACTION);
+}
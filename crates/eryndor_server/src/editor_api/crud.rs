@@ -1,7 +1,7 @@
 //! Generic CRUD handlers for content types.
 //!
 //! This module provides generic handlers that work for any JSON content type
-//! (items, enemies, NPCs, quests, abilities, loot tables) by parameterizing
+//! (items, enemies, NPCs, quests, abilities, loot tables, dialogues, shops, formulas, behaviors) by parameterizing
 //! the directory name, file extension, and display name.
 
 use axum::{
@@ -65,6 +65,30 @@ pub mod configs {
         extension: "loot",
         display_name: "Loot table",
     };
+
+    pub const DIALOGUE: ContentConfig = ContentConfig {
+        directory: "dialogues",
+        extension: "dialogue",
+        display_name: "Dialogue",
+    };
+
+    pub const SHOP: ContentConfig = ContentConfig {
+        directory: "shops",
+        extension: "shop",
+        display_name: "Shop",
+    };
+
+    pub const FORMULAS: ContentConfig = ContentConfig {
+        directory: "formulas",
+        extension: "formulas",
+        display_name: "Combat formulas",
+    };
+
+    pub const BEHAVIOR: ContentConfig = ContentConfig {
+        directory: "behaviors",
+        extension: "behavior",
+        display_name: "Behavior tree",
+    };
 }
 
 /// Convert a name to a filesystem-safe slug (lowercase, spaces to underscores)
@@ -576,3 +600,151 @@ pub async fn delete_loot_table(
 ) -> impl IntoResponse {
     delete(&state, &id, &configs::LOOT_TABLE).await
 }
+
+// Dialogues
+pub async fn list_dialogues(
+    State(state): State<EditorApiState>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    list(&state, &query, &configs::DIALOGUE).await
+}
+
+pub async fn get_dialogue(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    get(&state, &id, &configs::DIALOGUE).await
+}
+
+pub async fn create_dialogue(
+    State(state): State<EditorApiState>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    create(&state, data, &configs::DIALOGUE).await
+}
+
+pub async fn update_dialogue(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    update(&state, &id, data, &configs::DIALOGUE).await
+}
+
+pub async fn delete_dialogue(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    delete(&state, &id, &configs::DIALOGUE).await
+}
+
+// Shops
+pub async fn list_shops(
+    State(state): State<EditorApiState>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    list(&state, &query, &configs::SHOP).await
+}
+
+pub async fn get_shop(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    get(&state, &id, &configs::SHOP).await
+}
+
+pub async fn create_shop(
+    State(state): State<EditorApiState>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    create(&state, data, &configs::SHOP).await
+}
+
+pub async fn update_shop(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    update(&state, &id, data, &configs::SHOP).await
+}
+
+pub async fn delete_shop(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    delete(&state, &id, &configs::SHOP).await
+}
+
+// Combat formulas
+pub async fn list_formulas(
+    State(state): State<EditorApiState>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    list(&state, &query, &configs::FORMULAS).await
+}
+
+pub async fn get_formulas(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    get(&state, &id, &configs::FORMULAS).await
+}
+
+pub async fn create_formulas(
+    State(state): State<EditorApiState>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    create(&state, data, &configs::FORMULAS).await
+}
+
+pub async fn update_formulas(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    update(&state, &id, data, &configs::FORMULAS).await
+}
+
+pub async fn delete_formulas(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    delete(&state, &id, &configs::FORMULAS).await
+}
+
+// Behavior trees
+pub async fn list_behaviors(
+    State(state): State<EditorApiState>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    list(&state, &query, &configs::BEHAVIOR).await
+}
+
+pub async fn get_behavior(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    get(&state, &id, &configs::BEHAVIOR).await
+}
+
+pub async fn create_behavior(
+    State(state): State<EditorApiState>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    create(&state, data, &configs::BEHAVIOR).await
+}
+
+pub async fn update_behavior(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(data): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    update(&state, &id, data, &configs::BEHAVIOR).await
+}
+
+pub async fn delete_behavior(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    delete(&state, &id, &configs::BEHAVIOR).await
+}
@@ -1,7 +1,8 @@
 //! Editor API - HTTP endpoints for the game content editor.
 //!
 //! Provides CRUD operations for zones, items, enemies, NPCs, quests, abilities,
-//! loot tables, and assets.
+//! loot tables, dialogues, shops, combat formulas, behavior trees, and assets,
+//! plus a cross-check of all content (`GET /validate`).
 //!
 //! ## Module Structure
 //! - `crud` - Generic CRUD handlers for content types with id/name-based file storage
@@ -97,6 +98,7 @@ pub fn create_editor_router() -> Router {
     Router::new()
         // Health check
         .route("/health", get(health_check))
+        .route("/validate", get(validate_content))
         // Zones (special handling for tilemap)
         .route("/zones", get(list_zones))
         .route("/zones", post(create_zone))
@@ -105,6 +107,10 @@ pub fn create_editor_router() -> Router {
         .route("/zones/:id", delete(delete_zone))
         .route("/zones/:id/tilemap", get(get_zone_tilemap))
         .route("/zones/:id/tilemap", put(update_zone_tilemap))
+        .route("/zones/:id/patrol_paths", get(get_zone_patrol_paths))
+        .route("/zones/:id/patrol_paths", put(update_zone_patrol_paths))
+        .route("/zones/:id/weather", get(get_zone_weather))
+        .route("/zones/:id/weather", put(update_zone_weather))
         // Items (generic CRUD)
         .route("/items", get(crud::list_items))
         .route("/items", post(crud::create_item))
@@ -141,6 +147,30 @@ pub fn create_editor_router() -> Router {
         .route("/loot-tables/:id", get(crud::get_loot_table))
         .route("/loot-tables/:id", put(crud::update_loot_table))
         .route("/loot-tables/:id", delete(crud::delete_loot_table))
+        // Dialogues (generic CRUD)
+        .route("/dialogues", get(crud::list_dialogues))
+        .route("/dialogues", post(crud::create_dialogue))
+        .route("/dialogues/:id", get(crud::get_dialogue))
+        .route("/dialogues/:id", put(crud::update_dialogue))
+        .route("/dialogues/:id", delete(crud::delete_dialogue))
+        // Shops (generic CRUD)
+        .route("/shops", get(crud::list_shops))
+        .route("/shops", post(crud::create_shop))
+        .route("/shops/:id", get(crud::get_shop))
+        .route("/shops/:id", put(crud::update_shop))
+        .route("/shops/:id", delete(crud::delete_shop))
+        // Combat formulas (generic CRUD)
+        .route("/formulas", get(crud::list_formulas))
+        .route("/formulas", post(crud::create_formulas))
+        .route("/formulas/:id", get(crud::get_formulas))
+        .route("/formulas/:id", put(crud::update_formulas))
+        .route("/formulas/:id", delete(crud::delete_formulas))
+        // Behavior trees (generic CRUD)
+        .route("/behaviors", get(crud::list_behaviors))
+        .route("/behaviors", post(crud::create_behavior))
+        .route("/behaviors/:id", get(crud::get_behavior))
+        .route("/behaviors/:id", put(crud::update_behavior))
+        .route("/behaviors/:id", delete(crud::delete_behavior))
         // Assets
         .route("/assets", get(list_assets))
         .route("/assets/upload", post(upload_asset))
@@ -160,6 +190,17 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+// =============================================================================
+// Validation
+// =============================================================================
+
+/// Every problem the validation pass finds in the content on disk; an empty list means clean
+async fn validate_content(State(state): State<EditorApiState>) -> impl IntoResponse {
+    let issues = crate::validation::validate_content(&state.content_path);
+    info!("Validated content: {} problems", issues.len());
+    ApiResponse::success(issues)
+}
+
 // =============================================================================
 // Zone Handlers (special case - has tilemap sub-resource)
 // =============================================================================
@@ -435,6 +476,222 @@ async fn update_zone_tilemap(
     }
 }
 
+async fn get_zone_patrol_paths(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let zone_path = state
+        .content_path
+        .join("zones")
+        .join(format!("{}.zone.json", id));
+
+    match std::fs::read_to_string(&zone_path) {
+        Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(zone_data) => {
+                let paths = zone_data.get("patrol_paths").cloned().unwrap_or_else(|| serde_json::json!([]));
+                (StatusCode::OK, ApiResponse::success(paths))
+            }
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::error(format!("Failed to parse zone: {}", e)),
+            ),
+        },
+        Err(e) => (
+            StatusCode::NOT_FOUND,
+            ApiResponse::error(format!("Zone not found: {}", e)),
+        ),
+    }
+}
+
+async fn update_zone_patrol_paths(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(paths): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let zone_path = state
+        .content_path
+        .join("zones")
+        .join(format!("{}.zone.json", id));
+
+    // Reject paths the server couldn't load
+    match serde_json::from_value::<Vec<crate::game_data::PatrolPathDef>>(paths.clone()) {
+        Ok(parsed) => {
+            if let Some(path) = parsed.iter().find(|path| path.waypoints.len() < 2) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ApiResponse::<serde_json::Value>::error(format!("Patrol path '{}' needs at least two waypoints", path.path_id)),
+                );
+            }
+        }
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::error(format!("Invalid patrol paths: {}", e)),
+            )
+        }
+    }
+
+    let zone_content = match std::fs::read_to_string(&zone_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                ApiResponse::<serde_json::Value>::error(format!("Zone not found: {}", e)),
+            )
+        }
+    };
+
+    let mut zone_data: serde_json::Value = match serde_json::from_str(&zone_content) {
+        Ok(data) => data,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::error(format!("Failed to parse zone: {}", e)),
+            )
+        }
+    };
+
+    if let Some(obj) = zone_data.as_object_mut() {
+        obj.insert("patrol_paths".to_string(), paths.clone());
+    } else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiResponse::error("Zone data is not an object"),
+        );
+    }
+
+    match serde_json::to_string_pretty(&zone_data) {
+        Ok(content) => {
+            if let Err(e) = std::fs::write(&zone_path, content) {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiResponse::error(format!("Failed to write zone: {}", e)),
+                );
+            }
+            info!("Updated patrol paths for zone: {}", id);
+            (StatusCode::OK, ApiResponse::success(paths))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiResponse::error(format!("Failed to serialize zone: {}", e)),
+        ),
+    }
+}
+
+async fn get_zone_weather(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let zone_path = state
+        .content_path
+        .join("zones")
+        .join(format!("{}.zone.json", id));
+
+    match std::fs::read_to_string(&zone_path) {
+        Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(zone_data) => {
+                let weather = zone_data.get("weather").cloned().unwrap_or(serde_json::Value::Null);
+                (StatusCode::OK, ApiResponse::success(weather))
+            }
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::error(format!("Failed to parse zone: {}", e)),
+            ),
+        },
+        Err(e) => (
+            StatusCode::NOT_FOUND,
+            ApiResponse::error(format!("Zone not found: {}", e)),
+        ),
+    }
+}
+
+/// Replace a zone's weather table; `null` removes it so the zone stays clear
+async fn update_zone_weather(
+    State(state): State<EditorApiState>,
+    Path(id): Path<String>,
+    Json(weather): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let zone_path = state
+        .content_path
+        .join("zones")
+        .join(format!("{}.zone.json", id));
+
+    // Reject tables the server couldn't use
+    match serde_json::from_value::<Option<crate::game_data::WeatherTable>>(weather.clone()) {
+        Ok(Some(table)) => {
+            let problem = if table.entries.is_empty() {
+                Some("Weather table needs at least one entry".to_string())
+            } else if table.entries.iter().any(|entry| entry.weight < 0.0) {
+                Some("Weather weights can't be negative".to_string())
+            } else if table.min_duration <= 0.0 || table.max_duration < table.min_duration {
+                Some("Weather durations must be positive with min_duration <= max_duration".to_string())
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                return (StatusCode::BAD_REQUEST, ApiResponse::<serde_json::Value>::error(problem));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::error(format!("Invalid weather table: {}", e)),
+            )
+        }
+    }
+
+    let zone_content = match std::fs::read_to_string(&zone_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                ApiResponse::<serde_json::Value>::error(format!("Zone not found: {}", e)),
+            )
+        }
+    };
+
+    let mut zone_data: serde_json::Value = match serde_json::from_str(&zone_content) {
+        Ok(data) => data,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::error(format!("Failed to parse zone: {}", e)),
+            )
+        }
+    };
+
+    if let Some(obj) = zone_data.as_object_mut() {
+        if weather.is_null() {
+            obj.remove("weather");
+        } else {
+            obj.insert("weather".to_string(), weather.clone());
+        }
+    } else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiResponse::error("Zone data is not an object"),
+        );
+    }
+
+    match serde_json::to_string_pretty(&zone_data) {
+        Ok(content) => {
+            if let Err(e) = std::fs::write(&zone_path, content) {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiResponse::error(format!("Failed to write zone: {}", e)),
+                );
+            }
+            info!("Updated weather table for zone: {}", id);
+            (StatusCode::OK, ApiResponse::success(weather))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiResponse::error(format!("Failed to serialize zone: {}", e)),
+        ),
+    }
+}
+
 // =============================================================================
 // Asset Handlers (stubs - different pattern from content CRUD)
 // =============================================================================
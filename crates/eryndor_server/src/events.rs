@@ -0,0 +1,444 @@
+//! Scheduled world events.
+//!
+//! Events are listed in `events.toml` (or the file named by `EVENTS_PATH`), each with a
+//! cron-style schedule in UTC and a duration. When a schedule fires the event is announced
+//! to every player and started: a world boss spawns, kill XP is multiplied, or invasion waves
+//! start arriving. When the duration runs out (or the boss dies) the event is announced as
+//! over and anything it spawned is despawned.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::Deserialize;
+use eryndor_shared::*;
+use crate::game_data::EnemyDatabase;
+use crate::spawn::{EnemyTemplate, EntityTemplate};
+
+/// Most minutes replayed at once if the server stalls, so a long hitch can't flood the schedule
+const MAX_CATCH_UP_MINUTES: i64 = 60;
+
+/// Cron-style schedule: minute, hour, day of month, month, day of week (0 or 7 = Sunday).
+/// Fields accept `*`, numbers, ranges (`1-5`), lists (`0,30`) and steps (`*/15`, `8-20/2`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether day of month / day of week were restricted (cron matches either when both are)
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!("schedule '{}' must have 5 fields (minute hour day month weekday)", spec));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 is Sunday as well
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: *day != "*",
+            weekdays_restricted: *weekday != "*",
+        })
+    }
+
+    pub fn matches(&self, time: &DateTime<Utc>) -> bool {
+        let bit = |mask: u64, value: u32| mask & (1 << value) != 0;
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        let day_matches = match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        };
+
+        bit(self.minutes, time.minute())
+            && bit(self.hours, time.hour())
+            && bit(self.months, time.month())
+            && day_matches
+    }
+}
+
+/// Parse one cron field into a bitmask of allowed values
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step in '{}'", part))?;
+                if step == 0 {
+                    return Err(format!("step can't be zero in '{}'", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = start.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            let end = end.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            (start, end)
+        } else {
+            let value = range.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            // `5/10` means "from 5 every 10"
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// What an event does while it runs
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduledEventKind {
+    /// A single tough enemy; the event ends early when it dies
+    WorldBoss {
+        enemy_id: u32,
+        position: [f32; 2],
+        #[serde(default = "default_boss_level")]
+        level: u32,
+        #[serde(default = "default_health_multiplier")]
+        health_multiplier: f32,
+    },
+    /// Multiplies XP from kills
+    DoubleXp {
+        #[serde(default = "default_xp_multiplier")]
+        multiplier: f32,
+    },
+    /// Waves of enemies spread around a point
+    Invasion {
+        enemy_id: u32,
+        position: [f32; 2],
+        #[serde(default = "default_invasion_level")]
+        level: u32,
+        waves: u32,
+        enemies_per_wave: u32,
+        wave_interval_seconds: f32,
+        #[serde(default = "default_spread")]
+        spread: f32,
+    },
+}
+
+fn default_boss_level() -> u32 { 10 }
+fn default_health_multiplier() -> f32 { 5.0 }
+fn default_xp_multiplier() -> f32 { 2.0 }
+fn default_invasion_level() -> u32 { 1 }
+fn default_spread() -> f32 { 120.0 }
+
+/// One `[[event]]` entry in the schedule file
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduledEventConfig {
+    pub name: String,
+    pub schedule: String,
+    pub duration_minutes: u32,
+    /// Announcement when the event starts; a default built from the name is used otherwise
+    #[serde(default)]
+    pub announcement: Option<String>,
+    #[serde(flatten)]
+    pub kind: ScheduledEventKind,
+}
+
+#[derive(Deserialize)]
+struct EventScheduleFile {
+    #[serde(default, rename = "event")]
+    events: Vec<ScheduledEventConfig>,
+}
+
+/// Marks enemies spawned by an event so they're cleaned up when it ends
+#[derive(Component, Clone, Debug)]
+pub struct EventSpawned {
+    pub event: String,
+}
+
+/// Multiplier applied to kill XP (raised by double-XP events)
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XpMultiplier(pub f32);
+
+impl Default for XpMultiplier {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+struct ScheduledEvent {
+    config: ScheduledEventConfig,
+    schedule: CronSchedule,
+}
+
+struct ActiveEvent {
+    index: usize,
+    ends_at: DateTime<Utc>,
+    waves_spawned: u32,
+    next_wave_at: DateTime<Utc>,
+    /// Set once the event's spawns exist, so a world boss can be checked for defeat
+    spawned: bool,
+}
+
+/// The configured events and the ones currently running
+#[derive(Resource, Default)]
+pub struct EventScheduler {
+    events: Vec<ScheduledEvent>,
+    active: Vec<ActiveEvent>,
+    /// Last whole UTC minute the schedule was checked for
+    last_minute: Option<i64>,
+}
+
+impl EventScheduler {
+    /// Load the event schedule. A missing file means no scheduled events.
+    pub fn load() -> Result<Self, String> {
+        let path = std::env::var("EVENTS_PATH").unwrap_or_else(|_| "events.toml".to_string());
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("No event schedule at '{}', scheduled events disabled", path);
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(format!("Failed to read event schedule '{}': {}", path, e)),
+        };
+
+        let file: EventScheduleFile = toml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse event schedule '{}': {}", path, e))?;
+
+        let events = file.events.into_iter()
+            .map(|config| {
+                let schedule = CronSchedule::parse(&config.schedule)
+                    .map_err(|e| format!("Event '{}': {}", config.name, e))?;
+                Ok(ScheduledEvent { config, schedule })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        info!("Loaded {} scheduled events from '{}'", events.len(), path);
+        Ok(Self { events, active: Vec::new(), last_minute: None })
+    }
+
+    fn is_active(&self, index: usize) -> bool {
+        self.active.iter().any(|active| active.index == index)
+    }
+}
+
+fn announce(commands: &mut Commands, message: String) {
+    info!("[EVENT] {}", message);
+    commands.server_trigger(ToClients {
+        mode: SendMode::Broadcast,
+        message: NotificationEvent { message, notification_type: NotificationType::Info },
+    });
+}
+
+fn spawn_event_enemy(commands: &mut Commands, enemy_db: &EnemyDatabase, event: &str, enemy_id: u32, level: u32, health_multiplier: f32, position: Vec2) {
+    let Some(definition) = enemy_db.enemies.get(&enemy_id) else {
+        warn!("Event '{}' references unknown enemy {}", event, enemy_id);
+        return;
+    };
+    let mut template = EnemyTemplate::from_definition(definition, level);
+    template.health *= health_multiplier;
+    let entity = EntityTemplate::Enemy(template).spawn(commands, position);
+    commands.entity(entity).insert(EventSpawned { event: event.to_string() });
+}
+
+/// Start events whose schedule fired, run invasion waves and end finished events
+pub fn run_scheduled_events(
+    mut commands: Commands,
+    mut scheduler: ResMut<EventScheduler>,
+    mut xp_multiplier: ResMut<XpMultiplier>,
+    enemy_db: Res<EnemyDatabase>,
+    spawned: Query<(Entity, &EventSpawned)>,
+) {
+    if scheduler.events.is_empty() {
+        return;
+    }
+    let now = Utc::now();
+    let minute = now.timestamp().div_euclid(60);
+
+    // Find events due to start. On startup, pick up events that should already be running.
+    let mut starting: Vec<(usize, DateTime<Utc>)> = Vec::new();
+    match scheduler.last_minute {
+        None => {
+            for (index, event) in scheduler.events.iter().enumerate() {
+                let fired = (0..event.config.duration_minutes as i64)
+                    .map(|ago| DateTime::from_timestamp((minute - ago) * 60, 0).unwrap_or(now))
+                    .find(|time| event.schedule.matches(time));
+                if let Some(fired) = fired {
+                    starting.push((index, fired));
+                }
+            }
+        }
+        Some(last) if minute > last => {
+            for checked in (last + 1).max(minute - MAX_CATCH_UP_MINUTES + 1)..=minute {
+                let time = DateTime::from_timestamp(checked * 60, 0).unwrap_or(now);
+                for (index, event) in scheduler.events.iter().enumerate() {
+                    if event.schedule.matches(&time) {
+                        starting.push((index, time));
+                    }
+                }
+            }
+        }
+        Some(_) => {}
+    }
+    scheduler.last_minute = Some(minute);
+
+    for (index, fired) in starting {
+        if scheduler.is_active(index) {
+            continue;
+        }
+        let config = scheduler.events[index].config.clone();
+        let ends_at = fired + Duration::minutes(config.duration_minutes as i64);
+        if ends_at <= now {
+            continue;
+        }
+
+        let announcement = config.announcement.clone().unwrap_or_else(|| match &config.kind {
+            ScheduledEventKind::WorldBoss { .. } => format!("{} has appeared!", config.name),
+            ScheduledEventKind::DoubleXp { multiplier } => format!("{} has begun: {:.0}x experience from kills!", config.name, multiplier),
+            ScheduledEventKind::Invasion { .. } => format!("{} is under way! Defend the realm!", config.name),
+        });
+        announce(&mut commands, announcement);
+
+        if let ScheduledEventKind::WorldBoss { enemy_id, position, level, health_multiplier } = &config.kind {
+            spawn_event_enemy(&mut commands, &enemy_db, &config.name, *enemy_id, *level, *health_multiplier, Vec2::from(*position));
+        }
+        scheduler.active.push(ActiveEvent {
+            index,
+            ends_at,
+            waves_spawned: 0,
+            next_wave_at: now,
+            spawned: false,
+        });
+    }
+
+    // Invasion waves and world boss defeats
+    let mut ended: Vec<(usize, bool)> = Vec::new();
+    let EventScheduler { events, active, .. } = &mut *scheduler;
+    for (slot, event) in active.iter_mut().enumerate() {
+        let config = &events[event.index].config;
+        let remaining = spawned.iter().filter(|(_, spawned)| spawned.event == config.name).count();
+
+        match &config.kind {
+            ScheduledEventKind::WorldBoss { .. } => {
+                if event.spawned && remaining == 0 {
+                    ended.push((slot, true));
+                    continue;
+                }
+                // The boss exists from the frame after the event started
+                event.spawned = true;
+            }
+            ScheduledEventKind::Invasion { enemy_id, position, level, waves, enemies_per_wave, wave_interval_seconds, spread } => {
+                if event.waves_spawned < *waves && now >= event.next_wave_at {
+                    event.waves_spawned += 1;
+                    event.next_wave_at = now + Duration::milliseconds((*wave_interval_seconds * 1000.0) as i64);
+                    announce(&mut commands, format!("{}: wave {} of {} approaches!", config.name, event.waves_spawned, waves));
+
+                    for i in 0..*enemies_per_wave {
+                        // Spread the wave evenly around the invasion point
+                        let angle = std::f32::consts::TAU * i as f32 / *enemies_per_wave as f32;
+                        let offset = Vec2::from_angle(angle) * *spread;
+                        spawn_event_enemy(&mut commands, &enemy_db, &config.name, *enemy_id, *level, 1.0, Vec2::from(*position) + offset);
+                    }
+                }
+                // Repelled once every wave has arrived and been cleared
+                if event.waves_spawned == *waves && event.spawned && remaining == 0 {
+                    ended.push((slot, true));
+                    continue;
+                }
+                event.spawned = event.waves_spawned == *waves;
+            }
+            ScheduledEventKind::DoubleXp { .. } => {}
+        }
+
+        if now >= event.ends_at {
+            ended.push((slot, false));
+        }
+    }
+
+    // End events, latest slot first so indices stay valid
+    for (slot, completed) in ended.into_iter().rev() {
+        let event = scheduler.active.remove(slot);
+        let config = &scheduler.events[event.index].config;
+
+        let message = match (&config.kind, completed) {
+            (ScheduledEventKind::WorldBoss { .. }, true) => format!("{} has been defeated!", config.name),
+            (ScheduledEventKind::Invasion { .. }, true) => format!("{} has been repelled!", config.name),
+            (ScheduledEventKind::WorldBoss { .. }, false) => format!("{} has vanished...", config.name),
+            _ => format!("{} has ended.", config.name),
+        };
+        announce(&mut commands, message);
+
+        for (entity, _) in spawned.iter().filter(|(_, spawned)| spawned.event == config.name) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    // Running double-XP events set the multiplier (the best one wins)
+    let multiplier = scheduler.active.iter()
+        .filter_map(|active| match scheduler.events[active.index].config.kind {
+            ScheduledEventKind::DoubleXp { multiplier } => Some(multiplier),
+            _ => None,
+        })
+        .fold(1.0, f32::max);
+    if xp_multiplier.0 != multiplier {
+        xp_multiplier.0 = multiplier;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_lists_ranges_and_steps() {
+        assert_eq!(parse_field("*/15", 0, 59), Ok((1 << 0) | (1 << 15) | (1 << 30) | (1 << 45)));
+        assert_eq!(parse_field("1-3,5", 0, 59), Ok((1 << 1) | (1 << 2) | (1 << 3) | (1 << 5)));
+        assert_eq!(parse_field("8-12/2", 0, 23), Ok((1 << 8) | (1 << 10) | (1 << 12)));
+        assert!(parse_field("61", 0, 59).is_err());
+        assert!(parse_field("*/0", 0, 59).is_err());
+        assert!(CronSchedule::parse("0 20 * *").is_err());
+    }
+
+    #[test]
+    fn matches_weekly_schedule() {
+        // Fridays at 18:00
+        let schedule = CronSchedule::parse("0 18 * * 5").unwrap();
+        let friday = Utc.with_ymd_and_hms(2026, 10, 16, 18, 0, 0).unwrap();
+        assert!(schedule.matches(&friday));
+        assert!(!schedule.matches(&(friday + Duration::minutes(1))));
+        assert!(!schedule.matches(&(friday + Duration::days(1))));
+
+        // Sunday can be written as 7
+        let sunday = Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap();
+        assert!(CronSchedule::parse("0 0 * * 7").unwrap().matches(&sunday));
+    }
+
+    #[test]
+    fn restricted_day_and_weekday_match_either() {
+        // The 1st of the month, or any Monday
+        let schedule = CronSchedule::parse("0 12 1 * 1").unwrap();
+        let first = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let tuesday = Utc.with_ymd_and_hms(2026, 10, 20, 12, 0, 0).unwrap();
+        assert!(schedule.matches(&first));
+        assert!(schedule.matches(&monday));
+        assert!(!schedule.matches(&tuesday));
+    }
+}
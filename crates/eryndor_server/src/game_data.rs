@@ -29,6 +29,36 @@ pub struct ItemDefinition {
     /// Armor weight class for armor pieces (determines proficiency bonus)
     #[serde(default)]
     pub armor_class: Option<ArmorClass>,
+    /// Base gold value used for vendor buy and sell prices
+    #[serde(default)]
+    pub value: u32,
+    /// What using the item does, for potions and food
+    #[serde(default)]
+    pub consumable: Option<ConsumableEffect>,
+}
+
+/// Effect of using a consumable item
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConsumableEffect {
+    /// Items in the same category share a cooldown (e.g. "potion", "food")
+    pub cooldown_category: String,
+    pub cooldown: f32,
+    #[serde(default)]
+    pub restore_health: f32,
+    #[serde(default)]
+    pub restore_mana: f32,
+    #[serde(default)]
+    pub buff: Option<ConsumableBuff>,
+    /// Food can't be eaten mid-fight
+    #[serde(default)]
+    pub out_of_combat_only: bool,
+}
+
+/// Temporary stat buff granted by a consumable
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConsumableBuff {
+    pub duration: f32,
+    pub stat_bonuses: StatBonuses,
 }
 
 /// Stat bonuses provided by an item when equipped
@@ -53,6 +83,14 @@ impl ItemStatBonuses {
 }
 
 impl ItemDatabase {
+    /// Every consumable sharing a cooldown category
+    pub fn items_in_cooldown_category(&self, category: &str) -> Vec<u32> {
+        self.items.values()
+            .filter(|item| item.consumable.as_ref().is_some_and(|effect| effect.cooldown_category == category))
+            .map(|item| item.id)
+            .collect()
+    }
+
     /// Calculate total stat bonuses from equipped items
     pub fn calculate_equipment_bonuses(&self, equipment: &Equipment) -> ItemStatBonuses {
         let mut total = ItemStatBonuses::default();
@@ -228,6 +266,9 @@ pub struct EnemyDefinition {
     pub loot_table: LootTable,
     #[serde(default)]
     pub visual: VisualData,
+    /// Behavior tree id (content/behaviors); enemies without one use the default chase AI
+    #[serde(default)]
+    pub behavior: Option<String>,
 }
 
 fn default_aggro_range() -> f32 { 150.0 }
@@ -253,26 +294,198 @@ pub struct ZoneDefinition {
     /// Optional tilemap data in new Tiled-compatible TilemapMap format (multiple layers, objects, tilesets)
     #[serde(default)]
     pub tilemap_map: Option<TilemapMap>,
-    /// DEPRECATED: Enemy spawns are now defined in Tiled map files (.tmj)
+    /// Enemy spawn regions with their spawn tables
     #[serde(default)]
     pub enemy_spawns: Vec<EnemySpawnRegion>,
-    /// DEPRECATED: NPC spawns are now defined in Tiled map files (.tmj)
+    /// NPCs placed in this zone
     #[serde(default)]
     pub npc_spawns: Vec<NpcSpawnDef>,
+    /// Waypoint paths idle enemies walk along
+    #[serde(default)]
+    pub patrol_paths: Vec<PatrolPathDef>,
+    /// Weather the zone rolls between; zones without a table stay clear
+    #[serde(default)]
+    pub weather: Option<WeatherTable>,
+}
+
+/// An authored route for patrolling enemies. Open paths are walked back and forth,
+/// looped paths return from the last waypoint to the first.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PatrolPathDef {
+    pub path_id: String,
+    pub waypoints: Vec<Vec2Data>,
+    /// Fraction of the enemy's move speed used while patrolling
+    #[serde(default = "default_patrol_speed")]
+    pub speed_multiplier: f32,
+    /// Seconds to wait at each waypoint
+    #[serde(default = "default_patrol_pause")]
+    pub pause: f32,
+    /// Per-waypoint pause overrides, indexed like `waypoints`
+    #[serde(default)]
+    pub waypoint_pauses: Vec<Option<f32>>,
+    #[serde(default)]
+    pub looped: bool,
+}
+
+fn default_patrol_speed() -> f32 { 0.5 }
+fn default_patrol_pause() -> f32 { 2.0 }
+
+/// Weighted weather for a zone, re-rolled every `min_duration` to `max_duration` seconds
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WeatherTable {
+    pub entries: Vec<WeatherTableEntry>,
+    #[serde(default = "default_weather_min_duration")]
+    pub min_duration: f32,
+    #[serde(default = "default_weather_max_duration")]
+    pub max_duration: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WeatherTableEntry {
+    pub weather: Weather,
+    pub weight: f32,
+}
+
+fn default_weather_min_duration() -> f32 { 300.0 }
+fn default_weather_max_duration() -> f32 { 900.0 }
+
+impl WeatherTable {
+    /// Pick a weather by weight from a roll in `0.0..1.0`
+    pub fn pick(&self, roll: f32) -> Weather {
+        let total: f32 = self.entries.iter().map(|entry| entry.weight.max(0.0)).sum();
+        let mut remaining = roll * total;
+        for entry in &self.entries {
+            remaining -= entry.weight.max(0.0);
+            if remaining < 0.0 {
+                return entry.weather;
+            }
+        }
+        self.entries.last().map(|entry| entry.weather).unwrap_or_default()
+    }
+}
+
+impl ZoneDefinition {
+    /// All patrol paths in this zone: the explicit `patrol_paths` list plus any "patrol_path"
+    /// polyline/polygon objects on the Tiled map (properties: speed_multiplier, pause, looped)
+    pub fn all_patrol_paths(&self) -> Vec<PatrolPathDef> {
+        let mut paths = self.patrol_paths.clone();
+
+        if let Some(map) = &self.tilemap_map {
+            for object in map.objects_of_type("patrol_path") {
+                let waypoints: Vec<Vec2Data> = object.path_points().into_iter()
+                    .map(|(x, y)| Vec2Data { x, y })
+                    .collect();
+                if waypoints.len() < 2 {
+                    warn!("Patrol path object {} in zone {} needs at least two points", object.id, self.zone_id);
+                    continue;
+                }
+
+                paths.push(PatrolPathDef {
+                    path_id: if object.name.is_empty() { format!("object_{}", object.id) } else { object.name.clone() },
+                    waypoints,
+                    speed_multiplier: object.property_f32("speed_multiplier").unwrap_or_else(default_patrol_speed),
+                    pause: object.property_f32("pause").unwrap_or_else(default_patrol_pause),
+                    waypoint_pauses: Vec::new(),
+                    looped: object.polygon.is_some()
+                        || object.property("looped").and_then(|v| v.as_bool()).unwrap_or(false),
+                });
+            }
+        }
+
+        paths
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct EnemySpawnRegion {
     pub region_id: String,
+    /// Single enemy type, used when `spawn_table` is empty
+    #[serde(default)]
     pub enemy_type: u32,
     pub spawn_points: Vec<Vec2Data>,
+    /// Weighted enemy entries rolled independently for each spawn point
+    #[serde(default)]
+    pub spawn_table: Vec<SpawnTableEntry>,
+    /// Overrides the enemy definition's respawn delay for this region
+    #[serde(default)]
+    pub respawn_delay: Option<f32>,
+    /// Patrol path (by path_id in the same zone) enemies from this region walk while idle
+    #[serde(default)]
+    pub patrol_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnTableEntry {
+    pub enemy_type: u32,
+    #[serde(default = "default_spawn_weight")]
+    pub weight: f32,
+    #[serde(default = "default_spawn_level")]
+    pub level_min: u32,
+    #[serde(default = "default_spawn_level")]
+    pub level_max: u32,
+}
+
+fn default_spawn_weight() -> f32 { 1.0 }
+fn default_spawn_level() -> u32 { 1 }
+
+impl EnemySpawnRegion {
+    /// Pick an enemy type and level from the spawn table.
+    /// Falls back to the single `enemy_type` at level 1 when no table is defined.
+    pub fn roll_enemy(&self) -> Option<(u32, u32)> {
+        use rand::Rng;
+
+        if self.spawn_table.is_empty() {
+            return (self.enemy_type != 0).then_some((self.enemy_type, 1));
+        }
+
+        let total_weight: f32 = self.spawn_table.iter().map(|e| e.weight.max(0.0)).sum();
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut roll = rng.gen_range(0.0..total_weight);
+        let entry = self.spawn_table.iter()
+            .find(|e| {
+                let weight = e.weight.max(0.0);
+                if roll < weight {
+                    true
+                } else {
+                    roll -= weight;
+                    false
+                }
+            })
+            .unwrap_or(&self.spawn_table[self.spawn_table.len() - 1]);
+
+        let level_max = entry.level_max.max(entry.level_min);
+        let level = rng.gen_range(entry.level_min..=level_max);
+        Some((entry.enemy_type, level))
+    }
+}
+
+impl ZoneDatabase {
+    /// Find a spawn region by id across all loaded zones
+    pub fn find_spawn_region(&self, region_id: &str) -> Option<&EnemySpawnRegion> {
+        self.zones.values()
+            .flat_map(|zone| zone.enemy_spawns.iter())
+            .find(|region| region.region_id == region_id)
+    }
+
+    /// Patrol path assigned to a spawn region, looked up in the region's own zone
+    pub fn find_region_patrol_path(&self, region_id: &str) -> Option<PatrolPathDef> {
+        self.zones.values().find_map(|zone| {
+            let region = zone.enemy_spawns.iter().find(|region| region.region_id == region_id)?;
+            let path_id = region.patrol_path.as_deref()?;
+            zone.all_patrol_paths().into_iter().find(|path| path.path_id == path_id)
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NpcSpawnDef {
     pub npc_id: u32,
     pub name: String,
-    pub npc_type: String,  // "QuestGiver" or "Trainer"
+    pub npc_type: String,  // "QuestGiver", "Trainer", "Vendor" or "SpiritHealer"
     pub position: Vec2Data,
     #[serde(default)]
     pub quests: Vec<u32>,
@@ -282,6 +495,12 @@ pub struct NpcSpawnDef {
     pub trainer_type: Option<TrainerType>,
     #[serde(default)]
     pub teaching_quests: Vec<u32>,
+    /// Dialogue graph id served when a player talks to this NPC
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    /// Shop id sold by this NPC (makes it a vendor)
+    #[serde(default)]
+    pub shop: Option<String>,
     pub visual: VisualData,
 }
 
@@ -306,6 +525,17 @@ pub struct VisualData {
     pub size: f32,
 }
 
+impl VisualData {
+    pub fn shape_type(&self) -> ShapeType {
+        match self.shape.as_str() {
+            "Square" | "Rectangle" => ShapeType::Square,
+            "Triangle" => ShapeType::Triangle,
+            "Diamond" => ShapeType::Diamond,
+            _ => ShapeType::Circle,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TrainerItem {
     pub item_id: u32,
@@ -353,3 +583,157 @@ pub struct TrainerDefinition {
     pub name: String,
     pub items: Vec<TrainerItem>,
 }
+
+// ============================================================================
+// DIALOGUE DEFINITIONS
+// ============================================================================
+
+/// Dialogue graphs authored in the editor, keyed by dialogue id
+#[derive(Resource, Default)]
+pub struct DialogueDatabase {
+    pub dialogues: HashMap<String, DialogueDefinition>,
+}
+
+/// A dialogue graph: a set of nodes connected by choices
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DialogueDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Node the conversation starts on
+    pub start_node: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DialogueNode {
+    /// Speaker name shown in the dialogue window (defaults to the NPC name)
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Quest offered to the player when this node is reached
+    #[serde(default)]
+    pub offer_quest: Option<u32>,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Node to continue to (None ends the conversation)
+    #[serde(default)]
+    pub next: Option<String>,
+    /// All conditions must pass for this choice to be taken
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum DialogueCondition {
+    QuestAvailable { quest_id: u32 },
+    QuestActive { quest_id: u32 },
+    QuestCompleted { quest_id: u32 },
+    MinLevel { level: u32 },
+}
+
+impl DialogueCondition {
+    pub fn is_met(&self, quest_log: &QuestLog, level: u32) -> bool {
+        match self {
+            DialogueCondition::QuestAvailable { quest_id } => quest_log.can_accept_quest(*quest_id),
+            DialogueCondition::QuestActive { quest_id } => quest_log.has_active_quest(*quest_id),
+            DialogueCondition::QuestCompleted { quest_id } => quest_log.has_completed_quest(*quest_id),
+            DialogueCondition::MinLevel { level: required } => level >= *required,
+        }
+    }
+}
+
+impl DialogueNode {
+    /// Replies whose conditions all pass, with their position in `choices`
+    pub fn available_choices<'a>(&'a self, quest_log: &'a QuestLog, level: u32) -> impl Iterator<Item = (usize, &'a DialogueChoice)> + 'a {
+        self.choices.iter()
+            .enumerate()
+            .filter(move |(_, choice)| choice.conditions.iter().all(|c| c.is_met(quest_log, level)))
+    }
+}
+
+// ============================================================================
+// LOOT TABLE DEFINITIONS
+// ============================================================================
+
+/// Named loot tables authored in the editor, keyed by table id.
+/// Enemies reference these through `LootTable::table_id`.
+#[derive(Resource, Default)]
+pub struct LootTableDatabase {
+    pub tables: HashMap<String, LootTableDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LootTableDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Editor category ("Enemy", "Chest", ...)
+    #[serde(default)]
+    pub table_type: String,
+    #[serde(flatten)]
+    pub table: LootTable,
+}
+
+// ============================================================================
+// SHOP DEFINITIONS
+// ============================================================================
+
+/// Vendor shop inventories authored in the editor, keyed by shop id
+#[derive(Resource, Default)]
+pub struct ShopDatabase {
+    pub shops: HashMap<String, ShopDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShopDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub items: Vec<ShopItem>,
+    /// Fraction of an item's value paid to players who sell to this shop
+    #[serde(default = "default_sell_rate")]
+    pub sell_rate: f32,
+    /// Multiplier applied to item value for items without an explicit price
+    #[serde(default = "default_buy_markup")]
+    pub buy_markup: f32,
+}
+
+fn default_sell_rate() -> f32 {
+    0.25
+}
+
+fn default_buy_markup() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShopItem {
+    pub item_id: u32,
+    /// Fixed price overriding the item value and markup
+    #[serde(default)]
+    pub price: Option<u32>,
+}
+
+impl ShopDefinition {
+    /// Price this shop charges for an item it stocks
+    pub fn buy_price(&self, shop_item: &ShopItem, item: &ItemDefinition) -> u32 {
+        shop_item.price
+            .unwrap_or_else(|| (item.value as f32 * self.buy_markup).round() as u32)
+            .max(1)
+    }
+
+    /// Gold paid per unit when a player sells an item here. `None` if the shop won't buy it.
+    pub fn sell_price(&self, item: &ItemDefinition) -> Option<u32> {
+        if matches!(item.item_type, ItemType::QuestItem) || item.value == 0 {
+            return None;
+        }
+        Some(((item.value as f32 * self.sell_rate).floor() as u32).max(1))
+    }
+}
@@ -0,0 +1,355 @@
+//! Parties and guilds, the groups the party and guild chat channels route on.
+//!
+//! Parties last for the session: the server hands out their ids and a party breaks up once
+//! only one member is left. Guild membership is saved with the character, and a guild exists
+//! for as long as any character carries its name. Players join either by accepting an invite
+//! from a member, which stays open for `GROUP_INVITE_TIMEOUT` seconds.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::collections::HashMap;
+use crate::auth::{ActiveCharacterEntity, CharacterDatabaseId};
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+use crate::moderation;
+use crate::social::notify;
+
+/// Party the player is in. Players without one are told they aren't in a party.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PartyId(pub u64);
+
+/// Guild the player belongs to
+#[derive(Component, Clone, PartialEq, Eq, Debug)]
+pub struct GuildName(pub String);
+
+/// Hands out party ids
+#[derive(Resource, Default)]
+pub struct PartyIds {
+    next: u64,
+}
+
+impl PartyIds {
+    pub fn allocate(&mut self) -> PartyId {
+        self.next += 1;
+        PartyId(self.next)
+    }
+}
+
+/// Open party or guild invite, stored on the invited player
+#[derive(Component, Clone, Debug)]
+pub struct PendingGroupInvite {
+    pub kind: GroupKind,
+    pub inviter: Entity,
+    /// Guild the invite was sent for, so it lapses if the inviter leaves it
+    pub guild: Option<String>,
+    pub expires_at: f32,
+}
+
+/// Components of a player that decide which groups they are in
+type MemberQuery = (Entity, &'static Character, &'static OwnedBy, Option<&'static PartyId>, Option<&'static GuildName>);
+
+/// Tell every online member of a party
+fn tell_party(commands: &mut Commands, members: &Query<MemberQuery, With<Player>>, party: PartyId, message: &str) {
+    for (_, _, owner, member_party, _) in members {
+        if member_party == Some(&party) {
+            notify(commands, owner.0, message.to_string(), NotificationType::Info);
+        }
+    }
+}
+
+/// Tell every online member of a guild
+fn tell_guild(commands: &mut Commands, members: &Query<MemberQuery, With<Player>>, guild: &GuildName, message: &str) {
+    for (_, _, owner, _, member_guild) in members {
+        if member_guild == Some(guild) {
+            notify(commands, owner.0, message.to_string(), NotificationType::Info);
+        }
+    }
+}
+
+fn party_size(members: &Query<MemberQuery, With<Player>>, party: PartyId) -> usize {
+    members.iter().filter(|(.., member_party, _)| *member_party == Some(&party)).count()
+}
+
+/// Parties with fewer than two members left
+fn lone_parties<'a>(members: impl IntoIterator<Item = &'a PartyId>) -> Vec<PartyId> {
+    let mut sizes: HashMap<PartyId, usize> = HashMap::new();
+    for party in members {
+        *sizes.entry(*party).or_default() += 1;
+    }
+    sizes.into_iter().filter(|(_, size)| *size < 2).map(|(party, _)| party).collect()
+}
+
+pub fn handle_group_invite(
+    trigger: On<FromClient<GroupInviteRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    members: Query<MemberQuery, With<Player>>,
+    pending: Query<(), With<PendingGroupInvite>>,
+    time: Res<Time>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((inviter, inviter_char, _, inviter_party, inviter_guild)) = members.get(active_char.0) else { return };
+
+    let request = trigger.event();
+    let Some((target, target_char, target_owner, target_party, target_guild)) = members.iter()
+        .find(|(_, character, ..)| character.name.eq_ignore_ascii_case(request.target.trim()))
+    else {
+        notify(&mut commands, client_entity, format!("{} is not online", request.target.trim()), NotificationType::Warning);
+        return;
+    };
+
+    let refusal = if target == inviter {
+        Some("You can't invite yourself".to_string())
+    } else {
+        match request.kind {
+            GroupKind::Party if target_party.is_some() => Some(format!("{} is already in a party", target_char.name)),
+            GroupKind::Party if inviter_party.is_some_and(|party| party_size(&members, *party) >= MAX_PARTY_SIZE) => {
+                Some("Your party is full".to_string())
+            }
+            GroupKind::Guild if inviter_guild.is_none() => Some("You are not in a guild".to_string()),
+            GroupKind::Guild if target_guild.is_some() => Some(format!("{} is already in a guild", target_char.name)),
+            _ if pending.contains(target) => Some(format!("{} is considering another invite", target_char.name)),
+            _ => None,
+        }
+    };
+    if let Some(message) = refusal {
+        notify(&mut commands, client_entity, message, NotificationType::Warning);
+        return;
+    }
+
+    let guild = match request.kind {
+        GroupKind::Party => None,
+        GroupKind::Guild => inviter_guild.map(|guild| guild.0.clone()),
+    };
+    commands.entity(target).insert(PendingGroupInvite {
+        kind: request.kind,
+        inviter,
+        guild: guild.clone(),
+        expires_at: time.elapsed_secs() + GROUP_INVITE_TIMEOUT,
+    });
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(target_owner.0)),
+        message: GroupInviteEvent {
+            kind: request.kind,
+            inviter_name: inviter_char.name.clone(),
+            group_name: guild,
+        },
+    });
+    notify(&mut commands, client_entity, format!("You invited {} to your {}", target_char.name, request.kind.label()), NotificationType::Info);
+}
+
+pub fn handle_group_invite_response(
+    trigger: On<FromClient<GroupInviteResponse>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    invited: Query<(&PendingGroupInvite, Option<&CharacterDatabaseId>)>,
+    members: Query<MemberQuery, With<Player>>,
+    mut party_ids: ResMut<PartyIds>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+
+    let Ok((invite, db_id)) = invited.get(char_entity) else { return };
+    commands.entity(char_entity).remove::<PendingGroupInvite>();
+
+    let Ok((_, character, _, party, guild)) = members.get(char_entity) else { return };
+    let Ok((inviter, inviter_char, inviter_owner, inviter_party, inviter_guild)) = members.get(invite.inviter) else {
+        notify(&mut commands, client_entity, format!("The {} invite is no longer valid", invite.kind.label()), NotificationType::Warning);
+        return;
+    };
+
+    if !trigger.event().accept {
+        notify(&mut commands, inviter_owner.0, format!("{} declined your {} invite", character.name, invite.kind.label()), NotificationType::Info);
+        return;
+    }
+
+    match invite.kind {
+        GroupKind::Party => {
+            if party.is_some() {
+                notify(&mut commands, client_entity, "You are already in a party".to_string(), NotificationType::Warning);
+                return;
+            }
+            let party = match inviter_party {
+                Some(party) if party_size(&members, *party) >= MAX_PARTY_SIZE => {
+                    notify(&mut commands, client_entity, format!("{}'s party is full", inviter_char.name), NotificationType::Warning);
+                    return;
+                }
+                Some(party) => *party,
+                None => {
+                    let party = party_ids.allocate();
+                    commands.entity(inviter).insert(party);
+                    notify(&mut commands, inviter_owner.0, format!("{} has joined the party", character.name), NotificationType::Info);
+                    party
+                }
+            };
+            tell_party(&mut commands, &members, party, &format!("{} has joined the party", character.name));
+            commands.entity(char_entity).insert(party);
+            notify(&mut commands, client_entity, format!("You joined {}'s party", inviter_char.name), NotificationType::Success);
+        }
+        GroupKind::Guild => {
+            let still_member = inviter_guild.zip(invite.guild.as_ref()).is_some_and(|(current, invited_to)| current.0 == *invited_to);
+            let Some(guild_name) = invite.guild.clone().filter(|_| still_member) else {
+                notify(&mut commands, client_entity, "The guild invite is no longer valid".to_string(), NotificationType::Warning);
+                return;
+            };
+            if guild.is_some() {
+                notify(&mut commands, client_entity, "You are already in a guild".to_string(), NotificationType::Warning);
+                return;
+            }
+
+            let guild = GuildName(guild_name.clone());
+            tell_guild(&mut commands, &members, &guild, &format!("{} has joined the guild", character.name));
+            commands.entity(char_entity).insert(guild);
+            notify(&mut commands, client_entity, format!("You joined the guild {}", guild_name), NotificationType::Success);
+
+            if let Some(db_id) = db_id {
+                let character_id = db_id.0;
+                db.execute("set_guild", move |pool| async move {
+                    database::set_guild(&pool, character_id, Some(guild_name.as_str())).timed("set_guild").await
+                });
+            }
+        }
+    }
+    info!("{} joined {}'s {}", character.name, inviter_char.name, invite.kind.label());
+}
+
+pub fn handle_leave_group(
+    trigger: On<FromClient<LeaveGroupRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    members: Query<MemberQuery, With<Player>>,
+    db_ids: Query<&CharacterDatabaseId>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+    let Ok((_, character, _, party, guild)) = members.get(char_entity) else { return };
+
+    match trigger.event().kind {
+        GroupKind::Party => {
+            let Some(party) = party.copied() else {
+                notify(&mut commands, client_entity, "You are not in a party".to_string(), NotificationType::Warning);
+                return;
+            };
+            commands.entity(char_entity).remove::<PartyId>();
+            tell_party(&mut commands, &members, party, &format!("{} has left the party", character.name));
+        }
+        GroupKind::Guild => {
+            let Some(guild) = guild else {
+                notify(&mut commands, client_entity, "You are not in a guild".to_string(), NotificationType::Warning);
+                return;
+            };
+            commands.entity(char_entity).remove::<GuildName>();
+            tell_guild(&mut commands, &members, guild, &format!("{} has left the guild", character.name));
+
+            if let Ok(db_id) = db_ids.get(char_entity) {
+                let character_id = db_id.0;
+                db.execute("set_guild", move |pool| async move {
+                    database::set_guild(&pool, character_id, None).timed("set_guild").await
+                });
+            }
+        }
+    }
+}
+
+pub fn handle_create_guild(
+    trigger: On<FromClient<CreateGuildRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    players: Query<(&CharacterDatabaseId, Has<GuildName>)>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((db_id, in_guild)) = players.get(active_char.0) else { return };
+
+    if in_guild {
+        notify(&mut commands, client_entity, "Leave your guild before founding another".to_string(), NotificationType::Warning);
+        return;
+    }
+    let check = moderation::check_guild_name(&trigger.event().name);
+    if !check.is_appropriate {
+        let reason = check.reason.unwrap_or_else(|| "Guild name is not appropriate".to_string());
+        notify(&mut commands, client_entity, reason, NotificationType::Warning);
+        return;
+    }
+
+    let (character, character_id, name) = (active_char.0, db_id.0, check.filtered_text);
+    db.run(
+        move |pool| async move {
+            let result = database::create_guild(&pool, character_id, &name).timed("create_guild").await;
+            result.map(|_| name)
+        },
+        move |result, world| {
+            if let Err(e) = world.run_system_cached_with(finish_create_guild, (client_entity, character, result)) {
+                error!("Failed to create guild: {}", e);
+            }
+        },
+    );
+}
+
+fn finish_create_guild(
+    In((client_entity, character, result)): In<(Entity, Entity, Result<String, String>)>,
+    mut commands: Commands,
+    players: Query<&Character>,
+) {
+    // The character may have logged out while the database was busy
+    let Ok(founder) = players.get(character) else { return };
+
+    match result {
+        Ok(name) => {
+            info!("{} founded the guild {}", founder.name, name);
+            notify(&mut commands, client_entity, format!("You founded the guild {}", name), NotificationType::Success);
+            commands.entity(character).insert(GuildName(name));
+        }
+        Err(e) => notify(&mut commands, client_entity, e, NotificationType::Warning),
+    }
+}
+
+/// Expire old invites and break up parties that are down to one member
+pub fn update_groups(
+    mut commands: Commands,
+    pending: Query<(Entity, &PendingGroupInvite, &OwnedBy)>,
+    parties: Query<(Entity, &PartyId, &OwnedBy)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, invite, owner) in &pending {
+        if now >= invite.expires_at {
+            commands.entity(entity).remove::<PendingGroupInvite>();
+            notify(&mut commands, owner.0, format!("The {} invite expired", invite.kind.label()), NotificationType::Info);
+        }
+    }
+
+    // Members who left or logged out leave the last one behind
+    let lone = lone_parties(parties.iter().map(|(_, party, _)| party));
+    for (entity, party, owner) in &parties {
+        if lone.contains(party) {
+            commands.entity(entity).remove::<PartyId>();
+            notify(&mut commands, owner.0, "Your party has disbanded".to_string(), NotificationType::Info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn party_ids_are_unique() {
+        let mut ids = PartyIds::default();
+        assert_ne!(ids.allocate(), ids.allocate());
+    }
+
+    #[test]
+    fn parties_of_one_are_lone() {
+        let members = [PartyId(1), PartyId(2), PartyId(1), PartyId(3), PartyId(3)];
+        assert_eq!(lone_parties(&members), vec![PartyId(2)]);
+        assert!(lone_parties(&[]).is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::auth::ActiveCharacterEntity;
+use crate::abilities::add_buff;
 use crate::game_data::ItemDatabase;
 
 pub fn handle_pickup_item(
@@ -295,6 +296,106 @@ pub fn handle_unequip_item(
     }
 }
 
+/// Use a consumable: restore health/mana and/or apply its buff, then start its category cooldown
+pub fn handle_use_item(
+    trigger: On<FromClient<UseItemRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    mut players: Query<(&mut Inventory, &mut Health, &mut Mana, &mut ItemCooldowns, &InCombat, Has<Dead>, Has<Ghost>)>,
+    item_db: Res<ItemDatabase>,
+    time: Res<Time>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let request = trigger.event();
+
+    // Get client's character
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+
+    let Ok((mut inventory, mut health, mut mana, mut cooldowns, in_combat, is_dead, is_ghost)) = players.get_mut(char_entity) else { return };
+
+    let warn = |commands: &mut Commands, message: String| {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message,
+                notification_type: NotificationType::Warning,
+            },
+        });
+    };
+
+    if is_dead || is_ghost {
+        return;
+    }
+    if !inventory.has_item(request.item_id) {
+        return;
+    }
+    let Some(item_def) = item_db.items.get(&request.item_id) else { return };
+    let Some(effect) = &item_def.consumable else {
+        warn(&mut commands, format!("{} cannot be used!", item_def.name));
+        return;
+    };
+
+    let remaining = cooldowns.remaining(&effect.cooldown_category);
+    if remaining > 0.0 {
+        warn(&mut commands, format!("{} is not ready yet ({:.0}s)", item_def.name, remaining.ceil()));
+        return;
+    }
+    if effect.out_of_combat_only && in_combat.0 {
+        warn(&mut commands, format!("You can't use {} in combat!", item_def.name));
+        return;
+    }
+    // Don't waste a potion that would do nothing
+    let restores_something = (effect.restore_health > 0.0 && health.current < health.max)
+        || (effect.restore_mana > 0.0 && mana.current < mana.max);
+    if effect.buff.is_none() && !restores_something {
+        warn(&mut commands, "You don't need that right now".to_string());
+        return;
+    }
+
+    inventory.consume_item(request.item_id);
+    health.current = (health.current + effect.restore_health).min(health.max);
+    mana.current = (mana.current + effect.restore_mana).min(mana.max);
+
+    if let Some(buff) = &effect.buff {
+        let buff = ActiveBuff {
+            ability_id: 0,
+            item_id: Some(request.item_id),
+            stat_bonuses: buff.stat_bonuses.clone(),
+            expires_at: time.elapsed_secs() + buff.duration,
+        };
+        commands.entity(char_entity).queue(move |mut entity: EntityWorldMut| add_buff(&mut entity, buff));
+    }
+
+    // A negative cooldown from content would panic in Timer::from_seconds
+    let cooldown = effect.cooldown.max(0.0);
+    cooldowns.cooldowns.insert(
+        effect.cooldown_category.clone(),
+        Timer::from_seconds(cooldown, TimerMode::Once),
+    );
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: ItemCooldownEvent {
+            item_ids: item_db.items_in_cooldown_category(&effect.cooldown_category),
+            duration: cooldown,
+        },
+    });
+
+    info!("Player used {}", item_def.name);
+}
+
+pub fn update_item_cooldowns(
+    mut query: Query<&mut ItemCooldowns>,
+    time: Res<Time>,
+) {
+    for mut cooldowns in &mut query {
+        cooldowns.cooldowns.retain(|_, timer| {
+            timer.tick(time.delta());
+            !timer.is_finished()
+        });
+    }
+}
+
 pub fn handle_set_hotbar_slot(
     trigger: On<FromClient<SetHotbarSlotRequest>>,
     clients: Query<&ActiveCharacterEntity>,
@@ -0,0 +1,353 @@
+//! Eryndor game server.
+//!
+//! Everything that makes up the game lives in [`GameServerPlugin`]. The `server` binary adds
+//! logging, the network transports and the HTTP endpoints around it; the integration tests in
+//! `eryndor_testing` run it with an in-memory transport and database instead.
+
+// Allow common clippy warnings for game development
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+#![allow(unused_assignments)]
+#![allow(unused_imports)]
+#![allow(clippy::type_complexity)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::manual_unwrap_or_default)]
+#![allow(clippy::single_match)]
+#![allow(clippy::derivable_impls)]
+#![allow(clippy::items_after_test_module)]
+#![allow(clippy::unnecessary_unwrap)]
+
+pub mod abilities;
+pub mod animation;
+pub mod admin;
+pub mod admin_api;
+pub mod admin_cli;
+pub mod assets;
+pub mod audit;
+pub mod auth;
+pub mod bans;
+pub mod behavior;
+pub mod character;
+pub mod chat;
+pub mod combat;
+pub mod combat_formulas;
+pub mod combat_log;
+pub mod config;
+pub mod console;
+pub mod dashboard;
+pub mod data_reload;
+pub mod database;
+pub mod death;
+pub mod editor_api;
+pub mod events;
+pub mod game_data;
+pub mod groups;
+pub mod inventory;
+pub mod metrics;
+pub mod moderation;
+pub mod movement;
+pub mod pathfinding;
+pub mod persistence;
+pub mod patrol;
+pub mod profiling;
+pub mod pvp;
+pub mod quest;
+pub mod relay;
+pub mod replication;
+pub mod social;
+pub mod shards;
+pub mod shutdown;
+pub mod spatial;
+pub mod spawn;
+pub mod threat;
+pub mod trainer;
+pub mod two_factor;
+pub mod validation;
+pub mod vendor;
+pub mod weapon;
+pub mod weather;
+pub mod world;
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use avian2d::prelude::*;
+use governor::{Quota, RateLimiter, state::keyed::DefaultKeyedStateStore};
+use std::num::NonZeroU32;
+use std::net::IpAddr;
+
+use eryndor_shared::*;
+
+// Rate limiters for security (per-IP rate limiting)
+#[derive(Resource)]
+pub struct RateLimiters {
+    pub account_creation: RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, governor::clock::DefaultClock>,
+    pub login_attempts: RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, governor::clock::DefaultClock>,
+}
+
+impl RateLimiters {
+    fn from_config(config: &config::ServerConfig) -> Self {
+        Self {
+            account_creation: RateLimiter::keyed(
+                Quota::per_hour(NonZeroU32::new(config.rate_limits.account_creation_per_hour).unwrap())
+            ),
+            login_attempts: RateLimiter::keyed(
+                Quota::per_hour(NonZeroU32::new(config.rate_limits.login_attempts_per_hour).unwrap())
+            ),
+        }
+    }
+}
+
+// Disambiguate Position - use our custom one for replication
+use eryndor_shared::Position as SharedPosition;
+// Import Avian physics components for movement
+use avian2d::prelude::Position as PhysicsPosition;
+use avian2d::prelude::LinearVelocity as PhysicsVelocity;
+
+/// Game systems, observers and resources, without a transport.
+///
+/// Expects `MinimalPlugins`, `StatesPlugin`, `AssetPlugin` and `RepliconPlugins` (with
+/// `tick_schedule: None`, the replication tick is advanced at the configured rate) to be
+/// added alongside it. Clients connecting without the UDP/WebSocket transport need a
+/// `ClientMetadata` inserted on their client entity before they can log in.
+pub struct GameServerPlugin {
+    pub config: config::ServerConfig,
+}
+
+impl Plugin for GameServerPlugin {
+    fn build(&self, app: &mut App) {
+        let config = &self.config;
+        let checkpoint_interval = std::time::Duration::from_secs(config.persistence.checkpoint_interval_seconds.max(1));
+        let shard_heartbeat = std::time::Duration::from_secs(config.shard.heartbeat_seconds.max(1));
+
+        app
+            // Physics - server-authoritative with fixed timestep
+            // Use headless mode - bevy_scene feature disabled in Cargo.toml
+            .add_plugins(PhysicsPlugins::default().with_length_unit(1.0))
+            .insert_resource(Gravity(Vec2::ZERO))  // Top-down game, no gravity
+            .insert_resource(Time::<Fixed>::from_hz(60.0))  // 60 Hz physics tick rate
+            // Asset loading system for JSON content
+            .add_plugins(assets::GameAssetsPlugin)
+            // Configuration
+            .insert_resource(RateLimiters::from_config(config))
+            .insert_resource(replication::ReplicationTickTimer::from_config(config))
+            .insert_resource(replication::ReplicationStats::new(config.network.tick_rate))
+            .insert_resource(replication::ComponentRate::<SharedPosition>::new(config.network.position_rate, config.network.tick_rate))
+            .insert_resource(replication::ComponentRate::<Velocity>::new(config.network.velocity_rate, config.network.tick_rate))
+            .insert_resource(replication::ComponentRate::<Health>::new(config.network.health_rate, config.network.tick_rate))
+            .insert_resource(replication::ComponentRate::<Mana>::new(config.network.mana_rate, config.network.tick_rate))
+            .insert_resource(config.clone())
+            .init_resource::<events::EventScheduler>()
+            .init_resource::<events::XpMultiplier>()
+            .init_resource::<weather::WorldTime>()
+            .init_resource::<social::OnlineCharacters>()
+            .init_resource::<chat::ChatRateLimiter>()
+            .init_resource::<groups::PartyIds>()
+            .init_resource::<admin_api::AdminApiBridge>()
+            .init_resource::<console::ServerConsole>()
+            .init_resource::<metrics::TickStart>()
+            .init_resource::<shutdown::ShutdownState>()
+            .init_resource::<shards::ShardRegistry>()
+            .init_resource::<relay::Relay>()
+            .init_resource::<spatial::SpatialIndex>()
+            // Database
+            .init_resource::<database::DatabaseConnection>()
+            // Game data resources
+            .init_resource::<abilities::AbilityDatabase>()
+            .init_resource::<game_data::ItemDatabase>()
+            .init_resource::<game_data::QuestDatabase>()
+            .init_resource::<game_data::TrainerDatabase>()
+            .init_resource::<game_data::EnemyDatabase>()
+            // Replicated components and network events (shared with every client)
+            .add_plugins(ProtocolPlugin)
+            // Register observers for client triggers
+            .add_observer(auth::handle_login)
+            .add_observer(auth::handle_create_account)
+            .add_observer(auth::handle_oauth_login)
+            .add_observer(two_factor::handle_two_factor_setup)
+            .add_observer(two_factor::handle_two_factor_confirm)
+            .add_observer(two_factor::handle_two_factor_disable)
+            .add_observer(auth::handle_create_character)
+            .add_observer(auth::handle_select_character)
+            .add_observer(shards::handle_shard_list_request)
+            .add_observer(movement::handle_move_input)
+            .add_observer(combat::handle_set_target)
+            .add_observer(combat::handle_use_ability)
+            .add_observer(inventory::handle_pickup_item)
+            .add_observer(inventory::handle_drop_item)
+            .add_observer(inventory::handle_equip_item)
+            .add_observer(inventory::handle_use_item)
+            .add_observer(inventory::handle_unequip_item)
+            .add_observer(inventory::handle_set_hotbar_slot)
+            .add_observer(inventory::handle_open_loot_container)
+            .add_observer(inventory::handle_loot_item)
+            .add_observer(inventory::handle_auto_loot)
+            .add_observer(quest::handle_interact_npc)
+            .add_observer(quest::handle_choose_dialogue_option)
+            .add_observer(death::handle_release_spirit)
+            .add_observer(death::handle_resurrect_at_corpse)
+            .add_observer(pvp::handle_duel_challenge)
+            .add_observer(pvp::handle_duel_response)
+            .add_observer(pvp::handle_set_pvp_flag)
+            .add_observer(quest::handle_accept_quest)
+            .add_observer(quest::handle_complete_quest)
+            .add_observer(trainer::handle_purchase_from_trainer)
+            .add_observer(vendor::handle_buy_from_vendor)
+            .add_observer(vendor::handle_sell_to_vendor)
+            .add_observer(vendor::handle_buyback)
+            .add_observer(auth::handle_disconnect_character)
+            .add_observer(admin::handle_admin_command)
+            .add_observer(chat::handle_chat_message)
+            .add_observer(chat::handle_whisper)
+            .add_observer(social::handle_add_friend)
+            .add_observer(social::handle_remove_friend)
+            .add_observer(groups::handle_group_invite)
+            .add_observer(groups::handle_group_invite_response)
+            .add_observer(groups::handle_leave_group)
+            .add_observer(groups::handle_create_guild)
+            // Dashboard observers
+            .add_observer(dashboard::handle_get_player_list)
+            .add_observer(dashboard::handle_get_ban_list)
+            .add_observer(dashboard::handle_get_server_stats)
+            .add_observer(dashboard::handle_get_audit_logs)
+            // Respawn system
+            .add_observer(spawn::schedule_respawn)
+            // Threat
+            .add_observer(threat::apply_threat)
+            .add_observer(threat::apply_healing_threat)
+            .add_observer(threat::apply_taunt)
+            // Combat log entries go out to the players involved
+            .add_observer(combat_log::send_combat_log)
+            // Systems
+            .add_systems(Startup, database::setup_database)
+            // Register this shard as soon as the database is up, then keep its entry fresh
+            .add_systems(Startup, shards::publish_heartbeat.after(database::setup_database))
+            .add_systems(Update, shards::publish_heartbeat.run_if(bevy::time::common_conditions::on_timer(shard_heartbeat)))
+            // The replicated world clock exists from startup
+            .add_systems(Startup, weather::spawn_world_clock)
+            // Spawn world boundaries at startup (doesn't depend on JSON data)
+            .add_systems(Startup, world::spawn_world_boundaries)
+            // Spawn collision, NPCs and enemies once zone and enemy data are loaded
+            .add_systems(Update, world::spawn_world.run_if(world::zone_data_loaded))
+            // Finished database jobs hand their results back before this frame's systems run
+            .add_systems(PreUpdate, database::apply_results)
+            // Proximity searches this frame look entities up by where they ended last frame
+            .add_systems(PreUpdate, spatial::update_spatial_index)
+            .add_systems(Update, (
+                // Auth systems
+                auth::handle_client_disconnect,
+                // Movement
+                movement::update_positions,
+                // Collision streaming
+                world::stream_collision_chunks,
+                // Animation state updates
+                animation::update_animation_states,
+                animation::update_enemy_animation_states,
+                // Combat
+                combat::update_ai_activation_delays,
+                combat::process_auto_attacks,
+                combat::update_ability_cooldowns,
+                combat::regenerate_resources,
+                combat::check_deaths,
+                combat::check_level_ups,
+                combat::check_weapon_proficiency_level_ups,
+                combat::enemy_ai,
+                // Ability effects
+                abilities::process_buffs_and_debuffs,
+                abilities::process_dots,
+                // Quests
+                quest::update_quest_progress,
+                // Respawn
+                spawn::process_respawns,
+            ))
+            // Consumable category cooldowns
+            .add_systems(Update, inventory::update_item_cooldowns)
+            // Threat decay runs ahead of target selection
+            .add_systems(Update, threat::decay_threat.before(combat::enemy_ai))
+            // Behavior trees make their decisions before the chase/attack AI acts on them
+            .add_systems(Update, (
+                behavior::attach_enemy_behaviors,
+                behavior::run_behavior_trees,
+            ).chain().before(combat::enemy_ai))
+            // Idle enemies walk their patrol paths unless a behavior tree is steering them
+            .add_systems(Update, (
+                patrol::attach_patrol_routes,
+                patrol::follow_patrol_routes,
+            ).chain().after(behavior::run_behavior_trees).before(combat::enemy_ai))
+            // Duelists stop at 1 HP; lost, fled and abandoned duels end after that
+            .add_systems(Update, (
+                pvp::protect_duelists,
+                pvp::update_duels,
+            ).chain().before(combat::check_deaths))
+            .add_systems(Update, pvp::update_pvp_flags)
+            // Friend presence notifications
+            .add_systems(Update, social::update_presence)
+            // Party and guild invites expire; parties left with one member break up
+            .add_systems(Update, groups::update_groups)
+            // Global chat, presence and broadcasts from the other shards
+            .add_systems(Startup, relay::start_relay)
+            .add_systems(Update, (
+                relay::receive_relay_messages,
+                relay::publish_roster.run_if(bevy::time::common_conditions::on_timer(std::time::Duration::from_secs(30))),
+            ))
+            // Day/night cycle and zone weather
+            .add_systems(Update, (
+                weather::advance_world_clock,
+                weather::update_zone_weather,
+            ))
+            // World bosses, double XP and invasions from the event schedule
+            .add_systems(Update, events::run_scheduled_events.run_if(resource_exists::<world::WorldSpawned>))
+            // Projectiles in flight hit enemies or fizzle against walls
+            .add_systems(Update, abilities::update_projectiles)
+            // Reroute enemies around walls once enemy_ai has picked where they're heading
+            .add_systems(Update, pathfinding::steer_enemies_along_paths.after(combat::enemy_ai))
+            // Replication snapshot rate
+            .add_systems(Update, replication::advance_replication_tick)
+            // Per-component update rates once every system has written this frame's values, then
+            // load tracking of what will actually go out
+            .add_systems(PostUpdate, (
+                (
+                    replication::update_wire_components::<SharedPosition>,
+                    replication::update_wire_components::<Velocity>,
+                    replication::update_wire_components::<Health>,
+                    replication::update_wire_components::<Mana>,
+                ),
+                replication::track_replicated_changes,
+            ).chain().after(sync_physics_to_position).before(ServerSystems::Send))
+            // Nearby entities are updated more often than distant ones
+            .add_systems(Update, replication::prioritize_nearby_entities.run_if(bevy::time::common_conditions::on_timer(std::time::Duration::from_millis(250))))
+            // Metrics: frame time bracketing the whole schedule, gauges once a second
+            .add_systems(First, metrics::start_tick)
+            .add_systems(Last, metrics::finish_tick)
+            .add_systems(Update, metrics::update_gauges.run_if(bevy::time::common_conditions::on_timer(std::time::Duration::from_secs(1))))
+            // Web admin dashboard: publish live data and deliver queued broadcasts
+            .add_systems(Update, (
+                admin_api::publish_snapshot.run_if(bevy::time::common_conditions::on_timer(std::time::Duration::from_secs(1))),
+                admin_api::deliver_broadcasts,
+            ))
+            // Operator console commands from the terminal and the admin API
+            .add_systems(Update, console::run_console_commands)
+            // Lift temporary bans that have run out; drop banned players once they've been told
+            .add_systems(Update, (
+                bans::expire_bans.run_if(bevy::time::common_conditions::on_timer(std::time::Duration::from_secs(60))),
+                bans::disconnect_pending_clients,
+            ))
+            // Checkpoint characters whose state changed so a crash can't lose much progress
+            .add_systems(Update, persistence::checkpoint_characters.run_if(bevy::time::common_conditions::on_timer(checkpoint_interval)))
+            // Save everyone and exit cleanly on SIGTERM / Ctrl+C
+            .add_systems(Update, shutdown::run_shutdown.after(auth::handle_client_disconnect))
+            // Physics sync - runs after physics update to sync PhysicsPosition -> Position
+            .add_systems(PostUpdate, sync_physics_to_position);
+    }
+}
+
+/// Syncs Avian's PhysicsPosition to our replicated Position component
+/// This runs after physics updates so clients get the physics-driven positions
+/// Uses change detection to only sync when physics actually moved the entity
+fn sync_physics_to_position(
+    mut query: Query<(&PhysicsPosition, &mut SharedPosition), Changed<PhysicsPosition>>,
+) {
+    for (physics_pos, mut position) in &mut query {
+        position.0 = physics_pos.0;
+    }
+}
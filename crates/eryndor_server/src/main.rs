@@ -12,37 +12,14 @@
 #![allow(clippy::items_after_test_module)]
 #![allow(clippy::unnecessary_unwrap)]
 
-mod abilities;
-mod animation;
-mod admin;
-mod admin_cli;
-mod assets;
-mod audit;
-mod auth;
-mod character;
-mod combat;
-mod config;
-mod dashboard;
-mod database;
-mod editor_api;
-mod game_data;
-mod inventory;
-mod moderation;
-mod movement;
-mod quest;
-mod spawn;
-mod trainer;
-mod weapon;
-mod world;
-
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use bevy_replicon_renet2::RepliconRenetPlugins;
-use avian2d::prelude::*;
-use governor::{Quota, RateLimiter, state::keyed::DefaultKeyedStateStore};
-use std::num::NonZeroU32;
-use std::net::IpAddr;
 
+use eryndor_server::{
+    admin_api, admin_cli, auth, config, console, database, editor_api, events, metrics, shutdown,
+    GameServerPlugin,
+};
 use eryndor_shared::*;
 
 // Resource to keep tokio runtime alive for WebTransport/WebSocket servers
@@ -51,32 +28,6 @@ use eryndor_shared::*;
 #[derive(Resource)]
 struct TokioRuntimeResource(tokio::runtime::Runtime);
 
-// Rate limiters for security (per-IP rate limiting)
-#[derive(Resource)]
-pub struct RateLimiters {
-    pub account_creation: RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, governor::clock::DefaultClock>,
-    pub login_attempts: RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, governor::clock::DefaultClock>,
-}
-
-impl RateLimiters {
-    fn from_config(config: &config::ServerConfig) -> Self {
-        Self {
-            account_creation: RateLimiter::keyed(
-                Quota::per_hour(NonZeroU32::new(config.rate_limits.account_creation_per_hour).unwrap())
-            ),
-            login_attempts: RateLimiter::keyed(
-                Quota::per_hour(NonZeroU32::new(config.rate_limits.login_attempts_per_hour).unwrap())
-            ),
-        }
-    }
-}
-
-// Disambiguate Position - use our custom one for replication
-use eryndor_shared::Position as SharedPosition;
-// Import Avian physics components for movement
-use avian2d::prelude::Position as PhysicsPosition;
-use avian2d::prelude::LinearVelocity as PhysicsVelocity;
-
 fn main() {
     // Initialize early logging BEFORE any other operations
     // This ensures we can see errors during config loading
@@ -123,10 +74,26 @@ fn main() {
         }
     };
 
+    // Load the world event schedule from events.toml
+    let event_scheduler = match events::EventScheduler::load() {
+        Ok(scheduler) => scheduler,
+        Err(e) => {
+            eprintln!("FATAL ERROR: Failed to load event schedule: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     App::new()
         .add_plugins((
             MinimalPlugins,
-            bevy::log::LogPlugin::default(),
+            // Span timings can be recorded to a Chrome trace at runtime (/trace start), and the
+            // console's set-loglevel swaps the filter in front of it. That filter decides what's
+            // logged, so LogPlugin's own level lets everything through.
+            bevy::log::LogPlugin {
+                level: bevy::log::Level::TRACE,
+                custom_layer: console::log_layers,
+                ..default()
+            },
             bevy::state::app::StatesPlugin,
             bevy::asset::AssetPlugin {
                 // Use ASSETS_PATH env var if set (Docker: "assets"), otherwise use workspace path for local dev
@@ -134,206 +101,32 @@ fn main() {
                 watch_for_changes_override: Some(true),  // Enable automatic hot-reload
                 ..Default::default()
             },
-            RepliconPlugins,
+            // Server tick is advanced manually by replication::advance_replication_tick at the configured rate
+            RepliconPlugins.set(ServerPlugin {
+                tick_schedule: None,
+                ..Default::default()
+            }),
         ))
         .add_plugins(RepliconRenetPlugins)
-        // Physics - server-authoritative with fixed timestep
-        // Use headless mode - bevy_scene feature disabled in Cargo.toml
-        .add_plugins(PhysicsPlugins::default().with_length_unit(1.0))
-        .insert_resource(Gravity(Vec2::ZERO))  // Top-down game, no gravity
-        .insert_resource(Time::<Fixed>::from_hz(60.0))  // 60 Hz physics tick rate
-        // Asset loading system for JSON content
-        .add_plugins(assets::GameAssetsPlugin)
-        // Configuration
-        .insert_resource(RateLimiters::from_config(&config))
-        .insert_resource(config)
-        // Database
-        .init_resource::<database::DatabaseConnection>()
-        // Game data resources
-        .init_resource::<abilities::AbilityDatabase>()
-        .init_resource::<game_data::ItemDatabase>()
-        .init_resource::<game_data::QuestDatabase>()
-        .init_resource::<game_data::TrainerDatabase>()
-        .init_resource::<game_data::EnemyDatabase>()
-        // Register replicated components
-        .replicate::<Player>()
-        .replicate::<Character>()
-        .replicate::<OwnedBy>()
-        .replicate::<SharedPosition>()
-        .replicate::<Experience>()
-        .replicate::<WeaponProficiencyExp>()
-        .replicate::<ArmorProficiency>()
-        .replicate::<ArmorProficiencyExp>()
-        .replicate::<UnlockedArmorPassives>()
-        .replicate::<Velocity>()
-        .replicate::<MoveSpeed>()
-        .replicate::<AnimationState>()
-        .replicate::<Health>()
-        .replicate::<Mana>()
-        .replicate::<HealthRegen>()
-        .replicate::<ManaRegen>()
-        .replicate::<CombatStats>()
-        .replicate::<CurrentTarget>()
-        .replicate::<InCombat>()
-        .replicate::<AutoAttack>()
-        .replicate::<WeaponProficiency>()
-        .replicate::<Gold>()
-        .replicate::<Inventory>()
-        .replicate::<Equipment>()
-        .replicate::<Hotbar>()
-        .replicate::<LearnedAbilities>()
-        .replicate::<QuestLog>()
-        .replicate::<Npc>()
-        .replicate::<QuestGiver>()
-        .replicate::<NpcName>()
-        .replicate::<Enemy>()
-        .replicate::<EnemyType>()
-        .replicate::<EnemyName>()
-        .replicate::<AiState>()
-        .replicate::<WorldItem>()
-        .replicate::<GoldDrop>()
-        .replicate::<LootContainer>()
-        .replicate::<Interactable>()
-        .replicate::<VisualShape>()
-        .replicate::<ActiveBuffs>()
-        .replicate::<ActiveDebuffs>()
-        .replicate::<ActiveDoTs>()
-        // Register client -> server events (Events API)
-        .add_client_event::<LoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateAccountRequest>(Channel::Ordered)
-        .add_client_event::<OAuthLoginRequest>(Channel::Ordered)
-        .add_client_event::<CreateCharacterRequest>(Channel::Ordered)
-        .add_client_event::<SelectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<MoveInput>(Channel::Unreliable)
-        .add_mapped_client_event::<SetTargetRequest>(Channel::Ordered)
-        .add_client_event::<UseAbilityRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PickupItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<OpenLootContainerRequest>(Channel::Ordered)
-        .add_mapped_client_event::<LootItemRequest>(Channel::Ordered)
-        .add_client_event::<AutoLootRequest>(Channel::Ordered)
-        .add_client_event::<DropItemRequest>(Channel::Ordered)
-        .add_client_event::<EquipItemRequest>(Channel::Ordered)
-        .add_client_event::<UnequipItemRequest>(Channel::Ordered)
-        .add_mapped_client_event::<InteractNpcRequest>(Channel::Ordered)
-        .add_client_event::<AcceptQuestRequest>(Channel::Ordered)
-        .add_client_event::<CompleteQuestRequest>(Channel::Ordered)
-        .add_mapped_client_event::<PurchaseFromTrainerRequest>(Channel::Ordered)
-        .add_client_event::<SetHotbarSlotRequest>(Channel::Ordered)
-        .add_client_event::<DisconnectCharacterRequest>(Channel::Ordered)
-        .add_client_event::<AdminCommandRequest>(Channel::Ordered)
-        .add_client_event::<SendChatMessage>(Channel::Ordered)
-        // Dashboard query events
-        .add_client_event::<GetPlayerListRequest>(Channel::Ordered)
-        .add_client_event::<GetBanListRequest>(Channel::Ordered)
-        .add_client_event::<GetServerStatsRequest>(Channel::Ordered)
-        .add_client_event::<GetAuditLogsRequest>(Channel::Ordered)
-        // Register server -> client events (Events API)
-        .add_server_event::<LoginResponse>(Channel::Ordered)
-        .add_server_event::<CreateAccountResponse>(Channel::Ordered)
-        .add_server_event::<OAuthLoginResponse>(Channel::Ordered)
-        .add_server_event::<CharacterListResponse>(Channel::Ordered)
-        .add_server_event::<CreateCharacterResponse>(Channel::Ordered)
-        .add_server_event::<SelectCharacterResponse>(Channel::Ordered)
-        .add_server_event::<CombatEvent>(Channel::Ordered)
-        .add_server_event::<QuestUpdateEvent>(Channel::Ordered)
-        .add_server_event::<DeathEvent>(Channel::Ordered)
-        .add_server_event::<NotificationEvent>(Channel::Ordered)
-        .add_server_event::<QuestDialogueEvent>(Channel::Ordered)
-        .add_server_event::<TrainerDialogueEvent>(Channel::Ordered)
-        .add_mapped_server_event::<LootContainerContentsEvent>(Channel::Ordered)
-        .add_server_event::<LevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ProficiencyLevelUpEvent>(Channel::Ordered)
-        .add_server_event::<ChatMessage>(Channel::Ordered)
-        // Dashboard response events
-        .add_server_event::<PlayerListResponse>(Channel::Ordered)
-        .add_server_event::<BanListResponse>(Channel::Ordered)
-        .add_server_event::<ServerStatsResponse>(Channel::Ordered)
-        .add_server_event::<AuditLogsResponse>(Channel::Ordered)
-        // Register observers for client triggers
-        .add_observer(auth::handle_login)
-        .add_observer(auth::handle_create_account)
-        .add_observer(auth::handle_oauth_login)
-        .add_observer(auth::handle_create_character)
-        .add_observer(auth::handle_select_character)
-        .add_observer(movement::handle_move_input)
-        .add_observer(combat::handle_set_target)
-        .add_observer(combat::handle_use_ability)
-        .add_observer(inventory::handle_pickup_item)
-        .add_observer(inventory::handle_drop_item)
-        .add_observer(inventory::handle_equip_item)
-        .add_observer(inventory::handle_unequip_item)
-        .add_observer(inventory::handle_set_hotbar_slot)
-        .add_observer(inventory::handle_open_loot_container)
-        .add_observer(inventory::handle_loot_item)
-        .add_observer(inventory::handle_auto_loot)
-        .add_observer(quest::handle_interact_npc)
-        .add_observer(quest::handle_accept_quest)
-        .add_observer(quest::handle_complete_quest)
-        .add_observer(trainer::handle_purchase_from_trainer)
-        .add_observer(auth::handle_disconnect_character)
-        .add_observer(admin::handle_admin_command)
-        .add_observer(admin::handle_chat_message)
-        // Dashboard observers
-        .add_observer(dashboard::handle_get_player_list)
-        .add_observer(dashboard::handle_get_ban_list)
-        .add_observer(dashboard::handle_get_server_stats)
-        .add_observer(dashboard::handle_get_audit_logs)
-        // Respawn system
-        .add_observer(spawn::schedule_respawn)
-        // Systems
-        .add_systems(Startup, (
-            setup_server,
-            database::setup_database,
-        ))
-        // Spawn world boundaries at startup (doesn't depend on JSON data)
-        .add_systems(Startup, world::spawn_world_boundaries)
-        // Spawn NPCs and enemies when zone data is loaded (legacy - will be removed)
-        .add_systems(Update, world::spawn_world.run_if(world::zone_data_loaded))
-        .add_systems(Update, (
-            // Connection tracking (must run first to capture IPs)
-            auth::track_client_connections,
-            // Auth systems
-            auth::handle_client_disconnect,
-            // Movement
-            movement::update_positions,
-            // Animation state updates
-            animation::update_animation_states,
-            animation::update_enemy_animation_states,
-            // Combat
-            combat::update_ai_activation_delays,
-            combat::process_auto_attacks,
-            combat::update_ability_cooldowns,
-            combat::regenerate_resources,
-            combat::check_deaths,
-            combat::check_level_ups,
-            combat::check_weapon_proficiency_level_ups,
-            combat::enemy_ai,
-            // Ability effects
-            abilities::process_buffs_and_debuffs,
-            abilities::process_dots,
-            // Quests
-            quest::update_quest_progress,
-            // Respawn
-            spawn::process_respawns,
-        ))
-        // Physics sync - runs after physics update to sync PhysicsPosition -> Position
-        .add_systems(PostUpdate, sync_physics_to_position)
+        .insert_resource(event_scheduler)
+        .add_plugins(GameServerPlugin { config })
+        // Connection tracking captures client IPs from the transport
+        .add_systems(Update, auth::track_client_connections)
+        // The HTTP server needs the database pool for the admin API
+        .add_systems(Startup, setup_server.after(database::setup_database))
+        // Runtime commands typed into the server's terminal
+        .add_systems(Startup, console::read_stdin)
         .run();
 }
 
-/// Syncs Avian's PhysicsPosition to our replicated Position component
-/// This runs after physics updates so clients get the physics-driven positions
-/// Uses change detection to only sync when physics actually moved the entity
-fn sync_physics_to_position(
-    mut query: Query<(&PhysicsPosition, &mut SharedPosition), Changed<PhysicsPosition>>,
+fn setup_server(
+    mut commands: Commands,
+    channels: Res<RepliconChannels>,
+    config: Res<config::ServerConfig>,
+    db: Res<database::DatabaseConnection>,
+    admin_bridge: Res<admin_api::AdminApiBridge>,
+    console: Res<console::ServerConsole>,
 ) {
-    for (physics_pos, mut position) in &mut query {
-        position.0 = physics_pos.0;
-    }
-}
-
-
-fn setup_server(mut commands: Commands, channels: Res<RepliconChannels>) {
     info!("Starting Eryndor MMO Server with multi-transport support...");
 
     use bevy_renet2::prelude::{RenetServer, ConnectionConfig};
@@ -396,13 +189,25 @@ fn setup_server(mut commands: Commands, channels: Res<RepliconChannels>) {
     let ws_socket = BoxedSocket::new(ws_server);
     info!("WebSocket socket listening on {}", ws_addr);
 
+    // Web admin dashboard shares the HTTP server
+    let admin_state = match db.pool() {
+        Some(pool) if config.admin.dashboard_enabled => {
+            Some(admin_api::AdminApiState::new(pool.clone(), admin_bridge.clone(), console.clone(), &config))
+        }
+        _ => None,
+    };
+
     // Spawn HTTP server to serve certificate hash for WASM clients
     // Must run on tokio runtime (not Bevy's IoTaskPool which uses async-executor)
     let cert_hash_clone = cert_hash.clone();
+    let metrics_enabled = config.metrics.enabled;
     tokio_handle.spawn(async move {
-        serve_cert_hash(cert_hash_clone).await;
+        serve_cert_hash(cert_hash_clone, admin_state, metrics_enabled).await;
     });
 
+    // SIGTERM / Ctrl+C start a graceful shutdown instead of killing the process
+    tokio_handle.spawn(shutdown::listen_for_signals());
+
     // Register all three socket addresses
     let server_config = ServerSetupConfig {
         current_time,
@@ -433,15 +238,19 @@ fn setup_server(mut commands: Commands, channels: Res<RepliconChannels>) {
     info!("Server ready - UDP: {}, WebTransport: {}, WebSocket: {}", udp_addr, wt_addr, ws_addr);
 }
 
-// HTTP server to serve WebTransport certificate hash and editor API
-async fn serve_cert_hash(cert_hash: bevy_renet2::netcode::ServerCertHash) {
+// HTTP server to serve WebTransport certificate hash, editor API, admin dashboard and metrics
+async fn serve_cert_hash(
+    cert_hash: bevy_renet2::netcode::ServerCertHash,
+    admin_state: Option<admin_api::AdminApiState>,
+    metrics_enabled: bool,
+) {
     use axum::{routing::get, Router, Json};
     use tower_http::cors::{CorsLayer, Any};
 
     // Create editor API router
     let editor_router = editor_api::create_editor_router();
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/cert", get(move || async move {
             // Return the hash bytes as JSON array (matching renet2 example pattern)
             Json(cert_hash.hash.to_vec())
@@ -455,6 +264,24 @@ async fn serve_cert_hash(cert_hash: bevy_renet2::netcode::ServerCertHash) {
                 .allow_headers(Any)
         );
 
+    // Prometheus scrape endpoint
+    if metrics_enabled {
+        app = app.route("/metrics", get(|| async {
+            (
+                [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                metrics::METRICS.render(),
+            )
+        }));
+    }
+
+    // Mount the admin dashboard at /admin and its API at /api/admin
+    let admin_enabled = admin_state.is_some();
+    if let Some(admin_state) = admin_state {
+        app = app
+            .route("/admin", get(admin_api::dashboard_page))
+            .nest("/api/admin", admin_api::create_admin_router(admin_state));
+    }
+
     let cert_server_addr = format!("{}:{}",
         eryndor_shared::constants::server_addr(),
         eryndor_shared::constants::server_cert_port()
@@ -466,8 +293,15 @@ async fn serve_cert_hash(cert_hash: bevy_renet2::netcode::ServerCertHash) {
     info!("HTTP server listening on http://{}", cert_server_addr);
     info!("  - Certificate hash: /cert");
     info!("  - Editor API: /api/editor/*");
+    if admin_enabled {
+        info!("  - Admin dashboard: /admin");
+    }
+    if metrics_enabled {
+        info!("  - Metrics: /metrics");
+    }
 
-    axum::serve(listener, app)
+    // Connect info gives the admin login rate limiter the caller's IP
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
         .await
         .expect("Failed to start HTTP server");
 }
@@ -0,0 +1,270 @@
+//! Server health metrics in the Prometheus text format.
+//!
+//! Metrics live in a process-wide registry so database calls on tokio and systems in the
+//! game loop can record into it without threading a resource through. The HTTP server
+//! exposes the registry at `/metrics`.
+
+use bevy::prelude::*;
+use bevy_renet2::prelude::RenetServer;
+use bevy_replicon::prelude::*;
+use bevy_replicon::shared::backend::connected_client::NetworkId;
+use eryndor_shared::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+use tracing::Instrument;
+use crate::auth::ActiveCharacterEntity;
+use crate::replication::{collect_client_network_stats, ReplicationStats};
+
+/// Bucket upper bounds (seconds) for tick and system durations
+const TICK_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.0167, 0.025, 0.05, 0.1, 0.25];
+/// Bucket upper bounds (seconds) for database queries
+const QUERY_BUCKETS: &[f64] = &[0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0];
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Cumulative histogram in the Prometheus sense: each bucket counts every observation at or below it
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, self.count);
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let _ = writeln!(out, "{}_sum{} {}", name, braces, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, braces, self.count);
+    }
+}
+
+struct Gauge {
+    help: &'static str,
+    value: f64,
+}
+
+struct MetricsInner {
+    gauges: BTreeMap<&'static str, Gauge>,
+    tick: Histogram,
+    queries: BTreeMap<&'static str, Histogram>,
+    systems: BTreeMap<&'static str, Histogram>,
+}
+
+impl Default for MetricsInner {
+    fn default() -> Self {
+        Self {
+            gauges: BTreeMap::new(),
+            tick: Histogram::new(TICK_BUCKETS),
+            queries: BTreeMap::new(),
+            systems: BTreeMap::new(),
+        }
+    }
+}
+
+/// Registry of everything exported at /metrics
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl Metrics {
+    pub fn set_gauge(&self, name: &'static str, help: &'static str, value: f64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.gauges.insert(name, Gauge { help, value });
+        }
+    }
+
+    pub fn observe_tick(&self, seconds: f64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.tick.observe(seconds);
+        }
+    }
+
+    pub fn observe_query(&self, operation: &'static str, seconds: f64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.queries.entry(operation).or_insert_with(|| Histogram::new(QUERY_BUCKETS)).observe(seconds);
+        }
+    }
+
+    pub fn observe_system(&self, system: &'static str, seconds: f64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.systems.entry(system).or_insert_with(|| Histogram::new(TICK_BUCKETS)).observe(seconds);
+        }
+    }
+
+    /// Everything in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let Ok(inner) = self.inner.lock() else { return String::new() };
+        let mut out = String::new();
+
+        for (name, gauge) in &inner.gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, gauge.help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, gauge.value);
+        }
+
+        let _ = writeln!(out, "# HELP eryndor_tick_duration_seconds Time spent running one server frame");
+        let _ = writeln!(out, "# TYPE eryndor_tick_duration_seconds histogram");
+        inner.tick.render(&mut out, "eryndor_tick_duration_seconds", "");
+
+        let _ = writeln!(out, "# HELP eryndor_db_query_duration_seconds Database call latency by operation");
+        let _ = writeln!(out, "# TYPE eryndor_db_query_duration_seconds histogram");
+        for (operation, histogram) in &inner.queries {
+            histogram.render(&mut out, "eryndor_db_query_duration_seconds", &format!("operation=\"{}\"", operation));
+        }
+
+        let _ = writeln!(out, "# HELP eryndor_system_duration_seconds Run time of instrumented game systems");
+        let _ = writeln!(out, "# TYPE eryndor_system_duration_seconds histogram");
+        for (system, histogram) in &inner.systems {
+            histogram.render(&mut out, "eryndor_system_duration_seconds", &format!("system=\"{}\"", system));
+        }
+
+        out
+    }
+}
+
+/// Records how long a system ran when dropped, inside a tracing span for profiling
+pub struct SystemTimer {
+    system: &'static str,
+    start: Instant,
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Drop for SystemTimer {
+    fn drop(&mut self) {
+        METRICS.observe_system(self.system, self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// Time the rest of the calling system: `let _timer = metrics::time_system("combat::enemy_ai");`
+pub fn time_system(system: &'static str) -> SystemTimer {
+    SystemTimer {
+        system,
+        start: Instant::now(),
+        _span: tracing::info_span!("system", name = system).entered(),
+    }
+}
+
+/// Time database futures: `database::load_character(&pool, id).timed("load_character").await`
+pub trait TimedQuery: Future + Sized {
+    fn timed(self, operation: &'static str) -> impl Future<Output = Self::Output> {
+        let span = tracing::info_span!("database", name = operation);
+        async move {
+            let start = Instant::now();
+            let output = self.await;
+            METRICS.observe_query(operation, start.elapsed().as_secs_f64());
+            output
+        }
+        .instrument(span)
+    }
+}
+
+impl<F: Future> TimedQuery for F {}
+
+/// When the current frame started
+#[derive(Resource)]
+pub struct TickStart(Instant);
+
+impl Default for TickStart {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+pub fn start_tick(mut tick: ResMut<TickStart>) {
+    tick.0 = Instant::now();
+}
+
+pub fn finish_tick(tick: Res<TickStart>) {
+    METRICS.observe_tick(tick.0.elapsed().as_secs_f64());
+}
+
+/// Refresh entity, client and network gauges
+pub fn update_gauges(
+    entities: Query<Entity>,
+    players: Query<(), With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    characters: Query<&Character>,
+    network_clients: Query<(&NetworkId, Option<&ActiveCharacterEntity>), With<ConnectedClient>>,
+    renet_server: Option<Res<RenetServer>>,
+    replication: Res<ReplicationStats>,
+) {
+    METRICS.set_gauge("eryndor_entities", "Entities in the world", entities.iter().count() as f64);
+    METRICS.set_gauge("eryndor_players_online", "Player characters in the world", players.iter().count() as f64);
+    METRICS.set_gauge("eryndor_enemies", "Enemies in the world", enemies.iter().count() as f64);
+    METRICS.set_gauge("eryndor_connected_clients", "Connected network clients", network_clients.iter().count() as f64);
+    METRICS.set_gauge("eryndor_replicated_entities", "Entities replicated to clients", replication.replicated_entities as f64);
+    METRICS.set_gauge(
+        "eryndor_replication_changes_per_tick",
+        "Average replicated entities changed per snapshot over the last second",
+        replication.entity_changes_per_tick() as f64,
+    );
+
+    let clients = renet_server
+        .map(|server| collect_client_network_stats(&server, &network_clients, &characters, &replication))
+        .unwrap_or_default();
+    METRICS.set_gauge(
+        "eryndor_network_sent_bytes_per_second",
+        "Bytes per second sent to all clients",
+        clients.iter().map(|client| client.bytes_sent_per_sec).sum(),
+    );
+    METRICS.set_gauge(
+        "eryndor_network_received_bytes_per_second",
+        "Bytes per second received from all clients",
+        clients.iter().map(|client| client.bytes_received_per_sec).sum(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new(&[0.01, 0.1]);
+        histogram.observe(0.005);
+        histogram.observe(0.05);
+        histogram.observe(1.0);
+
+        let mut out = String::new();
+        histogram.render(&mut out, "tick", "");
+        assert!(out.contains("tick_bucket{le=\"0.01\"} 1\n"));
+        assert!(out.contains("tick_bucket{le=\"0.1\"} 2\n"));
+        assert!(out.contains("tick_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("tick_count 3\n"));
+    }
+
+    #[test]
+    fn labelled_histograms_render_labels() {
+        let mut histogram = Histogram::new(&[0.01]);
+        histogram.observe(0.001);
+
+        let mut out = String::new();
+        histogram.render(&mut out, "query", "operation=\"load\"");
+        assert!(out.contains("query_bucket{operation=\"load\",le=\"0.01\"} 1\n"));
+        assert!(out.contains("query_count{operation=\"load\"} 1\n"));
+    }
+}
@@ -3,7 +3,9 @@
 // ============================================================================
 // Uses rustrict library for basic profanity and inappropriate content filtering
 
+use eryndor_shared::MAX_GUILD_NAME_LENGTH;
 use rustrict::CensorStr;
+use crate::config::{Chat, FilterSeverity, Moderation};
 
 /// Result of content moderation check
 #[derive(Debug, Clone)]
@@ -146,6 +148,174 @@ pub fn check_character_name(name: &str) -> ModerationResult {
     }
 }
 
+/// Check if a guild name is appropriate
+/// Same characters as character names, but guild names may run longer
+pub fn check_guild_name(name: &str) -> ModerationResult {
+    let result = check_content(name, ModerationLevel::Strict);
+
+    if !result.is_appropriate {
+        return ModerationResult {
+            is_appropriate: false,
+            reason: Some("Guild name contains inappropriate content".to_string()),
+            filtered_text: result.filtered_text,
+        };
+    }
+
+    let trimmed = name.trim();
+
+    if trimmed.chars().count() < 3 {
+        return ModerationResult {
+            is_appropriate: false,
+            reason: Some("Guild name must be at least 3 characters".to_string()),
+            filtered_text: name.to_string(),
+        };
+    }
+
+    if trimmed.chars().count() > MAX_GUILD_NAME_LENGTH {
+        return ModerationResult {
+            is_appropriate: false,
+            reason: Some(format!("Guild name must be no more than {} characters", MAX_GUILD_NAME_LENGTH)),
+            filtered_text: name.to_string(),
+        };
+    }
+
+    if !trimmed.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '\'' || c == '-') || trimmed.contains("  ") {
+        return ModerationResult {
+            is_appropriate: false,
+            reason: Some("Guild name can only contain letters, numbers, single spaces, apostrophes, and hyphens".to_string()),
+            filtered_text: name.to_string(),
+        };
+    }
+
+    ModerationResult {
+        is_appropriate: true,
+        reason: None,
+        filtered_text: trimmed.to_string(),
+    }
+}
+
+/// What the chat filter decided about a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatVerdict {
+    /// Send the message, possibly censored
+    Allow(String),
+    /// Drop the message for its language and give the sender a strike
+    Block(String),
+    /// Drop the message as spam (links, character floods) and give the sender a strike
+    Spam(String),
+    /// Drop the message and mute the sender
+    Mute(String),
+}
+
+/// Top level domains treated as links even without http:// or www.
+const LINK_TLDS: [&str; 12] = ["com", "net", "org", "io", "gg", "co", "uk", "ru", "xyz", "info", "biz", "me"];
+
+/// Run a chat message through the wordlist, link and flood checks, then the profanity filter
+pub fn check_chat_message(text: &str, moderation: &Moderation, chat: &Chat) -> ChatVerdict {
+    let mut filtered = text.to_string();
+
+    // Wordlist: the most severe match decides
+    let mut severity = None;
+    for entry in &chat.filtered_words {
+        let Some(censored) = censor_word(&filtered, &entry.word) else { continue };
+        filtered = censored;
+        severity = severity.max(Some(entry.severity));
+    }
+    match severity {
+        Some(FilterSeverity::Mute) => return ChatVerdict::Mute("Message contains banned language".to_string()),
+        Some(FilterSeverity::Block) => return ChatVerdict::Block("Message contains blocked language".to_string()),
+        Some(FilterSeverity::Censor) | None => {}
+    }
+
+    if chat.block_links {
+        if let Some(domain) = find_link(text) {
+            let allowed = chat.allowed_link_domains.iter().any(|allowed| {
+                let allowed = allowed.to_lowercase();
+                domain == allowed || domain.ends_with(&format!(".{}", allowed))
+            });
+            if !allowed {
+                return ChatVerdict::Spam("Links are not allowed in chat".to_string());
+            }
+        }
+    }
+
+    if longest_run(text) > chat.max_repeated_characters {
+        return ChatVerdict::Spam("Message looks like spam".to_string());
+    }
+
+    if moderation.enable_profanity_filter {
+        let result = check_content(&filtered, ModerationLevel::Moderate);
+        if !result.is_appropriate {
+            if moderation.censor_instead_of_block {
+                filtered = result.filtered_text;
+            } else if moderation.block_profane_messages {
+                return ChatVerdict::Block("Message contains inappropriate content".to_string());
+            }
+        }
+    }
+
+    ChatVerdict::Allow(filtered)
+}
+
+/// Replace whole-word, case-insensitive matches of `word` with asterisks.
+/// Returns None if the word doesn't appear.
+fn censor_word(text: &str, word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    if word.is_empty() {
+        return None;
+    }
+
+    let mut found = false;
+    let censored = text
+        .split_inclusive(|c: char| !c.is_alphanumeric())
+        .map(|token| {
+            let end = token.find(|c: char| !c.is_alphanumeric()).unwrap_or(token.len());
+            let (letters, rest) = token.split_at(end);
+            if letters.to_lowercase() == word {
+                found = true;
+                format!("{}{}", "*".repeat(letters.chars().count()), rest)
+            } else {
+                token.to_string()
+            }
+        })
+        .collect();
+
+    found.then_some(censored)
+}
+
+/// Find the domain of the first link in the text, lowercased
+fn find_link(text: &str) -> Option<String> {
+    text.split_whitespace().find_map(|token| {
+        let token = token.to_lowercase();
+        let token = token.trim_matches(|c: char| !c.is_alphanumeric());
+        let without_scheme = token
+            .strip_prefix("https://")
+            .or_else(|| token.strip_prefix("http://"));
+        let has_scheme = without_scheme.is_some();
+        let rest = without_scheme.unwrap_or(token);
+        let rest = rest.strip_prefix("www.").unwrap_or(rest);
+        let domain = rest.split(['/', '?', '#', ':']).next().unwrap_or(rest);
+
+        let (name, tld) = domain.rsplit_once('.')?;
+        let looks_like_domain = !name.is_empty() && (has_scheme || token.starts_with("www.") || LINK_TLDS.contains(&tld));
+        looks_like_domain.then(|| domain.to_string())
+    })
+}
+
+/// Length of the longest run of one repeated character, ignoring spaces
+fn longest_run(text: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        let c = c.to_ascii_lowercase();
+        current = if previous == Some(c) { current + 1 } else { 1 };
+        previous = Some(c);
+        longest = longest.max(current);
+    }
+    longest
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,9 +353,59 @@ mod tests {
         assert!(result.is_appropriate);
     }
 
+    #[test]
+    fn test_guild_name() {
+        assert_eq!(check_guild_name("  Knights of Dawn ").filtered_text, "Knights of Dawn");
+        assert!(!check_guild_name("KD").is_appropriate);
+        assert!(!check_guild_name("The Very Long Guild Name Of Eryndor").is_appropriate);
+        assert!(!check_guild_name("Knights  of Dawn").is_appropriate);
+        assert!(!check_guild_name("<Knights>").is_appropriate);
+    }
+
     #[test]
     fn test_strict_moderation() {
         let result = check_content("test content", ModerationLevel::Strict);
         assert!(result.is_appropriate);
     }
+
+    fn chat_config(words: &[(&str, FilterSeverity)]) -> (Moderation, Chat) {
+        let moderation = Moderation {
+            enable_profanity_filter: false,
+            block_profane_messages: true,
+            censor_instead_of_block: false,
+        };
+        let chat = Chat {
+            filtered_words: words.iter()
+                .map(|(word, severity)| crate::config::FilteredWord { word: word.to_string(), severity: *severity })
+                .collect(),
+            allowed_link_domains: vec!["eryndor.com".to_string()],
+            ..Chat::default()
+        };
+        (moderation, chat)
+    }
+
+    #[test]
+    fn test_wordlist_severity() {
+        let (moderation, chat) = chat_config(&[("darn", FilterSeverity::Censor), ("goldseller", FilterSeverity::Mute)]);
+        assert_eq!(check_chat_message("Darn, missed!", &moderation, &chat), ChatVerdict::Allow("****, missed!".to_string()));
+        // Only whole words match
+        assert_eq!(check_chat_message("darnation", &moderation, &chat), ChatVerdict::Allow("darnation".to_string()));
+        assert!(matches!(check_chat_message("darn goldseller here", &moderation, &chat), ChatVerdict::Mute(_)));
+    }
+
+    #[test]
+    fn test_link_detection() {
+        let (moderation, chat) = chat_config(&[]);
+        assert!(matches!(check_chat_message("cheap gold at goldz.ru", &moderation, &chat), ChatVerdict::Spam(_)));
+        assert!(matches!(check_chat_message("see https://example.org/page", &moderation, &chat), ChatVerdict::Spam(_)));
+        assert!(matches!(check_chat_message("patch notes on www.eryndor.com", &moderation, &chat), ChatVerdict::Allow(_)));
+        assert!(matches!(check_chat_message("meet at the inn. then go north", &moderation, &chat), ChatVerdict::Allow(_)));
+    }
+
+    #[test]
+    fn test_character_flood() {
+        let (moderation, chat) = chat_config(&[]);
+        assert!(matches!(check_chat_message("lol!!!!!!!!!!!!!!!!!!", &moderation, &chat), ChatVerdict::Spam(_)));
+        assert!(matches!(check_chat_message("nooooo", &moderation, &chat), ChatVerdict::Allow(_)));
+    }
 }
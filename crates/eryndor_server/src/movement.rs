@@ -7,7 +7,7 @@ use avian2d::prelude::LinearVelocity;
 pub fn handle_move_input(
     trigger: On<FromClient<MoveInput>>,
     clients: Query<&ActiveCharacterEntity>,
-    mut players: Query<(&mut Velocity, &mut LinearVelocity, &MoveSpeed, &OwnedBy)>,
+    mut players: Query<(&mut Velocity, &mut LinearVelocity, &MoveSpeed, &OwnedBy, Has<Dead>, Has<Ghost>)>,
 ) {
     let Some(client_entity) = trigger.client_id.entity() else { return };
     let input = trigger.event();
@@ -17,9 +17,17 @@ pub fn handle_move_input(
     let char_entity = active_char.0;
 
     // Update both our custom velocity (for replication) and physics velocity
-    if let Ok((mut velocity, mut physics_velocity, speed, _owner)) = players.get_mut(char_entity) {
+    if let Ok((mut velocity, mut physics_velocity, speed, _owner, is_dead, is_ghost)) = players.get_mut(char_entity) {
+        // The dead lie still until they release; ghosts run faster
+        let speed = if is_dead {
+            0.0
+        } else if is_ghost {
+            speed.0 * GHOST_SPEED_MULTIPLIER
+        } else {
+            speed.0
+        };
         let direction = input.direction.normalize_or_zero();
-        let vel = direction * speed.0;
+        let vel = direction * speed;
         velocity.0 = vel;
         physics_velocity.0 = vel;
     }
@@ -29,6 +37,7 @@ pub fn update_positions(
     mut players: Query<(&mut Position, &Velocity)>,
     time: Res<Time>,
 ) {
+    let _timer = crate::metrics::time_system("movement::update_positions");
     // NOTE: This system is now deprecated and will be removed once physics is fully integrated
     // Physics engine handles position updates via PhysicsPosition
     // The sync_physics_to_position system copies PhysicsPosition -> Position
@@ -0,0 +1,130 @@
+//! Enemy navigation around collision tiles.
+//!
+//! enemy_ai steers straight at its goal. When the zone's walkability grid says that
+//! straight line is blocked, this reroutes the enemy along an A* path instead, keeping
+//! whatever speed enemy_ai chose.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use avian2d::prelude::LinearVelocity;
+use std::collections::VecDeque;
+use crate::behavior::EnemyBehavior;
+use crate::patrol::{enemy_home, PatrolRoute};
+use crate::world::{CurrentZone, STARTER_ZONE};
+use crate::spawn::SpawnPoint;
+use crate::world::ZoneNavigation;
+
+/// Seconds between path recalculations while the goal keeps moving
+const REPATH_INTERVAL: f32 = 0.5;
+
+/// Path an enemy is currently following around obstacles
+#[derive(Component, Default, Debug)]
+pub struct NavPath {
+    waypoints: VecDeque<Vec2>,
+    goal: Vec2,
+    repath_at: f32,
+}
+
+/// Reroute chasing and leashing enemies whose direct line to their goal crosses a wall
+pub fn steer_enemies_along_paths(
+    mut commands: Commands,
+    mut enemies: Query<(
+        Entity,
+        &Position,
+        &AiState,
+        &mut Velocity,
+        &mut LinearVelocity,
+        Option<&mut NavPath>,
+        Option<&SpawnPoint>,
+        Option<&PatrolRoute>,
+        Option<&EnemyBehavior>,
+        Option<&CurrentZone>,
+    ), (With<Enemy>, Without<Player>, Without<AiActivationDelay>)>,
+    players: Query<&Position, (With<Player>, Without<Enemy>)>,
+    navigation: Option<Res<ZoneNavigation>>,
+    time: Res<Time>,
+) {
+    let _timer = crate::metrics::time_system("pathfinding::steer_enemies_along_paths");
+    let Some(navigation) = navigation else { return };
+    let now = time.elapsed_secs();
+
+    for (entity, position, ai_state, mut velocity, mut physics_velocity, nav_path, spawn_point, patrol, behavior, zone) in &mut enemies {
+        let goal = match ai_state {
+            AiState::Chasing(target) => players.get(*target).ok().map(|target_pos| target_pos.0),
+            AiState::Returning => enemy_home(spawn_point, patrol),
+            AiState::Idle | AiState::Attacking(_) => None,
+        };
+
+        // Stationary, or a behavior tree is steering (fleeing) - leave the velocity alone
+        let speed = velocity.0.length();
+        let stationary = speed <= 0.0 || behavior.is_some_and(|b| b.controlling);
+        let Some(goal) = goal.filter(|_| !stationary) else {
+            if nav_path.is_some() {
+                commands.entity(entity).remove::<NavPath>();
+            }
+            continue;
+        };
+
+        let zone_id = zone.map(|z| z.0.as_str()).unwrap_or(STARTER_ZONE);
+        let Some(grid) = navigation.get(zone_id) else { continue };
+
+        let from = (position.0.x, position.0.y);
+        if grid.has_line_of_sight(from, (goal.x, goal.y)) {
+            if let Some(mut nav_path) = nav_path {
+                nav_path.waypoints.clear();
+            }
+            continue;
+        }
+
+        let mut nav_path = match nav_path {
+            Some(nav_path) => nav_path,
+            None => {
+                // Computed now and cached from next frame on
+                let mut new_path = NavPath::default();
+                if plan_path(&mut new_path, grid, position.0, goal, now) {
+                    steer(&mut new_path, position.0, speed, grid.tile_size, &mut velocity, &mut physics_velocity);
+                }
+                commands.entity(entity).insert(new_path);
+                continue;
+            }
+        };
+
+        // Replan at most every REPATH_INTERVAL, and only when the last plan failed or the goal moved
+        let goal_moved = nav_path.goal.distance(goal) > grid.tile_size;
+        if now >= nav_path.repath_at && (nav_path.waypoints.is_empty() || goal_moved) {
+            if !plan_path(&mut nav_path, grid, position.0, goal, now) {
+                continue;
+            }
+        }
+        steer(&mut nav_path, position.0, speed, grid.tile_size, &mut velocity, &mut physics_velocity);
+    }
+}
+
+/// Run A* to the goal. Unreachable goals leave the enemy on its direct course.
+fn plan_path(nav_path: &mut NavPath, grid: &NavGrid, from: Vec2, goal: Vec2, now: f32) -> bool {
+    nav_path.goal = goal;
+    nav_path.repath_at = now + REPATH_INTERVAL;
+    nav_path.waypoints = grid.find_path((from.x, from.y), (goal.x, goal.y), MAX_PATH_SEARCH_NODES)
+        .map(|points| points.into_iter().map(|(x, y)| Vec2::new(x, y)).collect())
+        .unwrap_or_default();
+    !nav_path.waypoints.is_empty()
+}
+
+/// Point the enemy at the next unreached waypoint
+fn steer(
+    nav_path: &mut NavPath,
+    position: Vec2,
+    speed: f32,
+    tile_size: f32,
+    velocity: &mut Velocity,
+    physics_velocity: &mut LinearVelocity,
+) {
+    while nav_path.waypoints.len() > 1 && nav_path.waypoints[0].distance(position) < tile_size / 2.0 {
+        nav_path.waypoints.pop_front();
+    }
+    let Some(next) = nav_path.waypoints.front() else { return };
+
+    let vel = (*next - position).normalize_or_zero() * speed;
+    velocity.0 = vel;
+    physics_velocity.0 = vel;
+}
@@ -0,0 +1,187 @@
+//! Enemies walking authored patrol paths while idle.
+//!
+//! Paths come from the zone (`patrol_paths` or "patrol_path" map objects) and are assigned
+//! per spawn region. Patrol movement uses the same replicated Position as chasing, so clients
+//! get it through the regular snapshot interpolation.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use avian2d::prelude::LinearVelocity;
+use crate::behavior::EnemyBehavior;
+use crate::game_data::{PatrolPathDef, ZoneDatabase};
+use crate::spawn::SpawnPoint;
+use crate::threat::ThreatTable;
+
+/// Patrolling enemies count as arrived within this distance (pixels)
+const WAYPOINT_ARRIVE_DISTANCE: f32 = 6.0;
+
+/// Patrol route an enemy follows while idle
+#[derive(Component, Debug)]
+pub struct PatrolRoute {
+    pub path_id: String,
+    pub waypoints: Vec<Vec2>,
+    pub pauses: Vec<f32>,
+    pub speed_multiplier: f32,
+    pub looped: bool,
+    /// Index of the waypoint being walked to
+    next: usize,
+    /// Walking the path backwards (open paths only)
+    reversing: bool,
+    wait_until: f32,
+    /// Last waypoint reached; leashing enemies return here instead of their spawn point
+    anchor: Vec2,
+}
+
+impl PatrolRoute {
+    pub fn from_definition(def: &PatrolPathDef, start: Vec2) -> Self {
+        let waypoints: Vec<Vec2> = def.waypoints.iter().copied().map(Vec2::from).collect();
+        let pauses = (0..waypoints.len())
+            .map(|i| def.waypoint_pauses.get(i).copied().flatten().unwrap_or(def.pause).max(0.0))
+            .collect();
+
+        // Start at whichever waypoint is closest to where the enemy spawned
+        let next = waypoints.iter()
+            .enumerate()
+            .min_by(|a, b| a.1.distance(start).total_cmp(&b.1.distance(start)))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        Self {
+            path_id: def.path_id.clone(),
+            waypoints,
+            pauses,
+            speed_multiplier: def.speed_multiplier.max(0.0),
+            looped: def.looped,
+            next,
+            reversing: false,
+            wait_until: 0.0,
+            anchor: start,
+        }
+    }
+
+    pub fn anchor(&self) -> Vec2 {
+        self.anchor
+    }
+
+    /// Move on to the following waypoint, bouncing at the ends of open paths
+    fn advance(&mut self) {
+        let last = self.waypoints.len().saturating_sub(1);
+        if last == 0 {
+            return;
+        }
+
+        if self.looped {
+            self.next = (self.next + 1) % self.waypoints.len();
+        } else if self.reversing {
+            if self.next == 0 {
+                self.reversing = false;
+                self.next = 1;
+            } else {
+                self.next -= 1;
+            }
+        } else if self.next == last {
+            self.reversing = true;
+            self.next = last - 1;
+        } else {
+            self.next += 1;
+        }
+    }
+}
+
+/// Where an enemy leashes back to: its patrol route if it has one, otherwise its spawn point
+pub fn enemy_home(spawn_point: Option<&SpawnPoint>, patrol: Option<&PatrolRoute>) -> Option<Vec2> {
+    patrol.map(|route| route.anchor()).or(spawn_point.map(|spawn| spawn.position))
+}
+
+/// Give newly spawned enemies the patrol path of their spawn region
+pub fn attach_patrol_routes(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Position, &SpawnPoint), (With<Enemy>, Added<SpawnPoint>)>,
+    zone_db: Res<ZoneDatabase>,
+) {
+    for (entity, position, spawn_point) in &enemies {
+        let Some(region_id) = spawn_point.region_id.as_deref() else { continue };
+        let Some(path) = zone_db.find_region_patrol_path(region_id) else { continue };
+        if path.waypoints.is_empty() {
+            warn!("Patrol path '{}' has no waypoints", path.path_id);
+            continue;
+        }
+        commands.entity(entity).insert(PatrolRoute::from_definition(&path, position.0));
+    }
+}
+
+/// Walk idle, out-of-combat enemies along their patrol routes
+pub fn follow_patrol_routes(
+    mut enemies: Query<(
+        &Position,
+        &mut Velocity,
+        &mut LinearVelocity,
+        &MoveSpeed,
+        &AiState,
+        &ThreatTable,
+        &mut PatrolRoute,
+        Option<&EnemyBehavior>,
+    ), (With<Enemy>, Without<AiActivationDelay>)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+
+    for (position, mut velocity, mut physics_velocity, move_speed, ai_state, threat, mut route, behavior) in &mut enemies {
+        if !matches!(ai_state, AiState::Idle) || !threat.is_empty() || behavior.is_some_and(|b| b.controlling) {
+            continue;
+        }
+
+        let mut vel = Vec2::ZERO;
+        if now >= route.wait_until {
+            let target = route.waypoints[route.next];
+            let to_target = target - position.0;
+            if to_target.length() <= WAYPOINT_ARRIVE_DISTANCE {
+                route.anchor = target;
+                route.wait_until = now + route.pauses[route.next];
+                route.advance();
+            } else {
+                vel = to_target.normalize() * move_speed.0 * route.speed_multiplier;
+            }
+        }
+
+        velocity.0 = vel;
+        physics_velocity.0 = vel;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data::Vec2Data;
+
+    fn path(looped: bool) -> PatrolPathDef {
+        PatrolPathDef {
+            path_id: "test".to_string(),
+            waypoints: vec![Vec2Data { x: 0.0, y: 0.0 }, Vec2Data { x: 10.0, y: 0.0 }, Vec2Data { x: 20.0, y: 0.0 }],
+            speed_multiplier: 0.5,
+            pause: 1.0,
+            waypoint_pauses: vec![None, Some(3.0)],
+            looped,
+        }
+    }
+
+    #[test]
+    fn open_paths_walk_back_and_forth() {
+        let mut route = PatrolRoute::from_definition(&path(false), Vec2::ZERO);
+        let mut visited = vec![route.next];
+        for _ in 0..5 {
+            route.advance();
+            visited.push(route.next);
+        }
+        assert_eq!(visited, vec![0, 1, 2, 1, 0, 1]);
+        assert_eq!(route.pauses, vec![1.0, 3.0, 1.0]);
+    }
+
+    #[test]
+    fn looped_paths_wrap_around() {
+        let mut route = PatrolRoute::from_definition(&path(true), Vec2::new(19.0, 0.0));
+        assert_eq!(route.next, 2);
+        route.advance();
+        assert_eq!(route.next, 0);
+    }
+}
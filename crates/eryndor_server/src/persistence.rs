@@ -0,0 +1,156 @@
+//! Character persistence shared by disconnects, periodic checkpoints and server shutdown.
+//!
+//! Characters are always saved on disconnect. Checkpoints additionally save any online
+//! character whose state changed since the last checkpoint, so a crash loses at most one
+//! checkpoint interval of progress.
+//!
+//! Saves are queued on the database worker with a snapshot of the character taken when the
+//! save is requested, so the character can despawn while the write is still in flight.
+
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use eryndor_shared::*;
+use crate::database::DbPool;
+use crate::auth::CharacterDatabaseId;
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+
+/// Everything written back to the database when a character is saved
+#[derive(QueryData)]
+pub struct CharacterSaveData {
+    pub entity: Entity,
+    pub owned_by: &'static OwnedBy,
+    pub character: &'static Character,
+    pub db_id: &'static CharacterDatabaseId,
+    pub position: &'static Position,
+    pub health: &'static Health,
+    pub mana: &'static Mana,
+    pub gold: &'static Gold,
+    pub equipment: &'static Equipment,
+    pub inventory: &'static Inventory,
+    pub quest_log: &'static QuestLog,
+    pub experience: &'static Experience,
+    pub weapon_prof: &'static WeaponProficiency,
+    pub weapon_exp: &'static WeaponProficiencyExp,
+    pub armor_prof: &'static ArmorProficiency,
+    pub armor_exp: &'static ArmorProficiencyExp,
+    pub unlocked_passives: &'static UnlockedArmorPassives,
+}
+
+/// Owned copy of everything in `CharacterSaveData`, taken when a save is queued
+pub struct CharacterSnapshot {
+    pub db_id: i64,
+    pub name: String,
+    pub level: u32,
+    pub position: Position,
+    pub health: Health,
+    pub mana: Mana,
+    pub gold: Gold,
+    pub equipment: Equipment,
+    pub inventory: Inventory,
+    pub quest_log: QuestLog,
+    pub experience: Experience,
+    pub weapon_prof: WeaponProficiency,
+    pub weapon_exp: WeaponProficiencyExp,
+    pub armor_prof: ArmorProficiency,
+    pub armor_exp: ArmorProficiencyExp,
+    pub unlocked_passives: UnlockedArmorPassives,
+}
+
+pub fn snapshot(data: &CharacterSaveDataItem) -> CharacterSnapshot {
+    CharacterSnapshot {
+        db_id: data.db_id.0,
+        name: data.character.name.clone(),
+        level: data.character.level,
+        position: *data.position,
+        health: *data.health,
+        mana: *data.mana,
+        gold: *data.gold,
+        equipment: data.equipment.clone(),
+        inventory: data.inventory.clone(),
+        quest_log: data.quest_log.clone(),
+        experience: *data.experience,
+        weapon_prof: data.weapon_prof.clone(),
+        weapon_exp: *data.weapon_exp,
+        armor_prof: *data.armor_prof,
+        armor_exp: *data.armor_exp,
+        unlocked_passives: data.unlocked_passives.clone(),
+    }
+}
+
+/// Save a character's state, position, equipment, inventory, quests and progression.
+/// Every part is attempted even if an earlier one fails.
+pub async fn save_character_data(pool: &DbPool, data: &CharacterSnapshot) -> Result<(), String> {
+    let id = data.db_id;
+    let results = [
+        database::save_character(pool, id, &data.position, &data.health, &data.mana, &data.gold)
+            .timed("save_character").await,
+        database::save_equipment(pool, id, &data.equipment).timed("save_equipment").await,
+        database::save_inventory(pool, id, &data.inventory).timed("save_inventory").await,
+        database::save_quest_log(pool, id, &data.quest_log).timed("save_quest_log").await,
+        database::save_progression(
+            pool,
+            id,
+            data.level,
+            &data.experience,
+            &data.weapon_prof,
+            &data.weapon_exp,
+            &data.armor_prof,
+            &data.armor_exp,
+            &data.unlocked_passives,
+        ).timed("save_progression").await,
+    ];
+
+    let errors: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Queue a save of `data`, logging the outcome with `what` (e.g. "saved on disconnect")
+pub fn queue_save(db: &DatabaseConnection, data: &CharacterSaveDataItem, what: &'static str) -> bool {
+    let snapshot = snapshot(data);
+    db.run(
+        move |pool| async move {
+            let result = save_character_data(&pool, &snapshot).await;
+            (snapshot.name, result)
+        },
+        move |(name, result), _| match result {
+            Ok(_) => debug!("Character '{}' {}", name, what),
+            Err(e) => error!("Failed to save character '{}' ({}): {}", name, what, e),
+        },
+    )
+}
+
+/// Characters with anything worth saving changed since the last checkpoint
+type DirtyCharacter = Or<(
+    Changed<Position>,
+    Changed<Health>,
+    Changed<Mana>,
+    Changed<Gold>,
+    Changed<Equipment>,
+    Changed<Inventory>,
+    Changed<QuestLog>,
+    Changed<Experience>,
+    Changed<WeaponProficiencyExp>,
+    Changed<ArmorProficiencyExp>,
+    Changed<UnlockedArmorPassives>,
+)>;
+
+/// Save characters that changed since this system last ran.
+/// Runs every `persistence.checkpoint_interval_seconds`.
+pub fn checkpoint_characters(
+    characters: Query<CharacterSaveData, DirtyCharacter>,
+    db: Res<DatabaseConnection>,
+) {
+    let _timer = crate::metrics::time_system("persistence::checkpoint_characters");
+
+    let queued = characters.iter()
+        .filter(|data| queue_save(&db, data, "checkpointed"))
+        .count();
+    if queued > 0 {
+        debug!("Queued checkpoint for {} characters", queued);
+    }
+}
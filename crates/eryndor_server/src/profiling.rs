@@ -0,0 +1,175 @@
+//! Chrome trace recording for live performance profiling.
+//!
+//! Instrumented systems and database calls open tracing spans (see `metrics::time_system`
+//! and `metrics::TimedQuery`). While a recording is running, a tracing layer collects every
+//! span enter/exit; stopping the recording writes them as a Chrome trace JSON file that opens
+//! in chrome://tracing, Perfetto or speedscope as a flamegraph. Admins start and stop
+//! recordings with `/trace start` and `/trace stop`.
+
+use bevy::log::tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+use bevy::log::BoxedLayer;
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+
+/// Recordings stop on their own after this many span events (roughly 100 MB)
+const MAX_TRACE_EVENTS: usize = 1_000_000;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static EVENTS: LazyLock<Mutex<Vec<TraceEvent>>> = LazyLock::new(Default::default);
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// One entry in the Chrome trace event format
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    /// "B" for begin, "E" for end
+    ph: &'static str,
+    /// Microseconds since the server started
+    ts: f64,
+    pid: u32,
+    tid: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceFile<'a> {
+    trace_events: &'a [TraceEvent],
+}
+
+/// Display name for a span: its `name` field if it has one, otherwise the span's own name
+struct TraceName(String);
+
+struct NameVisitor(Option<String>);
+
+impl Visit for NameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Tracing layer that records span timings while a recording is running
+pub struct ChromeTraceLayer;
+
+impl ChromeTraceLayer {
+    fn push(&self, name: String, ph: &'static str) {
+        let Ok(mut events) = EVENTS.lock() else { return };
+        if events.len() >= MAX_TRACE_EVENTS {
+            // Don't let a forgotten recording eat the server's memory
+            RECORDING.store(false, Ordering::Relaxed);
+            return;
+        }
+        events.push(TraceEvent {
+            name,
+            ph,
+            ts: EPOCH.elapsed().as_secs_f64() * 1_000_000.0,
+            pid: 1,
+            tid: THREAD_ID.with(|id| *id),
+        });
+    }
+
+    fn span_name<S>(id: &Id, ctx: &Context<'_, S>) -> Option<String>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let span = ctx.span(id)?;
+        let name = span.extensions()
+            .get::<TraceName>()
+            .map(|name| name.0.clone())
+            .unwrap_or_else(|| span.name().to_string());
+        Some(name)
+    }
+}
+
+impl<S> Layer<S> for ChromeTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !RECORDING.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut visitor = NameVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(TraceName(name));
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if RECORDING.load(Ordering::Relaxed) {
+            if let Some(name) = Self::span_name(id, &ctx) {
+                self.push(name, "B");
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if RECORDING.load(Ordering::Relaxed) {
+            if let Some(name) = Self::span_name(id, &ctx) {
+                self.push(name, "E");
+            }
+        }
+    }
+}
+
+/// Hooked into `LogPlugin::custom_layer`
+pub fn chrome_trace_layer(_app: &mut App) -> Option<BoxedLayer> {
+    Some(Box::new(ChromeTraceLayer))
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Start a new recording, discarding anything left from the last one
+pub fn start_recording() -> Result<(), String> {
+    if is_recording() {
+        return Err("A trace is already being recorded".to_string());
+    }
+    if let Ok(mut events) = EVENTS.lock() {
+        events.clear();
+    }
+    RECORDING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop recording and write the trace to `dir`, returning the file and its event count
+pub fn stop_recording(dir: &str) -> Result<(PathBuf, usize), String> {
+    RECORDING.store(false, Ordering::Relaxed);
+
+    let events = EVENTS.lock()
+        .map(|mut events| std::mem::take(&mut *events))
+        .map_err(|_| "Trace buffer unavailable".to_string())?;
+    if events.is_empty() {
+        return Err("No trace is being recorded".to_string());
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+    let path = PathBuf::from(dir).join(format!("trace-{}.json", chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+    let json = serde_json::to_vec(&TraceFile { trace_events: &events })
+        .map_err(|e| format!("Failed to serialize trace: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok((path, events.len()))
+}
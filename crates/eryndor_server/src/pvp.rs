@@ -0,0 +1,276 @@
+//! Duels and open-world PvP.
+//!
+//! Players can only harm each other when `PvpStanding::can_attack` allows it: duel opponents,
+//! or two PvP-flagged players of hostile factions. Duels are fought inside an arena around
+//! where they started and end when someone drops to 1 HP, leaves the arena or disconnects.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use crate::auth::ActiveCharacterEntity;
+
+/// Components that make up a player's `PvpStanding`
+pub type PvpStandingQuery = (Option<&'static Faction>, Has<PvpFlagged>, Option<&'static Dueling>);
+
+/// Open duel challenge, stored on the challenged player
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PendingDuel {
+    pub challenger: Entity,
+    pub expires_at: f32,
+}
+
+/// Counts down before an unflag request clears `PvpFlagged`
+#[derive(Component, Debug)]
+pub struct PvpUnflagTimer(pub Timer);
+
+/// Whether `attacker` may harm the player `target`
+pub fn can_attack(standings: &Query<PvpStandingQuery>, attacker: Entity, target: Entity) -> bool {
+    let standing = |entity| standings.get(entity).ok().map(|(faction, flagged, duel)| PvpStanding {
+        faction: faction.copied().unwrap_or_default(),
+        flagged,
+        duel_opponent: duel.map(|duel| duel.opponent),
+    });
+    match (standing(attacker), standing(target)) {
+        (Some(attacker_standing), Some(target_standing)) => attacker_standing.can_attack(attacker, target, &target_standing),
+        _ => false,
+    }
+}
+
+/// Damage a player through commands, for systems already borrowing player health elsewhere
+pub fn damage_player(commands: &mut Commands, target: Entity, damage: f32) {
+    commands.entity(target).queue(move |mut entity: EntityWorldMut| {
+        if let Some(mut health) = entity.get_mut::<Health>() {
+            health.current = (health.current - damage).max(0.0);
+        }
+    });
+}
+
+fn notify(commands: &mut Commands, client: Entity, message: String, notification_type: NotificationType) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client)),
+        message: NotificationEvent { message, notification_type },
+    });
+}
+
+pub fn handle_duel_challenge(
+    trigger: On<FromClient<DuelChallengeRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    players: Query<(&Position, &Character, &OwnedBy, &Health, Has<Dueling>, Has<PendingDuel>), With<Player>>,
+    time: Res<Time>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let challenger = active_char.0;
+    let target = trigger.event().target;
+
+    let Ok((challenger_pos, challenger_char, _, challenger_health, challenger_dueling, _)) = players.get(challenger) else { return };
+    let Ok((target_pos, target_char, target_owner, target_health, target_dueling, target_pending)) = players.get(target) else {
+        notify(&mut commands, client_entity, "You can only duel other players.".to_string(), NotificationType::Warning);
+        return;
+    };
+
+    let refusal = if challenger == target {
+        Some("You can't duel yourself.".to_string())
+    } else if challenger_dueling || target_dueling {
+        Some(format!("{} is already in a duel.", if challenger_dueling { "You are" } else { target_char.name.as_str() }))
+    } else if challenger_health.is_dead() || target_health.is_dead() {
+        Some("The dead can't duel.".to_string())
+    } else if target_pending {
+        Some(format!("{} is considering another challenge.", target_char.name))
+    } else if challenger_pos.0.distance(target_pos.0) > DUEL_CHALLENGE_RANGE {
+        Some(format!("{} is too far away.", target_char.name))
+    } else {
+        None
+    };
+    if let Some(message) = refusal {
+        notify(&mut commands, client_entity, message, NotificationType::Warning);
+        return;
+    }
+
+    commands.entity(target).insert(PendingDuel {
+        challenger,
+        expires_at: time.elapsed_secs() + DUEL_CHALLENGE_TIMEOUT,
+    });
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(target_owner.0)),
+        message: DuelChallengeEvent { challenger_name: challenger_char.name.clone() },
+    });
+    notify(&mut commands, client_entity, format!("You challenged {} to a duel.", target_char.name), NotificationType::Info);
+    info!("{} challenged {} to a duel", challenger_char.name, target_char.name);
+}
+
+pub fn handle_duel_response(
+    trigger: On<FromClient<DuelResponseRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    challenged: Query<(&PendingDuel, &Position, &Character, &OwnedBy)>,
+    challengers: Query<(&Position, &Character, &OwnedBy, Has<Dueling>), With<Player>>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+
+    let Ok((pending, position, character, owner)) = challenged.get(char_entity) else { return };
+    commands.entity(char_entity).remove::<PendingDuel>();
+
+    let Ok((challenger_pos, challenger_char, challenger_owner, challenger_dueling)) = challengers.get(pending.challenger) else {
+        notify(&mut commands, owner.0, "Your challenger is gone.".to_string(), NotificationType::Warning);
+        return;
+    };
+
+    if !trigger.event().accept {
+        notify(&mut commands, challenger_owner.0, format!("{} declined your duel.", character.name), NotificationType::Info);
+        return;
+    }
+    if challenger_dueling {
+        notify(&mut commands, owner.0, format!("{} is already in a duel.", challenger_char.name), NotificationType::Warning);
+        return;
+    }
+
+    let arena_center = (position.0 + challenger_pos.0) / 2.0;
+    commands.entity(char_entity).insert(Dueling { opponent: pending.challenger, arena_center });
+    commands.entity(pending.challenger).insert(Dueling { opponent: char_entity, arena_center });
+
+    let message = format!("Duel between {} and {} has begun!", challenger_char.name, character.name);
+    notify(&mut commands, owner.0, message.clone(), NotificationType::Info);
+    notify(&mut commands, challenger_owner.0, message, NotificationType::Info);
+    info!("Duel started between {} and {} at {:?}", challenger_char.name, character.name, arena_center);
+}
+
+/// Duelists never die to each other: they stop at 1 HP and lose the duel instead.
+/// Runs before `check_deaths` so it sees every health change first.
+pub fn protect_duelists(mut duelists: Query<&mut Health, (With<Dueling>, Changed<Health>)>) {
+    for mut health in &mut duelists {
+        if health.current < 1.0 {
+            health.current = 1.0;
+        }
+    }
+}
+
+/// End duels that were lost, forfeited by leaving the arena or abandoned, and expire old challenges
+pub fn update_duels(
+    mut commands: Commands,
+    mut duelists: Query<(Entity, &Dueling, &Position, &Health, &Character, &OwnedBy, &mut CurrentTarget, &mut AutoAttack)>,
+    pending: Query<(Entity, &PendingDuel, &OwnedBy)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, challenge, owner) in &pending {
+        if now >= challenge.expires_at {
+            commands.entity(entity).remove::<PendingDuel>();
+            notify(&mut commands, owner.0, "The duel challenge expired.".to_string(), NotificationType::Info);
+        }
+    }
+
+    // (loser, reason) for each duel that ended this frame
+    let mut losers: Vec<(Entity, &'static str)> = Vec::new();
+    for (entity, duel, position, health, ..) in &duelists {
+        if losers.iter().any(|(loser, _)| *loser == entity || *loser == duel.opponent) {
+            continue;
+        }
+        if health.current <= 1.0 {
+            losers.push((entity, "defeated"));
+        } else if position.0.distance(duel.arena_center) > DUEL_ARENA_RADIUS {
+            losers.push((entity, "fled from"));
+        } else if !duelists.contains(duel.opponent) {
+            // Opponent disconnected or was despawned
+            losers.push((duel.opponent, "abandoned"));
+        }
+    }
+
+    for (loser, reason) in losers {
+        let winner = if let Ok((_, duel, ..)) = duelists.get(loser) {
+            Some(duel.opponent)
+        } else {
+            // The loser is gone; find whoever was fighting them
+            duelists.iter().find(|(_, duel, ..)| duel.opponent == loser).map(|(entity, ..)| entity)
+        };
+
+        let loser_name = duelists.get(loser).map(|(.., character, _, _, _)| character.name.clone()).ok();
+        let winner_name = winner.and_then(|winner| duelists.get(winner).ok()).map(|(.., character, _, _, _)| character.name.clone());
+        let message = match (winner_name, loser_name) {
+            (Some(winner), Some(loser)) if reason == "fled from" => format!("{} fled from the duel. {} wins!", loser, winner),
+            (Some(winner), Some(loser)) => format!("{} has defeated {} in a duel!", winner, loser),
+            (Some(winner), None) => format!("Your opponent abandoned the duel. {} wins!", winner),
+            _ => "The duel is over.".to_string(),
+        };
+
+        for entity in [Some(loser), winner].into_iter().flatten() {
+            if let Ok((_, _, _, _, _, owner, mut current_target, mut auto_attack)) = duelists.get_mut(entity) {
+                current_target.0 = None;
+                auto_attack.enabled = false;
+                notify(&mut commands, owner.0, message.clone(), NotificationType::Info);
+                commands.entity(entity).remove::<Dueling>();
+            }
+        }
+        info!("Duel ended ({}): {}", reason, message);
+    }
+}
+
+pub fn handle_set_pvp_flag(
+    trigger: On<FromClient<SetPvpFlagRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    players: Query<Has<PvpFlagged>, With<Player>>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+    let Ok(flagged) = players.get(char_entity) else { return };
+
+    if trigger.event().enabled {
+        commands.entity(char_entity).insert(PvpFlagged).remove::<PvpUnflagTimer>();
+        notify(&mut commands, client_entity, "You are now flagged for PvP.".to_string(), NotificationType::Warning);
+    } else if flagged {
+        // Flagging off lingers so players can't drop out of a fight they started
+        commands.entity(char_entity).insert(PvpUnflagTimer(Timer::from_seconds(PVP_UNFLAG_DELAY, TimerMode::Once)));
+        notify(&mut commands, client_entity, format!("Your PvP flag will clear in {:.0} seconds.", PVP_UNFLAG_DELAY), NotificationType::Info);
+    }
+}
+
+pub fn update_pvp_flags(
+    mut commands: Commands,
+    mut timers: Query<(Entity, &mut PvpUnflagTimer)>,
+    time: Res<Time>,
+) {
+    for (entity, mut timer) in &mut timers {
+        timer.0.tick(time.delta());
+        if timer.0.is_finished() {
+            commands.entity(entity).remove::<(PvpFlagged, PvpUnflagTimer)>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(faction: Faction, flagged: bool, duel_opponent: Option<Entity>) -> PvpStanding {
+        PvpStanding { faction, flagged, duel_opponent }
+    }
+
+    #[test]
+    fn open_world_pvp_needs_both_flagged_and_opposing_factions() {
+        let (a, b) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        let flagged_dawn = standing(Faction::Dawnguard, true, None);
+        let flagged_iron = standing(Faction::Ironclad, true, None);
+
+        assert!(flagged_dawn.can_attack(a, b, &flagged_iron));
+        assert!(!flagged_dawn.can_attack(a, b, &standing(Faction::Ironclad, false, None)));
+        assert!(!flagged_dawn.can_attack(a, b, &standing(Faction::Dawnguard, true, None)));
+        assert!(!flagged_dawn.can_attack(a, a, &flagged_dawn));
+    }
+
+    #[test]
+    fn duelists_only_fight_each_other() {
+        let (a, b, c) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap(), Entity::from_raw_u32(3).unwrap());
+        let a_dueling = standing(Faction::Dawnguard, false, Some(b));
+        let b_dueling = standing(Faction::Dawnguard, false, Some(a));
+        let outsider = standing(Faction::Ironclad, true, None);
+
+        assert!(a_dueling.can_attack(a, b, &b_dueling));
+        assert!(!outsider.can_attack(c, a, &standing(Faction::Dawnguard, true, Some(b))));
+        assert!(!a_dueling.can_attack(a, c, &outsider));
+    }
+}
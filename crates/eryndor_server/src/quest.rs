@@ -2,17 +2,152 @@ use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::auth::ActiveCharacterEntity;
-use crate::game_data::QuestDatabase;
+use crate::game_data::{QuestDatabase, QuestDefinition, DialogueDatabase, DialogueDefinition, ItemDatabase, ShopDatabase};
 use crate::abilities::AbilityDatabase;
+use crate::spatial::SpatialIndex;
+
+/// Dialogue graph an NPC uses when players talk to it
+#[derive(Component, Clone, Debug)]
+pub struct NpcDialogue(pub String);
+
+/// Conversation a player is in, kept while the node they're on has replies to pick from
+#[derive(Component, Clone, Debug)]
+pub struct ActiveDialogue {
+    pub npc: Entity,
+    pub dialogue_id: String,
+    pub node_id: String,
+}
+
+/// Send the node a conversation has reached. A node offering a quest the player can take opens
+/// the quest window and ends the conversation; otherwise the player gets the node's text and the
+/// replies they qualify for.
+fn show_dialogue_node(
+    commands: &mut Commands,
+    client_entity: Entity,
+    char_entity: Entity,
+    conversation: ActiveDialogue,
+    npc_name: &str,
+    dialogue: &DialogueDefinition,
+    quest_log: &QuestLog,
+    level: u32,
+    quest_db: &QuestDatabase,
+) {
+    let Some(node) = dialogue.nodes.get(&conversation.node_id) else {
+        warn!("Dialogue '{}' has no node '{}'", dialogue.id, conversation.node_id);
+        commands.entity(char_entity).remove::<ActiveDialogue>();
+        return;
+    };
+    let speaker = node.speaker.clone().unwrap_or_else(|| npc_name.to_string());
+
+    let offered_quest = node.offer_quest
+        .filter(|quest_id| quest_log.can_accept_quest(*quest_id))
+        .and_then(|quest_id| quest_db.quests.get(&quest_id));
+    if let Some(quest_def) = offered_quest {
+        info!("Dialogue '{}' offering quest {} to player", dialogue.id, quest_def.id);
+        commands.entity(char_entity).remove::<ActiveDialogue>();
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: QuestDialogueEvent {
+                npc_name: speaker,
+                quest_id: quest_def.id,
+                quest_name: quest_def.name.clone(),
+                description: node.text.clone(),
+                objectives_text: format_objectives_text(quest_def),
+                rewards_text: format!("{} XP", quest_def.reward_exp),
+            },
+        });
+        return;
+    }
+
+    let choices: Vec<DialogueOption> = node.available_choices(quest_log, level)
+        .map(|(index, choice)| DialogueOption { index, text: choice.text.clone() })
+        .collect();
+    if choices.is_empty() {
+        commands.entity(char_entity).remove::<ActiveDialogue>();
+    } else {
+        commands.entity(char_entity).insert(conversation);
+    }
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: DialogueNodeEvent {
+            npc_name: speaker,
+            text: node.text.clone(),
+            choices,
+        },
+    });
+}
+
+/// Follow the reply a player picked in their open conversation
+pub fn handle_choose_dialogue_option(
+    trigger: On<FromClient<ChooseDialogueOptionRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    players: Query<(&Position, &QuestLog, &Character, &ActiveDialogue)>,
+    npcs: Query<(&Position, &NpcName), With<Npc>>,
+    dialogue_db: Res<DialogueDatabase>,
+    quest_db: Res<QuestDatabase>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else {
+        return;
+    };
+    let Ok(active_char) = clients.get(client_entity) else {
+        return;
+    };
+    let char_entity = active_char.0;
+    let Ok((player_pos, quest_log, character, conversation)) = players.get(char_entity) else {
+        return;
+    };
+
+    // Walking away or the NPC despawning ends the conversation
+    let npc = npcs.get(conversation.npc).ok()
+        .filter(|(npc_pos, _)| player_pos.0.distance(npc_pos.0) <= INTERACTION_RANGE);
+    let Some((_, npc_name)) = npc else {
+        commands.entity(char_entity).remove::<ActiveDialogue>();
+        return;
+    };
+    let Some(dialogue) = dialogue_db.dialogues.get(&conversation.dialogue_id) else {
+        commands.entity(char_entity).remove::<ActiveDialogue>();
+        return;
+    };
+
+    // Only replies the player was offered can be taken, so conditions are checked again
+    let index = trigger.event().index;
+    let choice = dialogue.nodes.get(&conversation.node_id)
+        .and_then(|node| node.available_choices(quest_log, character.level).find(|(i, _)| *i == index));
+    let Some((_, choice)) = choice else {
+        warn!("Client {:?} picked unavailable reply {} in dialogue '{}'", client_entity, index, dialogue.id);
+        return;
+    };
+
+    match &choice.next {
+        Some(next) => {
+            let next_node = ActiveDialogue { node_id: next.clone(), ..conversation.clone() };
+            show_dialogue_node(&mut commands, client_entity, char_entity, next_node, &npc_name.0, dialogue, quest_log, character.level, &quest_db);
+        }
+        None => {
+            commands.entity(char_entity).remove::<ActiveDialogue>();
+        }
+    }
+}
 
 pub fn handle_interact_npc(
     trigger: On<FromClient<InteractNpcRequest>>,
     mut commands: Commands,
     clients: Query<&ActiveCharacterEntity>,
-    players: Query<(&Position, &QuestLog, &WeaponProficiency, &ArmorProficiency)>,
+    players: Query<(&Position, &QuestLog, &WeaponProficiency, &ArmorProficiency, &Character)>,
     npcs: Query<(Entity, &Position, Option<&QuestGiver>, Option<&Trainer>, &NpcName), With<Npc>>,
+    npc_dialogues: Query<&NpcDialogue>,
+    vendors: Query<&crate::vendor::Vendor>,
+    spirit_healers: Query<(), With<crate::death::SpiritHealer>>,
+    fallen: Query<Has<Ghost>, Or<(With<Dead>, With<Ghost>)>>,
+    player_inventories: Query<(&Inventory, Option<&crate::vendor::Buyback>)>,
     quest_db: Res<QuestDatabase>,
     ability_db: Res<AbilityDatabase>,
+    dialogue_db: Res<DialogueDatabase>,
+    shop_db: Res<ShopDatabase>,
+    item_db: Res<ItemDatabase>,
+    spatial: Res<SpatialIndex>,
+    time: Res<Time>,
 ) {
     info!("=== INTERACT NPC HANDLER CALLED ===");
     let Some(client_entity) = trigger.client_id.entity() else {
@@ -35,7 +170,7 @@ pub fn handle_interact_npc(
     info!("Client sent NPC entity: {:?} (this is a client-side replicated entity)", request.npc_entity);
 
     // Get player position first
-    let Ok((player_pos, quest_log, weapon_prof, armor_prof)) = players.get(char_entity) else {
+    let Ok((player_pos, quest_log, weapon_prof, armor_prof, character)) = players.get(char_entity) else {
         info!("Failed to get player data for character {:?}", char_entity);
         return;
     };
@@ -43,7 +178,7 @@ pub fn handle_interact_npc(
 
     // Find the closest NPC to the player within interaction range
     let mut closest_npc: Option<(Entity, f32, &Position, Option<&QuestGiver>, Option<&Trainer>, &NpcName)> = None;
-    for (entity, npc_pos, quest_giver, trainer, npc_name) in npcs.iter() {
+    for (entity, npc_pos, quest_giver, trainer, npc_name) in spatial.nearby(player_pos.0, INTERACTION_RANGE).filter_map(|entity| npcs.get(entity).ok()) {
         let distance = player_pos.0.distance(npc_pos.0);
         info!("Found NPC '{}' at {:?}, distance: {:.2}", npc_name.0, npc_pos.0, distance);
 
@@ -73,6 +208,40 @@ pub fn handle_interact_npc(
     info!("Found closest NPC: {} at distance {:.2}", npc_name.0, distance);
     info!("Player interacting with NPC: {}", npc_name.0);
 
+    // The dead can only talk to spirit healers, and only once they've released
+    if let Ok(is_ghost) = fallen.get(char_entity) {
+        if is_ghost && spirit_healers.contains(npc_entity) {
+            crate::death::resurrect_at_spirit_healer(&mut commands, char_entity, player_pos.0, time.elapsed_secs());
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: NotificationEvent {
+                    message: format!("{} returns you to life. You suffer from resurrection sickness.", npc_name.0),
+                    notification_type: NotificationType::Info,
+                },
+            });
+        } else {
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: NotificationEvent {
+                    message: "The living cannot hear you.".to_string(),
+                    notification_type: NotificationType::Warning,
+                },
+            });
+        }
+        return;
+    }
+
+    if spirit_healers.contains(npc_entity) {
+        commands.server_trigger(ToClients {
+            mode: SendMode::Direct(ClientId::Client(client_entity)),
+            message: NotificationEvent {
+                message: format!("{} only tends to the spirits of the fallen.", npc_name.0),
+                notification_type: NotificationType::Info,
+            },
+        });
+        return;
+    }
+
     // Check if NPC is a trainer
     if let Some(trainer_comp) = trainer {
         info!("NPC is a trainer with {} items for sale, {} teaching quests",
@@ -129,6 +298,29 @@ pub fn handle_interact_npc(
         return;
     }
 
+    // Vendors open their shop window
+    if let Some(shop) = vendors.get(npc_entity).ok().and_then(|v| shop_db.shops.get(&v.0)) {
+        if let Ok((inventory, buyback)) = player_inventories.get(char_entity) {
+            info!("NPC is a vendor for shop '{}'", shop.id);
+            commands.server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: crate::vendor::build_vendor_window(&npc_name.0, shop, inventory, buyback, &item_db),
+            });
+            return;
+        }
+    }
+
+    // Authored dialogue takes priority over the generic quest giver flow
+    if let Some(dialogue) = npc_dialogues.get(npc_entity).ok().and_then(|d| dialogue_db.dialogues.get(&d.0)) {
+        let conversation = ActiveDialogue {
+            npc: npc_entity,
+            dialogue_id: dialogue.id.clone(),
+            node_id: dialogue.start_node.clone(),
+        };
+        show_dialogue_node(&mut commands, client_entity, char_entity, conversation, &npc_name.0, dialogue, quest_log, character.level, &quest_db);
+        return;
+    }
+
     // Check if NPC is a quest giver
     let Some(quest_giver) = quest_giver else {
         info!("NPC is neither a trainer nor a quest giver");
@@ -169,21 +361,7 @@ pub fn handle_interact_npc(
                 }
 
                 has_available_quest = true;
-                // Format objectives text
-                let objectives_vec: Vec<String> = quest_def.objectives.iter().enumerate()
-                    .map(|(i, obj)| match obj {
-                        crate::game_data::QuestObjective::ObtainItem { item_id, count } => {
-                            format!("{}. Obtain {} x{}", i + 1, item_id, count)
-                        }
-                        crate::game_data::QuestObjective::KillEnemy { enemy_type, count } => {
-                            format!("{}. Kill {} enemies x{}", i + 1, enemy_type, count)
-                        }
-                        crate::game_data::QuestObjective::TalkToNpc { npc_id } => {
-                            format!("{}. Talk to NPC {}", i + 1, npc_id)
-                        }
-                    })
-                    .collect();
-                let objectives_text = objectives_vec.join("\n");
+                let objectives_text = format_objectives_text(quest_def);
 
                 let rewards_text = format!("{} XP", quest_def.reward_exp);
 
@@ -216,6 +394,24 @@ pub fn handle_interact_npc(
     }
 }
 
+/// Format quest objectives as a numbered list for dialogue windows
+fn format_objectives_text(quest_def: &QuestDefinition) -> String {
+    quest_def.objectives.iter().enumerate()
+        .map(|(i, obj)| match obj {
+            crate::game_data::QuestObjective::ObtainItem { item_id, count } => {
+                format!("{}. Obtain {} x{}", i + 1, item_id, count)
+            }
+            crate::game_data::QuestObjective::KillEnemy { enemy_type, count } => {
+                format!("{}. Kill {} enemies x{}", i + 1, enemy_type, count)
+            }
+            crate::game_data::QuestObjective::TalkToNpc { npc_id } => {
+                format!("{}. Talk to NPC {}", i + 1, npc_id)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 pub fn handle_accept_quest(
     trigger: On<FromClient<AcceptQuestRequest>>,
     mut commands: Commands,
@@ -417,6 +613,7 @@ pub fn update_quest_progress(
     mut players: Query<(Entity, &mut QuestLog, &Inventory, &OwnedBy), Changed<Inventory>>,
     quest_db: Res<QuestDatabase>,
 ) {
+    let _timer = crate::metrics::time_system("quest::update_quest_progress");
     for (_entity, mut quest_log, inventory, owned_by) in &mut players {
         for active_quest in &mut quest_log.active_quests {
             let Some(quest_def) = quest_db.quests.get(&active_quest.quest_id) else {
@@ -0,0 +1,466 @@
+//! Message relay between shards.
+//!
+//! Shards are separate processes, so anything players on one shard should see from another
+//! goes through a small hub over TCP: global chat, friends logging in and out, and admin
+//! broadcasts. One process hosts the hub (`relay.hub_bind`) and every shard, the host
+//! included, keeps a connection to it and reconnects if it drops. The hub forwards each
+//! message to every shard but the one that sent it.
+//!
+//! Messages are newline-delimited JSON, at most `MAX_LINE_BYTES` a line. A connection opens
+//! with a hello carrying the shared secret; the hub drops connections that get it wrong or
+//! don't send it within `HELLO_TIMEOUT`. Messages sent while the hub is unreachable are dropped
+//! rather than delivered late.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use serde::{Deserialize, Serialize};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc as tokio_mpsc};
+use crate::config::ServerConfig;
+use crate::social::{announce_presence, FriendList, OnlineCharacters, RemoteCharacter};
+
+/// How long a shard waits before trying the hub again
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Messages the hub holds for a shard that is slow to read before dropping them
+const HUB_BACKLOG: usize = 1024;
+
+/// Longest line either end accepts, newline included. A full roster of a busy shard is well
+/// under this.
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// How long the hub waits for a new connection's hello
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something one shard tells the others
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayMessage {
+    /// Global chat from a player on the sending shard
+    GlobalChat { sender: String, message: String },
+    /// A character logged in or out
+    Presence { character_id: i64, name: String, online: bool },
+    /// Every character on the sending shard; replaces whatever was known about it
+    Roster { characters: Vec<(i64, String)> },
+    /// Admin broadcast to every player
+    Broadcast { message: String },
+    /// Sent by the hub when it loses a shard's connection
+    ShardOffline,
+}
+
+/// A message and the shard it came from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RelayEnvelope {
+    pub shard_id: String,
+    pub shard_name: String,
+    pub message: RelayMessage,
+}
+
+/// First line on every connection to the hub
+#[derive(Serialize, Deserialize, Clone)]
+struct Hello {
+    shard_id: String,
+    shard_name: String,
+    secret: String,
+}
+
+/// This shard's connection to the relay. Does nothing unless `relay.enabled` is set.
+#[derive(Resource, Default)]
+pub struct Relay {
+    link: Option<RelayLink>,
+}
+
+struct RelayLink {
+    // Owns the hub and link tasks
+    _runtime: tokio::runtime::Runtime,
+    outgoing: tokio_mpsc::UnboundedSender<RelayMessage>,
+    incoming: Mutex<mpsc::Receiver<RelayEnvelope>>,
+}
+
+impl Relay {
+    /// Pass a message on to the other shards
+    pub fn send(&self, message: RelayMessage) {
+        if let Some(link) = &self.link {
+            let _ = link.outgoing.send(message);
+        }
+    }
+
+    /// Messages from other shards since the last call
+    fn received(&self) -> Vec<RelayEnvelope> {
+        self.link.as_ref()
+            .and_then(|link| link.incoming.lock().ok().map(|incoming| incoming.try_iter().collect()))
+            .unwrap_or_default()
+    }
+}
+
+/// Start the hub (if this shard hosts it) and the connection to it
+pub fn start_relay(mut relay: ResMut<Relay>, config: Res<ServerConfig>) {
+    if !config.relay.enabled {
+        return;
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("relay")
+        .enable_all()
+        .build()
+        .expect("Failed to create relay runtime");
+
+    if !config.relay.hub_bind.is_empty() {
+        let (bind, secret) = (config.relay.hub_bind.clone(), config.relay.secret.clone());
+        runtime.spawn(async move {
+            match TcpListener::bind(&bind).await {
+                Ok(listener) => {
+                    info!("Relay hub listening on {}", bind);
+                    run_hub(listener, secret).await;
+                }
+                Err(e) => error!("Relay hub could not listen on {}: {}", bind, e),
+            }
+        });
+    }
+
+    let hello = Hello {
+        shard_id: config.shard.id.clone(),
+        shard_name: config.shard.name.clone(),
+        secret: config.relay.secret.clone(),
+    };
+    let (outgoing, outgoing_queue) = tokio_mpsc::unbounded_channel();
+    let (incoming_sender, incoming) = mpsc::channel();
+    runtime.spawn(run_link(config.relay.hub_address.clone(), hello, outgoing_queue, incoming_sender));
+
+    relay.link = Some(RelayLink {
+        _runtime: runtime,
+        outgoing,
+        incoming: Mutex::new(incoming),
+    });
+}
+
+/// Act on messages from other shards
+pub fn receive_relay_messages(
+    mut commands: Commands,
+    relay: Res<Relay>,
+    mut online: ResMut<OnlineCharacters>,
+    friend_lists: Query<(Entity, &FriendList, &OwnedBy)>,
+) {
+    for envelope in relay.received() {
+        let RelayEnvelope { shard_id, shard_name, message } = envelope;
+        match message {
+            RelayMessage::GlobalChat { sender, message } => {
+                commands.server_trigger(ToClients {
+                    mode: SendMode::Broadcast,
+                    message: ChatMessage {
+                        sender: format!("{} ({})", sender, shard_name),
+                        message,
+                        channel: ChatChannel::Global,
+                        target: None,
+                    },
+                });
+            }
+            RelayMessage::Presence { character_id, name, online: logged_in } => {
+                if logged_in {
+                    online.remote.insert(character_id, RemoteCharacter { name: name.clone(), shard_id });
+                } else {
+                    online.remote.remove(&character_id);
+                }
+                announce_presence(&mut commands, &online, &friend_lists, None, character_id, &name, logged_in);
+            }
+            RelayMessage::Roster { characters } => {
+                online.remote.retain(|_, character| character.shard_id != shard_id);
+                for (character_id, name) in characters {
+                    online.remote.insert(character_id, RemoteCharacter { name, shard_id: shard_id.clone() });
+                }
+            }
+            RelayMessage::Broadcast { message } => {
+                commands.server_trigger(ToClients {
+                    mode: SendMode::Broadcast,
+                    message: NotificationEvent {
+                        message: format!("[ADMIN BROADCAST] {}", message),
+                        notification_type: NotificationType::Warning,
+                    },
+                });
+            }
+            RelayMessage::ShardOffline => {
+                info!("Shard '{}' dropped off the relay", shard_id);
+                online.remote.retain(|_, character| character.shard_id != shard_id);
+            }
+        }
+    }
+}
+
+/// Tell the other shards who is online here. Presence messages cover changes as they
+/// happen; the roster catches shards up after they (or this one) reconnect.
+pub fn publish_roster(relay: Res<Relay>, online: Res<OnlineCharacters>) {
+    let characters = online.characters.values()
+        .map(|character| (character.character_id, character.name.clone()))
+        .collect();
+    relay.send(RelayMessage::Roster { characters });
+}
+
+/// Newline-delimited reader that gives up on lines over `MAX_LINE_BYTES` instead of buffering
+/// whatever the other end sends
+struct LineReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin> LineReader<R> {
+    fn new(reader: R) -> Self {
+        Self { reader, buffer: Vec::new() }
+    }
+
+    /// The next line without its newline, or None once the stream ends. Safe to use in
+    /// `select!`: a partly read line is kept for the next call.
+    async fn next_line(&mut self) -> Result<Option<String>, String> {
+        let limit = MAX_LINE_BYTES.saturating_sub(self.buffer.len()) as u64;
+        let read = (&mut self.reader).take(limit)
+            .read_until(b'\n', &mut self.buffer).await
+            .map_err(|e| e.to_string())?;
+        if read == 0 && self.buffer.is_empty() {
+            return Ok(None);
+        }
+        if self.buffer.last() != Some(&b'\n') && self.buffer.len() >= MAX_LINE_BYTES {
+            return Err(format!("line longer than {} bytes", MAX_LINE_BYTES));
+        }
+
+        let line = String::from_utf8(std::mem::take(&mut self.buffer)).map_err(|e| e.to_string())?;
+        Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+}
+
+/// Compare every byte whatever the first difference, so how quickly a wrong secret is turned
+/// away doesn't tell how much of it was right
+fn secrets_match(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    let mut difference = given.len() ^ expected.len();
+    for (i, &byte) in expected.iter().enumerate() {
+        difference |= (byte ^ given.get(i).copied().unwrap_or(0)) as usize;
+    }
+    std::hint::black_box(difference) == 0
+}
+
+async fn write_line<T: Serialize>(writer: &mut (impl AsyncWrite + Unpin), value: &T) -> Result<(), String> {
+    let mut line = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await.map_err(|e| e.to_string())
+}
+
+/// Accept shard connections and forward what each one sends to all the others
+async fn run_hub(listener: TcpListener, secret: String) {
+    let (bus, _) = broadcast::channel::<RelayEnvelope>(HUB_BACKLOG);
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Relay hub failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let (bus, secret) = (bus.clone(), secret.clone());
+        tokio::spawn(async move {
+            if let Err(e) = serve_shard(stream, bus, &secret).await {
+                warn!("Relay connection from {} closed: {}", addr, e);
+            }
+        });
+    }
+}
+
+/// One shard's connection to the hub
+async fn serve_shard(stream: TcpStream, bus: broadcast::Sender<RelayEnvelope>, secret: &str) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = LineReader::new(BufReader::new(reader));
+
+    let hello = tokio::time::timeout(HELLO_TIMEOUT, lines.next_line()).await
+        .map_err(|_| "no hello in time".to_string())??;
+    let hello: Hello = match hello {
+        Some(line) => serde_json::from_str(&line).map_err(|e| format!("bad hello: {}", e))?,
+        None => return Ok(()),
+    };
+    if !secrets_match(&hello.secret, secret) {
+        return Err(format!("shard '{}' sent the wrong secret", hello.shard_id));
+    }
+    info!("Shard '{}' joined the relay", hello.shard_id);
+
+    let mut forwarded = bus.subscribe();
+    let result = loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => match serde_json::from_str::<RelayEnvelope>(&line) {
+                    Ok(envelope) if envelope.shard_id == hello.shard_id => {
+                        let _ = bus.send(envelope);
+                    }
+                    Ok(envelope) => warn!("Shard '{}' sent a message as '{}'", hello.shard_id, envelope.shard_id),
+                    Err(e) => warn!("Bad relay message from shard '{}': {}", hello.shard_id, e),
+                },
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            },
+            envelope = forwarded.recv() => match envelope {
+                Ok(envelope) if envelope.shard_id != hello.shard_id => {
+                    if let Err(e) = write_line(&mut writer, &envelope).await {
+                        break Err(e);
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Shard '{}' fell behind; dropped {} relay messages", hello.shard_id, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break Ok(()),
+            },
+        }
+    };
+
+    info!("Shard '{}' left the relay", hello.shard_id);
+    let _ = bus.send(RelayEnvelope {
+        shard_id: hello.shard_id,
+        shard_name: hello.shard_name,
+        message: RelayMessage::ShardOffline,
+    });
+    result
+}
+
+/// Keep this shard connected to the hub for as long as the relay exists
+async fn run_link(
+    hub_address: String,
+    hello: Hello,
+    mut outgoing: tokio_mpsc::UnboundedReceiver<RelayMessage>,
+    incoming: mpsc::Sender<RelayEnvelope>,
+) {
+    loop {
+        match TcpStream::connect(&hub_address).await {
+            Ok(stream) => {
+                info!("Connected to relay hub at {}", hub_address);
+                match exchange(stream, &hello, &mut outgoing, &incoming).await {
+                    // The server is shutting down
+                    Ok(()) => return,
+                    Err(e) => warn!("Lost the relay hub connection: {}", e),
+                }
+            }
+            Err(e) => warn!("Could not reach relay hub at {}: {}", hub_address, e),
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+        // Anything said while the hub was unreachable is stale by now
+        while outgoing.try_recv().is_ok() {}
+    }
+}
+
+/// Send this shard's messages and pass on everyone else's until the connection drops.
+/// Returns Ok once the game side has gone away.
+async fn exchange(
+    stream: TcpStream,
+    hello: &Hello,
+    outgoing: &mut tokio_mpsc::UnboundedReceiver<RelayMessage>,
+    incoming: &mpsc::Sender<RelayEnvelope>,
+) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = LineReader::new(BufReader::new(reader));
+    write_line(&mut writer, hello).await?;
+
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => {
+                let Some(message) = message else { return Ok(()) };
+                let envelope = RelayEnvelope {
+                    shard_id: hello.shard_id.clone(),
+                    shard_name: hello.shard_name.clone(),
+                    message,
+                };
+                write_line(&mut writer, &envelope).await?;
+            }
+            line = lines.next_line() => {
+                let line = line?.ok_or("hub closed the connection")?;
+                match serde_json::from_str(&line) {
+                    Ok(envelope) => {
+                        if incoming.send(envelope).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Err(e) => warn!("Bad relay message from the hub: {}", e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(shard_id: &str, secret: &str) -> Hello {
+        Hello { shard_id: shard_id.to_string(), shard_name: shard_id.to_uppercase(), secret: secret.to_string() }
+    }
+
+    /// Connect a shard to the hub, returning its outgoing queue and what it receives
+    fn link(
+        address: std::net::SocketAddr,
+        hello: Hello,
+    ) -> (tokio_mpsc::UnboundedSender<RelayMessage>, tokio_mpsc::UnboundedReceiver<RelayEnvelope>) {
+        let (outgoing, outgoing_queue) = tokio_mpsc::unbounded_channel();
+        let (incoming_sender, incoming) = mpsc::channel();
+        tokio::spawn(run_link(address.to_string(), hello, outgoing_queue, incoming_sender));
+
+        // Hand received messages over to async code
+        let (received_sender, received) = tokio_mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            for envelope in incoming {
+                if received_sender.send(envelope).is_err() {
+                    break;
+                }
+            }
+        });
+        (outgoing, received)
+    }
+
+    #[tokio::test]
+    async fn hub_forwards_to_other_shards_only() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(run_hub(listener, "secret".to_string()));
+
+        let (north, mut north_received) = link(address, hello("north", "secret"));
+        let (_south, mut south_received) = link(address, hello("south", "secret"));
+        let (_intruder, mut intruder_received) = link(address, hello("intruder", "guess"));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let chat = RelayMessage::GlobalChat { sender: "Aria".to_string(), message: "hello".to_string() };
+        north.send(chat.clone()).unwrap();
+
+        let envelope = tokio::time::timeout(Duration::from_secs(5), south_received.recv()).await.unwrap().unwrap();
+        assert_eq!(envelope, RelayEnvelope {
+            shard_id: "north".to_string(),
+            shard_name: "NORTH".to_string(),
+            message: chat,
+        });
+
+        // Nothing comes back to the sender, and nothing reaches a shard with the wrong secret
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(north_received.try_recv().is_err());
+        assert!(intruder_received.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn overlong_lines_are_refused() {
+        let mut input = "a".repeat(MAX_LINE_BYTES + 10).into_bytes();
+        input.push(b'\n');
+        let mut lines = LineReader::new(input.as_slice());
+        assert!(lines.next_line().await.is_err());
+
+        let mut lines = LineReader::new("first\r\nsecond".as_bytes());
+        assert_eq!(lines.next_line().await, Ok(Some("first".to_string())));
+        assert_eq!(lines.next_line().await, Ok(Some("second".to_string())));
+        assert_eq!(lines.next_line().await, Ok(None));
+    }
+
+    #[test]
+    fn secrets_must_match_exactly() {
+        assert!(secrets_match("secret", "secret"));
+        assert!(!secrets_match("secre", "secret"));
+        assert!(!secrets_match("secrets", "secret"));
+        assert!(!secrets_match("Secret", "secret"));
+        assert!(!secrets_match("", "secret"));
+    }
+}
@@ -0,0 +1,352 @@
+use bevy::ecs::system::SystemChangeTick;
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use bevy_replicon::shared::backend::connected_client::NetworkId;
+use bevy_renet2::prelude::RenetServer;
+use eryndor_shared::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::marker::PhantomData;
+use crate::auth::ActiveCharacterEntity;
+use crate::config::ServerConfig;
+use crate::spatial::SpatialIndex;
+
+/// Drives replicon's server tick at the configured snapshot rate.
+/// Replicon's own tick schedule is disabled (see `GameServerPlugin`) so this is the only place the tick advances.
+#[derive(Resource)]
+pub struct ReplicationTickTimer(pub Timer);
+
+impl ReplicationTickTimer {
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let interval = 1.0 / config.network.tick_rate.max(1) as f32;
+        Self(Timer::from_seconds(interval, TimerMode::Repeating))
+    }
+}
+
+/// Rolling replication load statistics surfaced through ServerStatsResponse
+#[derive(Resource, Default)]
+pub struct ReplicationStats {
+    pub tick_rate: u32,
+    pub replicated_entities: u32,
+    /// Entities changed since the last snapshot
+    pending_changes: HashSet<Entity>,
+    /// Changed-entity counts of recent snapshots, oldest first (one second's worth)
+    recent_changes: VecDeque<u32>,
+    /// The same per client id, each change weighted by the client's priority for the entity
+    recent_client_changes: HashMap<u64, VecDeque<f32>>,
+}
+
+impl ReplicationStats {
+    pub fn new(tick_rate: u32) -> Self {
+        Self { tick_rate, ..default() }
+    }
+
+    pub fn entity_changes_per_tick(&self) -> f32 {
+        if self.recent_changes.is_empty() {
+            return 0.0;
+        }
+        self.recent_changes.iter().sum::<u32>() as f32 / self.recent_changes.len() as f32
+    }
+
+    /// Average changed entities per snapshot that a client is sent. Far entities count for
+    /// their priority, since they only go out in that share of snapshots.
+    pub fn client_entity_changes_per_tick(&self, client_id: u64) -> f32 {
+        match self.recent_client_changes.get(&client_id) {
+            Some(recent) if !recent.is_empty() => recent.iter().sum::<f32>() / recent.len() as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Close out a snapshot: move the pending changes into the per-second history, overall
+    /// and for each client
+    fn record_snapshot<'a>(&mut self, clients: impl Iterator<Item = (u64, &'a PriorityMap)>) {
+        let window = self.tick_rate.max(1) as usize;
+
+        let mut client_changes = HashMap::new();
+        for (client_id, priorities) in clients {
+            let changes: f32 = self.pending_changes.iter()
+                .map(|entity| priorities.get(entity).copied().unwrap_or(1.0))
+                .sum();
+            let mut recent = self.recent_client_changes.remove(&client_id).unwrap_or_default();
+            push_windowed(&mut recent, changes, window);
+            client_changes.insert(client_id, recent);
+        }
+        // Disconnected clients drop out here
+        self.recent_client_changes = client_changes;
+
+        let changed = self.pending_changes.len() as u32;
+        self.pending_changes.clear();
+        push_windowed(&mut self.recent_changes, changed, window);
+    }
+}
+
+fn push_windowed<T>(recent: &mut VecDeque<T>, value: T, window: usize) {
+    recent.push_back(value);
+    while recent.len() > window {
+        recent.pop_front();
+    }
+}
+
+/// Advance the server tick when the snapshot interval elapses, which makes replicon send a snapshot
+pub fn advance_replication_tick(
+    time: Res<Time>,
+    mut timer: ResMut<ReplicationTickTimer>,
+    mut server_tick: ResMut<ServerTick>,
+    mut stats: ResMut<ReplicationStats>,
+    clients: Query<(&NetworkId, &PriorityMap), With<ConnectedClient>>,
+) {
+    timer.0.tick(time.delta());
+
+    // Never send more than one snapshot per frame, even after a hitch
+    if timer.0.just_finished() {
+        server_tick.increment();
+        stats.record_snapshot(clients.iter().map(|(network_id, priorities)| (network_id.get(), priorities)));
+    }
+}
+
+/// Record which replicated entities changed since the last snapshot.
+/// Only the frequently mutated components are watched; they dominate replication traffic.
+pub fn track_replicated_changes(
+    mut stats: ResMut<ReplicationStats>,
+    replicated: Query<(), With<Replicated>>,
+    changed: Query<
+        Entity,
+        (
+            With<Replicated>,
+            Or<(Changed<Wire<Position>>, Changed<Wire<Health>>, Changed<Wire<Mana>>, Changed<AnimationState>, Changed<CurrentTarget>)>,
+        ),
+    >,
+) {
+    let _timer = crate::metrics::time_system("replication::track_replicated_changes");
+    stats.replicated_entities = replicated.iter().count() as u32;
+    stats.pending_changes.extend(changed.iter());
+}
+
+/// Caps how often a component that changes every frame is replicated.
+///
+/// `C` itself isn't replicated; clients get `Wire<C>`, which `update_wire_components` refreshes
+/// from `C` at the configured rate. Server systems read and write `C` as usual and see every
+/// change; only the copy that goes out lags behind, by at most one interval.
+#[derive(Resource)]
+pub struct ComponentRate<C: Component> {
+    /// None when every change goes out in the next snapshot
+    timer: Option<Timer>,
+    marker: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> ComponentRate<C> {
+    /// `rate` updates per second; 0, or anything at or above the snapshot rate, is unlimited
+    pub fn new(rate: u32, tick_rate: u32) -> Self {
+        let timer = (rate > 0 && rate < tick_rate)
+            .then(|| Timer::from_seconds(1.0 / rate as f32, TimerMode::Repeating));
+        Self { timer, marker: PhantomData }
+    }
+}
+
+/// Copy `C` into the replicated `Wire<C>` of every entity whose `C` changed since its last
+/// copy, once per interval. Runs after everything that writes `C` and before replicon sends.
+pub fn update_wire_components<C: Component + Clone>(
+    mut commands: Commands,
+    time: Res<Time>,
+    ticks: SystemChangeTick,
+    mut rate: ResMut<ComponentRate<C>>,
+    mut components: Query<(Entity, Ref<C>, Option<&mut Wire<C>>), With<Replicated>>,
+    orphaned: Query<Entity, (With<Wire<C>>, Without<C>)>,
+) {
+    let release = match &mut rate.timer {
+        Some(timer) => timer.tick(time.delta()).just_finished(),
+        None => true,
+    };
+
+    for (entity, component, wire) in &mut components {
+        match wire {
+            // New entities go out straight away with everything else they spawned with
+            None => {
+                commands.entity(entity).insert(Wire(C::clone(&component)));
+            }
+            // However long ago the last change was, it goes out at the next release
+            Some(mut wire) if release && component.last_changed().is_newer_than(wire.last_changed(), ticks.this_run()) => {
+                wire.0 = C::clone(&component);
+            }
+            Some(_) => {}
+        }
+    }
+
+    for entity in &orphaned {
+        commands.entity(entity).remove::<Wire<C>>();
+    }
+}
+
+/// Favor entities near each player's character: those within `full_priority_distance` are
+/// updated in every snapshot, further ones less often down to `min_priority`. A client that
+/// is over its bandwidth budget gets its far entities slowed further, never its nearby ones.
+///
+/// Works a spatial index cell at a time, so cells wholly in range or wholly at the floor are
+/// settled without measuring each entity in them.
+pub fn prioritize_nearby_entities(
+    config: Res<ServerConfig>,
+    renet_server: Option<Res<RenetServer>>,
+    spatial: Res<SpatialIndex>,
+    mut clients: Query<(&mut PriorityMap, Option<&ActiveCharacterEntity>, Option<&NetworkId>), With<ConnectedClient>>,
+    viewers: Query<&Position>,
+    replicated: Query<(), With<Replicated>>,
+) {
+    let _timer = crate::metrics::time_system("replication::prioritize_nearby_entities");
+    let network = &config.network;
+    let full_distance = network.full_priority_distance.max(1.0);
+
+    for (mut priorities, active_character, network_id) in &mut clients {
+        priorities.clear();
+        let Some(viewer) = active_character.and_then(|active| viewers.get(active.0).ok()) else {
+            continue;
+        };
+
+        let budget = network.client_bandwidth_budget as f64;
+        let sent = renet_server.as_deref()
+            .zip(network_id)
+            .and_then(|(server, id)| server.network_info(id.get()).ok())
+            .map(|info| info.bytes_sent_per_second)
+            .unwrap_or(0.0);
+        let throttle = if budget > 0.0 && sent > budget { (budget / sent) as f32 } else { 1.0 };
+        let priority_at = |distance: f32| (full_distance / distance * throttle).clamp(network.min_priority, 1.0);
+
+        for (area, entries) in spatial.cells() {
+            let nearest = viewer.0.distance(viewer.0.clamp(area.min, area.max));
+            let farthest = (viewer.0 - area.center()).abs() + area.half_size();
+            // The whole cell is in full priority range
+            if farthest.length() <= full_distance {
+                continue;
+            }
+            // Every entity in the cell is far enough away to be at the floor
+            let at_floor = nearest > full_distance && priority_at(nearest) <= network.min_priority;
+
+            for &(entity, position) in entries {
+                if !replicated.contains(entity) {
+                    continue;
+                }
+                let priority = if at_floor {
+                    network.min_priority
+                } else {
+                    let distance = position.distance(viewer.0);
+                    if distance <= full_distance {
+                        continue;
+                    }
+                    priority_at(distance)
+                };
+                priorities.insert(entity, priority);
+            }
+        }
+    }
+}
+
+/// Collect per-client round trip, packet loss and bandwidth from the transport, along with
+/// how many entity changes each client is being sent
+pub fn collect_client_network_stats(
+    server: &RenetServer,
+    clients: &Query<(&NetworkId, Option<&ActiveCharacterEntity>), With<ConnectedClient>>,
+    characters: &Query<&Character>,
+    replication: &ReplicationStats,
+) -> Vec<ClientNetworkStats> {
+    clients.iter()
+        .filter_map(|(network_id, active_character)| {
+            let client_id = network_id.get();
+            let info = server.network_info(client_id).ok()?;
+            let character_name = active_character
+                .and_then(|active| characters.get(active.0).ok())
+                .map(|character| character.name.clone());
+
+            Some(ClientNetworkStats {
+                client_id,
+                character_name,
+                rtt_ms: info.rtt * 1000.0,
+                packet_loss: info.packet_loss,
+                bytes_sent_per_sec: info.bytes_sent_per_second,
+                bytes_received_per_sec: info.bytes_received_per_second,
+                entity_changes_per_tick: replication.client_entity_changes_per_tick(client_id),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn wire_x(world: &World, entity: Entity) -> f32 {
+        world.get::<Wire<Position>>(entity).unwrap().0.0.x
+    }
+
+    #[test]
+    fn changes_between_releases_are_held_back() {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(ComponentRate::<Position>::new(10, 30));
+        let update = world.register_system(update_wire_components::<Position>);
+        let entity = world.spawn((Replicated, Position(Vec2::ZERO))).id();
+
+        // Spawned: sent straight away
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(20));
+        world.run_system(update).unwrap();
+        assert_eq!(wire_x(&world, entity), 0.0);
+
+        // Moved before the next release: the server sees the move, the wire doesn't yet
+        world.get_mut::<Position>(entity).unwrap().0 = Vec2::new(5.0, 0.0);
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(20));
+        world.run_system(update).unwrap();
+        assert_eq!(wire_x(&world, entity), 0.0);
+        assert_eq!(world.get::<Position>(entity).unwrap().0.x, 5.0);
+
+        // Moved again once the interval has passed: released
+        world.get_mut::<Position>(entity).unwrap().0 = Vec2::new(10.0, 0.0);
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(100));
+        world.run_system(update).unwrap();
+        assert_eq!(wire_x(&world, entity), 10.0);
+    }
+
+    #[test]
+    fn the_last_change_before_a_quiet_spell_still_goes_out() {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(ComponentRate::<Position>::new(10, 30));
+        let update = world.register_system(update_wire_components::<Position>);
+        let entity = world.spawn((Replicated, Position(Vec2::ZERO))).id();
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(20));
+        world.run_system(update).unwrap();
+
+        // One move between releases, then nothing
+        world.get_mut::<Position>(entity).unwrap().0 = Vec2::new(5.0, 0.0);
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(20));
+        world.run_system(update).unwrap();
+        assert_eq!(wire_x(&world, entity), 0.0);
+
+        world.resource_mut::<Time>().advance_by(Duration::from_millis(100));
+        world.run_system(update).unwrap();
+        assert_eq!(wire_x(&world, entity), 5.0);
+    }
+
+    #[test]
+    fn rates_at_or_above_the_snapshot_rate_are_unlimited() {
+        assert!(ComponentRate::<Position>::new(0, 30).timer.is_none());
+        assert!(ComponentRate::<Position>::new(30, 30).timer.is_none());
+        assert!(ComponentRate::<Position>::new(20, 30).timer.is_some());
+    }
+
+    #[test]
+    fn client_changes_are_weighted_by_priority() {
+        let mut world = World::new();
+        let near = world.spawn_empty().id();
+        let far = world.spawn_empty().id();
+        let mut priorities = PriorityMap::default();
+        priorities.insert(far, 0.25);
+
+        let mut stats = ReplicationStats::new(30);
+        stats.pending_changes.extend([near, far]);
+        stats.record_snapshot([(1, &priorities)].into_iter());
+        assert_eq!(stats.entity_changes_per_tick(), 2.0);
+        assert_eq!(stats.client_entity_changes_per_tick(1), 1.25);
+
+        // Client 1 left before the next snapshot
+        stats.record_snapshot(std::iter::empty());
+        assert_eq!(stats.client_entity_changes_per_tick(1), 0.0);
+    }
+}
@@ -0,0 +1,121 @@
+//! World shards.
+//!
+//! Each server process runs one shard, set up in `[shard]` of config.toml. Shards that
+//! share a database register themselves in the `shards` table on a heartbeat, which is how
+//! each process learns about the others for the shard selector and the dashboard stats.
+//! Characters are stamped with the shard they were created on and can only be played there;
+//! switching shards means the client reconnects to that shard's address and logs in again.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use crate::auth::Authenticated;
+use crate::config::ServerConfig;
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+use crate::social::OnlineCharacters;
+
+/// Every shard as of the last heartbeat, this one included
+#[derive(Resource, Default)]
+pub struct ShardRegistry {
+    local_id: String,
+    shards: Vec<ShardInfo>,
+}
+
+impl ShardRegistry {
+    /// The shard list with this shard's population brought up to date
+    pub fn with_population(&self, population: u32) -> Vec<ShardInfo> {
+        self.shards.iter()
+            .cloned()
+            .map(|mut shard| {
+                if shard.id == self.local_id {
+                    shard.population = population;
+                }
+                shard
+            })
+            .collect()
+    }
+}
+
+/// This process's registry entry
+pub fn local_shard(config: &ServerConfig, population: u32) -> ShardInfo {
+    let cert_url = if config.shard.cert_url.is_empty() {
+        format!("http://{}:{}/cert", config.shard.public_address, server_cert_port())
+    } else {
+        config.shard.cert_url.clone()
+    };
+
+    ShardInfo {
+        id: config.shard.id.clone(),
+        name: config.shard.name.clone(),
+        address: config.shard.public_address.clone(),
+        udp_port: server_port(),
+        webtransport_port: server_port_webtransport(),
+        websocket_port: server_port_websocket(),
+        cert_url,
+        population,
+        max_players: config.server.max_players as u32,
+        online: true,
+    }
+}
+
+/// Seconds without a heartbeat before a shard shows as offline
+fn stale_after(config: &ServerConfig) -> i64 {
+    (config.shard.heartbeat_seconds.max(1) * 3) as i64
+}
+
+/// Publish this shard's entry and pick up everyone else's. Runs at startup and then every
+/// `shard.heartbeat_seconds`.
+pub fn publish_heartbeat(
+    config: Res<ServerConfig>,
+    online: Res<OnlineCharacters>,
+    db: Res<DatabaseConnection>,
+) {
+    let shard = local_shard(&config, online.characters.len() as u32);
+    let stale_after = stale_after(&config);
+    db.run(
+        move |pool| async move {
+            database::shards::heartbeat(&pool, &shard).timed("shard_heartbeat").await?;
+            database::shards::list_shards(&pool, stale_after).timed("list_shards").await
+                .map(|shards| (shard.id, shards))
+        },
+        |result, world| match result {
+            Ok((local_id, shards)) => {
+                world.insert_resource(ShardRegistry { local_id, shards });
+            }
+            Err(e) => error!("{}", e),
+        },
+    );
+}
+
+/// Send the shard list to a player at character select
+pub fn handle_shard_list_request(
+    trigger: On<FromClient<GetShardListRequest>>,
+    mut commands: Commands,
+    clients: Query<(), With<Authenticated>>,
+    registry: Res<ShardRegistry>,
+    online: Res<OnlineCharacters>,
+    config: Res<ServerConfig>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    if !clients.contains(client_entity) {
+        warn!("Unauthenticated client requested the shard list");
+        return;
+    }
+
+    let mut shards = registry.with_population(online.characters.len() as u32);
+    // The registry is empty until the first heartbeat comes back
+    if !shards.iter().any(|shard| shard.id == config.shard.id) {
+        shards.push(local_shard(&config, online.characters.len() as u32));
+    }
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: ShardListResponse { current_shard: config.shard.id.clone(), shards },
+    });
+}
+
+/// Whether this shard has room for another character in the world
+pub fn has_room(config: &ServerConfig, online: &OnlineCharacters) -> bool {
+    online.characters.len() < config.server.max_players
+}
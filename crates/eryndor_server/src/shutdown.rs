@@ -0,0 +1,144 @@
+//! Graceful shutdown on SIGTERM / Ctrl+C.
+//!
+//! The first signal starts the shutdown: new connections are turned away, players are warned
+//! and every online character is saved straight away so a hard kill can't lose progress.
+//! Characters are saved again and the server exits once the grace period is up, everyone
+//! has logged off, or a second signal arrives. The exit waits for the database worker to
+//! finish every queued write.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::sync::atomic::{AtomicU32, Ordering};
+use crate::config::ServerConfig;
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+use crate::persistence::{queue_save, CharacterSaveData};
+
+/// Shutdown signals received so far
+static SIGNALS: AtomicU32 = AtomicU32::new(0);
+
+/// Count SIGTERM and Ctrl+C signals; spawned on the server's tokio runtime
+pub async fn listen_for_signals() {
+    loop {
+        wait_for_signal().await;
+        if SIGNALS.fetch_add(1, Ordering::Relaxed) > 0 {
+            warn!("Second shutdown signal received - skipping the rest of the grace period");
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        Err(e) => {
+            error!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Set once a shutdown has started
+#[derive(Resource, Default)]
+pub struct ShutdownState {
+    /// `Time::elapsed_secs_f64` at which the server exits
+    deadline: Option<f64>,
+}
+
+impl ShutdownState {
+    pub fn in_progress(&self) -> bool {
+        self.deadline.is_some()
+    }
+}
+
+/// Drive the shutdown once a signal has arrived
+pub fn run_shutdown(
+    mut commands: Commands,
+    mut state: ResMut<ShutdownState>,
+    mut exit: MessageWriter<AppExit>,
+    time: Res<Time>,
+    config: Res<ServerConfig>,
+    characters: Query<CharacterSaveData>,
+    clients: Query<Entity, With<ConnectedClient>>,
+    db: Res<DatabaseConnection>,
+) {
+    let signals = SIGNALS.load(Ordering::Relaxed);
+    if signals == 0 {
+        return;
+    }
+
+    let now = time.elapsed_secs_f64();
+    let deadline = match state.deadline {
+        Some(deadline) => deadline,
+        None => {
+            let grace = config.shutdown.grace_period_seconds;
+            warn!("Shutdown requested - closing in {} seconds", grace);
+
+            commands.server_trigger(ToClients {
+                mode: SendMode::Broadcast,
+                message: NotificationEvent {
+                    message: format!("Server is shutting down in {} seconds. Your progress has been saved.", grace),
+                    notification_type: NotificationType::Warning,
+                },
+            });
+            save_all_characters(&characters, &db);
+
+            let deadline = now + grace as f64;
+            state.deadline = Some(deadline);
+            deadline
+        }
+    };
+
+    if now < deadline && signals < 2 && !characters.is_empty() {
+        return;
+    }
+
+    // Final save picks up anything that changed during the grace period
+    save_all_characters(&characters, &db);
+    // Take this shard out of the selector rather than waiting for its heartbeat to go stale
+    let shard_id = config.shard.id.clone();
+    db.execute("deregister_shard", move |pool| async move {
+        database::shards::deregister(&pool, &shard_id).timed("deregister_shard").await
+    });
+    db.flush();
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Broadcast,
+        message: NotificationEvent {
+            message: "Server is shutting down now".to_string(),
+            notification_type: NotificationType::Warning,
+        },
+    });
+    for client in clients.iter() {
+        commands.entity(client).despawn();
+    }
+
+    info!("Shutdown complete");
+    exit.write(AppExit::Success);
+}
+
+fn save_all_characters(characters: &Query<CharacterSaveData>, db: &DatabaseConnection) {
+    let total = characters.iter().count();
+    let queued = characters.iter()
+        .filter(|data| queue_save(db, data, "saved for shutdown"))
+        .count();
+
+    if queued < total {
+        error!("Database not available - {} characters could not be saved", total - queued);
+    } else {
+        info!("Saving {} online characters", queued);
+    }
+}
@@ -0,0 +1,233 @@
+//! Friends and presence.
+//!
+//! Friend lists are stored in the database and loaded with the character. The server keeps
+//! an index of online characters so friends can be told when someone logs in or out, and so
+//! whispers can find their recipient by name.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::collections::HashMap;
+use crate::auth::{ActiveCharacterEntity, CharacterDatabaseId};
+use crate::database::{self, DatabaseConnection};
+use crate::metrics::TimedQuery;
+use crate::relay::{Relay, RelayMessage};
+
+/// Characters on this character's friend list
+#[derive(Component, Default)]
+pub struct FriendList {
+    /// (character id, name)
+    pub friends: Vec<(i64, String)>,
+}
+
+impl FriendList {
+    pub fn contains(&self, character_id: i64) -> bool {
+        self.friends.iter().any(|(id, _)| *id == character_id)
+    }
+}
+
+/// An online character, by entity
+pub struct OnlineCharacter {
+    pub character_id: i64,
+    pub name: String,
+    pub client: Entity,
+}
+
+/// A character online on another shard, as last heard over the relay
+pub struct RemoteCharacter {
+    pub name: String,
+    pub shard_id: String,
+}
+
+/// Every character currently in the world
+#[derive(Resource, Default)]
+pub struct OnlineCharacters {
+    pub characters: HashMap<Entity, OnlineCharacter>,
+    /// Characters on other shards, by character id
+    pub remote: HashMap<i64, RemoteCharacter>,
+}
+
+impl OnlineCharacters {
+    /// Whether the character is online here or on another shard
+    pub fn is_online(&self, character_id: i64) -> bool {
+        self.characters.values().any(|online| online.character_id == character_id)
+            || self.remote.contains_key(&character_id)
+    }
+
+    /// Find an online character by name, ignoring case
+    pub fn find_by_name(&self, name: &str) -> Option<&OnlineCharacter> {
+        self.characters.values().find(|online| online.name.eq_ignore_ascii_case(name))
+    }
+}
+
+pub fn notify(commands: &mut Commands, client: Entity, message: String, notification_type: NotificationType) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client)),
+        message: NotificationEvent { message, notification_type },
+    });
+}
+
+fn send_friend_list(commands: &mut Commands, client: Entity, list: &FriendList, online: &OnlineCharacters) {
+    let friends = list.friends.iter()
+        .map(|(character_id, name)| FriendInfo {
+            character_id: *character_id,
+            name: name.clone(),
+            online: online.is_online(*character_id),
+        })
+        .collect();
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client)),
+        message: FriendListEvent { friends },
+    });
+}
+
+/// Tell everyone who has the character as a friend that it logged in or out.
+/// `changed` is the character's own entity when it's on this shard.
+pub fn announce_presence(
+    commands: &mut Commands,
+    online: &OnlineCharacters,
+    friend_lists: &Query<(Entity, &FriendList, &OwnedBy)>,
+    changed: Option<Entity>,
+    character_id: i64,
+    name: &str,
+    logged_in: bool,
+) {
+    for (entity, list, owned_by) in friend_lists {
+        if Some(entity) == changed {
+            // The newly logged in player gets their own list
+            if logged_in {
+                send_friend_list(commands, owned_by.0, list, online);
+            }
+            continue;
+        }
+        if !list.contains(character_id) {
+            continue;
+        }
+
+        let message = if logged_in {
+            format!("{} has come online", name)
+        } else {
+            format!("{} has gone offline", name)
+        };
+        notify(commands, owned_by.0, message, NotificationType::Info);
+        send_friend_list(commands, owned_by.0, list, online);
+    }
+}
+
+/// Track logins and logouts, and tell anyone who has the character as a friend, here and
+/// on the other shards
+pub fn update_presence(
+    mut commands: Commands,
+    mut online: ResMut<OnlineCharacters>,
+    joined: Query<(Entity, &Character, &CharacterDatabaseId, &OwnedBy), Added<FriendList>>,
+    mut left: RemovedComponents<CharacterDatabaseId>,
+    friend_lists: Query<(Entity, &FriendList, &OwnedBy)>,
+    relay: Res<Relay>,
+) {
+    let mut changes = Vec::new();
+
+    for (entity, character, db_id, owned_by) in &joined {
+        online.characters.insert(entity, OnlineCharacter {
+            character_id: db_id.0,
+            name: character.name.clone(),
+            client: owned_by.0,
+        });
+        changes.push((entity, db_id.0, character.name.clone(), true));
+    }
+
+    for entity in left.read() {
+        if let Some(character) = online.characters.remove(&entity) {
+            changes.push((entity, character.character_id, character.name, false));
+        }
+    }
+
+    for (changed_entity, character_id, name, logged_in) in changes {
+        announce_presence(&mut commands, &online, &friend_lists, Some(changed_entity), character_id, &name, logged_in);
+        relay.send(RelayMessage::Presence { character_id, name, online: logged_in });
+    }
+}
+
+pub fn handle_add_friend(
+    trigger: On<FromClient<AddFriendRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    players: Query<(&CharacterDatabaseId, &FriendList)>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((db_id, list)) = players.get(active_char.0) else { return };
+
+    let name = trigger.event().name.trim();
+    if name.is_empty() {
+        return;
+    }
+    if list.friends.iter().any(|(_, friend)| friend.eq_ignore_ascii_case(name)) {
+        notify(&mut commands, client_entity, format!("{} is already your friend", name), NotificationType::Warning);
+        return;
+    }
+    if list.friends.len() >= MAX_FRIENDS {
+        notify(&mut commands, client_entity, "Your friend list is full".to_string(), NotificationType::Warning);
+        return;
+    }
+
+    let (character, character_id, name) = (active_char.0, db_id.0, name.to_string());
+    db.run(
+        move |pool| async move { database::add_friend(&pool, character_id, &name).timed("add_friend").await },
+        move |result, world| {
+            if let Err(e) = world.run_system_cached_with(finish_add_friend, (client_entity, character, result)) {
+                error!("Failed to add friend: {}", e);
+            }
+        },
+    );
+}
+
+fn finish_add_friend(
+    In((client_entity, character, result)): In<(Entity, Entity, Result<(i64, String), String>)>,
+    mut commands: Commands,
+    mut players: Query<(&CharacterDatabaseId, &mut FriendList)>,
+    online: Res<OnlineCharacters>,
+) {
+    // The character may have logged out while the database was busy
+    let Ok((db_id, mut list)) = players.get_mut(character) else { return };
+
+    match result {
+        Ok((friend_id, friend_name)) => {
+            info!("Character {} added {} as a friend", db_id.0, friend_name);
+            notify(&mut commands, client_entity, format!("{} added to friends", friend_name), NotificationType::Success);
+            if !list.friends.iter().any(|(id, _)| *id == friend_id) {
+                list.friends.push((friend_id, friend_name));
+                list.friends.sort_by(|(_, a), (_, b)| a.cmp(b));
+            }
+            send_friend_list(&mut commands, client_entity, &list, &online);
+        }
+        Err(e) => notify(&mut commands, client_entity, e, NotificationType::Warning),
+    }
+}
+
+pub fn handle_remove_friend(
+    trigger: On<FromClient<RemoveFriendRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    mut players: Query<(&CharacterDatabaseId, &mut FriendList)>,
+    online: Res<OnlineCharacters>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((db_id, mut list)) = players.get_mut(active_char.0) else { return };
+
+    let friend_id = trigger.event().character_id;
+    let Some(index) = list.friends.iter().position(|(id, _)| *id == friend_id) else { return };
+
+    // Nothing waits on the delete; a failure is only logged
+    let character_id = db_id.0;
+    db.execute("remove_friend", move |pool| async move {
+        database::remove_friend(&pool, character_id, friend_id).timed("remove_friend").await
+    });
+
+    let (_, name) = list.friends.remove(index);
+    notify(&mut commands, client_entity, format!("{} removed from friends", name), NotificationType::Info);
+    send_friend_list(&mut commands, client_entity, &list, &online);
+}
@@ -0,0 +1,132 @@
+//! Uniform grid of every positioned entity, for "what's near here" searches.
+//!
+//! Aggro checks, calls for help, NPC interaction and replication priorities used to walk every
+//! player, enemy or NPC in the world; with the grid they only look at the few cells around the
+//! point they care about. The grid is rebuilt from `Position` once a frame rather than patched
+//! from `Changed<Position>`: most indexed entities move all the time anyway, and a rebuild never
+//! leaves despawned entities behind.
+//!
+//! Positions are the ones entities had at the start of the frame, so callers that need an exact
+//! range check still make it against the entity's current position. Area-of-effect hits and
+//! projectiles go through Avian's spatial query instead, which knows about collider shapes.
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use std::collections::HashMap;
+
+/// Cell edge in pixels. Most searches (interaction, aggro, calls for help) reach one or two
+/// cells in each direction.
+pub const CELL_SIZE: f32 = 128.0;
+
+#[derive(Resource)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        Self { cell_size: cell_size.max(1.0), cells: HashMap::new() }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Empty every cell, dropping the ones that were already empty so deserted parts of the
+    /// world don't keep allocations around
+    pub fn clear(&mut self) {
+        self.cells.retain(|_, entries| {
+            let occupied = !entries.is_empty();
+            entries.clear();
+            occupied
+        });
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    /// Entities indexed within `radius` pixels of `center`
+    pub fn nearby(&self, center: Vec2, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+        let radius = radius.max(0.0);
+        let min = self.cell(center - Vec2::splat(radius));
+        let max = self.cell(center + Vec2::splat(radius));
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |(_, position)| position.distance_squared(center) <= radius * radius)
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Occupied cells with the area each covers
+    pub fn cells(&self) -> impl Iterator<Item = (Rect, &[(Entity, Vec2)])> {
+        self.cells.iter()
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(cell, entries)| {
+                let min = cell.as_vec2() * self.cell_size;
+                (Rect::from_corners(min, min + Vec2::splat(self.cell_size)), entries.as_slice())
+            })
+    }
+}
+
+/// Re-index every positioned entity. Runs at the start of the frame, once last frame's physics
+/// has been synced back to `Position`.
+pub fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    positions: Query<(Entity, &Position)>,
+) {
+    let _timer = crate::metrics::time_system("spatial::update_spatial_index");
+    index.clear();
+    for (entity, position) in &positions {
+        index.insert(entity, position.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearby_only_returns_entities_in_range() {
+        let mut world = World::new();
+        let [near, across_cells, far] = [(); 3].map(|_| world.spawn_empty().id());
+
+        let mut index = SpatialIndex::new(100.0);
+        index.insert(near, Vec2::new(10.0, 10.0));
+        index.insert(across_cells, Vec2::new(-60.0, 40.0));
+        index.insert(far, Vec2::new(180.0, 0.0));
+
+        let mut found: Vec<Entity> = index.nearby(Vec2::ZERO, 80.0).collect();
+        found.sort();
+        let mut expected = vec![near, across_cells];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(index.nearby(Vec2::new(200.0, 0.0), 25.0).collect::<Vec<_>>(), vec![far]);
+    }
+
+    #[test]
+    fn clearing_drops_everything() {
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+
+        let mut index = SpatialIndex::new(100.0);
+        index.insert(entity, Vec2::new(-250.0, 320.0));
+        assert_eq!(index.cells().count(), 1);
+        let (area, entries) = index.cells().next().unwrap();
+        assert_eq!(area, Rect::new(-300.0, 300.0, -200.0, 400.0));
+        assert_eq!(entries, &[(entity, Vec2::new(-250.0, 320.0))]);
+
+        index.clear();
+        assert_eq!(index.nearby(Vec2::new(-250.0, 320.0), 10.0).count(), 0);
+        assert_eq!(index.cells().count(), 0);
+    }
+}
@@ -3,12 +3,15 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use avian2d::prelude::{RigidBody, Collider, CollisionLayers};
 use crate::{PhysicsPosition, PhysicsVelocity};
+use crate::game_data::{EnemyDatabase, EnemyDefinition, NpcSpawnDef, ZoneDatabase, ZoneDefinition};
 
 /// Defines a spawn point for an entity that can respawn
 #[derive(Component, Clone, Debug)]
 pub struct SpawnPoint {
     pub position: Vec2,
     pub respawn_delay: f32, // seconds
+    /// Zone spawn region this point belongs to; respawns re-roll from its spawn table
+    pub region_id: Option<String>,
 }
 
 /// Server-only event triggered when an entity should respawn
@@ -18,6 +21,7 @@ pub struct RespawnEvent {
     pub spawn_position: Vec2,
     pub respawn_delay: f32,
     pub template: EntityTemplate,
+    pub region_id: Option<String>,
 }
 
 /// Timer tracking when an entity should respawn
@@ -26,6 +30,7 @@ pub struct RespawnTimer {
     pub timer: Timer,
     pub template: EntityTemplate,
     pub spawn_position: Vec2,
+    pub region_id: Option<String>,
 }
 
 /// Template for creating entities with all their components
@@ -53,6 +58,28 @@ pub struct EnemyTemplate {
     pub leash_range: f32,
 }
 
+impl EnemyTemplate {
+    /// Build a template from an enemy definition, scaling stats for the given level
+    pub fn from_definition(def: &EnemyDefinition, level: u32) -> Self {
+        let bonus_levels = level.saturating_sub(1) as f32;
+        Self {
+            enemy_type_id: def.id,
+            name: def.name.clone(),
+            health: def.max_health * (1.0 + 0.10 * bonus_levels),
+            move_speed: def.move_speed,
+            attack_power: def.attack_power * (1.0 + 0.08 * bonus_levels),
+            defense: def.defense * (1.0 + 0.05 * bonus_levels),
+            crit_chance: 0.05,
+            visual_shape: def.visual.shape_type(),
+            color: def.visual.color,
+            size: def.visual.size,
+            loot_table: def.loot_table.clone(),
+            aggro_range: def.aggro_range,
+            leash_range: def.leash_range,
+        }
+    }
+}
+
 impl EntityTemplate {
     /// Create an enemy template from existing component data
     pub fn from_enemy_components(
@@ -108,6 +135,8 @@ impl EntityTemplate {
                 // Add additional components in a second batch (Bevy bundle limit workaround)
                 commands.entity(enemy_entity).insert((
                     AiState::default(),
+                    crate::combat::EnemySwingTimer::default(),
+                    crate::threat::ThreatTable::default(),
                     Interactable::enemy(),
                     VisualShape {
                         shape_type: template.visual_shape,
@@ -164,7 +193,7 @@ impl SpawnRegistry {
         template: EnemyTemplate,
     ) {
         self.register(
-            SpawnPoint { position, respawn_delay },
+            SpawnPoint { position, respawn_delay, region_id: None },
             EntityTemplate::Enemy(template),
         );
     }
@@ -185,6 +214,7 @@ pub fn schedule_respawn(
         timer: Timer::from_seconds(event.respawn_delay, TimerMode::Once),
         template: event.template.clone(),
         spawn_position: event.spawn_position,
+        region_id: event.region_id.clone(),
     });
 }
 
@@ -193,18 +223,31 @@ pub fn process_respawns(
     mut commands: Commands,
     mut respawn_query: Query<(Entity, &mut RespawnTimer)>,
     time: Res<Time>,
+    zone_db: Res<ZoneDatabase>,
+    enemy_db: Res<EnemyDatabase>,
 ) {
     for (timer_entity, mut respawn_timer) in &mut respawn_query {
         respawn_timer.timer.tick(time.delta());
 
         if respawn_timer.timer.is_finished() {
+            // Region spawns re-roll from the current spawn table; otherwise reuse the template
+            let rerolled = respawn_timer.region_id.as_deref()
+                .and_then(|region_id| zone_db.find_spawn_region(region_id))
+                .and_then(|region| region.roll_enemy())
+                .and_then(|(enemy_type, level)| {
+                    enemy_db.enemies.get(&enemy_type)
+                        .map(|def| EntityTemplate::Enemy(EnemyTemplate::from_definition(def, level)))
+                });
+            let template = rerolled.unwrap_or_else(|| respawn_timer.template.clone());
+
             // Spawn the entity from template at the stored position
-            let new_entity = respawn_timer.template.spawn(&mut commands, respawn_timer.spawn_position);
+            let new_entity = template.spawn(&mut commands, respawn_timer.spawn_position);
 
             // Add SpawnPoint component to the newly spawned entity for future respawns
             commands.entity(new_entity).insert(SpawnPoint {
                 position: respawn_timer.spawn_position,
                 respawn_delay: respawn_timer.timer.duration().as_secs_f32(),
+                region_id: respawn_timer.region_id.clone(),
             });
 
             // Despawn the timer entity
@@ -213,3 +256,96 @@ pub fn process_respawns(
         }
     }
 }
+
+// ============================================================================
+// ZONE SPAWNING
+// ============================================================================
+
+/// Spawn the initial enemies for every spawn region in a zone
+pub fn spawn_zone_enemies(commands: &mut Commands, zone: &ZoneDefinition, enemy_db: &EnemyDatabase) {
+    let mut spawned = 0;
+
+    for region in &zone.enemy_spawns {
+        for point in &region.spawn_points {
+            let Some((enemy_type, level)) = region.roll_enemy() else {
+                warn!("Spawn region '{}' has no valid spawn table entries", region.region_id);
+                break;
+            };
+            let Some(def) = enemy_db.enemies.get(&enemy_type) else {
+                warn!("Spawn region '{}' references unknown enemy type {}", region.region_id, enemy_type);
+                continue;
+            };
+
+            let position = Vec2::from(*point);
+            let template = EntityTemplate::Enemy(EnemyTemplate::from_definition(def, level));
+            let entity = template.spawn(commands, position);
+            commands.entity(entity).insert(SpawnPoint {
+                position,
+                respawn_delay: region.respawn_delay.unwrap_or(def.respawn_delay),
+                region_id: Some(region.region_id.clone()),
+            });
+            spawned += 1;
+        }
+    }
+
+    info!("Spawned {} enemies for zone {}", spawned, zone.zone_id);
+}
+
+/// Spawn all NPCs placed in a zone
+pub fn spawn_zone_npcs(commands: &mut Commands, zone: &ZoneDefinition) {
+    for npc in &zone.npc_spawns {
+        spawn_npc(commands, npc);
+    }
+    info!("Spawned {} NPCs for zone {}", zone.npc_spawns.len(), zone.zone_id);
+}
+
+fn spawn_npc(commands: &mut Commands, def: &NpcSpawnDef) -> Entity {
+    let position = Vec2::from(def.position);
+
+    let npc_entity = commands.spawn((
+        Replicated,
+        Npc,
+        NpcName(def.name.clone()),
+        Position(position),
+        Interactable::npc(),
+        VisualShape {
+            shape_type: def.visual.shape_type(),
+            color: def.visual.color,
+            size: def.visual.size,
+        },
+        PhysicsPosition(position),
+        RigidBody::Static,
+        Collider::circle(def.visual.size / 2.0),
+        CollisionLayers::new(GameLayer::Npc, [GameLayer::Player, GameLayer::Enemy]),
+    )).id();
+
+    if !def.quests.is_empty() {
+        commands.entity(npc_entity).insert(QuestGiver {
+            available_quests: def.quests.clone(),
+        });
+    }
+
+    if def.npc_type == "Trainer" {
+        commands.entity(npc_entity).insert(Trainer {
+            items_for_sale: def.trainer_items.iter()
+                .map(|item| TrainerItem { item_id: item.item_id, cost: item.cost })
+                .collect(),
+            trainer_type: def.trainer_type,
+            teaching_quests: def.teaching_quests.clone(),
+        });
+    }
+
+    if let Some(dialogue_id) = &def.dialogue {
+        commands.entity(npc_entity).insert(crate::quest::NpcDialogue(dialogue_id.clone()));
+    }
+
+    if let Some(shop_id) = &def.shop {
+        commands.entity(npc_entity).insert(crate::vendor::Vendor(shop_id.clone()));
+    }
+
+    if def.npc_type == "SpiritHealer" {
+        commands.entity(npc_entity).insert(crate::death::SpiritHealer);
+    }
+
+    npc_entity
+}
@@ -0,0 +1,210 @@
+//! Per-enemy threat tables.
+//!
+//! Damage and healing generate threat on the enemies involved, and each enemy attacks
+//! whoever holds the most threat. A new target has to exceed the current one by
+//! `TARGET_SWITCH_MARGIN` to pull aggro, unless a taunt forces the target for a while.
+//! Threat decays over time and is wiped when the enemy leashes back to its spawn point.
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use eryndor_shared::*;
+
+/// Threat added when a player wanders into an idle enemy's aggro range
+pub const PROXIMITY_THREAT: f32 = 1.0;
+/// Threat per point of damage dealt
+pub const DAMAGE_THREAT_MULTIPLIER: f32 = 1.0;
+/// Threat per point of healing, split across every enemy fighting the healer
+pub const HEALING_THREAT_MULTIPLIER: f32 = 0.5;
+/// Minimum threat for any hostile action, so misses still draw attention
+pub const MIN_ACTION_THREAT: f32 = 1.0;
+/// A challenger must exceed the current target's threat by this factor to pull aggro
+pub const TARGET_SWITCH_MARGIN: f32 = 1.1;
+/// Fraction of threat lost per second
+pub const THREAT_DECAY_PER_SECOND: f32 = 0.02;
+/// Entries below this are forgotten
+pub const MIN_THREAT: f32 = 0.1;
+/// Enemies returning home snap back to idle within this distance (pixels)
+pub const LEASH_ARRIVE_DISTANCE: f32 = 8.0;
+
+/// Who this enemy is angry at and how much
+#[derive(Component, Default, Debug)]
+pub struct ThreatTable {
+    pub threat: HashMap<Entity, f32>,
+    /// Forced target and seconds left on the taunt
+    pub taunt: Option<(Entity, f32)>,
+}
+
+impl ThreatTable {
+    pub fn add(&mut self, source: Entity, amount: f32) {
+        *self.threat.entry(source).or_insert(0.0) += amount.max(0.0);
+    }
+
+    pub fn remove(&mut self, source: Entity) {
+        self.threat.remove(&source);
+        if self.taunt.is_some_and(|(taunter, _)| taunter == source) {
+            self.taunt = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.threat.clear();
+        self.taunt = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.threat.is_empty()
+    }
+
+    /// Target to attack given the current one: taunts win, otherwise the current
+    /// target is kept unless someone beats it by the switch margin
+    pub fn select_target(&self, current: Option<Entity>) -> Option<Entity> {
+        if let Some((taunter, _)) = self.taunt {
+            if self.threat.contains_key(&taunter) {
+                return Some(taunter);
+            }
+        }
+
+        let (top, top_threat) = self.threat.iter()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(entity, threat)| (*entity, *threat))?;
+
+        match current.and_then(|current| self.threat.get(&current).map(|threat| (current, *threat))) {
+            Some((current, current_threat)) if top_threat <= current_threat * TARGET_SWITCH_MARGIN => Some(current),
+            _ => Some(top),
+        }
+    }
+}
+
+/// Server-only event: `source` generated threat on `enemy`
+#[derive(Event, Clone, Debug)]
+pub struct ThreatEvent {
+    pub enemy: Entity,
+    pub source: Entity,
+    pub amount: f32,
+}
+
+/// Server-only event: `healer` restored health, angering every enemy already fighting them
+#[derive(Event, Clone, Debug)]
+pub struct HealingThreatEvent {
+    pub healer: Entity,
+    pub amount: f32,
+}
+
+/// Server-only event: force `enemy` to attack `taunter` for `duration` seconds
+#[derive(Event, Clone, Debug)]
+pub struct TauntEvent {
+    pub enemy: Entity,
+    pub taunter: Entity,
+    pub duration: f32,
+}
+
+pub fn apply_threat(
+    trigger: On<ThreatEvent>,
+    mut enemies: Query<(&mut ThreatTable, &AiState), With<Enemy>>,
+) {
+    let event = trigger.event();
+    let Ok((mut table, ai_state)) = enemies.get_mut(event.enemy) else { return };
+
+    // Enemies running home ignore everything until they arrive
+    if matches!(ai_state, AiState::Returning) {
+        return;
+    }
+    table.add(event.source, event.amount.max(MIN_ACTION_THREAT) * DAMAGE_THREAT_MULTIPLIER);
+}
+
+pub fn apply_healing_threat(
+    trigger: On<HealingThreatEvent>,
+    mut enemies: Query<(&mut ThreatTable, &AiState), With<Enemy>>,
+) {
+    let event = trigger.event();
+    if event.amount <= 0.0 {
+        return;
+    }
+
+    let engaged: Vec<_> = enemies.iter_mut()
+        .filter(|(table, ai_state)| !matches!(ai_state, AiState::Returning) && table.threat.contains_key(&event.healer))
+        .map(|(table, _)| table)
+        .collect();
+    if engaged.is_empty() {
+        return;
+    }
+
+    let share = event.amount * HEALING_THREAT_MULTIPLIER / engaged.len() as f32;
+    for mut table in engaged {
+        table.add(event.healer, share);
+    }
+}
+
+pub fn apply_taunt(
+    trigger: On<TauntEvent>,
+    mut enemies: Query<(&mut ThreatTable, &AiState), With<Enemy>>,
+) {
+    let event = trigger.event();
+    let Ok((mut table, ai_state)) = enemies.get_mut(event.enemy) else { return };
+    if matches!(ai_state, AiState::Returning) {
+        return;
+    }
+
+    // Match the current top threat so the taunter keeps aggro after the taunt wears off
+    let top = table.threat.values().copied().fold(0.0, f32::max);
+    let current = table.threat.get(&event.taunter).copied().unwrap_or(0.0);
+    table.add(event.taunter, (top - current).max(MIN_ACTION_THREAT));
+    table.taunt = Some((event.taunter, event.duration));
+    info!("Enemy {:?} taunted by {:?} for {:.1}s", event.enemy, event.taunter, event.duration);
+}
+
+/// Decay threat, tick taunts, and forget players who died or left
+pub fn decay_threat(
+    mut enemies: Query<&mut ThreatTable, With<Enemy>>,
+    players: Query<&Health, With<Player>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_secs();
+    let decay = (1.0 - THREAT_DECAY_PER_SECOND * dt).max(0.0);
+
+    for mut table in &mut enemies {
+        if table.is_empty() && table.taunt.is_none() {
+            continue;
+        }
+
+        table.threat.retain(|source, threat| {
+            *threat *= decay;
+            *threat >= MIN_THREAT && players.get(*source).is_ok_and(|health| health.current > 0.0)
+        });
+
+        if let Some((taunter, remaining)) = table.taunt {
+            let remaining = remaining - dt;
+            table.taunt = (remaining > 0.0 && table.threat.contains_key(&taunter)).then_some((taunter, remaining));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_target_kept_within_switch_margin() {
+        let (a, b) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        let mut table = ThreatTable::default();
+        table.add(a, 100.0);
+        table.add(b, 105.0);
+        assert_eq!(table.select_target(Some(a)), Some(a));
+
+        table.add(b, 10.0);
+        assert_eq!(table.select_target(Some(a)), Some(b));
+    }
+
+    #[test]
+    fn taunt_overrides_threat() {
+        let (a, b) = (Entity::from_raw_u32(1).unwrap(), Entity::from_raw_u32(2).unwrap());
+        let mut table = ThreatTable::default();
+        table.add(a, 500.0);
+        table.add(b, 1.0);
+        table.taunt = Some((b, 3.0));
+        assert_eq!(table.select_target(Some(a)), Some(b));
+
+        table.remove(b);
+        assert_eq!(table.select_target(None), Some(a));
+    }
+}
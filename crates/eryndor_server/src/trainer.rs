@@ -3,6 +3,7 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use crate::auth::ActiveCharacterEntity;
 use crate::game_data::ItemDatabase;
+use crate::spatial::SpatialIndex;
 
 pub fn handle_purchase_from_trainer(
     trigger: On<FromClient<PurchaseFromTrainerRequest>>,
@@ -11,6 +12,7 @@ pub fn handle_purchase_from_trainer(
     mut players: Query<(&Position, &mut Gold, &mut Inventory)>,
     trainers: Query<(Entity, &Position, &Trainer, &NpcName), With<Npc>>,
     item_db: Res<ItemDatabase>,
+    spatial: Res<SpatialIndex>,
 ) {
     info!("=== PURCHASE FROM TRAINER HANDLER CALLED ===");
     let Some(client_entity) = trigger.client_id.entity() else {
@@ -37,7 +39,7 @@ pub fn handle_purchase_from_trainer(
 
     // Find the closest trainer to the player within interaction range
     let mut closest_trainer: Option<(Entity, f32, &Trainer, &NpcName)> = None;
-    for (entity, trainer_pos, trainer, npc_name) in trainers.iter() {
+    for (entity, trainer_pos, trainer, npc_name) in spatial.nearby(player_pos.0, INTERACTION_RANGE).filter_map(|entity| trainers.get(entity).ok()) {
         let distance = player_pos.0.distance(trainer_pos.0);
         info!("Found Trainer '{}' at {:?}, distance: {:.2}", npc_name.0, trainer_pos.0, distance);
 
@@ -0,0 +1,477 @@
+//! Two-factor authentication with time-based one-time passwords (RFC 6238).
+//!
+//! Players enroll from the client's settings window. The server generates a secret, the
+//! player adds it to an authenticator app and confirms with the first code. Only then is
+//! two-factor switched on and the backup codes shown (once). After that, password logins
+//! need a current code or an unused backup code. Admins remove two-factor from an account
+//! with `/reset2fa` in game or `reset-2fa` on the CLI.
+//!
+//! OAuth logins skip this: the provider handles its own second factor.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+use crate::audit::{log_audit_event, AuditActionType};
+use crate::auth::{Authenticated, ClientMetadata};
+use crate::database::two_factor::{self as records, TwoFactorRecord};
+use crate::database::{DatabaseConnection, DbPool};
+
+const SECRET_BYTES: usize = 20;
+const STEP_SECONDS: i64 = 30;
+const CODE_DIGITS: usize = 6;
+/// Steps either side of the current one that are still accepted, for clock drift
+const ALLOWED_DRIFT: i64 = 1;
+const BACKUP_CODE_COUNT: usize = 10;
+/// No 0/o, 1/l/i so codes survive being written down
+const BACKUP_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const ISSUER: &str = "Eryndor";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// ============================================================================
+// TOTP
+// ============================================================================
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Base32 (RFC 4648, no padding), the encoding authenticator apps expect
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET.iter().position(|&a| a as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// HOTP value (RFC 4226) for one counter
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(CODE_DIGITS as u32)
+}
+
+/// New random secret, base32 encoded
+pub fn generate_secret() -> String {
+    let bytes: [u8; SECRET_BYTES] = rand::rngs::OsRng.gen();
+    base32_encode(&bytes)
+}
+
+/// The time step `code` belongs to, if it's valid for `secret` at `now` (unix seconds)
+pub fn verify_code(secret: &str, code: &str, now: i64) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != CODE_DIGITS || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = base32_decode(secret)?;
+
+    let current = now / STEP_SECONDS;
+    (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT)
+        .filter(|step| *step >= 0)
+        .find(|step| hotp(&key, *step as u64) == code)
+}
+
+/// Link authenticator apps can import (usually as a QR code)
+pub fn otpauth_uri(username: &str, secret: &str) -> String {
+    let label: String = username.bytes()
+        .map(|b| if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            (b as char).to_string()
+        } else {
+            format!("%{:02X}", b)
+        })
+        .collect();
+    format!(
+        "otpauth://totp/{issuer}:{label}?secret={secret}&issuer={issuer}&digits={digits}&period={period}",
+        issuer = ISSUER,
+        label = label,
+        secret = secret,
+        digits = CODE_DIGITS,
+        period = STEP_SECONDS,
+    )
+}
+
+// ============================================================================
+// BACKUP CODES
+// ============================================================================
+
+/// Fresh backup codes as shown to the player, e.g. "k7m2-x9qa"
+fn generate_backup_codes() -> Vec<String> {
+    let mut rng = rand::rngs::OsRng;
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..8)
+                .map(|_| BACKUP_CODE_ALPHABET[rng.gen_range(0..BACKUP_CODE_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..4], &chars[4..])
+        })
+        .collect()
+}
+
+/// Backup codes are compared without dashes, spaces or case
+fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn hash_backup_code(code: &str) -> String {
+    use argon2::{
+        password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+        Argon2,
+    };
+
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(normalize_backup_code(code).as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
+
+fn backup_code_matches(code: &str, hash: &str) -> bool {
+    use argon2::{
+        password_hash::{PasswordHash, PasswordVerifier},
+        Argon2,
+    };
+
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(code.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// LOGIN CHECK
+// ============================================================================
+
+/// How a login satisfied two-factor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecondFactor {
+    /// The account hasn't enabled two-factor
+    NotEnabled,
+    Code,
+    BackupCode { remaining: usize },
+}
+
+/// Why a login's second factor wasn't accepted
+pub enum SecondFactorError {
+    /// Two-factor is on and no code was sent; the client should ask for one
+    Missing,
+    Rejected(String),
+}
+
+/// Check the code sent with a login against the account's two-factor settings.
+/// An accepted code is used up.
+pub async fn check_second_factor(
+    pool: &DbPool,
+    account_id: i64,
+    code: Option<&str>,
+) -> Result<SecondFactor, SecondFactorError> {
+    let record = match records::get_two_factor(pool, account_id).await {
+        Ok(Some(record)) if record.enabled => record,
+        Ok(_) => return Ok(SecondFactor::NotEnabled),
+        Err(e) => return Err(SecondFactorError::Rejected(e)),
+    };
+
+    match code.filter(|code| !code.trim().is_empty()) {
+        Some(code) => redeem_code(pool, account_id, &record, code).await.map_err(SecondFactorError::Rejected),
+        None => Err(SecondFactorError::Missing),
+    }
+}
+
+/// Accept an authenticator or backup code for an enabled account, using it up
+async fn redeem_code(
+    pool: &DbPool,
+    account_id: i64,
+    record: &TwoFactorRecord,
+    code: &str,
+) -> Result<SecondFactor, String> {
+    if let Some(step) = verify_code(&record.secret, code, unix_now()) {
+        return if records::claim_totp_step(pool, account_id, step).await? {
+            Ok(SecondFactor::Code)
+        } else {
+            Err("That code was already used - wait for the next one".to_string())
+        };
+    }
+
+    let code = normalize_backup_code(code);
+    if code.len() == 8 {
+        let backup_codes = records::unused_backup_codes(pool, account_id).await?;
+        for (id, hash) in &backup_codes {
+            if backup_code_matches(&code, hash) && records::claim_backup_code(pool, *id).await? {
+                return Ok(SecondFactor::BackupCode { remaining: backup_codes.len() - 1 });
+            }
+        }
+    }
+
+    Err("Invalid authentication code".to_string())
+}
+
+// ============================================================================
+// ENROLLMENT HANDLERS
+// ============================================================================
+
+/// The logged-in account and IP of a client, or None (with an error sent) if it isn't logged in
+fn authenticated_account(
+    commands: &mut Commands,
+    client_entity: Entity,
+    clients: &Query<(&Authenticated, Option<&ClientMetadata>)>,
+) -> Option<(i64, Option<std::net::IpAddr>)> {
+    match clients.get(client_entity) {
+        Ok((auth, metadata)) => Some((auth.account_id, metadata.map(|m| m.ip_address))),
+        Err(_) => {
+            send_status(commands, client_entity, false, false, "You must be logged in".to_string(), Vec::new());
+            None
+        }
+    }
+}
+
+fn send_status(
+    commands: &mut Commands,
+    client_entity: Entity,
+    success: bool,
+    enabled: bool,
+    message: String,
+    backup_codes: Vec<String>,
+) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: TwoFactorStatusResponse { success, message, enabled, backup_codes },
+    });
+}
+
+/// Codes are guessable, so attempts share the login rate limit
+fn rate_limited(
+    commands: &mut Commands,
+    client_entity: Entity,
+    ip_address: Option<std::net::IpAddr>,
+    rate_limiters: &crate::RateLimiters,
+    enabled: bool,
+) -> bool {
+    let limited = ip_address.is_some_and(|ip| rate_limiters.login_attempts.check_key(&ip).is_err());
+    if limited {
+        send_status(commands, client_entity, false, enabled, "Too many attempts. Please try again later.".to_string(), Vec::new());
+    }
+    limited
+}
+
+/// Start enrollment: store a new secret and send it to the client
+pub fn handle_two_factor_setup(
+    trigger: On<FromClient<TwoFactorSetupRequest>>,
+    mut commands: Commands,
+    clients: Query<(&Authenticated, Option<&ClientMetadata>)>,
+    db: Res<DatabaseConnection>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Some((account_id, _)) = authenticated_account(&mut commands, client_entity, &clients) else { return };
+
+    db.run(
+        move |pool| async move {
+            let Some(username) = records::password_account_username(&pool, account_id).await? else {
+                return Err("Two-factor for this account is managed by your sign-in provider".to_string());
+            };
+            if records::get_two_factor(&pool, account_id).await?.is_some_and(|record| record.enabled) {
+                return Err("Two-factor authentication is already enabled".to_string());
+            }
+
+            let secret = generate_secret();
+            records::begin_enrollment(&pool, account_id, &secret).await?;
+            Ok((otpauth_uri(&username, &secret), secret))
+        },
+        move |result, world| {
+            let response = match result {
+                Ok((otpauth_uri, secret)) => TwoFactorSetupResponse {
+                    success: true,
+                    message: "Add this key to your authenticator app, then enter the code it shows".to_string(),
+                    secret,
+                    otpauth_uri,
+                },
+                Err(message) => TwoFactorSetupResponse {
+                    success: false,
+                    message,
+                    secret: String::new(),
+                    otpauth_uri: String::new(),
+                },
+            };
+            world.commands().server_trigger(ToClients {
+                mode: SendMode::Direct(ClientId::Client(client_entity)),
+                message: response,
+            });
+        },
+    );
+}
+
+/// Finish enrollment once the player proves their authenticator works
+pub fn handle_two_factor_confirm(
+    trigger: On<FromClient<TwoFactorConfirmRequest>>,
+    mut commands: Commands,
+    clients: Query<(&Authenticated, Option<&ClientMetadata>)>,
+    db: Res<DatabaseConnection>,
+    rate_limiters: Res<crate::RateLimiters>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Some((account_id, ip_address)) = authenticated_account(&mut commands, client_entity, &clients) else { return };
+    if rate_limited(&mut commands, client_entity, ip_address, &rate_limiters, false) {
+        return;
+    }
+
+    let code = trigger.event().code.clone();
+    db.run(
+        move |pool| async move {
+            let record = match records::get_two_factor(&pool, account_id).await? {
+                Some(record) if record.enabled => return Err("Two-factor authentication is already enabled".to_string()),
+                Some(record) => record,
+                None => return Err("Start two-factor setup first".to_string()),
+            };
+            let Some(step) = verify_code(&record.secret, &code, unix_now()) else {
+                return Err("That code doesn't match - check your device's clock and try again".to_string());
+            };
+
+            let backup_codes = generate_backup_codes();
+            let hashes: Vec<String> = backup_codes.iter().map(|code| hash_backup_code(code)).collect();
+            records::enable_two_factor(&pool, account_id, step, &hashes).await?;
+
+            let ip = ip_address.map(|ip| ip.to_string());
+            let _ = log_audit_event(&pool, AuditActionType::TwoFactorEnabled, Some(account_id), Some(account_id), None, ip.as_deref(), None).await;
+            Ok(backup_codes)
+        },
+        move |result, world| {
+            let mut commands = world.commands();
+            match result {
+                Ok(backup_codes) => {
+                    info!("Account {} enabled two-factor authentication", account_id);
+                    let message = "Two-factor authentication is on. Store these backup codes somewhere safe - they won't be shown again".to_string();
+                    send_status(&mut commands, client_entity, true, true, message, backup_codes);
+                }
+                Err(message) => send_status(&mut commands, client_entity, false, false, message, Vec::new()),
+            }
+        },
+    );
+}
+
+/// Turn two-factor off with a current authenticator or backup code
+pub fn handle_two_factor_disable(
+    trigger: On<FromClient<TwoFactorDisableRequest>>,
+    mut commands: Commands,
+    clients: Query<(&Authenticated, Option<&ClientMetadata>)>,
+    db: Res<DatabaseConnection>,
+    rate_limiters: Res<crate::RateLimiters>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let Some((account_id, ip_address)) = authenticated_account(&mut commands, client_entity, &clients) else { return };
+    if rate_limited(&mut commands, client_entity, ip_address, &rate_limiters, true) {
+        return;
+    }
+
+    let code = trigger.event().code.clone();
+    db.run(
+        move |pool| async move {
+            let record = match records::get_two_factor(&pool, account_id).await? {
+                Some(record) if record.enabled => record,
+                _ => return Ok(()),
+            };
+            redeem_code(&pool, account_id, &record, &code).await?;
+            records::remove_two_factor(&pool, account_id).await?;
+
+            let ip = ip_address.map(|ip| ip.to_string());
+            let _ = log_audit_event(&pool, AuditActionType::TwoFactorDisabled, Some(account_id), Some(account_id), None, ip.as_deref(), None).await;
+            Ok(())
+        },
+        move |result, world| {
+            let mut commands = world.commands();
+            match result {
+                Ok(()) => {
+                    info!("Account {} disabled two-factor authentication", account_id);
+                    send_status(&mut commands, client_entity, true, false, "Two-factor authentication is off".to_string(), Vec::new());
+                }
+                Err(message) => send_status(&mut commands, client_entity, false, true, message, Vec::new()),
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret ("12345678901234567890"), SHA-1, truncated to 6 digits
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn base32_round_trip() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(base32_decode(RFC_SECRET).unwrap(), b"12345678901234567890");
+        assert_eq!(base32_decode("gezd gnbv").unwrap(), base32_decode("GEZDGNBV").unwrap());
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        for (time, code) in [(59, "287082"), (1111111109, "081804"), (1234567890, "005924"), (2000000000, "279037")] {
+            assert_eq!(verify_code(RFC_SECRET, code, time), Some(time / STEP_SECONDS));
+        }
+    }
+
+    #[test]
+    fn allows_one_step_of_drift() {
+        // The code for t=59 (step 1) is still valid one step later, but not two
+        assert_eq!(verify_code(RFC_SECRET, "287082", 59 + STEP_SECONDS), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "287 082", 59), Some(1));
+        assert_eq!(verify_code(RFC_SECRET, "287082", 59 + 2 * STEP_SECONDS), None);
+        assert_eq!(verify_code(RFC_SECRET, "28708", 59), None);
+    }
+
+    #[test]
+    fn backup_codes_are_hashed_and_normalized() {
+        let codes = generate_backup_codes();
+        assert_eq!(codes.len(), BACKUP_CODE_COUNT);
+        let hash = hash_backup_code(&codes[0]);
+        assert!(!hash.contains(&normalize_backup_code(&codes[0])));
+        assert!(backup_code_matches(&normalize_backup_code(&codes[0].to_uppercase().replace('-', " ")), &hash));
+        assert!(!backup_code_matches(&normalize_backup_code(&codes[1]), &hash));
+    }
+
+    #[test]
+    fn otpauth_uri_escapes_username() {
+        assert_eq!(
+            otpauth_uri("Sir Knight", "ABC"),
+            "otpauth://totp/Eryndor:Sir%20Knight?secret=ABC&issuer=Eryndor&digits=6&period=30"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Cross-checks for game content.
+//!
+//! Parsing catches malformed files; this catches well-formed ones that don't fit together: quests
+//! asking for items, enemies or NPCs that don't exist, abilities whose effects can never do
+//! anything, loot that can never drop, negative cooldowns and avoidance caps. Every issue names
+//! the file and entry it was found in.
+//!
+//! The same pass runs headless (`server validate-data`), from the editor (`GET
+//! /api/editor/validate`) and before `reload-data` swaps anything in.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::data_reload::GameData;
+use crate::game_data::QuestObjective;
+use eryndor_shared::{AbilityType, AbilityUnlockRequirement, LootTable};
+
+/// One problem with the content, located by file and entry
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub file: String,
+    /// Definition inside the file, e.g. "quest 12"; none for problems with the file as a whole
+    pub entry: Option<String>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn new(file: impl fmt::Display, entry: Option<&str>, message: impl fmt::Display) -> Self {
+        Self {
+            file: file.to_string(),
+            entry: entry.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entry {
+            Some(entry) => write!(f, "{}: {}: {}", self.file, entry, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Load everything under `dir` and check it, parse errors included
+pub fn validate_content(dir: &Path) -> Vec<ValidationIssue> {
+    let (data, mut issues) = GameData::load(dir);
+    issues.extend(validate(&data));
+    issues
+}
+
+/// Every reference that doesn't resolve and every definition that can't work, sorted by file
+pub fn validate(data: &GameData) -> Vec<ValidationIssue> {
+    let mut checks = Checks { data, issues: Vec::new() };
+    let npcs: HashSet<u32> = data.zones.values()
+        .flat_map(|zone| zone.npc_spawns.iter().map(|npc| npc.npc_id))
+        .collect();
+
+    for item in data.items.values() {
+        let key = format!("item {}", item.id);
+        if let Some(ability_id) = item.grants_ability {
+            checks.ability(&key, ability_id, "grants");
+        }
+        if let Some(effect) = &item.consumable {
+            if effect.cooldown < 0.0 {
+                checks.report(&key, format!("has a negative cooldown ({})", effect.cooldown));
+            }
+        }
+    }
+
+    for quest in data.quests.values() {
+        let key = format!("quest {}", quest.id);
+        for objective in &quest.objectives {
+            match objective {
+                QuestObjective::ObtainItem { item_id, .. } => checks.item(&key, *item_id, "asks for"),
+                QuestObjective::KillEnemy { enemy_type, .. } => checks.enemy(&key, *enemy_type, "asks to kill"),
+                QuestObjective::TalkToNpc { npc_id } if !npcs.contains(npc_id) => {
+                    checks.report(&key, format!("asks to talk to NPC {}, who isn't placed in any zone", npc_id));
+                }
+                QuestObjective::TalkToNpc { .. } => {}
+            }
+        }
+        for ability_id in &quest.reward_abilities {
+            checks.ability(&key, *ability_id, "rewards");
+        }
+    }
+
+    for ability in data.abilities.values() {
+        checks.ability_effects(&format!("ability {}", ability.id), &ability.ability_types);
+        if let AbilityUnlockRequirement::Quest(quest_id) = ability.unlock_requirement {
+            checks.quest(&format!("ability {}", ability.id), quest_id, "is unlocked by");
+        }
+    }
+
+    for enemy in data.enemies.values() {
+        let key = format!("enemy {}", enemy.id);
+        checks.loot(&key, &enemy.loot_table);
+        if let Some(table_id) = &enemy.loot_table.table_id {
+            if !data.loot_tables.contains_key(table_id) {
+                checks.report(&key, format!("uses unknown loot table '{}'", table_id));
+            }
+        }
+    }
+
+    for table in data.loot_tables.values() {
+        checks.loot(&format!("loot table {}", table.id), &table.table);
+    }
+
+    for trainer in data.trainers.values() {
+        let key = format!("trainer {}", trainer.name);
+        for stock in &trainer.items {
+            checks.item(&key, stock.item_id, "sells");
+        }
+    }
+
+    for shop in data.shops.values() {
+        let key = format!("shop {}", shop.id);
+        for stock in &shop.items {
+            checks.item(&key, stock.item_id, "sells");
+        }
+    }
+
+    for zone in data.zones.values() {
+        for npc in &zone.npc_spawns {
+            let key = format!("npc {}", npc.npc_id);
+            for quest_id in npc.quests.iter().chain(&npc.teaching_quests) {
+                checks.quest(&key, *quest_id, "offers");
+            }
+        }
+        let key = format!("zone {}", zone.zone_id);
+        for region in &zone.enemy_spawns {
+            if region.spawn_table.is_empty() {
+                checks.enemy(&key, region.enemy_type, &format!("spawns (region '{}')", region.region_id));
+            }
+            for entry in &region.spawn_table {
+                checks.enemy(&key, entry.enemy_type, &format!("spawns (region '{}')", region.region_id));
+            }
+        }
+    }
+
+    for formulas in data.formulas.values() {
+        let key = format!("formulas {}", formulas.id);
+        let caps = [
+            ("max_dodge_chance", formulas.max_dodge_chance),
+            ("max_parry_chance", formulas.max_parry_chance),
+            ("max_block_chance", formulas.max_block_chance),
+        ];
+        for (name, cap) in caps {
+            if cap < 0.0 {
+                checks.report(&key, format!("{} is negative ({})", name, cap));
+            }
+        }
+    }
+
+    let mut issues = checks.issues;
+    issues.sort_by(|a, b| (&a.file, &a.entry, &a.message).cmp(&(&b.file, &b.entry, &b.message)));
+    issues
+}
+
+struct Checks<'a> {
+    data: &'a GameData,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Checks<'_> {
+    fn report(&mut self, key: &str, message: String) {
+        self.issues.push(ValidationIssue::new(self.data.source(key), Some(key), message));
+    }
+
+    fn item(&mut self, key: &str, item_id: u32, verb: &str) {
+        if !self.data.items.contains_key(&item_id) {
+            self.report(key, format!("{} unknown item {}", verb, item_id));
+        }
+    }
+
+    fn enemy(&mut self, key: &str, enemy_id: u32, verb: &str) {
+        if !self.data.enemies.contains_key(&enemy_id) {
+            self.report(key, format!("{} unknown enemy {}", verb, enemy_id));
+        }
+    }
+
+    fn ability(&mut self, key: &str, ability_id: u32, verb: &str) {
+        if !self.data.abilities.contains_key(&ability_id) {
+            self.report(key, format!("{} unknown ability {}", verb, ability_id));
+        }
+    }
+
+    fn quest(&mut self, key: &str, quest_id: u32, verb: &str) {
+        if !self.data.quests.contains_key(&quest_id) {
+            self.report(key, format!("{} unknown quest {}", verb, quest_id));
+        }
+    }
+
+    /// Areas and projectiles only deliver the ability's other effects, and timed effects need
+    /// time to run
+    fn ability_effects(&mut self, key: &str, effects: &[AbilityType]) {
+        if effects.is_empty() {
+            self.report(key, "has no effects".to_string());
+        } else if effects.iter().all(|effect| matches!(effect, AbilityType::AreaOfEffect { .. } | AbilityType::Projectile { .. })) {
+            self.report(key, "has an area or projectile but no effect for it to deliver".to_string());
+        }
+
+        for effect in effects {
+            let (name, duration) = match effect {
+                AbilityType::DamageOverTime { duration, ticks, .. } => {
+                    if *ticks == 0 {
+                        self.report(key, "DamageOverTime has no ticks".to_string());
+                    }
+                    ("DamageOverTime", *duration)
+                }
+                AbilityType::Buff { duration, .. } => ("Buff", *duration),
+                AbilityType::Debuff { duration, .. } => ("Debuff", *duration),
+                AbilityType::ManaShield { duration, .. } => ("ManaShield", *duration),
+                AbilityType::Taunt { duration } => ("Taunt", *duration),
+                _ => continue,
+            };
+            if duration <= 0.0 {
+                self.report(key, format!("{} has no duration", name));
+            }
+        }
+    }
+
+    /// Drops must be real items and weighted groups must be able to pick something
+    fn loot(&mut self, key: &str, loot: &LootTable) {
+        for drop in &loot.items {
+            self.item(key, drop.item_id, "drops");
+            if let Some(quest_id) = drop.required_quest {
+                self.quest(key, quest_id, "has a drop for");
+            }
+        }
+
+        for group in &loot.groups {
+            for entry in &group.entries {
+                self.item(key, entry.item_id, &format!("drops (group '{}')", group.name));
+                if let Some(quest_id) = entry.required_quest {
+                    self.quest(key, quest_id, &format!("has a drop (group '{}') for", group.name));
+                }
+            }
+            let weight: f32 = group.entries.iter().map(|entry| entry.weight.max(0.0)).sum();
+            if weight <= 0.0 {
+                self.report(key, format!("loot group '{}' has no weighted entries, so it never drops anything", group.name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn parse<T: DeserializeOwned>(value: serde_json::Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn sample() -> GameData {
+        let mut data = GameData::default();
+        data.abilities.insert(1, parse(json!({
+            "id": 1, "name": "Strike", "description": "", "damage_multiplier": 1.0,
+            "cooldown": 1.0, "range": 30.0, "mana_cost": 0.0,
+            "ability_types": [{ "DirectDamage": { "multiplier": 1.0 } }], "unlock_requirement": "None",
+        })));
+        data.items.insert(10, parse(json!({
+            "id": 10, "name": "Sword", "item_type": "Weapon", "grants_ability": 1,
+            "stat_bonuses": { "attack_power": 5.0, "defense": 0.0, "max_health": 0.0, "max_mana": 0.0, "crit_chance": 0.0 },
+        })));
+        data.enemies.insert(5, parse(json!({
+            "id": 5, "name": "Wolf", "max_health": 50.0, "attack_power": 5.0, "defense": 1.0,
+            "move_speed": 100.0, "loot_table": { "items": [{ "item_id": 10 }] },
+        })));
+        data.quests.insert(7, parse(json!({
+            "id": 7, "name": "Hunt", "description": "",
+            "objectives": [{ "type": "KillEnemy", "enemy_type": 5, "count": 3 }],
+            "reward_exp": 10, "proficiency_requirements": [], "reward_abilities": [1],
+        })));
+        data
+    }
+
+    #[test]
+    fn consistent_data_passes() {
+        assert_eq!(validate(&sample()), Vec::new());
+    }
+
+    #[test]
+    fn shipped_content_is_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/content");
+        let issues: Vec<String> = validate_content(&dir).iter().map(ToString::to_string).collect();
+        assert_eq!(issues, Vec::<String>::new());
+    }
+
+    #[test]
+    fn dangling_references_name_the_file_and_entry() {
+        let mut data = sample();
+        data.items.get_mut(&10).unwrap().grants_ability = Some(99);
+        data.files.insert("item 10".to_string(), PathBuf::from("items/sword.item.json"));
+        data.quests.get_mut(&7).unwrap().objectives.push(QuestObjective::TalkToNpc { npc_id: 4 });
+        data.trainers.insert("Smith".to_string(), TrainerDefinition {
+            name: "Smith".to_string(),
+            items: vec![TrainerItem { item_id: 12, cost: 5 }],
+        });
+
+        let issues: Vec<String> = validate(&data).iter().map(ToString::to_string).collect();
+        assert_eq!(issues, vec![
+            "built-in: quest 7: asks to talk to NPC 4, who isn't placed in any zone",
+            "built-in: trainer Smith: sells unknown item 12",
+            "items/sword.item.json: item 10: grants unknown ability 99",
+        ]);
+    }
+
+    #[test]
+    fn unusable_abilities_and_loot_are_reported() {
+        let mut data = sample();
+        data.abilities.get_mut(&1).unwrap().ability_types = vec![AbilityType::Projectile { speed: 10.0, radius: 0.5 }];
+        data.enemies.get_mut(&5).unwrap().loot_table.groups.push(parse(json!({
+            "name": "Pelts", "entries": [{ "item_id": 10, "weight": 0.0 }],
+        })));
+
+        let issues: Vec<String> = validate(&data).iter().map(|issue| issue.message.clone()).collect();
+        assert_eq!(issues, vec![
+            "has an area or projectile but no effect for it to deliver",
+            "loot group 'Pelts' has no weighted entries, so it never drops anything",
+        ]);
+    }
+
+    #[test]
+    fn negative_consumable_cooldowns_are_reported() {
+        let mut data = sample();
+        data.items.insert(11, parse(json!({
+            "id": 11, "name": "Potion", "item_type": "Consumable",
+            "stat_bonuses": { "attack_power": 0.0, "defense": 0.0, "max_health": 0.0, "max_mana": 0.0, "crit_chance": 0.0 },
+            "consumable": { "cooldown_category": "potion", "cooldown": -1.0, "restore_health": 50.0 },
+        })));
+
+        let issues: Vec<String> = validate(&data).iter().map(ToString::to_string).collect();
+        assert_eq!(issues, vec!["built-in: item 11: has a negative cooldown (-1)"]);
+    }
+
+    #[test]
+    fn negative_avoidance_caps_are_reported() {
+        let mut data = sample();
+        data.formulas.insert("combat".to_string(), crate::combat_formulas::CombatFormulas {
+            max_parry_chance: -0.5,
+            ..Default::default()
+        });
+
+        let issues: Vec<String> = validate(&data).iter().map(ToString::to_string).collect();
+        assert_eq!(issues, vec!["built-in: formulas combat: max_parry_chance is negative (-0.5)"]);
+    }
+}
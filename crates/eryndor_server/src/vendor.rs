@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use std::collections::VecDeque;
+use crate::auth::ActiveCharacterEntity;
+use crate::game_data::{ItemDatabase, ShopDatabase, ShopDefinition};
+use crate::spatial::SpatialIndex;
+
+/// Most recent sales a player can buy back
+pub const BUYBACK_LIMIT: usize = 10;
+
+/// Shop an NPC sells from (id into ShopDatabase)
+#[derive(Component, Clone, Debug)]
+pub struct Vendor(pub String);
+
+/// Stacks a player sold this session, newest first
+#[derive(Component, Default, Debug)]
+pub struct Buyback {
+    pub entries: VecDeque<BuybackEntry>,
+}
+
+/// Build the vendor window contents for a player
+pub fn build_vendor_window(
+    npc_name: &str,
+    shop: &ShopDefinition,
+    inventory: &Inventory,
+    buyback: Option<&Buyback>,
+    item_db: &ItemDatabase,
+) -> VendorWindowEvent {
+    let items = shop.items.iter()
+        .filter_map(|shop_item| {
+            let item_def = item_db.items.get(&shop_item.item_id)?;
+            Some(VendorItemInfo { item_id: shop_item.item_id, price: shop.buy_price(shop_item, item_def) })
+        })
+        .collect();
+
+    let mut sell_prices: Vec<VendorItemInfo> = Vec::new();
+    for stack in inventory.slots.iter().flatten() {
+        if sell_prices.iter().any(|info| info.item_id == stack.item_id) {
+            continue;
+        }
+        if let Some(price) = item_db.items.get(&stack.item_id).and_then(|def| shop.sell_price(def)) {
+            sell_prices.push(VendorItemInfo { item_id: stack.item_id, price });
+        }
+    }
+
+    VendorWindowEvent {
+        npc_name: npc_name.to_string(),
+        shop_name: shop.name.clone(),
+        items,
+        sell_prices,
+        buyback: buyback.map(|b| b.entries.iter().cloned().collect()).unwrap_or_default(),
+    }
+}
+
+/// Find the closest vendor within interaction range of a position
+fn find_closest_vendor<'a>(
+    player_pos: Vec2,
+    vendors: &'a Query<(&Position, &Vendor, &NpcName), With<Npc>>,
+    spatial: &SpatialIndex,
+) -> Option<(&'a Vendor, &'a NpcName)> {
+    spatial.nearby(player_pos, INTERACTION_RANGE)
+        .filter_map(|entity| vendors.get(entity).ok())
+        .map(|(pos, vendor, name)| (player_pos.distance(pos.0), vendor, name))
+        .filter(|(distance, _, _)| *distance <= INTERACTION_RANGE)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, vendor, name)| (vendor, name))
+}
+
+fn notify(commands: &mut Commands, client_entity: Entity, message: String, notification_type: NotificationType) {
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: NotificationEvent { message, notification_type },
+    });
+}
+
+pub fn handle_buy_from_vendor(
+    trigger: On<FromClient<BuyFromVendorRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    mut players: Query<(&Position, &mut Gold, &mut Inventory, Option<&Buyback>)>,
+    vendors: Query<(&Position, &Vendor, &NpcName), With<Npc>>,
+    spatial: Res<SpatialIndex>,
+    shop_db: Res<ShopDatabase>,
+    item_db: Res<ItemDatabase>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let request = trigger.event();
+
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((player_pos, mut gold, mut inventory, buyback)) = players.get_mut(active_char.0) else { return };
+
+    let Some((vendor, npc_name)) = find_closest_vendor(player_pos.0, &vendors, &spatial) else {
+        notify(&mut commands, client_entity, "No vendor nearby!".to_string(), NotificationType::Warning);
+        return;
+    };
+    let Some(shop) = shop_db.shops.get(&vendor.0) else {
+        warn!("Vendor {} references unknown shop {}", npc_name.0, vendor.0);
+        return;
+    };
+
+    let Some(shop_item) = shop.items.iter().find(|item| item.item_id == request.item_id) else {
+        notify(&mut commands, client_entity, "This item is not sold here!".to_string(), NotificationType::Warning);
+        return;
+    };
+    let Some(item_def) = item_db.items.get(&request.item_id) else {
+        error!("Item definition not found for item ID {}", request.item_id);
+        return;
+    };
+
+    let price = shop.buy_price(shop_item, item_def);
+    if gold.0 < price {
+        notify(&mut commands, client_entity, format!("Not enough gold! Need {} gold.", price), NotificationType::Warning);
+        return;
+    }
+
+    if !inventory.add_item(ItemStack { item_id: request.item_id, quantity: 1 }) {
+        notify(&mut commands, client_entity, "Inventory is full!".to_string(), NotificationType::Warning);
+        return;
+    }
+    gold.0 -= price;
+
+    info!("{:?} bought {} from {} for {} gold", active_char.0, item_def.name, npc_name.0, price);
+    notify(&mut commands, client_entity, format!("Purchased {} for {} gold!", item_def.name, price), NotificationType::Success);
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: build_vendor_window(&npc_name.0, shop, &inventory, buyback, &item_db),
+    });
+}
+
+pub fn handle_sell_to_vendor(
+    trigger: On<FromClient<SellToVendorRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    mut players: Query<(&Position, &mut Gold, &mut Inventory, Option<&mut Buyback>)>,
+    vendors: Query<(&Position, &Vendor, &NpcName), With<Npc>>,
+    spatial: Res<SpatialIndex>,
+    shop_db: Res<ShopDatabase>,
+    item_db: Res<ItemDatabase>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let request = trigger.event();
+
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let char_entity = active_char.0;
+    let Ok((player_pos, mut gold, mut inventory, buyback)) = players.get_mut(char_entity) else { return };
+
+    let Some((vendor, npc_name)) = find_closest_vendor(player_pos.0, &vendors, &spatial) else {
+        notify(&mut commands, client_entity, "No vendor nearby!".to_string(), NotificationType::Warning);
+        return;
+    };
+    let Some(shop) = shop_db.shops.get(&vendor.0) else {
+        warn!("Vendor {} references unknown shop {}", npc_name.0, vendor.0);
+        return;
+    };
+
+    let Some(Some(stack)) = inventory.slots.get(request.slot_index).cloned() else {
+        return;
+    };
+    let Some(item_def) = item_db.items.get(&stack.item_id) else {
+        error!("Item definition not found for item ID {}", stack.item_id);
+        return;
+    };
+    let Some(unit_price) = shop.sell_price(item_def) else {
+        notify(&mut commands, client_entity, format!("{} cannot be sold.", item_def.name), NotificationType::Warning);
+        return;
+    };
+
+    let Some(total) = unit_price.checked_mul(stack.quantity) else {
+        warn!("{:?} tried to sell {}x {}, worth more gold than fits", char_entity, stack.quantity, item_def.name);
+        notify(&mut commands, client_entity, format!("{} cannot be sold.", item_def.name), NotificationType::Warning);
+        return;
+    };
+
+    inventory.remove_item(request.slot_index);
+    gold.0 = gold.0.saturating_add(total);
+
+    let entry = BuybackEntry { item_id: stack.item_id, quantity: stack.quantity, price: total };
+    let buyback_snapshot = match buyback {
+        Some(mut buyback) => {
+            buyback.entries.push_front(entry);
+            buyback.entries.truncate(BUYBACK_LIMIT);
+            Buyback { entries: buyback.entries.clone() }
+        }
+        None => {
+            let buyback = Buyback { entries: VecDeque::from([entry]) };
+            commands.entity(char_entity).insert(Buyback { entries: buyback.entries.clone() });
+            buyback
+        }
+    };
+
+    info!("{:?} sold {}x {} to {} for {} gold", char_entity, stack.quantity, item_def.name, npc_name.0, total);
+    notify(&mut commands, client_entity, format!("Sold {} for {} gold.", item_def.name, total), NotificationType::Info);
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: build_vendor_window(&npc_name.0, shop, &inventory, Some(&buyback_snapshot), &item_db),
+    });
+}
+
+pub fn handle_buyback(
+    trigger: On<FromClient<BuybackRequest>>,
+    mut commands: Commands,
+    clients: Query<&ActiveCharacterEntity>,
+    mut players: Query<(&Position, &mut Gold, &mut Inventory, &mut Buyback)>,
+    vendors: Query<(&Position, &Vendor, &NpcName), With<Npc>>,
+    spatial: Res<SpatialIndex>,
+    shop_db: Res<ShopDatabase>,
+    item_db: Res<ItemDatabase>,
+) {
+    let Some(client_entity) = trigger.client_id.entity() else { return };
+    let request = trigger.event();
+
+    let Ok(active_char) = clients.get(client_entity) else { return };
+    let Ok((player_pos, mut gold, mut inventory, mut buyback)) = players.get_mut(active_char.0) else { return };
+
+    let Some((vendor, npc_name)) = find_closest_vendor(player_pos.0, &vendors, &spatial) else {
+        notify(&mut commands, client_entity, "No vendor nearby!".to_string(), NotificationType::Warning);
+        return;
+    };
+    let Some(shop) = shop_db.shops.get(&vendor.0) else {
+        warn!("Vendor {} references unknown shop {}", npc_name.0, vendor.0);
+        return;
+    };
+
+    let Some(entry) = buyback.entries.get(request.index).cloned() else {
+        return;
+    };
+    if gold.0 < entry.price {
+        notify(&mut commands, client_entity, format!("Not enough gold! Need {} gold.", entry.price), NotificationType::Warning);
+        return;
+    }
+    if !inventory.add_item(ItemStack { item_id: entry.item_id, quantity: entry.quantity }) {
+        notify(&mut commands, client_entity, "Inventory is full!".to_string(), NotificationType::Warning);
+        return;
+    }
+
+    gold.0 -= entry.price;
+    buyback.entries.remove(request.index);
+
+    let item_name = item_db.items.get(&entry.item_id).map(|def| def.name.as_str()).unwrap_or("item");
+    info!("{:?} bought back {}x {} for {} gold", active_char.0, entry.quantity, item_name, entry.price);
+    notify(&mut commands, client_entity, format!("Bought back {} for {} gold.", item_name, entry.price), NotificationType::Success);
+
+    commands.server_trigger(ToClients {
+        mode: SendMode::Direct(ClientId::Client(client_entity)),
+        message: build_vendor_window(&npc_name.0, shop, &inventory, Some(&buyback), &item_db),
+    });
+}
@@ -0,0 +1,158 @@
+//! World time and zone weather.
+//!
+//! A single replicated `WorldClock` entity carries the time of day, advanced by the server so
+//! every client sees the same day/night cycle. Each zone with a weather table in its zone file
+//! gets a replicated `ZoneWeather` entity that re-rolls its weather on a timer.
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use eryndor_shared::*;
+use rand::Rng;
+use crate::game_data::{WeatherTable, ZoneDatabase};
+
+/// Seconds between world clock updates sent to clients
+const CLOCK_UPDATE_INTERVAL: f32 = 1.0;
+
+/// Server-side precise time of day; the replicated `WorldClock` is updated from it each interval
+#[derive(Resource)]
+pub struct WorldTime {
+    /// In-game hours since the server started, offset by `WORLD_START_HOUR`
+    hours: f64,
+    update_timer: Timer,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self {
+            hours: WORLD_START_HOUR as f64,
+            update_timer: Timer::from_seconds(CLOCK_UPDATE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl WorldTime {
+    fn clock(&self) -> WorldClock {
+        WorldClock {
+            hour: self.hours.rem_euclid(24.0) as f32,
+            day: (self.hours / 24.0) as u32 + 1,
+        }
+    }
+}
+
+/// Seconds until a zone's weather is rolled again
+#[derive(Component)]
+pub struct WeatherTimer(pub Timer);
+
+pub fn spawn_world_clock(mut commands: Commands, world_time: Res<WorldTime>) {
+    commands.spawn((Replicated, world_time.clock()));
+}
+
+pub fn advance_world_clock(
+    mut world_time: ResMut<WorldTime>,
+    mut clocks: Query<&mut WorldClock>,
+    time: Res<Time>,
+) {
+    world_time.hours += time.delta_secs_f64() * 24.0 / DAY_LENGTH_SECONDS as f64;
+    world_time.update_timer.tick(time.delta());
+    if !world_time.update_timer.just_finished() {
+        return;
+    }
+
+    let clock = world_time.clock();
+    for mut world_clock in &mut clocks {
+        *world_clock = clock;
+    }
+}
+
+fn roll_weather(table: &WeatherTable, rng: &mut impl Rng) -> (Weather, f32, Timer) {
+    let weather = table.pick(rng.gen());
+    let intensity = match weather {
+        Weather::Clear | Weather::Cloudy => 0.0,
+        Weather::Fog | Weather::Rain => rng.gen_range(0.4..=0.8),
+        Weather::Storm => rng.gen_range(0.8..=1.0),
+    };
+    let duration = if table.max_duration > table.min_duration {
+        rng.gen_range(table.min_duration..=table.max_duration)
+    } else {
+        table.min_duration
+    };
+    (weather, intensity, Timer::from_seconds(duration.max(1.0), TimerMode::Once))
+}
+
+/// Keep one weather entity per zone with a weather table and re-roll weather when it runs out
+pub fn update_zone_weather(
+    mut commands: Commands,
+    zone_db: Res<ZoneDatabase>,
+    mut zones: Query<(Entity, &mut ZoneWeather, &mut WeatherTimer)>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (zone_id, zone) in &zone_db.zones {
+        let Some(table) = zone.weather.as_ref().filter(|table| !table.entries.is_empty()) else { continue };
+        if zones.iter().any(|(_, weather, _)| weather.zone_id == *zone_id) {
+            continue;
+        }
+
+        let (weather, intensity, timer) = roll_weather(table, &mut rng);
+        commands.spawn((
+            Replicated,
+            ZoneWeather { zone_id: zone_id.clone(), weather, intensity },
+            WeatherTimer(timer),
+        ));
+        info!("Zone {} weather starts {}", zone_id, weather.as_str());
+    }
+
+    for (entity, mut zone_weather, mut timer) in &mut zones {
+        // Zone removed, or its weather table taken out (hot reload)
+        let Some(table) = zone_db.zones.get(&zone_weather.zone_id)
+            .and_then(|zone| zone.weather.as_ref())
+            .filter(|table| !table.entries.is_empty()) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        timer.0.tick(time.delta());
+        if !timer.0.is_finished() {
+            continue;
+        }
+
+        let (weather, intensity, next) = roll_weather(table, &mut rng);
+        if weather != zone_weather.weather {
+            info!("Zone {} weather changes from {} to {}", zone_weather.zone_id, zone_weather.weather.as_str(), weather.as_str());
+        }
+        zone_weather.weather = weather;
+        zone_weather.intensity = intensity;
+        timer.0 = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data::WeatherTableEntry;
+
+    #[test]
+    fn weather_is_picked_by_weight() {
+        let table = WeatherTable {
+            entries: vec![
+                WeatherTableEntry { weather: Weather::Clear, weight: 3.0 },
+                WeatherTableEntry { weather: Weather::Rain, weight: 1.0 },
+            ],
+            min_duration: 60.0,
+            max_duration: 60.0,
+        };
+        assert_eq!(table.pick(0.0), Weather::Clear);
+        assert_eq!(table.pick(0.74), Weather::Clear);
+        assert_eq!(table.pick(0.76), Weather::Rain);
+        assert_eq!(table.pick(0.999), Weather::Rain);
+    }
+
+    #[test]
+    fn daylight_follows_the_clock() {
+        assert_eq!(WorldClock { hour: 12.0, day: 1 }.daylight(), 1.0);
+        assert_eq!(WorldClock { hour: 0.0, day: 1 }.daylight(), 0.0);
+        assert!((WorldClock { hour: 6.0, day: 1 }.daylight() - 0.5).abs() < 1e-6);
+        assert!((WorldClock { hour: 19.0, day: 1 }.daylight() - 0.5).abs() < 1e-6);
+    }
+}
@@ -3,7 +3,17 @@ use bevy_replicon::prelude::*;
 use eryndor_shared::*;
 use avian2d::prelude::{RigidBody, Collider, CollisionLayers};
 use crate::PhysicsPosition;
-use crate::game_data::ZoneDatabase;
+use crate::assets::{EnemyAsset, LoadedContentAssets};
+use crate::game_data::{EnemyDatabase, ZoneDatabase};
+use bevy::asset::LoadState;
+use std::collections::{HashMap, HashSet};
+
+/// Zone the player is currently in. Players without this component are in the starter zone.
+#[derive(Component, Clone, Debug)]
+pub struct CurrentZone(pub String);
+
+/// The zone the world is built from. It is the only zone the server spawns.
+pub const STARTER_ZONE: &str = "starter_zone";
 
 /// Marker resource indicating the world has been spawned
 #[derive(Resource, Default)]
@@ -12,10 +22,25 @@ pub struct WorldSpawned;
 /// Run condition: returns true when zone data is loaded and world hasn't been spawned
 pub fn zone_data_loaded(
     zone_db: Res<ZoneDatabase>,
+    enemy_db: Res<EnemyDatabase>,
+    asset_server: Res<AssetServer>,
+    enemy_assets: Res<Assets<EnemyAsset>>,
+    loaded_assets: Res<LoadedContentAssets>,
     world_spawned: Option<Res<WorldSpawned>>,
 ) -> bool {
     // Only spawn if zone data exists and world hasn't been spawned yet
-    world_spawned.is_none() && zone_db.zones.contains_key("starter_zone")
+    if world_spawned.is_some() || !zone_db.zones.contains_key(STARTER_ZONE) {
+        return false;
+    }
+
+    // Wait until every enemy file has finished loading (or failed to) and reached the database.
+    // Enemies the zone names that still aren't known are skipped with a warning at spawn time.
+    loaded_assets.enemies.keys().all(|&id| match asset_server.get_load_state(id) {
+        Some(LoadState::Loaded) => enemy_assets.get(id)
+            .is_some_and(|asset| enemy_db.enemies.contains_key(&asset.0.id)),
+        Some(LoadState::Failed(_)) => true,
+        _ => false,
+    })
 }
 
 /// System to spawn world boundaries at startup (doesn't depend on JSON data)
@@ -117,162 +142,199 @@ pub fn spawn_world_boundaries(mut commands: Commands) {
 #[derive(Component)]
 pub struct TilemapCollider;
 
+/// Baked walkability grids per zone, used by enemy pathfinding (see pathfinding.rs)
+#[derive(Resource, Default)]
+pub struct ZoneNavigation {
+    pub grids: HashMap<String, NavGrid>,
+}
+
+impl ZoneNavigation {
+    pub fn get(&self, zone_id: &str) -> Option<&NavGrid> {
+        self.grids.get(zone_id)
+    }
+}
+
 /// System to spawn world entities from zone data (runs when zone data is loaded)
-/// Note: Enemy and NPC spawning now handled by Tiled map via tiled_spawner module
 pub fn spawn_world(
     mut commands: Commands,
     zone_db: Res<ZoneDatabase>,
+    enemy_db: Res<EnemyDatabase>,
 ) {
-    info!("Zone data loaded, spawning tilemap collision...");
+    info!("Zone data loaded, preparing tilemap collision and spawning NPCs and enemies...");
 
     // Mark world as spawned
     commands.insert_resource(WorldSpawned);
 
-    // Spawn collision from zone data (enemies/NPCs now come from Tiled map)
-    if let Some(zone) = zone_db.zones.get("starter_zone") {
-        spawn_tilemap_collision(&mut commands, zone);
+    // Collision colliders are streamed in per chunk around players by stream_collision_chunks
+    if let Some(zone) = zone_db.zones.get(STARTER_ZONE) {
+        commands.insert_resource(build_collision_chunks(zone));
+
+        let mut navigation = ZoneNavigation::default();
+        if let Some(grid) = bake_navigation(zone) {
+            info!("Baked navigation grid for zone {} ({} blocked tiles)", zone.zone_id, grid.blocked.len());
+            navigation.grids.insert(zone.zone_id.clone(), grid);
+        }
+        commands.insert_resource(navigation);
+
+        crate::spawn::spawn_zone_npcs(&mut commands, zone);
+        crate::spawn::spawn_zone_enemies(&mut commands, zone, &enemy_db);
     }
 
     info!("World initialization complete!");
 }
 
 
-/// Spawn tilemap collision entities from zone tilemap data
-/// Supports both new TilemapMap format (priority) and legacy ZoneTilemap format
-fn spawn_tilemap_collision(commands: &mut Commands, zone: &crate::game_data::ZoneDefinition) {
-    // Try new TilemapMap format first
+/// Bake a walkability grid from a zone's collision layer
+pub fn bake_navigation(zone: &crate::game_data::ZoneDefinition) -> Option<NavGrid> {
     if let Some(tilemap) = &zone.tilemap_map {
-        spawn_tilemapmap_collision(commands, tilemap, &zone.zone_id);
-        return;
+        return Some(NavGrid::bake_tilemap_map(tilemap, WORLD_WIDTH, WORLD_HEIGHT));
     }
-
-    // Fall back to legacy ZoneTilemap format
     if let Some(tilemap) = &zone.tilemap {
-        spawn_legacy_tilemap_collision(commands, tilemap, &zone.zone_id);
-        return;
+        return Some(NavGrid::bake_zone_tilemap(tilemap, WORLD_WIDTH, WORLD_HEIGHT));
     }
+    None
+}
 
-    info!("No tilemap data for zone {}, skipping collision spawning", zone.zone_id);
+/// Tiles per side of a collision streaming chunk
+pub const COLLISION_CHUNK_TILES: i32 = 16;
+/// Chunks within this many chunks of a player (or an enemy in combat) have their colliders loaded
+pub const COLLISION_LOAD_RADIUS: i32 = 2;
+/// Loaded chunks are only unloaded once beyond this radius, so walking along a chunk border doesn't thrash
+pub const COLLISION_UNLOAD_RADIUS: i32 = 3;
+
+/// Pre-merged collision rectangles for a zone, bucketed by chunk, and the colliders currently spawned for them
+#[derive(Resource, Default)]
+pub struct ZoneCollisionChunks {
+    pub tile_size: f32,
+    pub chunks: HashMap<(i32, i32), Vec<TileRect>>,
+    pub loaded: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl ZoneCollisionChunks {
+    /// Chunk coordinate containing a world-space position
+    pub fn chunk_at(&self, position: Vec2) -> (i32, i32) {
+        let chunk_world = self.tile_size * COLLISION_CHUNK_TILES as f32;
+        ((position.x / chunk_world).floor() as i32, (position.y / chunk_world).floor() as i32)
+    }
 }
 
-/// Spawn collision entities from new TilemapMap format
-/// Looks for a layer named "Collision" and treats any non-zero tile as a collision tile
-fn spawn_tilemapmap_collision(commands: &mut Commands, tilemap: &eryndor_shared::TilemapMap, zone_id: &str) {
-    let tile_size = tilemap.tile_width as f32;
-    let chunk_size = 16u32; // Default chunk size for infinite maps
-    let mut collision_count = 0;
+/// Build the streaming chunk table from zone tilemap data.
+/// Supports both new TilemapMap format (priority) and legacy ZoneTilemap format.
+/// Adjacent blocked tiles are merged into rectangles per chunk so we spawn far fewer colliders.
+fn build_collision_chunks(zone: &crate::game_data::ZoneDefinition) -> ZoneCollisionChunks {
+    // Try new TilemapMap format first
+    let (tiles, tile_size) = if let Some(tilemap) = &zone.tilemap_map {
+        (tilemap.collision_tiles(), tilemap.tile_width as f32)
+    } else if let Some(tilemap) = &zone.tilemap {
+        // Fall back to legacy ZoneTilemap format
+        (tilemap.collision_tiles(), tilemap.tile_size as f32)
+    } else {
+        info!("No tilemap data for zone {}, skipping collision", zone.zone_id);
+        return ZoneCollisionChunks::default();
+    };
+
+    let mut buckets: HashMap<(i32, i32), HashSet<(i32, i32)>> = HashMap::new();
+    for &(x, y) in &tiles {
+        let chunk = (x.div_euclid(COLLISION_CHUNK_TILES), y.div_euclid(COLLISION_CHUNK_TILES));
+        buckets.entry(chunk).or_default().insert((x, y));
+    }
 
-    // Find the collision layer (case-insensitive search)
-    let collision_layer = tilemap.layers.iter().find(|l| {
-        l.name.to_lowercase() == "collision" && l.is_tile_layer()
-    });
+    let chunks: HashMap<(i32, i32), Vec<TileRect>> = buckets.into_iter()
+        .map(|(chunk, chunk_tiles)| (chunk, merge_tiles_into_rects(&chunk_tiles)))
+        .collect();
+
+    info!(
+        "Prepared {} collision rects in {} chunks ({} tiles) for zone: {}",
+        chunks.values().map(Vec::len).sum::<usize>(),
+        chunks.len(),
+        tiles.len(),
+        zone.zone_id
+    );
+
+    ZoneCollisionChunks {
+        tile_size,
+        chunks,
+        loaded: HashMap::new(),
+    }
+}
 
-    let Some(layer) = collision_layer else {
-        info!("No 'Collision' layer found in TilemapMap for zone {}", zone_id);
+/// Spawn and despawn chunk colliders based on player proximity.
+/// Enemies chasing or attacking also keep their surroundings loaded so they can't walk through walls.
+pub fn stream_collision_chunks(
+    mut commands: Commands,
+    collision: Option<ResMut<ZoneCollisionChunks>>,
+    players: Query<&Position, With<Player>>,
+    enemies: Query<(&Position, &AiState), (With<Enemy>, Without<Player>)>,
+) {
+    let _timer = crate::metrics::time_system("world::stream_collision_chunks");
+    let Some(mut collision) = collision else { return };
+    if collision.chunks.is_empty() {
         return;
+    }
+
+    let player_chunks: Vec<(i32, i32)> = players.iter().map(|p| collision.chunk_at(p.0)).collect();
+    let anchors: Vec<(i32, i32)> = if player_chunks.is_empty() {
+        Vec::new()
+    } else {
+        player_chunks.iter().copied()
+            .chain(enemies.iter()
+                .filter(|(_, state)| !matches!(state, AiState::Idle))
+                .map(|(p, _)| collision.chunk_at(p.0)))
+            .collect()
     };
 
-    // Get chunk data from the layer
-    if let Some(chunks) = &layer.chunks {
-        for chunk in chunks {
-            let chunk_world_x = chunk.x;
-            let chunk_world_y = chunk.y;
-            let chunk_width = chunk.width as i32;
-
-            // Iterate through tiles in this chunk
-            for (idx, &gid) in chunk.data.iter().enumerate() {
-                if gid == 0 {
-                    continue; // No collision for empty tiles
-                }
+    let within = |chunk: (i32, i32), radius: i32| {
+        anchors.iter().any(|a| (a.0 - chunk.0).abs() <= radius && (a.1 - chunk.1).abs() <= radius)
+    };
 
-                // Calculate tile position within chunk
-                let local_x = (idx as i32) % chunk_width;
-                let local_y = (idx as i32) / chunk_width;
-
-                // Calculate world position
-                let tile_x = chunk_world_x + local_x;
-                let tile_y = chunk_world_y + local_y;
-                let world_x = tile_x as f32 * tile_size + (tile_size / 2.0);
-                let world_y = tile_y as f32 * tile_size + (tile_size / 2.0);
-
-                // Spawn collision entity
-                commands.spawn((
-                    TilemapCollider,
-                    PhysicsPosition(Vec2::new(world_x, world_y)),
-                    RigidBody::Static,
-                    Collider::rectangle(tile_size, tile_size),
-                    CollisionLayers::new(GameLayer::Environment, [GameLayer::Player, GameLayer::Enemy]),
-                ));
-                collision_count += 1;
+    // Unload chunks nobody is near any more
+    let to_unload: Vec<(i32, i32)> = collision.loaded.keys()
+        .copied()
+        .filter(|chunk| !within(*chunk, COLLISION_UNLOAD_RADIUS))
+        .collect();
+    for chunk in to_unload {
+        if let Some(entities) = collision.loaded.remove(&chunk) {
+            for entity in entities {
+                if let Ok(mut entity_commands) = commands.get_entity(entity) {
+                    entity_commands.despawn();
+                }
             }
+            debug!("Unloaded collision chunk {:?}", chunk);
         }
     }
 
-    // Also check for finite map data (non-chunked)
-    if let Some(data) = &layer.data {
-        let map_width = tilemap.width as i32;
-        for (idx, &gid) in data.iter().enumerate() {
-            if gid == 0 {
-                continue;
+    // Load chunks around every anchor
+    let mut to_load = HashSet::new();
+    for anchor in &anchors {
+        for dy in -COLLISION_LOAD_RADIUS..=COLLISION_LOAD_RADIUS {
+            for dx in -COLLISION_LOAD_RADIUS..=COLLISION_LOAD_RADIUS {
+                let chunk = (anchor.0 + dx, anchor.1 + dy);
+                if collision.chunks.contains_key(&chunk) && !collision.loaded.contains_key(&chunk) {
+                    to_load.insert(chunk);
+                }
             }
-
-            let tile_x = (idx as i32) % map_width;
-            let tile_y = (idx as i32) / map_width;
-            let world_x = tile_x as f32 * tile_size + (tile_size / 2.0);
-            let world_y = tile_y as f32 * tile_size + (tile_size / 2.0);
-
-            commands.spawn((
-                TilemapCollider,
-                PhysicsPosition(Vec2::new(world_x, world_y)),
-                RigidBody::Static,
-                Collider::rectangle(tile_size, tile_size),
-                CollisionLayers::new(GameLayer::Environment, [GameLayer::Player, GameLayer::Enemy]),
-            ));
-            collision_count += 1;
         }
     }
-
-    if collision_count > 0 {
-        info!("Spawned {} TilemapMap collision entities for zone: {}", collision_count, zone_id);
+    for chunk in to_load {
+        let tile_size = collision.tile_size;
+        let entities: Vec<Entity> = collision.chunks[&chunk].iter()
+            .map(|rect| spawn_collision_rect(&mut commands, rect, tile_size))
+            .collect();
+        debug!("Loaded collision chunk {:?} ({} colliders)", chunk, entities.len());
+        collision.loaded.insert(chunk, entities);
     }
 }
 
-/// Spawn collision entities from legacy ZoneTilemap format
-fn spawn_legacy_tilemap_collision(commands: &mut Commands, tilemap: &eryndor_shared::ZoneTilemap, zone_id: &str) {
-    let tile_size = tilemap.tile_size as f32;
-    let chunk_size = tilemap.chunk_size as i32;
-    let mut collision_count = 0;
-
-    for (chunk_key, chunk) in &tilemap.chunks {
-        let Some((chunk_x, chunk_y)) = eryndor_shared::ZoneTilemap::parse_chunk_key(chunk_key) else {
-            continue;
-        };
-
-        // Iterate through collision layer
-        for (row_idx, row) in chunk.collision.iter().enumerate() {
-            for (col_idx, &is_blocked) in row.iter().enumerate() {
-                if is_blocked == 0 {
-                    continue;
-                }
-
-                // Calculate world position for this tile
-                let world_x = (chunk_x * chunk_size + col_idx as i32) as f32 * tile_size + (tile_size / 2.0);
-                let world_y = (chunk_y * chunk_size + row_idx as i32) as f32 * tile_size + (tile_size / 2.0);
-
-                // Spawn collision entity
-                commands.spawn((
-                    TilemapCollider,
-                    PhysicsPosition(Vec2::new(world_x, world_y)),
-                    RigidBody::Static,
-                    Collider::rectangle(tile_size, tile_size),
-                    CollisionLayers::new(GameLayer::Environment, [GameLayer::Player, GameLayer::Enemy]),
-                ));
-                collision_count += 1;
-            }
-        }
-    }
+/// Spawn a single static collider covering a rectangle of tiles
+fn spawn_collision_rect(commands: &mut Commands, rect: &TileRect, tile_size: f32) -> Entity {
+    let (center_x, center_y) = rect.world_center(tile_size);
+    let (width, height) = rect.world_size(tile_size);
 
-    if collision_count > 0 {
-        info!("Spawned {} legacy tilemap collision entities for zone: {}", collision_count, zone_id);
-    }
+    commands.spawn((
+        TilemapCollider,
+        PhysicsPosition(Vec2::new(center_x, center_y)),
+        RigidBody::Static,
+        Collider::rectangle(width, height),
+        CollisionLayers::new(GameLayer::Environment, [GameLayer::Player, GameLayer::Enemy]),
+    )).id()
 }
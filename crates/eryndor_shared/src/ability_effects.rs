@@ -16,6 +16,9 @@ pub struct ActiveBuffs {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ActiveBuff {
     pub ability_id: u32,
+    /// Consumable that applied the buff (`ability_id` is 0 for these)
+    #[serde(default)]
+    pub item_id: Option<u32>,
     pub stat_bonuses: StatBonuses,
     pub expires_at: f32,  // Game time in seconds
 }
@@ -26,6 +29,31 @@ pub struct ActiveDebuffs {
     pub debuffs: Vec<ActiveDebuff>,
 }
 
+impl ActiveDebuffs {
+    fn any(&self, predicate: impl Fn(&DebuffType) -> bool) -> bool {
+        self.debuffs.iter().any(|debuff| predicate(&debuff.effect))
+    }
+
+    /// Stunned: no movement, abilities or attacks
+    pub fn is_stunned(&self) -> bool {
+        self.any(|effect| matches!(effect, DebuffType::Stun))
+    }
+
+    /// Cannot move (rooted or stunned)
+    pub fn is_rooted(&self) -> bool {
+        self.any(|effect| matches!(effect, DebuffType::Root | DebuffType::Stun))
+    }
+
+    /// Cannot use abilities (silenced or stunned)
+    pub fn is_silenced(&self) -> bool {
+        self.any(|effect| matches!(effect, DebuffType::Silence | DebuffType::Stun))
+    }
+
+    pub fn is_slowed(&self) -> bool {
+        self.any(|effect| matches!(effect, DebuffType::Slow { .. }))
+    }
+}
+
 /// A single active debuff with expiration time
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ActiveDebuff {
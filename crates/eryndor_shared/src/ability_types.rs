@@ -44,8 +44,13 @@ pub enum AbilityType {
     },
     /// Area of effect that hits multiple targets
     AreaOfEffect {
+        /// Circle radius, or the reach of cones and lines (meters)
         radius: f32,
         max_targets: u32,
+        #[serde(default)]
+        shape: AoeShape,
+        #[serde(default)]
+        placement: AoePlacement,
     },
     /// Temporary stat increase
     Buff {
@@ -73,6 +78,52 @@ pub enum AbilityType {
         /// Mana cost per point of damage absorbed (e.g., 2.0 means 2 mana per 1 damage)
         mana_per_damage: f32,
     },
+    /// Force the target to attack the caster for a while
+    Taunt {
+        duration: f32,
+    },
+    /// Launch a projectile that carries the ability's target effects and applies them on impact.
+    /// It flies in a straight line toward where the target was, so slow projectiles can be dodged.
+    Projectile {
+        /// Travel speed in meters per second
+        speed: f32,
+        /// Collision radius in meters
+        radius: f32,
+    },
+    /// Bring the targeted fallen player (or their corpse) back to life
+    Resurrect {
+        /// Percent of max health and mana restored (0 - 100)
+        health_percent: f32,
+    },
+}
+
+/// Shape of an area-of-effect ability
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum AoeShape {
+    #[default]
+    Circle,
+    /// Wedge pointing from the area's origin toward the aim point
+    Cone {
+        /// Full opening angle in degrees
+        angle: f32,
+    },
+    /// Rectangle extending from the area's origin toward the aim point
+    Line {
+        /// Width in meters
+        width: f32,
+    },
+}
+
+/// Where an area-of-effect ability is centered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AoePlacement {
+    /// Around the current target
+    #[default]
+    Target,
+    /// Around the caster (no target needed)
+    Caster,
+    /// At a ground position the player aims at, within the ability's range
+    Ground,
 }
 
 /// Types of debuffs that can be applied
@@ -90,6 +141,8 @@ pub enum DebuffType {
     Stun,
     /// Cannot move but can still attack
     Root,
+    /// Cannot use abilities but can still move and auto-attack
+    Silence,
 }
 
 /// Requirements for unlocking an ability
@@ -127,3 +180,26 @@ pub struct AbilityDefinition {
     /// Requirements to learn/use this ability
     pub unlock_requirement: AbilityUnlockRequirement,
 }
+
+impl AbilityDefinition {
+    /// Shape, placement and size of this ability's area of effect, if it has one
+    pub fn area_of_effect(&self) -> Option<(AoeShape, AoePlacement, f32)> {
+        self.ability_types.iter().find_map(|ability_type| match ability_type {
+            AbilityType::AreaOfEffect { radius, shape, placement, .. } => Some((*shape, *placement, *radius)),
+            _ => None,
+        })
+    }
+
+    /// Speed and radius (meters) of the projectile this ability launches, if any
+    pub fn projectile(&self) -> Option<(f32, f32)> {
+        self.ability_types.iter().find_map(|ability_type| match ability_type {
+            AbilityType::Projectile { speed, radius } => Some((*speed, *radius)),
+            _ => None,
+        })
+    }
+
+    /// Whether the player places this ability on the ground with a targeting reticle
+    pub fn is_ground_targeted(&self) -> bool {
+        self.area_of_effect().is_some_and(|(_, placement, _)| placement == AoePlacement::Ground)
+    }
+}
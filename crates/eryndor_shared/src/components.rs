@@ -71,6 +71,130 @@ impl CharacterClass {
     }
 }
 
+/// In-game time of day, replicated on a single world entity
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct WorldClock {
+    /// Hour of the day, 0.0 (midnight) up to 24.0
+    pub hour: f32,
+    pub day: u32,
+}
+
+impl WorldClock {
+    /// How lit the world is: 0.0 at night, 1.0 through the day, blending over dawn (5-7) and dusk (18-20)
+    pub fn daylight(&self) -> f32 {
+        let hour = self.hour.rem_euclid(24.0);
+        if (7.0..18.0).contains(&hour) {
+            1.0
+        } else if (5.0..7.0).contains(&hour) {
+            (hour - 5.0) / 2.0
+        } else if (18.0..20.0).contains(&hour) {
+            1.0 - (hour - 18.0) / 2.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Weather conditions a zone can have
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Cloudy,
+    Fog,
+    Rain,
+    Storm,
+}
+
+impl Weather {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Weather::Clear => "Clear",
+            Weather::Cloudy => "Cloudy",
+            Weather::Fog => "Fog",
+            Weather::Rain => "Rain",
+            Weather::Storm => "Storm",
+        }
+    }
+
+    /// Whether rain falls in this weather
+    pub fn is_wet(&self) -> bool {
+        matches!(self, Weather::Rain | Weather::Storm)
+    }
+}
+
+/// Current weather in a zone, replicated on one entity per zone with a weather table
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct ZoneWeather {
+    pub zone_id: String,
+    pub weather: Weather,
+    /// 0.0 to 1.0; how heavy the rain or thick the fog is
+    pub intensity: f32,
+}
+
+/// Faction chosen at character creation. Players flagged for PvP can fight the other faction.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Faction {
+    #[default]
+    Dawnguard,
+    Ironclad,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 2] = [Faction::Dawnguard, Faction::Ironclad];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Faction::Dawnguard => "Dawnguard",
+            Faction::Ironclad => "Ironclad",
+        }
+    }
+
+    pub fn is_hostile_to(&self, other: Faction) -> bool {
+        *self != other
+    }
+}
+
+/// Player who opted into open-world PvP
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct PvpFlagged;
+
+/// A duel in progress against `opponent`, fought within `DUEL_ARENA_RADIUS` of `arena_center`
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+#[component(map_entities)]
+pub struct Dueling {
+    pub opponent: Entity,
+    pub arena_center: Vec2,
+}
+
+impl bevy::ecs::entity::MapEntities for Dueling {
+    fn map_entities<M: bevy::ecs::entity::EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.opponent = entity_mapper.get_mapped(self.opponent);
+    }
+}
+
+/// What decides whether a player may attack another
+#[derive(Clone, Copy, Debug)]
+pub struct PvpStanding {
+    pub faction: Faction,
+    pub flagged: bool,
+    pub duel_opponent: Option<Entity>,
+}
+
+impl PvpStanding {
+    /// Duel opponents can always fight each other, and duelists nobody else. Outside duels
+    /// both players must be flagged and of hostile factions.
+    pub fn can_attack(&self, me: Entity, target: Entity, other: &PvpStanding) -> bool {
+        if me == target {
+            return false;
+        }
+        if self.duel_opponent.is_some() || other.duel_opponent.is_some() {
+            return self.duel_opponent == Some(target) && other.duel_opponent == Some(me);
+        }
+        self.flagged && other.flagged && self.faction.is_hostile_to(other.faction)
+    }
+}
+
 /// Tracks which client owns this entity
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct OwnedBy(pub Entity);
@@ -83,8 +207,8 @@ pub struct AccountId(pub i64);
 // SPATIAL COMPONENTS
 // ============================================================================
 
-/// World position
-#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+/// World position. Replicated as 1/8 pixel fixed point (see `quantize`).
+#[derive(Component, Clone, Copy, Debug)]
 pub struct Position(pub Vec2);
 
 impl Default for Position {
@@ -93,8 +217,8 @@ impl Default for Position {
     }
 }
 
-/// Movement velocity
-#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+/// Movement velocity. Replicated as 1/16 pixel per second fixed point (see `quantize`).
+#[derive(Component, Clone, Copy, Debug)]
 pub struct Velocity(pub Vec2);
 
 impl Default for Velocity {
@@ -193,8 +317,8 @@ impl AnimationState {
 // COMBAT COMPONENTS
 // ============================================================================
 
-/// Health
-#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+/// Health. Replicated as whole points (see `quantize`).
+#[derive(Component, Clone, Copy, Debug)]
 pub struct Health {
     pub current: f32,
     pub max: f32,
@@ -218,8 +342,8 @@ impl Health {
     }
 }
 
-/// Mana/Energy
-#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+/// Mana/Energy. Replicated as whole points (see `quantize`).
+#[derive(Component, Clone, Copy, Debug)]
 pub struct Mana {
     pub current: f32,
     pub max: f32,
@@ -428,6 +552,22 @@ impl Inventory {
         })
     }
 
+    /// Take one of an item from its first stack, emptying the slot when the stack runs out
+    pub fn consume_item(&mut self, item_id: u32) -> bool {
+        for slot in &mut self.slots {
+            if let Some(stack) = slot {
+                if stack.item_id == item_id {
+                    stack.quantity = stack.quantity.saturating_sub(1);
+                    if stack.quantity == 0 {
+                        *slot = None;
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     /// Count total quantity of a specific item across all inventory slots
     pub fn count_item(&self, item_id: u32) -> u32 {
         self.slots.iter()
@@ -467,6 +607,8 @@ pub struct Hotbar {
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum HotbarSlot {
     Ability(u32),
+    /// Consumable item, used from the first stack in the inventory
+    Item(u32),
 }
 
 /// Learned abilities
@@ -491,6 +633,22 @@ pub struct AbilityCooldowns {
     pub cooldowns: HashMap<u32, Timer>,
 }
 
+/// Consumable cooldowns keyed by item category, e.g. all potions share one timer
+/// (server-side only, clients are told through `ItemCooldownEvent`)
+#[derive(Component, Default)]
+pub struct ItemCooldowns {
+    pub cooldowns: HashMap<String, Timer>,
+}
+
+impl ItemCooldowns {
+    /// Seconds left before the category can be used again
+    pub fn remaining(&self, category: &str) -> f32 {
+        self.cooldowns.get(category)
+            .map(|timer| timer.remaining_secs())
+            .unwrap_or(0.0)
+    }
+}
+
 // ============================================================================
 // QUEST COMPONENTS
 // ============================================================================
@@ -589,17 +747,26 @@ pub enum AiState {
     Idle,
     Chasing(Entity),
     Attacking(Entity),
+    /// Leashed - running back to spawn, ignoring players until it arrives
+    Returning,
 }
 
 /// Loot table for enemies - defines what they drop on death
 #[derive(Component, Serialize, Deserialize, Clone, Debug, Default)]
 pub struct LootTable {
+    /// Named loot table (content/loot) rolled in addition to the inline entries
+    #[serde(default)]
+    pub table_id: Option<String>,
     #[serde(default)]
     pub gold_min: u32,
     #[serde(default)]
     pub gold_max: u32,
+    /// Independent drops, each rolled against its own drop chance
     #[serde(default)]
     pub items: Vec<LootItem>,
+    /// Weighted groups, each picking at most one entry per roll
+    #[serde(default)]
+    pub groups: Vec<LootGroup>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -611,18 +778,51 @@ pub struct LootItem {
     pub quantity_min: u32,
     #[serde(default = "default_quantity_max")]
     pub quantity_max: u32,
+    /// Only drops while a participating player has this quest active
+    #[serde(default)]
+    pub required_quest: Option<u32>,
+}
+
+/// A weighted drop group (e.g. "one of these weapons")
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LootGroup {
+    #[serde(default)]
+    pub name: String,
+    /// How many times this group is rolled
+    #[serde(default = "default_group_rolls")]
+    pub rolls: u32,
+    /// Weight of rolling nothing from this group
+    #[serde(default)]
+    pub empty_weight: f32,
+    pub entries: Vec<LootGroupEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LootGroupEntry {
+    pub item_id: u32,
+    #[serde(default = "default_entry_weight")]
+    pub weight: f32,
+    #[serde(default = "default_quantity_min")]
+    pub quantity_min: u32,
+    #[serde(default = "default_quantity_max")]
+    pub quantity_max: u32,
+    /// Only eligible while a participating player has this quest active
+    #[serde(default)]
+    pub required_quest: Option<u32>,
 }
 
 fn default_drop_chance() -> f32 { 1.0 }
 fn default_quantity_min() -> u32 { 1 }
 fn default_quantity_max() -> u32 { 1 }
+fn default_group_rolls() -> u32 { 1 }
+fn default_entry_weight() -> f32 { 1.0 }
 
 impl bevy::ecs::entity::MapEntities for AiState {
     fn map_entities<M: bevy::ecs::entity::EntityMapper>(&mut self, entity_mapper: &mut M) {
         match self {
             AiState::Chasing(entity) => *entity = entity_mapper.get_mapped(*entity),
             AiState::Attacking(entity) => *entity = entity_mapper.get_mapped(*entity),
-            AiState::Idle => {},
+            AiState::Idle | AiState::Returning => {},
         }
     }
 }
@@ -692,6 +892,10 @@ impl Interactable {
     pub fn loot_container() -> Self {
         Self::new(InteractionType::LootContainer, 40.0)
     }
+
+    pub fn corpse() -> Self {
+        Self::new(InteractionType::Corpse, 30.0)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -703,6 +907,7 @@ pub enum InteractionType {
     Door,
     LoreObject,
     LootContainer,
+    Corpse,
 }
 
 // ============================================================================
@@ -828,6 +1033,28 @@ pub enum LootContents {
     Item(ItemStack),
 }
 
+/// Player who died and hasn't released their spirit yet
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Dead;
+
+/// Player who released their spirit and is running back to their corpse
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Ghost {
+    pub corpse_position: Vec2,
+}
+
+/// Body left behind where a player died
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Corpse {
+    pub owner_name: String,
+}
+
+/// Projectile in flight, simulated by the server
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Projectile {
+    pub ability_id: u32,
+}
+
 /// Visual representation data
 #[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct VisualShape {
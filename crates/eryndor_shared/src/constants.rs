@@ -70,8 +70,21 @@ pub const SPRINT_MOVE_SPEED: f32 = 350.0;
 pub const INTERACTION_RANGE: f32 = 50.0;
 pub const PICKUP_RANGE: f32 = 40.0;
 
+/// How close a ghost must be to their corpse to resurrect there
+pub const CORPSE_RESURRECT_RANGE: f32 = 60.0;
+/// Ghosts run faster than the living
+pub const GHOST_SPEED_MULTIPLIER: f32 = 1.5;
+
 pub const MAX_INVENTORY_SLOTS: usize = 20;
 
+pub const MAX_FRIENDS: usize = 50;
+
+pub const MAX_PARTY_SIZE: usize = 5;
+/// Guild names are 3 to this many characters
+pub const MAX_GUILD_NAME_LENGTH: usize = 24;
+/// Seconds a party or guild invite stays open
+pub const GROUP_INVITE_TIMEOUT: f32 = 60.0;
+
 // ============================================================================
 // COMBAT CONSTANTS
 // ============================================================================
@@ -82,6 +95,18 @@ pub const RANGED_RANGE: f32 = 200.0;
 pub const AGGRO_RANGE: f32 = 150.0;
 pub const LEASH_RANGE: f32 = 300.0;
 
+/// Duels can be offered to players within this distance
+pub const DUEL_CHALLENGE_RANGE: f32 = 200.0;
+/// Leaving this radius around the duel's starting point forfeits it
+pub const DUEL_ARENA_RADIUS: f32 = 400.0;
+/// Seconds a duel challenge stays open
+pub const DUEL_CHALLENGE_TIMEOUT: f32 = 30.0;
+/// Seconds the PvP flag lingers after turning it off
+pub const PVP_UNFLAG_DELAY: f32 = 30.0;
+
+/// Ability ranges and radii are authored in meters
+pub const PIXELS_PER_METER: f32 = 20.0;
+
 // ============================================================================
 // ABILITY IDS
 // ============================================================================
@@ -146,6 +171,15 @@ pub const ITEM_LEATHER_BOOTS: u32 = 40;
 pub const ITEM_CLOTH_SHOES: u32 = 41;
 pub const ITEM_IRON_BOOTS: u32 = 42;
 
+// Consumables - Potions
+pub const ITEM_MINOR_HEALING_POTION: u32 = 50;
+pub const ITEM_MINOR_MANA_POTION: u32 = 51;
+pub const ITEM_ELIXIR_OF_STRENGTH: u32 = 52;
+
+// Consumables - Food
+pub const ITEM_TRAVEL_BREAD: u32 = 60;
+pub const ITEM_ROASTED_BOAR: u32 = 61;
+
 // ============================================================================
 // QUEST IDS
 // ============================================================================
@@ -203,9 +237,19 @@ pub const COLOR_ITEM_DAGGER: [f32; 4] = [0.8, 0.8, 0.2, 1.0]; // Yellow
 pub const COLOR_ITEM_WAND: [f32; 4] = [0.8, 0.2, 0.8, 1.0]; // Purple
 pub const COLOR_ITEM_SWORD: [f32; 4] = [0.7, 0.7, 0.7, 1.0]; // Silver
 pub const COLOR_LOOT_CONTAINER: [f32; 4] = [0.6, 0.4, 0.2, 1.0]; // Brown (like a chest/bag)
+pub const COLOR_CORPSE: [f32; 4] = [0.4, 0.4, 0.4, 0.8]; // Grey
 
 // ============================================================================
 // LOOT CONSTANTS
 // ============================================================================
 
 pub const LOOT_CONTAINER_SIZE: f32 = 20.0;
+
+// ============================================================================
+// WORLD TIME & WEATHER CONSTANTS
+// ============================================================================
+
+/// Real seconds in one in-game day
+pub const DAY_LENGTH_SECONDS: f32 = 1440.0;
+/// In-game hour the world clock starts at when the server boots
+pub const WORLD_START_HOUR: f32 = 8.0;
@@ -16,6 +16,9 @@ pub mod protocol;
 pub mod constants;
 pub mod tilemap;
 pub mod sprite;
+pub mod navigation;
+pub mod network;
+pub mod quantize;
 
 pub use ability_effects::*;
 pub use ability_types::*;
@@ -24,3 +27,6 @@ pub use protocol::*;
 pub use constants::*;
 pub use tilemap::*;
 pub use sprite::*;
+pub use navigation::*;
+pub use network::*;
+pub use quantize::*;
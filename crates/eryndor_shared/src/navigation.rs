@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::tilemap::{TilemapMap, ZoneTilemap};
+
+// ============================================================================
+// WALKABILITY GRID
+// ============================================================================
+
+/// Tiles A* may expand before giving up on a path
+pub const MAX_PATH_SEARCH_NODES: usize = 4096;
+/// Step costs for A* (orthogonal, diagonal), scaled by 10 to stay in integers
+const ORTHOGONAL_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Walkability grid baked from a zone's collision data.
+/// Tiles are walkable unless they are in `blocked`; anything outside `bounds` is unwalkable.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NavGrid {
+    /// Size of a navigation cell in world units (matches tilemap tile size)
+    pub tile_size: f32,
+    /// Inclusive tile-space bounds (min_x, min_y, max_x, max_y)
+    pub bounds: (i32, i32, i32, i32),
+    /// Blocked tile coordinates
+    pub blocked: HashSet<(i32, i32)>,
+}
+
+impl NavGrid {
+    /// Bake from a set of blocked tiles, clamped to world-space bounds (centered on origin)
+    pub fn from_blocked_tiles(blocked: HashSet<(i32, i32)>, tile_size: f32, world_width: f32, world_height: f32) -> Self {
+        let tile_size = tile_size.max(1.0);
+        let half_w = (world_width / 2.0 / tile_size).ceil() as i32;
+        let half_h = (world_height / 2.0 / tile_size).ceil() as i32;
+
+        Self {
+            tile_size,
+            bounds: (-half_w, -half_h, half_w - 1, half_h - 1),
+            blocked,
+        }
+    }
+
+    /// Bake from a Tiled-compatible map's collision layer
+    pub fn bake_tilemap_map(map: &TilemapMap, world_width: f32, world_height: f32) -> Self {
+        Self::from_blocked_tiles(map.collision_tiles(), map.tile_width as f32, world_width, world_height)
+    }
+
+    /// Bake from the legacy chunked tilemap
+    pub fn bake_zone_tilemap(map: &ZoneTilemap, world_width: f32, world_height: f32) -> Self {
+        Self::from_blocked_tiles(map.collision_tiles(), map.tile_size as f32, world_width, world_height)
+    }
+
+    /// Mark an extra world-space rectangle (e.g. an entity collider) as blocked
+    pub fn block_world_rect(&mut self, center: (f32, f32), size: (f32, f32)) {
+        let (min_x, min_y) = self.world_to_tile(center.0 - size.0 / 2.0, center.1 - size.1 / 2.0);
+        let (max_x, max_y) = self.world_to_tile(center.0 + size.0 / 2.0, center.1 + size.1 / 2.0);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                self.blocked.insert((x, y));
+            }
+        }
+    }
+
+    pub fn in_bounds(&self, tile: (i32, i32)) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+        tile.0 >= min_x && tile.0 <= max_x && tile.1 >= min_y && tile.1 <= max_y
+    }
+
+    pub fn is_walkable(&self, tile: (i32, i32)) -> bool {
+        self.in_bounds(tile) && !self.blocked.contains(&tile)
+    }
+
+    /// Check walkability for a world-space position
+    pub fn is_walkable_world(&self, x: f32, y: f32) -> bool {
+        self.is_walkable(self.world_to_tile(x, y))
+    }
+
+    pub fn world_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.tile_size).floor() as i32, (y / self.tile_size).floor() as i32)
+    }
+
+    /// World-space center of a tile
+    pub fn tile_to_world(&self, tile: (i32, i32)) -> (f32, f32) {
+        (
+            (tile.0 as f32 + 0.5) * self.tile_size,
+            (tile.1 as f32 + 0.5) * self.tile_size,
+        )
+    }
+
+    /// Walkable 8-way neighbours of a tile. Diagonals are only allowed when both
+    /// adjacent orthogonal tiles are walkable so paths never cut wall corners.
+    pub fn neighbors(&self, tile: (i32, i32)) -> Vec<(i32, i32)> {
+        let mut result = Vec::with_capacity(8);
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let next = (tile.0 + dx, tile.1 + dy);
+            if !self.is_walkable(next) {
+                continue;
+            }
+            if dx != 0 && dy != 0
+                && (!self.is_walkable((tile.0 + dx, tile.1)) || !self.is_walkable((tile.0, tile.1 + dy)))
+            {
+                continue;
+            }
+            result.push(next);
+        }
+        result
+    }
+    /// Whether a straight line between two world positions stays on walkable tiles.
+    /// Samples every quarter tile, which is enough to catch single-tile walls.
+    pub fn has_line_of_sight(&self, from: (f32, f32), to: (f32, f32)) -> bool {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let steps = (length / (self.tile_size / 4.0)).ceil().max(1.0) as u32;
+        (0..=steps).all(|i| {
+            let t = i as f32 / steps as f32;
+            self.is_walkable_world(from.0 + dx * t, from.1 + dy * t)
+        })
+    }
+
+    /// A* search from one world position to another over walkable tiles.
+    /// Returns world-space waypoints (excluding the start, ending at `goal`) with
+    /// redundant corners removed, or None when the goal is unreachable within `max_nodes` expansions.
+    pub fn find_path(&self, start: (f32, f32), goal: (f32, f32), max_nodes: usize) -> Option<Vec<(f32, f32)>> {
+        let start_tile = self.world_to_tile(start.0, start.1);
+        let goal_tile = self.world_to_tile(goal.0, goal.1);
+        if !self.is_walkable(goal_tile) {
+            return None;
+        }
+        if start_tile == goal_tile {
+            return Some(vec![goal]);
+        }
+
+        let heuristic = |tile: (i32, i32)| {
+            let (dx, dy) = ((tile.0 - goal_tile.0).unsigned_abs(), (tile.1 - goal_tile.1).unsigned_abs());
+            ORTHOGONAL_COST * dx.max(dy) + (DIAGONAL_COST - ORTHOGONAL_COST) * dx.min(dy)
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+        let mut cost_so_far: HashMap<(i32, i32), u32> = HashMap::new();
+        open.push(Reverse((heuristic(start_tile), start_tile)));
+        cost_so_far.insert(start_tile, 0);
+
+        let mut expanded = 0;
+        while let Some(Reverse((_, tile))) = open.pop() {
+            if tile == goal_tile {
+                return Some(self.build_path(start, goal, tile, &came_from));
+            }
+            expanded += 1;
+            if expanded > max_nodes {
+                return None;
+            }
+
+            let cost = cost_so_far[&tile];
+            for next in self.neighbors(tile) {
+                let step = if next.0 != tile.0 && next.1 != tile.1 { DIAGONAL_COST } else { ORTHOGONAL_COST };
+                let next_cost = cost + step;
+                if cost_so_far.get(&next).is_none_or(|&known| next_cost < known) {
+                    cost_so_far.insert(next, next_cost);
+                    came_from.insert(next, tile);
+                    open.push(Reverse((next_cost + heuristic(next), next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Walk `came_from` back to the start, then drop every waypoint that can be skipped in a straight line
+    fn build_path(
+        &self,
+        start: (f32, f32),
+        goal: (f32, f32),
+        goal_tile: (i32, i32),
+        came_from: &HashMap<(i32, i32), (i32, i32)>,
+    ) -> Vec<(f32, f32)> {
+        let mut tiles = vec![goal_tile];
+        let mut current = goal_tile;
+        while let Some(&previous) = came_from.get(&current) {
+            tiles.push(previous);
+            current = previous;
+        }
+        tiles.pop(); // start tile
+        tiles.reverse();
+
+        let mut points: Vec<(f32, f32)> = tiles.iter().map(|&tile| self.tile_to_world(tile)).collect();
+        if let Some(last) = points.last_mut() {
+            *last = goal;
+        }
+
+        let mut smoothed = Vec::with_capacity(points.len());
+        let mut from = start;
+        let mut i = 0;
+        while i < points.len() {
+            // Furthest point still visible from the current position
+            let mut furthest = i;
+            for j in (i + 1..points.len()).rev() {
+                if self.has_line_of_sight(from, points[j]) {
+                    furthest = j;
+                    break;
+                }
+            }
+            smoothed.push(points[furthest]);
+            from = points[furthest];
+            i = furthest + 1;
+        }
+        smoothed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20x20 tile grid with a vertical wall at x = 2 from y = -5 to y = 5
+    fn walled_grid() -> NavGrid {
+        let blocked = (-5..=5).map(|y| (2, y)).collect();
+        NavGrid::from_blocked_tiles(blocked, 16.0, 320.0, 320.0)
+    }
+
+    #[test]
+    fn path_goes_around_wall() {
+        let grid = walled_grid();
+        let start = grid.tile_to_world((0, 0));
+        let goal = grid.tile_to_world((4, 0));
+        assert!(!grid.has_line_of_sight(start, goal));
+
+        let path = grid.find_path(start, goal, MAX_PATH_SEARCH_NODES).expect("path exists");
+        assert_eq!(*path.last().unwrap(), goal);
+
+        let mut from = start;
+        for &point in &path {
+            assert!(grid.has_line_of_sight(from, point), "segment {:?} -> {:?} crosses the wall", from, point);
+            from = point;
+        }
+    }
+
+    #[test]
+    fn open_ground_is_a_straight_line() {
+        let grid = walled_grid();
+        let start = grid.tile_to_world((-6, -6));
+        let goal = grid.tile_to_world((-1, 4));
+        assert_eq!(grid.find_path(start, goal, MAX_PATH_SEARCH_NODES), Some(vec![goal]));
+    }
+
+    #[test]
+    fn blocked_goal_is_unreachable() {
+        let grid = walled_grid();
+        assert_eq!(grid.find_path(grid.tile_to_world((0, 0)), grid.tile_to_world((2, 0)), MAX_PATH_SEARCH_NODES), None);
+    }
+}
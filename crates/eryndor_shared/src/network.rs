@@ -0,0 +1,187 @@
+//! Replicated components and network events, registered the same way by every app that
+//! talks to the server.
+//!
+//! replicon identifies components and events by the order they were registered in, so the
+//! server, the game client and the bot client must all register exactly the same list in
+//! exactly the same order. Add new components and events here, never in one app alone.
+//!
+//! Position, Velocity, Health and Mana change on most frames, so they go out as `Wire` copies
+//! that the server refreshes at a configured rate; clients copy them back into the real
+//! components as they arrive.
+
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::*;
+
+/// The replicated copy of a component whose updates are rate limited. Serializes exactly like
+/// the component itself.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct Wire<C>(pub C);
+
+/// Client side: give each entity the value its `Wire<C>` last brought in
+fn apply_wire<C: Component<Mutability = Mutable> + Clone>(
+    mut commands: Commands,
+    mut wires: Query<(Entity, &Wire<C>, Option<&mut C>), Changed<Wire<C>>>,
+) {
+    for (entity, wire, component) in &mut wires {
+        match component {
+            Some(mut component) => *component = wire.0.clone(),
+            None => {
+                commands.entity(entity).insert(wire.0.clone());
+            }
+        }
+    }
+}
+
+/// Registers everything the server and its clients exchange. Add after `RepliconPlugins`.
+pub struct ProtocolPlugin;
+
+impl Plugin for ProtocolPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Replicated components
+            .replicate::<Player>()
+            .replicate::<Character>()
+            .replicate::<OwnedBy>()
+            .replicate::<Wire<Position>>()
+            .replicate::<Experience>()
+            .replicate::<WeaponProficiencyExp>()
+            .replicate::<ArmorProficiency>()
+            .replicate::<ArmorProficiencyExp>()
+            .replicate::<UnlockedArmorPassives>()
+            .replicate::<Wire<Velocity>>()
+            .replicate::<MoveSpeed>()
+            .replicate::<AnimationState>()
+            .replicate::<Wire<Health>>()
+            .replicate::<Wire<Mana>>()
+            .replicate::<HealthRegen>()
+            .replicate::<ManaRegen>()
+            .replicate::<CombatStats>()
+            .replicate::<CurrentTarget>()
+            .replicate::<InCombat>()
+            .replicate::<AutoAttack>()
+            .replicate::<WeaponProficiency>()
+            .replicate::<Gold>()
+            .replicate::<Inventory>()
+            .replicate::<Equipment>()
+            .replicate::<Hotbar>()
+            .replicate::<LearnedAbilities>()
+            .replicate::<QuestLog>()
+            .replicate::<Npc>()
+            .replicate::<QuestGiver>()
+            .replicate::<NpcName>()
+            .replicate::<Enemy>()
+            .replicate::<EnemyType>()
+            .replicate::<EnemyName>()
+            .replicate::<AiState>()
+            .replicate::<WorldItem>()
+            .replicate::<GoldDrop>()
+            .replicate::<LootContainer>()
+            .replicate::<Interactable>()
+            .replicate::<VisualShape>()
+            .replicate::<Projectile>()
+            .replicate::<Dead>()
+            .replicate::<Ghost>()
+            .replicate::<Corpse>()
+            .replicate::<Faction>()
+            .replicate::<PvpFlagged>()
+            .replicate::<Dueling>()
+            .replicate::<WorldClock>()
+            .replicate::<ZoneWeather>()
+            .replicate::<ActiveBuffs>()
+            .replicate::<ActiveDebuffs>()
+            .replicate::<ActiveDoTs>()
+            // Client -> server events
+            .add_client_event::<LoginRequest>(Channel::Ordered)
+            .add_client_event::<CreateAccountRequest>(Channel::Ordered)
+            .add_client_event::<OAuthLoginRequest>(Channel::Ordered)
+            .add_client_event::<TwoFactorSetupRequest>(Channel::Ordered)
+            .add_client_event::<TwoFactorConfirmRequest>(Channel::Ordered)
+            .add_client_event::<TwoFactorDisableRequest>(Channel::Ordered)
+            .add_client_event::<CreateCharacterRequest>(Channel::Ordered)
+            .add_client_event::<SelectCharacterRequest>(Channel::Ordered)
+            .add_client_event::<GetShardListRequest>(Channel::Ordered)
+            .add_client_event::<MoveInput>(Channel::Unreliable)
+            .add_mapped_client_event::<SetTargetRequest>(Channel::Ordered)
+            .add_client_event::<UseAbilityRequest>(Channel::Ordered)
+            .add_mapped_client_event::<PickupItemRequest>(Channel::Ordered)
+            .add_mapped_client_event::<OpenLootContainerRequest>(Channel::Ordered)
+            .add_mapped_client_event::<LootItemRequest>(Channel::Ordered)
+            .add_client_event::<AutoLootRequest>(Channel::Ordered)
+            .add_client_event::<DropItemRequest>(Channel::Ordered)
+            .add_client_event::<EquipItemRequest>(Channel::Ordered)
+            .add_client_event::<UseItemRequest>(Channel::Ordered)
+            .add_client_event::<UnequipItemRequest>(Channel::Ordered)
+            .add_mapped_client_event::<InteractNpcRequest>(Channel::Ordered)
+            .add_client_event::<ReleaseSpiritRequest>(Channel::Ordered)
+            .add_client_event::<ResurrectAtCorpseRequest>(Channel::Ordered)
+            .add_mapped_client_event::<DuelChallengeRequest>(Channel::Ordered)
+            .add_client_event::<DuelResponseRequest>(Channel::Ordered)
+            .add_client_event::<SetPvpFlagRequest>(Channel::Ordered)
+            .add_client_event::<AcceptQuestRequest>(Channel::Ordered)
+            .add_client_event::<ChooseDialogueOptionRequest>(Channel::Ordered)
+            .add_client_event::<CompleteQuestRequest>(Channel::Ordered)
+            .add_mapped_client_event::<PurchaseFromTrainerRequest>(Channel::Ordered)
+            .add_client_event::<BuyFromVendorRequest>(Channel::Ordered)
+            .add_client_event::<SellToVendorRequest>(Channel::Ordered)
+            .add_client_event::<BuybackRequest>(Channel::Ordered)
+            .add_client_event::<SetHotbarSlotRequest>(Channel::Ordered)
+            .add_client_event::<DisconnectCharacterRequest>(Channel::Ordered)
+            .add_client_event::<AdminCommandRequest>(Channel::Ordered)
+            .add_client_event::<SendChatMessage>(Channel::Ordered)
+            .add_client_event::<WhisperRequest>(Channel::Ordered)
+            .add_client_event::<AddFriendRequest>(Channel::Ordered)
+            .add_client_event::<RemoveFriendRequest>(Channel::Ordered)
+            .add_client_event::<GroupInviteRequest>(Channel::Ordered)
+            .add_client_event::<GroupInviteResponse>(Channel::Ordered)
+            .add_client_event::<LeaveGroupRequest>(Channel::Ordered)
+            .add_client_event::<CreateGuildRequest>(Channel::Ordered)
+            // Dashboard query events
+            .add_client_event::<GetPlayerListRequest>(Channel::Ordered)
+            .add_client_event::<GetBanListRequest>(Channel::Ordered)
+            .add_client_event::<GetServerStatsRequest>(Channel::Ordered)
+            .add_client_event::<GetAuditLogsRequest>(Channel::Ordered)
+            // Server -> client events
+            .add_server_event::<LoginResponse>(Channel::Ordered)
+            .add_server_event::<CreateAccountResponse>(Channel::Ordered)
+            .add_server_event::<OAuthLoginResponse>(Channel::Ordered)
+            .add_server_event::<TwoFactorSetupResponse>(Channel::Ordered)
+            .add_server_event::<TwoFactorStatusResponse>(Channel::Ordered)
+            .add_server_event::<CharacterListResponse>(Channel::Ordered)
+            .add_server_event::<CreateCharacterResponse>(Channel::Ordered)
+            .add_server_event::<SelectCharacterResponse>(Channel::Ordered)
+            .add_server_event::<ShardListResponse>(Channel::Ordered)
+            .add_server_event::<CombatEvent>(Channel::Ordered)
+            .add_server_event::<CombatLogEvent>(Channel::Ordered)
+            .add_server_event::<QuestUpdateEvent>(Channel::Ordered)
+            .add_server_event::<DeathEvent>(Channel::Ordered)
+            .add_server_event::<NotificationEvent>(Channel::Ordered)
+            .add_server_event::<ItemCooldownEvent>(Channel::Ordered)
+            .add_server_event::<QuestDialogueEvent>(Channel::Ordered)
+            .add_server_event::<DialogueNodeEvent>(Channel::Ordered)
+            .add_server_event::<TrainerDialogueEvent>(Channel::Ordered)
+            .add_server_event::<VendorWindowEvent>(Channel::Ordered)
+            .add_mapped_server_event::<LootContainerContentsEvent>(Channel::Ordered)
+            .add_server_event::<LevelUpEvent>(Channel::Ordered)
+            .add_server_event::<ProficiencyLevelUpEvent>(Channel::Ordered)
+            .add_server_event::<DuelChallengeEvent>(Channel::Ordered)
+            .add_server_event::<ChatMessage>(Channel::Ordered)
+            .add_server_event::<FriendListEvent>(Channel::Ordered)
+            .add_server_event::<GroupInviteEvent>(Channel::Ordered)
+            // Dashboard response events
+            .add_server_event::<PlayerListResponse>(Channel::Ordered)
+            .add_server_event::<BanListResponse>(Channel::Ordered)
+            .add_server_event::<ServerStatsResponse>(Channel::Ordered)
+            .add_server_event::<AuditLogsResponse>(Channel::Ordered)
+            // Rate limited components, unpacked as soon as they're received
+            .add_systems(PreUpdate, (
+                apply_wire::<Position>,
+                apply_wire::<Velocity>,
+                apply_wire::<Health>,
+                apply_wire::<Mana>,
+            ).after(ClientSystems::Receive).run_if(client_connected));
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use bevy::ecs::entity::MapEntities;
 
 use crate::components::*;
+use crate::ability_types::DebuffType;
 
 // ============================================================================
 // CLIENT -> SERVER MESSAGES
@@ -13,6 +14,12 @@ use crate::components::*;
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Authenticator or backup code, for accounts with two-factor enabled
+    #[serde(default)]
+    pub totp_code: Option<String>,
+    /// Random id the client keeps on this device, checked against hardware bans
+    #[serde(default)]
+    pub hardware_id: Option<String>,
 }
 
 /// Create new account
@@ -28,6 +35,26 @@ pub struct CreateAccountRequest {
 pub struct OAuthLoginRequest {
     pub provider: String,  // "google", "discord", etc.
     pub token: String,      // OAuth access token from provider
+    /// Random id the client keeps on this device, checked against hardware bans
+    #[serde(default)]
+    pub hardware_id: Option<String>,
+}
+
+/// Start two-factor enrollment: the server replies with a new secret to add to an
+/// authenticator app. Nothing changes until the first code is confirmed.
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoFactorSetupRequest;
+
+/// Confirm enrollment with the authenticator's current code
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoFactorConfirmRequest {
+    pub code: String,
+}
+
+/// Turn two-factor off; needs a current authenticator or backup code
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoFactorDisableRequest {
+    pub code: String,
 }
 
 /// Request to create a new character
@@ -35,6 +62,8 @@ pub struct OAuthLoginRequest {
 pub struct CreateCharacterRequest {
     pub name: String,
     pub class: CharacterClass,
+    #[serde(default)]
+    pub faction: Faction,
 }
 
 /// Request to select and spawn a character
@@ -43,6 +72,10 @@ pub struct SelectCharacterRequest {
     pub character_id: i64,
 }
 
+/// Ask for the shard list shown at character select
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct GetShardListRequest;
+
 /// Movement input from client
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct MoveInput {
@@ -95,6 +128,12 @@ pub struct EquipItemRequest {
     pub slot_index: usize,
 }
 
+/// Use a consumable (potion, food) from the inventory
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct UseItemRequest {
+    pub item_id: u32,
+}
+
 /// Unequip item from equipment slot
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct UnequipItemRequest {
@@ -129,6 +168,12 @@ pub struct AcceptQuestRequest {
     pub quest_id: u32,
 }
 
+/// Pick a reply in the NPC dialogue the player has open, by its `DialogueOption::index`
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct ChooseDialogueOptionRequest {
+    pub index: usize,
+}
+
 /// Complete a quest with NPC
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct CompleteQuestRequest {
@@ -148,6 +193,24 @@ impl MapEntities for PurchaseFromTrainerRequest {
     }
 }
 
+/// Buy an item from the nearest vendor
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct BuyFromVendorRequest {
+    pub item_id: u32,
+}
+
+/// Sell the stack in an inventory slot to the nearest vendor
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct SellToVendorRequest {
+    pub slot_index: usize,
+}
+
+/// Buy back a previously sold stack (index into the buyback list)
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct BuybackRequest {
+    pub index: usize,
+}
+
 /// Set hotbar slot
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct SetHotbarSlotRequest {
@@ -155,6 +218,38 @@ pub struct SetHotbarSlotRequest {
     pub content: Option<HotbarSlot>,
 }
 
+/// Release a dead character's spirit: become a ghost at the nearest spirit healer
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct ReleaseSpiritRequest;
+
+/// Resurrect a ghost at their corpse (must be standing near it)
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct ResurrectAtCorpseRequest;
+
+/// Challenge another player to a duel
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct DuelChallengeRequest {
+    pub target: Entity,
+}
+
+impl MapEntities for DuelChallengeRequest {
+    fn map_entities<M: bevy::ecs::entity::EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.target = entity_mapper.get_mapped(self.target);
+    }
+}
+
+/// Accept or decline the pending duel challenge
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct DuelResponseRequest {
+    pub accept: bool,
+}
+
+/// Turn open-world PvP on or off (turning it off takes effect after `PVP_UNFLAG_DELAY`)
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct SetPvpFlagRequest {
+    pub enabled: bool,
+}
+
 /// Disconnect from current character (return to character select)
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct DisconnectCharacterRequest;
@@ -195,6 +290,50 @@ impl MapEntities for LootItemRequest {
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct AutoLootRequest;
 
+/// Add a character to your friend list by name
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct AddFriendRequest {
+    pub name: String,
+}
+
+/// Remove a character from your friend list
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct RemoveFriendRequest {
+    pub character_id: i64,
+}
+
+/// Private message to an online character
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct WhisperRequest {
+    pub target: String,
+    pub message: String,
+}
+
+/// Invite an online character, by name, to your party or guild
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupInviteRequest {
+    pub kind: GroupKind,
+    pub target: String,
+}
+
+/// Accept or decline the pending party or guild invite
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupInviteResponse {
+    pub accept: bool,
+}
+
+/// Leave your party or guild
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct LeaveGroupRequest {
+    pub kind: GroupKind,
+}
+
+/// Found a new guild with yourself as its first member
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct CreateGuildRequest {
+    pub name: String,
+}
+
 // ============================================================================
 // ADMIN COMMANDS (CLIENT -> SERVER)
 // ============================================================================
@@ -209,6 +348,7 @@ pub struct AdminCommandRequest {
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct SendChatMessage {
     pub message: String,
+    pub channel: ChatChannel,
 }
 
 // ============================================================================
@@ -222,6 +362,32 @@ pub struct LoginResponse {
     pub message: String,
     pub account_id: Option<i64>,
     pub is_admin: bool,
+    /// Password was accepted but the account needs a two-factor code
+    #[serde(default)]
+    pub two_factor_required: bool,
+    #[serde(default)]
+    pub two_factor_enabled: bool,
+}
+
+/// New two-factor secret for the authenticator app
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoFactorSetupResponse {
+    pub success: bool,
+    pub message: String,
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// otpauth:// URI for apps that import links or QR codes
+    pub otpauth_uri: String,
+}
+
+/// Result of confirming or disabling two-factor
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoFactorStatusResponse {
+    pub success: bool,
+    pub message: String,
+    pub enabled: bool,
+    /// One-time backup codes, only sent when two-factor is first enabled
+    pub backup_codes: Vec<String>,
 }
 
 /// OAuth login response
@@ -245,12 +411,48 @@ pub struct CharacterListResponse {
     pub characters: Vec<CharacterData>,
 }
 
+/// Shards the player can switch to, and which one they're connected to
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct ShardListResponse {
+    pub current_shard: String,
+    pub shards: Vec<ShardInfo>,
+}
+
+/// A world shard. Each one is a separate server process with its own address.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShardInfo {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub udp_port: u16,
+    pub webtransport_port: u16,
+    pub websocket_port: u16,
+    /// Where web clients fetch the WebTransport certificate hash
+    pub cert_url: String,
+    /// Characters in the world right now
+    pub population: u32,
+    pub max_players: u32,
+    /// False once the shard stops sending heartbeats
+    pub online: bool,
+}
+
+impl ShardInfo {
+    pub fn is_full(&self) -> bool {
+        self.population >= self.max_players
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CharacterData {
     pub id: i64,
     pub name: String,
     pub class: CharacterClass,
     pub level: u32,
+    #[serde(default)]
+    pub faction: Faction,
+    /// Shard the character lives on; it can only be played there
+    #[serde(default)]
+    pub shard_id: String,
 }
 
 /// Character creation response
@@ -276,6 +478,53 @@ pub struct CombatEvent {
     pub damage: f32,
     pub ability_id: u32,
     pub is_crit: bool,
+    pub result: AttackResult,
+}
+
+/// How an attack landed (or failed to)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AttackResult {
+    #[default]
+    Hit,
+    Crit,
+    Miss,
+    Dodge,
+    Parry,
+    Block,
+}
+
+/// Detailed combat log entry, sent to the players on either end of it
+/// Names are resolved on the server so entries survive the entities despawning
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct CombatLogEvent {
+    pub source: String,
+    pub target: String,
+    /// Ability used, 0 for auto-attacks
+    pub ability_id: u32,
+    pub entry: CombatLogEntry,
+}
+
+/// What happened in a combat log entry
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum CombatLogEntry {
+    /// Damage that landed; `result` is Hit, Crit or Block
+    Damage {
+        amount: f32,
+        result: AttackResult,
+        /// Damage removed by defense and blocking
+        mitigated: f32,
+        /// Damage soaked up by a mana shield
+        absorbed: f32,
+    },
+    /// Missed, dodged or parried
+    Avoided { result: AttackResult },
+    /// Damage-over-time tick
+    Periodic { amount: f32 },
+    Heal { amount: f32 },
+    /// Debuff applied for `duration` seconds, or resisted through diminishing returns
+    Debuff { effect: DebuffType, duration: Option<f32> },
+    /// The target died
+    Death,
 }
 
 /// Quest update notification
@@ -293,11 +542,93 @@ pub struct DeathEvent {
     pub position: Vec2,  // Position for client VFX
 }
 
+/// Another player challenged you to a duel
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct DuelChallengeEvent {
+    pub challenger_name: String,
+}
+
+/// Someone invited you to their party or guild
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupInviteEvent {
+    pub kind: GroupKind,
+    pub inviter_name: String,
+    /// The guild's name; parties don't have one
+    pub group_name: Option<String>,
+}
+
+/// A consumable category went on cooldown; lists every item sharing it
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct ItemCooldownEvent {
+    pub item_ids: Vec<u32>,
+    pub duration: f32,
+}
+
 /// Chat message
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct ChatMessage {
     pub sender: String,
     pub message: String,
+    pub channel: ChatChannel,
+    /// Who a whisper was sent to (so the sender's copy can read "To ...")
+    pub target: Option<String>,
+}
+
+/// Chat channel a message is sent on
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum ChatChannel {
+    /// Players in the same zone
+    #[default]
+    Say,
+    Party,
+    Guild,
+    /// Everyone online
+    Global,
+    /// Private message to one player
+    Whisper,
+}
+
+impl ChatChannel {
+    pub const ALL: [Self; 5] = [Self::Say, Self::Party, Self::Guild, Self::Global, Self::Whisper];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Say => "Say",
+            Self::Party => "Party",
+            Self::Guild => "Guild",
+            Self::Global => "Global",
+            Self::Whisper => "Whisper",
+        }
+    }
+}
+
+/// Which kind of group an invite or leave request is about
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupKind {
+    Party,
+    Guild,
+}
+
+impl GroupKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Party => "party",
+            Self::Guild => "guild",
+        }
+    }
+}
+
+/// Your friend list with who is online; sent on login and whenever it changes
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct FriendListEvent {
+    pub friends: Vec<FriendInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FriendInfo {
+    pub character_id: i64,
+    pub name: String,
+    pub online: bool,
 }
 
 /// Notification message to client
@@ -340,6 +671,23 @@ pub struct QuestDialogueEvent {
     pub rewards_text: String,
 }
 
+/// A line of authored NPC dialogue - sent when the conversation reaches a node
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct DialogueNodeEvent {
+    pub npc_name: String,
+    pub text: String,
+    /// Replies the player can pick; empty when the conversation ends here
+    pub choices: Vec<DialogueOption>,
+}
+
+/// A reply the player meets the conditions for
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DialogueOption {
+    /// Position of the reply in the node, sent back in `ChooseDialogueOptionRequest`
+    pub index: usize,
+    pub text: String,
+}
+
 /// Trainer dialogue data - sent when player interacts with trainer
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct TrainerDialogueEvent {
@@ -361,6 +709,33 @@ pub struct TrainerQuestInfo {
     pub is_completed: bool,  // Has player already completed this quest?
 }
 
+/// Vendor shop window - sent when player interacts with a vendor and after every transaction
+#[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
+pub struct VendorWindowEvent {
+    pub npc_name: String,
+    pub shop_name: String,
+    pub items: Vec<VendorItemInfo>,
+    /// Per-unit sell price for each item id in the player's inventory this vendor will buy
+    pub sell_prices: Vec<VendorItemInfo>,
+    /// Stacks the player recently sold, newest first
+    pub buyback: Vec<BuybackEntry>,
+}
+
+/// An item id with its price at a vendor
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VendorItemInfo {
+    pub item_id: u32,
+    pub price: u32,
+}
+
+/// A sold stack the player can buy back for what they were paid
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BuybackEntry {
+    pub item_id: u32,
+    pub quantity: u32,
+    pub price: u32,
+}
+
 /// Level up event - notifies client of level up
 #[derive(Event, Message, Serialize, Deserialize, Clone, Debug)]
 pub struct LevelUpEvent {
@@ -443,6 +818,28 @@ pub struct BanInfo {
     pub banned_at: i64,
     pub expires_at: Option<i64>,
     pub is_active: bool,
+    /// What the ban matches on besides `target`
+    #[serde(default)]
+    pub account_id: Option<i64>,
+    #[serde(default)]
+    pub ip_address: Option<String>,
+    #[serde(default)]
+    pub hardware_id: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Most recent appeal against this ban
+    #[serde(default)]
+    pub appeal: Option<BanAppeal>,
+}
+
+/// A player's appeal against a ban
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BanAppeal {
+    pub id: i64,
+    pub text: String,
+    pub submitted_at: i64,
+    pub status: String,  // "pending", "approved", "denied"
+    pub review_notes: Option<String>,
 }
 
 /// Request server statistics
@@ -457,6 +854,30 @@ pub struct ServerStatsResponse {
     pub total_characters: u32,
     pub active_bans: u32,
     pub server_time_utc: i64, // Unix timestamp in seconds
+    /// Configured replication snapshots per second
+    pub tick_rate: u32,
+    pub replicated_entities: u32,
+    /// Average number of replicated entities changed per snapshot over the last second
+    pub entity_changes_per_tick: f32,
+    pub clients: Vec<ClientNetworkStats>,
+    /// Population and cap of every shard, this one included
+    #[serde(default)]
+    pub shards: Vec<ShardInfo>,
+}
+
+/// Per-client connection and bandwidth statistics
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClientNetworkStats {
+    pub client_id: u64,
+    pub character_name: Option<String>,
+    pub rtt_ms: f64,
+    pub packet_loss: f64,
+    pub bytes_sent_per_sec: f64,
+    pub bytes_received_per_sec: f64,
+    /// Average replicated entities changed per snapshot sent to this client, far entities
+    /// weighted by their priority
+    #[serde(default)]
+    pub entity_changes_per_tick: f32,
 }
 
 /// Request audit logs with pagination
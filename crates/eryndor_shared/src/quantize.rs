@@ -0,0 +1,152 @@
+//! Compact wire formats for the hottest replicated components.
+//!
+//! Position, Velocity, Health and Mana change on most ticks for every entity that moves or
+//! fights, so their serde impls trade precision nobody can see for size. replicon serializes
+//! with postcard, which writes each f32 as 4 bytes; these components go out as fixed 2-byte
+//! integers instead, halving them:
+//!
+//! | Component  | Wire format | Precision         | Range           |
+//! |------------|-------------|-------------------|-----------------|
+//! | `Position` | 2 × i16     | 1/8 pixel         | ±4096 pixels    |
+//! | `Velocity` | 2 × i16     | 1/16 pixel/second | ±2048 pixels/s  |
+//! | `Health`   | 2 × u16     | 1 point           | 0 – 65535       |
+//! | `Mana`     | 2 × u16     | 1 point           | 0 – 65535       |
+//!
+//! Values outside the range are clamped. The world is 2000 pixels across, so positions have
+//! plenty of headroom; widen the scale here before building anything bigger.
+//!
+//! The integers are written as little-endian byte pairs rather than plain i16/u16 because
+//! postcard varint-encodes integers, which would take 3 bytes for most positions.
+
+use crate::components::{Health, Mana, Position, Velocity};
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Fixed point steps per pixel for `Position`
+pub const POSITION_SCALE: f32 = 8.0;
+
+/// Fixed point steps per pixel/second for `Velocity`
+pub const VELOCITY_SCALE: f32 = 16.0;
+
+fn quantize(value: f32, scale: f32) -> [u8; 2] {
+    let steps = (value * scale).round().clamp(i16::MIN as f32, i16::MAX as f32);
+    (steps as i16).to_le_bytes()
+}
+
+fn dequantize(bytes: [u8; 2], scale: f32) -> f32 {
+    i16::from_le_bytes(bytes) as f32 / scale
+}
+
+fn quantize_vec2<S: Serializer>(value: Vec2, scale: f32, serializer: S) -> Result<S::Ok, S::Error> {
+    (quantize(value.x, scale), quantize(value.y, scale)).serialize(serializer)
+}
+
+fn dequantize_vec2<'de, D: Deserializer<'de>>(scale: f32, deserializer: D) -> Result<Vec2, D::Error> {
+    let (x, y) = <([u8; 2], [u8; 2])>::deserialize(deserializer)?;
+    Ok(Vec2::new(dequantize(x, scale), dequantize(y, scale)))
+}
+
+/// Whole points, already rounded the way the caller wants
+fn pack_points(points: f32) -> [u8; 2] {
+    (points.clamp(0.0, u16::MAX as f32) as u16).to_le_bytes()
+}
+
+fn unpack_points(bytes: [u8; 2]) -> f32 {
+    u16::from_le_bytes(bytes) as f32
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        quantize_vec2(self.0, POSITION_SCALE, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        dequantize_vec2(POSITION_SCALE, deserializer).map(Position)
+    }
+}
+
+impl Serialize for Velocity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        quantize_vec2(self.0, VELOCITY_SCALE, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Velocity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        dequantize_vec2(VELOCITY_SCALE, deserializer).map(Velocity)
+    }
+}
+
+impl Serialize for Health {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Rounded up, so anything still alive never shows an empty bar
+        (pack_points(self.current.ceil()), pack_points(self.max.round())).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Health {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (current, max) = <([u8; 2], [u8; 2])>::deserialize(deserializer)?;
+        Ok(Health { current: unpack_points(current), max: unpack_points(max) })
+    }
+}
+
+impl Serialize for Mana {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Rounded down, so the client never thinks it can afford a spell the server won't cast
+        (pack_points(self.current.floor()), pack_points(self.max.round())).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mana {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (current, max) = <([u8; 2], [u8; 2])>::deserialize(deserializer)?;
+        Ok(Mana { current: unpack_points(current), max: unpack_points(max) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> (T, usize) {
+        let bytes = postcard::to_allocvec(value).unwrap();
+        (postcard::from_bytes(&bytes).unwrap(), bytes.len())
+    }
+
+    #[test]
+    fn positions_survive_to_an_eighth_of_a_pixel() {
+        let (position, size) = round_trip(&Position(Vec2::new(-734.31, 999.94)));
+        assert_eq!(size, 4);
+        assert!((position.0.x - -734.31).abs() <= 0.5 / POSITION_SCALE);
+        assert!((position.0.y - 999.94).abs() <= 0.5 / POSITION_SCALE);
+
+        // Twice the size of the plain f32 pair it replaces
+        assert_eq!(postcard::to_allocvec(&(0.0f32, 0.0f32)).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn out_of_range_values_clamp() {
+        let (position, _) = round_trip(&Position(Vec2::new(1.0e6, -1.0e6)));
+        assert_eq!(position.0, Vec2::new(i16::MAX as f32, i16::MIN as f32) / POSITION_SCALE);
+
+        let (velocity, _) = round_trip(&Velocity(Vec2::new(525.0, f32::NAN)));
+        assert_eq!(velocity.0, Vec2::new(525.0, 0.0));
+    }
+
+    #[test]
+    fn points_round_towards_what_the_player_should_see() {
+        let (health, size) = round_trip(&Health { current: 0.2, max: 120.4 });
+        assert_eq!(size, 4);
+        assert_eq!((health.current, health.max), (1.0, 120.0));
+        assert!(!health.is_dead());
+
+        let (mana, _) = round_trip(&Mana { current: 29.9, max: 50.0 });
+        assert_eq!(mana.current, 29.0);
+
+        let (dead, _) = round_trip(&Health { current: -15.0, max: 100.0 });
+        assert!(dead.is_dead());
+    }
+}
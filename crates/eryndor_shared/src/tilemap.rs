@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // ============================================================================
 // TILEMAP DATA STRUCTURES
@@ -496,6 +496,66 @@ pub struct EmbeddedTileset {
     pub image_width: Option<u32>,
     #[serde(rename = "imageheight", default)]
     pub image_height: Option<u32>,
+    /// Per-tile metadata (animations, properties)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<TilesetTile>,
+}
+
+impl EmbeddedTileset {
+    /// Get the animation for a local tile ID, if it has one
+    pub fn get_animation(&self, local_id: u32) -> Option<&[TileAnimationFrame]> {
+        self.tiles
+            .iter()
+            .find(|t| t.id == local_id)
+            .and_then(|t| t.animation.as_deref())
+            .filter(|frames| !frames.is_empty())
+    }
+
+    /// Resolve which local tile ID to display for an animated tile at the given time.
+    /// Non-animated tiles return their own ID.
+    pub fn animated_tile_id(&self, local_id: u32, elapsed_ms: u64) -> u32 {
+        // No animation and an empty frame list ("animation": []) both show the tile itself
+        let frames = self.get_animation(local_id).unwrap_or_default();
+        let Some(first) = frames.first() else {
+            return local_id;
+        };
+
+        let total: u64 = frames.iter().map(|f| f.duration as u64).sum();
+        if total == 0 {
+            return first.tile_id;
+        }
+
+        let mut t = elapsed_ms % total;
+        for frame in frames {
+            if t < frame.duration as u64 {
+                return frame.tile_id;
+            }
+            t -= frame.duration as u64;
+        }
+        first.tile_id
+    }
+}
+
+/// Per-tile data inside a tileset (Tiled "tiles" array)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TilesetTile {
+    /// Local tile ID within the tileset
+    pub id: u32,
+    /// Animation frames, played in order and looped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation: Option<Vec<TileAnimationFrame>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<CustomProperty>,
+}
+
+/// Single frame of an animated tile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TileAnimationFrame {
+    /// Local tile ID shown during this frame
+    #[serde(rename = "tileid")]
+    pub tile_id: u32,
+    /// Frame duration in milliseconds
+    pub duration: u32,
 }
 
 /// Custom property (Tiled-compatible)
@@ -514,6 +574,75 @@ fn default_string_type() -> String {
     "string".to_string()
 }
 
+// ============================================================================
+// TILE FLIP FLAGS
+// ============================================================================
+
+/// Tiled stores per-tile flips in the top three bits of each GID
+pub const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x8000_0000;
+pub const FLIPPED_VERTICALLY_FLAG: u32 = 0x4000_0000;
+pub const FLIPPED_DIAGONALLY_FLAG: u32 = 0x2000_0000;
+/// Mask that strips all flip flags from a GID
+pub const GID_MASK: u32 = 0x1FFF_FFFF;
+
+/// Flip/rotation state of a single placed tile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileFlip {
+    pub horizontal: bool,
+    pub vertical: bool,
+    /// Anti-diagonal flip (swaps x/y); combined with the other flags this gives 90° rotations
+    pub diagonal: bool,
+}
+
+impl TileFlip {
+    /// Split a raw GID into its tile ID and flip flags
+    pub fn split_gid(gid: u32) -> (u32, TileFlip) {
+        let flip = TileFlip {
+            horizontal: gid & FLIPPED_HORIZONTALLY_FLAG != 0,
+            vertical: gid & FLIPPED_VERTICALLY_FLAG != 0,
+            diagonal: gid & FLIPPED_DIAGONALLY_FLAG != 0,
+        };
+        (gid & GID_MASK, flip)
+    }
+
+    /// Encode these flags onto a bare GID
+    pub fn apply(self, gid: u32) -> u32 {
+        let mut gid = gid & GID_MASK;
+        if self.horizontal {
+            gid |= FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if self.vertical {
+            gid |= FLIPPED_VERTICALLY_FLAG;
+        }
+        if self.diagonal {
+            gid |= FLIPPED_DIAGONALLY_FLAG;
+        }
+        gid
+    }
+
+    /// Rotate the tile 90° clockwise (same convention as Tiled's "Rotate" action)
+    pub fn rotate_cw(self) -> Self {
+        Self {
+            horizontal: !self.vertical,
+            vertical: self.horizontal,
+            diagonal: !self.diagonal,
+        }
+    }
+
+    /// Rotate the tile 90° counter-clockwise
+    pub fn rotate_ccw(self) -> Self {
+        Self {
+            horizontal: self.vertical,
+            vertical: !self.horizontal,
+            diagonal: !self.diagonal,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        !self.horizontal && !self.vertical && !self.diagonal
+    }
+}
+
 // ============================================================================
 // TILEMAPMAP IMPLEMENTATION
 // ============================================================================
@@ -556,7 +685,7 @@ impl TilemapMap {
         }
 
         // Strip flip flags (highest 3 bits in Tiled format)
-        let raw_gid = gid & 0x1FFFFFFF;
+        let raw_gid = gid & GID_MASK;
 
         // Find tileset with highest firstgid <= raw_gid
         let mut best_match: Option<(usize, u32)> = None;
@@ -610,6 +739,31 @@ impl TilemapMap {
         self.layers.iter().find(|l| l.name == name)
     }
 
+    /// Iterate over all objects in every object layer (including nested groups)
+    pub fn all_objects(&self) -> Vec<&MapObject> {
+        fn collect<'a>(layers: &'a [MapLayer], out: &mut Vec<&'a MapObject>) {
+            for layer in layers {
+                if let Some(objects) = &layer.objects {
+                    out.extend(objects.iter());
+                }
+                if let Some(sublayers) = &layer.sublayers {
+                    collect(sublayers, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.layers, &mut out);
+        out
+    }
+
+    /// All objects with the given Tiled class/type (e.g. "spawn_point", "portal", "waypoint")
+    pub fn objects_of_type(&self, obj_type: &str) -> Vec<&MapObject> {
+        self.all_objects()
+            .into_iter()
+            .filter(|o| o.obj_type.eq_ignore_ascii_case(obj_type))
+            .collect()
+    }
+
     /// Remove a layer by ID
     pub fn remove_layer(&mut self, id: u32) -> Option<MapLayer> {
         if let Some(pos) = self.layers.iter().position(|l| l.id == id) {
@@ -980,6 +1134,48 @@ impl MapObject {
             properties: Vec::new(),
         }
     }
+
+    /// Look up a custom property by name
+    pub fn property(&self, name: &str) -> Option<&serde_json::Value> {
+        self.properties.iter().find(|p| p.name == name).map(|p| &p.value)
+    }
+
+    /// Custom property as a string
+    pub fn property_str(&self, name: &str) -> Option<&str> {
+        self.property(name).and_then(|v| v.as_str())
+    }
+
+    /// Custom property as a float (accepts int or float values)
+    pub fn property_f32(&self, name: &str) -> Option<f32> {
+        self.property(name).and_then(|v| v.as_f64()).map(|v| v as f32)
+    }
+
+    /// Custom property as an unsigned integer
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        self.property(name).and_then(|v| v.as_u64()).map(|v| v as u32)
+    }
+
+    /// Center of the object in map pixel space (points use their position directly)
+    pub fn center(&self) -> (f32, f32) {
+        if self.point {
+            (self.x, self.y)
+        } else {
+            (self.x + self.width / 2.0, self.y + self.height / 2.0)
+        }
+    }
+
+    /// Polyline or polygon vertices in map pixel space (empty for other shapes)
+    pub fn path_points(&self) -> Vec<(f32, f32)> {
+        self.polyline.as_ref()
+            .or(self.polygon.as_ref())
+            .map(|points| points.iter().map(|p| (self.x + p.x, self.y + p.y)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether a map-space point lies inside this object's bounding rectangle
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
 }
 
 // ============================================================================
@@ -1098,3 +1294,182 @@ impl TilemapMap {
         map
     }
 }
+
+// ============================================================================
+// COLLISION HELPERS
+// ============================================================================
+
+/// Axis-aligned rectangle of tiles (in tile coordinates)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TileRect {
+    /// World-space center of this rectangle
+    pub fn world_center(&self, tile_size: f32) -> (f32, f32) {
+        (
+            (self.x as f32 + self.width as f32 / 2.0) * tile_size,
+            (self.y as f32 + self.height as f32 / 2.0) * tile_size,
+        )
+    }
+
+    /// World-space size of this rectangle
+    pub fn world_size(&self, tile_size: f32) -> (f32, f32) {
+        (self.width as f32 * tile_size, self.height as f32 * tile_size)
+    }
+}
+
+impl TilemapMap {
+    /// Find the collision layer (name "Collision", case-insensitive)
+    pub fn collision_layer(&self) -> Option<&MapLayer> {
+        self.layers
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case("collision") && l.is_tile_layer())
+    }
+
+    /// Collect every blocked tile from the collision layer (any non-zero GID blocks)
+    pub fn collision_tiles(&self) -> HashSet<(i32, i32)> {
+        let mut tiles = HashSet::new();
+        let Some(layer) = self.collision_layer() else {
+            return tiles;
+        };
+
+        if let Some(chunks) = &layer.chunks {
+            for chunk in chunks {
+                let chunk_width = chunk.width.max(1) as i32;
+                for (idx, &gid) in chunk.data.iter().enumerate() {
+                    if gid & GID_MASK == 0 {
+                        continue;
+                    }
+                    let local_x = idx as i32 % chunk_width;
+                    let local_y = idx as i32 / chunk_width;
+                    tiles.insert((chunk.x + local_x, chunk.y + local_y));
+                }
+            }
+        }
+
+        if let Some(data) = &layer.data {
+            let map_width = self.width.max(1) as i32;
+            for (idx, &gid) in data.iter().enumerate() {
+                if gid & GID_MASK == 0 {
+                    continue;
+                }
+                tiles.insert((idx as i32 % map_width, idx as i32 / map_width));
+            }
+        }
+
+        tiles
+    }
+}
+
+impl ZoneTilemap {
+    /// Collect every blocked tile from the legacy collision grids
+    pub fn collision_tiles(&self) -> HashSet<(i32, i32)> {
+        let chunk_size = self.chunk_size as i32;
+        let mut tiles = HashSet::new();
+
+        for (key, chunk) in &self.chunks {
+            let Some((chunk_x, chunk_y)) = Self::parse_chunk_key(key) else {
+                continue;
+            };
+            for (row_idx, row) in chunk.collision.iter().enumerate() {
+                for (col_idx, &blocked) in row.iter().enumerate() {
+                    if blocked != 0 {
+                        tiles.insert((chunk_x * chunk_size + col_idx as i32, chunk_y * chunk_size + row_idx as i32));
+                    }
+                }
+            }
+        }
+
+        tiles
+    }
+}
+
+/// Greedily merge a set of tiles into as few rectangles as possible.
+/// Each run is grown along X first, then extended down while the whole row matches.
+pub fn merge_tiles_into_rects(tiles: &HashSet<(i32, i32)>) -> Vec<TileRect> {
+    let mut sorted: Vec<(i32, i32)> = tiles.iter().copied().collect();
+    sorted.sort_by_key(|&(x, y)| (y, x));
+
+    let mut visited: HashSet<(i32, i32)> = HashSet::with_capacity(tiles.len());
+    let mut rects = Vec::new();
+
+    for (x, y) in sorted {
+        if visited.contains(&(x, y)) {
+            continue;
+        }
+
+        // Extend right
+        let mut width = 1;
+        while tiles.contains(&(x + width, y)) && !visited.contains(&(x + width, y)) {
+            width += 1;
+        }
+
+        // Extend down while the full row is available
+        let mut height = 1;
+        'grow: loop {
+            let next_y = y + height;
+            for dx in 0..width {
+                let tile = (x + dx, next_y);
+                if !tiles.contains(&tile) || visited.contains(&tile) {
+                    break 'grow;
+                }
+            }
+            height += 1;
+        }
+
+        for dy in 0..height {
+            for dx in 0..width {
+                visited.insert((x + dx, y + dy));
+            }
+        }
+
+        rects.push(TileRect { x, y, width, height });
+    }
+
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_tiles_into_rects() {
+        // 3x2 block plus a detached single tile
+        let mut tiles = HashSet::new();
+        for x in 0..3 {
+            for y in 0..2 {
+                tiles.insert((x, y));
+            }
+        }
+        tiles.insert((5, 5));
+
+        let rects = merge_tiles_into_rects(&tiles);
+        assert_eq!(rects.len(), 2);
+        assert!(rects.contains(&TileRect { x: 0, y: 0, width: 3, height: 2 }));
+        assert!(rects.contains(&TileRect { x: 5, y: 5, width: 1, height: 1 }));
+
+        let covered: i32 = rects.iter().map(|r| r.width * r.height).sum();
+        assert_eq!(covered as usize, tiles.len());
+    }
+
+    #[test]
+    fn empty_animations_show_the_tile_itself() {
+        let tileset: EmbeddedTileset = serde_json::from_value(serde_json::json!({
+            "name": "water", "tilewidth": 16, "tileheight": 16, "tilecount": 4, "columns": 2,
+            "tiles": [
+                { "id": 1, "animation": [] },
+                { "id": 2, "animation": [{ "tileid": 2, "duration": 100 }, { "tileid": 3, "duration": 100 }] },
+            ],
+        })).unwrap();
+
+        assert_eq!(tileset.animated_tile_id(1, 250), 1);
+        assert_eq!(tileset.animated_tile_id(2, 150), 3);
+        assert_eq!(tileset.animated_tile_id(0, 150), 0);
+    }
+}
@@ -0,0 +1,308 @@
+//! End-to-end test harness.
+//!
+//! Boots the real game server (`GameServerPlugin`, with an in-memory SQLite database) and any
+//! number of headless clients in the same process. Clients and server talk through
+//! replicon's in-memory test transport instead of sockets, and every app advances by a fixed
+//! step, so a test drives the whole protocol one frame at a time:
+//!
+//! ```ignore
+//! let mut world = TestWorld::new();
+//! let alice = world.connect_client();
+//! world.enter_world(alice, "alice", CharacterClass::Knight)?;
+//! ```
+//!
+//! Clients only have the shared protocol, like the load test bots, not the game client's
+//! rendering or UI. Database jobs finish on their own thread, so anything that waits on the
+//! server goes through [`TestWorld::wait_until`] rather than a fixed number of steps.
+
+use bevy::asset::AssetPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_replicon::prelude::*;
+use bevy_replicon::test_app::ServerTestAppExt;
+use eryndor_server::auth::ClientMetadata;
+use eryndor_server::config::ServerConfig;
+use eryndor_server::database::DatabaseConnection;
+use eryndor_server::GameServerPlugin;
+use eryndor_shared::*;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+/// Game time every app advances per step (the server's 60 Hz physics tick)
+pub const STEP: Duration = Duration::from_nanos(16_666_667);
+
+/// How many steps `wait_until` gives the server before failing
+pub const MAX_WAIT_STEPS: usize = 1200;
+
+/// Every account the harness creates uses this password
+pub const PASSWORD: &str = "TestPassword123";
+
+/// Server responses every client records from the start
+fn watch_login_flow(client: &mut App) {
+    record::<CreateAccountResponse>(client);
+    record::<LoginResponse>(client);
+    record::<CharacterListResponse>(client);
+    record::<CreateCharacterResponse>(client);
+    record::<SelectCharacterResponse>(client);
+    record::<NotificationEvent>(client);
+    record::<ChatMessage>(client);
+}
+
+/// Server events of one type a client has received, oldest first
+#[derive(Resource)]
+struct Received<E>(Vec<E>);
+
+fn record<E: Event + Clone>(client: &mut App) {
+    if client.world().contains_resource::<Received<E>>() {
+        return;
+    }
+    client
+        .insert_resource(Received::<E>(Vec::new()))
+        .add_observer(|trigger: On<E>, mut received: ResMut<Received<E>>| {
+            received.0.push(trigger.event().clone());
+        });
+}
+
+/// A server and its connected clients
+pub struct TestWorld {
+    pub server: App,
+    clients: Vec<App>,
+    /// Event types recorded on every client, including ones that connect later
+    watched: Vec<fn(&mut App)>,
+}
+
+impl Default for TestWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestWorld {
+    /// A server with the default config, minus the per-IP rate limits: every test client
+    /// connects from the same address
+    pub fn new() -> Self {
+        let mut config = ServerConfig::default();
+        config.rate_limits.account_creation_per_hour = 10_000;
+        config.rate_limits.login_attempts_per_hour = 10_000;
+        config.rate_limits.chat_messages_per_minute = 10_000;
+        Self::with_config(config)
+    }
+
+    pub fn with_config(config: ServerConfig) -> Self {
+        let database = DatabaseConnection::open("sqlite::memory:")
+            .unwrap_or_else(|e| panic!("Failed to open test database: {}", e));
+
+        let mut server = App::new();
+        server
+            .add_plugins((
+                MinimalPlugins,
+                StatesPlugin,
+                AssetPlugin {
+                    file_path: concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets").to_string(),
+                    ..Default::default()
+                },
+                RepliconPlugins.set(ServerPlugin {
+                    tick_schedule: None,
+                    ..Default::default()
+                }),
+                GameServerPlugin { config },
+            ))
+            .insert_resource(database)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+        server.finish();
+        server.cleanup();
+        server.update();
+
+        Self {
+            server,
+            clients: Vec::new(),
+            watched: vec![watch_login_flow],
+        }
+    }
+
+    /// Connect a new client and return its index
+    pub fn connect_client(&mut self) -> usize {
+        let mut client = App::new();
+        client
+            .add_plugins((MinimalPlugins, StatesPlugin, RepliconPlugins, ProtocolPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+        for watch in &self.watched {
+            watch(&mut client);
+        }
+        client.finish();
+        client.cleanup();
+
+        self.server.connect_client(&mut client);
+
+        // Normally taken from the transport when the client connects; login needs it
+        let world = self.server.world_mut();
+        let new_clients: Vec<Entity> = world
+            .query_filtered::<Entity, (With<ConnectedClient>, Without<ClientMetadata>)>()
+            .iter(world)
+            .collect();
+        for entity in new_clients {
+            world.entity_mut(entity).insert(ClientMetadata {
+                ip_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                socket_id: 0,
+                connect_time: std::time::SystemTime::now(),
+                hardware_id: None,
+            });
+        }
+
+        self.clients.push(client);
+        self.clients.len() - 1
+    }
+
+    pub fn disconnect_client(&mut self, client: usize) {
+        let client = &mut self.clients[client];
+        self.server.disconnect_client(client);
+    }
+
+    pub fn client(&mut self, client: usize) -> &mut App {
+        &mut self.clients[client]
+    }
+
+    /// Record server events of type `E` on every client from now on
+    pub fn watch<E: Event + Clone>(&mut self) {
+        self.watched.push(record::<E>);
+        for client in &mut self.clients {
+            record::<E>(client);
+        }
+    }
+
+    /// Everything of type `E` a client has received so far
+    pub fn received<E: Event + Clone>(&self, client: usize) -> Vec<E> {
+        match self.clients[client].world().get_resource::<Received<E>>() {
+            Some(received) => received.0.clone(),
+            None => panic!("{} isn't being recorded, call watch first", std::any::type_name::<E>()),
+        }
+    }
+
+    /// The most recent `E` a client has received
+    pub fn last_received<E: Event + Clone>(&self, client: usize) -> Option<E> {
+        self.received::<E>(client).pop()
+    }
+
+    /// Trigger a client event; the server handles it on the next step
+    pub fn send<E: Event>(&mut self, client: usize, event: E) {
+        let world = self.clients[client].world_mut();
+        world.commands().client_trigger(event);
+        world.flush();
+    }
+
+    /// Advance every app by one step: the server runs, then each client receives what it sent
+    /// and hands back its own messages for the server's next step
+    pub fn step(&mut self) {
+        self.server.update();
+        for client in &mut self.clients {
+            self.server.exchange_with_client(client);
+            client.update();
+            self.server.exchange_with_client(client);
+        }
+    }
+
+    pub fn steps(&mut self, count: usize) {
+        for _ in 0..count {
+            self.step();
+        }
+    }
+
+    /// Step until `condition` holds, giving up after `MAX_WAIT_STEPS`.
+    ///
+    /// Game time stays at one `STEP` per step; the short real sleep only gives the database
+    /// thread a chance to finish queued jobs.
+    pub fn wait_until(&mut self, what: &str, mut condition: impl FnMut(&mut TestWorld) -> bool) -> Result<(), String> {
+        for _ in 0..MAX_WAIT_STEPS {
+            if condition(self) {
+                return Ok(());
+            }
+            self.step();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Err(format!("Timed out waiting for {}", what))
+    }
+
+    /// Wait for the next `E` a client receives
+    pub fn wait_for<E: Event + Clone>(&mut self, client: usize) -> Result<E, String> {
+        let seen = self.received::<E>(client).len();
+        self.wait_until(std::any::type_name::<E>(), |world| world.received::<E>(client).len() > seen)?;
+        Ok(self.received::<E>(client).remove(seen))
+    }
+
+    /// Create an account named `username` (if it doesn't exist yet) and log in to it
+    pub fn log_in(&mut self, client: usize, username: &str) -> Result<(), String> {
+        self.send(client, CreateAccountRequest {
+            email: format!("{}@test.invalid", username),
+            username: username.to_string(),
+            password: PASSWORD.to_string(),
+        });
+        self.wait_for::<CreateAccountResponse>(client)?;
+
+        self.send(client, LoginRequest {
+            username: username.to_string(),
+            password: PASSWORD.to_string(),
+            totp_code: None,
+            hardware_id: None,
+        });
+        let response = self.wait_for::<LoginResponse>(client)?;
+        if !response.success {
+            return Err(format!("Login as {} refused: {}", username, response.message));
+        }
+        Ok(())
+    }
+
+    /// Log in, create a character named after the account and play it. Returns the client's
+    /// replicated copy of the character.
+    pub fn enter_world(&mut self, client: usize, username: &str, class: CharacterClass) -> Result<Entity, String> {
+        let characters = self.received::<CharacterListResponse>(client).len();
+        self.log_in(client, username)?;
+        self.wait_until("the character list", |world| world.received::<CharacterListResponse>(client).len() > characters)?;
+
+        let name = character_name(username);
+        self.send(client, CreateCharacterRequest {
+            name: name.clone(),
+            class,
+            faction: Faction::default(),
+        });
+        let created = self.wait_for::<CreateCharacterResponse>(client)?;
+        let character = match created.character {
+            Some(character) if created.success => character,
+            _ => return Err(format!("Character creation refused: {}", created.message)),
+        };
+
+        self.send(client, SelectCharacterRequest { character_id: character.id });
+        self.wait_for::<SelectCharacterResponse>(client)?;
+
+        let mut player = None;
+        self.wait_until("the character to replicate", |world| {
+            player = world.find_player(client, &name);
+            player.is_some()
+        })?;
+        player.ok_or_else(|| format!("{} never appeared", name))
+    }
+
+    /// A player entity by character name, as one client sees it
+    pub fn find_player(&mut self, client: usize, name: &str) -> Option<Entity> {
+        let world = self.clients[client].world_mut();
+        world
+            .query_filtered::<(Entity, &Character), With<Player>>()
+            .iter(world)
+            .find(|(_, character)| character.name == name)
+            .map(|(entity, _)| entity)
+    }
+
+    /// A component of a replicated entity, as one client sees it
+    pub fn client_component<C: Component + Clone>(&self, client: usize, entity: Entity) -> Option<C> {
+        self.clients[client].world().get::<C>(entity).cloned()
+    }
+}
+
+/// "alice" plays "Alice"
+pub fn character_name(username: &str) -> String {
+    let mut chars = username.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
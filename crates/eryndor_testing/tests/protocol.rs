@@ -0,0 +1,69 @@
+//! Login, replication and chat, end to end through the real server
+
+use bevy::prelude::*;
+use eryndor_shared::*;
+use eryndor_testing::TestWorld;
+
+#[test]
+fn players_see_each_other() {
+    let mut world = TestWorld::new();
+    let alice = world.connect_client();
+    let bob = world.connect_client();
+
+    world.enter_world(alice, "alice", CharacterClass::Knight).unwrap();
+    world.enter_world(bob, "bob", CharacterClass::Mage).unwrap();
+
+    world.wait_until("alice to see bob", |world| world.find_player(alice, "Bob").is_some()).unwrap();
+    let alice_on_bob = world.find_player(bob, "Alice").expect("bob sees alice");
+    let character: Character = world.client_component(bob, alice_on_bob).unwrap();
+    assert_eq!(character.class, CharacterClass::Knight);
+}
+
+#[test]
+fn wrong_password_is_refused() {
+    let mut world = TestWorld::new();
+    let client = world.connect_client();
+    world.log_in(client, "carol").unwrap();
+
+    world.send(client, LoginRequest {
+        username: "carol".to_string(),
+        password: "not the password".to_string(),
+        totp_code: None,
+        hardware_id: None,
+    });
+    let response = world.wait_for::<LoginResponse>(client).unwrap();
+    assert!(!response.success);
+}
+
+#[test]
+fn movement_replicates_to_the_client() {
+    let mut world = TestWorld::new();
+    let client = world.connect_client();
+    let player = world.enter_world(client, "dave", CharacterClass::Rogue).unwrap();
+    let start: Position = world.client_component(client, player).unwrap();
+
+    world.send(client, MoveInput { direction: Vec2::X });
+    world.steps(30);
+    world.send(client, MoveInput { direction: Vec2::ZERO });
+
+    world.wait_until("the move to replicate", |world| {
+        world.client_component::<Position>(client, player).is_some_and(|position| position.0.x > start.0.x + 10.0)
+    }).unwrap();
+}
+
+#[test]
+fn say_reaches_players_in_the_zone() {
+    let mut world = TestWorld::new();
+    let erin = world.connect_client();
+    let frank = world.connect_client();
+    world.enter_world(erin, "erin", CharacterClass::Knight).unwrap();
+    world.enter_world(frank, "frank", CharacterClass::Knight).unwrap();
+
+    world.send(erin, SendChatMessage {
+        message: "well met".to_string(),
+        channel: ChatChannel::Say,
+    });
+    world.wait_until("frank to hear erin", |world| {
+        world.received::<ChatMessage>(frank).iter().any(|chat| chat.sender == "Erin" && chat.message == "well met")
+    }).unwrap();
+}